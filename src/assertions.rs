@@ -0,0 +1,270 @@
+//! Assertions de plages attendues par servo, pour les tests automatisés sur banc matériel.
+//!
+//! Exemple de spécification :
+//!
+//! ```toml
+//! sample_duration_ms = 2000
+//!
+//! [[servo]]
+//! id = 1
+//! voltage = { min = 11.0, max = 12.6 }
+//! temperature = { min = 10, max = 50 }
+//! idle_current = { min = 0, max = 50 }
+//! position_stability = 5
+//! max_ping_ms = 10
+//! ```
+
+use serde::Deserialize;
+use crate::backend::ServoBackend;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct AssertionSpec {
+    #[serde(default = "default_sample_duration_ms")]
+    pub sample_duration_ms: u64,
+    #[serde(default = "default_sample_interval_ms")]
+    pub sample_interval_ms: u64,
+    #[serde(default)]
+    pub servo: Vec<ServoExpectations>,
+}
+
+fn default_sample_duration_ms() -> u64 {
+    2000
+}
+
+fn default_sample_interval_ms() -> u64 {
+    50
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct Range {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Range {
+    fn contains(&self, value: f64) -> bool {
+        value >= self.min && value <= self.max
+    }
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct ServoExpectations {
+    pub id: u8,
+    pub voltage: Option<Range>,
+    pub temperature: Option<Range>,
+    /// Courant (mA) au repos, torque désactivé
+    pub idle_current: Option<Range>,
+    /// Écart max (ticks) entre les positions lues pendant l'échantillonnage
+    pub position_stability: Option<u16>,
+    pub max_ping_ms: Option<f64>,
+}
+
+/// Mesures relevées pour un servo pendant la durée d'échantillonnage
+#[derive(Clone, Debug, Default)]
+pub struct ServoSamples {
+    pub voltage: Vec<f32>,
+    pub temperature: Vec<u8>,
+    pub current: Vec<f32>,
+    pub position: Vec<u16>,
+    pub ping_ms: Vec<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    pub id: u8,
+    pub check: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ID {} {}: {}", self.id, self.check, self.message)
+    }
+}
+
+pub fn load_spec(path: &Path) -> Result<AssertionSpec, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+fn check_range(id: u8, check: &'static str, range: Option<Range>, values: &[f64], unit: &str) -> Option<Violation> {
+    let range = range?;
+    if values.is_empty() {
+        return Some(Violation { id, check, message: "no reading".to_string() });
+    }
+    let min = values.iter().cloned().fold(f64::INFINITY, f64::min);
+    let max = values.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+    if range.contains(min) && range.contains(max) {
+        None
+    } else {
+        Some(Violation {
+            id,
+            check,
+            message: format!(
+                "observed {:.1}..{:.1}{} outside expected {:.1}..{:.1}{}",
+                min, max, unit, range.min, range.max, unit
+            ),
+        })
+    }
+}
+
+/// Évalue toutes les assertions d'un servo et retourne chacune de celles qui échouent
+pub fn evaluate(expect: &ServoExpectations, samples: &ServoSamples) -> Vec<Violation> {
+    let id = expect.id;
+    let as_f64 = |v: &[f32]| v.iter().map(|x| *x as f64).collect::<Vec<_>>();
+    let mut violations = Vec::new();
+
+    violations.extend(check_range(id, "voltage", expect.voltage, &as_f64(&samples.voltage), " V"));
+    let temperatures: Vec<f64> = samples.temperature.iter().map(|t| *t as f64).collect();
+    violations.extend(check_range(id, "temperature", expect.temperature, &temperatures, " °C"));
+    violations.extend(check_range(id, "idle_current", expect.idle_current, &as_f64(&samples.current), " mA"));
+
+    if let Some(max_spread) = expect.position_stability {
+        match (samples.position.iter().min(), samples.position.iter().max()) {
+            (Some(min), Some(max)) if max - min > max_spread => violations.push(Violation {
+                id,
+                check: "position_stability",
+                message: format!("readback spread {} ticks exceeds {}", max - min, max_spread),
+            }),
+            (None, _) | (_, None) => violations.push(Violation {
+                id,
+                check: "position_stability",
+                message: "no reading".to_string(),
+            }),
+            _ => {}
+        }
+    }
+
+    if let Some(max_ping) = expect.max_ping_ms {
+        let worst = samples.ping_ms.iter().cloned().fold(f64::NAN, f64::max);
+        if worst.is_nan() {
+            violations.push(Violation { id, check: "max_ping_ms", message: "no ping reply".to_string() });
+        } else if worst > max_ping {
+            violations.push(Violation {
+                id,
+                check: "max_ping_ms",
+                message: format!("worst ping {:.1} ms exceeds {:.1} ms", worst, max_ping),
+            });
+        }
+    }
+
+    violations
+}
+
+/// Échantillonne tous les servos de la spécification pendant `sample_duration_ms`
+pub fn sample(driver: &dyn ServoBackend, spec: &AssertionSpec) -> BTreeMap<u8, ServoSamples> {
+    let mut samples: BTreeMap<u8, ServoSamples> = spec.servo.iter().map(|s| (s.id, ServoSamples::default())).collect();
+    let start = Instant::now();
+    let duration = Duration::from_millis(spec.sample_duration_ms);
+
+    loop {
+        for (&id, s) in samples.iter_mut() {
+            let ping_start = Instant::now();
+            if driver.ping_servo(id) {
+                s.ping_ms.push(ping_start.elapsed().as_secs_f64() * 1000.0);
+            }
+            s.voltage.extend(driver.read_voltage(id));
+            s.temperature.extend(driver.read_temperature(id));
+            s.current.extend(driver.read_current(id));
+            s.position.extend(driver.read_position(id));
+        }
+        if start.elapsed() >= duration {
+            break;
+        }
+        thread::sleep(Duration::from_millis(spec.sample_interval_ms));
+    }
+
+    samples
+}
+
+/// Échantillonne puis évalue toute la spécification
+pub fn run(driver: &dyn ServoBackend, spec: &AssertionSpec) -> Vec<Violation> {
+    let samples = sample(driver, spec);
+    spec.servo
+        .iter()
+        .flat_map(|expect| evaluate(expect, samples.get(&expect.id).unwrap_or(&ServoSamples::default())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{MockBackend, MockServo};
+
+    const SPEC: &str = r#"
+        sample_duration_ms = 0
+
+        [[servo]]
+        id = 1
+        voltage = { min = 11.0, max = 12.6 }
+        temperature = { min = 10, max = 50 }
+        position_stability = 5
+        max_ping_ms = 1000
+
+        [[servo]]
+        id = 2
+        idle_current = { min = 0, max = 50 }
+    "#;
+
+    fn expectations(id: u8) -> ServoExpectations {
+        ServoExpectations { id, voltage: None, temperature: None, idle_current: None, position_stability: None, max_ping_ms: None }
+    }
+
+    #[test]
+    fn spec_parses_with_defaults() {
+        let spec: AssertionSpec = toml::from_str(SPEC).unwrap();
+        assert_eq!((spec.sample_duration_ms, spec.sample_interval_ms), (0, 50));
+        assert_eq!(spec.servo.len(), 2);
+        assert_eq!(spec.servo[0].position_stability, Some(5));
+        assert!(spec.servo[1].voltage.is_none());
+    }
+
+    #[test]
+    fn ranges_check_both_extremes() {
+        let expect = ServoExpectations { voltage: Some(Range { min: 11.0, max: 12.6 }), ..expectations(1) };
+        let inside = ServoSamples { voltage: vec![11.5, 12.0], ..Default::default() };
+        assert!(evaluate(&expect, &inside).is_empty());
+
+        let sagging = ServoSamples { voltage: vec![12.0, 10.4], ..Default::default() };
+        let violations = evaluate(&expect, &sagging);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].check, "voltage");
+        assert!(violations[0].message.contains("10.4..12.0"));
+        assert_eq!(evaluate(&expect, &ServoSamples::default())[0].message, "no reading");
+    }
+
+    #[test]
+    fn position_spread_and_ping() {
+        let expect = ServoExpectations { position_stability: Some(5), max_ping_ms: Some(10.0), ..expectations(3) };
+        let steady = ServoSamples { position: vec![2048, 2050, 2046], ping_ms: vec![2.0, 4.5], ..Default::default() };
+        assert!(evaluate(&expect, &steady).is_empty());
+
+        let drifting = ServoSamples { position: vec![2048, 2060], ping_ms: vec![2.0, 14.0], ..Default::default() };
+        let checks: Vec<&str> = evaluate(&expect, &drifting).iter().map(|v| v.check).collect();
+        assert_eq!(checks, vec!["position_stability", "max_ping_ms"]);
+
+        let silent = evaluate(&expect, &ServoSamples::default());
+        assert_eq!(silent.iter().map(|v| v.message.as_str()).collect::<Vec<_>>(), vec!["no reading", "no ping reply"]);
+        assert_eq!(silent[1].to_string(), "ID 3 max_ping_ms: no ping reply");
+    }
+
+    #[test]
+    fn run_samples_the_bus() {
+        let spec: AssertionSpec = toml::from_str(SPEC).unwrap();
+        let mock = MockBackend::new()
+            .with_servo(1, MockServo { temperature: 60, ..Default::default() })
+            .with_servo(2, MockServo { current: 20.0, ..Default::default() });
+        let violations = run(&mock, &spec);
+        assert_eq!(violations.len(), 1);
+        assert_eq!((violations[0].id, violations[0].check), (1, "temperature"));
+
+        mock.unplug(2);
+        let violations = run(&mock, &spec);
+        assert!(violations.iter().any(|v| v.id == 2 && v.message == "no reading"));
+    }
+}
@@ -0,0 +1,503 @@
+//! Opérations de bus utilisées par les threads de communication, derrière un trait pour pouvoir
+//! remplacer le servo réel.
+//!
+//! `SerialBackend` est le bus réel : le pilote `ST3215` pour les transactions courantes, et un
+//! port ouvert directement pour les accès que le pilote n'offre pas (registres, trames brutes,
+//! ping en diffusion). `MockBackend` renvoie des valeurs scriptées et journalise les écritures,
+//! pour dérouler la logique des workers sans servo ni port série.
+
+use crate::packet;
+use st3215::{PortHandler, ProtocolPacketHandler, BROADCAST_ID, INST_SYNC_WRITE, STS_ACC, ST3215};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Lectures et écritures du pilote, avec les signatures de `ST3215`, puis les accès directs
+pub trait ServoBackend: Send + Sync {
+    fn ping_servo(&self, id: u8) -> bool;
+    fn list_servos(&self) -> Vec<u8>;
+    fn read_position(&self, id: u8) -> Option<u16>;
+    fn read_temperature(&self, id: u8) -> Option<u8>;
+    fn read_voltage(&self, id: u8) -> Option<f32>;
+    fn read_current(&self, id: u8) -> Option<f32>;
+    fn read_speed(&self, id: u8) -> Option<i16>;
+    fn read_load(&self, id: u8) -> Option<f32>;
+    fn read_mode(&self, id: u8) -> Option<u8>;
+    fn is_moving(&self, id: u8) -> Option<bool>;
+    fn move_to(&self, id: u8, position: u16, speed: u16, acceleration: u8, wait: bool) -> Option<bool>;
+    fn write_position(&self, id: u8, position: u16) -> Option<bool>;
+    fn enable_torque(&self, id: u8) -> Result<(), String>;
+    fn disable_torque(&self, id: u8) -> Result<(), String>;
+    /// Vitesse signée en mode roue (passe le servo en mode roue)
+    fn rotate(&self, id: u8, speed: i16) -> Result<(), String>;
+    fn set_mode(&self, id: u8, mode: u8) -> Result<(), String>;
+    fn change_id(&self, id: u8, new_id: u8) -> Result<(), String>;
+
+    /// Lecture brute de `size` octets à partir de `address`
+    fn read_register(&self, id: u8, address: u8, size: u8) -> Result<Vec<u8>, String>;
+    /// Écriture brute à partir de `address`, acquittée par le servo
+    fn write_register(&self, id: u8, address: u8, data: &[u8]) -> Result<(), String>;
+    /// Trame complète (console bas niveau, sync write) ; la réponse éventuelle est rendue brute
+    fn send_raw(&self, frame: &[u8]) -> Result<Option<Vec<u8>>, String>;
+    /// Ping en diffusion : tout ce qui arrive pendant `window`, une trame de statut par servo
+    fn broadcast_ping(&self, window: Duration) -> Result<Vec<u8>, String>;
+}
+
+// Ce qui tient le port série : le pilote, un accès direct, ou rien après un échec d'ouverture
+enum Link {
+    Driver(ST3215),
+    Direct(PortHandler),
+    Closed,
+}
+
+/// Bus série réel. `ST3215` garde son port privé : un accès direct ferme le pilote et ouvre le
+/// port pour lui, la transaction suivante du pilote le rouvre. Un port qui ne se rouvre pas
+/// laisse chaque transaction en échec, comme un bus muet.
+pub struct SerialBackend {
+    port: String,
+    link: Mutex<Link>,
+}
+
+impl SerialBackend {
+    /// Pilote ouvert tout de suite, pour signaler un port absent dès la connexion
+    pub fn open(port: &str) -> Result<Self, String> {
+        Ok(Self { port: port.to_string(), link: Mutex::new(Link::Driver(ST3215::new(port)?)) })
+    }
+
+    /// Port ouvert pour des accès directs seulement (registres en ligne de commande)
+    pub fn open_direct(port: &str) -> Result<Self, String> {
+        let mut handler = PortHandler::new(port);
+        handler.open_port()?;
+        Ok(Self { port: port.to_string(), link: Mutex::new(Link::Direct(handler)) })
+    }
+
+    fn driver<T>(&self, failed: T, f: impl FnOnce(&ST3215) -> T) -> T {
+        let mut link = self.link.lock().unwrap();
+        if !matches!(*link, Link::Driver(_)) {
+            // Le port direct est fermé avant que le pilote ne le rouvre
+            *link = Link::Closed;
+            match ST3215::new(&self.port) {
+                Ok(driver) => *link = Link::Driver(driver),
+                Err(e) => log::debug!(target: crate::logging::BUS, "{}: {}", self.port, e),
+            }
+        }
+        match &*link {
+            Link::Driver(driver) => f(driver),
+            _ => failed,
+        }
+    }
+
+    fn direct<T>(&self, f: impl FnOnce(&mut PortHandler) -> Result<T, String>) -> Result<T, String> {
+        let mut link = self.link.lock().unwrap();
+        if !matches!(*link, Link::Direct(_)) {
+            *link = Link::Closed;
+            let mut handler = PortHandler::new(&self.port);
+            handler.open_port()?;
+            *link = Link::Direct(handler);
+        }
+        match &mut *link {
+            Link::Direct(handler) => f(handler),
+            _ => unreachable!("port opened above"),
+        }
+    }
+}
+
+impl ServoBackend for SerialBackend {
+    fn ping_servo(&self, id: u8) -> bool {
+        self.driver(false, |d| d.ping_servo(id))
+    }
+
+    fn list_servos(&self) -> Vec<u8> {
+        self.driver(Vec::new(), |d| d.list_servos())
+    }
+
+    fn read_position(&self, id: u8) -> Option<u16> {
+        self.driver(None, |d| d.read_position(id))
+    }
+
+    fn read_temperature(&self, id: u8) -> Option<u8> {
+        self.driver(None, |d| d.read_temperature(id))
+    }
+
+    fn read_voltage(&self, id: u8) -> Option<f32> {
+        self.driver(None, |d| d.read_voltage(id))
+    }
+
+    fn read_current(&self, id: u8) -> Option<f32> {
+        self.driver(None, |d| d.read_current(id))
+    }
+
+    fn read_speed(&self, id: u8) -> Option<i16> {
+        self.driver(None, |d| d.read_speed(id))
+    }
+
+    fn read_load(&self, id: u8) -> Option<f32> {
+        self.driver(None, |d| d.read_load(id))
+    }
+
+    fn read_mode(&self, id: u8) -> Option<u8> {
+        self.driver(None, |d| d.read_mode(id))
+    }
+
+    fn is_moving(&self, id: u8) -> Option<bool> {
+        self.driver(None, |d| d.is_moving(id))
+    }
+
+    fn move_to(&self, id: u8, position: u16, speed: u16, acceleration: u8, wait: bool) -> Option<bool> {
+        self.driver(None, |d| d.move_to(id, position, speed, acceleration, wait))
+    }
+
+    fn write_position(&self, id: u8, position: u16) -> Option<bool> {
+        self.driver(None, |d| d.write_position(id, position))
+    }
+
+    fn enable_torque(&self, id: u8) -> Result<(), String> {
+        self.driver(Err(format!("{}: port closed", self.port)), |d| d.enable_torque(id))
+    }
+
+    fn disable_torque(&self, id: u8) -> Result<(), String> {
+        self.driver(Err(format!("{}: port closed", self.port)), |d| d.disable_torque(id))
+    }
+
+    fn rotate(&self, id: u8, speed: i16) -> Result<(), String> {
+        self.driver(Err(format!("{}: port closed", self.port)), |d| d.rotate(id, speed))
+    }
+
+    fn set_mode(&self, id: u8, mode: u8) -> Result<(), String> {
+        self.driver(Err(format!("{}: port closed", self.port)), |d| d.set_mode(id, mode))
+    }
+
+    fn change_id(&self, id: u8, new_id: u8) -> Result<(), String> {
+        self.driver(Err(format!("{}: port closed", self.port)), |d| d.change_id(id, new_id))
+    }
+
+    fn read_register(&self, id: u8, address: u8, size: u8) -> Result<Vec<u8>, String> {
+        self.direct(|port| {
+            let (data, result, _) = ProtocolPacketHandler::new(port).read_tx_rx(id, address, size);
+            if !result.is_success() || data.len() < size as usize {
+                return Err(format!("read at {} on ID {}: {:?}", address, id, result));
+            }
+            Ok(data)
+        })
+    }
+
+    fn write_register(&self, id: u8, address: u8, data: &[u8]) -> Result<(), String> {
+        self.direct(|port| {
+            let (result, _) = ProtocolPacketHandler::new(port).write_tx_rx(id, address, data);
+            match result.is_success() {
+                true => Ok(()),
+                false => Err(format!("write at {} on ID {}: {:?}", address, id, result)),
+            }
+        })
+    }
+
+    fn send_raw(&self, frame: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        self.direct(|port| packet::send_frame(port, frame))
+    }
+
+    fn broadcast_ping(&self, window: Duration) -> Result<Vec<u8>, String> {
+        self.direct(|port| packet::broadcast_ping(port, window))
+    }
+}
+
+/// Écriture reçue par `MockBackend`, dans l'ordre d'arrivée
+#[derive(Clone, Debug, PartialEq)]
+pub enum BackendCall {
+    MoveTo { id: u8, position: u16, speed: u16, acceleration: u8 },
+    WritePosition { id: u8, position: u16 },
+    EnableTorque(u8),
+    DisableTorque(u8),
+    Rotate { id: u8, speed: i16 },
+    SetMode { id: u8, mode: u8 },
+    ChangeId { id: u8, new_id: u8 },
+    WriteRegister { id: u8, address: u8, data: Vec<u8> },
+    RawFrame(Vec<u8>),
+    BroadcastPing,
+}
+
+/// Valeurs renvoyées par un servo scripté ; une consigne de position est atteinte immédiatement
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MockServo {
+    pub position: u16,
+    pub temperature: u8,
+    pub voltage: f32,
+    pub current: f32,
+    pub speed: i16,
+    pub load: f32,
+    pub mode: u8,
+    pub moving: bool,
+    pub torque: bool,
+}
+
+impl Default for MockServo {
+    fn default() -> Self {
+        Self {
+            position: 2048,
+            temperature: 30,
+            voltage: 12.0,
+            current: 0.0,
+            speed: 0,
+            load: 0.0,
+            mode: 0,
+            moving: false,
+            torque: false,
+        }
+    }
+}
+
+#[derive(Default)]
+struct MockState {
+    servos: BTreeMap<u8, MockServo>,
+    // Octets écrits en direct, par (ID, adresse), hors registres suivis par `MockServo`
+    registers: BTreeMap<(u8, u8), u8>,
+    calls: Vec<BackendCall>,
+}
+
+// Registres que `MockServo` suit lui-même, lus et écrits par les accès directs
+const MOCK_TORQUE_ENABLE: u8 = 40;
+const MOCK_MODE: u8 = 33;
+const MOCK_GOAL_POSITION: u8 = 42;
+const MOCK_PRESENT_POSITION: u8 = 56;
+const MOCK_VOLTAGE: u8 = 62;
+const MOCK_TEMPERATURE: u8 = 63;
+const MOCK_MOVING: u8 = 66;
+
+impl MockServo {
+    fn register_byte(&self, address: u8) -> Option<u8> {
+        let [position_low, position_high] = self.position.to_le_bytes();
+        Some(match address {
+            MOCK_TORQUE_ENABLE => u8::from(self.torque),
+            MOCK_MODE => self.mode,
+            MOCK_GOAL_POSITION | MOCK_PRESENT_POSITION => position_low,
+            a if a == MOCK_GOAL_POSITION + 1 || a == MOCK_PRESENT_POSITION + 1 => position_high,
+            MOCK_VOLTAGE => (self.voltage * 10.0).round() as u8,
+            MOCK_TEMPERATURE => self.temperature,
+            MOCK_MOVING => u8::from(self.moving),
+            _ => return None,
+        })
+    }
+
+    fn write_register_byte(&mut self, address: u8, value: u8) {
+        let [low, high] = self.position.to_le_bytes();
+        match address {
+            MOCK_TORQUE_ENABLE => self.torque = value != 0,
+            MOCK_MODE => self.mode = value,
+            MOCK_GOAL_POSITION => self.position = u16::from_le_bytes([value, high]),
+            a if a == MOCK_GOAL_POSITION + 1 => self.position = u16::from_le_bytes([low, value]),
+            _ => {}
+        }
+    }
+}
+
+// Trame de statut sans erreur ni paramètre, comme la réponse d'un servo à un ping
+fn status_frame(id: u8) -> Vec<u8> {
+    packet::build_frame(id, 0, &[]).expect("empty status frame")
+}
+
+/// Bus scripté. Les clones partagent le même état : on en garde un pour modifier les valeurs
+/// et relire les écritures pendant que le worker utilise l'autre.
+#[derive(Clone, Default)]
+pub struct MockBackend {
+    state: Arc<Mutex<MockState>>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ajoute un servo (ou remplace celui qui porte cet ID)
+    pub fn with_servo(self, id: u8, servo: MockServo) -> Self {
+        self.state.lock().unwrap().servos.insert(id, servo);
+        self
+    }
+
+    /// Modifie les valeurs d'un servo présent ; sans effet sinon
+    pub fn update(&self, id: u8, f: impl FnOnce(&mut MockServo)) {
+        if let Some(servo) = self.state.lock().unwrap().servos.get_mut(&id) {
+            f(servo);
+        }
+    }
+
+    /// Servo débranché : il ne répond plus à rien
+    pub fn unplug(&self, id: u8) {
+        self.state.lock().unwrap().servos.remove(&id);
+    }
+
+    pub fn servo(&self, id: u8) -> Option<MockServo> {
+        self.state.lock().unwrap().servos.get(&id).copied()
+    }
+
+    /// Valeur brute d'un registre, telle qu'un accès direct la lirait
+    pub fn register(&self, id: u8, address: u8) -> Option<u8> {
+        let state = self.state.lock().unwrap();
+        let servo = state.servos.get(&id)?;
+        Some(servo.register_byte(address).unwrap_or_else(|| state.registers.get(&(id, address)).copied().unwrap_or(0)))
+    }
+
+    /// Registre préchargé, comme une EEPROM déjà réglée
+    pub fn set_register(&self, id: u8, address: u8, value: u8) {
+        let mut state = self.state.lock().unwrap();
+        state.registers.insert((id, address), value);
+        if let Some(servo) = state.servos.get_mut(&id) {
+            servo.write_register_byte(address, value);
+        }
+    }
+
+    /// Écritures reçues depuis la création (ou le dernier `take_calls`)
+    pub fn calls(&self) -> Vec<BackendCall> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    pub fn take_calls(&self) -> Vec<BackendCall> {
+        std::mem::take(&mut self.state.lock().unwrap().calls)
+    }
+
+    fn read<T>(&self, id: u8, f: impl FnOnce(&MockServo) -> T) -> Option<T> {
+        self.state.lock().unwrap().servos.get(&id).map(f)
+    }
+
+    /// Écriture journalisée même sans servo, comme une trame partie sur le bus sans réponse
+    fn write(&self, call: BackendCall, id: u8, f: impl FnOnce(&mut MockServo)) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(call);
+        match state.servos.get_mut(&id) {
+            Some(servo) => {
+                f(servo);
+                Ok(())
+            }
+            None => Err(format!("ID {}: no response", id)),
+        }
+    }
+}
+
+impl ServoBackend for MockBackend {
+    fn ping_servo(&self, id: u8) -> bool {
+        self.read(id, |_| ()).is_some()
+    }
+
+    fn list_servos(&self) -> Vec<u8> {
+        self.state.lock().unwrap().servos.keys().copied().collect()
+    }
+
+    fn read_position(&self, id: u8) -> Option<u16> {
+        self.read(id, |s| s.position)
+    }
+
+    fn read_temperature(&self, id: u8) -> Option<u8> {
+        self.read(id, |s| s.temperature)
+    }
+
+    fn read_voltage(&self, id: u8) -> Option<f32> {
+        self.read(id, |s| s.voltage)
+    }
+
+    fn read_current(&self, id: u8) -> Option<f32> {
+        self.read(id, |s| s.current)
+    }
+
+    fn read_speed(&self, id: u8) -> Option<i16> {
+        self.read(id, |s| s.speed)
+    }
+
+    fn read_load(&self, id: u8) -> Option<f32> {
+        self.read(id, |s| s.load)
+    }
+
+    fn read_mode(&self, id: u8) -> Option<u8> {
+        self.read(id, |s| s.mode)
+    }
+
+    fn is_moving(&self, id: u8) -> Option<bool> {
+        self.read(id, |s| s.moving)
+    }
+
+    fn move_to(&self, id: u8, position: u16, speed: u16, acceleration: u8, _wait: bool) -> Option<bool> {
+        let call = BackendCall::MoveTo { id, position, speed, acceleration };
+        self.write(call, id, |s| s.position = position).ok().map(|()| true)
+    }
+
+    fn write_position(&self, id: u8, position: u16) -> Option<bool> {
+        self.write(BackendCall::WritePosition { id, position }, id, |s| s.position = position).ok().map(|()| true)
+    }
+
+    fn enable_torque(&self, id: u8) -> Result<(), String> {
+        self.write(BackendCall::EnableTorque(id), id, |s| s.torque = true)
+    }
+
+    fn disable_torque(&self, id: u8) -> Result<(), String> {
+        self.write(BackendCall::DisableTorque(id), id, |s| s.torque = false)
+    }
+
+    fn rotate(&self, id: u8, speed: i16) -> Result<(), String> {
+        self.write(BackendCall::Rotate { id, speed }, id, |s| {
+            s.mode = 1;
+            s.speed = speed;
+        })
+    }
+
+    fn set_mode(&self, id: u8, mode: u8) -> Result<(), String> {
+        self.write(BackendCall::SetMode { id, mode }, id, |s| s.mode = mode)
+    }
+
+    fn change_id(&self, id: u8, new_id: u8) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(BackendCall::ChangeId { id, new_id });
+        let servo = state.servos.remove(&id).ok_or_else(|| format!("ID {}: no response", id))?;
+        state.servos.insert(new_id, servo);
+        Ok(())
+    }
+
+    fn read_register(&self, id: u8, address: u8, size: u8) -> Result<Vec<u8>, String> {
+        (address..address.saturating_add(size))
+            .map(|a| self.register(id, a).ok_or_else(|| format!("read at {} on ID {}: no response", address, id)))
+            .collect()
+    }
+
+    fn write_register(&self, id: u8, address: u8, data: &[u8]) -> Result<(), String> {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(BackendCall::WriteRegister { id, address, data: data.to_vec() });
+        if !state.servos.contains_key(&id) {
+            return Err(format!("write at {} on ID {}: no response", address, id));
+        }
+        for (a, &value) in (address..).zip(data) {
+            state.registers.insert((id, a), value);
+            if let Some(servo) = state.servos.get_mut(&id) {
+                servo.write_register_byte(a, value);
+            }
+        }
+        Ok(())
+    }
+
+    /// Un sync write de consignes est appliqué ; un servo présent répond aux autres trames qui
+    /// lui sont adressées par un statut sans erreur
+    fn send_raw(&self, frame: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(BackendCall::RawFrame(frame.to_vec()));
+        let (Some(&id), Some(&instruction)) = (frame.get(2), frame.get(4)) else {
+            return Err("truncated frame".to_string());
+        };
+        if id != BROADCAST_ID {
+            return match state.servos.contains_key(&id) {
+                true => Ok(Some(status_frame(id))),
+                false => Err(format!("ID {}: no response", id)),
+            };
+        }
+        let params = &frame[5..frame.len().saturating_sub(1).max(5)];
+        if instruction == INST_SYNC_WRITE && params.first() == Some(&STS_ACC) {
+            let length = usize::from(params.get(1).copied().unwrap_or(0)) + 1;
+            for entry in params[2..].chunks_exact(length) {
+                if let Some(servo) = state.servos.get_mut(&entry[0]) {
+                    servo.position = u16::from_le_bytes([entry[2], entry[3]]);
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    fn broadcast_ping(&self, _window: Duration) -> Result<Vec<u8>, String> {
+        let mut state = self.state.lock().unwrap();
+        state.calls.push(BackendCall::BroadcastPing);
+        Ok(state.servos.keys().flat_map(|&id| status_frame(id)).collect())
+    }
+}
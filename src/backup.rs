@@ -0,0 +1,222 @@
+//! Sauvegarde de la configuration complète d'un servo (toute la table EEPROM) dans un fichier,
+//! et restauration registre par registre avec relecture.
+//!
+//! ```json
+//! { "registers": { "Model": 777, "ID": 1, "Max Torque": 1000, "P Coefficient": 32 } }
+//! ```
+//!
+//! Le même contenu est accepté en TOML (extension `.toml`). La restauration n'écrit jamais l'ID :
+//! l'appelant le change à part, par le changement d'ID vérifié.
+
+use crate::registers::{self, Register, RegisterGroup, RegisterPort, REGISTERS};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConfigDump {
+    /// Valeur lisible par nom de registre
+    pub registers: BTreeMap<String, i32>,
+}
+
+impl ConfigDump {
+    /// Lecture de toute la table EEPROM ; un registre illisible fait échouer la sauvegarde
+    pub fn read(bus: &mut RegisterPort, id: u8) -> Result<Self, String> {
+        let mut registers = BTreeMap::new();
+        for register in REGISTERS {
+            registers.insert(register.name.to_string(), bus.read(id, register)?);
+        }
+        Ok(Self { registers })
+    }
+
+    /// Lecture JSON, ou TOML selon l'extension
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+        } else {
+            serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+        }
+    }
+
+    /// Écriture JSON, ou TOML selon l'extension
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::to_string_pretty(self).map_err(|e| e.to_string())?
+        } else {
+            serde_json::to_string_pretty(self).map_err(|e| e.to_string())?
+        };
+        std::fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Valeur du fichier pour un registre, sous son nom ou l'un de ses alias
+    pub fn get(&self, register: &Register) -> Option<i32> {
+        self.registers.iter().find(|(name, _)| registers::find_register(name) == Some(register)).map(|(_, &v)| v)
+    }
+
+    pub fn id(&self) -> Option<u8> {
+        self.get(id_register()).and_then(|id| u8::try_from(id).ok())
+    }
+}
+
+fn id_register() -> &'static Register {
+    REGISTERS.iter().find(|r| r.name == "ID").expect("ID register")
+}
+
+fn model_register() -> &'static Register {
+    REGISTERS.iter().find(|r| r.name == "Model").expect("Model register")
+}
+
+/// Sort d'une entrée du fichier à la restauration
+#[derive(Clone, Debug, PartialEq)]
+pub enum RestoreStatus {
+    /// Le servo a déjà cette valeur
+    Unchanged,
+    /// Écrite et relue
+    Written,
+    Skipped(String),
+    Failed(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RestoreRow {
+    pub name: String,
+    pub value: i32,
+    pub status: RestoreStatus,
+}
+
+impl RestoreRow {
+    pub fn failed(&self) -> bool {
+        matches!(self.status, RestoreStatus::Failed(_))
+    }
+}
+
+/// Le modèle du fichier doit être celui du servo, sauf `force`
+pub fn check_model(bus: &mut RegisterPort, id: u8, dump: &ConfigDump, force: bool) -> Result<(), String> {
+    if force {
+        return Ok(());
+    }
+    let file = dump.get(model_register()).ok_or("file has no Model entry")?;
+    let live = bus.read(id, model_register())?;
+    if file != live {
+        return Err(format!("file is for model {}, servo ID {} is model {}", file, id, live));
+    }
+    Ok(())
+}
+
+/// Écrit les valeurs du fichier qui diffèrent du servo, une par une avec relecture, par adresse
+/// croissante. Comme à l'import, seuls les groupes inscriptibles sont écrits : ni les registres
+/// d'information, ni ceux de communication (ID, débit, délai de réponse, niveau de statut), dont
+/// une valeur d'un autre montage couperait la liaison.
+pub fn restore(bus: &mut RegisterPort, id: u8, dump: &ConfigDump) -> Vec<RestoreRow> {
+    let mut entries: Vec<(Option<&'static Register>, &String, i32)> =
+        dump.registers.iter().map(|(name, &value)| (registers::find_register(name), name, value)).collect();
+    entries.sort_by_key(|(register, _, _)| register.map_or(u16::MAX, |r| u16::from(r.address)));
+
+    entries
+        .into_iter()
+        .map(|(register, name, value)| {
+            let status = match register {
+                None => RestoreStatus::Skipped("unknown register".to_string()),
+                Some(register) if register == id_register() => match value == i32::from(id) {
+                    true => RestoreStatus::Unchanged,
+                    false => RestoreStatus::Skipped("the ID is not restored with the other registers".to_string()),
+                },
+                // Sans objet si le servo a déjà la valeur du fichier
+                Some(register) if !register.group.is_writable() || !register.writable => {
+                    match bus.read(id, register) {
+                        Ok(live) if live == value => RestoreStatus::Unchanged,
+                        _ if register.group == RegisterGroup::Communication => {
+                            RestoreStatus::Skipped("communication settings are not restored".to_string())
+                        }
+                        _ => RestoreStatus::Skipped("read-only".to_string()),
+                    }
+                }
+                Some(register) => match bus.read(id, register) {
+                    Ok(live) if live == value => RestoreStatus::Unchanged,
+                    _ => match bus.write(id, register, value) {
+                        Ok(_) => RestoreStatus::Written,
+                        Err(e) => RestoreStatus::Failed(e),
+                    },
+                },
+            };
+            RestoreRow { name: name.clone(), value, status }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendCall, MockBackend, MockServo};
+
+    fn dump(entries: &[(&str, i32)]) -> ConfigDump {
+        ConfigDump { registers: entries.iter().map(|&(name, value)| (name.to_string(), value)).collect() }
+    }
+
+    fn status<'a>(rows: &'a [RestoreRow], name: &str) -> &'a RestoreStatus {
+        &rows.iter().find(|row| row.name == name).unwrap().status
+    }
+
+    #[test]
+    fn restore_skips_communication_registers() {
+        let mock = MockBackend::new().with_servo(2, MockServo::default());
+        let file = dump(&[("Return Delay", 250), ("Response Status Level", 1), ("P Coefficient", 40), ("ID", 2)]);
+        let rows = restore(&mut RegisterPort::new(&mock), 2, &file);
+
+        assert!(matches!(status(&rows, "Return Delay"), RestoreStatus::Skipped(_)));
+        assert!(matches!(status(&rows, "Response Status Level"), RestoreStatus::Skipped(_)));
+        assert_eq!(status(&rows, "P Coefficient"), &RestoreStatus::Written);
+        assert_eq!(status(&rows, "ID"), &RestoreStatus::Unchanged);
+        let written: Vec<u8> = mock
+            .calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                BackendCall::WriteRegister { address, .. } => Some(address),
+                _ => None,
+            })
+            .collect();
+        assert!(!written.contains(&7) && !written.contains(&8));
+    }
+
+    #[test]
+    fn matching_values_are_left_alone() {
+        let mock = MockBackend::new().with_servo(2, MockServo::default());
+        mock.set_register(2, 21, 32);
+        let rows = restore(&mut RegisterPort::new(&mock), 2, &dump(&[("P", 32), ("Bogus", 1)]));
+        assert_eq!(status(&rows, "P"), &RestoreStatus::Unchanged);
+        assert!(matches!(status(&rows, "Bogus"), RestoreStatus::Skipped(_)));
+        assert!(mock.calls().is_empty());
+    }
+
+    #[test]
+    fn dump_round_trips_through_json_and_toml() {
+        let mock = MockBackend::new().with_servo(2, MockServo::default());
+        mock.set_register(2, 5, 2);
+        let read = ConfigDump::read(&mut RegisterPort::new(&mock), 2).unwrap();
+        assert_eq!(read.registers.len(), REGISTERS.len());
+        assert_eq!(read.id(), Some(2));
+
+        for ext in ["json", "toml"] {
+            let path = std::env::temp_dir().join(format!("init-servo-backup-{}.{}", std::process::id(), ext));
+            read.save(&path).unwrap();
+            let loaded = ConfigDump::load(&path);
+            std::fs::remove_file(&path).ok();
+            assert_eq!(loaded.unwrap(), read);
+        }
+    }
+
+    #[test]
+    fn check_model_refuses_another_model_unless_forced() {
+        let mock = MockBackend::new().with_servo(2, MockServo::default());
+        let mut bus = RegisterPort::new(&mock);
+        let read = ConfigDump::read(&mut bus, 2).unwrap();
+        assert_eq!(check_model(&mut bus, 2, &read, false), Ok(()));
+
+        let mut other = read.clone();
+        *other.registers.get_mut("Model").unwrap() += 1;
+        assert!(check_model(&mut bus, 2, &other, false).unwrap_err().contains("model"));
+        assert_eq!(check_model(&mut bus, 2, &other, true), Ok(()));
+        assert!(check_model(&mut bus, 2, &dump(&[("ID", 2)]), false).is_err());
+    }
+}
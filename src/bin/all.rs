@@ -1,19 +1,184 @@
 use eframe::egui;
-use st3215::ST3215;
-use std::collections::BTreeMap;
+use servo_control::choreography::{Choreography, ChoreographyServo, PhaseClock, Waveform, CHOREOGRAPHY_FILE};
+use servo_control::coalesce::{self, SliderMode};
+use servo_control::config::{self, BusConfig, Config, ServoSettings};
+use servo_control::derating::{Derating, DeratingCurve, ThermalLockout};
+use servo_control::estop::{self, EmergencyStop};
+use servo_control::follow::FollowSettings;
+use servo_control::gamepad::{self, BindingTarget, CalibrationForm, Gamepad, GamepadSettings, PadCommand, PadController, PadStatus};
+use servo_control::events::{self, Event, EventStore};
+use servo_control::grip::{GripController, GripSettings, GripStatus};
+use servo_control::groups::{self, ServoGroup};
+use servo_control::hotplug::{self, Presence, RescanSettings};
+use servo_control::ids::{self, ScanRange};
+use servo_control::keyframes::{Keyframe, KeyframeSequence, Playback};
+use servo_control::latency::{self, CommandTiming, LatencyStats, Timed};
+use servo_control::identity::ServoIdentity;
+use servo_control::inversion::{self, Inversions, Mapping};
+use servo_control::jog;
+use servo_control::limits::{SoftLimits, TorqueLimit};
+use servo_control::logging;
+use servo_control::recording::{self, Recorder, Replay};
+use servo_control::regdiff::{self, RegisterCache, RegisterDiff};
+use servo_control::registers::{self, Register, PRESENT_LOAD};
+use servo_control::report::{Metric, SessionReport, SessionTelemetry};
+use servo_control::oplock::OperationLock;
+use servo_control::overrides::{OverrideKind, Overrides, DEFAULT_OVERRIDE_DURATION};
+use servo_control::portlock::{self, ConflictChoice, LockOwner};
+use servo_control::packet;
+use servo_control::palette::{Action, PaletteState};
+use servo_control::odometer::{self, Odometer, OdometerEntry, ODOMETER_FILE};
+use servo_control::mode::{ServoMode, MAX_WHEEL_SPEED};
+use servo_control::motion::{coordinated_speeds, MAX_SPEED};
+use servo_control::report::format_duration;
+use servo_control::response::{self, CommandId, Responder, Tracker};
+use servo_control::retry::{self, CommErrors, ErrorCount};
+use servo_control::shutdown::{self, ExitSettings, ShutdownSignal};
+use servo_control::plugins::TelemetryFrame;
+use servo_control::poses::{Pose, PoseLibrary, POSES_FILE};
+use servo_control::sim::Simulation;
+use servo_control::sound::{SoundAlerts, SoundClass};
+use servo_control::stall::{self, Stall, StallAction, StallSettings};
+use servo_control::telemetrylog::{self, LogSettings, TelemetryLog};
+use servo_control::warmup::{Warmup, WarmupEnd, WarmupSettings};
+use servo_control::theme::{self, temperature_status, Palette, Status, Theme};
+use servo_control::units::{self, degrees_to_ticks, ticks_to_degrees, AngleDisplay};
+use servo_control::dryrun::Driver;
+use servo_control::validation::{
+    validate_move, validate_wheel_speed, MoveConstraints, ValidatedMove, ValidationError, MAX_ACCELERATION,
+};
+use servo_control::worker::{Command, Dispatcher, Executed, PollPlan, Protection, ServoWorker, WorkerEvent};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::sync::mpsc::{channel, Receiver, Sender};
-use std::thread;
-use std::time::{Duration, Instant};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 // --- CONSTANTES ---
-const SERIAL_PORT: &str = "/dev/ttyACM0";
-const MAX_SERVO_ID: u8 = 15;
+const COPY_DEFAULT_SPEED: u16 = 300;
+// Vitesse plafond de « Center all » (mise en route d'un robot)
+const CENTER_SPEED: u16 = 400;
+const COORDINATED_ACCELERATION: u8 = 50;
+// Réglages de mouvement d'un servo tant que l'utilisateur n'en a pas choisi (0 = vitesse max)
+const DEFAULT_SPEED: u16 = 0;
+const DEFAULT_ACCELERATION: u8 = 50;
+// Écart max (ticks) entre position lue et consigne pour considérer un servo arrivé
+const ARRIVAL_TOLERANCE: u16 = 10;
+// Cycles de polling entre deux lectures de courant, de vitesse et du drapeau de mouvement
+const CURRENT_POLL_CYCLES: u32 = 3;
+const SPEED_POLL_CYCLES: u32 = 2;
+// Charge signée lue par accès registre direct (port rouvert) : seulement tous les N cycles
+const LOAD_POLL_CYCLES: u32 = 25;
+// Au-delà de ce multiple de la tolérance, l'écart est affiché en rouge
+const DELTA_LARGE_FACTOR: u16 = 10;
+// Servo hors tolérance immobile depuis ce délai : considéré bloqué
+const STUCK_AFTER: Duration = Duration::from_secs(1);
+const ODOMETER_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+// Pause entre deux cycles du worker, sauf `[bus] poll_interval_ms`
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+// Alerte d'un ID dont les réponses au scan sont incohérentes
+const DUPLICATE_ALERT: &str = "possible duplicate ID: moves blocked";
 
 // --- COMMANDES ---
+// Sources des commandes, pour les statistiques de latence
+const SOURCE_CARD: &str = "servo card";
+const SOURCE_COPY: &str = "copy position";
+const SOURCE_COORDINATED: &str = "coordinated";
+const SOURCE_CHOREOGRAPHY: &str = "choreography";
+const SOURCE_WARMUP: &str = "warm-up";
+const SOURCE_POSE: &str = "pose";
+const SOURCE_SEQUENCE: &str = "keyframe sequence";
+const SOURCE_TEACH: &str = "teach";
+const SOURCE_GROUP: &str = "group";
+const SOURCE_GAMEPAD: &str = "gamepad";
+const SOURCE_BULK: &str = "bulk actions";
+// Nom du groupe implicite des actions sur tous les servos
+const ALL_SERVOS: &str = "all servos";
+
+#[derive(Debug)]
 enum AppCommand {
-    Move { id: u8, position: u16, speed: u16 },
-    ToggleTorque { id: u8, enable: bool },
+    // Mouvement, couple et arrêt d'urgence, exécutés par le `Dispatcher` commun aux deux interfaces
+    Servo(Command),
+    // Passage en mode position (roue arrêtée d'abord) ou en mode roue
+    SetMode { id: u8, mode: ServoMode },
+    // Vitesse signée d'un servo en mode roue
+    Rotate { id: u8, speed: i16 },
+    // Écriture relue du registre Torque Limit
+    SetTorqueLimit { id: u8, limit: TorqueLimit },
+    Grip { id: u8, settings: GripSettings },
+    Release { id: u8, settings: GripSettings },
+    // Pose : (id, consigne, vitesse max)
+    CoordinatedMove { targets: Vec<(u8, u16, u16)>, duration: Duration },
+    // Consignes (id, position, vitesse) envoyées en un seul sync write ; avec `sync`, vitesses
+    // recalculées pour une arrivée simultanée en au moins cette durée (zéro = rythme de l'axe le
+    // plus lent), la vitesse donnée servant de plafond
+    MoveGroup { targets: Vec<(u8, u16, u16)>, sync: Option<Duration> },
+    ResetOdometer { id: u8 },
+    // Démarre ou met à jour la chorégraphie (None = arrêt)
+    Choreography(Option<Choreography>),
+    Registers(RegisterJob),
+    // Offsets matériels (Position Offset) des servos d'une copie de position
+    ReadOffsets { ids: Vec<u8> },
+    StartWarmup { ids: Vec<u8>, settings: WarmupSettings },
+    StopWarmup,
+    // Lecture d'une séquence d'images clés (remplace celle en cours)
+    PlaySequence { sequence: KeyframeSequence, looped: bool, stepped: bool },
+    PauseSequence(bool),
+    StopSequence,
+    // Apprentissage : couple coupé sur `ids`, positions relevées à `rate_hz`
+    StartTeach { ids: Vec<u8>, rate_hz: f32 },
+    StopTeach,
+    // Arrête le scan en cours ; les servos déjà trouvés sont gardés
+    CancelScan,
+    // Commande d'un groupe nommé, répartie sur ses membres à la sortie de la file
+    Group { group: String, command: GroupCommand },
+}
+
+// Commande de groupe ; les consignes passent par le sync write de `MoveGroup`
+#[derive(Debug)]
+enum GroupCommand {
+    // Chaque membre a son identifiant, suivi par sa carte comme un bouton de couple
+    Torque { members: Vec<(CommandId, u8)>, enable: bool },
+    // Consignes (id, position, vitesse) : pose du groupe ou décalage commun
+    Move { targets: Vec<(u8, u16, u16)> },
+}
+
+// Accès registre direct, exécuté en libérant la connexion du driver
+#[derive(Debug)]
+enum RegisterJob {
+    // `refresh` : relire les deux servos au lieu d'utiliser le cache
+    Compare { a: u8, b: u8, refresh: bool },
+    Copy { from: u8, to: u8, register: &'static Register },
+}
+
+impl AppCommand {
+    fn name(&self) -> &'static str {
+        match self {
+            AppCommand::Servo(command) => command.name(),
+            AppCommand::SetMode { .. } => "mode",
+            AppCommand::Rotate { .. } => "rotate",
+            AppCommand::SetTorqueLimit { .. } => "torque limit",
+            AppCommand::Grip { .. } => "grip",
+            AppCommand::Release { .. } => "release",
+            AppCommand::CoordinatedMove { .. } => "coordinated move",
+            AppCommand::MoveGroup { .. } => "group move",
+            AppCommand::ResetOdometer { .. } => "reset odometer",
+            AppCommand::Choreography(_) => "choreography",
+            AppCommand::StartWarmup { .. } => "warm-up start",
+            AppCommand::StopWarmup => "warm-up stop",
+            AppCommand::PlaySequence { .. } => "sequence play",
+            AppCommand::PauseSequence(_) => "sequence pause",
+            AppCommand::StopSequence => "sequence stop",
+            AppCommand::StartTeach { .. } => "teach start",
+            AppCommand::StopTeach => "teach stop",
+            AppCommand::CancelScan => "scan cancel",
+            AppCommand::Group { .. } => "group",
+            AppCommand::Registers(RegisterJob::Compare { .. }) => "register compare",
+            AppCommand::Registers(RegisterJob::Copy { .. }) => "register copy",
+            AppCommand::ReadOffsets { .. } => "read offsets",
+        }
+    }
 }
 
 // --- ÉTAT D'UN SERVO UNIQUE ---
@@ -22,258 +187,4380 @@ struct IndividualServo {
     id: u8,
     current_pos: u16,      // Position réelle lue
     target_pos: u16,       // Position du slider (consigne)
+    // Vitesse (0 = max) et accélération envoyées avec la consigne du slider
+    target_speed: u16,
+    acceleration: u8,
     temperature: u8,
     voltage: f32,
+    // Charge en %, signée selon le sens de l'effort
     load: f32,
+    current: f32,
+    speed: i16,
+    is_moving: bool,
     torque_on: bool,
+    // Mode lu à la détection ; en mode roue, le slider de vitesse remplace celui de position
+    mode: ServoMode,
+    wheel_speed: i16,
+    // Limite de couple relue (None avant la lecture du scan) et valeur du slider (%)
+    torque_limit: Option<TorqueLimit>,
+    torque_limit_input: f32,
+    // Modèle et firmware, lus avec la limite de couple
+    identity: Option<ServoIdentity>,
+    grip: GripSettings,
+    grip_status: GripStatus,
+    speed_cap: u16,
+    // Dernier instant où la position lue a changé
+    moved_at: Instant,
+    odometer: Option<OdometerEntry>,
+    // Premier clic sur « Reset odometer », en attente de confirmation
+    odometer_reset_armed: bool,
+    limits: SoftLimits,
+    // Plafond de vitesse dû à la température (%), et verrou thermique (vrai une fois refroidi)
+    derating_percent: u8,
+    thermal_lockout: Option<bool>,
+    // Arrêté par l'arrêt d'urgence, jusqu'à la réactivation de son couple
+    emergency_stopped: bool,
+    // Raison du dernier refus de consigne, effacée par la consigne acceptée suivante
+    rejection: Option<String>,
+    // Premier mouvement refusé par la garde, en attente de confirmation sur la carte
+    unconfirmed_move: Option<u16>,
+    // Dernière consigne ramenée dans les butées : (demandée, envoyée)
+    clamped: Option<(u16, u16)>,
+    // Blocage mécanique détecté, jusqu'à l'acquittement sur la carte
+    stall: Option<Stall>,
+    // Hors ligne au-delà de `hotplug::OFFLINE_AFTER` sans réponse : carte grisée, relu rarement
+    presence: Presence,
+    // Réponses incohérentes au scan : plusieurs servos semblent partager cet ID
+    duplicate_id: bool,
+    // Échecs de transaction (nouvelles tentatives comprises), relevés à chaque affichage
+    comm_errors: ErrorCount,
+    // Nom saisi sur la carte, sens inversé, vitesse/accélération propres au servo et suivi d'un
+    // autre servo : gardés dans `[servos]` de la configuration
+    name: String,
+    inverted: bool,
+    motion_saved: bool,
+    follow: FollowSettings,
+}
+
+// Libellés des servos (« coude gauche (3) ») ; « ID n » pour un servo inconnu
+struct Labels(BTreeMap<u8, String>);
+
+impl Labels {
+    fn get(&self, id: u8) -> String {
+        self.0.get(&id).cloned().unwrap_or_else(|| config::servo_label(id, ""))
+    }
+
+    fn list(&self, ids: impl IntoIterator<Item = u8>) -> String {
+        ids.into_iter().map(|id| self.get(id)).collect::<Vec<_>>().join(", ")
+    }
+}
+
+// Contraintes courantes d'un servo, pour `validate_move`
+fn constraints_of(state: &SharedState, deratings: &HashMap<u8, Derating>, thermal: &ThermalLockout, id: u8) -> MoveConstraints {
+    MoveConstraints {
+        limits: state.limits_of(id),
+        speed_cap: None,
+        derating: deratings.get(&id).copied().unwrap_or_default(),
+        cut_off: thermal.is_locked(id),
+        emergency_stop: state.estop.is_stopped(id),
+        stalled: state.servos.get(&id).is_some_and(|s| s.stall.is_some()),
+        duplicate_id: state.servos.get(&id).is_some_and(|s| s.duplicate_id),
+        wheel_mode: state.servos.get(&id).is_some_and(|s| s.mode == ServoMode::Wheel),
+        first_move: None,
+    }
+}
+
+// Retour vers la carte : raison du refus, ou effacement si la consigne est acceptée
+fn report_validation(state: &Arc<Mutex<SharedState>>, id: u8, error: Option<&ValidationError>) {
+    if let Some(servo) = state.lock().unwrap().servos.get_mut(&id) {
+        servo.rejection = error.map(|e| e.to_string());
+    }
+}
+
+// Consigne ramenée dans les butées logicielles : signalée sur la carte et dans la console
+fn report_clamp(state: &Arc<Mutex<SharedState>>, id: u8, requested: u16, validated: &ValidatedMove) {
+    let mut s = state.lock().unwrap();
+    let limits = s.limits_of(id);
+    if let Some(servo) = s.servos.get_mut(&id) {
+        servo.clamped = validated.clamped.then_some((requested, validated.position));
+    }
+    if validated.clamped {
+        println!(
+            "ID {}: consigne {} ramenée à {} (butées {}-{})",
+            id, requested, validated.position, limits.min, limits.max
+        );
+    }
+}
+
+// Blocage confirmé : arrêt selon le réglage, servo marqué bloqué jusqu'à l'acquittement
+fn stop_stalled(driver: &Driver, state: &Arc<Mutex<SharedState>>, id: u8, stall: Stall) {
+    let mut s = state.lock().unwrap();
+    let action = s.stall.action;
+    let Some(servo) = s.servos.get_mut(&id) else { return };
+    match action {
+        // En mode roue, tenir la position revient à arrêter la roue
+        StallAction::Hold if servo.mode == ServoMode::Wheel => {
+            servo.wheel_speed = 0;
+            s.record_outcome(id, "stall wheel stop", driver.rotate(id, 0));
+        }
+        StallAction::Hold => {
+            let position = servo.current_pos;
+            servo.target_pos = position;
+            s.record_outcome(id, "stall hold", sent(driver.move_to(id, position, 0, DEFAULT_ACCELERATION, false)));
+        }
+        StallAction::TorqueOff => {
+            servo.torque_on = false;
+            s.record_outcome(id, "stall torque off", driver.disable_torque(id));
+        }
+    }
+    let Some(servo) = s.servos.get_mut(&id) else { return };
+    servo.stall = Some(stall);
+    println!("ID {}: blocage détecté ({}), {}", id, stall.describe(), action.label());
+    s.sounds.notify(SoundClass::Stall);
+}
+
+// État initial d'un servo trouvé par un scan à la position `pos`
+fn detected_servo(driver: &Driver, id: u8, pos: u16, thermal: &ThermalLockout) -> IndividualServo {
+    let temp = driver.read_temperature(id).unwrap_or(0);
+    let volt = driver.read_voltage(id).unwrap_or(0.0);
+    let duplicate_id = ids::probe_duplicate(|| driver.read_position(id));
+    if duplicate_id {
+        println!("/!\\ ID {} : plusieurs servos semblent répondre, n'en gardez qu'un branché", id);
+    }
+    IndividualServo {
+        id,
+        current_pos: pos,
+        target_pos: pos, // IMPORTANT: Le slider commence à la position actuelle !
+        target_speed: DEFAULT_SPEED,
+        acceleration: DEFAULT_ACCELERATION,
+        temperature: temp,
+        voltage: volt,
+        load: 0.0,
+        current: 0.0,
+        speed: 0,
+        is_moving: false,
+        torque_on: false, // Par défaut souvent off au démarrage
+        mode: driver.read_mode(id).and_then(ServoMode::from_register).unwrap_or_default(),
+        wheel_speed: 0,
+        torque_limit: None,
+        torque_limit_input: 100.0,
+        identity: None,
+        grip: GripSettings::default(),
+        grip_status: GripStatus::Idle,
+        speed_cap: MAX_SPEED,
+        moved_at: Instant::now(),
+        odometer: None,
+        odometer_reset_armed: false,
+        limits: SoftLimits::default(),
+        derating_percent: 100,
+        thermal_lockout: thermal.state(id),
+        emergency_stopped: false,
+        rejection: None,
+        unconfirmed_move: None,
+        clamped: None,
+        stall: None,
+        presence: Presence::new(Instant::now()),
+        comm_errors: ErrorCount::default(),
+        name: String::new(),
+        inverted: false,
+        motion_saved: false,
+        follow: FollowSettings::default(),
+        duplicate_id,
+    }
+}
+
+// Commande de groupe remplacée par les commandes de ses membres, traitées comme celles des cartes
+fn fan_out(timed: Timed<AppCommand>) -> Vec<Timed<AppCommand>> {
+    let Timed { id, source, enqueued, command } = timed;
+    let AppCommand::Group { group, command } = command else {
+        return vec![Timed { id, source, enqueued, command }];
+    };
+    log::debug!(target: logging::WORKER, "#{} group '{}': {:?}", id, group, command);
+    match command {
+        GroupCommand::Torque { members, enable } => members
+            .into_iter()
+            .map(|(member, servo)| Timed { id: member, source, enqueued, command: AppCommand::Servo(Command::Torque { id: servo, enable }) })
+            .collect(),
+        GroupCommand::Move { targets } => vec![Timed { id, source, enqueued, command: AppCommand::MoveGroup { targets, sync: None } }],
+    }
+}
+
+// Réponse d'un envoi de consigne, au format des autres écritures
+fn sent(reply: Option<bool>) -> Result<(), String> {
+    reply.map(|_| ()).ok_or_else(|| "no response".to_string())
+}
+
+// Retour en fin d'activité (chorégraphie, échauffement), validé comme toute consigne : un servo
+// arrêté, bloqué ou verrouillé entre-temps reste où il est, et le refus est journalisé
+fn return_to(
+    driver: &Driver,
+    s: &mut SharedState,
+    deratings: &HashMap<u8, Derating>,
+    thermal: &ThermalLockout,
+    id: u8,
+    position: u16,
+    what: &str,
+) {
+    let outcome = validate_move(&constraints_of(s, deratings, thermal, id), position.into(), 0, 50)
+        .map_err(|e| e.to_string())
+        .and_then(|m| sent(driver.move_to(id, m.position, m.speed, m.acceleration, false)));
+    s.record_outcome(id, what, outcome);
+}
+
+// Gravité de l'écart consigne − position réelle
+#[derive(Clone, Copy, PartialEq)]
+enum DeltaSeverity {
+    OnTarget,
+    Moderate,
+    Large,
+    Stuck,
+}
+
+impl IndividualServo {
+    fn delta(&self) -> i32 {
+        self.target_pos as i32 - self.current_pos as i32
+    }
+
+    fn delta_severity(&self, tolerance: u16) -> DeltaSeverity {
+        let delta = self.delta().unsigned_abs();
+        if delta <= tolerance as u32 {
+            DeltaSeverity::OnTarget
+        } else if self.moved_at.elapsed() > STUCK_AFTER {
+            DeltaSeverity::Stuck
+        } else if delta <= tolerance as u32 * DELTA_LARGE_FACTOR as u32 {
+            DeltaSeverity::Moderate
+        } else {
+            DeltaSeverity::Large
+        }
+    }
+}
+
+// --- COPIE DE POSITION ENTRE SERVOS ---
+#[derive(Clone, Debug, PartialEq)]
+enum CopySource {
+    Servo(u8),
+    // Entrée `entry` d'une pose du fichier des poses
+    Pose { name: String, entry: u8 },
+    Manual(u16),
+}
+
+#[derive(Clone, Debug)]
+struct CopyRequest {
+    source: CopySource,
+    to: u8,
+    speed: u16,
+    manual_in_degrees: bool,
+    // Offsets matériels demandés au worker pour comparer les correspondances
+    offsets_requested: bool,
+    // Envoi confirmé malgré des correspondances différentes
+    mapping_confirmed: bool,
+}
+
+impl CopyRequest {
+    fn new(source: CopySource, to: u8) -> Self {
+        Self { source, to, speed: COPY_DEFAULT_SPEED, manual_in_degrees: false, offsets_requested: false, mapping_confirmed: false }
+    }
+}
+
+// --- MOUVEMENT COORDONNÉ ---
+#[derive(Clone, Debug)]
+struct CoordinatedSettings {
+    enabled: bool,
+    duration_s: f32,
+}
+
+impl Default for CoordinatedSettings {
+    fn default() -> Self {
+        Self { enabled: false, duration_s: 2.0 }
+    }
+}
+
+// Résultat de la détection d'arrivée après un mouvement coordonné
+#[derive(Clone, Debug)]
+struct CoordinatedReport {
+    planned: Duration,
+    first_arrival: Option<Duration>,
+    last_arrival: Option<Duration>,
+    not_arrived: Vec<u8>,
+    // Position illisible au départ : vitesse calculée pour le trajet le plus long
+    unread: Vec<u8>,
+}
+
+// Mouvement coordonné en cours : les arrivées sont relevées par la scrutation de chaque cycle
+struct CoordinatedRun {
+    start: Instant,
+    planned: Duration,
+    targets: Vec<(u8, u16)>,
+    arrivals: BTreeMap<u8, Duration>,
+    unread: Vec<u8>,
+}
+
+impl CoordinatedRun {
+    fn observe(&mut self, positions: &HashMap<u8, u16>, now: Instant) {
+        for &(id, target) in &self.targets {
+            if positions.get(&id).is_some_and(|pos| pos.abs_diff(target) <= ARRIVAL_TOLERANCE) {
+                self.arrivals.entry(id).or_insert_with(|| now.saturating_duration_since(self.start));
+            }
+        }
+    }
+
+    // Tous arrivés, ou délai dépassé avec une marge sur la durée prévue
+    fn is_over(&self, now: Instant) -> bool {
+        self.arrivals.len() == self.targets.len() || now.saturating_duration_since(self.start) > self.planned * 2 + Duration::from_secs(1)
+    }
+
+    fn report(&self) -> CoordinatedReport {
+        CoordinatedReport {
+            planned: self.planned,
+            first_arrival: self.arrivals.values().min().copied(),
+            last_arrival: self.arrivals.values().max().copied(),
+            not_arrived: self.targets.iter().map(|&(id, _)| id).filter(|id| !self.arrivals.contains_key(id)).collect(),
+            unread: self.unread.clone(),
+        }
+    }
+}
+
+// --- CHORÉGRAPHIE ---
+#[derive(Default)]
+struct ChoreographyState {
+    config: Choreography,
+    running: bool,
+    // Phase globale courante (tours), renvoyée par le worker pour le diagramme
+    phase: f64,
+    status: Option<String>,
+}
+
+// Chorégraphie en cours côté worker : positions centrales mémorisées au démarrage
+struct ChoreographyRun {
+    config: Choreography,
+    clock: PhaseClock,
+    centers: HashMap<u8, u16>,
+    last_tick: Instant,
+}
+
+// --- POSES NOMMÉES ---
+#[derive(Default)]
+struct PoseState {
+    library: PoseLibrary,
+    // Nom saisi pour la prochaine capture
+    name: String,
+    // Rappel synchronisé : tous les servos arrivent ensemble, en `duration_s` (0 = axe le plus lent)
+    sync: bool,
+    duration_s: f32,
+    status: Option<(Status, String)>,
+}
+
+// --- GROUPES DE SERVOS ---
+#[derive(Default)]
+struct GroupState {
+    // Groupes de la configuration, écartés en bloc s'ils sont incohérents
+    groups: Vec<ServoGroup>,
+    // Décalage commun en cours, par nom de groupe
+    offsets: HashMap<String, GroupOffset>,
+    status: Option<(Status, String)>,
+}
+
+// Slider de décalage d'un groupe : consignes des membres au début du glissement
+#[derive(Default)]
+struct GroupOffset {
+    offset: i32,
+    base: Option<BTreeMap<u8, u16>>,
+}
+
+// --- ACTIONS SUR TOUS LES SERVOS ---
+struct BulkState {
+    // Consigne de « Go to », en ticks
+    goto: u16,
+    status: Option<(Status, String)>,
+}
+
+impl Default for BulkState {
+    fn default() -> Self {
+        Self { goto: units::CENTER_TICKS, status: None }
+    }
+}
+
+// --- SÉQUENCE D'IMAGES CLÉS ---
+struct KeyframeState {
+    path: String,
+    looped: bool,
+    // Une image par étape, atteinte par tous ses servos ensemble, au lieu de l'interpolation
+    stepped: bool,
+    // Lecture en cours et avancement, tenus à jour par le worker
+    playing: bool,
+    paused: bool,
+    progress: f32,
+    status: Option<(Status, String)>,
+}
+
+impl Default for KeyframeState {
+    fn default() -> Self {
+        Self {
+            path: "sequence.json".to_string(),
+            looped: false,
+            stepped: false,
+            playing: false,
+            paused: false,
+            progress: 0.0,
+            status: None,
+        }
+    }
+}
+
+// --- APPRENTISSAGE À LA MAIN ---
+struct TeachState {
+    // Servos laissés hors de l'apprentissage : un servo détecté ensuite y participe
+    excluded: BTreeSet<u8>,
+    rate_hz: f32,
+    active: bool,
+    // Images relevées pendant l'enregistrement en cours, renvoyées par le worker
+    samples: usize,
+    // Dernier enregistrement terminé, et plage (s) gardée à l'enregistrement du fichier
+    recording: Option<KeyframeSequence>,
+    trim: (f64, f64),
+    path: String,
+    status: Option<(Status, String)>,
+}
+
+impl Default for TeachState {
+    fn default() -> Self {
+        Self {
+            excluded: BTreeSet::new(),
+            rate_hz: 10.0,
+            active: false,
+            samples: 0,
+            recording: None,
+            trim: (0.0, 0.0),
+            path: "taught.json".to_string(),
+            status: None,
+        }
+    }
+}
+
+// Apprentissage en cours côté worker
+struct TeachRun {
+    ids: Vec<u8>,
+    period: Duration,
+    started: Instant,
+    next_sample: Instant,
+    sequence: KeyframeSequence,
+}
+
+// --- ÉCHAUFFEMENT ---
+#[derive(Default)]
+struct WarmupState {
+    settings: WarmupSettings,
+    selected: BTreeSet<u8>,
+    // Servos en cours, renvoyés par le worker
+    running: BTreeMap<u8, WarmupProgress>,
+    // Fin de chaque échauffement, la plus récente en dernier
+    log: Vec<String>,
+}
+
+#[derive(Clone, Copy)]
+struct WarmupProgress {
+    elapsed: Duration,
+    start_temperature: Option<u8>,
+    temperature: u8,
+    // °C/min
+    rise_rate: Option<f64>,
+}
+
+// --- RÉGLAGES DU BUS ---
+// Saisie du port et de la plage de scan, appliquée par un nouveau scan
+#[derive(Default)]
+struct BusForm {
+    open: bool,
+    port: String,
+    start: u8,
+    end: u8,
+    error: Option<String>,
+}
+
+// --- DÉROGATIONS TEMPORAIRES ---
+struct OverrideForm {
+    kind: OverrideKind,
+    servo: Option<u8>,
+    minutes: f64,
+    // Dérogation déjà active : la prolonger demande une nouvelle confirmation
+    confirm_extension: bool,
+    log: Vec<String>,
+}
+
+impl Default for OverrideForm {
+    fn default() -> Self {
+        Self {
+            kind: OverrideKind::SoftLimits,
+            servo: None,
+            minutes: DEFAULT_OVERRIDE_DURATION.as_secs_f64() / 60.0,
+            confirm_extension: false,
+            log: Vec::new(),
+        }
+    }
+}
+
+// Grant et revert sont journalisés dans la console et dans le panneau
+fn log_override(form: &mut OverrideForm, line: String) {
+    log::warn!(target: logging::OVERRIDE, "{}", line);
+    form.log.push(line);
+}
+
+// --- COMPARAISON DE REGISTRES ---
+#[derive(Default)]
+struct RegisterCompareState {
+    open: bool,
+    a: u8,
+    b: u8,
+    // Paire comparée et registres qui diffèrent
+    compared: Option<(u8, u8)>,
+    rows: Vec<RegisterDiff>,
+    // Copie A→B en attente de confirmation (écriture EEPROM)
+    confirm: Option<&'static Register>,
+    busy: bool,
+    status: Option<String>,
 }
 
 // --- ÉTAT GLOBAL DE L'APPLICATION ---
 struct SharedState {
     connected: bool,
+    port: String,
+    // Servos virtuels (--simulate) et port du bus simulé
+    simulation: Option<(Simulation, String)>,
+    // Rejeu d'un enregistrement (--replay) à la place du port série
+    replay: Option<Replay>,
+    // Ne jamais quitter le port configuré (plusieurs adaptateurs identiques)
+    pin_port: bool,
+    // Autre instance qui pilote le port, et choix fait dans la fenêtre de conflit
+    port_conflict: Option<LockOwner>,
+    port_choice: Option<ConflictChoice>,
+    // Commandes dont le résultat est attendu, et notifications d'échec
+    commands: Tracker,
+    // Compteurs d'échecs de transaction par servo, tenus par le bus du worker
+    comm_errors: CommErrors,
+    // Enregistrement de session, tenu par le bus du worker
+    recorder: Recorder,
+    // Servos inversés : le bus du worker convertit leurs positions logiques en ticks bruts
+    inversions: Inversions,
+    scan_range: ScanRange,
+    // Le worker ferme la connexion puis reconnecte et rescanne avec `port` et `scan_range`
+    rescan_requested: bool,
+    bus_form: BusForm,
     // On utilise BTreeMap pour qu'ils soient triés par ID (1, 2, 3...) automatiquement
     servos: BTreeMap<u8, IndividualServo>, 
+    copy_request: Option<CopyRequest>,
+    // Position Offset lu par servo, pour comparer les correspondances d'une copie
+    position_offsets: HashMap<u8, i32>,
+    coordinated: CoordinatedSettings,
+    coordinated_report: Option<CoordinatedReport>,
+    choreography: ChoreographyState,
+    poses: PoseState,
+    groups: GroupState,
+    bulk: BulkState,
+    keyframes: KeyframeState,
+    teach: TeachState,
+    register_compare: RegisterCompareState,
+    warmup: WarmupState,
+    overrides: Overrides,
+    override_form: OverrideForm,
+    stall: StallSettings,
+    // Liaisons de la manette, et manette vue par le worker
+    gamepad: GamepadSettings,
+    pad_status: PadStatus,
+    pad_calibration: CalibrationForm,
+    // Rescan périodique des IDs absents de la plage
+    rescan: RescanSettings,
+    // Scan de connexion en cours : (prochain ID, dernier ID)
+    scan_progress: Option<(u8, u8)>,
+    // Écriture destructive en cours (changement de mode, limite de couple, copie EEPROM) : une
+    // seule à la fois, la télémétrie du servo concerné est suspendue
+    operation: OperationLock,
+    delta_tolerance: u16,
+    slider_mode: SliderMode,
+    // Unité d'affichage des positions (les consignes restent en ticks)
+    angle: AngleDisplay,
+    // Vitesse et accélération par servo, gardées d'une connexion à l'autre, et réglage
+    // commun appliqué aux servos sans réglage propre
+    motion_memory: HashMap<u8, (u16, u8)>,
+    motion_defaults: (u16, u8),
+    // Butées enregistrées dans le fichier de configuration, réappliquées après chaque scan
+    saved_limits: BTreeMap<u8, SoftLimits>,
+    theme: Theme,
+    latency: LatencyStats,
+    sounds: SoundAlerts,
+    estop: EmergencyStop,
+    // Échecs du bus rapportés par le worker, affichés dans le panneau du bas
+    events: EventStore,
+    // Début de session, min/max et énergie par servo : matière du rapport de fin de session
+    started: Instant,
+    telemetry: SessionTelemetry,
+    report_status: Option<String>,
+    // Journal continu sur disque, tenu par le worker
+    log_enabled: bool,
+    log_settings: LogSettings,
+    // Parcage et coupure du couple à la fermeture, appliqués par le worker
+    exit: ExitSettings,
+    // Réglages par servo enregistrés (`[servos]`) : repris à la détection, et affichés en carte
+    // « non détecté » pour les servos absents du scan
+    servo_settings: BTreeMap<u8, ServoSettings>,
+    // Résultat du dernier « Save settings »
+    settings_status: Option<String>,
+    log_status: Option<String>,
+}
+
+impl SharedState {
+    // Butées effectives : levées le temps d'une dérogation
+    fn limits_of(&self, id: u8) -> SoftLimits {
+        match self.servos.get(&id) {
+            Some(_) if self.overrides.is_active(OverrideKind::SoftLimits, id) => SoftLimits::default(),
+            Some(servo) => servo.limits,
+            None => SoftLimits::default(),
+        }
+    }
+
+    // Vrai si l'écriture ou la lecture a réussi ; sinon l'échec est journalisé
+    fn record_outcome(&mut self, id: u8, what: &str, outcome: Result<(), String>) -> bool {
+        match outcome {
+            Ok(()) => true,
+            Err(e) => {
+                self.events.push(Event::Error { servo: Some(id), message: format!("{}: {}", what, e) });
+                false
+            }
+        }
+    }
+
+    // Réglages gardés d'une connexion à l'autre, réappliqués à un servo détecté
+    fn restore_settings(&self, servo: &mut IndividualServo) {
+        servo.emergency_stopped = self.estop.is_stopped(servo.id);
+        servo.limits = self.saved_limits.get(&servo.id).copied().unwrap_or_default();
+        let stored = self.servo_settings.get(&servo.id).cloned().unwrap_or_default();
+        let (speed, acceleration) = self.motion_defaults;
+        let defaults = (stored.speed.unwrap_or(speed), stored.acceleration.unwrap_or(acceleration));
+        (servo.target_speed, servo.acceleration) = self.motion_memory.get(&servo.id).copied().unwrap_or(defaults);
+        servo.motion_saved = stored.speed.is_some() || stored.acceleration.is_some();
+        servo.name = stored.name;
+        servo.inverted = stored.inverted;
+        servo.follow = stored.follow.unwrap_or_default();
+    }
+
+    // Nom de chaque servo suivi de son ID, pour tout affichage qui désigne un servo ; le nom saisi
+    // sur une carte prime sur celui du fichier
+    fn labels(&self) -> Labels {
+        let stored = self.servo_settings.iter().map(|(&id, settings)| (id, settings.name.as_str()));
+        let detected = self.servos.values().map(|servo| (servo.id, servo.name.as_str()));
+        Labels(stored.chain(detected).map(|(id, name)| (id, config::servo_label(id, name))).collect())
+    }
+
+    // Clé de l'odomètre : le nom enregistré, pas celui en cours de saisie sur la carte, qui
+    // créerait une entrée par frappe
+    fn odometer_key(&self, id: u8) -> String {
+        odometer::key(id, self.servo_settings.get(&id).map_or("", |settings| settings.name.as_str()))
+    }
+
+    // Servos enregistrés que le dernier scan n'a pas trouvés
+    fn missing(&self) -> Vec<u8> {
+        self.servo_settings.keys().copied().filter(|id| !self.servos.contains_key(id)).collect()
+    }
+
+    fn remember_motion(&mut self) {
+        for servo in self.servos.values() {
+            self.motion_memory.insert(servo.id, (servo.target_speed, servo.acceleration));
+        }
+    }
 }
 
 impl Default for SharedState {
     fn default() -> Self {
         Self {
             connected: false,
+            port: config::DEFAULT_PORT.to_string(),
+            simulation: None,
+            replay: None,
+            pin_port: false,
+            port_conflict: None,
+            port_choice: None,
+            commands: response::channel().1,
+            comm_errors: CommErrors::new(),
+            recorder: Recorder::new(),
+            inversions: Inversions::new(),
+            scan_range: ScanRange::default(),
+            rescan_requested: false,
+            bus_form: BusForm::default(),
             servos: BTreeMap::new(),
+            copy_request: None,
+            position_offsets: HashMap::new(),
+            coordinated: CoordinatedSettings::default(),
+            coordinated_report: None,
+            choreography: ChoreographyState::default(),
+            poses: PoseState::default(),
+            groups: GroupState::default(),
+            bulk: BulkState::default(),
+            keyframes: KeyframeState::default(),
+            teach: TeachState::default(),
+            register_compare: RegisterCompareState::default(),
+            warmup: WarmupState::default(),
+            overrides: Overrides::default(),
+            override_form: OverrideForm::default(),
+            stall: StallSettings::default(),
+            gamepad: GamepadSettings::default(),
+            pad_status: PadStatus::default(),
+            pad_calibration: CalibrationForm::default(),
+            rescan: RescanSettings::default(),
+            scan_progress: None,
+            operation: OperationLock::default(),
+            delta_tolerance: config::DEFAULT_DELTA_TOLERANCE,
+            slider_mode: SliderMode::default(),
+            angle: AngleDisplay::default(),
+            motion_memory: HashMap::new(),
+            motion_defaults: (DEFAULT_SPEED, DEFAULT_ACCELERATION),
+            saved_limits: BTreeMap::new(),
+            theme: Theme::default(),
+            latency: LatencyStats::default(),
+            sounds: SoundAlerts::new(),
+            estop: EmergencyStop::default(),
+            events: EventStore::new(Instant::now()),
+            started: Instant::now(),
+            telemetry: SessionTelemetry::default(),
+            report_status: None,
+            log_enabled: false,
+            log_settings: LogSettings::default(),
+            exit: ExitSettings::default(),
+            servo_settings: BTreeMap::new(),
+            settings_status: None,
+            log_status: None,
         }
     }
 }
 
+// --- PALETTE DE COMMANDES ---
+// Ctrl+P : actions de la barre d'en-tête, des poses et des cartes, cherchées au clavier
+type AllAction = Action<SharedState, egui::KeyboardShortcut>;
+
+fn shortcut(modifiers: egui::Modifiers, key: egui::Key) -> egui::KeyboardShortcut {
+    egui::KeyboardShortcut::new(modifiers, key)
+}
+
+fn palette_actions(state: &SharedState, tx: &Sender<Timed<AppCommand>>) -> Vec<AllAction> {
+    let any_online = |s: &SharedState| s.servos.values().any(|servo| s.connected && servo.presence.is_online());
+    let (estop_tx, off_tx, on_tx, center_tx) = (tx.clone(), tx.clone(), tx.clone(), tx.clone());
+    let mut actions = vec![
+        AllAction::new("Emergency stop", move |_| {
+            let _ = estop_tx.send(Timed::new(SOURCE_CARD, AppCommand::Servo(Command::EmergencyStop)));
+        })
+        .keywords("estop halt escape disable torque"),
+        AllAction::new("Torque off all", move |s| torque_all(s, &off_tx, false))
+            .keywords("disable release every servo")
+            .shortcut(shortcut(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::D))
+            .enabled_when(any_online),
+        AllAction::new("Torque on all", move |s| torque_all(s, &on_tx, true))
+            .keywords("enable hold every servo")
+            .shortcut(shortcut(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::E))
+            .enabled_when(any_online),
+        AllAction::new("Center all", move |s| move_all(s, &center_tx, units::CENTER_TICKS, Some(CENTER_SPEED)))
+            .keywords("home 2048 middle every servo")
+            .enabled_when(any_online),
+        AllAction::new("Rescan bus", |s| s.rescan_requested = true)
+            .keywords("scan reconnect detect servos")
+            .shortcut(shortcut(egui::Modifiers::COMMAND, egui::Key::R))
+            .enabled_when(|s| s.connected),
+        AllAction::new("Log telemetry to file", |s| s.log_enabled = !s.log_enabled).keywords("csv logging record toggle"),
+        AllAction::new("Generate session report", write_report).keywords("summary markdown"),
+        AllAction::new("Save settings", save_settings)
+            .keywords("config write toml")
+            .shortcut(shortcut(egui::Modifiers::COMMAND, egui::Key::S)),
+        AllAction::new("Bus settings", |s| {
+            let (port, range) = (s.port.clone(), s.scan_range);
+            s.bus_form = BusForm { open: true, port, start: range.start, end: range.end, error: None };
+        })
+        .keywords("port scan range serial"),
+        AllAction::new("Compare registers", |s| s.register_compare.open = !s.register_compare.open).keywords("eeprom diff"),
+    ];
+    // Pas d'enregistrement pendant un rejeu, comme le bouton de l'en-tête
+    if state.replay.is_none() {
+        let recording = state.recorder.is_recording();
+        let name = if recording { "Stop recording" } else { "Start recording" };
+        actions.push(
+            AllAction::new(name, move |s| {
+                if recording {
+                    s.recorder.stop();
+                } else if let Err(e) = s.recorder.start(recording::default_path()) {
+                    eprintln!("Could not start recording: {}", e);
+                }
+            })
+            .keywords("session record jsonl"),
+        );
+    }
+    for pose in &state.poses.library.poses {
+        let (name, tx) = (pose.name.clone(), tx.clone());
+        actions.push(
+            AllAction::new(format!("Go to pose: {}", pose.name), move |s| {
+                if let Some(pose) = s.poses.library.poses.iter().find(|pose| pose.name == name).cloned() {
+                    go_to_pose(s, &tx, &pose);
+                }
+            })
+            .keywords("pose move")
+            .enabled_when(any_online),
+        );
+    }
+    // Couple d'un servo, suivi comme le bouton de sa carte
+    for servo in state.servos.values() {
+        let (id, enable, tx) = (servo.id, !servo.torque_on, tx.clone());
+        let name = format!("Torque {}: {}", if enable { "on" } else { "off" }, config::servo_label(id, &servo.name));
+        actions.push(
+            AllAction::new(name, move |s| {
+                let timed = Timed::new(SOURCE_CARD, AppCommand::Servo(Command::Torque { id, enable }));
+                s.commands.track(timed.id, if enable { "torque on" } else { "torque off" }, Some(id));
+                let _ = tx.send(timed);
+            })
+            .keywords("servo card")
+            .enabled_when(move |s| s.servos.get(&id).is_some_and(|servo| servo.presence.is_online())),
+        );
+    }
+    actions
+}
+
 // --- APPLICATION GUI ---
 struct MultiServoApp {
     state: Arc<Mutex<SharedState>>,
-    tx: Sender<AppCommand>,
+    tx: Sender<Timed<AppCommand>>,
+    // Répétition : aucune écriture sur le bus (partagé avec le worker)
+    dry_run: Arc<AtomicBool>,
+    // Demande d'arrêt du worker, attendu à la fermeture
+    shutdown: ShutdownSignal,
+    worker: Option<JoinHandle<()>>,
+    palette: PaletteState,
 }
 
 impl MultiServoApp {
-    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+    fn new(cc: &eframe::CreationContext<'_>, launch: LaunchOptions) -> Self {
         let (tx, rx) = channel();
-        let state = Arc::new(Mutex::new(SharedState::default()));
+        let (responder, commands) = response::channel();
+        let config = Config::load();
+        let poses = match PoseLibrary::load(std::path::Path::new(POSES_FILE)) {
+            Ok(library) => PoseState { library, ..Default::default() },
+            Err(e) => PoseState { status: Some((Status::Danger, e)), ..Default::default() },
+        };
+        let groups = match groups::check_groups(&config.groups) {
+            Ok(()) => GroupState { groups: config.groups.clone(), ..Default::default() },
+            Err(e) => GroupState { status: Some((Status::Danger, format!("Groups ignored: {}", e))), ..Default::default() },
+        };
+        let state = Arc::new(Mutex::new(SharedState {
+            poses,
+            groups,
+            theme: config.ui.theme,
+            slider_mode: config.ui.slider_mode,
+            angle: config.ui.angle,
+            delta_tolerance: config.ui.delta_tolerance,
+            log_settings: config.logging.clone(),
+            exit: config.exit.clone(),
+            servo_settings: config.servos.clone(),
+            saved_limits: config.limits.clone(),
+            stall: config.stall.clone(),
+            gamepad: config.gamepad.clone(),
+            rescan: config.rescan.clone(),
+            scan_progress: None,
+            operation: OperationLock::default(),
+            port: launch.port,
+            pin_port: launch.pin_port,
+            simulation: launch.simulation,
+            replay: launch.replay,
+            commands,
+            scan_range: launch.scan_range,
+            ..Default::default()
+        }));
 
         // Configuration du style
         let mut style = (*cc.egui_ctx.style()).clone();
         style.visuals.window_corner_radius = egui::CornerRadius::same(8);
         style.spacing.item_spacing = egui::vec2(10.0, 10.0);
         cc.egui_ctx.set_style(style);
+        theme::apply(config.ui.theme, &cc.egui_ctx);
 
         // Lancement du thread de gestion des servos
         let state_clone = state.clone();
         let ctx_clone = cc.egui_ctx.clone();
-        thread::spawn(move || {
-            servo_worker(state_clone, rx, ctx_clone);
+        let dry_run = Arc::new(AtomicBool::new(launch.dry_run));
+        let worker_dry_run = dry_run.clone();
+        // Bus série réel sur le port choisi au lancement, ou enregistrement rejoué
+        let mut worker = {
+            let state = state.lock().unwrap();
+            match &state.replay {
+                Some(replay) => ServoWorker::with_connector(state.port.clone(), dry_run.clone(), replay.connector()),
+                None => ServoWorker::new(state.port.clone(), dry_run.clone()),
+            }
+        };
+        worker.set_retry(config.retry.clone());
+        let inversions = worker.inversions();
+        for (&id, settings) in &config.servos {
+            inversions.set(id, settings.inverted);
+        }
+        {
+            let mut state = state.lock().unwrap();
+            state.comm_errors = worker.comm_errors();
+            state.recorder = worker.recorder();
+            state.inversions = inversions;
+        }
+        let shutdown = ShutdownSignal::new();
+        let worker_shutdown = shutdown.clone();
+        let worker = thread::spawn(move || {
+            servo_worker(state_clone, rx, responder, ctx_clone, worker_dry_run, worker, worker_shutdown);
         });
 
-        Self { state, tx }
+        Self { state, tx, dry_run, shutdown, worker: Some(worker), palette: PaletteState::default() }
     }
 }
 
 impl eframe::App for MultiServoApp {
+    // Le worker finit sa transaction, parque et relâche les servos selon `[exit]`, puis s'arrête
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.shutdown.request();
+        if let Some(worker) = self.worker.take() {
+            let timeout = self.state.lock().unwrap().exit.park_timeout() + shutdown::JOIN_MARGIN;
+            if !shutdown::join_within(worker, timeout) {
+                eprintln!("Servo worker still busy after {:.0} s; exiting anyway", timeout.as_secs_f64());
+            }
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.events.events().is_empty() && state.telemetry.servos.is_empty() {
+            return;
+        }
+        write_report(&mut state);
+        if let Some(status) = &state.report_status {
+            println!("{}", status);
+        }
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         let mut state = self.state.lock().unwrap();
 
+        if let Some(owner) = state.port_conflict.clone() {
+            if let Some(choice) = portlock::conflict_dialog(ctx, &state.port, &owner) {
+                state.port_conflict = None;
+                state.port_choice = Some(choice);
+            }
+        }
+        // Le bouton de couple ne bascule qu'une fois l'écriture confirmée par le worker
+        for result in state.commands.poll() {
+            let enabled = match result.cmd {
+                "torque on" => true,
+                "torque off" => false,
+                _ => continue,
+            };
+            let servo = result.servo.and_then(|id| state.servos.get_mut(&id));
+            if let (Ok(()), Some(servo)) = (&result.outcome, servo) {
+                servo.torque_on = enabled;
+            }
+        }
+        response::show_toasts(ctx, &mut state.commands);
+        // Rejeu : les commandes de l'enregistrement rejoignent le journal d'événements à leur heure
+        if let Some(replay) = state.replay.clone() {
+            for recorded in replay.take_commands() {
+                let text = format!("replay {:.1} s, {}: {}", recorded.t, recorded.source, recorded.command);
+                state.events.push(Event::Annotation { text });
+            }
+        }
+        if state.bus_form.open {
+            draw_bus_window(ctx, &mut state);
+        }
+
         // --- EN-TÊTE ---
-        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
+        // En répétition, tout le bandeau passe en couleur d'alerte
+        let mut dry_run = self.dry_run.load(Ordering::Relaxed);
+        let mut top_frame = egui::Frame::side_top_panel(&ctx.style());
+        // Bandeau tant que le port ouvert est celui du bus simulé
+        let simulation = state.simulation.as_ref().filter(|(_, port)| *port == state.port).map(|(sim, _)| sim.banner());
+        let replay = state.replay.clone().filter(|replay| replay.port() == state.port);
+        if dry_run {
+            top_frame = top_frame.fill(state.theme.palette().warning());
+        } else if simulation.is_some() || replay.is_some() {
+            top_frame = top_frame.fill(state.theme.palette().info());
+        }
+        // Échap : arrêt d'urgence (sauf pour fermer la palette)
+        if !self.palette.open && ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+            let _ = self.tx.send(Timed::new(SOURCE_CARD, AppCommand::Servo(Command::EmergencyStop)));
+        }
+        let actions = palette_actions(&state, &self.tx);
+        self.palette.update(ctx, &actions, &mut state);
+        egui::TopBottomPanel::top("top_panel").frame(top_frame).show(ctx, |ui| {
             ui.add_space(8.0);
+            if dry_run {
+                ui.vertical_centered(|ui| {
+                    ui.heading(egui::RichText::new("DRY RUN — nothing is written to the servos").strong().color(egui::Color32::BLACK));
+                });
+            }
+            if let Some(banner) = &simulation {
+                ui.vertical_centered(|ui| {
+                    ui.heading(egui::RichText::new(banner).strong().color(egui::Color32::BLACK));
+                });
+            }
+            if let Some(replay) = &replay {
+                ui.vertical_centered(|ui| {
+                    ui.heading(egui::RichText::new(replay.banner()).strong().color(egui::Color32::BLACK));
+                    ui.horizontal(|ui| recording::replay_controls(ui, replay));
+                });
+            }
+            if state.estop.is_active() {
+                let stopped = state.labels().list(state.estop.stopped());
+                let danger = state.theme.palette().danger();
+                ui.vertical_centered(|ui| {
+                    ui.heading(
+                        egui::RichText::new(format!(
+                            "EMERGENCY STOP — torque off on {}; enable torque per servo to move again",
+                            stopped
+                        ))
+                        .strong()
+                        .color(danger),
+                    );
+                });
+            }
             ui.horizontal(|ui| {
-                ui.heading("🤖 Multi-Servo Controller (1-15)");
+                ui.heading(format!("🤖 Multi-Servo Controller ({})", state.scan_range));
+                let estop_button = egui::Button::new(
+                    egui::RichText::new("⛔ E-STOP").strong().size(18.0).color(egui::Color32::WHITE),
+                )
+                .fill(state.theme.palette().danger());
+                if ui.add(estop_button).on_hover_text("Disable torque on every servo (Esc)").clicked() {
+                    let _ = self.tx.send(Timed::new(SOURCE_CARD, AppCommand::Servo(Command::EmergencyStop)));
+                }
+                if ui.checkbox(&mut dry_run, "Dry run").changed() {
+                    self.dry_run.store(dry_run, Ordering::Relaxed);
+                }
+                if replay.is_none() {
+                    recording::record_button(ui, &state.recorder);
+                }
+                if shutdown::release_checkbox(ui, &mut state.exit) {
+                    let mut config = Config::load();
+                    config.exit.release_torque = state.exit.release_torque;
+                    if let Err(e) = config.save() {
+                        eprintln!("Could not save exit settings: {}", e);
+                    }
+                }
+                let hover = format!("Write the port, scan range, display options and limits to {}", config::resolve_path().display());
+                if ui.button("💾 Save settings").on_hover_text(hover).clicked() {
+                    save_settings(&mut state);
+                }
+                if let Some(status) = &state.settings_status {
+                    ui.weak(status);
+                }
+                if let Some(status) = &state.report_status {
+                    ui.weak(status);
+                }
+                ui.toggle_value(&mut state.register_compare.open, "🔍 Registers");
+                if ui.toggle_value(&mut state.bus_form.open, "⚙ Bus").clicked() && state.bus_form.open {
+                    let (port, range) = (state.port.clone(), state.scan_range);
+                    state.bus_form = BusForm { open: true, port, start: range.start, end: range.end, error: None };
+                }
+                draw_override_chips(ui, &mut state);
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    let palette = state.theme.palette();
                     if state.connected {
-                        ui.colored_label(egui::Color32::GREEN, "● Connected");
+                        palette.status_label(ui, Status::Ok, "Connected");
                     } else {
-                        ui.colored_label(egui::Color32::RED, "● Disconnected");
+                        palette.status_label(ui, Status::Danger, "Disconnected");
+                    }
+                    if let Some(op) = state.operation.current() {
+                        palette.status_label(ui, Status::Warning, format!("{} on ID {}: {}", op.name, op.servo, op.step));
+                    }
+
+                    let previous_theme = state.theme;
+                    egui::ComboBox::from_id_salt("theme")
+                        .selected_text(state.theme.label())
+                        .show_ui(ui, |ui| {
+                            for theme in Theme::ALL {
+                                ui.selectable_value(&mut state.theme, theme, theme.label());
+                            }
+                        });
+                    if state.theme != previous_theme {
+                        theme::apply(state.theme, ctx);
+                        let mut config = Config::load();
+                        config.ui.theme = state.theme;
+                        if let Err(e) = config.save() {
+                            eprintln!("Could not save theme: {}", e);
+                        }
                     }
                 });
             });
+            if let Some(progress) = state.scan_progress {
+                if hotplug::scan_progress(ui, progress) {
+                    let _ = self.tx.send(Timed::new(SOURCE_CARD, AppCommand::CancelScan));
+                }
+            }
             ui.add_space(8.0);
+            if state.connected && !state.servos.is_empty() {
+                draw_coordinated_panel(ui, &mut state, &self.tx);
+                draw_choreography_panel(ui, &mut state, &self.tx);
+                draw_poses_panel(ui, &mut state, &self.tx);
+                draw_keyframes_panel(ui, &mut state, &self.tx);
+                draw_teach_panel(ui, &mut state, &self.tx);
+                draw_warmup_panel(ui, &mut state, &self.tx);
+                draw_override_panel(ui, &mut state);
+                draw_stall_settings(ui, &mut state);
+                draw_gamepad_panel(ui, &mut state, &self.tx);
+                ui.horizontal(|ui| {
+                    ui.label("On-target tolerance (ticks):");
+                    let tolerance = ui.add(egui::DragValue::new(&mut state.delta_tolerance).range(0..=500));
+                    // Enregistrée une fois le réglage lâché, pas à chaque pas du glisser
+                    if tolerance.drag_stopped() || tolerance.lost_focus() {
+                        let mut config = Config::load();
+                        config.ui.delta_tolerance = state.delta_tolerance;
+                        if let Err(e) = config.save() {
+                            eprintln!("Could not save the on-target tolerance: {}", e);
+                        }
+                    }
+                    ui.separator();
+                    if ui.button("Move all to targets").on_hover_text("One synchronized write: every servo starts together").clicked() {
+                        let targets = state.servos.values().map(|s| (s.id, s.target_pos, s.target_speed)).collect();
+                        let _ = self.tx.send(Timed::new(SOURCE_CARD, AppCommand::MoveGroup { targets, sync: None }));
+                    }
+                    ui.separator();
+                    // Réglage commun repris par toutes les cartes (et les servos détectés ensuite)
+                    let (mut speed, mut acceleration) = state.motion_defaults;
+                    ui.label("Speed:");
+                    ui.add(egui::DragValue::new(&mut speed).range(0..=MAX_SPEED)).on_hover_text("0 = maximum speed");
+                    ui.label("Accel:");
+                    ui.add(egui::DragValue::new(&mut acceleration).range(0..=MAX_ACCELERATION))
+                        .on_hover_text("0 = maximum acceleration");
+                    state.motion_defaults = (speed, acceleration);
+                    if ui.button("Apply to all").clicked() {
+                        for servo in state.servos.values_mut() {
+                            (servo.target_speed, servo.acceleration) = (speed, acceleration);
+                            servo.motion_saved = false;
+                        }
+                        state.motion_memory.clear();
+                    }
+                    ui.separator();
+                    let previous_mode = state.slider_mode;
+                    ui.label("Sliders:");
+                    egui::ComboBox::from_id_salt("slider_mode")
+                        .selected_text(state.slider_mode.label())
+                        .show_ui(ui, |ui| {
+                            for mode in SliderMode::ALL {
+                                ui.selectable_value(&mut state.slider_mode, mode, mode.label());
+                            }
+                        });
+                    if state.slider_mode != previous_mode {
+                        let mut config = Config::load();
+                        config.ui.slider_mode = state.slider_mode;
+                        if let Err(e) = config.save() {
+                            eprintln!("Could not save slider mode: {}", e);
+                        }
+                    }
+                    ui.separator();
+                    if units::unit_picker(ui, &mut state.angle) {
+                        let mut config = Config::load();
+                        config.ui.angle = state.angle;
+                        if let Err(e) = config.save() {
+                            eprintln!("Could not save position units: {}", e);
+                        }
+                    }
+                });
+                draw_log_controls(ui, &mut state);
+                draw_latency_panel(ui, &mut state.latency);
+                egui::CollapsingHeader::new("Log console").show(ui, logging::console);
+                ui.add_space(8.0);
+            }
         });
 
+        // --- ÉVÉNEMENTS ---
+        let labels = state.labels();
+        events::bottom_panel(ctx, &state.events, &state.theme.palette(), &|id| labels.get(id));
+
         // --- ZONE PRINCIPALE (SCROLLABLE) ---
         egui::CentralPanel::default().show(ctx, |ui| {
-            if state.servos.is_empty() && state.connected {
+            // Servos enregistrés mais absents : listés une fois le scan terminé
+            let missing = match state.scan_progress {
+                Some(_) => Vec::new(),
+                None => state.missing(),
+            };
+            if state.servos.is_empty() && missing.is_empty() && state.connected {
                 ui.centered_and_justified(|ui| {
-                    ui.label("Scanning IDs 1 to 15... No servos found yet.");
+                    let text = match state.scan_progress {
+                        Some(_) => format!("Scanning IDs {}... No servos found yet.", state.scan_range),
+                        None => format!("No servo answered in IDs {}.", state.scan_range),
+                    };
+                    ui.label(text);
                 });
             } else if !state.connected {
                  ui.centered_and_justified(|ui| {
                     ui.heading("Connecting to Serial Port...");
                 });
             } else {
+                if !state.servos.is_empty() {
+                    draw_bulk_toolbar(ui, &mut state, &self.tx);
+                    ui.add_space(4.0);
+                }
                 egui::ScrollArea::vertical().show(ui, |ui| {
-                    // On itère sur tous les servos trouvés pour afficher leur contrôles
-                    for (id, servo) in state.servos.iter_mut() {
+                    let sources: Vec<(u8, String, u16)> = state.servos.values()
+                        .map(|s| (s.id, config::servo_label(s.id, &s.name), s.current_pos))
+                        .collect();
+                    let palette = state.theme.palette();
+                    let labels = state.labels();
+                    let SharedState { servos, copy_request, coordinated, delta_tolerance, slider_mode, angle, commands, comm_errors, servo_settings, groups, inversions, .. } = &mut *state;
+                    // En mode coordonné, les sliders préparent la pose sans l'envoyer
+                    let options = CardOptions {
+                        live: !coordinated.enabled,
+                        slider_mode: *slider_mode,
+                        angle: *angle,
+                        delta_tolerance: *delta_tolerance,
+                        palette,
+                        inversions: inversions.clone(),
+                    };
+                    for servo in servos.values_mut() {
+                        servo.comm_errors = comm_errors.get(servo.id);
+                    }
+                    // Membres de chaque groupe sous son en-tête repliable, puis les servos hors groupe
+                    if let Some((status, text)) = &groups.status {
+                        options.palette.status_label(ui, *status, text);
+                    }
+                    let mut captured = None;
+                    for group in &groups.groups {
+                        let offset = groups.offsets.entry(group.name.clone()).or_default();
+                        let id = ui.make_persistent_id(("group", &group.name));
+                        egui::collapsing_header::CollapsingState::load_with_default_open(ui.ctx(), id, true)
+                            .show_header(ui, |ui| {
+                                let detected: Vec<u8> = servos.keys().copied().collect();
+                                let (present, missing) = group.members(&detected);
+                                ui.strong(&group.name);
+                                ui.label(format!("{}/{}", present.len(), group.ids.len()))
+                                    .on_hover_text(labels.list(group.ids.iter().copied()));
+                                if !missing.is_empty() {
+                                    options.palette.status_label(ui, Status::Warning, format!("missing: {}", labels.list(missing)));
+                                }
+                                let pose = draw_group_actions(ui, group, servos, offset, commands, &options, &self.tx);
+                                if let Some(pose) = pose {
+                                    captured = Some((group.name.clone(), pose));
+                                }
+                            })
+                            .body(|ui| {
+                                for id in &group.ids {
+                                    if let Some(servo) = servos.get_mut(id) {
+                                        ui.push_id(*id, |ui| {
+                                            draw_servo_card(ui, servo, &sources, copy_request, commands, &options, &self.tx);
+                                        });
+                                    }
+                                }
+                            });
+                    }
+                    let grouped: BTreeSet<u8> = groups.groups.iter().flat_map(|group| group.ids.iter().copied()).collect();
+                    for (id, servo) in servos.iter_mut().filter(|(id, _)| !grouped.contains(id)) {
                         ui.push_id(*id, |ui| {
-                            draw_servo_card(ui, servo, &self.tx);
+                            draw_servo_card(ui, servo, &sources, copy_request, commands, &options, &self.tx);
                         });
                     }
+                    if let Some((name, pose)) = captured {
+                        groups.status = Some(save_group_pose(&mut groups.groups, &name, pose));
+                    }
+                    for id in missing {
+                        let forget = ui.push_id(id, |ui| draw_missing_card(ui, id, &servo_settings[&id], &options.palette)).inner;
+                        if forget {
+                            servo_settings.remove(&id);
+                            let mut config = Config::load();
+                            config.servos.remove(&id);
+                            if let Err(e) = config.save() {
+                                eprintln!("Could not save servo settings: {}", e);
+                            }
+                        }
+                    }
                 });
+                save_servo_settings(&mut state);
             }
         });
+
+        draw_copy_window(ctx, &mut state, &self.tx);
+        draw_register_compare(ctx, &mut state, &self.tx);
     }
 }
 
-// --- COMPOSANT GRAPHIQUE POUR UN SERVO ---
-fn draw_servo_card(ui: &mut egui::Ui, servo: &mut IndividualServo, tx: &Sender<AppCommand>) {
-    egui::Frame::group(ui.style())
-        .inner_margin(10.0)
-        .show(ui, |ui| {
-            ui.horizontal(|ui| {
-                // ID et Température
-                ui.colored_label(egui::Color32::LIGHT_BLUE, format!("ID {}", servo.id));
-                ui.separator();
-                
-                // Indicateur Température
-                let temp_color = if servo.temperature > 60 { egui::Color32::RED } else { egui::Color32::GRAY };
-                ui.colored_label(temp_color, format!("{}°C", servo.temperature));
-                
-                // Indicateur Voltage
-                ui.label(format!("{:.1}V", servo.voltage));
-                
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
-                    // Bouton Torque
-                    let btn_text = if servo.torque_on { "Torque ON" } else { "Torque OFF" };
-                    let btn = ui.button(btn_text);
-                    if btn.clicked() {
-                        servo.torque_on = !servo.torque_on;
-                        let _ = tx.send(AppCommand::ToggleTorque { 
-                            id: servo.id, 
-                            enable: servo.torque_on 
-                        });
-                    }
-                });
-            });
-
-            ui.add_space(5.0);
+// --- PANNEAU DE MOUVEMENT COORDONNÉ ---
+fn draw_coordinated_panel(ui: &mut egui::Ui, state: &mut SharedState, tx: &Sender<Timed<AppCommand>>) {
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut state.coordinated.enabled, "Coordinated move");
+        ui.add_enabled(
+            state.coordinated.enabled,
+            egui::Slider::new(&mut state.coordinated.duration_s, 0.2..=10.0)
+                .text("s")
+                .fixed_decimals(1),
+        );
+        let label = format!("Reach pose in {:.1} s", state.coordinated.duration_s);
+        if ui.add_enabled(state.coordinated.enabled, egui::Button::new(label)).clicked() {
+            let overrides = &state.overrides;
+            let targets = state.servos.values()
+                .map(|s| {
+                    let cap = if overrides.is_active(OverrideKind::SpeedCap, s.id) { MAX_SPEED } else { s.speed_cap };
+                    (s.id, s.target_pos, cap)
+                })
+                .collect();
+            let duration = Duration::from_secs_f32(state.coordinated.duration_s);
+            let _ = tx.send(Timed::new(SOURCE_COORDINATED, AppCommand::CoordinatedMove { targets, duration }));
+            for servo in state.servos.values_mut() {
+                servo.moved_at = Instant::now();
+            }
+            state.coordinated_report = None;
+        }
+    });
 
-            // Slider de Position
-            ui.horizontal(|ui| {
-                ui.label("Pos:");
-                // Slider qui contrôle 'target_pos'
-                let slider = ui.add(egui::Slider::new(&mut servo.target_pos, 0..=4095)
-                    .text("Target"));
-                
-                // Si l'utilisateur bouge le slider, on envoie la commande
-                if slider.changed() {
-                    let _ = tx.send(AppCommand::Move { 
-                        id: servo.id, 
-                        position: servo.target_pos, 
-                        speed: 0 // 0 = vitesse max ou par défaut selon config
+    if let Some(report) = &state.coordinated_report {
+        let mut text = format!("Planned {:.2} s", report.planned.as_secs_f32());
+        if let (Some(first), Some(last)) = (report.first_arrival, report.last_arrival) {
+            text += &format!(
+                " | arrivals {:.2}-{:.2} s, spread {:.0} ms",
+                first.as_secs_f32(),
+                last.as_secs_f32(),
+                (last - first).as_secs_f32() * 1000.0
+            );
+        }
+        if !report.not_arrived.is_empty() {
+            text += &format!(" | not arrived: {:?}", report.not_arrived);
+        }
+        if !report.unread.is_empty() {
+            text += &format!(" | position unread: {:?}", report.unread);
+        }
+        if report.not_arrived.is_empty() && report.unread.is_empty() {
+            ui.label(text);
+        } else {
+            state.theme.palette().status_label(ui, Status::Warning, text);
+        }
+    }
+}
+
+// --- PANNEAU DE CHORÉGRAPHIE ---
+fn draw_choreography_panel(ui: &mut egui::Ui, state: &mut SharedState, tx: &Sender<Timed<AppCommand>>) {
+    let palette = state.theme.palette();
+    let ids: Vec<u8> = state.servos.keys().copied().collect();
+    let labels = state.labels();
+    let choreo = &mut state.choreography;
+    let before = choreo.config.clone();
+
+    egui::CollapsingHeader::new("Choreography").show(ui, |ui| {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("choreo_waveform")
+                .selected_text(choreo.config.waveform.label())
+                .show_ui(ui, |ui| {
+                    for waveform in Waveform::ALL {
+                        ui.selectable_value(&mut choreo.config.waveform, waveform, waveform.label());
+                    }
+                });
+            ui.label("Amplitude:");
+            ui.add(egui::DragValue::new(&mut choreo.config.amplitude).range(0..=2048).suffix(" ticks"));
+            ui.label("Period:");
+            ui.add(egui::DragValue::new(&mut choreo.config.period_s).range(0.2..=60.0).speed(0.05).suffix(" s"));
+        });
+
+        // Sélection des servos, dans l'ordre des IDs
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Servos:");
+            for &id in &ids {
+                let mut included = choreo.config.servos.iter().any(|s| s.id == id);
+                if ui.checkbox(&mut included, labels.get(id)).changed() {
+                    if included {
+                        choreo.config.servos.push(ChoreographyServo { id, phase: 0.0 });
+                        choreo.config.servos.sort_by_key(|s| s.id);
+                    } else {
+                        choreo.config.servos.retain(|s| s.id != id);
+                    }
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            // Décalages en degrés à l'écran, en tours dans la configuration
+            egui::Grid::new("choreo_phases").show(ui, |ui| {
+                for servo in choreo.config.servos.iter_mut() {
+                    ui.label(labels.get(servo.id));
+                    let mut degrees = servo.phase * 360.0;
+                    if ui.add(egui::DragValue::new(&mut degrees).range(0.0..=359.0).suffix("°")).changed() {
+                        servo.phase = degrees / 360.0;
+                    }
+                    ui.end_row();
+                }
+            });
+            draw_phase_diagram(ui, &choreo.config, choreo.phase, &palette);
+        });
+
+        ui.horizontal(|ui| {
+            if ui.button("Distribute evenly").clicked() {
+                choreo.config.distribute_phases();
+            }
+            let can_start = !choreo.config.servos.is_empty();
+            if choreo.running {
+                if ui.button("⏹ Stop").clicked() {
+                    choreo.running = false;
+                    let _ = tx.send(Timed::new(SOURCE_CHOREOGRAPHY, AppCommand::Choreography(None)));
+                }
+            } else if ui.add_enabled(can_start, egui::Button::new("▶ Start")).clicked() {
+                choreo.running = true;
+                let _ = tx.send(Timed::new(SOURCE_CHOREOGRAPHY, AppCommand::Choreography(Some(choreo.config.clone()))));
+            }
+            if ui.button("Save").clicked() {
+                choreo.status = Some(match choreo.config.save(std::path::Path::new(CHOREOGRAPHY_FILE)) {
+                    Ok(()) => format!("Saved to {}", CHOREOGRAPHY_FILE),
+                    Err(e) => e,
+                });
+            }
+            if ui.button("Load").clicked() {
+                match Choreography::load(std::path::Path::new(CHOREOGRAPHY_FILE)) {
+                    Ok(config) => {
+                        choreo.config = config;
+                        choreo.status = Some(format!("Loaded {}", CHOREOGRAPHY_FILE));
+                    }
+                    Err(e) => choreo.status = Some(e),
+                }
+            }
+            if let Some(status) = &choreo.status {
+                ui.label(status);
+            }
+        });
+    });
+
+    // Modification en cours de route : le worker garde sa phase, pas de saut
+    if choreo.running && choreo.config != before {
+        let _ = tx.send(Timed::new(SOURCE_CHOREOGRAPHY, AppCommand::Choreography(Some(choreo.config.clone()))));
+    }
+}
+
+// --- PANNEAU DES POSES ---
+// Chaque capture ou suppression est enregistrée aussitôt dans POSES_FILE
+fn draw_poses_panel(ui: &mut egui::Ui, state: &mut SharedState, tx: &Sender<Timed<AppCommand>>) {
+    let palette = state.theme.palette();
+    let detected: Vec<u8> = state.servos.keys().copied().collect();
+    let labels = state.labels();
+    let mut changed = false;
+    let mut go = None;
+
+    egui::CollapsingHeader::new(format!("Poses ({})", state.poses.library.poses.len())).show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.add(egui::TextEdit::singleline(&mut state.poses.name).hint_text("pose name").desired_width(160.0));
+            let name = state.poses.name.trim().to_string();
+            let replaces = state.poses.library.get(&name).is_some();
+            let capture = ui
+                .add_enabled(!name.is_empty(), egui::Button::new("📷 Capture"))
+                .on_hover_text(if replaces { "Replace the saved pose with the current positions" } else { "Save every servo's current position" });
+            if capture.clicked() {
+                let positions = state.servos.values().map(|s| (s.id, s.current_pos));
+                state.poses.library.capture(&name, positions);
+                state.poses.status = Some((Status::Ok, format!("Captured '{}' ({} servos)", name, detected.len())));
+                changed = true;
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut state.poses.sync, "Arrive together")
+                .on_hover_text("Scale each servo's speed so the whole pose is reached at the same time");
+            ui.add_enabled(state.poses.sync, egui::DragValue::new(&mut state.poses.duration_s).range(0.0..=30.0).speed(0.1).suffix(" s"))
+                .on_hover_text("Minimum duration; 0 = at the pace of the slowest servo");
+        });
+
+        let mut deleted = None;
+        egui::Grid::new("poses").striped(true).show(ui, |ui| {
+            for pose in &state.poses.library.poses {
+                ui.label(&pose.name);
+                ui.label(labels.list(pose.positions.keys().copied()));
+                if ui.button("▶ Go").on_hover_text("One synchronized write to every servo of the pose").clicked() {
+                    go = Some(pose.clone());
+                }
+                if ui.button("🗑 Delete").clicked() {
+                    deleted = Some(pose.name.clone());
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(name) = deleted {
+            state.poses.library.remove(&name);
+            state.poses.status = Some((Status::Ok, format!("Deleted '{}'", name)));
+            changed = true;
+        }
+
+        if let Some((status, text)) = &state.poses.status {
+            palette.status_label(ui, *status, text);
+        }
+    });
+
+    if let Some(pose) = go {
+        go_to_pose(state, tx, &pose);
+    }
+    if changed {
+        if let Err(e) = state.poses.library.save(std::path::Path::new(POSES_FILE)) {
+            state.poses.status = Some((Status::Danger, e));
+        }
+    }
+}
+
+// Pose envoyée en un sync write aux servos détectés ; bilan dans le panneau des poses
+fn go_to_pose(state: &mut SharedState, tx: &Sender<Timed<AppCommand>>, pose: &Pose) {
+    let detected: Vec<u8> = state.servos.keys().copied().collect();
+    let (present, missing) = pose.targets(&detected);
+    let mut targets = Vec::new();
+    for (id, position) in present {
+        if let Some(servo) = state.servos.get_mut(&id) {
+            servo.target_pos = position;
+            servo.moved_at = Instant::now();
+            targets.push((id, position, servo.target_speed));
+        }
+    }
+    let sync = state.poses.sync.then(|| Duration::from_secs_f32(state.poses.duration_s));
+    let _ = tx.send(Timed::new(SOURCE_POSE, AppCommand::MoveGroup { targets, sync }));
+    let moving = match sync {
+        Some(duration) if !duration.is_zero() => format!("Moving to '{}' in {:.1} s", pose.name, duration.as_secs_f32()),
+        Some(_) => format!("Moving to '{}' together", pose.name),
+        None => format!("Moving to '{}'", pose.name),
+    };
+    state.poses.status = Some(if missing.is_empty() {
+        (Status::Ok, moving)
+    } else {
+        (Status::Warning, format!("{}; {} not detected, skipped", moving, state.labels().list(missing)))
+    });
+}
+
+// --- PANNEAU DE SÉQUENCE D'IMAGES CLÉS ---
+// Le fichier est relu à chaque lecture : on peut le modifier entre deux essais
+fn draw_keyframes_panel(ui: &mut egui::Ui, state: &mut SharedState, tx: &Sender<Timed<AppCommand>>) {
+    let palette = state.theme.palette();
+    let keyframes = &mut state.keyframes;
+
+    egui::CollapsingHeader::new("Keyframe sequence").show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("File:");
+            ui.add_enabled(!keyframes.playing, egui::TextEdit::singleline(&mut keyframes.path).desired_width(200.0))
+                .on_hover_text("JSON, or TOML with a .toml extension");
+            ui.add_enabled(!keyframes.playing, egui::Checkbox::new(&mut keyframes.looped, "Loop"));
+            ui.add_enabled(!keyframes.playing, egui::Checkbox::new(&mut keyframes.stepped, "Synchronized steps")).on_hover_text(
+                "Send each keyframe once and scale speeds so its servos arrive together; a keyframe's duration_s \
+                 (default: time since the previous keyframe, 0 = pace of the slowest servo) sets the step duration",
+            );
+
+            if ui.add_enabled(!keyframes.playing, egui::Button::new("▶ Play")).clicked() {
+                match KeyframeSequence::load(std::path::Path::new(&keyframes.path)) {
+                    Ok(sequence) => {
+                        keyframes.playing = true;
+                        keyframes.paused = false;
+                        keyframes.progress = 0.0;
+                        keyframes.status = None;
+                        let (looped, stepped) = (keyframes.looped, keyframes.stepped);
+                        let _ = tx.send(Timed::new(SOURCE_SEQUENCE, AppCommand::PlaySequence { sequence, looped, stepped }));
+                    }
+                    Err(e) => keyframes.status = Some((Status::Danger, e)),
+                }
+            }
+            let pause_text = if keyframes.paused { "⏵ Resume" } else { "⏸ Pause" };
+            if ui.add_enabled(keyframes.playing, egui::Button::new(pause_text)).clicked() {
+                keyframes.paused = !keyframes.paused;
+                let _ = tx.send(Timed::new(SOURCE_SEQUENCE, AppCommand::PauseSequence(keyframes.paused)));
+            }
+            if ui.add_enabled(keyframes.playing, egui::Button::new("⏹ Stop")).clicked() {
+                let _ = tx.send(Timed::new(SOURCE_SEQUENCE, AppCommand::StopSequence));
+            }
+        });
+        if keyframes.playing {
+            ui.add(egui::ProgressBar::new(keyframes.progress).show_percentage());
+        }
+        if let Some((status, text)) = &keyframes.status {
+            palette.status_label(ui, *status, text);
+        }
+    });
+}
+
+// --- PANNEAU D'APPRENTISSAGE ---
+fn draw_teach_panel(ui: &mut egui::Ui, state: &mut SharedState, tx: &Sender<Timed<AppCommand>>) {
+    let palette = state.theme.palette();
+    let ids: Vec<u8> = state.servos.keys().copied().collect();
+    let labels = state.labels();
+    let teach = &mut state.teach;
+    let mut saved_path = None;
+
+    egui::CollapsingHeader::new("Teach mode").show(ui, |ui| {
+        ui.add_enabled_ui(!teach.active, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Servos:");
+                for &id in &ids {
+                    let mut included = !teach.excluded.contains(&id);
+                    if ui.checkbox(&mut included, labels.get(id)).changed() {
+                        if included {
+                            teach.excluded.remove(&id);
+                        } else {
+                            teach.excluded.insert(id);
+                        }
+                    }
+                }
+            });
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Sample rate:");
+            ui.add_enabled(!teach.active, egui::DragValue::new(&mut teach.rate_hz).range(1.0..=50.0).suffix(" Hz"));
+            let selected: Vec<u8> = ids.iter().copied().filter(|id| !teach.excluded.contains(id)).collect();
+            let toggle = ui
+                .add_enabled(teach.active || !selected.is_empty(), egui::Button::new("🖐 Teach").selected(teach.active))
+                .on_hover_text("Torque off: move the joints by hand while positions are recorded");
+            if toggle.clicked() {
+                teach.active = !teach.active;
+                if teach.active {
+                    teach.samples = 0;
+                    let _ = tx.send(Timed::new(SOURCE_TEACH, AppCommand::StartTeach { ids: selected, rate_hz: teach.rate_hz }));
+                } else {
+                    let _ = tx.send(Timed::new(SOURCE_TEACH, AppCommand::StopTeach));
+                }
+            }
+            if teach.active {
+                ui.spinner();
+                ui.label(format!("Recording: {} keyframes", teach.samples));
+            }
+        });
+
+        // Temps morts au début et à la fin retirés avant l'enregistrement
+        if let (Some(recording), false) = (&teach.recording, teach.active) {
+            let duration = recording.duration().as_secs_f64();
+            ui.horizontal(|ui| {
+                ui.label("Keep from");
+                ui.add(egui::DragValue::new(&mut teach.trim.0).range(0.0..=teach.trim.1).speed(0.05).suffix(" s"));
+                ui.label("to");
+                ui.add(egui::DragValue::new(&mut teach.trim.1).range(teach.trim.0..=duration).speed(0.05).suffix(" s"));
+                let trimmed = recording.trimmed(teach.trim.0, teach.trim.1);
+                ui.label(format!("{} of {} keyframes", trimmed.keyframes.len(), recording.keyframes.len()));
+            });
+            ui.horizontal(|ui| {
+                ui.label("File:");
+                ui.add(egui::TextEdit::singleline(&mut teach.path).desired_width(200.0));
+                if ui.button("💾 Save").clicked() {
+                    let trimmed = recording.trimmed(teach.trim.0, teach.trim.1);
+                    let path = std::path::Path::new(&teach.path);
+                    teach.status = Some(match trimmed.check().and_then(|_| trimmed.save(path)) {
+                        Ok(()) => {
+                            saved_path = Some(teach.path.clone());
+                            (Status::Ok, format!("Saved {} keyframes to {}", trimmed.keyframes.len(), teach.path))
+                        }
+                        Err(e) => (Status::Danger, e),
+                    });
+                }
+            });
+        }
+        if let Some((status, text)) = &teach.status {
+            palette.status_label(ui, *status, text);
+        }
+    });
+
+    // Le fichier enregistré devient celui du lecteur de séquence
+    if let Some(path) = saved_path {
+        state.keyframes.path = path;
+    }
+}
+
+// --- PANNEAU D'ÉCHAUFFEMENT ---
+fn draw_warmup_panel(ui: &mut egui::Ui, state: &mut SharedState, tx: &Sender<Timed<AppCommand>>) {
+    let palette = state.theme.palette();
+    let ids: Vec<u8> = state.servos.keys().copied().collect();
+    let labels = state.labels();
+    let warmup = &mut state.warmup;
+
+    egui::CollapsingHeader::new("Warm-up").show(ui, |ui| {
+        ui.horizontal(|ui| {
+            ui.label("Amplitude:");
+            ui.add(egui::DragValue::new(&mut warmup.settings.amplitude).range(10..=500).suffix(" ticks"));
+            ui.label("Period:");
+            ui.add(egui::DragValue::new(&mut warmup.settings.period_s).range(0.5..=10.0).speed(0.05).suffix(" s"));
+            ui.label("Speed:");
+            ui.add(egui::DragValue::new(&mut warmup.settings.speed).range(50..=MAX_SPEED));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Stop at:");
+            ui.add(egui::DragValue::new(&mut warmup.settings.target_temperature).range(5..=60).suffix(" °C"));
+            ui.label("or after:");
+            let mut minutes = warmup.settings.max_duration_s / 60.0;
+            if ui.add(egui::DragValue::new(&mut minutes).range(1.0..=60.0).speed(0.1).suffix(" min")).changed() {
+                warmup.settings.max_duration_s = minutes * 60.0;
+            }
+        });
+
+        ui.horizontal_wrapped(|ui| {
+            ui.label("Servos:");
+            for &id in &ids {
+                let mut included = warmup.selected.contains(&id);
+                if ui.checkbox(&mut included, labels.get(id)).changed() {
+                    if included {
+                        warmup.selected.insert(id);
+                    } else {
+                        warmup.selected.remove(&id);
+                    }
+                }
+            }
+        });
+
+        ui.horizontal(|ui| {
+            let can_start = !warmup.selected.is_empty();
+            if ui.add_enabled(can_start, egui::Button::new("▶ Start")).clicked() {
+                let ids = warmup.selected.iter().copied().collect();
+                let command = AppCommand::StartWarmup { ids, settings: warmup.settings.clone() };
+                let _ = tx.send(Timed::new(SOURCE_WARMUP, command));
+            }
+            if ui.add_enabled(!warmup.running.is_empty(), egui::Button::new("⏹ Stop")).clicked() {
+                let _ = tx.send(Timed::new(SOURCE_WARMUP, AppCommand::StopWarmup));
+            }
+        });
+
+        if !warmup.running.is_empty() {
+            egui::Grid::new("warmup_progress").striped(true).show(ui, |ui| {
+                for header in ["Servo", "Elapsed", "Temperature", "Rise"] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+                for (&id, progress) in &warmup.running {
+                    ui.label(labels.get(id));
+                    ui.label(format_duration(progress.elapsed.as_secs_f64()));
+                    let start = progress.start_temperature.map(|t| format!("{}°C → ", t)).unwrap_or_default();
+                    palette.status_label(
+                        ui,
+                        temperature_status(progress.temperature),
+                        format!("{}{}°C / {}°C", start, progress.temperature, warmup.settings.target_temperature),
+                    );
+                    ui.label(progress.rise_rate.map(|r| format!("{:+.1} °C/min", r)).unwrap_or("…".into()));
+                    ui.end_row();
+                }
+            });
+        }
+        for line in warmup.log.iter().rev().take(5) {
+            ui.label(line);
+        }
+    });
+}
+
+// --- DÉROGATIONS : PUCES DU BANDEAU ET PANNEAU ---
+// Une puce par dérogation active, avec compte à rebours ; ✖ l'annule tout de suite
+fn draw_override_chips(ui: &mut egui::Ui, state: &mut SharedState) {
+    let palette = state.theme.palette();
+    let now = Instant::now();
+    let mut revoke = None;
+    for o in state.overrides.active() {
+        let remaining = o.remaining(now).as_secs();
+        palette.status_label(ui, Status::Warning, format!("⏱ {} {}:{:02}", o.describe(), remaining / 60, remaining % 60));
+        if ui.small_button("✖").on_hover_text("Revert now").clicked() {
+            revoke = Some((o.kind, o.servo));
+        }
+    }
+    if let Some((kind, servo)) = revoke {
+        if let Some(o) = state.overrides.revoke(kind, servo) {
+            log_override(&mut state.override_form, format!("{} reverted by user", o.describe()));
+        }
+    }
+}
+
+// Port et plage de scan : appliqués sans redémarrer, par une reconnexion suivie d'un scan
+fn draw_bus_window(ctx: &egui::Context, state: &mut SharedState) {
+    let mut open = state.bus_form.open;
+    let mut apply = false;
+    egui::Window::new("Bus settings").open(&mut open).resizable(false).show(ctx, |ui| {
+        let form = &mut state.bus_form;
+        egui::Grid::new("bus_settings").num_columns(2).show(ui, |ui| {
+            ui.label("Serial port:");
+            ui.text_edit_singleline(&mut form.port);
+            ui.end_row();
+            ui.label("Scan IDs:");
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut form.start).range(0..=ids::MAX_SERVO_ID));
+                ui.label("to");
+                ui.add(egui::DragValue::new(&mut form.end).range(0..=ids::MAX_SERVO_ID));
+            });
+            ui.end_row();
+        });
+        if hotplug::settings_editor(ui, &mut state.rescan) {
+            let mut config = Config::load();
+            config.rescan = state.rescan.clone();
+            if let Err(e) = config.save() {
+                eprintln!("Could not save rescan settings: {}", e);
+            }
+        }
+        if let Some(error) = &form.error {
+            ui.colored_label(state.theme.palette().danger(), error);
+        }
+        apply = ui.button("Apply and rescan").clicked();
+    });
+    state.bus_form.open = open;
+
+    if apply {
+        let form = &mut state.bus_form;
+        match ScanRange::new(form.start, form.end) {
+            Ok(range) if !form.port.trim().is_empty() => {
+                form.error = None;
+                state.port = form.port.trim().to_string();
+                state.scan_range = range;
+                state.rescan_requested = true;
+            }
+            Ok(_) => form.error = Some("Serial port is empty".to_string()),
+            Err(e) => form.error = Some(e),
+        }
+    }
+}
+
+fn draw_override_panel(ui: &mut egui::Ui, state: &mut SharedState) {
+    let ids: Vec<u8> = state.servos.keys().copied().collect();
+    let palette = state.theme.palette();
+    let labels = state.labels();
+    let form = &mut state.override_form;
+    let servo_label = |servo: Option<u8>| servo.map(|id| labels.get(id)).unwrap_or("All servos".into());
+
+    egui::CollapsingHeader::new("Temporary overrides").show(ui, |ui| {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_salt("override_kind")
+                .selected_text(form.kind.label())
+                .show_ui(ui, |ui| {
+                    for kind in OverrideKind::ALL {
+                        ui.selectable_value(&mut form.kind, kind, kind.label());
+                    }
+                });
+            egui::ComboBox::from_id_salt("override_servo")
+                .selected_text(servo_label(form.servo))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut form.servo, None, servo_label(None));
+                    for &id in &ids {
+                        ui.selectable_value(&mut form.servo, Some(id), servo_label(Some(id)));
+                    }
+                });
+            ui.label("for");
+            ui.add(egui::DragValue::new(&mut form.minutes).range(0.5..=60.0).speed(0.1).suffix(" min"));
+
+            let existing = state.overrides.find(form.kind, form.servo).is_some();
+            let grant = if existing && !form.confirm_extension {
+                if ui.button("Extend...").clicked() {
+                    form.confirm_extension = true;
+                }
+                false
+            } else {
+                ui.button("Grant").clicked()
+            };
+            if grant {
+                let duration = Duration::from_secs_f64(form.minutes * 60.0);
+                let o = state.overrides.grant(form.kind, form.servo, duration, Instant::now());
+                let verb = if existing { "extended" } else { "granted" };
+                let line = format!("{} {} for {}", o.describe(), verb, format_duration(duration.as_secs_f64()));
+                form.confirm_extension = false;
+                log_override(form, line);
+            }
+        });
+        if form.confirm_extension {
+            ui.horizontal(|ui| {
+                palette.status_label(ui, Status::Warning, format!(
+                    "{} is still active. Extend it by {:.1} min?",
+                    form.kind.label(),
+                    form.minutes
+                ));
+                if ui.button("Cancel").clicked() {
+                    form.confirm_extension = false;
+                }
+            });
+        }
+        ui.weak("Permanent changes belong in init-servo.toml.");
+        for line in form.log.iter().rev().take(5) {
+            ui.label(line);
+        }
+    });
+}
+
+// Diagramme de phase : un point par servo sur le cercle, à sa phase courante
+fn draw_phase_diagram(ui: &mut egui::Ui, config: &Choreography, phase: f64, palette: &Palette) {
+    let (rect, _) = ui.allocate_exact_size(egui::vec2(140.0, 140.0), egui::Sense::hover());
+    let painter = ui.painter_at(rect);
+    let center = rect.center();
+    let radius = rect.width() / 2.0 - 16.0;
+    painter.circle_stroke(center, radius, ui.visuals().widgets.noninteractive.bg_stroke);
+    for (i, servo) in config.servos.iter().enumerate() {
+        // 0 en haut, sens horaire
+        let angle = ((phase + servo.phase) * std::f64::consts::TAU) as f32;
+        let dir = egui::vec2(angle.sin(), -angle.cos());
+        let color = palette.trace(i);
+        painter.circle_filled(center + dir * radius, 5.0, color);
+        painter.text(
+            center + dir * (radius + 10.0),
+            egui::Align2::CENTER_CENTER,
+            servo.id.to_string(),
+            egui::FontId::proportional(11.0),
+            color,
+        );
+    }
+}
+
+// --- FENÊTRE DE COMPARAISON DE REGISTRES ---
+fn draw_register_compare(ctx: &egui::Context, state: &mut SharedState, tx: &Sender<Timed<AppCommand>>) {
+    let palette = state.theme.palette();
+    let ids: Vec<u8> = state.servos.keys().copied().collect();
+    let labels = state.labels();
+    let compare = &mut state.register_compare;
+    let mut open = compare.open;
+    if !open {
+        return;
+    }
+    let send = |job| {
+        let _ = tx.send(Timed::new(SOURCE_CARD, AppCommand::Registers(job)));
+    };
+
+    egui::Window::new("Register compare").open(&mut open).resizable(true).show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            for (label, id) in [("A", &mut compare.a), ("B", &mut compare.b)] {
+                egui::ComboBox::from_id_salt(label)
+                    .selected_text(format!("{}: {}", label, labels.get(*id)))
+                    .show_ui(ui, |ui| {
+                        for &candidate in &ids {
+                            ui.selectable_value(id, candidate, labels.get(candidate));
+                        }
+                    });
+            }
+            let valid = compare.a != compare.b && !compare.busy;
+            if ui.add_enabled(valid, egui::Button::new("Compare")).clicked() {
+                compare.busy = true;
+                send(RegisterJob::Compare { a: compare.a, b: compare.b, refresh: false });
+            }
+            if ui.add_enabled(valid, egui::Button::new("Re-read")).on_hover_text("Ignore cached values").clicked() {
+                compare.busy = true;
+                send(RegisterJob::Compare { a: compare.a, b: compare.b, refresh: true });
+            }
+        });
+        if let Some(status) = &compare.status {
+            ui.label(status);
+        }
+
+        let Some((a, b)) = compare.compared else {
+            return;
+        };
+        if let Some(register) = compare.confirm {
+            let value = compare.rows.iter().find(|r| r.register == register).and_then(|r| r.a);
+            ui.horizontal(|ui| {
+                palette.status_label(ui, Status::Warning, format!(
+                    "Write {} = {} to the EEPROM of {}?",
+                    register.name,
+                    value.map(|v| v.to_string()).unwrap_or("?".into()),
+                    labels.get(b)
+                ));
+                if ui.button("Write").clicked() {
+                    compare.busy = true;
+                    compare.confirm = None;
+                    send(RegisterJob::Copy { from: a, to: b, register });
+                }
+                if ui.button("Cancel").clicked() {
+                    compare.confirm = None;
+                }
+            });
+        }
+
+        let show = |v: Option<i32>| v.map(|v| v.to_string()).unwrap_or("?".into());
+        egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+            egui::Grid::new("register_diff").striped(true).show(ui, |ui| {
+                for header in ["Register", "Group", &labels.get(a), &labels.get(b), ""] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+                for row in &compare.rows {
+                    // Groupes qui expliquent le plus souvent un écart de comportement
+                    if row.register.group.is_important() {
+                        ui.colored_label(palette.warning(), format!("{} {}", Status::Warning.icon(), row.register.name));
+                    } else {
+                        ui.label(row.register.name);
+                    }
+                    ui.label(row.register.group.label());
+                    ui.label(show(row.a));
+                    ui.label(show(row.b));
+                    let enabled = row.copyable() && !compare.busy && compare.confirm.is_none();
+                    if ui.add_enabled(enabled, egui::Button::new("Copy A→B")).clicked() {
+                        compare.confirm = Some(row.register);
+                    }
+                    ui.end_row();
+                }
+            });
+        });
+    });
+    compare.open = open;
+}
+
+// --- JOURNAL CONTINU ---
+// Chemin et taille figés pendant l'enregistrement
+fn draw_log_controls(ui: &mut egui::Ui, state: &mut SharedState) {
+    ui.horizontal(|ui| {
+        let enabled = state.log_enabled;
+        let LogSettings { path, max_size_mb } = &mut state.log_settings;
+        ui.add_enabled(!enabled, egui::TextEdit::singleline(path).desired_width(160.0));
+        ui.add_enabled(!enabled, egui::DragValue::new(max_size_mb).range(1..=10_000).suffix(" MB"))
+            .on_hover_text("A new numbered file is started past this size");
+        ui.toggle_value(&mut state.log_enabled, "⏺ Log to file");
+        if let Some(status) = &state.log_status {
+            ui.label(status);
+        }
+    });
+}
+
+// --- LATENCE DES COMMANDES ---
+fn draw_latency_panel(ui: &mut egui::Ui, stats: &mut LatencyStats) {
+    egui::CollapsingHeader::new("Command latency").show(ui, |ui| {
+        if stats.is_empty() {
+            ui.label("No command sent yet.");
+            return;
+        }
+        egui::Grid::new("latency_grid").striped(true).show(ui, |ui| {
+            for header in ["Source", "Command", "Count", "p50", "p95", "p99"] {
+                ui.strong(header);
+            }
+            ui.end_row();
+            for (source, command, histogram) in stats.rows() {
+                ui.label(source);
+                ui.label(command);
+                ui.label(histogram.count().to_string());
+                for q in [0.50, 0.95, 0.99] {
+                    // Borne haute du seau : « ≤ »
+                    let text = histogram.percentile(q)
+                        .map(|d| format!("≤ {}", latency::format_latency(d)))
+                        .unwrap_or_default();
+                    ui.label(text);
+                }
+                ui.end_row();
+            }
+        });
+        if ui.button("Reset").clicked() {
+            stats.clear();
+        }
+    });
+}
+
+// --- MANETTE ---
+fn draw_gamepad_panel(ui: &mut egui::Ui, state: &mut SharedState, tx: &Sender<Timed<AppCommand>>) {
+    let labels = state.labels();
+    let servos = state.servos.keys().map(|&id| (BindingTarget::Servo(id), labels.get(id)));
+    let groups = state.groups.groups.iter().map(|group| (BindingTarget::Group(group.name.clone()), format!("Group {}", group.name)));
+    let targets: Vec<(BindingTarget, String)> = servos.chain(groups).collect();
+    // Essai d'étalonnage : servos en mode position qui ne suivent pas un autre servo
+    let testable: Vec<(u8, String)> = state
+        .servos
+        .values()
+        .filter(|servo| servo.presence.is_online() && servo.mode == ServoMode::Position && !servo.follow.enabled)
+        .map(|servo| (servo.id, labels.get(servo.id)))
+        .collect();
+    let status = state.pad_status.clone();
+    let (changed, test_move) = egui::CollapsingHeader::new("Gamepad")
+        .show(ui, |ui| {
+            let changed = gamepad::mapping_panel(ui, &mut state.gamepad, &targets, &status);
+            ui.separator();
+            ui.strong("Calibration");
+            let outcome = gamepad::calibration_panel(ui, &mut state.gamepad, &testable, &status, &mut state.pad_calibration);
+            (changed || outcome.changed, outcome.test_move)
+        })
+        .body_returned
+        .unwrap_or_default();
+    if let Some((id, position)) = test_move {
+        if let Some(servo) = state.servos.get_mut(&id) {
+            servo.target_pos = position;
+            servo.moved_at = Instant::now();
+            let command = Command::Move { id, position, speed: gamepad::CALIBRATION_TEST_SPEED, acceleration: servo.acceleration, acknowledge_large: false };
+            let _ = tx.send(Timed::new(SOURCE_GAMEPAD, AppCommand::Servo(command)));
+        }
+    }
+    if changed {
+        let mut config = Config::load();
+        config.gamepad = state.gamepad.clone();
+        if let Err(e) = config.save() {
+            eprintln!("Could not save gamepad settings: {}", e);
+        }
+    }
+}
+
+// Échantillon de la manette en consignes de carte ; un servo hors ligne, arrêté d'urgence ou qui
+// suit un autre servo n'en reçoit pas
+fn gamepad_commands(state: &Mutex<SharedState>, pad: &mut Gamepad, controller: &mut PadController) -> Vec<Timed<AppCommand>> {
+    let sample = pad.sample();
+    let mut s = state.lock().unwrap();
+    let deadman_held = sample.as_ref().is_some_and(|(_, pad)| pad.is_pressed(s.gamepad.deadman_button));
+    s.pad_status = PadStatus { connected: sample.as_ref().map(|(name, _)| name.clone()), deadman_held, sample: sample.as_ref().map(|(_, pad)| pad.clone()) };
+    let detected: Vec<u8> = s.servos.keys().copied().collect();
+    let members = |target: &BindingTarget| match target {
+        BindingTarget::Servo(id) => vec![*id],
+        BindingTarget::Group(name) => s.groups.groups.iter().find(|group| group.name == *name).map(|group| group.members(&detected).0).unwrap_or_default(),
+    };
+    let movable = |id: u8| {
+        let servo = s.servos.get(&id).filter(|servo| servo.presence.is_online() && !servo.emergency_stopped && !servo.follow.enabled)?;
+        Some((servo.target_pos, s.limits_of(id)))
+    };
+    let commands = controller.update(&s.gamepad, sample.as_ref().map(|(_, pad)| pad), Instant::now(), members, movable);
+    commands
+        .into_iter()
+        .filter_map(|command| match command {
+            PadCommand::Move { id, position } => {
+                let servo = s.servos.get_mut(&id).filter(|servo| servo.mode == ServoMode::Position)?;
+                servo.target_pos = position;
+                servo.moved_at = Instant::now();
+                Some(AppCommand::Servo(Command::Move { id, position, speed: servo.target_speed, acceleration: servo.acceleration, acknowledge_large: false }))
+            }
+            PadCommand::Wheel { id, speed } => {
+                let servo = s.servos.get_mut(&id).filter(|servo| servo.mode == ServoMode::Wheel)?;
+                servo.wheel_speed = speed;
+                Some(AppCommand::Rotate { id, speed })
+            }
+        })
+        .map(|command| Timed::new(SOURCE_GAMEPAD, command))
+        .collect()
+}
+
+// --- DÉTECTION DE BLOCAGE ---
+fn draw_stall_settings(ui: &mut egui::Ui, state: &mut SharedState) {
+    let changed = egui::CollapsingHeader::new("Stall detection")
+        .show(ui, |ui| stall::settings_editor(ui, &mut state.stall))
+        .body_returned
+        .unwrap_or(false);
+    if changed {
+        let mut config = Config::load();
+        config.stall = state.stall.clone();
+        if let Err(e) = config.save() {
+            eprintln!("Could not save stall settings: {}", e);
+        }
+    }
+}
+
+// --- RÉGLAGES PAR SERVO ---
+// Nom, butées, vitesse/accélération propres et sens modifiés sur une carte : enregistrés aussitôt
+// dans le fichier de configuration
+fn save_servo_settings(state: &mut SharedState) {
+    let SharedState { servos, saved_limits, servo_settings, .. } = state;
+    let mut limits_changed = false;
+    let mut changed = Vec::new();
+    for servo in servos.values() {
+        if saved_limits.get(&servo.id).copied().unwrap_or_default() != servo.limits {
+            saved_limits.insert(servo.id, servo.limits);
+            limits_changed = true;
+        }
+        let settings = ServoSettings {
+            name: servo.name.clone(),
+            speed: servo.motion_saved.then_some(servo.target_speed),
+            acceleration: servo.motion_saved.then_some(servo.acceleration),
+            inverted: servo.inverted,
+            follow: (servo.follow != FollowSettings::default()).then_some(servo.follow),
+        };
+        if servo_settings.get(&servo.id).cloned().unwrap_or_default() != settings {
+            changed.push((servo.id, settings));
+        }
+    }
+    if !limits_changed && changed.is_empty() {
+        return;
+    }
+    let mut config = Config::load();
+    config.limits = saved_limits.clone();
+    for (id, settings) in changed {
+        config.set_servo(id, settings);
+    }
+    *servo_settings = config.servos.clone();
+    if let Err(e) = config.save() {
+        eprintln!("Could not save servo settings: {}", e);
+    }
+}
+
+// Réglages courants écrits dans le fichier de configuration ; le port du bus simulé ou du rejeu
+// n'est pas gardé
+fn save_settings(state: &mut SharedState) {
+    save_servo_settings(state);
+    let mut config = Config::load();
+    if state.simulation.is_none() && state.replay.is_none() {
+        config.bus.port = state.port.clone();
+    }
+    config.bus.scan = state.scan_range;
+    config.ui.theme = state.theme;
+    config.ui.slider_mode = state.slider_mode;
+    config.ui.angle = state.angle;
+    config.ui.delta_tolerance = state.delta_tolerance;
+    config.limits = state.saved_limits.clone();
+    config.stall = state.stall.clone();
+    config.rescan = state.rescan.clone();
+    config.logging = state.log_settings.clone();
+    config.exit.release_torque = state.exit.release_torque;
+    state.settings_status = Some(match config.save() {
+        Ok(()) => format!("✓ Saved to {}", config::resolve_path().display()),
+        Err(e) => format!("✗ {}", e),
+    });
+}
+
+// Rapport écrit dans le répertoire courant, avec liens vers le journal continu et l'enregistrement
+fn write_report(state: &mut SharedState) {
+    let duration = state.started.elapsed().as_secs_f64();
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+    let started = now_ms.saturating_sub((duration * 1000.0) as u64);
+    let mut report = SessionReport::build(started, duration, state.events.events(), &state.telemetry);
+    report.odometers = state.servos.values().filter_map(|servo| Some((servo.id, servo.odometer.clone()?))).collect();
+    if std::path::Path::new(&state.log_settings.path).exists() {
+        report.link("Telemetry log", state.log_settings.path.as_str());
+    }
+    if let Some(recording) = state.recorder.status() {
+        report.link("Session recording", recording.path.display().to_string());
+    }
+    state.report_status = Some(match report.write(std::path::Path::new("."), false) {
+        Ok(paths) => format!("✓ Report written to {}", paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")),
+        Err(e) => format!("✗ {}", e),
+    });
+}
+
+// --- ACTIONS SUR TOUS LES SERVOS ---
+// Centrage, couple et consigne commune des servos en ligne, en une commande du groupe implicite
+// « all servos » : un seul sync write pour les consignes
+fn draw_bulk_toolbar(ui: &mut egui::Ui, state: &mut SharedState, tx: &Sender<Timed<AppCommand>>) {
+    let angle = state.angle;
+    let mut move_to = None;
+    ui.horizontal(|ui| {
+        if ui.button("Center all").on_hover_text(format!("Move every online servo to 2048, at {} steps/s at most", CENTER_SPEED)).clicked() {
+            move_to = Some((units::CENTER_TICKS, Some(CENTER_SPEED)));
+        }
+        ui.separator();
+        for (text, enable) in [("Torque all ON", true), ("Torque all OFF", false)] {
+            if ui.button(text).clicked() {
+                torque_all(state, tx, enable);
+            }
+        }
+        ui.separator();
+        let bulk = &mut state.bulk;
+        ui.add(units::position_field(&mut bulk.goto, 0..=units::MAX_TICKS, angle)).on_hover_text("Target for every servo, in the selected unit");
+        if ui.button("Go to…").on_hover_text("Move every online servo to this target, at its own speed").clicked() {
+            move_to = Some((bulk.goto, None));
+        }
+        if let Some((status, text)) = &bulk.status {
+            state.theme.palette().status_label(ui, *status, text);
+        }
+    });
+    if let Some((target, speed_cap)) = move_to {
+        move_all(state, tx, target, speed_cap);
+    }
+}
+
+fn send_bulk(tx: &Sender<Timed<AppCommand>>, command: GroupCommand) {
+    let _ = tx.send(Timed::new(SOURCE_BULK, AppCommand::Group { group: ALL_SERVOS.to_string(), command }));
+}
+
+// Couple de tous les servos en ligne, chacun suivi comme le bouton de sa carte
+fn torque_all(state: &mut SharedState, tx: &Sender<Timed<AppCommand>>, enable: bool) {
+    let cmd = if enable { "torque on" } else { "torque off" };
+    let online: Vec<u8> = state.servos.values().filter(|servo| servo.presence.is_online()).map(|servo| servo.id).collect();
+    let members = online
+        .into_iter()
+        .map(|id| {
+            let member = response::next_id();
+            state.commands.track(member, cmd, Some(id));
+            (member, id)
+        })
+        .collect();
+    send_bulk(tx, GroupCommand::Torque { members, enable });
+}
+
+// Consigne commune des servos en ligne ; bilan dans la barre d'actions
+fn move_all(state: &mut SharedState, tx: &Sender<Timed<AppCommand>>, target: u16, speed_cap: Option<u16>) {
+    let online: Vec<u8> = state.servos.values().filter(|servo| servo.presence.is_online()).map(|servo| servo.id).collect();
+    let offline: Vec<u8> = state.servos.values().filter(|servo| !servo.presence.is_online()).map(|servo| servo.id).collect();
+    let angle = state.angle;
+    // Suiveurs et roues gardent leur pilotage ; butées de chaque servo appliquées ici, pour le dire
+    let mut targets = Vec::new();
+    let mut clamped = Vec::new();
+    for id in online {
+        let limits = state.limits_of(id);
+        let Some(servo) = state.servos.get_mut(&id).filter(|servo| !servo.follow.enabled && servo.mode != ServoMode::Wheel) else { continue };
+        let (position, speed) = groups::bulk_target(target, &limits, servo.target_speed, speed_cap);
+        if position != target {
+            clamped.push(format!("{} → {}", config::servo_label(id, &servo.name), angle.format(position)));
+        }
+        servo.target_pos = position;
+        servo.moved_at = Instant::now();
+        targets.push((id, position, speed));
+    }
+    let mut notes = Vec::new();
+    if !clamped.is_empty() {
+        notes.push(format!("clamped to soft limits: {}", clamped.join(", ")));
+    }
+    if !offline.is_empty() {
+        notes.push(format!("skipped offline: {}", state.labels().list(offline)));
+    }
+    let summary = format!("{} servo(s) to {}", targets.len(), angle.format(target));
+    state.bulk.status = Some(match notes.is_empty() {
+        true => (Status::Ok, summary),
+        false => (Status::Warning, format!("{}; {}", summary, notes.join("; "))),
+    });
+    if !targets.is_empty() {
+        send_bulk(tx, GroupCommand::Move { targets });
+    }
+}
+
+// --- FENÊTRE DE COPIE DE POSITION ---
+fn draw_copy_window(ctx: &egui::Context, state: &mut SharedState, tx: &Sender<Timed<AppCommand>>) {
+    let Some(mut request) = state.copy_request.take() else {
+        return;
+    };
+    let Some(dest_pos) = state.servos.get(&request.to).map(|s| s.current_pos) else {
+        return;
+    };
+    let labels = state.labels();
+    let palette = state.theme.palette();
+    // Servo de la source dont la correspondance (sens, offset) est comparée à la destination
+    let mapped_from = match &request.source {
+        CopySource::Servo(from) => Some(*from),
+        CopySource::Pose { entry, .. } => Some(*entry),
+        CopySource::Manual(_) => None,
+    }
+    .filter(|&from| from != request.to);
+    if let Some(from) = mapped_from.filter(|_| !request.offsets_requested) {
+        request.offsets_requested = true;
+        let _ = tx.send(Timed::new(SOURCE_COPY, AppCommand::ReadOffsets { ids: vec![from, request.to] }));
+    }
+    let mapping = |id: u8| Mapping { inverted: state.inversions.is_inverted(id), offset: state.position_offsets.get(&id).copied() };
+    let differences = mapped_from.map_or_else(Vec::new, |from| mapping(from).differences(&mapping(request.to)));
+
+    let mut open = true;
+    let mut keep = true;
+    egui::Window::new("Copy position")
+        .open(&mut open)
+        .collapsible(false)
+        .resizable(false)
+        .show(ctx, |ui| {
+            let target = match &mut request.source {
+                CopySource::Servo(from) => {
+                    let live = state.servos.get(from).map(|s| s.current_pos);
+                    match live {
+                        Some(pos) => ui.label(format!("Source: {} (live {})", labels.get(*from), pos)),
+                        None => palette.status_label(ui, Status::Danger, format!("Source {} not detected", labels.get(*from))),
+                    };
+                    live
+                }
+                CopySource::Pose { name, entry } => {
+                    let poses = &state.poses.library.poses;
+                    if !poses.iter().any(|pose| pose.name == *name) {
+                        name.clone_from(&poses.first().map(|pose| pose.name.clone()).unwrap_or_default());
+                    }
+                    let mut position = None;
+                    ui.horizontal(|ui| {
+                        ui.label("Source: pose");
+                        egui::ComboBox::from_id_salt("copy_pose").selected_text(name.as_str()).show_ui(ui, |ui| {
+                            for pose in poses {
+                                ui.selectable_value(name, pose.name.clone(), &pose.name);
+                            }
+                        });
+                        match poses.iter().find(|pose| pose.name == *name) {
+                            Some(pose) => {
+                                egui::ComboBox::from_id_salt("copy_pose_entry").selected_text(labels.get(*entry)).show_ui(ui, |ui| {
+                                    for &id in pose.positions.keys() {
+                                        ui.selectable_value(entry, id, labels.get(id));
+                                    }
+                                });
+                                position = pose.positions.get(entry).copied();
+                                if position.is_none() {
+                                    palette.status_label(ui, Status::Warning, "no entry for this servo");
+                                }
+                            }
+                            None => {
+                                palette.status_label(ui, Status::Warning, format!("No poses in {}", POSES_FILE));
+                            }
+                        }
+                    });
+                    position
+                }
+                CopySource::Manual(ticks) => {
+                    ui.horizontal(|ui| {
+                        ui.label("Source: manual");
+                        if request.manual_in_degrees {
+                            let mut deg = ticks_to_degrees(*ticks);
+                            if ui.add(egui::DragValue::new(&mut deg).range(-180.0..=180.0).speed(0.5).suffix("°")).changed() {
+                                *ticks = degrees_to_ticks(deg);
+                            }
+                        } else {
+                            ui.add(egui::DragValue::new(ticks).range(0..=4095));
+                        }
+                        ui.checkbox(&mut request.manual_in_degrees, "degrees");
+                    });
+                    Some(*ticks)
+                }
+            };
+
+            ui.label(format!("Destination: {} (currently {})", labels.get(request.to), dest_pos));
+            ui.horizontal(|ui| {
+                ui.label("Speed:");
+                ui.add(egui::DragValue::new(&mut request.speed).range(1..=3400));
+            });
+
+            // Aperçu de ce que le worker enverra : butées et verrous de la destination appliqués
+            let constraints = constraints_of(state, &HashMap::new(), &ThermalLockout::default(), request.to);
+            let validated = target.map(|target| validate_move(&constraints, target.into(), request.speed.into(), 0));
+            match &validated {
+                Some(Ok(m)) => {
+                    ui.strong(format!(
+                        "Target: {} ({:+} ticks, {:.1}°)",
+                        m.position,
+                        m.position as i32 - dest_pos as i32,
+                        ticks_to_degrees(m.position)
+                    ));
+                    if m.clamped {
+                        palette.status_label(ui, Status::Warning, format!("{} clamped to the destination's soft limits", target.unwrap_or_default()));
+                    }
+                }
+                Some(Err(e)) => {
+                    palette.status_label(ui, Status::Danger, e.to_string());
+                }
+                None => {}
+            }
+
+            for difference in &differences {
+                palette.status_label(ui, Status::Warning, format!("Mapping mismatch: {}", difference));
+            }
+            if !differences.is_empty() {
+                ui.checkbox(&mut request.mapping_confirmed, "Send anyway: the same position gives a different angle on the destination");
+            }
+
+            ui.horizontal(|ui| {
+                let sendable = matches!(validated, Some(Ok(_))) && (differences.is_empty() || request.mapping_confirmed);
+                if ui.add_enabled(sendable, egui::Button::new("Send")).clicked() {
+                    if let Some(Ok(m)) = validated {
+                        let acceleration = state.servos.get(&request.to).map_or(DEFAULT_ACCELERATION, |s| s.acceleration);
+                        let _ = tx.send(Timed::new(SOURCE_COPY, AppCommand::Servo(Command::Move {
+                            id: request.to,
+                            position: m.position,
+                            speed: request.speed,
+                            acceleration,
+                            acknowledge_large: false,
+                        })));
+                        if let Some(dest) = state.servos.get_mut(&request.to) {
+                            dest.target_pos = m.position;
+                            dest.moved_at = Instant::now();
+                        }
+                    }
+                    keep = false;
+                }
+                if ui.button("Cancel").clicked() {
+                    keep = false;
+                }
+            });
+        });
+
+    if open && keep {
+        state.copy_request = Some(request);
+    }
+}
+
+// --- COMPOSANT GRAPHIQUE POUR UN SERVO ---
+// Réglages d'affichage communs à toutes les cartes
+struct CardOptions {
+    // Le slider envoie directement la consigne (hors mode coordonné)
+    live: bool,
+    slider_mode: SliderMode,
+    angle: AngleDisplay,
+    delta_tolerance: u16,
+    palette: Palette,
+    // Le changement de sens d'une carte vaut dès la transaction suivante
+    inversions: Inversions,
+}
+
+// Slider de vitesse signée et arrêt d'un servo en mode roue
+fn draw_wheel_controls(ui: &mut egui::Ui, servo: &mut IndividualServo, slider_mode: SliderMode, tx: &Sender<Timed<AppCommand>>) {
+    ui.horizontal(|ui| {
+        ui.label("Speed:");
+        let slider = ui.add(
+            egui::Slider::new(&mut servo.wheel_speed, -MAX_WHEEL_SPEED..=MAX_WHEEL_SPEED)
+                .suffix(" steps/s")
+                .text("Wheel"),
+        );
+        let send = slider_mode.should_send(&slider);
+        let stop = ui.button("Stop").clicked();
+        if stop {
+            servo.wheel_speed = 0;
+        }
+        if send || stop {
+            let _ = tx.send(Timed::new(SOURCE_CARD, AppCommand::Rotate { id: servo.id, speed: servo.wheel_speed }));
+        }
+        if !servo.torque_on {
+            ui.weak("(torque off)").on_hover_text("Enable torque for the wheel to turn");
+        }
+        ui.label(format!("(Real: {})", servo.current_pos)).on_hover_text("Position keeps wrapping around in wheel mode");
+    });
+}
+
+// Actions de l'en-tête d'un groupe : couple, pose du groupe et décalage commun des membres
+// présents. Rend la pose capturée, à enregistrer
+fn draw_group_actions(
+    ui: &mut egui::Ui,
+    group: &ServoGroup,
+    servos: &mut BTreeMap<u8, IndividualServo>,
+    offset: &mut GroupOffset,
+    commands: &mut Tracker,
+    options: &CardOptions,
+    tx: &Sender<Timed<AppCommand>>,
+) -> Option<BTreeMap<u8, u16>> {
+    let detected: Vec<u8> = servos.keys().copied().collect();
+    let (present, _) = group.members(&detected);
+    let send = |command| {
+        let _ = tx.send(Timed::new(SOURCE_GROUP, AppCommand::Group { group: group.name.clone(), command }));
+    };
+    if present.is_empty() {
+        return None;
+    }
+    ui.separator();
+
+    for (text, enable) in [("Torque ON", true), ("Torque OFF", false)] {
+        if ui.button(text).clicked() {
+            let cmd = if enable { "torque on" } else { "torque off" };
+            let members = present
+                .iter()
+                .map(|&id| {
+                    let member = response::next_id();
+                    commands.track(member, cmd, Some(id));
+                    (member, id)
+                })
+                .collect();
+            send(GroupCommand::Torque { members, enable });
+        }
+    }
+    ui.separator();
+
+    // Pose du groupe : un seul sync write pour les membres présents
+    let go = ui.add_enabled(!group.pose.is_empty(), egui::Button::new("▶ Pose"))
+        .on_hover_text("Move every member to the group pose (one synchronized write)");
+    if go.clicked() {
+        let (targets, _) = group.pose_targets(&detected);
+        let targets = targets
+            .into_iter()
+            .filter_map(|(id, position)| {
+                let servo = servos.get_mut(&id)?;
+                servo.target_pos = position;
+                servo.moved_at = Instant::now();
+                Some((id, position, servo.target_speed))
+            })
+            .collect();
+        send(GroupCommand::Move { targets });
+    }
+    let capture = ui.button("📷 Capture").on_hover_text("Store the members' current positions as the group pose").clicked();
+    ui.separator();
+
+    // Décalage commun, retourné pour les servos inversés ; repart de zéro au relâchement
+    ui.label("Offset:");
+    let slider = ui.add(egui::Slider::new(&mut offset.offset, -1024..=1024).suffix(" ticks"))
+        .on_hover_text("Moves every member by the same amount from its current target");
+    if slider.changed() && offset.base.is_none() {
+        offset.base = Some(present.iter().filter_map(|id| servos.get(id)).map(|s| (s.id, s.target_pos)).collect());
+    }
+    let released = slider.drag_stopped() || (slider.changed() && !slider.is_pointer_button_down_on());
+    let write = match options.slider_mode {
+        SliderMode::Live => slider.changed(),
+        SliderMode::OnRelease => released,
+    };
+    if let Some(base) = &offset.base {
+        let mut targets = Vec::new();
+        for (id, position) in groups::offset_targets(base, offset.offset) {
+            if let Some(servo) = servos.get_mut(&id) {
+                servo.target_pos = position;
+                servo.moved_at = Instant::now();
+                targets.push((id, position, servo.target_speed));
+            }
+        }
+        if write && options.live {
+            send(GroupCommand::Move { targets });
+        }
+    }
+    if released {
+        *offset = GroupOffset::default();
+    }
+
+    capture.then(|| present.iter().filter_map(|id| servos.get(id)).map(|s| (s.id, s.current_pos)).collect())
+}
+
+// Pose capturée enregistrée dans le groupe du fichier de configuration
+fn save_group_pose(groups: &mut [ServoGroup], name: &str, pose: BTreeMap<u8, u16>) -> (Status, String) {
+    let mut config = Config::load();
+    for group in groups.iter_mut().chain(config.groups.iter_mut()).filter(|group| group.name == name) {
+        group.pose = pose.clone();
+    }
+    match config.save() {
+        Ok(()) => (Status::Ok, format!("Captured the pose of '{}' ({} servos)", name, pose.len())),
+        Err(e) => (Status::Danger, e),
+    }
+}
+
+// Servo enregistré dans `[servos]` que le scan n'a pas trouvé ; vrai pour l'oublier
+fn draw_missing_card(ui: &mut egui::Ui, id: u8, stored: &ServoSettings, palette: &Palette) -> bool {
+    let mut forget = false;
+    egui::Frame::group(ui.style())
+        .inner_margin(10.0)
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.weak(config::servo_label(id, &stored.name));
+                palette.status_label(ui, Status::Danger, "NOT DETECTED")
+                    .on_hover_text("Listed in the configuration file but not found by the last scan: check its cable");
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    forget = ui.button("Forget").on_hover_text("Remove its name and settings from the configuration file").clicked();
+                });
+            });
+        });
+    forget
+}
+
+fn draw_servo_card(
+    ui: &mut egui::Ui,
+    servo: &mut IndividualServo,
+    sources: &[(u8, String, u16)],
+    copy_request: &mut Option<CopyRequest>,
+    commands: &mut Tracker,
+    options: &CardOptions,
+    tx: &Sender<Timed<AppCommand>>,
+) {
+    let CardOptions { live, slider_mode, angle, delta_tolerance, ref palette, ref inversions } = *options;
+    egui::Frame::group(ui.style())
+        .inner_margin(10.0)
+        .show(ui, |ui| {
+            // Servo muet : carte grisée, valeurs figées à la dernière lecture
+            if !servo.presence.is_online() {
+                ui.disable();
+            }
+            ui.horizontal(|ui| {
+                // Menu d'actions de la carte
+                ui.menu_button("⋯", |ui| {
+                    ui.menu_button("Copy position from", |ui| {
+                        for (from, label, pos) in sources.iter().filter(|(from, _, _)| *from != servo.id) {
+                            if ui.button(format!("{} · {}", label, pos)).clicked() {
+                                *copy_request = Some(CopyRequest::new(CopySource::Servo(*from), servo.id));
+                                ui.close();
+                            }
+                        }
+                        if ui.button("Pose entry...").clicked() {
+                            *copy_request = Some(CopyRequest::new(CopySource::Pose { name: String::new(), entry: servo.id }, servo.id));
+                            ui.close();
+                        }
+                        if ui.button("Manual value...").clicked() {
+                            *copy_request = Some(CopyRequest::new(CopySource::Manual(servo.current_pos), servo.id));
+                            ui.close();
+                        }
+                    });
+                });
+
+                // Nom (modifiable), ID et Température
+                ui.add(egui::TextEdit::singleline(&mut servo.name).hint_text("name").desired_width(110.0))
+                    .on_hover_text("Shown instead of the ID everywhere; saved in the configuration file");
+                let id = servo.identity.map_or(format!("ID {}", servo.id), |identity| identity.describe(servo.id));
+                ui.colored_label(palette.info(), format!("({})", id));
+                ui.separator();
+                if !servo.presence.is_online() {
+                    palette.status_label(ui, Status::Danger, "OFFLINE").on_disabled_hover_text(format!(
+                        "No response for {:.0} s; values are the last ones read",
+                        servo.presence.last_seen().elapsed().as_secs_f32()
+                    ));
+                }
+                
+                // Indicateur Température
+                palette.status_label(ui, temperature_status(servo.temperature), format!("{}°C", servo.temperature));
+                if servo.follow.enabled {
+                    let source = sources.iter().find(|(from, _, _)| *from == servo.follow.source)
+                        .map_or(format!("ID {}", servo.follow.source), |(_, label, _)| label.clone());
+                    ui.colored_label(palette.info(), "🔗").on_hover_text(format!("Following {}", source));
+                }
+                if servo.emergency_stopped {
+                    palette.status_label(ui, Status::Danger, "E-STOP")
+                        .on_hover_text("Enable torque on this servo to move it again");
+                }
+                if let Some(cooled) = servo.thermal_lockout {
+                    let hint = if cooled {
+                        "Cooled down: enable torque to re-arm"
+                    } else {
+                        "Torque cut for overheating; it can be enabled again once the servo has cooled down"
+                    };
+                    palette.status_label(ui, Status::Danger, "THERMAL LOCKOUT").on_hover_text(hint);
+                } else if servo.derating_percent < 100 {
+                    palette.status_label(ui, Status::Warning, format!("Speed & torque {}%", servo.derating_percent))
+                        .on_hover_text("Maximum speed and torque limit reduced while the servo is hot");
+                }
+                if let Some(reason) = &servo.rejection {
+                    palette.status_label(ui, Status::Danger, format!("Rejected: {}", reason));
+                }
+                if let Some(position) = servo.unconfirmed_move {
+                    if ui.small_button("Confirm move").on_hover_text("Send this large first move anyway").clicked() {
+                        let _ = tx.send(Timed::new(SOURCE_CARD, AppCommand::Servo(Command::Move {
+                            id: servo.id,
+                            position,
+                            speed: servo.target_speed,
+                            acceleration: servo.acceleration,
+                            acknowledge_large: true,
+                        })));
+                        servo.unconfirmed_move = None;
+                    }
+                }
+                if servo.duplicate_id {
+                    palette.status_label(ui, Status::Danger, "DUPLICATE ID?").on_hover_text(
+                        "Replies to this ID were garbled or inconsistent: several servos may share it. \
+                         Moves are blocked; unplug all but one servo and rescan.",
+                    );
+                }
+                if let Some(stall) = servo.stall {
+                    palette.status_label(ui, Status::Danger, "STALLED").on_hover_text(stall.describe());
+                    if ui.small_button("Clear stall").on_hover_text("The mechanism is free: accept moves again").clicked() {
+                        servo.stall = None;
+                        servo.rejection = None;
+                    }
+                }
+                if let Some((requested, sent)) = servo.clamped {
+                    palette.status_label(ui, Status::Warning, format!("Clamped {} → {}", angle.format(requested), angle.format(sent)))
+                        .on_hover_text(format!("Target held inside the soft limits ({} → {} ticks)", requested, sent));
+                }
+                
+                // Indicateur Voltage
+                ui.label(format!("{:.1}V", servo.voltage));
+                retry::error_label(ui, palette, servo.comm_errors);
+
+                if let Some(odo) = &servo.odometer {
+                    ui.weak(format!("odo {} ticks", odometer::format_ticks(odo.ticks)))
+                        .on_hover_text(format!(
+                            "{} ticks since {}",
+                            odo.ticks,
+                            odometer::format_date(odo.first_seen_unix_ms)
+                        ));
+                }
+                
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    // Bouton Torque
+                    // Le libellé ne change qu'au résultat confirmé ; en attendant, bouton inactif
+                    let btn_text = if servo.torque_on { "Torque ON" } else { "Torque OFF" };
+                    let pending = commands.pending("torque on", Some(servo.id)) || commands.pending("torque off", Some(servo.id));
+                    let btn = ui.add_enabled(!pending, egui::Button::new(btn_text));
+                    if pending {
+                        ui.spinner();
+                    }
+                    if btn.clicked() {
+                        let enable = !servo.torque_on;
+                        let timed = Timed::new(SOURCE_CARD, AppCommand::Servo(Command::Torque { id: servo.id, enable }));
+                        commands.track(timed.id, if enable { "torque on" } else { "torque off" }, Some(servo.id));
+                        let _ = tx.send(timed);
+                    }
+                });
+            });
+
+            ui.add_space(5.0);
+
+            // Mode de fonctionnement : la roue est arrêtée par le worker avant le retour en position
+            ui.horizontal(|ui| {
+                ui.label("Mode:");
+                let before = servo.mode;
+                egui::ComboBox::from_id_salt("servo_mode")
+                    .selected_text(servo.mode.label())
+                    .show_ui(ui, |ui| {
+                        for mode in ServoMode::ALL {
+                            ui.selectable_value(&mut servo.mode, mode, mode.label());
+                        }
+                    });
+                if servo.mode != before {
+                    servo.wheel_speed = 0;
+                    let _ = tx.send(Timed::new(SOURCE_CARD, AppCommand::SetMode { id: servo.id, mode: servo.mode }));
+                }
+            });
+
+            if servo.mode == ServoMode::Wheel {
+                draw_wheel_controls(ui, servo, slider_mode, tx);
+            } else {
+                // Slider de Position
+                ui.horizontal(|ui| {
+                    ui.label("Pos:");
+                    // Slider qui contrôle 'target_pos'
+                    let target = servo.target_pos;
+                    // Suiveur : la consigne vient de la source, slider et saisie ne font que l'afficher
+                    let editable = !servo.follow.enabled;
+                    let range = servo.limits.range();
+                    let slider = ui.add_enabled(editable, units::position_slider(&mut servo.target_pos, range.clone(), angle).show_value(false))
+                        .on_hover_text(format!("{} ticks", target));
+                    // Saisie exacte et petits pas, envoyés comme le slider
+                    let field = ui.add_enabled(editable, units::position_field(&mut servo.target_pos, range.clone(), angle))
+                        .on_hover_text("Target, in the selected unit");
+                    let nudged = ui.add_enabled_ui(editable, |ui| units::nudge_buttons(ui, &[-10, -1, 1, 10])).inner;
+                    if let Some(delta) = nudged {
+                        servo.target_pos = jog::nudge(servo.target_pos, delta, range);
+                    }
+                
+                    // Nouvelle consigne : on laisse au servo le temps de démarrer avant de le dire bloqué
+                    if slider.changed() || field.changed() || nudged.is_some() {
+                        servo.moved_at = Instant::now();
+                    }
+                    let send = slider_mode.should_send(&slider) || slider_mode.should_send(&field) || nudged.is_some();
+                    if send && live {
+                        let _ = tx.send(Timed::new(SOURCE_CARD, AppCommand::Servo(Command::Move {
+                            id: servo.id,
+                            position: servo.target_pos,
+                            speed: servo.target_speed,
+                            acceleration: servo.acceleration,
+                            acknowledge_large: false,
+                        })));
+                    }
+                
+                    // Repère de la position réelle sur le rail du slider
+                    let severity = servo.delta_severity(delta_tolerance);
+                    let status = match severity {
+                        DeltaSeverity::OnTarget => Status::Ok,
+                        DeltaSeverity::Moderate => Status::Warning,
+                        DeltaSeverity::Large | DeltaSeverity::Stuck => Status::Danger,
+                    };
+                    let color = palette.status(status);
+                    let rail = egui::Rect::from_min_size(
+                        slider.rect.left_top(),
+                        egui::vec2(ui.spacing().slider_width, slider.rect.height()),
+                    );
+                    let inset = rail.height() / 2.5;
+                    // Le rail ne couvre que la fenêtre des butées : une position hors butées est au bord
+                    let limits = servo.limits;
+                    let (low, high) = (*limits.range().start(), *limits.range().end());
+                    let to_x = |ticks: u16| {
+                        let ratio = if high > low { (ticks.clamp(low, high) - low) as f32 / (high - low) as f32 } else { 0.5 };
+                        egui::lerp(rail.left() + inset..=rail.right() - inset, ratio)
+                    };
+
+                    // Zones d'approche ralentie
+                    let shade = |from: u16, to: u16, color: egui::Color32| {
+                        if to > from {
+                            let band = egui::Rect::from_x_y_ranges(to_x(from)..=to_x(to), rail.y_range());
+                            ui.painter().rect_filled(band, 0.0, color.gamma_multiply(0.3));
+                        }
+                    };
+                    if limits.slowdown {
+                        shade(limits.min, limits.min.saturating_add(limits.approach_zone).min(limits.max), palette.warning());
+                        shade(limits.max.saturating_sub(limits.approach_zone).max(limits.min), limits.max, palette.warning());
+                    }
+
+                    ui.painter().vline(to_x(servo.current_pos), rail.y_range(), egui::Stroke::new(2.0, color));
+
+                    // Affichage de la position réelle (feedback)
+                    ui.label(format!("(Real: {})", angle.format(servo.current_pos)))
+                        .on_hover_text(format!("{} ticks", servo.current_pos));
+                    let delta_text = match severity {
+                        DeltaSeverity::Stuck => format!("Δ {} stuck", angle.format_delta(servo.delta())),
+                        _ => format!("Δ {}", angle.format_delta(servo.delta())),
+                    };
+                    palette.status_label(ui, status, delta_text)
+                        .on_hover_text(format!("{:+} ticks", servo.delta()));
+                });
+
+                // Vitesse et accélération des consignes du slider ; une fois modifiées, propres au servo
+                ui.horizontal(|ui| {
+                    ui.label("Speed:");
+                    let speed = ui.add(egui::DragValue::new(&mut servo.target_speed).range(0..=MAX_SPEED))
+                        .on_hover_text("0 = maximum speed");
+                    ui.label("Accel:");
+                    let acceleration = ui.add(egui::DragValue::new(&mut servo.acceleration).range(0..=MAX_ACCELERATION))
+                        .on_hover_text("0 = maximum acceleration");
+                    if speed.changed() || acceleration.changed() {
+                        servo.motion_saved = true;
+                    }
+                    let inverted = ui.checkbox(&mut servo.inverted, "Inverted")
+                        .on_hover_text("Mirror positions around 2048 for a joint mounted the other way round, so a left/right pair shares the same poses");
+                    if inverted.changed() {
+                        // Même position physique vue depuis l'autre sens : consigne, relevé et butées suivent
+                        servo.current_pos = inversion::mirror(servo.current_pos);
+                        servo.target_pos = inversion::mirror(servo.target_pos);
+                        servo.limits = servo.limits.mirrored();
+                        inversions.set(servo.id, servo.inverted);
+                    }
+                });
+
+                // Suivi d'un autre servo : consigne recalculée par le worker à chaque cycle
+                ui.horizontal(|ui| {
+                    let others: Vec<&(u8, String, u16)> = sources.iter().filter(|(from, _, _)| *from != servo.id).collect();
+                    let follow = &mut servo.follow;
+                    let toggle = ui.add_enabled(!others.is_empty(), egui::Checkbox::new(&mut follow.enabled, "Follow"))
+                        .on_hover_text("Track another servo's position in real time: target = 2048 + (source − 2048) × scale + offset");
+                    if toggle.changed() {
+                        if follow.enabled && !others.iter().any(|(from, _, _)| *from == follow.source) {
+                            follow.source = others[0].0;
+                        }
+                        // Arrêt du suivi : le servo garde sa position, le slider repart de là
+                        if !follow.enabled {
+                            servo.target_pos = servo.current_pos;
+                        }
+                    }
+                    let selected = others.iter().find(|(from, _, _)| *from == follow.source).map_or(format!("ID {}", follow.source), |(_, label, _)| label.clone());
+                    egui::ComboBox::from_id_salt("follow_source").selected_text(selected).show_ui(ui, |ui| {
+                        for (from, label, _) in &others {
+                            ui.selectable_value(&mut follow.source, *from, label);
+                        }
+                    });
+                    ui.label("×");
+                    ui.add(egui::DragValue::new(&mut follow.scale).range(-4.0..=4.0).speed(0.01))
+                        .on_hover_text("Negative: opposite direction, mirrored around 2048");
+                    ui.label("+");
+                    ui.add(egui::DragValue::new(&mut follow.offset).range(-4095..=4095).suffix(" ticks"));
+                    ui.label("Deadband:");
+                    ui.add(egui::DragValue::new(&mut follow.deadband).range(1..=200).suffix(" ticks"))
+                        .on_hover_text("Smallest change of the mapped target worth a new command");
+                });
+            }
+            
+            // Charge signée (flèche = sens de l'effort), courant, vitesse et mouvement
+            ui.horizontal(|ui| {
+                let arrow = match servo.load {
+                    l if l > 0.0 => "▶",
+                    l if l < 0.0 => "◀",
+                    _ => "·",
+                };
+                // Barre pleine = le servo pousse à sa limite de couple
+                let limit = servo.torque_limit.unwrap_or_default();
+                ui.add(
+                    egui::ProgressBar::new(limit.load_ratio(servo.load))
+                        .desired_width(120.0)
+                        .text(format!("Load {} {:+.1}%", arrow, servo.load)),
+                )
+                .on_hover_text(format!("Relative to the torque limit ({:.1}%)", limit.percent()));
+                ui.label(format!("{:.0} mA", servo.current));
+                ui.label(format!("{:+} steps/s", servo.speed));
+                if servo.is_moving {
+                    ui.colored_label(palette.status(Status::Ok), "●").on_hover_text("Moving");
+                } else {
+                    ui.weak("○").on_hover_text("Stopped");
+                }
+            });
+
+            // Limite de couple, écrite au relâchement du slider
+            ui.horizontal(|ui| {
+                ui.label("Torque limit:");
+                let slider = ui.add(egui::Slider::new(&mut servo.torque_limit_input, 0.0..=100.0).suffix(" %").fixed_decimals(1));
+                if slider.drag_stopped() || (slider.changed() && !slider.is_pointer_button_down_on()) {
+                    let limit = TorqueLimit::from_percent(servo.torque_limit_input);
+                    let _ = tx.send(Timed::new(SOURCE_CARD, AppCommand::SetTorqueLimit { id: servo.id, limit }));
+                }
+                match servo.torque_limit {
+                    Some(limit) => ui.weak(format!("(servo: {:.1}%)", limit.percent())),
+                    None => ui.weak("(not read)"),
+                };
+            });
+
+            // Préhension limitée en courant
+            ui.horizontal(|ui| {
+                if ui.button("Grip").clicked() {
+                    let _ = tx.send(Timed::new(SOURCE_CARD, AppCommand::Grip { id: servo.id, settings: servo.grip }));
+                }
+                if ui.button("Release").clicked() {
+                    let _ = tx.send(Timed::new(SOURCE_CARD, AppCommand::Release { id: servo.id, settings: servo.grip }));
+                }
+                match servo.grip_status {
+                    GripStatus::Idle => {}
+                    GripStatus::Closing => { ui.label("Closing..."); }
+                    GripStatus::Opening => { ui.label("Opening..."); }
+                    GripStatus::Holding { position, current_ma, contact } => {
+                        let text = format!("Holding at {} ({:.0} mA)", position, current_ma);
+                        if contact {
+                            palette.status_label(ui, Status::Ok, text);
+                        } else {
+                            palette.status_label(ui, Status::Warning, format!("{} - no contact", text));
+                        }
+                    }
+                }
+            });
+
+            egui::CollapsingHeader::new("Advanced").show(ui, |ui| {
+                // Servo remplacé : on repart de zéro, après confirmation
+                ui.horizontal(|ui| {
+                    if !servo.odometer_reset_armed {
+                        if ui.button("Reset odometer").clicked() {
+                            servo.odometer_reset_armed = true;
+                        }
+                    } else {
+                        palette.status_label(ui, Status::Warning, "Reset lifetime travel?");
+                        if ui.button("Confirm reset").clicked() {
+                            let _ = tx.send(Timed::new(SOURCE_CARD, AppCommand::ResetOdometer { id: servo.id }));
+                            servo.odometer_reset_armed = false;
+                        }
+                        if ui.button("Cancel").clicked() {
+                            servo.odometer_reset_armed = false;
+                        }
+                    }
+                });
+
+                egui::Grid::new("grip_settings").num_columns(2).show(ui, |ui| {
+                    ui.label("Grip current (mA):");
+                    ui.add(egui::DragValue::new(&mut servo.grip.current_threshold_ma).range(0.0..=3000.0));
+                    ui.end_row();
+
+                    ui.label("Hold time (ms):");
+                    let mut hold_ms = servo.grip.hold_time.as_millis() as u64;
+                    if ui.add(egui::DragValue::new(&mut hold_ms).range(0..=5000)).changed() {
+                        servo.grip.hold_time = Duration::from_millis(hold_ms);
+                    }
+                    ui.end_row();
+
+                    ui.label("Closed position:");
+                    ui.add(egui::DragValue::new(&mut servo.grip.closed_position).range(0..=4095));
+                    ui.end_row();
+
+                    ui.label("Open position:");
+                    ui.add(egui::DragValue::new(&mut servo.grip.open_position).range(0..=4095));
+                    ui.end_row();
+
+                    ui.label("Grip speed:");
+                    ui.add(egui::DragValue::new(&mut servo.grip.speed).range(1..=3400));
+                    ui.end_row();
+
+                    ui.label("Soft min / max:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut servo.limits.min).range(0..=4095));
+                        ui.add(egui::DragValue::new(&mut servo.limits.max).range(0..=4095));
                     });
-                }
-                
-                // Affichage de la position réelle (feedback)
-                ui.label(format!("(Real: {})", servo.current_pos));
+                    ui.end_row();
+
+                    ui.label("Slow down near limits:");
+                    ui.checkbox(&mut servo.limits.slowdown, "");
+                    ui.end_row();
+
+                    ui.label("Approach zone (ticks):");
+                    ui.add_enabled(servo.limits.slowdown, egui::DragValue::new(&mut servo.limits.approach_zone).range(0..=2048));
+                    ui.end_row();
+
+                    ui.label("Creep speed:");
+                    ui.add_enabled(servo.limits.slowdown, egui::DragValue::new(&mut servo.limits.creep_speed).range(1..=MAX_SPEED));
+                    ui.end_row();
+
+                    ui.label("Coordinated speed cap:");
+                    ui.add(egui::DragValue::new(&mut servo.speed_cap).range(1..=MAX_SPEED));
+                    ui.end_row();
+                });
             });
-            
-            // Barre de charge (Load)
-            let load_pct = (servo.load.abs() / 1000.0).clamp(0.0, 1.0);
-            ui.add(egui::ProgressBar::new(load_pct).text("Load"));
         });
 }
 
 // --- BACKEND (THREAD) ---
-fn servo_worker(state: Arc<Mutex<SharedState>>, rx: Receiver<AppCommand>, ctx: egui::Context) {
-    let mut driver_opt: Option<ST3215> = None;
+fn servo_worker(
+    state: Arc<Mutex<SharedState>>,
+    rx: Receiver<Timed<AppCommand>>,
+    responder: Responder,
+    ctx: egui::Context,
+    dry_run: Arc<AtomicBool>,
+    mut worker: ServoWorker,
+    shutdown: ShutdownSignal,
+) {
+    // Préhensions en cours, par ID
+    let mut grips: HashMap<u8, GripController> = HashMap::new();
+    let odometer_path = std::path::Path::new(ODOMETER_FILE);
+    let mut odometer = Odometer::load(odometer_path);
+    let mut odometer_saved = Instant::now();
+    // Adaptateur suivi pour le retrouver s'il change de chemin
+    worker.set_pinned(state.lock().unwrap().pin_port);
+    let mut choreography: Option<ChoreographyRun> = None;
+    let mut coordinated: Option<CoordinatedRun> = None;
+    let mut playback: Option<Playback> = None;
+    let mut teach: Option<TeachRun> = None;
+    // Étape due de la lecture par étapes, reprise en tête de file au cycle suivant
+    let mut sequence_step: Option<Timed<AppCommand>> = None;
+    // Déclassement thermique par servo ; servos coupés pour surchauffe par le worker
+    let curve: DeratingCurve = Config::load().derating;
+    worker.set_derating(curve.clone());
+    let mut deratings: HashMap<u8, Derating> = HashMap::new();
+    let thermal = worker.thermal();
+    // Dernière consigne de suivi envoyée, par suiveur
+    let mut follow_targets: HashMap<u8, u16> = HashMap::new();
+    // Manette lue à chaque cycle (gilrs se crée dans le thread qui la lit)
+    let mut pad = Gamepad::new();
+    let mut pad_controller = PadController::default();
+    let mut register_cache = RegisterCache::default();
+    let mut warmups: HashMap<u8, Warmup> = HashMap::new();
+    let mut poll_cycle = 0u32;
+    let mut telemetry_log: Option<TelemetryLog> = None;
+    // Servos dont la limite de couple et l'identité ont déjà été lues (une fois par détection)
+    let mut detection_read: HashSet<u8> = HashSet::new();
+    let session_start = Instant::now();
+    let recorder = worker.recorder();
+    let inversions = worker.inversions();
+    let poll_interval = Config::load().bus.poll_interval(POLL_INTERVAL);
+    // Premier mouvement de chaque servo gardé, à nouveau après chaque connexion ; mouvements en
+    // deux temps près des butées
+    let mut dispatcher = Dispatcher::new(Config::load().first_move.max_delta);
 
     loop {
-        // 1. Tentative de connexion si pas connecté
-        if driver_opt.is_none() {
-            if let Ok(mut driver) = ST3215::new(SERIAL_PORT) {
-                println!("Serial Open. Scanning 1-15...");
-                let mut detected_servos = BTreeMap::new();
-
-                // 2. SCAN INITIAL (1 à 15)
-                for id in 1..=MAX_SERVO_ID {
-                    // On essaie de lire la position pour voir si le servo existe
-                    if let Some(pos) = driver.read_position(id) {
-                        println!("Found Servo ID {}", id);
-                        let temp = driver.read_temperature(id).unwrap_or(0);
-                        let volt = driver.read_voltage(id).unwrap_or(0.0);
-                        
-                        // Création de l'état initial
-                        detected_servos.insert(id, IndividualServo {
-                            id,
-                            current_pos: pos,
-                            target_pos: pos, // IMPORTANT: Le slider commence à la position actuelle !
-                            temperature: temp,
-                            voltage: volt,
-                            load: 0.0,
-                            torque_on: false, // Par défaut souvent off au démarrage
-                        });
+        // Fenêtre fermée : la transaction précédente est finie, la file est abandonnée
+        if shutdown.is_requested() {
+            let (settings, ids): (ExitSettings, Vec<u8>) = {
+                let s = state.lock().unwrap();
+                (s.exit.clone(), s.servos.keys().copied().collect())
+            };
+            if let Some(driver) = worker.driver().filter(|_| !settings.is_noop()) {
+                for step in shutdown::park_and_release(driver, &ids, &settings) {
+                    match step.outcome {
+                        Ok(()) => println!("ID {}: {} on exit", step.id, step.action.label()),
+                        Err(e) => eprintln!("ID {}: {} on exit failed: {}", step.id, step.action.label(), e),
                     }
                 }
-
-                // Mise à jour de l'état partagé
+            }
+            if let Err(e) = odometer.save(odometer_path) {
+                eprintln!("Could not save odometer: {}", e);
+            }
+            recorder.stop();
+            return;
+        }
+        // Choix fait dans la fenêtre de conflit de port
+        let (port, port_choice, rescan) = {
+            let mut s = state.lock().unwrap();
+            // Dérogations échues : retour automatique aux sécurités
+            for o in s.overrides.expire(Instant::now()) {
+                log_override(&mut s.override_form, format!("{} expired and reverted", o.describe()));
+            }
+            worker.set_scan_range(s.scan_range.ids());
+            worker.set_rescan(s.rescan.clone());
+            worker.set_stall(s.stall.clone());
+            (s.port.clone(), s.port_choice.take(), std::mem::take(&mut s.rescan_requested))
+        };
+        // Nouveau port ou nouvelle plage : on repart d'une connexion neuve
+        worker.set_port(port.as_str());
+        if rescan {
+            worker.disconnect();
+            responder.new_epoch();
+            let mut s = state.lock().unwrap();
+            s.connected = false;
+            s.scan_progress = None;
+            s.remember_motion();
+            s.servos.clear();
+            detection_read.clear();
+        }
+        let mut force_lock = false;
+        match port_choice {
+            Some(ConflictChoice::SwitchPort(new_port)) => {
+                worker.disconnect();
+                responder.new_epoch();
                 let mut s = state.lock().unwrap();
-                s.connected = true;
-                s.servos = detected_servos;
-                driver_opt = Some(driver);
+                s.port = new_port;
+                s.scan_progress = None;
+                continue;
+            }
+            Some(ConflictChoice::TakeOver) => force_lock = true,
+            None => {}
+        }
+        // 1. Tentative de connexion si pas connecté (et si aucune autre instance ne tient le port)
+        let events = worker.maintain(force_lock);
+        let conflict = events.iter().find_map(|event| match event {
+            WorkerEvent::PortConflict(owner) => Some(owner.clone()),
+            _ => None,
+        });
+        if conflict.is_some() {
+            ctx.request_repaint();
+        }
+        state.lock().unwrap().port_conflict = conflict;
+        for event in events {
+            match event {
+                WorkerEvent::Connected { .. } => {
+                    // Les commandes restées en file pendant la coupure ne sont plus attendues par l'interface
+                    responder.new_epoch();
+                    dispatcher.arm_first_moves();
+                    // 2. SCAN INITIAL (plage configurée), étalé par le worker sur les cycles de la boucle
+                    let mut s = state.lock().unwrap();
+                    println!("Serial Open. Scanning {}...", s.scan_range);
+                    s.connected = true;
+                    s.remember_motion();
+                    s.scan_progress = worker.scan_progress();
+                }
+                WorkerEvent::PortMoved { from, to } => {
+                    let mut s = state.lock().unwrap();
+                    s.port = to.clone();
+                    s.events.push(Event::PortChanged { from, to });
+                }
+                _ => {}
             }
         }
 
         // 3. Boucle principale de communication
-        if let Some(ref mut driver) = driver_opt {
-            // A. Traitement des commandes UI (Move, Torque)
-            while let Ok(cmd) = rx.try_recv() {
+        let mut register_job: Option<RegisterJob> = None;
+        let mut offset_read: Option<Vec<u8>> = None;
+        let mut torque_limit_writes: Vec<(u8, TorqueLimit)> = Vec::new();
+        // Servos dont le déclassement a changé : limite de couple à réécrire
+        let mut rederated: Vec<u8> = Vec::new();
+        let mut sync_move: Option<Vec<(u8, u16, u16)>> = None;
+        let mut load_read: Option<Vec<u8>> = None;
+        let mut cancel_scan = false;
+        // Relevés du cycle pour le journal continu (charge complétée après sa lecture)
+        let mut log_frames: Vec<TelemetryFrame> = Vec::new();
+        if let Some(driver) = worker.driver() {
+            // A. Traitement des commandes UI (Move, Torque), après l'étape de séquence due
+            let mut queued: VecDeque<Timed<AppCommand>> = sequence_step.take().into_iter().chain(rx.try_iter().flat_map(fan_out)).collect();
+            // Consignes de la manette après celles de l'interface, soumises au même arrêt d'urgence
+            queued.extend(gamepad_commands(&state, &mut pad, &mut pad_controller));
+            // Arrêt d'urgence : traité avant la file, dont les consignes de mouvement sont abandonnées
+            let emergency = estop::take_emergency(
+                &mut queued,
+                |timed| matches!(timed.command, AppCommand::Servo(Command::EmergencyStop)),
+                |timed| {
+                    matches!(
+                        timed.command,
+                        AppCommand::Servo(Command::Move { .. })
+                            | AppCommand::Rotate { .. }
+                            | AppCommand::Grip { .. }
+                            | AppCommand::Release { .. }
+                            | AppCommand::CoordinatedMove { .. }
+                            | AppCommand::MoveGroup { .. }
+                            | AppCommand::Choreography(Some(_))
+                            | AppCommand::StartWarmup { .. }
+                            | AppCommand::PlaySequence { .. }
+                    )
+                },
+            );
+            if emergency {
+                let ids: Vec<u8> = state.lock().unwrap().servos.keys().copied().collect();
+                let stopped = dispatcher.execute(driver, &Command::EmergencyStop, |id| constraints_of(&state.lock().unwrap(), &deratings, &thermal, id), &ids);
+                let mut s = state.lock().unwrap();
+                if let Ok(Executed::Stopped { failures }) = stopped {
+                    for (id, e) in failures {
+                        s.record_outcome(id, "emergency stop torque off", Err(e));
+                    }
+                }
+                for &id in &ids {
+                    // Vitesse de roue remise à zéro : la roue ne repart pas à la réactivation du couple
+                    if s.servos.get(&id).is_some_and(|servo| servo.mode == ServoMode::Wheel) {
+                        s.record_outcome(id, "emergency stop wheel", driver.backend().rotate(id, 0));
+                    }
+                }
+                println!("EMERGENCY STOP: torque off on {:?}", ids);
+                grips.clear();
+                // Le suivi ne reprend pas tout seul à la réactivation du couple
+                follow_targets.clear();
+                for servo in s.servos.values_mut() {
+                    servo.follow.enabled = false;
+                }
+                choreography = None;
+                coordinated = None;
+                // Y compris une lecture demandée dans la file, abandonnée avec les autres consignes
+                if playback.take().is_some() || s.keyframes.playing {
+                    s.keyframes.playing = false;
+                    s.keyframes.status = Some((Status::Danger, "Stopped by the emergency stop".to_string()));
+                }
+                for id in warmups.drain().map(|(id, _)| id) {
+                    s.warmup.log.push(format!("ID {}: warm-up {}", id, WarmupEnd::Stopped.label()));
+                }
+                s.warmup.running.clear();
+                // L'enregistrement est gardé, le couple reste coupé
+                if let Some(run) = teach.take() {
+                    s.teach.trim = (0.0, run.sequence.duration().as_secs_f64());
+                    s.teach.recording = Some(run.sequence);
+                }
+                s.teach.active = false;
+                s.estop.trigger(ids);
+                for servo in s.servos.values_mut() {
+                    servo.torque_on = false;
+                    servo.emergency_stopped = true;
+                    servo.wheel_speed = 0;
+                }
+                s.sounds.notify(SoundClass::EmergencyStop);
+            }
+            // Consignes de slider en rafale : seule la dernière de chaque servo est écrite
+            let queued = coalesce::keep_latest(queued.into(), |timed: &Timed<AppCommand>| match timed.command {
+                AppCommand::Servo(Command::Move { id, .. }) => Some((id, ServoMode::Position)),
+                AppCommand::Rotate { id, .. } => Some((id, ServoMode::Wheel)),
+                _ => None,
+            });
+            for Timed { id: command_id, source, enqueued, command: cmd } in queued {
+                let dequeued = Instant::now();
+                let name = cmd.name();
+                log::debug!(
+                    target: logging::WORKER,
+                    "#{} from {} after {} µs in queue: {:?}",
+                    command_id,
+                    source,
+                    dequeued.saturating_duration_since(enqueued).as_micros(),
+                    cmd
+                );
+                recorder.command(source, &cmd);
+                let limits_of = |id: u8| state.lock().unwrap().limits_of(id);
+                let constraints = |id: u8| constraints_of(&state.lock().unwrap(), &deratings, &thermal, id);
                 match cmd {
-                    AppCommand::Move { id, position, speed } => {
-                        // On assume speed=0 pour vitesse max, time=0
-                        let _ = driver.move_to(id, position, speed, 50, false); // Accel à 50 arbitraire
+                    AppCommand::Servo(command @ Command::Move { id, position, .. }) => {
+                        // Une consigne manuelle annule la préhension en cours
+                        grips.remove(&id);
+                        // speed=0 : vitesse max
+                        let executed = dispatcher.execute(driver, &command, constraints, &[]);
+                        report_validation(&state, id, executed.as_ref().err());
+                        if let Some(servo) = state.lock().unwrap().servos.get_mut(&id) {
+                            servo.unconfirmed_move = match &executed {
+                                Err(ValidationError::LargeFirstMove { target, .. }) => Some(*target),
+                                _ => None,
+                            };
+                        }
+                        if let Ok(Executed::Moved { accepted, outcome, .. }) = executed {
+                            report_clamp(&state, id, position, &accepted);
+                            state.lock().unwrap().record_outcome(id, "move", outcome);
+                        }
+                    }
+                    AppCommand::SetMode { id, mode } => {
+                        // Le servo change de pilotage : préhension et approche en cours abandonnées
+                        grips.remove(&id);
+                        dispatcher.cancel_approach(id);
+                        if let Err(e) = state.lock().unwrap().operation.begin(id, "Mode change") {
+                            state.lock().unwrap().record_outcome(id, "mode", Err(e));
+                            continue;
+                        }
+                        let outcome = driver.set_mode(id, mode);
+                        let actual = match outcome {
+                            Ok(()) => Some(mode),
+                            Err(_) => driver.read_mode(id).and_then(ServoMode::from_register),
+                        };
+                        let position = driver.position(id);
+                        let mut s = state.lock().unwrap();
+                        s.operation.finish();
+                        s.record_outcome(id, "mode", outcome);
+                        if let Some(servo) = s.servos.get_mut(&id) {
+                            servo.mode = actual.unwrap_or(servo.mode);
+                            servo.wheel_speed = 0;
+                            // Le slider de position repart de la position atteinte par la roue
+                            if let Some(position) = position {
+                                servo.current_pos = position;
+                                servo.target_pos = position;
+                            }
+                        }
+                    }
+                    AppCommand::SetTorqueLimit { id, limit } => {
+                        if driver.dry_run() {
+                            log::info!(target: logging::DRY_RUN, "ID {}: torque limit {:.1}%", id, limit.percent());
+                            continue;
+                        }
+                        // Écrite en fin de cycle : le verrou reste pris jusque-là
+                        let mut s = state.lock().unwrap();
+                        match s.operation.begin(id, "Torque limit") {
+                            Ok(()) => {
+                                s.operation.progress("writing limit");
+                                torque_limit_writes.push((id, limit));
+                            }
+                            Err(e) => {
+                                s.record_outcome(id, "torque limit", Err(e));
+                            }
+                        }
+                    }
+                    AppCommand::Rotate { id, speed } => {
+                        // Une vitesse en retard sur un retour en mode position ne doit pas le défaire
+                        let wheel = state.lock().unwrap().servos.get(&id).is_some_and(|s| s.mode == ServoMode::Wheel);
+                        if wheel {
+                            let validated = validate_wheel_speed(&constraints(id), speed.into());
+                            report_validation(&state, id, validated.as_ref().err());
+                            if let Ok(speed) = validated {
+                                let outcome = driver.rotate(id, speed);
+                                state.lock().unwrap().record_outcome(id, "rotate", outcome);
+                            }
+                        }
+                    }
+                    AppCommand::Grip { id, settings } => {
+                        dispatcher.cancel_approach(id);
+                        let stopped = thermal.is_locked(id) || state.lock().unwrap().estop.is_stopped(id);
+                        if let Some(pos) = driver.read_position(id).filter(|_| !stopped) {
+                            let outcome = driver.enable_torque(id);
+                            state.lock().unwrap().record_outcome(id, "torque on before grip", outcome);
+                            grips.insert(id, GripController::close(settings, pos));
+                        }
+                    }
+                    AppCommand::Release { id, settings } => {
+                        let validated = validate_move(
+                            &constraints(id),
+                            settings.open_position.into(),
+                            settings.speed.into(),
+                            DEFAULT_ACCELERATION.into(),
+                        );
+                        report_validation(&state, id, validated.as_ref().err());
+                        if let Ok(m) = validated {
+                            let outcome = dispatcher.start_move(driver, &limits_of(id), id, m);
+                            state.lock().unwrap().record_outcome(id, "release", outcome);
+                            grips.insert(id, GripController::open(settings));
+                        }
+                    }
+                    AppCommand::CoordinatedMove { targets, duration } => {
+                        for (id, _, _) in &targets {
+                            grips.remove(id);
+                            dispatcher.cancel_approach(*id);
+                        }
+                        let plan = plan_group(driver, &state, constraints, targets, Some(duration));
+                        send_group(driver, &state, &mut sync_move, &plan.group, "coordinated move");
+                        coordinated = Some(CoordinatedRun {
+                            start: Instant::now(),
+                            planned: plan.planned,
+                            targets: plan.group.iter().map(|&(id, target, _)| (id, target)).collect(),
+                            arrivals: BTreeMap::new(),
+                            unread: plan.unread,
+                        });
+                    }
+                    AppCommand::MoveGroup { targets, sync } => {
+                        for (id, _, _) in &targets {
+                            grips.remove(id);
+                            dispatcher.cancel_approach(*id);
+                        }
+                        let plan = plan_group(driver, &state, constraints, targets, sync);
+                        send_group(driver, &state, &mut sync_move, &plan.group, "group move");
+                    }
+                    AppCommand::Choreography(Some(config)) => {
+                        for servo in &config.servos {
+                            grips.remove(&servo.id);
+                            dispatcher.cancel_approach(servo.id);
+                        }
+                        // Mise à jour en cours : on garde l'horloge de phase et les centres
+                        let run = choreography.get_or_insert_with(|| ChoreographyRun {
+                            config: config.clone(),
+                            clock: PhaseClock::default(),
+                            centers: HashMap::new(),
+                            last_tick: Instant::now(),
+                        });
+                        for servo in &config.servos {
+                            if let std::collections::hash_map::Entry::Vacant(e) = run.centers.entry(servo.id) {
+                                if let Some(pos) = driver.position(servo.id) {
+                                    e.insert(pos);
+                                }
+                            }
+                        }
+                        run.config = config;
+                    }
+                    AppCommand::Choreography(None) => {
+                        // Retour au centre de chaque servo
+                        if let Some(run) = choreography.take() {
+                            let mut s = state.lock().unwrap();
+                            for (&id, &center) in &run.centers {
+                                return_to(driver, &mut s, &deratings, &thermal, id, center, "return to center");
+                            }
+                        }
+                    }
+                    AppCommand::StartWarmup { ids, settings } => {
+                        // Un servo de la chorégraphie ou en surchauffe n'est pas échauffé
+                        let estop = state.lock().unwrap().estop.clone();
+                        let busy = |id: &u8| {
+                            thermal.is_locked(*id)
+                                || estop.is_stopped(*id)
+                                || choreography.as_ref().is_some_and(|run| run.config.servos.iter().any(|s| s.id == *id))
+                        };
+                        let now = Instant::now();
+                        for id in ids.into_iter().filter(|id| !busy(id)) {
+                            grips.remove(&id);
+                            dispatcher.cancel_approach(id);
+                            if let Some(center) = driver.position(id) {
+                                let outcome = driver.enable_torque(id);
+                                let mut s = state.lock().unwrap();
+                                s.record_outcome(id, "torque on before warm-up", outcome);
+                                if let Some(servo_state) = s.servos.get_mut(&id) {
+                                    servo_state.torque_on = true;
+                                }
+                                warmups.insert(id, Warmup::new(settings.clone(), center, now));
+                            }
+                        }
+                    }
+                    AppCommand::PlaySequence { sequence, looped, stepped } => {
+                        // Couple activé sur tous les servos de la séquence avant la première image
+                        let mut s = state.lock().unwrap();
+                        let mut missing = Vec::new();
+                        for id in sequence.ids() {
+                            if !s.servos.contains_key(&id) {
+                                missing.push(id);
+                                continue;
+                            }
+                            grips.remove(&id);
+                            dispatcher.cancel_approach(id);
+                            if thermal.is_locked(id) || s.estop.is_stopped(id) {
+                                continue;
+                            }
+                            if driver.enable_torque(id).is_ok() {
+                                if let Some(servo_state) = s.servos.get_mut(&id) {
+                                    servo_state.torque_on = true;
+                                }
+                            }
+                        }
+                        let name = if sequence.name.is_empty() { "sequence".to_string() } else { format!("'{}'", sequence.name) };
+                        s.keyframes.status = Some(if missing.is_empty() {
+                            (Status::Ok, format!("Playing {}", name))
+                        } else {
+                            (Status::Warning, format!("Playing {}; {} not detected, skipped", name, s.labels().list(missing)))
+                        });
+                        playback = Some(Playback::new(sequence, looped, stepped, Instant::now()));
+                    }
+                    AppCommand::PauseSequence(paused) => {
+                        if let Some(run) = playback.as_mut() {
+                            run.set_paused(paused, Instant::now());
+                        }
+                    }
+                    AppCommand::StopSequence => {
+                        let mut s = state.lock().unwrap();
+                        if playback.take().is_some() {
+                            s.keyframes.status = Some((Status::Ok, "Stopped".to_string()));
+                        }
+                        s.keyframes.playing = false;
+                    }
+                    AppCommand::StartTeach { ids, rate_hz } => {
+                        // Plus aucune consigne automatique : les articulations se manipulent à la main
+                        let mut s = state.lock().unwrap();
+                        if playback.take().is_some() {
+                            s.keyframes.playing = false;
+                            s.keyframes.status = Some((Status::Ok, "Stopped for teach mode".to_string()));
+                        }
+                        if choreography.take().is_some() {
+                            s.choreography.running = false;
+                        }
+                        for &id in &ids {
+                            grips.remove(&id);
+                            dispatcher.cancel_approach(id);
+                            if warmups.remove(&id).is_some() {
+                                s.warmup.running.remove(&id);
+                                s.warmup.log.push(format!("ID {}: warm-up {}", id, WarmupEnd::Stopped.label()));
+                            }
+                            s.record_outcome(id, "torque off for teach mode", driver.disable_torque(id));
+                            if let Some(servo_state) = s.servos.get_mut(&id) {
+                                servo_state.torque_on = false;
+                            }
+                        }
+                        let now = Instant::now();
+                        s.teach.status = Some((Status::Ok, format!("Teaching ID {:?}: move the joints by hand", ids)));
+                        teach = Some(TeachRun {
+                            ids,
+                            period: Duration::from_secs_f32(1.0 / rate_hz.max(1.0)),
+                            started: now,
+                            next_sample: now,
+                            sequence: KeyframeSequence { name: "taught".to_string(), keyframes: Vec::new() },
+                        });
+                    }
+                    AppCommand::StopTeach => {
+                        if let Some(run) = teach.take() {
+                            // Consigne = position actuelle avant de remettre le couple : rien ne saute
+                            let mut s = state.lock().unwrap();
+                            for &id in &run.ids {
+                                if thermal.is_locked(id) || s.estop.is_stopped(id) {
+                                    continue;
+                                }
+                                let Some(pos) = driver.position(id) else { continue };
+                                s.record_outcome(id, "hold after teach", sent(driver.move_to(id, pos, 0, DEFAULT_ACCELERATION, false)));
+                                let enabled = s.record_outcome(id, "torque on after teach", driver.enable_torque(id));
+                                if let Some(servo_state) = s.servos.get_mut(&id) {
+                                    servo_state.target_pos = pos;
+                                    servo_state.torque_on = enabled;
+                                }
+                            }
+                            let duration = run.sequence.duration().as_secs_f64();
+                            s.teach.status = Some((
+                                Status::Ok,
+                                format!("Recorded {} keyframes over {:.1} s", run.sequence.keyframes.len(), duration),
+                            ));
+                            s.teach.trim = (0.0, duration);
+                            s.teach.recording = Some(run.sequence);
+                        }
+                        state.lock().unwrap().teach.active = false;
+                    }
+                    AppCommand::StopWarmup => {
+                        let mut s = state.lock().unwrap();
+                        for (id, warmup) in warmups.drain() {
+                            return_to(driver, &mut s, &deratings, &thermal, id, warmup.center(), "warm-up return");
+                            s.warmup.log.push(format!("ID {}: warm-up {}", id, WarmupEnd::Stopped.label()));
+                        }
+                        s.warmup.running.clear();
+                    }
+                    AppCommand::CancelScan => cancel_scan = true,
+                    // Déjà traité avant la file, ou réparti sur les membres du groupe
+                    AppCommand::Servo(Command::EmergencyStop) | AppCommand::Group { .. } => {}
+                    AppCommand::Registers(job) => {
+                        // Traité hors de l'emprunt du driver (voir plus bas)
+                        register_job = Some(job);
                     }
-                    AppCommand::ToggleTorque { id, enable } => {
+                    AppCommand::ReadOffsets { ids } => offset_read = Some(ids),
+                    AppCommand::ResetOdometer { id } => {
+                        let key = state.lock().unwrap().odometer_key(id);
+                        odometer.reset(&key);
+                        if let Err(e) = odometer.save(odometer_path) {
+                            eprintln!("Could not save odometer: {}", e);
+                        }
+                    }
+                    AppCommand::Servo(Command::Torque { id, enable }) => {
+                        let what = if enable { "torque on" } else { "torque off" };
+                        // Pas de remise en couple tant que le servo n'est pas redescendu sous le réarmement
+                        let released = if enable { thermal.release(&curve, id) } else { Ok(()) };
+                        let outcome = match &released {
+                            Ok(()) => dispatcher
+                                .execute(driver, &Command::Torque { id, enable }, |id| constraints_of(&state.lock().unwrap(), &deratings, &thermal, id), &[])
+                                .map_or_else(|e| Err(e.to_string()), |done| done.outcome()),
+                            Err(e) => Err(e.clone()),
+                        };
+                        responder.send(command_id, what, Some(id), outcome.clone());
+                        let mut s = state.lock().unwrap();
                         if enable {
-                            let _ = driver.enable_torque(id);
+                            if let Some(servo_state) = s.servos.get_mut(&id) {
+                                servo_state.rejection = released.as_ref().err().cloned();
+                                servo_state.thermal_lockout = thermal.state(id);
+                            }
+                            // Un verrou pas encore levé est affiché comme refus, pas comme erreur de bus
+                            let enabled = released.is_ok() && s.record_outcome(id, what, outcome);
+                            // Réactivation explicite : lève l'arrêt d'urgence de ce servo
+                            if enabled {
+                                s.estop.release(id);
+                                if let Some(servo_state) = s.servos.get_mut(&id) {
+                                    servo_state.emergency_stopped = false;
+                                }
+                            } else if let Some(servo_state) = s.servos.get_mut(&id) {
+                                servo_state.torque_on = false;
+                            }
                         } else {
-                            let _ = driver.disable_torque(id);
+                            s.record_outcome(id, what, outcome);
+                        }
+                    }
+                }
+                let timing = CommandTiming { enqueued, dequeued, written: Instant::now() };
+                state.lock().unwrap().latency.record(source, name, &timing);
+            }
+
+            // Chorégraphie : consignes calculées à partir de la phase globale
+            if let Some(run) = choreography.as_mut() {
+                let now = Instant::now();
+                let phase = run.clock.advance(now - run.last_tick, run.config.period_s);
+                run.last_tick = now;
+                for servo in &run.config.servos {
+                    if let Some(&center) = run.centers.get(&servo.id) {
+                        let target = run.config.target(servo, center, phase);
+                        let limits = constraints_of(&state.lock().unwrap(), &deratings, &thermal, servo.id);
+                        if let Ok(m) = validate_move(&limits, target.into(), 0, 50) {
+                            let outcome = sent(driver.move_to(servo.id, m.position, m.speed, 50, false));
+                            state.lock().unwrap().record_outcome(servo.id, "choreography move", outcome);
+                        }
+                    }
+                }
+                state.lock().unwrap().choreography.phase = phase;
+            }
+
+            // Séquence d'images clés : consigne interpolée envoyée à chaque cycle ou, en lecture
+            // par étapes, chaque image due reprise en mouvement de groupe synchronisé
+            if let Some(run) = playback.as_mut() {
+                let running = if run.stepped {
+                    run.step(Instant::now()).map(|step| {
+                        sequence_step = step.map(|step| {
+                            let targets = step.targets.iter().map(|t| (t.id, t.position, t.speed.unwrap_or(0))).collect();
+                            Timed::new(SOURCE_SEQUENCE, AppCommand::MoveGroup { targets, sync: Some(step.duration) })
+                        });
+                    })
+                } else {
+                    run.tick(Instant::now()).map(|targets| {
+                        for target in targets {
+                            let limits = {
+                                let s = state.lock().unwrap();
+                                if !s.servos.contains_key(&target.id) {
+                                    continue;
+                                }
+                                constraints_of(&s, &deratings, &thermal, target.id)
+                            };
+                            let speed = target.speed.unwrap_or(0);
+                            if let Ok(m) = validate_move(&limits, target.position.into(), speed.into(), 50) {
+                                let outcome = sent(driver.move_to(target.id, m.position, m.speed, 50, false));
+                                state.lock().unwrap().record_outcome(target.id, "keyframe move", outcome);
+                            }
+                        }
+                    })
+                };
+                match running {
+                    Some(()) => {
+                        let mut s = state.lock().unwrap();
+                        s.keyframes.progress = run.progress();
+                        s.keyframes.paused = run.is_paused();
+                    }
+                    None => {
+                        playback = None;
+                        let mut s = state.lock().unwrap();
+                        s.keyframes.playing = false;
+                        s.keyframes.progress = 1.0;
+                        s.keyframes.status = Some((Status::Ok, "Sequence finished".to_string()));
+                        s.sounds.notify(SoundClass::Completion);
+                    }
+                }
+            }
+
+            // Apprentissage : positions relevées au rythme choisi
+            if let Some(run) = teach.as_mut() {
+                let now = Instant::now();
+                if now >= run.next_sample {
+                    run.next_sample = now + run.period;
+                    let positions: BTreeMap<u8, u16> =
+                        run.ids.iter().filter_map(|&id| driver.position(id).map(|pos| (id, pos))).collect();
+                    if !positions.is_empty() {
+                        let time_s = (now - run.started).as_secs_f64();
+                        run.sequence.keyframes.push(Keyframe { time_s, positions, speed: None, duration_s: None });
+                    }
+                    state.lock().unwrap().teach.samples = run.sequence.keyframes.len();
+                }
+            }
+
+            // Échauffement : oscillation autour du départ, arrêt à la cible, à la durée max ou sur défaut
+            if !warmups.is_empty() {
+                let now = Instant::now();
+                let mut ended = Vec::new();
+                let mut progress = BTreeMap::new();
+                for (&id, warmup) in warmups.iter_mut() {
+                    let servo = {
+                        let s = state.lock().unwrap();
+                        s.servos.get(&id).map(|servo| (servo.temperature, constraints_of(&s, &deratings, &thermal, id)))
+                    };
+                    let Some((temperature, limits)) = servo else {
+                        ended.push((id, WarmupEnd::Fault("servo lost".into()), None, false));
+                        continue;
+                    };
+                    // Oscillation refusée (blocage, arrêt d'urgence…) : pas de retour non plus
+                    let mut rejected = false;
+                    warmup.record_temperature(now, temperature);
+                    let end = if thermal.is_locked(id) {
+                        Some(WarmupEnd::Fault("overheat cut-off".into()))
+                    } else {
+                        warmup.finished(now)
+                    };
+                    let end = end.or_else(|| {
+                        let target = warmup.target(now);
+                        let m = match validate_move(&limits, target.into(), warmup.settings.speed.into(), 50) {
+                            Ok(m) => m,
+                            Err(e) => {
+                                rejected = true;
+                                return Some(WarmupEnd::Fault(e.to_string()));
+                            }
+                        };
+                        let answered = driver.move_to(id, m.position, m.speed, 50, false).is_some();
+                        (!warmup.reply(answered)).then(|| WarmupEnd::Fault("servo not responding".into()))
+                    });
+                    match end {
+                        Some(end) => ended.push((id, end, Some(temperature), !rejected)),
+                        None => {
+                            progress.insert(id, WarmupProgress {
+                                elapsed: warmup.elapsed(now),
+                                start_temperature: warmup.start_temperature(),
+                                temperature,
+                                rise_rate: warmup.rise_rate(),
+                            });
+                        }
+                    }
+                }
+
+                let mut s = state.lock().unwrap();
+                for (id, end, temperature, returns) in ended {
+                    let Some(warmup) = warmups.remove(&id) else { continue };
+                    // Retour au départ, sauf servo coupé pour surchauffe ou consigne refusée
+                    if returns && !thermal.is_locked(id) {
+                        return_to(driver, &mut s, &deratings, &thermal, id, warmup.center(), "warm-up return");
+                    }
+                    let temperatures = match (warmup.start_temperature(), temperature) {
+                        (Some(start), Some(end)) => format!(" ({}°C → {}°C)", start, end),
+                        _ => String::new(),
+                    };
+                    s.warmup.log.push(format!(
+                        "ID {}: warm-up {} after {}{}",
+                        id,
+                        end.label(),
+                        format_duration(warmup.elapsed(now).as_secs_f64()),
+                        temperatures
+                    ));
+                    s.sounds.notify(SoundClass::Completion);
+                }
+                s.warmup.running = progress;
+            }
+
+            // B. Préhensions : avance de la consigne en surveillant le courant
+            let now = Instant::now();
+            let mut grip_statuses = Vec::new();
+            let mut refused_grips = Vec::new();
+            for (&id, grip) in grips.iter_mut() {
+                if let (Some(pos), Some(current)) = (driver.read_position(id), driver.read_current(id)) {
+                    if let Some(target) = grip.update(pos, current, now) {
+                        let constraints = constraints_of(&state.lock().unwrap(), &deratings, &thermal, id);
+                        let validated = validate_move(&constraints, target.into(), grip.settings().speed.into(), 50);
+                        report_validation(&state, id, validated.as_ref().err());
+                        match validated {
+                            Ok(m) => {
+                                let outcome = sent(driver.move_to(id, m.position, m.speed, m.acceleration, false));
+                                state.lock().unwrap().record_outcome(id, "grip move", outcome);
+                            }
+                            // Préhension abandonnée : la consigne suivante serait refusée aussi
+                            Err(_) => refused_grips.push(id),
+                        }
+                    }
+                }
+                let status = if refused_grips.contains(&id) { GripStatus::Idle } else { grip.status() };
+                grip_statuses.push((id, status));
+            }
+            grips.retain(|id, grip| grip.status() != GripStatus::Idle && !refused_grips.contains(id));
+            {
+                let mut s = state.lock().unwrap();
+                for (id, status) in grip_statuses {
+                    if let Some(servo_state) = s.servos.get_mut(&id) {
+                        servo_state.grip_status = status;
+                    }
+                }
+            }
+
+            // Passage en vitesse lente à l'entrée de la zone d'approche
+            for (id, outcome) in dispatcher.advance_approaches(driver) {
+                state.lock().unwrap().record_outcome(id, "approach move", outcome);
+            }
+        }
+        if cancel_scan {
+            if let Some(run) = worker.cancel_scan() {
+                println!("Scan annulé à l'ID {} ({} servo(s) trouvé(s))", run.progress().0, run.found().len());
+            }
+        }
+
+        // Scan de connexion : un lot d'IDs par cycle, chaque servo trouvé a aussitôt sa carte. Hors
+        // scan, servos branchés en cours de session : quelques pings par cycle dans la plage de scan,
+        // ajoutés sans toucher aux cartes existantes
+        let known: BTreeSet<u8> = state.lock().unwrap().servos.keys().copied().collect();
+        let events = worker.scan_step(|id| known.contains(&id), false);
+        for event in events {
+            let Some(driver) = worker.driver() else { break };
+            match event {
+                WorkerEvent::ScanHits(hits) => {
+                    let detected: Vec<IndividualServo> = hits
+                        .into_iter()
+                        .filter_map(|id| {
+                            let pos = driver.read_position(id)?;
+                            println!("Found Servo ID {}", id);
+                            Some(detected_servo(driver, id, pos, &thermal))
+                        })
+                        .collect();
+                    let mut s = state.lock().unwrap();
+                    for mut servo in detected {
+                        // Un blocage non acquitté survit au rescan
+                        servo.stall = s.servos.get(&servo.id).and_then(|old| old.stall);
+                        s.restore_settings(&mut servo);
+                        if servo.duplicate_id {
+                            s.events.push(Event::AlertRaised { servo: Some(servo.id), message: DUPLICATE_ALERT.to_string() });
+                        }
+                        s.servos.insert(servo.id, servo);
+                    }
+                }
+                WorkerEvent::ScanDone { found, .. } => {
+                    // Les servos d'une connexion précédente qui n'ont pas répondu perdent leur carte
+                    state.lock().unwrap().servos.retain(|id, _| found.contains(id));
+                    println!("Scan terminé : {} servo(s)", found.len());
+                }
+                WorkerEvent::ServoFound(id) => {
+                    let Some(pos) = driver.read_position(id) else { continue };
+                    let mut servo = detected_servo(driver, id, pos, &thermal);
+                    let mut s = state.lock().unwrap();
+                    s.restore_settings(&mut servo);
+                    if servo.duplicate_id {
+                        s.events.push(Event::AlertRaised { servo: Some(id), message: DUPLICATE_ALERT.to_string() });
+                    }
+                    s.servos.entry(id).or_insert(servo);
+                    println!("Nouveau servo détecté : ID {}", id);
+                    s.events.push(Event::ServoFound { servo: id });
+                }
+                _ => {}
+            }
+        }
+        state.lock().unwrap().scan_progress = worker.scan_progress();
+
+        // C. Mise à jour des infos (Polling)
+        // Lectures sur le bus sans verrou : l'interface n'attend pas la fin du cycle
+        // Courant, vitesse et mouvement en alternance pour ne pas surcharger le bus
+        poll_cycle = poll_cycle.wrapping_add(1);
+        let (ids, overrides, odometer_keys, unstalled) = {
+            let s = state.lock().unwrap();
+            let ids: Vec<u8> = s
+                .servos
+                .values()
+                .filter(|servo| servo.presence.should_poll(poll_cycle) && !s.operation.pauses(servo.id))
+                .map(|servo| servo.id)
+                .collect();
+            let keys: HashMap<u8, String> = ids.iter().map(|&id| (id, s.odometer_key(id))).collect();
+            let unstalled: HashSet<u8> = s.servos.values().filter(|servo| servo.stall.is_none()).map(|servo| servo.id).collect();
+            (ids, s.overrides.clone(), keys, unstalled)
+        };
+        let read_current = poll_cycle.is_multiple_of(CURRENT_POLL_CYCLES);
+        let read_speed = poll_cycle.is_multiple_of(SPEED_POLL_CYCLES);
+        if poll_cycle.is_multiple_of(LOAD_POLL_CYCLES) {
+            load_read = Some(ids.clone());
+        }
+        let plan = PollPlan { temperature: true, voltage: true, current: read_current, speed: read_speed, moving: read_speed };
+        // Coupure thermique levée par dérogation ; pas de détection de blocage pour un servo déjà
+        // bloqué, ni pour une préhension qui pousse volontairement contre l'objet
+        let (readings, events) = worker.poll(&ids, plan, |id| Protection {
+            thermal: !overrides.is_active(OverrideKind::TemperatureCutoff, id),
+            stall: unstalled.contains(&id) && !grips.contains_key(&id),
+        });
+        if let Some(driver) = worker.driver() {
+            let time = session_start.elapsed().as_secs_f64();
+            log_frames = readings.iter().map(|r| r.frame(time)).collect();
+            let positions: HashMap<u8, u16> = readings.iter().filter_map(|r| r.position.map(|pos| (r.id, pos))).collect();
+            if let Some(run) = coordinated.as_mut() {
+                let now = Instant::now();
+                run.observe(&positions, now);
+                if run.is_over(now) {
+                    state.lock().unwrap().coordinated_report = Some(run.report());
+                    coordinated = None;
+                }
+            }
+
+            // Odomètre et déclassement : état propre au worker, toujours hors verrou
+            let mut derated: HashMap<u8, u8> = HashMap::new();
+            for reading in &readings {
+                let id = reading.id;
+                if let Some(pos) = reading.position {
+                    odometer.record(&odometer_keys[&id], pos);
+                }
+                let Some(temp) = reading.temperature else { continue };
+                let previous = deratings.get(&id).map_or(100, Derating::percent);
+                // Dérogation temporaire : plafond de vitesse et de couple levé
+                let percent = if overrides.is_active(OverrideKind::SpeedCap, id) {
+                    deratings.insert(id, Derating::default());
+                    100
+                } else {
+                    deratings.entry(id).or_default().update(&curve, temp)
+                };
+                if percent != previous {
+                    rederated.push(id);
+                }
+                derated.insert(id, percent);
+            }
+            // Coupure thermique (couple déjà coupé par le worker, une roue est arrêtée en plus) et
+            // blocages confirmés
+            let mut newly_cut: HashSet<u8> = HashSet::new();
+            let mut stalled = Vec::new();
+            for event in events {
+                match event {
+                    WorkerEvent::ThermalCutOff { id, outcome, .. } => {
+                        let mut s = state.lock().unwrap();
+                        s.record_outcome(id, "thermal cut-off torque off", outcome);
+                        if let Some(servo) = s.servos.get_mut(&id).filter(|servo| servo.mode == ServoMode::Wheel) {
+                            servo.wheel_speed = 0;
+                            s.record_outcome(id, "thermal cut-off wheel", driver.backend().rotate(id, 0));
                         }
+                        drop(s);
+                        newly_cut.insert(id);
+                        grips.remove(&id);
+                        dispatcher.cancel_approach(id);
                     }
+                    WorkerEvent::Stalled { id, stall } => stalled.push((id, stall)),
+                    _ => {}
                 }
             }
 
-            // B. Mise à jour des infos (Polling)
+            // Un seul verrou pour recopier le cycle dans l'état partagé
+            let mut went_offline = Vec::new();
             {
                 let mut s = state.lock().unwrap();
-                // On récupère la liste des IDs à mettre à jour
-                let ids: Vec<u8> = s.servos.keys().cloned().collect();
-                
-                for id in ids {
-                    if let Some(mut servo_state) = s.servos.get_mut(&id) {
-                        // Lecture position réelle
-                        if let Some(pos) = driver.read_position(id) {
-                            servo_state.current_pos = pos;
+                for frame in &mut log_frames {
+                    frame.delta = s
+                        .servos
+                        .get(&frame.servo)
+                        .filter(|servo| servo.mode != ServoMode::Wheel)
+                        .zip(frame.position)
+                        .map(|(servo, pos)| i32::from(servo.target_pos) - i32::from(pos));
+                    for (metric, value) in [
+                        (Metric::Position, frame.position.map(f64::from)),
+                        (Metric::Temperature, frame.temperature.map(f64::from)),
+                        (Metric::Voltage, frame.voltage.map(f64::from)),
+                        (Metric::Current, frame.current.map(f64::from)),
+                    ] {
+                        if let Some(value) = value {
+                            s.telemetry.observe(frame.servo, metric, frame.time, value);
+                        }
+                    }
+                }
+                for reading in readings {
+                    let id = reading.id;
+                    let Some(servo_state) = s.servos.get_mut(&id) else { continue };
+                    // Aucune réponse à aucune lecture : le servo ne répond plus du tout
+                    let changed = servo_state.presence.observe(reading.responded(), Instant::now());
+                    let online = servo_state.presence.is_online();
+                    if changed.is_some() {
+                        let message = "offline: no response".to_string();
+                        if online {
+                            println!("ID {}: de nouveau en ligne", id);
+                            s.events.push(Event::AlertCleared { servo: Some(id), message });
+                        } else {
+                            println!("ID {}: hors ligne", id);
+                            s.events.push(Event::AlertRaised { servo: Some(id), message });
+                            went_offline.push(id);
                         }
-                        // Lecture température/voltage/load (cycle court)
-                        if let Some(temp) = driver.read_temperature(id) {
-                            servo_state.temperature = temp;
+                    } else if online && reading.position.is_none() {
+                        s.record_outcome(id, "position read", Err("no response".to_string()));
+                    }
+                    if !online {
+                        continue;
+                    }
+                    let Some(servo_state) = s.servos.get_mut(&id) else { continue };
+                    if let Some(pos) = reading.position {
+                        if pos.abs_diff(servo_state.current_pos) > 2 {
+                            servo_state.moved_at = Instant::now();
                         }
-                        if let Some(volt) = driver.read_voltage(id) {
-                            servo_state.voltage = volt;
+                        servo_state.current_pos = pos;
+                        servo_state.odometer = odometer.get(&odometer_keys[&id]).cloned();
+                    }
+                    if let Some(temp) = reading.temperature {
+                        servo_state.temperature = temp;
+                        if let Some(&percent) = derated.get(&id) {
+                            servo_state.derating_percent = percent;
                         }
-                         if let Some(load) = driver.read_load(id) {
-                            servo_state.load = load as f32;
+                        if newly_cut.contains(&id) {
+                            servo_state.torque_on = false;
                         }
+                        servo_state.thermal_lockout = thermal.state(id);
+                    }
+                    if let Some(volt) = reading.voltage {
+                        servo_state.voltage = volt;
+                    }
+                    if let Some(current) = reading.current {
+                        servo_state.current = current;
+                    }
+                    if let Some(speed) = reading.speed {
+                        servo_state.speed = speed;
+                    }
+                    if let Some(moving) = reading.is_moving {
+                        servo_state.is_moving = moving;
                     }
                 }
             } // Release lock
+            // Plus de consigne automatique vers un servo qui ne répond plus
+            for id in went_offline {
+                grips.remove(&id);
+                dispatcher.cancel_approach(id);
+            }
+            for (id, stall) in stalled {
+                dispatcher.cancel_approach(id);
+                stop_stalled(driver, &state, id, stall);
+            }
+
+            // Suivi : chaque suiveur vise la position de sa source lue à ce cycle
+            let (followers, released) = {
+                let s = state.lock().unwrap();
+                let followers: Vec<(u8, FollowSettings, u16, u8)> = s.servos.values()
+                    .filter(|servo| servo.follow.enabled && servo.follow.source != servo.id && servo.presence.is_online() && servo.mode != ServoMode::Wheel)
+                    .map(|servo| (servo.id, servo.follow, servo.target_speed, servo.acceleration))
+                    .collect();
+                let released: Vec<u8> = follow_targets.keys().copied()
+                    .filter(|id| s.servos.get(id).is_some_and(|servo| !servo.follow.enabled))
+                    .collect();
+                (followers, released)
+            };
+            follow_targets.retain(|id, _| followers.iter().any(|(follower, ..)| follower == id) || released.contains(id));
+            // Suivi coupé : le servo s'arrête là où il est au lieu de finir la dernière consigne
+            for id in released {
+                follow_targets.remove(&id);
+                let Some(&pos) = positions.get(&id) else { continue };
+                let mut s = state.lock().unwrap();
+                let Some(servo) = s.servos.get_mut(&id) else { continue };
+                servo.target_pos = pos;
+                let (speed, acceleration) = (servo.target_speed, servo.acceleration);
+                if let Ok(m) = validate_move(&constraints_of(&s, &deratings, &thermal, id), pos.into(), speed.into(), acceleration.into()) {
+                    s.record_outcome(id, "follow hold", sent(driver.move_to(id, m.position, m.speed, m.acceleration, false)));
+                }
+            }
+            for (id, follow, speed, acceleration) in followers {
+                let Some(&source) = positions.get(&follow.source) else { continue };
+                let target = follow.target(source);
+                let validated = validate_move(&constraints_of(&state.lock().unwrap(), &deratings, &thermal, id), target.into(), speed.into(), acceleration.into());
+                report_validation(&state, id, validated.as_ref().err());
+                let Ok(m) = validated else { continue };
+                if !follow.should_send(follow_targets.get(&id).copied(), m.position) {
+                    continue;
+                }
+                follow_targets.insert(id, m.position);
+                let outcome = sent(driver.move_to(id, m.position, m.speed, m.acceleration, false));
+                let mut s = state.lock().unwrap();
+                if let Some(servo) = s.servos.get_mut(&id) {
+                    servo.target_pos = m.position;
+                    servo.moved_at = Instant::now();
+                }
+                s.record_outcome(id, "follow move", outcome);
+            }
+
+            if odometer_saved.elapsed() > ODOMETER_SAVE_INTERVAL {
+                odometer_saved = Instant::now();
+                if let Err(e) = odometer.save(odometer_path) {
+                    eprintln!("Could not save odometer: {}", e);
+                }
+            }
             
             ctx.request_repaint(); // Rafraichir l'UI
         } else {
-            // Pas de driver, on indique déconnecté
+            // Pas de driver (ou liaison perdue pendant la scrutation), on indique déconnecté ; le
+            // worker espace lui-même les tentatives de reconnexion
             let mut s = state.lock().unwrap();
+            if s.connected {
+                responder.new_epoch();
+                s.scan_progress = None;
+                s.events.push(Event::Disconnected { port: worker.port().to_string() });
+            }
             s.connected = false;
-            // On attend avant de réessayer
-            thread::sleep(Duration::from_secs(1));
         }
 
-        thread::sleep(Duration::from_millis(20));
+        if let Some(ids) = load_read {
+            // Le pilote ne lit que l'octet bas de la charge, sans le sens : lecture du registre complet
+            let loads: Vec<(u8, f32)> = worker
+                .with_bus(|bus| {
+                    Ok(ids.into_iter().filter_map(|id| bus.read(id, &PRESENT_LOAD).ok().map(|raw| (id, inversions.signed(id, raw as f32 * 0.1)))).collect())
+                })
+                .unwrap_or_default();
+            for frame in &mut log_frames {
+                frame.load = loads.iter().find(|(id, _)| *id == frame.servo).map(|(_, load)| *load);
+            }
+            let mut stalled = Vec::new();
+            {
+                let mut s = state.lock().unwrap();
+                for (id, load) in loads {
+                    s.telemetry.observe(id, Metric::Load, session_start.elapsed().as_secs_f64(), f64::from(load));
+                    if let Some(servo_state) = s.servos.get_mut(&id) {
+                        servo_state.load = load;
+                        let protection = Protection { stall: servo_state.stall.is_none() && !grips.contains_key(&id), ..Protection::default() };
+                        if let Some(WorkerEvent::Stalled { id, stall }) = worker.observe_load(id, load, protection) {
+                            stalled.push((id, stall));
+                        }
+                    }
+                }
+            }
+            if let Some(driver) = worker.driver() {
+                for (id, stall) in stalled {
+                    dispatcher.cancel_approach(id);
+                    stop_stalled(driver, &state, id, stall);
+                }
+            }
+        }
+
+        // Limites de couple : écritures demandées et limites déclassées (la limite réglée reste
+        // affichée, le registre en reçoit le pourcentage courant), puis lecture de la limite, du
+        // modèle et du firmware des servos nouvellement détectés
+        let (unread, derated_limits) = {
+            let s = state.lock().unwrap();
+            let unread: Vec<u8> = s.servos.keys().copied().filter(|id| !detection_read.contains(id)).collect();
+            let derated_limits: Vec<(u8, TorqueLimit)> = rederated
+                .iter()
+                .filter(|&&id| !dry_run.load(Ordering::Relaxed) && !torque_limit_writes.iter().any(|&(written, _)| written == id))
+                .filter_map(|&id| s.servos.get(&id).map(|servo| (id, servo.torque_limit.unwrap_or_default())))
+                .collect();
+            (unread, derated_limits)
+        };
+        let limit_written = !torque_limit_writes.is_empty();
+        let capped = |id: u8, limit: TorqueLimit| deratings.get(&id).map_or(limit, |d| d.cap_torque(limit));
+        if worker.is_connected() && (!torque_limit_writes.is_empty() || !derated_limits.is_empty() || !unread.is_empty()) {
+            let mut results: Vec<(u8, Result<TorqueLimit, String>)> = Vec::new();
+            // Lecture en échec : identité inconnue plutôt qu'absente
+            let mut identities: Vec<(u8, ServoIdentity)> = unread.iter().map(|&id| (id, ServoIdentity::default())).collect();
+            // Une seule tentative par détection, même si le port ne s'ouvre pas
+            detection_read.extend(&unread);
+            let mut derating_outcomes: Vec<(u8, Result<(), String>)> = Vec::new();
+            let opened = worker.with_bus(|bus| {
+                for &(id, limit) in &torque_limit_writes {
+                    let written = capped(id, limit).write(bus, id).map(|()| limit);
+                    results.push((id, written));
+                }
+                for &(id, limit) in &derated_limits {
+                    derating_outcomes.push((id, capped(id, limit).write(bus, id)));
+                }
+                for (id, identity) in &mut identities {
+                    *identity = ServoIdentity::read(bus, *id);
+                    results.push((*id, TorqueLimit::read(bus, *id)));
+                }
+                Ok(())
+            });
+            if let Err(e) = opened {
+                results.extend(torque_limit_writes.into_iter().map(|(id, _)| (id, Err(e.clone()))));
+                derating_outcomes.extend(derated_limits.iter().map(|&(id, _)| (id, Err(e.clone()))));
+            }
+            let mut s = state.lock().unwrap();
+            for (id, outcome) in derating_outcomes {
+                s.record_outcome(id, "torque derating", outcome);
+            }
+            for (id, identity) in identities {
+                if let Some(servo) = s.servos.get_mut(&id) {
+                    servo.identity = Some(identity);
+                }
+            }
+            for (id, result) in results {
+                let outcome = result.as_ref().map(|_| ()).map_err(Clone::clone);
+                s.record_outcome(id, "torque limit", outcome);
+                if let (Some(servo), Ok(limit)) = (s.servos.get_mut(&id), result) {
+                    servo.torque_limit = Some(limit);
+                    servo.torque_limit_input = limit.percent();
+                }
+            }
+            ctx.request_repaint();
+        }
+        // Écritures faites, ou perdues avec la connexion
+        if limit_written {
+            state.lock().unwrap().operation.finish();
+        }
+
+        if let Some(group) = sync_move {
+            // Sync write en trame brute, donc en positions brutes
+            let outcome = packet::sync_move_frame(&inversions.raw_targets(&group), COORDINATED_ACCELERATION)
+                .and_then(|frame| worker.with_backend(|bus| bus.send_raw(&frame)));
+            if let Err(e) = outcome {
+                eprintln!("Group move to {} servo(s) failed: {}", group.len(), e);
+                let message = format!("group move to {} servo(s): {}", group.len(), e);
+                state.lock().unwrap().events.push(Event::Error { servo: None, message });
+            }
+        }
+
+        if let Some(ids) = offset_read {
+            let offset = registers::find_register("Position Offset").expect("Position Offset register");
+            let read = worker.with_bus(|bus| Ok(ids.iter().filter_map(|&id| register_cache.read(bus, id, offset).map(|value| (id, value))).collect::<Vec<_>>()));
+            match read {
+                Ok(offsets) => state.lock().unwrap().position_offsets.extend(offsets),
+                Err(e) => eprintln!("Could not read position offsets: {}", e),
+            }
+            ctx.request_repaint();
+        }
+        if let Some(job) = register_job {
+            let simulate = dry_run.load(Ordering::Relaxed);
+            // Copie EEPROM : refusée pendant une autre écriture destructive
+            let copying = match job {
+                RegisterJob::Copy { to, .. } if !simulate => Some(state.lock().unwrap().operation.begin(to, "Register copy")),
+                _ => None,
+            };
+            let outcome = match &copying {
+                Some(Err(e)) => Err(e.clone()),
+                _ => worker.with_bus(|bus| Ok(match job {
+                    RegisterJob::Compare { a, b, refresh } => {
+                        if refresh {
+                            register_cache.forget(a);
+                            register_cache.forget(b);
+                        }
+                        let rows = regdiff::compare(bus, &mut register_cache, a, b);
+                        let status = format!("{} differing register(s)", rows.len());
+                        ((a, b), rows, status)
+                    }
+                    RegisterJob::Copy { from, to, register } => {
+                        let status = if simulate {
+                            format!("[dry run] {} not written", register.name)
+                        } else {
+                            match regdiff::copy(bus, &mut register_cache, from, to, register) {
+                                Ok(value) => format!("✓ {} = {} copied to ID {} and verified", register.name, value, to),
+                                Err(e) => format!("✗ {}", e),
+                            }
+                        };
+                        // Nouvelle comparaison, servie par le cache
+                        ((from, to), regdiff::compare(bus, &mut register_cache, from, to), status)
+                    }
+                })),
+            };
+
+            let mut s = state.lock().unwrap();
+            if copying.is_some_and(|begun| begun.is_ok()) {
+                s.operation.finish();
+            }
+            let compare = &mut s.register_compare;
+            compare.busy = false;
+            match outcome {
+                Ok((pair, rows, status)) => {
+                    compare.compared = Some(pair);
+                    compare.rows = rows;
+                    compare.status = Some(status);
+                }
+                Err(e) => compare.status = Some(format!("✗ {}", e)),
+            }
+            ctx.request_repaint();
+        }
+
+        // Journal continu : fichier ouvert, écrit et vidé hors du verrou
+        let (log_enabled, log_settings) = {
+            let s = state.lock().unwrap();
+            (s.log_enabled, s.log_settings.clone())
+        };
+        if let Some(outcome) = telemetrylog::sync(&mut telemetry_log, log_enabled, &log_settings, &log_frames) {
+            let mut s = state.lock().unwrap();
+            s.log_status = Some(match outcome {
+                Ok(status) => status,
+                Err(e) => {
+                    s.log_enabled = false;
+                    format!("✗ {}", e)
+                }
+            });
+            ctx.request_repaint();
+        }
+
+        thread::sleep(poll_interval);
+    }
+}
+
+// Groupe prêt à envoyer : consignes validées (id, position, vitesse), durée commune prévue et
+// servos dont la position n'a pas pu être lue
+struct GroupPlan {
+    group: Vec<(u8, u16, u16)>,
+    planned: Duration,
+    unread: Vec<u8>,
+}
+
+// Consignes (id, position, vitesse) d'un mouvement de groupe ou coordonné : validées, couple
+// activé, servos déjà en place écartés ; avec `sync`, vitesses recalculées pour une arrivée
+// simultanée en au moins cette durée, la vitesse donnée servant de plafond
+fn plan_group(
+    driver: &Driver,
+    state: &Arc<Mutex<SharedState>>,
+    constraints: impl Fn(u8) -> MoveConstraints,
+    targets: Vec<(u8, u16, u16)>,
+    sync: Option<Duration>,
+) -> GroupPlan {
+    let mut group = Vec::new();
+    let mut distances = Vec::new();
+    let mut unread = Vec::new();
+    for (id, target, speed) in targets {
+        let validated = validate_move(&constraints(id), target.into(), speed.into(), COORDINATED_ACCELERATION.into());
+        report_validation(state, id, validated.as_ref().err());
+        let Ok(m) = validated else { continue };
+        report_clamp(state, id, target, &m);
+        // Déjà en place : rien à envoyer, ni à compter dans la durée commune
+        let position = driver.position(id);
+        if position.is_some_and(|pos| pos.abs_diff(m.position) <= ARRIVAL_TOLERANCE) {
+            continue;
+        }
+        let outcome = driver.enable_torque(id);
+        state.lock().unwrap().record_outcome(id, "torque on before group move", outcome);
+        group.push((id, m.position, m.speed));
+        // Position illisible : trajet le plus long possible, pour ne pas arriver en avance
+        if position.is_none() {
+            unread.push(id);
+        }
+        distances.push((id, position.map_or(units::MAX_TICKS, |pos| pos.abs_diff(m.position)), m.speed));
+    }
+    let planned = match sync.filter(|_| !group.is_empty()) {
+        Some(duration) => {
+            let (planned, speeds) = coordinated_speeds(&distances, duration, COORDINATED_ACCELERATION);
+            for ((_, _, speed), (_, coordinated)) in group.iter_mut().zip(speeds) {
+                *speed = coordinated;
+            }
+            log::debug!(target: logging::WORKER, "synchronized group move: {} servos in {:.2} s", group.len(), planned.as_secs_f32());
+            planned
+        }
+        None => Duration::ZERO,
+    };
+    GroupPlan { group, planned, unread }
+}
+
+// Consignes d'un groupe : sync write en fin de cycle, ou mouvements simulés un par un en dry-run
+fn send_group(
+    driver: &Driver,
+    state: &Arc<Mutex<SharedState>>,
+    sync_move: &mut Option<Vec<(u8, u16, u16)>>,
+    group: &[(u8, u16, u16)],
+    what: &str,
+) {
+    if driver.dry_run() {
+        for &(id, position, speed) in group {
+            let outcome = sent(driver.move_to(id, position, speed, COORDINATED_ACCELERATION, false));
+            state.lock().unwrap().record_outcome(id, what, outcome);
+        }
+    } else if !group.is_empty() {
+        // Plusieurs mouvements de groupe dans le cycle : une seule trame, la consigne la plus
+        // récente l'emportant par servo
+        let pending = sync_move.get_or_insert_with(Vec::new);
+        pending.retain(|(id, _, _)| !group.iter().any(|(member, _, _)| member == id));
+        pending.extend_from_slice(group);
+    }
+}
+
+// Options de lancement : `--dry-run`, `--port CHEMIN`, `--pin-port`, `--scan DÉBUT-FIN`, `--replay FICHIER`
+struct LaunchOptions {
+    dry_run: bool,
+    port: String,
+    pin_port: bool,
+    scan_range: ScanRange,
+    simulation: Option<(Simulation, String)>,
+    replay: Option<Replay>,
+}
+
+impl LaunchOptions {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let value = |name: &str| args.iter().position(|a| a == name).map(|i| args.get(i + 1).ok_or(format!("{} expects a value", name)));
+        // Options de la ligne de commande, puis `[bus]` de la configuration
+        let bus = Config::load().bus;
+        BusConfig::check_baud(bus.baud)?;
+        let port = value("--port").transpose()?.cloned().unwrap_or(bus.port);
+        // Le bus simulé ou l'enregistrement rejoué remplace le port série
+        let replay = Replay::from_args(args)?;
+        if replay.is_some() && Simulation::from_args(args)?.is_some() {
+            return Err("--replay and --simulate cannot be combined".to_string());
+        }
+        let simulation = Simulation::launch(args)?;
+        let port = match (&simulation, &replay) {
+            (Some((_, port)), _) => port.clone(),
+            (None, Some(replay)) => replay.port(),
+            (None, None) => port,
+        };
+        Ok(Self {
+            dry_run: args.iter().any(|a| a == "--dry-run"),
+            port,
+            // Le bus simulé et le rejeu n'ont pas d'adaptateur à suivre : port épinglé
+            pin_port: args.iter().any(|a| a == "--pin-port") || bus.pin_port || simulation.is_some() || replay.is_some(),
+            scan_range: value("--scan").transpose()?.map(|raw| raw.parse()).transpose()?.unwrap_or(bus.scan),
+            simulation,
+            replay,
+        })
     }
 }
 
 fn main() -> Result<(), eframe::Error> {
+    let launch = match logging::setup(std::env::args().skip(1).collect()).and_then(|args| LaunchOptions::parse(&args)) {
+        Ok(launch) => launch,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([500.0, 800.0]),
@@ -282,6 +4569,6 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "Servo Control Panel",
         options,
-        Box::new(|cc| Ok(Box::new(MultiServoApp::new(cc)))),
+        Box::new(move |cc| Ok(Box::new(MultiServoApp::new(cc, launch)))),
     )
 }
\ No newline at end of file
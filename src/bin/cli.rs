@@ -1,50 +1,1064 @@
-use st3215::ST3215;
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
+use servo_control::assertions;
+use servo_control::backend::{SerialBackend, ServoBackend};
+use servo_control::backup::{self, ConfigDump, RestoreStatus};
+use servo_control::calibration;
+use servo_control::config::{self, BusConfig, Config};
+use servo_control::fdimport;
+use servo_control::idchange::{self, IdChangeOutcome};
+use servo_control::identity::ServoIdentity;
+use servo_control::ids::{self, Access};
+use servo_control::inversion::{InvertedBackend, Inversions, Mapping};
+use servo_control::limits::TorqueLimit;
+use servo_control::logging::{self, LoggedBackend};
+use servo_control::packet;
+use servo_control::regdiff::{self, RegisterCache};
+use servo_control::registers::{self, RegisterPort};
+use servo_control::portlock::{LockError, PortLock};
+use servo_control::poses::{PoseLibrary, POSES_FILE};
+use servo_control::sequence::Sequence;
+use servo_control::shell::{self, HISTORY_FILE, SHELL_COMMANDS};
+use servo_control::shutdown::{self, ExitAction, ShutdownSignal};
+use servo_control::snapshot::{self, Snapshot};
+use servo_control::units::{degrees_to_ticks, ticks_to_degrees};
+use servo_control::dryrun::Driver;
+use servo_control::validation::{validate_move, FirstMoveGuard, MoveConstraints, ValidationError};
+use std::collections::BTreeSet;
+use std::str::FromStr;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
+
+// Valeur d'une option `--nom valeur`, si présente
+fn flag_value<T: FromStr>(args: &[String], name: &str) -> Result<Option<T>, String> {
+    match args.iter().position(|a| a == name) {
+        Some(i) => {
+            let raw = args.get(i + 1).ok_or(format!("{} attend une valeur", name))?;
+            raw.parse().map(Some).map_err(|_| format!("Valeur invalide pour {}: {}", name, raw))
+        }
+        None => Ok(None),
+    }
+}
+
+// ID visé par `--nom` : 254 (diffusion) exige `--broadcast` et reste refusé pour l'EEPROM
+fn target_id(args: &[String], name: &str, access: Access) -> Result<Option<u8>, String> {
+    let broadcast = args.iter().any(|a| a == "--broadcast");
+    flag_value(args, name)?.map(|id| ids::check_target(id, access, broadcast)).transpose()
+}
+
+// Port série : `--port CHEMIN` (ex. pseudo-terminal de simserial), sinon `[bus] port` de la
+// configuration. `--baud` (ou `[bus] baud`) est vérifié au passage : le pilote ouvre toujours le
+// port à son débit standard.
+fn serial_port(args: &[String]) -> Result<String, String> {
+    let bus = Config::load().bus;
+    let baud: u32 = flag_value(args, "--baud")?.unwrap_or(bus.baud);
+    BusConfig::check_baud(baud)?;
+    Ok(flag_value(args, "--port")?.unwrap_or(bus.port))
+}
+
+// Pilote du port ; chaque transaction est journalisée avec son temps aller-retour (`--trace`)
+fn open_bus(port: &str) -> Result<LoggedBackend, String> {
+    SerialBackend::open(port).map(|servo| LoggedBackend::new(Box::new(servo)))
+}
+
+// Verrou d'instance du port ; `--force` passe outre une autre instance vivante
+fn lock_port(args: &[String], port: &str) -> Result<PortLock, String> {
+    if args.iter().any(|a| a == "--force") {
+        return PortLock::force(port).map_err(|e| format!("Verrou de {} impossible: {}", port, e));
+    }
+    PortLock::acquire(port).map_err(|e| match e {
+        LockError::Held(owner) => format!("{} est déjà utilisé par {} (--force pour passer outre)", port, owner),
+        LockError::Io(e) => format!("Verrou de {} impossible: {}", port, e),
+    })
+}
+
+// `--dry-run` : validations et messages identiques, sans écriture sur le bus
+fn dry_run(args: &[String]) -> bool {
+    args.iter().any(|a| a == "--dry-run")
+}
+
+// Ouvre le port (`--port`) après avoir pris son verrou, gardé tant que le servo est utilisé
+fn open_servo(args: &[String]) -> Result<(Driver, PortLock), Box<dyn std::error::Error>> {
+    let port = serial_port(args)?;
+    let lock = lock_port(args, &port)?;
+    let driver = Driver::new(open_bus(&port)?, Arc::new(AtomicBool::new(dry_run(args))));
+    Ok((driver, lock))
+}
+
+// Pilote qui commande en positions logiques : les servos inversés dans la configuration sont
+// mis en miroir autour de 2048, comme dans les interfaces
+fn open_configured_servo(args: &[String], config: &Config) -> Result<(Driver, PortLock), Box<dyn std::error::Error>> {
+    let port = serial_port(args)?;
+    let lock = lock_port(args, &port)?;
+    let inversions = Inversions::new();
+    for (&id, settings) in &config.servos {
+        inversions.set(id, settings.inverted);
+    }
+    let bus = InvertedBackend::new(Box::new(open_bus(&port)?), inversions);
+    Ok((Driver::new(bus, Arc::new(AtomicBool::new(dry_run(args)))), lock))
+}
+
+// Butées logicielles du servo enregistrées par les interfaces, en positions logiques
+fn move_constraints(config: &Config, id: u8) -> MoveConstraints {
+    MoveConstraints { limits: config.limits.get(&id).copied().unwrap_or_default(), ..Default::default() }
+}
+
+// Contraintes d'un mouvement soumis à la garde du premier mouvement, sauf --large. Chaque commande
+// est une nouvelle connexion, donc un premier mouvement ; le shell garde sa `FirstMoveGuard`.
+fn guarded_constraints(args: &[String], config: &Config, guard: &FirstMoveGuard, servo: &Driver, id: u8) -> MoveConstraints {
+    let first_move = match args.iter().any(|a| a == "--large") {
+        true => None,
+        false => guard.check(id, config.first_move.max_delta, || servo.read_position(id)),
+    };
+    MoveConstraints { first_move, ..move_constraints(config, id) }
+}
+
+// Refus de validation, avec le moyen de confirmer un premier mouvement de grande amplitude
+fn large_move_hint(error: ValidationError) -> String {
+    match error {
+        ValidationError::LargeFirstMove { .. } => format!("{} (--large pour confirmer)", error),
+        _ => error.to_string(),
+    }
+}
+
+// Contrôle de doublon après un scan : IDs aux réponses incohérentes
+fn probe_duplicates(servo: &Driver, servos: &[u8]) -> Vec<u8> {
+    servos.iter().copied().filter(|&id| ids::probe_duplicate(|| servo.read_position(id))).collect()
+}
+
+fn warn_duplicates(duplicates: &[u8]) {
+    for id in duplicates {
+        println!("/!\\ ID {} : doublon possible, plusieurs servomoteurs semblent répondre sous cet ID", id);
+    }
+}
+
+// scan : liste les servos présents sur le bus
+fn scan(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let (servo, _lock) = open_servo(args)?;
+    let servos = servo.list_servos();
+    println!("Servomoteurs connectés: {:?} (Total: {})", servos, servos.len());
+    warn_duplicates(&probe_duplicates(&servo, &servos));
+    // Modèle et firmware : registres hors des lectures du pilote
+    let mut bus = RegisterPort::new(servo.backend());
+    for &id in &servos {
+        println!("  {}", ServoIdentity::read(&mut bus, id).describe(id));
+    }
+    Ok(())
+}
+
+// Changement d'ID relu : le nouvel ID doit répondre et l'ancien plus. Le résultat affiché
+// donne les ID qui répondent réellement.
+fn change_id_verified(servo: &Driver, old_id: u8, new_id: u8) -> Result<IdChangeOutcome, String> {
+    let verdict = servo.change_id_checked(old_id, new_id).inspect_err(|e| println!("✗ ID {} → {}: {}", old_id, new_id, e))?;
+    let message = match verdict {
+        IdChangeOutcome::Done => format!("✓ ID vérifiée: {} (l'ID {} ne répond plus)", new_id, old_id),
+        IdChangeOutcome::PowerCycleRequired => format!(
+            "✓ ID {} écrite, mais l'ID {} répond encore : remettez le servomoteur sous tension",
+            new_id, old_id
+        ),
+        IdChangeOutcome::StillOldId => {
+            format!("✗ ID non modifiée : le servomoteur répond toujours en ID {}, pas en ID {}", old_id, new_id)
+        }
+        IdChangeOutcome::NoResponse => {
+            format!("✗ Ni l'ID {} ni l'ID {} ne répondent : vérifiez l'alimentation", old_id, new_id)
+        }
+    };
+    println!("{}", message);
+    match verdict.is_success() {
+        true => Ok(verdict),
+        false => Err(verdict.describe(old_id, new_id)),
+    }
+}
+
+// swap-ids A B : échange les ID de deux servos via un ID temporaire libre, avec retour arrière
+fn swap_ids(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let id = |index: usize| -> Result<u8, String> {
+        let raw = args.get(index).ok_or("Usage: swap-ids <ID A> <ID B>")?;
+        let id = raw.parse().map_err(|_| format!("ID invalide: {}", raw))?;
+        ids::check_target(id, Access::Eeprom, false)
+    };
+    let (a, b) = (id(0)?, id(1)?);
+    let (servo, _lock) = open_servo(args)?;
+    let detected = servo.list_servos();
+    let plan = idchange::swap_plan(a, b, &detected)?;
+    println!("Échange des ID {} et {} via l'ID temporaire {}", a, b, plan[0].1);
+
+    let outcome = idchange::run_swap(&plan, |from, to| {
+        change_id_verified(&servo, from, to).and_then(|verdict| match verdict {
+            // L'ancien ID répond encore : l'étape suivante le réutiliserait
+            IdChangeOutcome::Done => Ok(()),
+            _ => Err(verdict.describe(from, to)),
+        })
+    });
+    let rescanned = servo.list_servos();
+    println!("Servomoteurs connectés: {:?} (Total: {})", rescanned, rescanned.len());
+    match outcome {
+        Ok(()) => {
+            println!("✓ ID {} et {} échangés", a, b);
+            Ok(())
+        }
+        Err(e) => Err(format!("Échange annulé : {}", e).into()),
+    }
+}
+
+// Suite d'ID de `assign-ids` : `--ids 10,11,20` ou `--start N [--count N]`
+fn id_sequence(args: &[String]) -> Result<Vec<u8>, String> {
+    let list: Option<String> = flag_value(args, "--ids")?;
+    let start: Option<u8> = flag_value(args, "--start")?;
+    let count: Option<usize> = flag_value(args, "--count")?;
+    match (list, start) {
+        (Some(list), None) if count.is_none() => ids::parse_id_list(&list),
+        (None, Some(start)) => {
+            let start = ids::check_new_id(start)?;
+            let sequence: Vec<u8> = (start..=ids::MAX_SERVO_ID).take(count.unwrap_or(usize::MAX)).collect();
+            match count {
+                Some(count) if sequence.len() < count => {
+                    Err(format!("--start {} --count {} dépasse l'ID {}", start, count, ids::MAX_SERVO_ID))
+                }
+                _ => Ok(sequence),
+            }
+        }
+        _ => Err("Usage: assign-ids (--ids 10,11,20 | --start N [--count N])".to_string()),
+    }
+}
+
+// Ligne du récapitulatif de `assign-ids`
+fn assignment_row(old_id: u8, new_id: u8, outcome: &str) -> String {
+    format!("  {:>3} → {:<3}  {}", old_id, new_id, outcome)
+}
+
+// assign-ids (--ids 10,11,20 | --start N [--count N]) : un servo branché à la fois reçoit l'ID suivant
+fn assign_ids(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let sequence = id_sequence(args)?;
+    let (servo, _lock) = open_servo(args)?;
+    let mut summary = Vec::new();
+
+    println!("=== Attribution des ID : {:?} ===", sequence);
+    println!("Un seul servomoteur branché à la fois : débranchez le précédent avant de brancher le suivant.");
+    'sequence: for (index, &target) in sequence.iter().enumerate() {
+        loop {
+            println!("\nBranchez le servomoteur suivant (→ ID {}) puis appuyez sur Entrée (q pour quitter)", target);
+            let mut input = String::new();
+            if std::io::stdin().read_line(&mut input)? == 0 || input.trim().eq_ignore_ascii_case("q") {
+                break 'sequence;
+            }
+
+            // Scan répété jusqu'à trouver au moins un servo
+            let mut servos = servo.list_servos();
+            while servos.is_empty() {
+                println!("/!\\ Aucun servomoteur détecté, nouveau scan dans 1 s (Ctrl+C pour quitter)");
+                thread::sleep(Duration::from_secs(1));
+                servos = servo.list_servos();
+            }
+            let old_id = match servos.as_slice() {
+                [id] => *id,
+                _ => {
+                    println!("✗ {} servomoteurs répondent {:?} : n'en laissez qu'un branché", servos.len(), servos);
+                    continue;
+                }
+            };
+            let duplicates = probe_duplicates(&servo, &servos);
+            if !duplicates.is_empty() {
+                warn_duplicates(&duplicates);
+                println!("✗ Débranchez tous les servomoteurs sauf un");
+                continue;
+            }
+
+            // Servo de l'étape précédente resté branché : on ne le renumérote pas
+            if sequence[..index].contains(&old_id) {
+                println!("✗ ID {} vient d'être attribué : débranchez ce servomoteur et branchez le suivant", old_id);
+                continue;
+            }
+            if old_id == target {
+                println!("✓ Le servomoteur a déjà l'ID {}", target);
+                summary.push(assignment_row(old_id, target, "déjà attribué"));
+                break;
+            }
+            match change_id_verified(&servo, old_id, target) {
+                Ok(verdict) => {
+                    let row = match verdict {
+                        IdChangeOutcome::PowerCycleRequired => "écrit, remise sous tension nécessaire",
+                        _ => "vérifié",
+                    };
+                    summary.push(assignment_row(old_id, target, row));
+                    break;
+                }
+                Err(_) => println!("Rebranchez le servomoteur et réessayez"),
+            }
+        }
+    }
+
+    println!("\nRécapitulatif ({}/{} servomoteurs) :", summary.len(), sequence.len());
+    for row in &summary {
+        println!("{}", row);
+    }
+    Ok(())
+}
+
+// move --id N --pos TICKS [--speed N] [--large]
+fn move_servo(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let id = target_id(args, "--id", Access::Command)?.ok_or("--id est obligatoire")?;
+    let position: i64 = flag_value(args, "--pos")?.ok_or("--pos est obligatoire")?;
+    let speed: i64 = flag_value(args, "--speed")?.unwrap_or(300);
+    let config = Config::load();
+    let (servo, _lock) = open_configured_servo(args, &config)?;
+    let m = validate_move(&guarded_constraints(args, &config, &FirstMoveGuard::new(), &servo, id), position, speed, 50).map_err(large_move_hint)?;
+    if m.clamped {
+        println!("Consigne {} ramenée à {} (butées logicielles de l'ID {})", position, m.position, id);
+    }
+
+    servo.enable_torque(id)?;
+    match servo.move_to(id, m.position, m.speed, m.acceleration, false) {
+        Some(_) => {
+            println!("✓ ID {} envoyé en position {}", id, m.position);
+            Ok(())
+        }
+        None => Err(format!("Le servo {} n'a pas accepté la consigne", id).into()),
+    }
+}
+
+// copy-pos --to N (--from N | --pose NOM [--entry N] | --value TICKS | --deg DEGRÉS) [--speed N] [--yes]
+// [--large] [--force-mapping]
+fn copy_position(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let to = target_id(args, "--to", Access::Command)?.ok_or("--to est obligatoire")?;
+    let from = target_id(args, "--from", Access::Command)?;
+    let pose: Option<String> = flag_value(args, "--pose")?;
+    let value: Option<i64> = flag_value(args, "--value")?;
+    let degrees: Option<f32> = flag_value(args, "--deg")?;
+    let speed: i64 = flag_value(args, "--speed")?.unwrap_or(300);
+
+    let config = Config::load();
+    let (servo, _lock) = open_configured_servo(args, &config)?;
+
+    // Source, et servo dont la correspondance est comparée à celle de la destination
+    let (target, source, mapped_from) = match (from, pose, value, degrees) {
+        (Some(from), None, None, None) => {
+            let pos = servo.read_position(from).ok_or(format!("Servo {} ne répond pas", from))?;
+            (pos as i64, format!("ID {}", from), Some(from))
+        }
+        (None, Some(name), None, None) => {
+            let entry = target_id(args, "--entry", Access::Command)?.unwrap_or(to);
+            let library = PoseLibrary::load(std::path::Path::new(POSES_FILE))?;
+            let pose = library.get(&name).ok_or(format!("Pose '{}' absente de {}", name, POSES_FILE))?;
+            let pos = *pose.positions.get(&entry).ok_or(format!("La pose '{}' n'a pas d'entrée pour l'ID {}", name, entry))?;
+            (pos as i64, format!("pose '{}', ID {}", name, entry), Some(entry))
+        }
+        (None, None, Some(ticks), None) => (ticks, "valeur manuelle".to_string(), None),
+        (None, None, None, Some(deg)) => (degrees_to_ticks(deg) as i64, format!("{:.1}°", deg), None),
+        _ => return Err("Précisez exactement une source: --from, --pose, --value ou --deg".into()),
+    };
+
+    let m = validate_move(&guarded_constraints(args, &config, &FirstMoveGuard::new(), &servo, to), target, speed, 50).map_err(large_move_hint)?;
+    if m.clamped {
+        println!("Consigne {} ramenée à {} (butées logicielles de l'ID {})", target, m.position, to);
+    }
+    let (target, speed) = (m.position, m.speed);
+
+    let current = servo.read_position(to).ok_or(format!("Servo {} ne répond pas", to))?;
+    println!(
+        "Copie de position: {} → ID {}: {} → {} ({:+} ticks, {:.1}°) à la vitesse {}",
+        source,
+        to,
+        current,
+        target,
+        target as i32 - current as i32,
+        ticks_to_degrees(target),
+        speed
+    );
+
+    // Sens ou offset différents : la même position logique ne donne pas le même angle
+    let differences = match mapped_from.filter(|&from| from != to) {
+        Some(from) => servo_mapping(&servo, &config, from).differences(&servo_mapping(&servo, &config, to)),
+        None => Vec::new(),
+    };
+    let force_mapping = args.iter().any(|a| a == "--force-mapping");
+    for difference in &differences {
+        println!("/!\\ Correspondances différentes : {}", difference);
+    }
+    let yes = args.iter().any(|a| a == "--yes");
+    if !differences.is_empty() && !force_mapping {
+        if yes {
+            return Err("Correspondances différentes entre source et destination (--force-mapping pour copier quand même)".into());
+        }
+        if !confirm("Copier malgré les correspondances différentes ? (o/n)")? {
+            println!("Annulé");
+            return Ok(());
+        }
+    }
+    if !yes && !confirm("Confirmer ? (o/n)")? {
+        println!("Annulé");
+        return Ok(());
+    }
+
+    servo.enable_torque(to)?;
+    match servo.move_to(to, target, speed, 50, false) {
+        Some(_) => println!("✓ ID {} envoyé en position {}", to, target),
+        None => println!("✗ Le servo {} n'a pas accepté la consigne", to),
+    }
+    Ok(())
+}
+
+fn confirm(question: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    println!("{}", question);
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_lowercase() == "o")
+}
+
+// Sens configuré et offset lu sur le servo (absent s'il ne répond pas)
+fn servo_mapping(servo: &Driver, config: &Config, id: u8) -> Mapping {
+    let inverted = config.servos.get(&id).is_some_and(|settings| settings.inverted);
+    let offset = registers::find_register("Position Offset").and_then(|register| RegisterPort::new(servo.backend()).read(id, register).ok());
+    Mapping { inverted, offset }
+}
+
+// assert spec.toml : code de sortie non nul si une assertion échoue
+fn run_assertions(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let path = args.first().ok_or("Usage: assert <spec.toml>")?;
+    let spec = assertions::load_spec(std::path::Path::new(path))?;
+    let (servo, _lock) = open_servo(args)?;
+
+    println!(
+        "Échantillonnage de {} servo(s) pendant {} ms...",
+        spec.servo.len(),
+        spec.sample_duration_ms
+    );
+    let violations = assertions::run(servo.backend(), &spec);
+
+    if violations.is_empty() {
+        println!("✓ Toutes les assertions sont respectées");
+        Ok(())
+    } else {
+        for violation in &violations {
+            println!("✗ {}", violation);
+        }
+        println!("{} assertion(s) en échec", violations.len());
+        std::process::exit(1);
+    }
+}
+
+// snapshot capture --id N --label L --sequence seq.json --out snap.json
+// snapshot compare avant.json après.json
+fn run_snapshot(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    match args.first().map(String::as_str) {
+        Some("capture") => {
+            let id = target_id(args, "--id", Access::Command)?.ok_or("--id est obligatoire")?;
+            let label: String = flag_value(args, "--label")?.ok_or("--label est obligatoire")?;
+            let sequence_path: String = flag_value(args, "--sequence")?.ok_or("--sequence est obligatoire")?;
+            let out: String = flag_value(args, "--out")?.unwrap_or(format!("{}.json", label));
+
+            let sequence = Sequence::load(std::path::Path::new(&sequence_path))?;
+            if dry_run(args) {
+                return Err("snapshot capture mesure une réponse réelle : indisponible en --dry-run".into());
+            }
+            let config = Config::load();
+            let (servo, _lock) = open_configured_servo(args, &config)?;
+            println!("Capture '{}' sur ID {} ({} étapes)...", label, id, sequence.steps.len());
+            let snap = snapshot::capture(servo.backend(), id, &label, &sequence, &move_constraints(&config, id))?;
+            snap.save(std::path::Path::new(&out))?;
+            println!("✓ {} échantillons enregistrés dans {}", snap.samples.len(), out);
+            Ok(())
+        }
+        Some("compare") => {
+            let (Some(before), Some(after)) = (args.get(1), args.get(2)) else {
+                return Err("Usage: snapshot compare <avant.json> <après.json>".into());
+            };
+            let before = Snapshot::load(std::path::Path::new(before))?;
+            let after = Snapshot::load(std::path::Path::new(after))?;
+            if before.sequence != after.sequence {
+                println!("⚠ Les deux instantanés n'utilisent pas la même séquence");
+            }
+            println!("{:<18} {:>12} {:>12} {:>12}", "", before.label, after.label, "Δ");
+            for row in snapshot::compare(&before, &after) {
+                println!(
+                    "{:<18} {:>12.2} {:>12.2} {:>+12.2} {}",
+                    row.name, row.before, row.after, row.delta(), row.unit
+                );
+            }
+            Ok(())
+        }
+        _ => Err("Usage: snapshot capture|compare ...".into()),
+    }
+}
+
+// import-fd export.txt --id N [--apply all|groupe,registre,...] [--yes]
+// Sans --apply : aperçu des différences avec le servo
+fn import_fd(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let path = args.first().ok_or("Usage: import-fd <export.txt> --id N [--apply all|...]")?;
+    let id = target_id(args, "--id", Access::Eeprom)?.ok_or("--id est obligatoire")?;
+    let selection: Option<String> = flag_value(args, "--apply")?;
+    let import = fdimport::load(std::path::Path::new(path))?;
+
+    for skipped in &import.skipped {
+        println!("⚠ ligne {}: {} ignoré ({})", skipped.line, skipped.name, skipped.reason);
+    }
+
+    let port = serial_port(args)?;
+    let _lock = lock_port(args, &port)?;
+    let mut bus = RegisterPort::open(&port)?;
+    let dry_run = dry_run(args);
+    let diff = fdimport::diff(&mut bus, id, &import);
+
+    println!("{:<28} {:<14} {:>8} {:>8}", "Registre", "Groupe", "Fichier", "Servo");
+    for row in &diff {
+        let live = row.live.map(|v| v.to_string()).unwrap_or("?".into());
+        let marker = if row.changed() { "*" } else { "" };
+        println!("{:<28} {:<14} {:>8} {:>8} {}", row.register.name, row.register.group.label(), row.file, live, marker);
+    }
+
+    let Some(selection) = selection else {
+        println!("Aperçu seulement : --apply all (ou une liste de groupes/registres) pour écrire");
+        return Ok(());
+    };
+    let tokens: Vec<String> = selection.split(',').map(|t| t.trim().to_lowercase()).collect();
+    let selected = |entry: &fdimport::FdEntry| {
+        tokens.iter().any(|t| {
+            t == "all"
+                || t == &entry.register.group.label().to_lowercase()
+                || registers::find_register(t) == Some(entry.register)
+        })
+    };
+    // Seules les valeurs qui diffèrent sont écrites ; jamais les registres d'info ou de communication
+    let entries: Vec<&fdimport::FdEntry> = import.entries.iter()
+        .filter(|e| e.register.group.is_writable() && selected(e))
+        .filter(|e| diff.iter().any(|row| row.register == e.register && row.changed()))
+        .collect();
+    if entries.is_empty() {
+        println!("Rien à appliquer");
+        return Ok(());
+    }
+
+    println!("{} registre(s) à écrire sur ID {}", entries.len(), id);
+    if dry_run {
+        for entry in &entries {
+            println!("[dry-run] {} ← {}", entry.register.name, entry.value);
+        }
+        println!("[dry-run] rien n'a été écrit");
+        return Ok(());
+    }
+    if !args.iter().any(|a| a == "--yes") {
+        println!("Confirmer ? (o/n)");
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        if input.trim().to_lowercase() != "o" {
+            println!("Annulé");
+            return Ok(());
+        }
+    }
+
+    let mismatches = fdimport::apply(&mut bus, id, &entries)?;
+    if mismatches.is_empty() {
+        println!("✓ {} registre(s) écrits et vérifiés", entries.len());
+        Ok(())
+    } else {
+        for row in &mismatches {
+            println!("✗ {} : attendu {}, relu {:?}", row.register.name, row.file, row.live);
+        }
+        Err(format!("{} registre(s) non vérifiés", mismatches.len()).into())
+    }
+}
+
+// config path | config init [--user] [--force] : fichier de configuration lu, ou modèle commenté
+fn config_command(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    match args.first().map(String::as_str) {
+        Some("path") => {
+            let path = config::resolve_path();
+            let state = if path.exists() { "" } else { " (absent : valeurs par défaut)" };
+            println!("{}{}", path.display(), state);
+            println!("Recherche : ./{} puis {}", config::CONFIG_FILE, config::user_path().map_or("-".to_string(), |p| p.display().to_string()));
+            Ok(())
+        }
+        Some("init") => {
+            // `--user` : répertoire de configuration de l'utilisateur plutôt que le répertoire courant
+            let path = match args.iter().any(|a| a == "--user") {
+                true => config::user_path().ok_or("Répertoire de configuration introuvable ($XDG_CONFIG_HOME et $HOME absents)")?,
+                false => std::path::PathBuf::from(config::CONFIG_FILE),
+            };
+            if path.exists() && !args.iter().any(|a| a == "--force") {
+                return Err(format!("{} existe déjà (--force pour le remplacer)", path.display()).into());
+            }
+            if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+                std::fs::create_dir_all(dir)?;
+            }
+            std::fs::write(&path, config::template())?;
+            println!("✓ Modèle écrit dans {}", path.display());
+            Ok(())
+        }
+        _ => Err("Usage: config path | config init [--user] [--force]".into()),
+    }
+}
+
+// dump-config <fichier> --id N : sauvegarde de toute l'EEPROM (JSON, ou TOML selon l'extension)
+fn dump_config(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let path = args.first().ok_or("Usage: dump-config <fichier.json|.toml> --id N")?;
+    let id = target_id(args, "--id", Access::Command)?.ok_or("--id est obligatoire")?;
+    let port = serial_port(args)?;
+    let _lock = lock_port(args, &port)?;
+    let dump = ConfigDump::read(&mut RegisterPort::open(&port)?, id)?;
+    dump.save(std::path::Path::new(path))?;
+    println!("✓ {} registres de l'ID {} sauvegardés dans {}", dump.registers.len(), id, path);
+    Ok(())
+}
+
+// load-config <fichier> --id N : restauration vérifiée ; l'ID n'est écrit qu'avec `--include-id`,
+// et un fichier d'un autre modèle n'est accepté qu'avec `--force-model`
+fn load_config(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let path = args.first().ok_or("Usage: load-config <fichier> --id N [--include-id] [--force-model]")?;
+    let id = target_id(args, "--id", Access::Eeprom)?.ok_or("--id est obligatoire")?;
+    let include_id = args.iter().any(|a| a == "--include-id");
+    let dump = ConfigDump::load(std::path::Path::new(path))?;
+    let port = serial_port(args)?;
+    let _lock = lock_port(args, &port)?;
+
+    let mut bus = RegisterPort::open(&port)?;
+    backup::check_model(&mut bus, id, &dump, args.iter().any(|a| a == "--force-model"))
+        .map_err(|e| format!("{} (--force-model pour importer quand même)", e))?;
+    if dry_run(args) {
+        for (name, value) in &dump.registers {
+            println!("[dry-run] {} ← {}", name, value);
+        }
+        println!("[dry-run] rien n'a été écrit");
+        return Ok(());
+    }
+
+    let rows = backup::restore(&mut bus, id, &dump);
+    for row in &rows {
+        match &row.status {
+            // Changé plus bas
+            RestoreStatus::Skipped(_) if include_id && row.name == "ID" => {}
+            RestoreStatus::Unchanged => {}
+            RestoreStatus::Written => println!("✓ {} ← {}", row.name, row.value),
+            RestoreStatus::Skipped(reason) => println!("- {} ignoré ({})", row.name, reason),
+            RestoreStatus::Failed(e) => println!("✗ {} ← {} : {}", row.name, row.value, e),
+        }
+    }
+    let count = |status: RestoreStatus| rows.iter().filter(|r| r.status == status).count();
+    let failed = rows.iter().filter(|r| r.failed()).count();
+    println!("{} registre(s) écrits, {} déjà à jour, {} en échec", count(RestoreStatus::Written), count(RestoreStatus::Unchanged), failed);
+    drop(bus);
+
+    // L'ID en dernier, par le changement d'ID vérifié
+    if let Some(new_id) = dump.id().filter(|&new_id| include_id && new_id != id) {
+        let servo = Driver::new(open_bus(&port)?, Arc::new(AtomicBool::new(false)));
+        let detected = servo.list_servos();
+        ids::check_free_id(id, new_id, &detected, false)?;
+        change_id_verified(&servo, id, new_id)?;
+    }
+    match failed {
+        0 => Ok(()),
+        _ => Err(format!("{} registre(s) non restaurés", failed).into()),
+    }
+}
+
+// calibrate --id N : la pose actuelle (couple coupé, articulation posée à la main) devient 2048
+fn calibrate(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let id = target_id(args, "--id", Access::Eeprom)?.ok_or("Usage: calibrate --id N [--yes] [--dry-run]")?;
+    let port = serial_port(args)?;
+    let _lock = lock_port(args, &port)?;
+    let mut bus = RegisterPort::open(&port)?;
+    let centering = calibration::plan(&mut bus, id)?;
+    println!(
+        "ID {} : position actuelle {} → {} (correction de position {})",
+        id,
+        calibration::CENTER as i32 + centering.shift,
+        calibration::CENTER,
+        centering.offset
+    );
+    if dry_run(args) {
+        println!("[dry-run] rien n'a été écrit");
+        return Ok(());
+    }
+    if !args.iter().any(|a| a == "--yes") {
+        println!("Écrire la correction en EEPROM ? (o/n)");
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        if input.trim().to_lowercase() != "o" {
+            println!("Annulé");
+            return Ok(());
+        }
+    }
+    let position = calibration::apply(&mut bus, id, &centering)?;
+    println!("✓ Correction {} écrite, position relue : {}", centering.offset, position);
+
+    // Butées logicielles partagées avec les interfaces : exprimées dans la nouvelle référence
+    let mut config = Config::load();
+    if let Some(limits) = config.limits.get(&id).copied() {
+        let shifted = centering.limits(&limits);
+        config.limits.insert(id, shifted);
+        match config.save() {
+            Ok(()) => println!("Butées logicielles de l'ID {} : {}..{} → {}..{}", id, limits.min, limits.max, shifted.min, shifted.max),
+            Err(e) => println!("/!\\ Butées non mises à jour : {}", e),
+        }
+    }
+    Ok(())
+}
+
+// set-torque-limit <pourcentage> --id N : effort max du servo (registre RAM, perdu à la mise hors tension)
+fn set_torque_limit(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let usage = "Usage: set-torque-limit <0-100> --id N [--dry-run]";
+    let percent: f32 = args.first().ok_or(usage)?.parse().map_err(|_| usage)?;
+    if !(0.0..=100.0).contains(&percent) {
+        return Err(format!("limite de couple {} hors de 0-100 %", percent).into());
+    }
+    let id = target_id(args, "--id", Access::Command)?.ok_or("--id est obligatoire")?;
+    let limit = TorqueLimit::from_percent(percent);
+    let port = serial_port(args)?;
+    let _lock = lock_port(args, &port)?;
+    let mut bus = RegisterPort::open(&port)?;
+    let before = TorqueLimit::read(&mut bus, id)?;
+    if dry_run(args) {
+        println!("[dry-run] ID {} : limite de couple {:.1} % → {:.1} %", id, before.percent(), limit.percent());
+        return Ok(());
+    }
+    limit.write(&mut bus, id)?;
+    println!("✓ ID {} : limite de couple {:.1} % → {:.1} % (relue)", id, before.percent(), limit.percent());
+    Ok(())
+}
+
+// factory-reset --id N : toute l'EEPROM revient aux valeurs d'usine, ID compris (1).
+// Sans --yes, l'ID du servo doit être retapé pour confirmer.
+fn factory_reset(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let id = target_id(args, "--id", Access::Eeprom)?.ok_or("Usage: factory-reset --id N [--yes] [--force-id] [--dry-run]")?;
+    let port = serial_port(args)?;
+    let _lock = lock_port(args, &port)?;
+    let servo = open_bus(&port)?;
+    if !servo.ping_servo(id) {
+        return Err(format!("l'ID {} ne répond pas", id).into());
+    }
+    // Deux servos en ID 1 répondraient ensemble à chaque commande
+    if id != 1 && servo.ping_servo(1) && !args.iter().any(|a| a == "--force-id") {
+        return Err("un servo répond déjà en ID 1 : débranchez-le (ou --force-id)".into());
+    }
+    println!("/!\\ ID {} : toute l'EEPROM revient aux réglages d'usine (correction, butées, gains...).", id);
+    println!("    Le servo repassera en ID 1.");
+    if dry_run(args) {
+        println!("[dry-run] rien n'a été envoyé");
+        return Ok(());
+    }
+    if !args.iter().any(|a| a == "--yes") {
+        println!("Tapez l'ID du servo ({}) pour confirmer :", id);
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        if input.trim() != id.to_string() {
+            println!("Annulé");
+            return Ok(());
+        }
+    }
+    packet::factory_reset(&servo, id)?;
+    thread::sleep(Duration::from_millis(500));
+    match servo.ping_servo(1) {
+        true => println!("✓ Réglages d'usine restaurés : le servo répond en ID 1"),
+        false => println!("✓ Reset envoyé, mais l'ID 1 ne répond pas encore : remettez le servo sous tension puis lancez un scan"),
+    }
+    Ok(())
+}
+
+// compare A B : registres qui diffèrent entre deux servos (« ! » = groupe important)
+fn compare_registers(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let (Some(a), Some(b)) = (args.first(), args.get(1)) else {
+        return Err("Usage: compare <ID A> <ID B>".into());
+    };
+    let parse = |raw: &String| -> Result<u8, String> {
+        let id = raw.parse().map_err(|_| format!("ID invalide: {}", raw))?;
+        ids::check_target(id, Access::Command, false)
+    };
+    let (a, b) = (parse(a)?, parse(b)?);
+
+    let port = serial_port(args)?;
+    let _lock = lock_port(args, &port)?;
+    let mut bus = RegisterPort::open(&port)?;
+    let rows = regdiff::compare(&mut bus, &mut RegisterCache::default(), a, b);
+
+    if rows.is_empty() {
+        println!("✓ Aucune différence entre ID {} et ID {}", a, b);
+        return Ok(());
+    }
+    let show = |v: Option<i32>| v.map(|v| v.to_string()).unwrap_or("?".into());
+    println!("  {:<28} {:<14} {:>8} {:>8}", "Registre", "Groupe", format!("ID {}", a), format!("ID {}", b));
+    for row in &rows {
+        let marker = if row.register.group.is_important() { "!" } else { " " };
+        println!(
+            "{} {:<28} {:<14} {:>8} {:>8}",
+            marker, row.register.name, row.register.group.label(), show(row.a), show(row.b)
+        );
+    }
+    println!("{} registre(s) différent(s)", rows.len());
+    Ok(())
+}
+
+// --- SHELL ---
+
+// Complétion des commandes et des ID vus au dernier scan
+#[derive(Default)]
+struct ShellHelper {
+    ids: Vec<u8>,
+}
+
+impl Completer for ShellHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(shell::completions(&line[..pos], &self.ids))
+    }
+}
+
+impl Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ShellHelper {}
+impl Validator for ShellHelper {}
+impl Helper for ShellHelper {}
+
+// ID positionnel ; `--broadcast` sur la commande autorise l'ID 254 comme pour les sous-commandes
+fn positional_id(command: &[String], index: usize, access: Access) -> Result<u8, String> {
+    let raw = command.get(index).ok_or(format!("Usage: {} <ID> ...", command[0]))?;
+    let id = raw.parse().map_err(|_| format!("ID invalide: {}", raw))?;
+    ids::check_target(id, access, command.iter().any(|a| a == "--broadcast"))
+}
+
+fn positional<T: FromStr>(command: &[String], index: usize, name: &str) -> Result<T, String> {
+    let raw = command.get(index).ok_or(format!("{} est obligatoire", name))?;
+    raw.parse().map_err(|_| format!("Valeur invalide pour {}: {}", name, raw))
+}
+
+// Une commande du shell ; `known` reçoit les ID vus (scan, read), `torqued` les servos mis sous couple
+fn run_shell_command(
+    servo: &Driver,
+    command: &[String],
+    config: &Config,
+    first_moves: &mut FirstMoveGuard,
+    known: &mut Vec<u8>,
+    torqued: &mut BTreeSet<u8>,
+) -> Result<(), String> {
+    match command[0].as_str() {
+        "scan" => {
+            *known = servo.list_servos();
+            println!("Servomoteurs connectés: {:?} (Total: {})", known, known.len());
+            warn_duplicates(&probe_duplicates(servo, known));
+        }
+        "read" => {
+            let id = positional_id(command, 1, Access::Command)?;
+            let position = servo.position(id).ok_or(format!("Le servo {} ne répond pas", id))?;
+            if let Err(index) = known.binary_search(&id) {
+                known.insert(index, id);
+            }
+            let show = |v: Option<f32>, unit: &str| v.map(|v| format!("{:.1} {}", v, unit)).unwrap_or("?".into());
+            println!(
+                "ID {} : position {} ({:.1}°), {}, {}, {}, charge {}",
+                id,
+                position,
+                ticks_to_degrees(position),
+                servo.read_temperature(id).map(|t| format!("{}°C", t)).unwrap_or("?".into()),
+                show(servo.read_voltage(id), "V"),
+                show(servo.read_current(id), "mA"),
+                show(servo.read_load(id), "%")
+            );
+        }
+        // move ID POS [--speed N] [--large]
+        "move" => {
+            let id = positional_id(command, 1, Access::Command)?;
+            let position: i64 = positional(command, 2, "la position")?;
+            let speed: i64 = flag_value(command, "--speed")?.unwrap_or(300);
+            let constraints = guarded_constraints(command, config, first_moves, servo, id);
+            let m = validate_move(&constraints, position, speed, 50).map_err(large_move_hint)?;
+            first_moves.passed(id);
+            if m.clamped {
+                println!("Consigne {} ramenée à {} (butées logicielles de l'ID {})", position, m.position, id);
+            }
+            servo.enable_torque(id)?;
+            torqued.insert(id);
+            servo
+                .move_to(id, m.position, m.speed, m.acceleration, false)
+                .ok_or(format!("Le servo {} n'a pas accepté la consigne", id))?;
+            println!("✓ ID {} envoyé en position {}", id, m.position);
+        }
+        // torque ID on|off
+        "torque" => {
+            let id = positional_id(command, 1, Access::Command)?;
+            match command.get(2).map(String::as_str) {
+                Some("on") => {
+                    servo.enable_torque(id)?;
+                    torqued.insert(id);
+                }
+                Some("off") => {
+                    servo.disable_torque(id)?;
+                    torqued.remove(&id);
+                }
+                _ => return Err("Usage: torque <ID> on|off".to_string()),
+            }
+            println!("✓ Couple {} pour ID {}", command[2], id);
+        }
+        "help" => {
+            println!("Commandes : {}", SHELL_COMMANDS.join(", "));
+            println!("  scan                       servos présents sur le bus");
+            println!("  read <ID>                  position, température, tension, courant, charge");
+            println!("  move <ID> <POS> [--speed N] [--large]");
+            println!("  torque <ID> on|off");
+            println!("Plusieurs commandes par ligne avec « ; », Ctrl+D ou « exit » pour quitter");
+        }
+        other => return Err(format!("Commande inconnue: {} (« help » pour la liste)", other)),
+    }
+    Ok(())
+}
+
+// shell : invite persistante, le port reste ouvert (et verrouillé) entre les commandes
+fn run_shell(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::load();
+    let (servo, _lock) = open_configured_servo(args, &config)?;
+    let mut first_moves = FirstMoveGuard::new();
+    let mut editor = Editor::<ShellHelper, DefaultHistory>::new()?;
+    // Pas de scan au démarrage (plusieurs secondes) : `scan` et `read` alimentent la complétion
+    editor.set_helper(Some(ShellHelper::default()));
+    let _ = editor.load_history(HISTORY_FILE);
+    let mut torqued = BTreeSet::new();
+
+    println!("Shell servo-cli (« help » pour les commandes, Ctrl+D ou « exit » pour quitter)");
+    'shell: loop {
+        let line = match editor.readline("servo> ") {
+            Ok(line) => line,
+            // Ctrl+C abandonne la ligne en cours, sans quitter
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        if !line.trim().is_empty() {
+            let _ = editor.add_history_entry(line.as_str());
+        }
+        let known = &mut editor.helper_mut().expect("helper installé au démarrage").ids;
+        for command in shell::split_line(&line) {
+            if command[0] == "exit" {
+                break 'shell;
+            }
+            if let Err(e) = run_shell_command(&servo, &command, &config, &mut first_moves, known, &mut torqued) {
+                println!("✗ {}", e);
+            }
+        }
+    }
+
+    if let Err(e) = editor.save_history(HISTORY_FILE) {
+        eprintln!("{}: {}", HISTORY_FILE, e);
+    }
+    if config.cli.torque_off_on_exit {
+        for id in torqued {
+            match servo.disable_torque(id) {
+                Ok(()) => println!("Couple coupé pour ID {}", id),
+                Err(e) => println!("✗ ID {}: {}", id, e),
+            }
+        }
+    }
+    Ok(())
+}
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // `--log-level`, `--log-file` et `--trace` valent pour toutes les commandes
+    let args = logging::setup(std::env::args().skip(1).collect())?;
+    match args.first().map(String::as_str) {
+        Some("shell") => return run_shell(&args[1..]),
+        Some("copy-pos") => return copy_position(&args[1..]),
+        Some("assert") => return run_assertions(&args[1..]),
+        Some("snapshot") => return run_snapshot(&args[1..]),
+        Some("scan") => return scan(&args[1..]),
+        Some("move") => return move_servo(&args[1..]),
+        Some("import-fd") => return import_fd(&args[1..]),
+        Some("config") => return config_command(&args[1..]),
+        Some("dump-config") => return dump_config(&args[1..]),
+        Some("load-config") => return load_config(&args[1..]),
+        Some("calibrate") => return calibrate(&args[1..]),
+        Some("set-torque-limit") => return set_torque_limit(&args[1..]),
+        Some("factory-reset") => return factory_reset(&args[1..]),
+        Some("compare") => return compare_registers(&args[1..]),
+        Some("assign-ids") => return assign_ids(&args[1..]),
+        Some("swap-ids") => return swap_ids(&args[1..]),
+        _ => {}
+    }
+    let port = serial_port(&args)?;
+    let _lock = lock_port(&args, &port)?;
+    let dry_run = Arc::new(AtomicBool::new(dry_run(&args)));
+    // `--retry` : attendre la carte au lieu de s'arrêter sur l'erreur d'ouverture
+    let retry = args.iter().any(|a| a == "--retry");
+    // `--force-id` : accepter un nouvel ID déjà présent sur le bus (`--force` concerne le verrou du port)
+    let force_id = args.iter().any(|a| a == "--force-id");
+    let mut last_error: Option<String> = None;
+    let exit = Config::load().exit;
+
+    // Ctrl+C : le cycle en cours se termine, puis parcage et coupure selon `[exit]` ; un second
+    // Ctrl+C (pendant une question, par exemple) quitte immédiatement
+    let interrupted = ShutdownSignal::new();
+    let handler_signal = interrupted.clone();
+    ctrlc::set_handler(move || {
+        if handler_signal.is_requested() {
+            std::process::exit(130);
+        }
+        println!("\nArrêt demandé (Ctrl+C à nouveau pour quitter immédiatement)");
+        handler_signal.request();
+    })?;
+
     println!("=== Cogni-robot - Initialisation des servomoteurs ===");
+    println!("Branchez un seul servomoteur à la fois : les servos neufs sont tous en ID 1 et");
+    println!("répondraient ensemble à chaque commande. Débranchez tous les autres avant de changer l'ID.");
     println!("Appuyez sur Ctrl+C pour quitter\n");
 
     let mut last_servos: Vec<u8> = Vec::new();
+    let mut last_duplicates: Vec<u8> = Vec::new();
     let mut servo_connected = false;
 
-    loop {
+    while !interrupted.is_requested() {
         // Tentative de connexion/reconnexion à la carte
-        match ST3215::new("/dev/ttyACM0") {
+        match open_bus(&port).map(|s| Driver::new(s, dry_run.clone())) {
             Ok(servo) => {
                 if !servo_connected {
-                    println!("Carte de contrôle détectée sur COM3");
+                    println!("Carte de contrôle détectée sur {}", port);
                     servo_connected = true;
+                    last_error = None;
                 }
 
                 // Récupérer la liste des servomoteurs connectés
                 let servos = servo.list_servos();
                 println!("Détection des servomoteurs... {}", servos.len());
 
+                // Un servo débranché d'une paire en doublon ne change pas la liste : contrôle à chaque scan
+                let duplicates = probe_duplicates(&servo, &servos);
+
                 // Détecter les changements
-                if servos != last_servos {
+                if servos != last_servos || duplicates != last_duplicates {
                     if servos.is_empty() {
                         println!("/!\\ Aucun servomoteur détecté");
                     } else {
                         println!("Servomoteurs connectés: {:?} (Total: {})", servos, servos.len());
                         
+                        warn_duplicates(&duplicates);
                         // Proposer l'initialisation si un seul servo est connecté
-                        if servos.len() == 1 {
+                        if !duplicates.is_empty() {
+                            println!("/!\\ Débranchez tous les servomoteurs sauf un : l'ID ne peut pas être changé sur un doublon.");
+                        } else if servos.len() == 1 {
                             println!("\nUn seul servomoteur détecté (ID: {})", servos[0]);
                             println!("Voulez-vous changer son ID ? (o/n)");
                             
                             let mut input = String::new();
-                            if std::io::stdin().read_line(&mut input).is_ok() {
-                                if input.trim().to_lowercase() == "o" {
-                                    println!("Entrez la nouvelle ID (0-253):");
-                                    let mut id_input = String::new();
-                                    if std::io::stdin().read_line(&mut id_input).is_ok() {
-                                        if let Ok(new_id) = id_input.trim().parse::<u8>() {
-                                            match servo.change_id(servos[0], new_id) {
-                                                Ok(_) => println!("✓ ID changée avec succès: {} → {}\n", servos[0], new_id),
-                                                Err(e) => println!("✗ Erreur: {}\n", e),
+                            if std::io::stdin().read_line(&mut input).is_ok() && input.trim().to_lowercase() == "o" {
+                                println!("Entrez la nouvelle ID (0-253):");
+                                let mut id_input = String::new();
+                                if std::io::stdin().read_line(&mut id_input).is_ok() {
+                                    if let Ok(new_id) = id_input.trim().parse::<u8>() {
+                                        match ids::check_free_id(servos[0], new_id, &servos, force_id) {
+                                            Ok(new_id) => {
+                                                // Nouveau scan immédiat, réussite ou non : la liste affichée
+                                                // reflète les ID qui répondent réellement
+                                                let _ = change_id_verified(&servo, servos[0], new_id);
+                                                let rescanned = servo.list_servos();
+                                                println!("Servomoteurs connectés: {:?} (Total: {})\n", rescanned, rescanned.len());
+                                                last_servos = rescanned;
+                                                continue;
                                             }
+                                            Err(e) => println!("✗ Erreur: {}\n", e),
                                         }
                                     }
                                 }
@@ -55,9 +1069,17 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                     }
                     
                     last_servos = servos;
+                    last_duplicates = duplicates;
                 }
             }
-            Err(_) => {
+            Err(e) => {
+                if !retry {
+                    return Err(format!("Impossible d'ouvrir {}: {} (--retry pour attendre la carte)", port, e).into());
+                }
+                if last_error.as_ref() != Some(&e) {
+                    println!("/!\\ {}: {}", port, e);
+                    last_error = Some(e);
+                }
                 if servo_connected {
                     println!("/!\\ Carte de contrôle déconnectée");
                     servo_connected = false;
@@ -66,7 +1088,30 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
 
-        // Attendre avant la prochaine détection
-        thread::sleep(Duration::from_millis(1000));
+        // Attendre avant la prochaine détection, sans retarder un Ctrl+C
+        let next_scan = Instant::now() + Duration::from_millis(1000);
+        while Instant::now() < next_scan && !interrupted.is_requested() {
+            thread::sleep(Duration::from_millis(50));
+        }
+    }
+
+    if !exit.is_noop() && !last_servos.is_empty() {
+        match open_bus(&port) {
+            Ok(s) => {
+                let servo = Driver::new(s, dry_run.clone());
+                for step in shutdown::park_and_release(&servo, &last_servos, &exit) {
+                    let action = match step.action {
+                        ExitAction::Park => "parcage",
+                        ExitAction::Release => "couple coupé",
+                    };
+                    match step.outcome {
+                        Ok(()) => println!("ID {}: {}", step.id, action),
+                        Err(e) => println!("✗ ID {} ({}): {}", step.id, action, e),
+                    }
+                }
+            }
+            Err(e) => println!("✗ {}: {}", port, e),
+        }
     }
+    Ok(())
 }
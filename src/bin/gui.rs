@@ -1,23 +1,99 @@
 use eframe::egui;
-use egui_plot::{Line, Plot, PlotPoints};
-use st3215::ST3215;
+use egui_plot::{Legend, Line, LineStyle, Plot, PlotPoints, PlotUi};
+use servo_control::backup::{self, ConfigDump, RestoreStatus};
+use servo_control::calibration;
+use servo_control::estop::{self, EmergencyStop};
+use servo_control::jog::{self, JogStep, KeyRepeat};
+use servo_control::movequeue::{MoveQueue, QueueStep, QueuedMove};
+use servo_control::gamepad::{self, BindingTarget, CalibrationForm, Gamepad, GamepadSettings, PadCommand, PadController, PadStatus};
+use servo_control::events::{self, Event, EventKind, EventStore};
+use servo_control::history::{History, MAX_HISTORY, MIN_HISTORY};
+use servo_control::hotplug::{self, RescanSettings};
+use servo_control::motion::{acceleration_ticks_per_s2, estimate_move_duration, ticks_to_degrees_per_s2};
+use servo_control::ids;
+use servo_control::derating::{DeratingCurve, ThermalLockout};
+use servo_control::idchange::{self, check_id_change, IdChangeOutcome, PendingIdChanges};
+use servo_control::identity::ServoIdentity;
+use servo_control::inversion::{self, Inversions};
+use servo_control::latency::Timed;
+use servo_control::limits::{AngleLimits, SoftLimits, TorqueLimit};
+use servo_control::logging;
+use servo_control::oplock::OperationLock;
+use servo_control::packet;
+use servo_control::palette::{Action, PaletteState};
+use servo_control::recording::{self, Recorder, Replay};
+use servo_control::reference::{self, ReferenceData};
+use servo_control::response::{self, CommandId, Responder, Tracker};
+use servo_control::retry::{self, CommErrors};
+use servo_control::registers::{self, RegisterPort, PRESENT_LOAD, TORQUE_ENABLE};
+use servo_control::ports;
+use servo_control::portlock::{self, ConflictChoice, LockOwner};
+use servo_control::sequence::Sequence;
+use servo_control::shutdown::{self, ExitSettings, ShutdownSignal};
+use servo_control::sim::Simulation;
+use servo_control::config::{self, AlertConfig, BusConfig, Config, ServoSettings};
+use servo_control::snapshot::{self, Snapshot};
+use servo_control::sound::{SoundAlerts, SoundClass};
+use servo_control::stall::{self, Stall, StallAction, StallSettings};
+use servo_control::telemetrylog::{self, LogSettings, TelemetryLog};
+use servo_control::tuning::{self, PidGains, StepResponse};
+use servo_control::theme::{self, temperature_status, Status, Theme};
+use servo_control::units::{self, AngleDisplay};
+use servo_control::dryrun::Driver;
+use servo_control::validation::{validate_move, FirstMoveSettings, MoveConstraints, ValidationError};
+use servo_control::worker::{Command, Dispatcher, Executed, PollPlan, Protection, ServoWorker, Telemetry, WorkerEvent};
+use servo_control::report::{format_timestamp, Metric, SessionReport, SessionTelemetry};
+use servo_control::plugins::{MovingAverage, ProcessorRegistry, TelemetryFrame};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ops::RangeInclusive;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::sync::mpsc::{channel, Sender, Receiver};
-use std::thread;
-use std::time::{Duration, Instant};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-#[derive(Clone)]
+#[derive(Clone, Debug)]
 enum ServoCommand {
-    Move { id: u8, position: u16, speed: u16, acceleration: u8 },
-    EnableTorque { id: u8 },
-    DisableTorque { id: u8 },
-    ScanServos,
+    // Mouvement, couple et arrêt d'urgence, exécutés par le `Dispatcher` commun aux deux interfaces
+    Servo(Command),
+    // Consigne mise en file : envoyée quand les précédentes du servo sont atteintes
+    QueueMove { id: u8, position: u16, speed: u16, acceleration: u8, acknowledge_large: bool },
+    ClearQueue { id: u8 },
+    // Ping en diffusion, sauf `exhaustive` : balayage ID par ID
+    ScanServos { exhaustive: bool },
+    CancelScan,
     ChangeId { old_id: u8, new_id: u8 },
+    // Échange des ID de deux servos via un ID temporaire
+    SwapIds { a: u8, b: u8 },
+    // Trame brute de la console d'instructions (mode expert)
+    RawInstruction { frame: Vec<u8> },
+    // Éditeur de registres : lecture de toute la table, écriture relue d'un registre
+    ReadAllRegisters { id: u8 },
+    WriteRegister { id: u8, addr: u8, value: i32 },
+    // Sauvegarde de l'EEPROM dans un fichier, et restauration vérifiée
+    ExportConfig { id: u8, path: String },
+    ImportConfig { id: u8, path: String, include_id: bool, force_model: bool },
+    // La position actuelle (couple coupé) devient 2048 par la correction de position
+    SetCenter { id: u8 },
+    // Instruction RESET : EEPROM aux valeurs d'usine, ID compris (1), puis scan rapide
+    FactoryReset { id: u8 },
+    // Butées matérielles écrites en EEPROM puis relues
+    WriteAngleLimits { id: u8, limits: AngleLimits },
+    // Limite de couple (registre RAM) écrite puis relue
+    WriteTorqueLimit { id: u8, limit: TorqueLimit },
+    // Gains de la boucle de position : lecture, écriture relue, et essai de réponse indicielle
+    ReadPid { id: u8 },
+    WritePid { id: u8, gains: PidGains },
+    StepTest { id: u8 },
+    CaptureSnapshot { id: u8, label: String, sequence: Sequence, path: String },
+    // Ferme la connexion courante et ouvre `port`
+    Connect { port: String },
 }
 
 struct ServoData {
     position: Option<u16>,
     speed: Option<u16>,
+    // Charge en %, signée selon le sens de l'effort
     load: Option<f32>,
     voltage: Option<f32>,
     current: Option<f32>,
@@ -41,54 +117,566 @@ impl Default for ServoData {
     }
 }
 
-const PORT: &str = "/dev/ttyACM0";
+// Origine des commandes : interface, ou relance par le worker lui-même
+const SOURCE_UI: &str = "ui";
+const SOURCE_WORKER: &str = "worker";
+const SOURCE_GAMEPAD: &str = "gamepad";
+// Pause entre deux cycles du thread de monitoring, sauf `[bus] poll_interval_ms`
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// Marge au-delà de la durée estimée avant de signaler un blocage
+const STALL_MARGIN: Duration = Duration::from_millis(1000);
+// Délai d'une consigne en file au-delà de sa durée estimée, avant de vider la file
+const QUEUE_TIMEOUT_MARGIN: Duration = Duration::from_secs(5);
+const KEEP_ALIVE_REPAINT: Duration = Duration::from_secs(1);
+// Relecture périodique du registre de couple (cycles de 100 ms) : le port est rouvert à chaque fois
+const TORQUE_READ_CYCLES: u32 = 30;
+// Charge signée : registre lu hors du pilote, port rouvert à chaque lecture
+const LOAD_READ_CYCLES: u32 = 10;
+
+// Mouvement refusé par la garde du premier Move, en attente de confirmation
+#[derive(Clone, Copy)]
+struct PendingLargeMove {
+    id: u8,
+    position: u16,
+    speed: u16,
+    acceleration: u8,
+    // Position lue au refus, absente si le servo n'a pas répondu
+    current: Option<u16>,
+}
+
+// Éditeur de registres du servo sélectionné
+struct RegisterPanel {
+    // Servo dont la table a été lue, et valeurs (ou erreur de lecture) par adresse
+    id: Option<u8>,
+    values: BTreeMap<u8, Result<i32, String>>,
+    // Valeurs saisies, par adresse
+    edits: BTreeMap<u8, i32>,
+    // Écriture EEPROM en attente de confirmation : (ID, adresse, valeur)
+    pending_eeprom: Option<(u8, u8, i32)>,
+    status: Option<String>,
+    // Fichier de sauvegarde, et options de restauration
+    backup_path: String,
+    include_id: bool,
+    force_model: bool,
+    // ID retapé pour autoriser le retour aux réglages d'usine
+    reset_confirm: String,
+}
+
+impl Default for RegisterPanel {
+    fn default() -> Self {
+        Self {
+            id: None,
+            values: BTreeMap::new(),
+            edits: BTreeMap::new(),
+            pending_eeprom: None,
+            status: None,
+            backup_path: "servo_config.json".to_string(),
+            include_id: false,
+            force_model: false,
+            reset_confirm: String::new(),
+        }
+    }
+}
+
+// Réglage des gains PID du servo sélectionné
+#[derive(Default)]
+struct PidPanel {
+    // Servo dont les gains ont été lus (ou demandés), et gains relus sur le servo
+    id: Option<u8>,
+    read: Option<PidGains>,
+    input: PidGains,
+    status: Option<String>,
+    response: Option<StepResponse>,
+}
+
+// Alignement des traces de référence sur les traces live
+#[derive(Clone, Copy, PartialEq)]
+enum ReferenceAlign {
+    // Temps du fichier tel quel (secondes depuis le début de sa session)
+    Absolute,
+    // Début de la référence placé sur la commande choisie dans la timeline
+    Marker,
+}
+
+// Les traces de référence prennent les couleurs du thème après les cinq traces live
+const REFERENCE_TRACE_OFFSET: usize = 5;
+
+// Durée estimée et mesurée du dernier mouvement envoyé
+#[derive(Clone, Copy)]
+struct MoveTiming {
+    id: u8,
+    started: Instant,
+    estimated: Duration,
+    measured: Option<Duration>,
+    target: u16,
+    // Position relue à l'arrêt, pour l'écart final à la consigne
+    final_position: Option<u16>,
+}
+
+impl MoveTiming {
+    // Écart final (ticks, consigne − position atteinte)
+    fn final_error(&self) -> Option<i32> {
+        self.final_position.map(|pos| i32::from(self.target) - i32::from(pos))
+    }
+}
+
+// Valeurs affichées, à la précision de l'affichage : le monitoring ne redessine que si l'une change
+#[derive(PartialEq)]
+struct DisplayedState {
+    connected: bool,
+    reconnecting: bool,
+    scan_progress: Option<(u8, u8)>,
+    port_conflict: bool,
+    servo_ids: Vec<u8>,
+    duplicate_ids: Vec<u8>,
+    selected_servo: Option<u8>,
+    position: Option<u16>,
+    is_moving: Option<bool>,
+    temperature: Option<u8>,
+    // Centièmes de volt, comme l'affichage
+    voltage: Option<i32>,
+    move_measured: Option<Option<Duration>>,
+    operation_step: Option<String>,
+    events: u64,
+    // Valeurs dérivées du servo sélectionné, au centième comme l'affichage
+    derived: Vec<i64>,
+}
+
+impl DisplayedState {
+    fn of(state: &AppState) -> Self {
+        Self {
+            connected: state.connected,
+            reconnecting: state.reconnecting,
+            scan_progress: state.scan_progress,
+            port_conflict: state.port_conflict.is_some(),
+            servo_ids: state.servo_ids.clone(),
+            duplicate_ids: state.duplicate_ids.clone(),
+            selected_servo: state.selected_servo,
+            position: state.servo_data.position,
+            is_moving: state.servo_data.is_moving,
+            temperature: state.servo_data.temperature,
+            voltage: state.servo_data.voltage.map(|v| (v * 100.0).round() as i32),
+            move_measured: state.last_move_timing.map(|t| t.measured),
+            operation_step: state.operation.current().map(|op| op.step.clone()),
+            events: state.events.revision(),
+            derived: state
+                .selected_servo
+                .map(|id| state.processors.latest(id).map(|d| (d.value * 100.0).round() as i64).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
 
 struct AppState {
     connected: bool,
+    // Liaison perdue en cours de session : le worker tente de rouvrir le port
+    reconnecting: bool,
+    port_name: String,
+    // Ports série proposés dans la barre du haut (rafraîchis à la demande)
+    available_ports: Vec<String>,
+    // Ne jamais quitter le port configuré (plusieurs adaptateurs identiques)
+    pin_port: bool,
+    // Autre instance qui pilote le port, et choix fait dans la fenêtre de conflit
+    port_conflict: Option<LockOwner>,
+    port_choice: Option<ConflictChoice>,
     servo_ids: Vec<u8>,
+    // IDs aux réponses incohérentes au dernier scan : plusieurs servos semblent les partager
+    duplicate_ids: Vec<u8>,
     selected_servo: Option<u8>,
     servo_data: ServoData,
     new_id_input: String,
+    // Résultat du dernier changement d'ID (ou saisie refusée), affiché sous le champ
+    id_change_status: Option<String>,
+    // Autorise un nouvel ID déjà présent sur le bus
+    force_id_change: bool,
+    // IDs choisis pour l'échange, et résultat du dernier échange
+    swap_pair: (Option<u8>, Option<u8>),
+    swap_status: Option<String>,
     target_position: u16,
+    // Pas du jog au clavier (enregistré dans le fichier de configuration) et touche tenue
+    jog_step: JogStep,
+    jog_repeat: KeyRepeat,
+    // Unité d'affichage des positions, enregistrée dans le fichier de configuration
+    angle: AngleDisplay,
     target_speed: u16,
     acceleration: u8,
-    torque_enabled: bool,
-    position_history: Vec<(f64, f64)>,
-    temperature_history: Vec<(f64, f64)>,
+    // Couple relu sur le servo ; absent tant qu'aucune lecture n'a abouti
+    torque: HashMap<u8, bool>,
+    // Servos arrêtés par l'arrêt d'urgence, jusqu'à la réactivation de leur couple
+    estop: EmergencyStop,
+    // Coupure thermique (seuils `[derating]` du fichier de configuration) et servos verrouillés
+    derating: DeratingCurve,
+    thermal: ThermalLockout,
+    // Détection de blocage, et blocages non acquittés par ID
+    stall_settings: StallSettings,
+    stalls: HashMap<u8, Stall>,
+    // Liaisons de la manette (`[gamepad]`), et manette vue par le thread de monitoring
+    gamepad: GamepadSettings,
+    pad_status: PadStatus,
+    pad_calibration: CalibrationForm,
+    // Rescan périodique des IDs absents (`[rescan]` du fichier de configuration)
+    rescan: RescanSettings,
+    // Le bouton Scan balaie chaque ID au lieu du ping en diffusion
+    exhaustive_scan: bool,
+    // Scan en cours : (prochain ID, dernier ID)
+    scan_progress: Option<(u8, u8)>,
+    // Écart max (ticks) autorisé sans confirmation pour le premier Move, 0 = désactivé
+    first_move_guard: u16,
+    // Butées logicielles par ID, partagées avec `all` par le fichier de configuration
+    limits: BTreeMap<u8, SoftLimits>,
+    // Butées matérielles lues sur chaque servo au scan, saisie en cours (par ID) et confirmation
+    angle_limits: HashMap<u8, AngleLimits>,
+    angle_limits_input: Option<(u8, AngleLimits)>,
+    pending_angle_limits: Option<(u8, AngleLimits)>,
+    angle_limits_status: Option<String>,
+    // Limites de couple lues au scan, valeur du slider (par ID) et résultat de la dernière écriture
+    torque_limits: HashMap<u8, TorqueLimit>,
+    torque_limit_input: Option<(u8, f32)>,
+    torque_limit_status: Option<String>,
+    // Modèle et firmware lus au scan, par ID
+    identities: HashMap<u8, ServoIdentity>,
+    pid: PidPanel,
+    pending_large_move: Option<PendingLargeMove>,
+    // Consignes en file par servo, tenues par le thread de monitoring
+    queued_moves: BTreeMap<u8, usize>,
+    // Réglage du milieu en attente de confirmation, et résultat du dernier réglage
+    pending_center: Option<u8>,
+    center_status: Option<String>,
+    registers: RegisterPanel,
+    last_move_timing: Option<MoveTiming>,
+    // Move grisé tant que le mouvement précédent n'est pas terminé
+    wait_for_completion: bool,
+    position_history: History,
+    temperature_history: History,
+    load_history: History,
+    current_history: History,
+    voltage_history: History,
+    // Vitesse signée (pas/s), exportée avec les autres séries
+    speed_history: History,
+    // Points conservés par série (réglage enregistré dans le fichier de configuration)
+    history_samples: usize,
+    // Journal continu sur disque, tenu par le thread de monitoring
+    log_enabled: bool,
+    log_settings: LogSettings,
+    // Parcage et coupure du couple à la fermeture, appliqués par le thread de monitoring
+    exit: ExitSettings,
+    // Seuil (°C) de l'alerte de surchauffe
+    over_temperature: u8,
+    // Nom, vitesse/accélération et sens par servo (`[servos]` de la configuration)
+    servo_settings: BTreeMap<u8, ServoSettings>,
+    // Résultat du dernier « Save settings »
+    settings_status: Option<String>,
+    log_status: Option<String>,
     start_time: Instant,
-    command_sender: Sender<ServoCommand>,
+    command_sender: Sender<Timed<ServoCommand>>,
+    // Commandes dont le résultat est attendu, et notifications d'échec
+    commands: Tracker,
+    // Compteurs d'échecs de transaction par servo, tenus par le bus du thread de monitoring
+    comm_errors: CommErrors,
+    // Enregistrement de session, tenu par le bus du thread de monitoring
+    recorder: Recorder,
+    // Servos inversés : le bus du thread de monitoring convertit leurs positions en ticks bruts
+    inversions: Inversions,
+    // Rejeu d'un enregistrement (--replay) à la place du port série
+    replay: Option<Replay>,
+    // Timeline de session
+    events: EventStore,
+    show_timeline: bool,
+    timeline_kinds: Vec<EventKind>,
+    timeline_servo: Option<u8>,
+    annotation_input: String,
+    events_export_path: String,
+    events_export_status: Option<String>,
+    // Min/max et énergie par servo, pour le rapport de fin de session
+    telemetry: SessionTelemetry,
+    report_html: bool,
+    report_status: Option<String>,
+    // Traitements de télémétrie enregistrés dans main (valeurs dérivées affichées et exportées)
+    processors: ProcessorRegistry,
+    // Instant (s) sur lequel recentrer les graphiques après un clic dans la timeline
+    plot_focus: Option<f64>,
+    // Console d'instructions bas niveau, visible uniquement en mode expert (--expert)
+    expert_mode: bool,
+    // Répétition : aucune écriture sur le bus (partagé avec le thread de monitoring)
+    dry_run: Arc<AtomicBool>,
+    // Servos virtuels (--simulate) et port du bus simulé
+    simulation: Option<(Simulation, String)>,
+    console_instruction: u8,
+    console_target_id: u8,
+    console_params: String,
+    console_confirm: String,
+    console_result: Option<String>,
+    // Comparaison avant/après réglage mécanique
+    snapshot_label: String,
+    snapshot_sequence_path: String,
+    snapshot_paths: [String; 2],
+    snapshots: [Option<Snapshot>; 2],
+    snapshot_status: Option<String>,
+    sounds: SoundAlerts,
+    // Opération destructive en cours (une seule à la fois)
+    operation: OperationLock,
+    // Servos qui répondent encore sur leur ancien ID après un changement
+    id_changes: PendingIdChanges,
+    // Thème de couleurs, enregistré dans le fichier de configuration
+    theme: Theme,
+    // Export CSV et traces de référence superposées aux graphiques
+    csv_export_path: String,
+    reference_path: String,
+    references: Vec<ReferenceData>,
+    reference_align: ReferenceAlign,
+    reference_marker: Option<f64>,
+    reference_status: Option<String>,
 }
 
 impl Default for AppState {
     fn default() -> Self {
         let (tx, _) = channel();
+        let start_time = Instant::now();
         Self {
             connected: false,
+            reconnecting: false,
+            port_name: config::DEFAULT_PORT.to_string(),
+            available_ports: Vec::new(),
+            pin_port: false,
+            port_conflict: None,
+            port_choice: None,
             servo_ids: Vec::new(),
+            duplicate_ids: Vec::new(),
             selected_servo: None,
             servo_data: ServoData::default(),
             new_id_input: String::new(),
+            id_change_status: None,
+            force_id_change: false,
+            swap_pair: (None, None),
+            swap_status: None,
             target_position: 2048,
+            jog_step: JogStep::default(),
+            jog_repeat: KeyRepeat::default(),
+            angle: AngleDisplay::default(),
             target_speed: 1000,
             acceleration: 50,
-            torque_enabled: false,
-            position_history: Vec::new(),
-            temperature_history: Vec::new(),
-            start_time: Instant::now(),
+            torque: HashMap::new(),
+            estop: EmergencyStop::default(),
+            derating: DeratingCurve::default(),
+            thermal: ThermalLockout::default(),
+            stall_settings: StallSettings::default(),
+            gamepad: GamepadSettings::default(),
+            pad_status: PadStatus::default(),
+            pad_calibration: CalibrationForm::default(),
+            rescan: RescanSettings::default(),
+            exhaustive_scan: false,
+            scan_progress: None,
+            stalls: HashMap::new(),
+            first_move_guard: FirstMoveSettings::default().max_delta,
+            limits: BTreeMap::new(),
+            pending_large_move: None,
+            queued_moves: BTreeMap::new(),
+            pending_center: None,
+            angle_limits: HashMap::new(),
+            angle_limits_input: None,
+            pending_angle_limits: None,
+            angle_limits_status: None,
+            torque_limits: HashMap::new(),
+            torque_limit_input: None,
+            torque_limit_status: None,
+            identities: HashMap::new(),
+            pid: PidPanel::default(),
+            center_status: None,
+            registers: RegisterPanel::default(),
+            last_move_timing: None,
+            wait_for_completion: false,
+            position_history: History::default(),
+            temperature_history: History::default(),
+            load_history: History::default(),
+            current_history: History::default(),
+            voltage_history: History::default(),
+            speed_history: History::default(),
+            history_samples: MIN_HISTORY,
+            log_enabled: false,
+            log_settings: LogSettings::default(),
+            exit: ExitSettings::default(),
+            over_temperature: AlertConfig::default().over_temperature,
+            servo_settings: BTreeMap::new(),
+            settings_status: None,
+            log_status: None,
+            start_time,
             command_sender: tx,
+            commands: response::channel().1,
+            comm_errors: CommErrors::new(),
+            recorder: Recorder::new(),
+            inversions: Inversions::new(),
+            replay: None,
+            events: EventStore::new(start_time),
+            show_timeline: false,
+            timeline_kinds: EventKind::ALL.to_vec(),
+            timeline_servo: None,
+            annotation_input: String::new(),
+            events_export_path: "session_events.json".to_string(),
+            events_export_status: None,
+            telemetry: SessionTelemetry::default(),
+            report_html: false,
+            report_status: None,
+            processors: ProcessorRegistry::default(),
+            plot_focus: None,
+            expert_mode: false,
+            dry_run: Arc::new(AtomicBool::new(false)),
+            simulation: None,
+            console_instruction: st3215::INST_PING,
+            console_target_id: 1,
+            console_params: String::new(),
+            console_confirm: String::new(),
+            console_result: None,
+            snapshot_label: "before".to_string(),
+            snapshot_sequence_path: "test_move.json".to_string(),
+            snapshot_paths: ["before.json".to_string(), "after.json".to_string()],
+            snapshots: [None, None],
+            snapshot_status: None,
+            sounds: SoundAlerts::new(),
+            operation: OperationLock::default(),
+            id_changes: PendingIdChanges::default(),
+            theme: Theme::default(),
+            csv_export_path: "monitoring.csv".to_string(),
+            reference_path: String::new(),
+            references: Vec::new(),
+            reference_align: ReferenceAlign::Absolute,
+            reference_marker: None,
+            reference_status: None,
+        }
+    }
+}
+
+impl AppState {
+    /// Commande pour le thread de monitoring ; l'identifiant permet d'en suivre le résultat
+    fn send(&self, cmd: ServoCommand) -> CommandId {
+        let timed = Timed::new(SOURCE_UI, cmd);
+        let id = timed.id;
+        let _ = self.command_sender.send(timed);
+        id
+    }
+
+    fn set_history_samples(&mut self, samples: usize) {
+        self.history_samples = samples.clamp(MIN_HISTORY, MAX_HISTORY);
+        for history in [
+            &mut self.position_history,
+            &mut self.temperature_history,
+            &mut self.load_history,
+            &mut self.current_history,
+            &mut self.voltage_history,
+            &mut self.speed_history,
+        ] {
+            history.set_capacity(self.history_samples);
+        }
+        self.processors.set_history_capacity(self.history_samples);
+    }
+
+    /// Nom du servo suivi de son ID, pour tout affichage qui désigne un servo
+    fn label(&self, id: u8) -> String {
+        config::servo_label(id, &self.stored(id).name)
+    }
+
+    fn stored(&self, id: u8) -> ServoSettings {
+        self.servo_settings.get(&id).cloned().unwrap_or_default()
+    }
+
+    /// Plage de consigne du servo : butées logicielles, dans les butées matérielles lues au scan
+    /// (ramenées en positions logiques pour un servo inversé)
+    fn target_range(&self, id: u8) -> RangeInclusive<u16> {
+        let limits = self.limits.get(&id).copied().unwrap_or_default();
+        let inverted = self.stored(id).inverted;
+        let hardware = self.angle_limits.get(&id).map(|h| if inverted { h.mirrored() } else { *h });
+        limits.range_within(hardware.as_ref())
+    }
+
+    /// Pas de jog suivant, enregistré aussitôt
+    fn cycle_jog_step(&mut self) {
+        self.jog_step = self.jog_step.next();
+        let mut config = Config::load();
+        config.ui.jog_step = self.jog_step;
+        if let Err(e) = config.save() {
+            eprintln!("Could not save jog step: {}", e);
+        }
+    }
+
+    /// Sélection d'un servo : la vitesse et l'accélération enregistrées pour lui sont reprises
+    fn select(&mut self, id: u8) {
+        self.selected_servo = Some(id);
+        self.pending_large_move = None;
+        let stored = self.stored(id);
+        self.target_speed = stored.speed.unwrap_or(self.target_speed);
+        self.acceleration = stored.acceleration.unwrap_or(self.acceleration);
+    }
+
+    /// Réglages d'un servo (et butées logicielles) enregistrés aussitôt dans le fichier de configuration
+    fn save_servo(&mut self, id: u8, settings: ServoSettings) {
+        let mut config = Config::load();
+        config.set_servo(id, settings);
+        config.limits = self.limits.clone();
+        if let Err(e) = config.save() {
+            eprintln!("Could not save servo settings: {}", e);
         }
+        self.servo_settings = config.servos;
     }
 }
 
+// Options de lancement passées en ligne de commande
+struct LaunchOptions {
+    expert_mode: bool,
+    dry_run: bool,
+    pin_port: bool,
+    processors: ProcessorRegistry,
+    simulation: Option<(Simulation, String)>,
+    replay: Option<Replay>,
+    // `--port CHEMIN`, prioritaire sur `[bus] port`
+    port: Option<String>,
+}
+
 struct ServoGuiApp {
     state: Arc<Mutex<AppState>>,
+    palette: PaletteState,
+    // Demande d'arrêt du thread de monitoring, attendu à la fermeture
+    shutdown: ShutdownSignal,
+    monitor: Option<JoinHandle<()>>,
 }
 
 impl ServoGuiApp {
-    fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let (tx, rx) = channel::<ServoCommand>();
-        let mut default_state = AppState::default();
-        default_state.command_sender = tx;
+    fn new(cc: &eframe::CreationContext<'_>, options: LaunchOptions) -> Self {
+        let (tx, rx) = channel::<Timed<ServoCommand>>();
+        let (responder, commands) = response::channel();
+        let config = Config::load();
+        let mut default_state = AppState {
+            command_sender: tx,
+            commands,
+            theme: config.ui.theme,
+            angle: config.ui.angle,
+            jog_step: config.ui.jog_step,
+            log_settings: config.logging.clone(),
+            exit: config.exit.clone(),
+            over_temperature: config.alerts.over_temperature,
+            servo_settings: config.servos.clone(),
+            limits: config.limits.clone(),
+            derating: config.derating.clone(),
+            stall_settings: config.stall.clone(),
+            gamepad: config.gamepad.clone(),
+            rescan: config.rescan.clone(),
+            first_move_guard: config.first_move.max_delta,
+            exhaustive_scan: false,
+            scan_progress: None,
+            expert_mode: options.expert_mode,
+            dry_run: Arc::new(AtomicBool::new(options.dry_run)),
+            // Le bus simulé et le rejeu n'ont pas d'adaptateur à suivre : port épinglé
+            pin_port: options.pin_port || config.bus.pin_port || options.simulation.is_some() || options.replay.is_some(),
+            port_name: match (&options.simulation, &options.replay) {
+                (Some((_, port)), _) => port.clone(),
+                (None, Some(replay)) => replay.port(),
+                (None, None) => options.port.unwrap_or_else(|| config.bus.port.clone()),
+            },
+            simulation: options.simulation,
+            replay: options.replay,
+            processors: options.processors,
+            available_ports: ports::list_ports(),
+            ..Default::default()
+        };
+        default_state.set_history_samples(config.ui.history_samples);
         let state = Arc::new(Mutex::new(default_state));
         
         // Configure le style moderne
@@ -97,41 +685,378 @@ impl ServoGuiApp {
         style.visuals.window_shadow.blur = 20;
         style.spacing.item_spacing = egui::vec2(8.0, 8.0);
         cc.egui_ctx.set_style(style);
+        theme::apply(config.ui.theme, &cc.egui_ctx);
         
-        // Thread de monitoring
+        // Thread de monitoring, sur le port série configuré ou sur l'enregistrement rejoué
+        let worker = {
+            let mut state = state.lock().unwrap();
+            let mut worker = match &state.replay {
+                Some(replay) => ServoWorker::with_connector(state.port_name.clone(), state.dry_run.clone(), replay.connector()),
+                None => ServoWorker::new(state.port_name.clone(), state.dry_run.clone()),
+            };
+            worker.set_retry(config.retry.clone());
+            worker.set_pinned(state.pin_port);
+            worker.set_derating(state.derating.clone());
+            state.thermal = worker.thermal();
+            state.comm_errors = worker.comm_errors();
+            state.recorder = worker.recorder();
+            state.inversions = worker.inversions();
+            for (&id, settings) in &config.servos {
+                state.inversions.set(id, settings.inverted);
+            }
+            worker
+        };
         let state_clone = Arc::clone(&state);
         let ctx_clone = cc.egui_ctx.clone();
-        thread::spawn(move || {
-            monitoring_thread(state_clone, ctx_clone, rx);
+        let shutdown = ShutdownSignal::new();
+        let worker_shutdown = shutdown.clone();
+        let monitor = thread::spawn(move || {
+            monitoring_thread(state_clone, ctx_clone, rx, responder, worker, worker_shutdown);
+        });
+
+        Self { state, palette: PaletteState::default(), shutdown, monitor: Some(monitor) }
+    }
+}
+
+// Réglages courants écrits dans le fichier de configuration ; le port du bus simulé ou du rejeu
+// n'est pas gardé
+fn save_settings(state: &mut AppState) {
+    let mut config = Config::load();
+    if state.simulation.is_none() && state.replay.is_none() {
+        config.bus.port = state.port_name.clone();
+    }
+    config.ui.theme = state.theme;
+    config.ui.angle = state.angle;
+    config.ui.history_samples = state.history_samples;
+    config.limits = state.limits.clone();
+    config.derating = state.derating.clone();
+    config.stall = state.stall_settings.clone();
+    config.rescan = state.rescan.clone();
+    config.logging = state.log_settings.clone();
+    config.alerts.over_temperature = state.over_temperature;
+    config.exit.release_torque = state.exit.release_torque;
+    config.first_move.max_delta = state.first_move_guard;
+    state.settings_status = Some(match config.save() {
+        Ok(()) => format!("✓ Saved to {}", config::resolve_path().display()),
+        Err(e) => format!("✗ {}", e),
+    });
+}
+
+// --- JOG AU CLAVIER ---
+// Flèches ou -/+ : un Move par pas, la répétition du clavier étant remplacée par celle de `KeyRepeat`
+fn keyboard_jog(ctx: &egui::Context, state: &mut AppState, enabled: bool) {
+    let jog_keys = |i: &egui::InputState, keys: &[egui::Key]| keys.iter().any(|&key| i.key_down(key));
+    let (minus, plus, cycle) = ctx.input(|i| {
+        // Ctrl/Alt laissés aux raccourcis (Ctrl+- : zoom) ; Maj reste permis pour le + du clavier
+        let free = !i.modifiers.command && !i.modifiers.alt;
+        let cycle = i.events.iter().any(|event| {
+            matches!(event, egui::Event::Key { key: egui::Key::S, pressed: true, repeat: false, modifiers, .. } if modifiers.is_none())
         });
+        (
+            free && jog_keys(i, &[egui::Key::ArrowLeft, egui::Key::ArrowDown, egui::Key::Minus]),
+            free && jog_keys(i, &[egui::Key::ArrowRight, egui::Key::ArrowUp, egui::Key::Plus, egui::Key::Equals]),
+            cycle,
+        )
+    });
+    let Some(id) = state.selected_servo.filter(|_| enabled) else {
+        state.jog_repeat.update(0, Instant::now());
+        return;
+    };
+    if cycle {
+        state.cycle_jog_step();
+    }
+    let steps = state.jog_repeat.update(plus as i8 - minus as i8, Instant::now());
+    if state.jog_repeat.is_held() {
+        ctx.request_repaint_after(jog::REPEAT_INTERVAL);
+    }
+    let target = jog::jog_target(state.target_position, steps, state.jog_step, state.target_range(id));
+    // Contre une butée, la touche tenue n'envoie plus rien
+    if target != state.target_position {
+        state.target_position = target;
+        state.send(ServoCommand::Servo(Command::Move {
+            id,
+            position: target,
+            speed: state.target_speed,
+            acceleration: state.acceleration,
+            acknowledge_large: false,
+        }));
+    }
+}
+
+// --- ACTIONS DE LA PALETTE ---
+type GuiAction = Action<AppState, egui::KeyboardShortcut>;
+
+fn shortcut(modifiers: egui::Modifiers, key: egui::Key) -> egui::KeyboardShortcut {
+    egui::KeyboardShortcut::new(modifiers, key)
+}
+
+fn set_torque(state: &mut AppState, enable: bool) {
+    if let Some(id) = state.selected_servo {
+        let command_id = state.send(ServoCommand::Servo(Command::Torque { id, enable }));
+        state.commands.track(command_id, if enable { "torque on" } else { "torque off" }, Some(id));
+    }
+}
+
+fn palette_actions(state: &AppState) -> Vec<GuiAction> {
+    let has_selection = |s: &AppState| s.selected_servo.is_some();
+    let mut actions = vec![
+        GuiAction::new("Torque off", |s| set_torque(s, false))
+            .keywords("disable torque release selected servo")
+            .shortcut(shortcut(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::D))
+            .enabled_when(has_selection),
+        GuiAction::new("Torque on", |s| set_torque(s, true))
+            .keywords("enable torque hold selected servo")
+            .shortcut(shortcut(egui::Modifiers::COMMAND | egui::Modifiers::SHIFT, egui::Key::E))
+            .enabled_when(has_selection),
+        GuiAction::new("Move to target", |s| {
+            if let Some(id) = s.selected_servo {
+                s.send(ServoCommand::Servo(Command::Move {
+                    id,
+                    position: s.target_position,
+                    speed: s.target_speed,
+                    acceleration: s.acceleration,
+                    acknowledge_large: false,
+                }));
+            }
+        })
+        .keywords("go position")
+        .enabled_when(has_selection),
+        GuiAction::new("Scan servos", |s| {
+            let command_id = s.send(ServoCommand::ScanServos { exhaustive: s.exhaustive_scan });
+            s.commands.track(command_id, "scan", None);
+        })
+        .keywords("detect rescan bus")
+        .shortcut(shortcut(egui::Modifiers::COMMAND, egui::Key::R))
+        .enabled_when(|s| s.connected),
+        GuiAction::new("Export CSV", export_csv)
+            .keywords("save monitoring history")
+            .shortcut(shortcut(egui::Modifiers::COMMAND, egui::Key::S)),
+        GuiAction::new("Export session events", export_events).keywords("timeline json save"),
+        GuiAction::new("Generate session report", write_report).keywords("summary markdown html"),
+        GuiAction::new("Toggle timeline", |s| s.show_timeline = !s.show_timeline)
+            .keywords("events panel history")
+            .shortcut(shortcut(egui::Modifiers::COMMAND, egui::Key::T)),
+        GuiAction::new("Mute sound alerts", |s| s.sounds.muted = !s.sounds.muted)
+            .keywords("audio silence unmute")
+            .shortcut(shortcut(egui::Modifiers::COMMAND, egui::Key::M)),
+        GuiAction::new("Clear reference traces", |s| s.references.clear())
+            .keywords("overlay plot")
+            .enabled_when(|s| !s.references.is_empty()),
+    ];
 
-        Self { state }
+    for &id in &state.servo_ids {
+        actions.push(
+            GuiAction::new(format!("Select servo {}", state.label(id)), move |s| s.select(id))
+            .keywords("choose"),
+        );
     }
+
+    actions
 }
 
 impl eframe::App for ServoGuiApp {
+    // Arrêt du thread de monitoring (parcage, coupure du couple), puis rapport automatique avec
+    // l'export des événements qu'il référence
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.shutdown.request();
+        if let Some(monitor) = self.monitor.take() {
+            let timeout = self.state.lock().unwrap().exit.park_timeout() + shutdown::JOIN_MARGIN;
+            if !shutdown::join_within(monitor, timeout) {
+                eprintln!("Monitoring thread still busy after {:.0} s; exiting anyway", timeout.as_secs_f64());
+            }
+        }
+        let mut state = self.state.lock().unwrap();
+        if state.events.events().is_empty() && state.telemetry.servos.is_empty() {
+            return;
+        }
+        export_events(&mut state);
+        write_report(&mut state);
+        if let Some(status) = &state.report_status {
+            println!("{}", status);
+        }
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        // Palette de commandes et raccourcis clavier des actions
+        {
+            let mut state = self.state.lock().unwrap();
+            if let Some(owner) = state.port_conflict.clone() {
+                if let Some(choice) = portlock::conflict_dialog(ctx, &state.port_name, &owner) {
+                    state.port_conflict = None;
+                    state.port_choice = Some(choice);
+                }
+            }
+            // Résultats des commandes suivies : l'état affiché suit les relectures, seuls les échecs sont notifiés
+            state.commands.poll();
+            response::show_toasts(ctx, &mut state.commands);
+            // Rejeu : les commandes de l'enregistrement rejoignent la timeline à leur heure
+            if let Some(replay) = state.replay.clone() {
+                for recorded in replay.take_commands() {
+                    let text = format!("replay {:.1} s, {}: {}", recorded.t, recorded.source, recorded.command);
+                    state.events.push(Event::Annotation { text });
+                }
+            }
+            // Échap : arrêt d'urgence (sauf pour fermer la palette)
+            if !self.palette.open && ctx.input(|i| i.key_pressed(egui::Key::Escape)) {
+                state.send(ServoCommand::Servo(Command::EmergencyStop));
+            }
+            // Jog au clavier, sauf pendant une saisie (champ de texte, palette)
+            keyboard_jog(ctx, &mut state, !self.palette.open && !ctx.wants_keyboard_input());
+            let actions = palette_actions(&state);
+            self.palette.update(ctx, &actions, &mut state);
+        }
+
         // Panel supérieur avec titre
-        egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
+        // En répétition, tout le bandeau passe en couleur d'alerte
+        let (dry_run_flag, palette, simulation, replay, recorder) = {
+            let state = self.state.lock().unwrap();
+            // Bandeau tant que le port ouvert est celui du bus simulé ou du rejeu
+            let simulation = state.simulation.as_ref().filter(|(_, port)| *port == state.port_name).map(|(sim, _)| sim.banner());
+            let replay = state.replay.clone().filter(|replay| replay.port() == state.port_name);
+            (state.dry_run.clone(), state.theme.palette(), simulation, replay, state.recorder.clone())
+        };
+        let mut dry_run = dry_run_flag.load(Ordering::Relaxed);
+        let mut top_frame = egui::Frame::side_top_panel(&ctx.style());
+        if dry_run {
+            top_frame = top_frame.fill(palette.warning());
+        } else if simulation.is_some() || replay.is_some() {
+            top_frame = top_frame.fill(palette.info());
+        }
+        egui::TopBottomPanel::top("top_panel").frame(top_frame).show(ctx, |ui| {
             ui.add_space(10.0);
+            if dry_run {
+                ui.vertical_centered(|ui| {
+                    ui.heading(egui::RichText::new("DRY RUN — nothing is written to the servos").strong().color(egui::Color32::BLACK));
+                });
+            }
+            if let Some(banner) = &simulation {
+                ui.vertical_centered(|ui| {
+                    ui.heading(egui::RichText::new(banner).strong().color(egui::Color32::BLACK));
+                });
+            }
+            if let Some(replay) = &replay {
+                ui.vertical_centered(|ui| {
+                    ui.heading(egui::RichText::new(replay.banner()).strong().color(egui::Color32::BLACK));
+                    ui.horizontal(|ui| recording::replay_controls(ui, replay));
+                });
+            }
+            {
+                let state = self.state.lock().unwrap();
+                if state.estop.is_active() {
+                    let stopped: Vec<String> = state.estop.stopped().map(|id| state.label(id)).collect();
+                    ui.vertical_centered(|ui| {
+                        ui.heading(
+                            egui::RichText::new(format!(
+                                "EMERGENCY STOP — torque off on {}; enable torque per servo to move again",
+                                stopped.join(", ")
+                            ))
+                            .strong()
+                            .color(palette.danger()),
+                        );
+                    });
+                }
+            }
             ui.horizontal(|ui| {
                 ui.heading("Cogni-Robot Servo Control");
+                let estop_button = egui::Button::new(
+                    egui::RichText::new("⛔ E-STOP").strong().size(18.0).color(egui::Color32::WHITE),
+                )
+                .fill(palette.danger());
+                if ui.add(estop_button).on_hover_text("Disable torque on every servo (Esc)").clicked() {
+                    self.state.lock().unwrap().send(ServoCommand::Servo(Command::EmergencyStop));
+                }
+                if ui.checkbox(&mut dry_run, "Dry run").changed() {
+                    dry_run_flag.store(dry_run, Ordering::Relaxed);
+                    let text = if dry_run { "Dry run enabled" } else { "Dry run disabled (live)" };
+                    self.state.lock().unwrap().events.push(Event::Annotation { text: text.to_string() });
+                }
+                if replay.is_none() {
+                    recording::record_button(ui, &recorder);
+                }
+                {
+                    let mut state = self.state.lock().unwrap();
+                    if shutdown::release_checkbox(ui, &mut state.exit) {
+                        let mut config = Config::load();
+                        config.exit.release_torque = state.exit.release_torque;
+                        if let Err(e) = config.save() {
+                            eprintln!("Could not save exit settings: {}", e);
+                        }
+                    }
+                    let hover = format!("Write the port, theme, units, history length, limits and alerts to {}", config::resolve_path().display());
+                    if ui.button("💾 Save settings").on_hover_text(hover).clicked() {
+                        save_settings(&mut state);
+                    }
+                    if let Some(status) = &state.settings_status {
+                        ui.weak(status);
+                    }
+                }
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
                     ui.label("by notpunchnox");
-                    let state = self.state.lock().unwrap();
-                    let status_color = if state.connected {
-                        egui::Color32::from_rgb(46, 204, 113)
+                    let mut state = self.state.lock().unwrap();
+                    ui.toggle_value(&mut state.show_timeline, "Timeline");
+                    let sound_icon = if state.sounds.muted { "🔇" } else { "🔊" };
+                    ui.toggle_value(&mut state.sounds.muted, sound_icon).on_hover_text("Mute sound alerts");
+                    ui.menu_button("🔔", |ui| {
+                        for class in SoundClass::ALL {
+                            let mut enabled = state.sounds.enabled.contains(&class);
+                            if ui.checkbox(&mut enabled, class.label()).changed() {
+                                if enabled {
+                                    state.sounds.enabled.push(class);
+                                } else {
+                                    state.sounds.enabled.retain(|c| *c != class);
+                                }
+                            }
+                        }
+                    });
+                    let previous_theme = state.theme;
+                    egui::ComboBox::from_id_salt("theme")
+                        .selected_text(state.theme.label())
+                        .show_ui(ui, |ui| {
+                            for theme in Theme::ALL {
+                                ui.selectable_value(&mut state.theme, theme, theme.label());
+                            }
+                        });
+                    if state.theme != previous_theme {
+                        theme::apply(state.theme, ctx);
+                        let mut config = Config::load();
+                        config.ui.theme = state.theme;
+                        if let Err(e) = config.save() {
+                            eprintln!("Could not save theme: {}", e);
+                        }
+                    }
+                    let palette = state.theme.palette();
+                    if state.connected {
+                        palette.status_label(ui, Status::Ok, "Connected");
+                    } else if state.reconnecting {
+                        palette.status_label(ui, Status::Warning, "Reconnecting…");
                     } else {
-                        egui::Color32::from_rgb(231, 76, 60)
-                    };
-                    ui.colored_label(status_color, if state.connected { "Connected" } else { "Disconnected" });
+                        palette.status_label(ui, Status::Danger, "Disconnected");
+                    }
+                    draw_port_picker(ui, &mut state);
+                    if let Some(op) = state.operation.current() {
+                        palette.status_label(ui, Status::Warning, format!("{} on ID {}: {}", op.name, op.servo, op.step));
+                    }
                 });
             });
             ui.add_space(10.0);
         });
 
+        {
+            let state = self.state.lock().unwrap();
+            events::bottom_panel(ctx, &state.events, &palette, &|id| state.label(id));
+        }
+
+        let show_timeline = self.state.lock().unwrap().show_timeline;
+        egui::SidePanel::right("timeline_panel")
+            .default_width(320.0)
+            .show_animated(ctx, show_timeline, |ui| {
+                let mut state = self.state.lock().unwrap();
+                draw_timeline(ui, &mut state);
+            });
+
         egui::CentralPanel::default().show(ctx, |ui| {
             let mut state = self.state.lock().unwrap();
+            let palette = state.theme.palette();
             
             ui.add_space(10.0);
             
@@ -142,17 +1067,37 @@ impl eframe::App for ServoGuiApp {
                 ui.add_space(5.0);
                 
                 ui.horizontal(|ui| {
-                    if ui.button("Scan Servos").clicked() {
-                        let _ = state.command_sender.send(ServoCommand::ScanServos);
+                    let scanning = state.scan_progress.is_some() || state.commands.pending("scan", None);
+                    if ui.add_enabled(!scanning, egui::Button::new("Scan Servos")).clicked() {
+                        let command_id = state.send(ServoCommand::ScanServos { exhaustive: state.exhaustive_scan });
+                        state.commands.track(command_id, "scan", None);
                     }
+                    // Ping en diffusion : pas de progression, seulement l'attente de la réponse
+                    if state.commands.pending("scan", None) && state.scan_progress.is_none() {
+                        ui.spinner();
+                    }
+                    ui.checkbox(&mut state.exhaustive_scan, "Exhaustive")
+                        .on_hover_text("Ping every ID one by one instead of a single broadcast ping");
                     
                     if state.servo_ids.is_empty() {
-                        ui.colored_label(egui::Color32::from_rgb(230, 126, 34), "⚠ No servo detected");
+                        palette.status_label(ui, Status::Warning, "No servo detected");
                     } else {
                         ui.label(format!("Detected servos: {} ", state.servo_ids.len()));
                         ui.label(format!("{:?}", state.servo_ids));
                     }
                 });
+                if let Some(progress) = state.scan_progress {
+                    if hotplug::scan_progress(ui, progress) {
+                        state.send(ServoCommand::CancelScan);
+                    }
+                }
+                if hotplug::settings_editor(ui, &mut state.rescan) {
+                    let mut config = Config::load();
+                    config.rescan = state.rescan.clone();
+                    if let Err(e) = config.save() {
+                        eprintln!("Could not save rescan settings: {}", e);
+                    }
+                }
                 
                 if !state.servo_ids.is_empty() {
                     ui.add_space(5.0);
@@ -160,11 +1105,62 @@ impl eframe::App for ServoGuiApp {
                         ui.label("Select servo:");
                         for &id in &state.servo_ids.clone() {
                             let is_selected = state.selected_servo == Some(id);
-                            if ui.selectable_label(is_selected, format!("ID {}", id)).clicked() {
-                                state.selected_servo = Some(id);
+                            // Modèle et firmware une fois lus : « ID 3 · ST3215 · FW 2.54 »
+                            let mut name = state.identities.get(&id).map_or(format!("ID {}", id), |identity| identity.describe(id));
+                            let servo_name = state.stored(id).name;
+                            if !servo_name.trim().is_empty() {
+                                name = format!("{} ({})", servo_name.trim(), name);
+                            }
+                            if let Some(depth) = state.queued_moves.get(&id) {
+                                name = format!("{} · {} queued", name, depth);
+                            }
+                            let label = match state.id_changes.power_cycle_required(id) {
+                                _ if state.duplicate_ids.contains(&id) => format!("{} ⚠ duplicate?", name),
+                                Some(_) => format!("{} ⚠", name),
+                                None => name,
+                            };
+                            if ui.selectable_label(is_selected, label).clicked() {
+                                state.select(id);
+                            }
+                        }
+                        // Servos enregistrés que le scan n'a pas trouvés
+                        if state.scan_progress.is_none() {
+                            let missing: Vec<u8> = state.servo_settings.keys().copied().filter(|id| !state.servo_ids.contains(id)).collect();
+                            for id in missing {
+                                palette.status_label(ui, Status::Danger, format!("{} not detected", state.label(id)))
+                                    .on_hover_text("Listed in the configuration file but not found by the last scan: check its cable");
+                            }
+                        }
+                        // Nom du servo sélectionné, enregistré à chaque modification
+                        if let Some(id) = state.selected_servo {
+                            ui.separator();
+                            ui.label("Name:");
+                            let mut stored = state.stored(id);
+                            let edit = ui.add(egui::TextEdit::singleline(&mut stored.name).hint_text("name").desired_width(120.0))
+                                .on_hover_text("Shown instead of the ID everywhere; saved in the configuration file");
+                            if edit.changed() {
+                                state.save_servo(id, stored);
                             }
                         }
                     });
+
+                    for &id in &state.duplicate_ids {
+                        palette.status_label(
+                            ui,
+                            Status::Danger,
+                            format!("ID {}: several servos may share this ID; unplug all but one and rescan", id),
+                        )
+                        .on_hover_text("Replies were garbled or inconsistent. Moves and ID changes are blocked on this ID.");
+                    }
+                    for &id in &state.servo_ids {
+                        if let Some(old_id) = state.id_changes.power_cycle_required(id) {
+                            palette.status_label(
+                                ui,
+                                Status::Warning,
+                                format!("ID {}: power-cycle required (still answering as ID {})", id, old_id),
+                            );
+                        }
+                    }
                 }
             });
 
@@ -183,30 +1179,95 @@ impl eframe::App for ServoGuiApp {
                             .desired_width(60.0)
                             .hint_text("0-253"));
                         
-                        if ui.button("Apply").clicked() {
+                        let busy = state.operation.current().is_some();
+                        let duplicate = state.duplicate_ids.contains(&state.servo_ids[0]);
+                        let mut apply = ui.add_enabled(!busy && !duplicate, egui::Button::new("Apply"));
+                        if state.commands.pending("change id", Some(state.servo_ids[0])) {
+                            ui.spinner();
+                        }
+                        if duplicate {
+                            apply = apply.on_disabled_hover_text("Several servos may share this ID: unplug all but one and rescan");
+                        }
+                        if apply.clicked() {
                             if let Ok(new_id) = state.new_id_input.parse::<u8>() {
-                                if new_id <= 253 {
-                                    let _ = state.command_sender.send(ServoCommand::ChangeId {
-                                        old_id: state.servo_ids[0],
-                                        new_id,
-                                    });
-                                    state.new_id_input.clear();
-                                } else {
-                                    eprintln!("Invalid new ID: {} (must be 0-253)", new_id);
+                                match ids::check_free_id(state.servo_ids[0], new_id, &state.servo_ids, state.force_id_change) {
+                                    Ok(new_id) => {
+                                        let old_id = state.servo_ids[0];
+                                        let command_id = state.send(ServoCommand::ChangeId { old_id, new_id });
+                                        state.commands.track(command_id, "change id", Some(old_id));
+                                        state.new_id_input.clear();
+                                        state.id_change_status = None;
+                                    }
+                                    Err(e) => state.id_change_status = Some(format!("✗ {}", e)),
                                 }
                             } else {
-                                eprintln!("Invalid input for new ID: {}", state.new_id_input);
+                                state.id_change_status = Some(format!("✗ Invalid ID: '{}'", state.new_id_input));
                             }
                         }
                     });
+                    ui.checkbox(&mut state.force_id_change, "Allow an ID already on the bus")
+                        .on_hover_text("Two servos sharing an ID answer together and must be unplugged one by one to separate them");
+                    if let Some(op) = state.operation.current().filter(|op| op.name == "Change ID") {
+                        ui.label(format!("⏳ {}", op.step));
+                    } else if let Some(status) = &state.id_change_status {
+                        ui.label(status);
+                    }
+                });
+                ui.add_space(10.0);
+            }
+
+            // Échange d'ID entre deux servos détectés
+            if state.servo_ids.len() >= 2 {
+                ui.group(|ui| {
+                    ui.heading("Swap IDs");
+                    ui.add_space(5.0);
+                    draw_swap_ids(ui, &mut state);
+                });
+                ui.add_space(10.0);
+            }
+
+            if state.expert_mode {
+                egui::CollapsingHeader::new("Instruction console (expert)").show(ui, |ui| {
+                    draw_instruction_console(ui, &mut state);
                 });
                 ui.add_space(10.0);
             }
 
+            let changed = egui::CollapsingHeader::new("Gamepad")
+                .show(ui, |ui| draw_gamepad(ui, &mut state))
+                .body_returned
+                .unwrap_or(false);
+            if changed {
+                let mut config = Config::load();
+                config.gamepad = state.gamepad.clone();
+                if let Err(e) = config.save() {
+                    eprintln!("Could not save gamepad settings: {}", e);
+                }
+            }
+            ui.add_space(10.0);
+
+            egui::CollapsingHeader::new("Snapshot compare").show(ui, |ui| {
+                draw_snapshot_compare(ui, &mut state);
+            });
+            ui.add_space(10.0);
+
+            if let Some(servo_id) = state.selected_servo {
+                egui::CollapsingHeader::new("Registers").show(ui, |ui| {
+                    draw_registers(ui, &mut state, servo_id);
+                });
+                ui.add_space(10.0);
+            }
+
+            egui::CollapsingHeader::new("Log console").show(ui, logging::console);
+            ui.add_space(10.0);
+
             // Section de contrôle du servo sélectionné
             if let Some(servo_id) = state.selected_servo {
                 ui.group(|ui| {
-                    ui.heading(format!("Control Servo ID {}", servo_id));
+                    ui.horizontal(|ui| {
+                        ui.heading(format!("Control Servo {}", state.label(servo_id)));
+                        retry::error_label(ui, &palette, state.comm_errors.get(servo_id));
+                    });
                     ui.add_space(5.0);
                     
                     // Affichage des données en temps réel
@@ -214,26 +1275,39 @@ impl eframe::App for ServoGuiApp {
                         columns[0].vertical(|ui| {
                             ui.label("Position:");
                             if let Some(pos) = state.servo_data.position {
-                                ui.heading(format!("{}", pos));
+                                ui.heading(state.angle.format(pos)).on_hover_text(format!("{} ticks", pos));
                             } else {
                                 ui.label("N/A");
                             }
+                            if state.servo_data.is_moving == Some(true) {
+                                ui.horizontal(|ui| {
+                                    ui.spinner();
+                                    ui.label("moving");
+                                });
+                            }
+                            if let Some(stall) = state.stalls.get(&servo_id).copied() {
+                                palette.status_label(ui, Status::Danger, format!("STALLED: {}", stall.describe()));
+                                if ui.button("Clear stall").on_hover_text("The mechanism is free: accept moves again").clicked() {
+                                    state.stalls.remove(&servo_id);
+                                }
+                            }
                         });
                         
                         columns[1].vertical(|ui| {
                             ui.label("Temperature:");
                             if let Some(temp) = state.servo_data.temperature {
-                                let color = if temp > 60 {
-                                    egui::Color32::RED
-                                } else if temp > 45 {
-                                    egui::Color32::from_rgb(230, 126, 34)
-                                } else {
-                                    egui::Color32::from_rgb(46, 204, 113)
-                                };
-                                ui.colored_label(color, format!("{}°C", temp));
+                                palette.status_label(ui, temperature_status(temp), format!("{}°C", temp));
                             } else {
                                 ui.label("N/A");
                             }
+                            if let Some(cooled) = state.thermal.state(servo_id) {
+                                let hint = if cooled {
+                                    "Cooled down: enable torque to re-arm".to_string()
+                                } else {
+                                    format!("Torque cut; enable it again once below {}°C", state.derating.rearm)
+                                };
+                                palette.status_label(ui, Status::Danger, "THERMAL LOCKOUT").on_hover_text(hint);
+                            }
                         });
                         
                         columns[2].vertical(|ui| {
@@ -245,46 +1319,297 @@ impl eframe::App for ServoGuiApp {
                             }
                         });
                     });
+
+                    // Sorties des traitements de télémétrie enregistrés
+                    if state.processors.latest(servo_id).next().is_some() {
+                        ui.add_space(5.0);
+                        ui.label(egui::RichText::new("Derived metrics").strong());
+                        egui::Grid::new("derived_metrics").num_columns(2).show(ui, |ui| {
+                            for derived in state.processors.latest(servo_id) {
+                                ui.label(&derived.name);
+                                ui.label(format!("{:.2}{}", derived.value, derived.unit));
+                                ui.end_row();
+                            }
+                        });
+                    }
                     
                     ui.add_space(10.0);
                     
                     // Contrôles de mouvement
                     ui.separator();
                     ui.add_space(5.0);
-                    ui.label("Target Position (0-4095):");
-                    ui.add(egui::Slider::new(&mut state.target_position, 0..=4095));
+                    let mut stored = state.stored(servo_id);
+                    ui.horizontal(|ui| {
+                        ui.label("Target Position");
+                        ui.separator();
+                        if units::unit_picker(ui, &mut state.angle) {
+                            let mut config = Config::load();
+                            config.ui.angle = state.angle;
+                            if let Err(e) = config.save() {
+                                eprintln!("Could not save position units: {}", e);
+                            }
+                        }
+                        ui.separator();
+                        let inverted = ui.checkbox(&mut stored.inverted, "Inverted")
+                            .on_hover_text("Mirror positions around 2048 for a joint mounted the other way round, so a left/right pair shares the same poses");
+                        if inverted.changed() {
+                            // Même position physique vue depuis l'autre sens : consigne et butées suivent
+                            state.target_position = inversion::mirror(state.target_position);
+                            if let Some(limits) = state.limits.get_mut(&servo_id) {
+                                *limits = limits.mirrored();
+                            }
+                            state.inversions.set(servo_id, stored.inverted);
+                            state.save_servo(servo_id, stored.clone());
+                        }
+                    });
+                    let angle = state.angle;
+                    let target = state.target_position;
+                    let range = state.target_range(servo_id);
+                    ui.horizontal(|ui| {
+                        ui.add(units::position_slider(&mut state.target_position, range.clone(), angle).show_value(false))
+                            .on_hover_text(format!("{} ticks", target));
+                        ui.add(units::position_field(&mut state.target_position, range.clone(), angle))
+                            .on_hover_text("Target, in the selected unit");
+                        if let Some(delta) = units::nudge_buttons(ui, &[-10, -1, 1, 10]) {
+                            state.target_position = jog::nudge(state.target_position, delta, range);
+                        }
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(format!("Jog step: {} ticks", state.jog_step.ticks()));
+                        if ui.small_button("Change").on_hover_text("Also with the S key").clicked() {
+                            state.cycle_jog_step();
+                        }
+                        ui.weak("←/→ or −/+ to jog");
+                    });
                     
                     ui.label("Speed (0-3400):");
-                    ui.add(egui::Slider::new(&mut state.target_speed, 0..=3400));
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Slider::new(&mut state.target_speed, 0..=3400).show_value(false));
+                        ui.add(egui::DragValue::new(&mut state.target_speed).range(0..=3400));
+                        if let Some(delta) = units::nudge_buttons(ui, &[-100, -10, 10, 100]) {
+                            state.target_speed = (i32::from(state.target_speed) + delta).clamp(0, 3400) as u16;
+                        }
+                    });
                     
                     ui.label("Acceleration (0-254):");
-                    ui.add(egui::Slider::new(&mut state.acceleration, 0..=254));
+                    ui.horizontal(|ui| {
+                        ui.add(egui::Slider::new(&mut state.acceleration, 0..=254).show_value(false));
+                        ui.add(egui::DragValue::new(&mut state.acceleration).range(0..=254));
+                        if let Some(delta) = units::nudge_buttons(ui, &[-10, -1, 1, 10]) {
+                            state.acceleration = (i32::from(state.acceleration) + delta).clamp(0, 254) as u8;
+                        }
+                        match acceleration_ticks_per_s2(state.acceleration) {
+                            Some(accel) => ui.label(format!(
+                                "= {:.0} ticks/s² ({:.0} °/s²)",
+                                accel,
+                                ticks_to_degrees_per_s2(accel)
+                            )),
+                            None => ui.label("= max"),
+                        };
+                    });
+
+                    ui.horizontal(|ui| {
+                        ui.label("First move guard (ticks, 0 = off):");
+                        ui.add(egui::DragValue::new(&mut state.first_move_guard).range(0..=4095));
+                    });
+
+                    egui::CollapsingHeader::new("Stall detection").show(ui, |ui| {
+                        if stall::settings_editor(ui, &mut state.stall_settings) {
+                            let mut config = Config::load();
+                            config.stall = state.stall_settings.clone();
+                            if let Err(e) = config.save() {
+                                eprintln!("Could not save stall settings: {}", e);
+                            }
+                        }
+                    });
+
+                    // Butées logicielles : toute consigne est ramenée dans la fenêtre par le worker
+                    egui::CollapsingHeader::new("Soft limits").show(ui, |ui| {
+                        let mut limits = state.limits.get(&servo_id).copied().unwrap_or_default();
+                        ui.horizontal(|ui| {
+                            ui.label("Min / max (ticks):");
+                            let max = limits.max;
+                            ui.add(egui::DragValue::new(&mut limits.min).range(0..=max))
+                                .on_hover_text(state.angle.format(limits.min));
+                            let min = limits.min;
+                            ui.add(egui::DragValue::new(&mut limits.max).range(min..=4095))
+                                .on_hover_text(state.angle.format(limits.max));
+                            if ui.button("Reset").clicked() {
+                                limits = SoftLimits::default();
+                            }
+                        });
+                        if state.limits.get(&servo_id).copied().unwrap_or_default() != limits {
+                            state.limits.insert(servo_id, limits);
+                            let mut config = Config::load();
+                            config.limits = state.limits.clone();
+                            if let Err(e) = config.save() {
+                                eprintln!("Could not save soft limits: {}", e);
+                            }
+                        }
+                    });
+
+                    // Butées matérielles : le servo lui-même refuse d'aller au-delà
+                    egui::CollapsingHeader::new("Hardware angle limits").show(ui, |ui| {
+                        draw_angle_limits(ui, &mut state, servo_id);
+                    });
+
+                    egui::CollapsingHeader::new("Torque limit").show(ui, |ui| {
+                        draw_torque_limit(ui, &mut state, servo_id);
+                    });
+
+                    egui::CollapsingHeader::new("PID tuning").show(ui, |ui| {
+                        draw_pid_tuning(ui, &mut state, servo_id);
+                    });
                     
                     ui.add_space(5.0);
                     
+                    // Mouvement en cours : drapeau du servo, ou dernier Move pas encore clos
+                    let in_motion = state.servo_data.is_moving == Some(true)
+                        || state.last_move_timing.is_some_and(|t| t.id == servo_id && t.measured.is_none());
                     ui.horizontal(|ui| {
-                        if ui.button("Move").clicked() {
-                            let _ = state.command_sender.send(ServoCommand::Move {
+                        let move_enabled = !(state.wait_for_completion && in_motion);
+                        if ui
+                            .add_enabled(move_enabled, egui::Button::new("Move"))
+                            .on_disabled_hover_text("Waiting for the previous move to complete")
+                            .clicked()
+                        {
+                            state.send(ServoCommand::Servo(Command::Move {
+                                id: servo_id,
+                                position: state.target_position,
+                                speed: state.target_speed,
+                                acceleration: state.acceleration,
+                                acknowledge_large: false,
+                            }));
+                        }
+                        if ui.button("Queue").on_hover_text("Add to this servo's move queue: each move starts once the previous one has arrived").clicked() {
+                            state.send(ServoCommand::QueueMove {
                                 id: servo_id,
                                 position: state.target_position,
                                 speed: state.target_speed,
                                 acceleration: state.acceleration,
+                                acknowledge_large: false,
                             });
-                            if !state.torque_enabled {
-                                state.torque_enabled = true;
+                        }
+                        if let Some(&depth) = state.queued_moves.get(&servo_id) {
+                            ui.label(format!("{} queued", depth));
+                            if ui.small_button("Clear queue").clicked() {
+                                state.send(ServoCommand::ClearQueue { id: servo_id });
                             }
                         }
+
+                        // Durée estimée du mouvement en attente
+                        if let Some(pos) = state.servo_data.position {
+                            let estimate = estimate_move_duration(
+                                pos.abs_diff(state.target_position),
+                                state.target_speed,
+                                state.acceleration,
+                            );
+                            ui.label(format!("≈ {:.2} s", estimate.as_secs_f64()));
+                        }
                         
-                        let torque_text = if state.torque_enabled { "Disable Torque" } else { "Enable Torque" };
-                        if ui.button(torque_text).clicked() {
-                            if state.torque_enabled {
-                                let _ = state.command_sender.send(ServoCommand::DisableTorque { id: servo_id });
-                            } else {
-                                let _ = state.command_sender.send(ServoCommand::EnableTorque { id: servo_id });
-                            }
-                            state.torque_enabled = !state.torque_enabled;
+                        // Le libellé suit la relecture du registre, pas le clic
+                        let torque_on = state.torque.get(&servo_id).copied();
+                        let torque_text = if torque_on == Some(true) { "Disable Torque" } else { "Enable Torque" };
+                        let torque_pending = state.commands.pending("torque on", Some(servo_id))
+                            || state.commands.pending("torque off", Some(servo_id));
+                        if ui.add_enabled(!torque_pending, egui::Button::new(torque_text)).clicked() {
+                            set_torque(&mut state, torque_on != Some(true));
+                        }
+                        if torque_pending {
+                            ui.spinner();
+                        }
+                        if torque_on.is_none() {
+                            ui.label("torque state unknown");
+                        }
+                    });
+                    ui.checkbox(&mut state.wait_for_completion, "Wait for completion")
+                        .on_hover_text("Keep Move disabled until the servo reports the previous move as finished");
+
+                    // Réglage du milieu : articulation posée à la main, couple coupé
+                    ui.horizontal(|ui| {
+                        let torque_off = state.torque.get(&servo_id) == Some(&false);
+                        let busy = state.operation.current().is_some();
+                        if ui
+                            .add_enabled(torque_off && !busy, egui::Button::new("Set current position as center"))
+                            .on_hover_text("Writes the position offset (EEPROM) so the current pose reads 2048")
+                            .on_disabled_hover_text("Turn the torque off and pose the joint by hand first")
+                            .clicked()
+                        {
+                            state.pending_center = Some(servo_id);
+                            state.center_status = None;
+                        }
+                        if let Some(op) = state.operation.current().filter(|op| op.name == "Set center") {
+                            ui.label(format!("⏳ {}", op.step));
+                        } else if let Some(status) = &state.center_status {
+                            ui.label(status);
                         }
                     });
+                    if let Some(id) = state.pending_center.filter(|&id| id == servo_id) {
+                        ui.horizontal(|ui| {
+                            palette.status_label(
+                                ui,
+                                Status::Warning,
+                                format!("Write the position offset of ID {} to EEPROM? Soft limits and history follow.", id),
+                            );
+                            if ui.button("Confirm").clicked() {
+                                state.send(ServoCommand::SetCenter { id });
+                                state.pending_center = None;
+                            }
+                            if ui.button("Cancel").clicked() {
+                                state.pending_center = None;
+                            }
+                        });
+                    }
+
+                    if let Some(timing) = state.last_move_timing.filter(|t| t.id == servo_id) {
+                        let measured = match timing.measured {
+                            Some(d) => format!("{:.2} s", d.as_secs_f64()),
+                            None => "moving...".to_string(),
+                        };
+                        ui.label(format!(
+                            "Last move: estimated {:.2} s, measured {}",
+                            timing.estimated.as_secs_f64(),
+                            measured
+                        ));
+                        if let Some(error) = timing.final_error() {
+                            ui.label(format!(
+                                "Final error: {} (target {})",
+                                state.angle.format_delta(error),
+                                state.angle.format(timing.target)
+                            ))
+                            .on_hover_text(format!("{:+} ticks", error));
+                        }
+                    }
+
+                    // Confirmation d'un premier mouvement de grande amplitude
+                    if let Some(pending) = state.pending_large_move {
+                        ui.add_space(5.0);
+                        ui.horizontal(|ui| {
+                            let summary = match pending.current {
+                                Some(current) => format!(
+                                    "Large first move: {} → {} ({} ticks)",
+                                    current,
+                                    pending.position,
+                                    current.abs_diff(pending.position)
+                                ),
+                                None => format!("First move to {}: current position unknown", pending.position),
+                            };
+                            palette.status_label(ui, Status::Warning, summary);
+                            if ui.button("Confirm move").clicked() {
+                                state.send(ServoCommand::Servo(Command::Move {
+                                    id: pending.id,
+                                    position: pending.position,
+                                    speed: pending.speed,
+                                    acceleration: pending.acceleration,
+                                    acknowledge_large: true,
+                                }));
+                                state.pending_large_move = None;
+                            }
+                            if ui.button("Cancel").clicked() {
+                                state.pending_large_move = None;
+                            }
+                        });
+                    }
                 });
                 
                 ui.add_space(10.0);
@@ -294,173 +1619,2398 @@ impl eframe::App for ServoGuiApp {
                     ui.heading("Real-time Monitoring");
                     ui.add_space(5.0);
                     
-                    // Graphique de position
-                    Plot::new("position_plot")
-                        .height(150.0)
-                        .view_aspect(2.0)
-                        .show(ui, |plot_ui| {
-                            let points: PlotPoints = state.position_history.iter()
-                                .map(|(x, y)| [*x, *y])
-                                .collect();
-                            plot_ui.line(Line::new("Position", points).color(egui::Color32::from_rgb(52, 152, 219)));
-                        });
-                    
+                    draw_reference_controls(ui, &mut state);
+
+                    ui.horizontal(|ui| {
+                        ui.label("History:");
+                        let mut samples = state.history_samples;
+                        let response = ui.add(
+                            egui::DragValue::new(&mut samples).range(MIN_HISTORY..=MAX_HISTORY).speed(100).suffix(" samples"),
+                        );
+                        if response.changed() {
+                            state.set_history_samples(samples);
+                        }
+                        if response.drag_stopped() || (response.changed() && !response.dragged()) {
+                            let mut config = Config::load();
+                            config.ui.history_samples = state.history_samples;
+                            if let Err(e) = config.save() {
+                                eprintln!("Could not save history length: {}", e);
+                            }
+                        }
+                    });
+
+                    let focus = state.plot_focus.take();
+
+                    draw_history_plot(ui, &state, "position", "Position", &state.position_history, palette.trace(0), focus);
                     ui.add_space(5.0);
-                    
-                    // Graphique de température
-                    Plot::new("temperature_plot")
-                        .height(150.0)
-                        .view_aspect(2.0)
-                        .show(ui, |plot_ui| {
-                            let points: PlotPoints = state.temperature_history.iter()
-                                .map(|(x, y)| [*x, *y])
-                                .collect();
-                            plot_ui.line(Line::new("Temperature", points).color(egui::Color32::from_rgb(231, 76, 60)));
-                        });
+                    draw_history_plot(ui, &state, "temperature", "Temperature", &state.temperature_history, palette.trace(1), focus);
+
+                    // Signes avant-coureurs d'un servo qui force : charge (signée), courant, tension
+                    egui::CollapsingHeader::new("Load, current and voltage").default_open(true).show(ui, |ui| {
+                        draw_history_plot(ui, &state, "load", "Load (%)", &state.load_history, palette.trace(2), focus);
+                        ui.add_space(5.0);
+                        draw_history_plot(ui, &state, "current", "Current (mA)", &state.current_history, palette.trace(3), focus);
+                        ui.add_space(5.0);
+                        draw_history_plot(ui, &state, "voltage", "Voltage (V)", &state.voltage_history, palette.trace(4), focus);
+                    });
                 });
             }2
         });
 
-        ctx.request_repaint_after(Duration::from_millis(200));
+        // Le thread de monitoring demande un repaint quand l'affichage change : simple maintien à 1 Hz
+        ctx.request_repaint_after(KEEP_ALIVE_REPAINT);
+    }
+}
+
+// --- EXPORT CSV ET TRACES DE RÉFÉRENCE ---
+fn draw_reference_controls(ui: &mut egui::Ui, state: &mut AppState) {
+    ui.horizontal(|ui| {
+        ui.text_edit_singleline(&mut state.csv_export_path);
+        if ui.button("Export CSV").clicked() {
+            export_csv(state);
+        }
+    });
+
+    ui.horizontal(|ui| {
+        let enabled = state.log_enabled;
+        let LogSettings { path, max_size_mb } = &mut state.log_settings;
+        ui.add_enabled(!enabled, egui::TextEdit::singleline(path).desired_width(160.0));
+        ui.add_enabled(!enabled, egui::DragValue::new(max_size_mb).range(1..=10_000).suffix(" MB"))
+            .on_hover_text("A new numbered file is started past this size");
+        ui.toggle_value(&mut state.log_enabled, "⏺ Log to file");
+    });
+    if let Some(status) = &state.log_status {
+        ui.label(status);
+    }
+
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(&mut state.reference_path).hint_text("reference.csv"));
+        if ui.button("Load reference data").clicked() {
+            match ReferenceData::load(std::path::Path::new(&state.reference_path)) {
+                Ok(data) => {
+                    state.reference_status = Some(format!("✓ Loaded {}", data.name));
+                    state.references.push(data);
+                }
+                Err(e) => state.reference_status = Some(format!("✗ {}", e)),
+            }
+        }
+        if ui.add_enabled(!state.references.is_empty(), egui::Button::new("Clear references")).clicked() {
+            state.references.clear();
+        }
+    });
+
+    if !state.references.is_empty() {
+        ui.horizontal(|ui| {
+            ui.label("Align:");
+            ui.radio_value(&mut state.reference_align, ReferenceAlign::Absolute, "Absolute time");
+            ui.radio_value(&mut state.reference_align, ReferenceAlign::Marker, "Command marker");
+            if state.reference_align == ReferenceAlign::Marker {
+                let markers: Vec<(f64, String)> = state.events
+                    .filtered(&[EventKind::Command], state.selected_servo)
+                    .map(|e| (e.elapsed, format!("{:.1}s {}", e.elapsed, e.event.summary())))
+                    .collect();
+                let selected_text = state.reference_marker
+                    .and_then(|t| markers.iter().find(|(m, _)| *m == t))
+                    .map(|(_, label)| label.clone())
+                    .unwrap_or_else(|| "Select command".to_string());
+                egui::ComboBox::from_id_salt("reference_marker")
+                    .selected_text(selected_text)
+                    .show_ui(ui, |ui| {
+                        for (t, label) in markers {
+                            ui.selectable_value(&mut state.reference_marker, Some(t), label);
+                        }
+                    });
+            }
+        });
+    }
+
+    if let Some(status) = &state.reference_status {
+        ui.label(status);
     }
 }
 
-fn monitoring_thread(state: Arc<Mutex<AppState>>, ctx: egui::Context, rx: Receiver<ServoCommand>) {
-    let mut servo_connection: Option<ST3215> = None;
+// Graphique d'une série live, sous les traces de référence de la même colonne
+fn draw_history_plot(
+    ui: &mut egui::Ui,
+    state: &AppState,
+    column: &str,
+    name: &str,
+    history: &History,
+    color: egui::Color32,
+    focus: Option<f64>,
+) {
+    Plot::new(format!("{}_plot", column))
+        .height(150.0)
+        .view_aspect(2.0)
+        .legend(Legend::default())
+        .show(ui, |plot_ui| {
+            if let Some(t) = focus {
+                plot_ui.set_plot_bounds_x(t - 5.0..=t + 5.0);
+            }
+            draw_reference_lines(plot_ui, state, column);
+            let points: PlotPoints = history.iter_ordered().map(|(x, y)| [x, y]).collect();
+            plot_ui.line(Line::new(name, points).color(color));
+        });
+}
+
+fn export_csv(state: &mut AppState) {
+    let mut series: Vec<(&str, Vec<(f64, f64)>)> = vec![
+        ("position", state.position_history.to_vec()),
+        ("temperature", state.temperature_history.to_vec()),
+        ("load", state.load_history.to_vec()),
+        ("current", state.current_history.to_vec()),
+        ("voltage", state.voltage_history.to_vec()),
+        ("speed", state.speed_history.to_vec()),
+    ];
+    series.extend(state.processors.history().iter().map(|(name, points)| (name.as_str(), points.to_vec())));
+    let columns: Vec<(&str, &[(f64, f64)])> = series.iter().map(|(name, points)| (*name, points.as_slice())).collect();
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+    let servo = state.selected_servo.map_or("none".to_string(), |id| id.to_string());
+    let header = format!("servo ID {}, exported {}", servo, format_timestamp(now_ms));
+    let csv = reference::to_csv(&[header], &columns);
+    state.reference_status = Some(match std::fs::write(&state.csv_export_path, csv) {
+        Ok(()) => format!("✓ Exported to {}", state.csv_export_path),
+        Err(e) => format!("✗ {}", e),
+    });
+}
+
+// Traces de référence (pointillés) pour une colonne donnée, dessinées sous les traces live
+fn draw_reference_lines(plot_ui: &mut PlotUi, state: &AppState, column: &str) {
+    for (index, data) in state.references.iter().enumerate() {
+        let Some(points) = data.get(column).filter(|p| !p.is_empty()) else {
+            continue;
+        };
+        let offset = match (state.reference_align, state.reference_marker) {
+            (ReferenceAlign::Marker, Some(marker)) => marker - points[0].0,
+            _ => 0.0,
+        };
+        let points: PlotPoints = points.iter().map(|(x, y)| [x + offset, *y]).collect();
+        plot_ui.line(
+            Line::new(data.name.clone(), points)
+                .color(state.theme.palette().trace(REFERENCE_TRACE_OFFSET + index))
+                .style(LineStyle::dashed_loose()),
+        );
+    }
+}
+
+// --- COMPARAISON D'INSTANTANÉS ---
+fn draw_snapshot_compare(ui: &mut egui::Ui, state: &mut AppState) {
+    let palette = state.theme.palette();
+    egui::Grid::new("snapshot_capture").num_columns(2).show(ui, |ui| {
+        ui.label("Test sequence:");
+        ui.text_edit_singleline(&mut state.snapshot_sequence_path);
+        ui.end_row();
+
+        ui.label("Label:");
+        ui.text_edit_singleline(&mut state.snapshot_label);
+        ui.end_row();
+    });
+
+    ui.horizontal(|ui| {
+        let selected = state.selected_servo;
+        if ui.add_enabled(selected.is_some(), egui::Button::new("Capture")).clicked() {
+            match Sequence::load(std::path::Path::new(&state.snapshot_sequence_path)) {
+                Ok(sequence) => {
+                    let label = state.snapshot_label.trim().to_string();
+                    state.send(ServoCommand::CaptureSnapshot {
+                        id: selected.unwrap_or_default(),
+                        path: format!("{}.json", label),
+                        label,
+                        sequence,
+                    });
+                    state.snapshot_status = Some("Capturing...".to_string());
+                }
+                Err(e) => state.snapshot_status = Some(format!("✗ {}", e)),
+            }
+        }
+        if let Some(status) = &state.snapshot_status {
+            ui.label(status);
+        }
+    });
+
+    ui.separator();
+    ui.horizontal(|ui| {
+        ui.label("Before:");
+        ui.text_edit_singleline(&mut state.snapshot_paths[0]);
+        ui.label("After:");
+        ui.text_edit_singleline(&mut state.snapshot_paths[1]);
+        if ui.button("Load").clicked() {
+            for i in 0..2 {
+                match Snapshot::load(std::path::Path::new(&state.snapshot_paths[i])) {
+                    Ok(snap) => state.snapshots[i] = Some(snap),
+                    Err(e) => {
+                        state.snapshots[i] = None;
+                        state.snapshot_status = Some(format!("✗ {}", e));
+                    }
+                }
+            }
+        }
+    });
+
+    let [Some(before), Some(after)] = &state.snapshots else {
+        return;
+    };
+
+    if before.sequence != after.sequence {
+        palette.status_label(ui, Status::Warning, "Snapshots were captured with different sequences");
+    }
+
+    Plot::new("snapshot_plot")
+        .height(150.0)
+        .view_aspect(2.0)
+        .legend(egui_plot::Legend::default())
+        .show(ui, |plot_ui| {
+            for (snap, color) in [(before, egui::Color32::GRAY), (after, palette.trace(0))] {
+                let points: PlotPoints = snap.samples.iter()
+                    .map(|s| [s.t, s.position as f64])
+                    .collect();
+                plot_ui.line(Line::new(snap.label.clone(), points).color(color));
+            }
+        });
+
+    egui::Grid::new("snapshot_diff").num_columns(4).striped(true).show(ui, |ui| {
+        ui.label("");
+        ui.strong(&before.label);
+        ui.strong(&after.label);
+        ui.strong("Δ");
+        ui.end_row();
+        for row in snapshot::compare(before, after) {
+            ui.label(format!("{} ({})", row.name, row.unit));
+            ui.label(format!("{:.2}", row.before));
+            ui.label(format!("{:.2}", row.after));
+            let status = if row.delta() <= 0.0 { Status::Ok } else { Status::Danger };
+            palette.status_label(ui, status, format!("{:+.2}", row.delta()));
+            ui.end_row();
+        }
+    });
+}
+
+// --- CONSOLE D'INSTRUCTIONS BAS NIVEAU ---
+const BROADCAST_CONFIRMATION: &str = "BROADCAST";
+
+fn draw_instruction_console(ui: &mut egui::Ui, state: &mut AppState) {
+    egui::Grid::new("instruction_console").num_columns(2).show(ui, |ui| {
+        ui.label("Instruction:");
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(&mut state.console_instruction).hexadecimal(2, false, true));
+            ui.label(packet::instruction_name(state.console_instruction));
+        });
+        ui.end_row();
+
+        ui.label("Target ID:");
+        ui.add(egui::DragValue::new(&mut state.console_target_id).range(0..=254));
+        ui.end_row();
+
+        ui.label("Parameters (hex):");
+        ui.add(egui::TextEdit::singleline(&mut state.console_params).hint_text("2A 00 08"));
+        ui.end_row();
+    });
+
+    // La confirmation de diffusion est demandée plus bas ; les écritures EEPROM en diffusion sont refusées
+    let frame = packet::parse_hex(&state.console_params).and_then(|params| {
+        let access = packet::access(state.console_instruction, &params);
+        ids::check_target(state.console_target_id, access, true)?;
+        packet::build_frame(state.console_target_id, state.console_instruction, &params)
+    });
+
+    match &frame {
+        Ok(frame) => {
+            ui.label(format!("Checksum: {:02X}", frame[frame.len() - 1]));
+            ui.monospace(packet::to_hex(frame));
+        }
+        Err(e) => {
+            state.theme.palette().status_label(ui, Status::Danger, e);
+        }
+    }
+
+    let needs_confirmation = packet::needs_broadcast_confirmation(state.console_target_id, state.console_instruction);
+    if needs_confirmation {
+        ui.colored_label(
+            state.theme.palette().danger(),
+            format!("✖ Broadcast write: every servo on the bus will execute it. Type {} to enable.", BROADCAST_CONFIRMATION),
+        );
+        ui.text_edit_singleline(&mut state.console_confirm);
+    }
+
+    let confirmed = !needs_confirmation || state.console_confirm == BROADCAST_CONFIRMATION;
+    if ui.add_enabled(frame.is_ok() && confirmed, egui::Button::new("Send")).clicked() {
+        if let Ok(frame) = frame {
+            state.send(ServoCommand::RawInstruction { frame });
+            state.console_confirm.clear();
+            state.console_result = Some("Sending...".to_string());
+        }
+    }
+
+    if let Some(result) = &state.console_result {
+        ui.monospace(result);
+    }
+}
+
+// Réponse brute formatée pour la console : hex puis champs décodés
+fn describe_raw_response(response: Result<Option<Vec<u8>>, String>) -> Result<String, String> {
+    match response {
+        Ok(None) => Ok("sent (no status packet expected)".to_string()),
+        Ok(Some(bytes)) => {
+            let hex = packet::to_hex(&bytes);
+            match packet::decode_response(&bytes) {
+                Ok(r) => Ok(format!(
+                    "{}\nID {} · error {:#04x} · params [{}]",
+                    hex,
+                    r.id,
+                    r.error,
+                    packet::to_hex(&r.params)
+                )),
+                Err(e) => Err(format!("{}\n{}", hex, e)),
+            }
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn export_events(state: &mut AppState) {
+    let path = std::path::PathBuf::from(&state.events_export_path);
+    state.events_export_status = Some(match state.events.export_json(&path) {
+        Ok(()) => format!("Exported to {}", path.display()),
+        Err(e) => format!("Export failed: {}", e),
+    });
+}
+
+// Rapport écrit à côté de l'export des événements, avec liens vers les fichiers de session existants
+fn write_report(state: &mut AppState) {
+    let duration = state.start_time.elapsed().as_secs_f64();
+    let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+    let started = now_ms.saturating_sub((duration * 1000.0) as u64);
+    let mut report = SessionReport::build(started, duration, state.events.events(), &state.telemetry);
+    report.derived = state.processors.all_latest().map(|(id, derived)| (id, derived.clone())).collect();
+    for (label, path) in [("Session events", &state.events_export_path), ("Monitoring CSV", &state.csv_export_path)] {
+        if std::path::Path::new(path).exists() {
+            report.link(label, path.as_str());
+        }
+    }
+    let dir = std::path::Path::new(&state.events_export_path)
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or(std::path::Path::new("."))
+        .to_path_buf();
+    state.report_status = Some(match report.write(&dir, state.report_html) {
+        Ok(paths) => format!("✓ Report written to {}", paths.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ")),
+        Err(e) => format!("✗ {}", e),
+    });
+}
+
+// --- TIMELINE DE SESSION ---
+fn draw_timeline(ui: &mut egui::Ui, state: &mut AppState) {
+    let palette = state.theme.palette();
+    ui.heading("Session Timeline");
+
+    ui.horizontal_wrapped(|ui| {
+        for kind in EventKind::ALL {
+            let mut enabled = state.timeline_kinds.contains(&kind);
+            if ui.checkbox(&mut enabled, kind.label()).changed() {
+                if enabled {
+                    state.timeline_kinds.push(kind);
+                } else {
+                    state.timeline_kinds.retain(|k| *k != kind);
+                }
+            }
+        }
+    });
+
+    let selected = state.timeline_servo.map_or("All servos".to_string(), |id| state.label(id));
+    egui::ComboBox::from_label("Servo")
+        .selected_text(selected)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut state.timeline_servo, None, "All servos");
+            for id in state.servo_ids.clone() {
+                let label = state.label(id);
+                ui.selectable_value(&mut state.timeline_servo, Some(id), label);
+            }
+        });
+
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(&mut state.annotation_input)
+            .desired_width(200.0)
+            .hint_text("Annotation"));
+        if ui.button("Add").clicked() && !state.annotation_input.trim().is_empty() {
+            let text = state.annotation_input.trim().to_string();
+            state.events.push(Event::Annotation { text });
+            state.annotation_input.clear();
+        }
+    });
+
+    ui.horizontal(|ui| {
+        ui.add(egui::TextEdit::singleline(&mut state.events_export_path).desired_width(200.0));
+        if ui.button("Export JSON").clicked() {
+            export_events(state);
+        }
+    });
+    if let Some(status) = &state.events_export_status {
+        ui.label(status);
+    }
+    ui.horizontal(|ui| {
+        if ui.button("Session report").clicked() {
+            write_report(state);
+        }
+        ui.checkbox(&mut state.report_html, "HTML");
+    });
+    if let Some(status) = &state.report_status {
+        ui.label(status);
+    }
+
+    ui.separator();
+
+    // Les graphiques ne couvrent que la fenêtre d'historique en mémoire
+    let history_start = state.position_history.first().map(|(t, _)| t);
+    let mut clicked = None;
+    egui::ScrollArea::vertical().stick_to_bottom(true).show(ui, |ui| {
+        for event in state.events.filtered(&state.timeline_kinds, state.timeline_servo) {
+            let color = match event.event.kind() {
+                EventKind::Connection => palette.info(),
+                EventKind::Command => egui::Color32::GRAY,
+                EventKind::Alert => palette.warning(),
+                EventKind::Error => palette.danger(),
+                EventKind::EmergencyStop => palette.danger(),
+                EventKind::Annotation => palette.accent(),
+            };
+            let servo = event.event.servo().map(|id| format!("{} · ", state.label(id))).unwrap_or_default();
+            let text = egui::RichText::new(format!("{:>7.1}s  {}{}", event.elapsed, servo, event.event.summary()))
+                .color(color);
+            let covered = history_start.is_some_and(|start| event.elapsed >= start);
+            let response = ui.add(egui::Label::new(text).sense(egui::Sense::click()));
+            if covered && response.on_hover_text("Show in plots").clicked() {
+                clicked = Some(event.elapsed);
+            }
+        }
+    });
+    if clicked.is_some() {
+        state.plot_focus = clicked;
+    }
+}
+
+// Choix du port série : la sélection est envoyée au thread de monitoring
+fn draw_port_picker(ui: &mut egui::Ui, state: &mut AppState) {
+    let mut selected = state.port_name.clone();
+    egui::ComboBox::from_id_salt("port_picker")
+        .selected_text(&state.port_name)
+        .show_ui(ui, |ui| {
+            if state.available_ports.is_empty() {
+                ui.label("No serial port found");
+            }
+            for port in &state.available_ports {
+                ui.selectable_value(&mut selected, port.clone(), port);
+            }
+        });
+    if ui.small_button("⟳").on_hover_text("Refresh serial ports").clicked() {
+        state.available_ports = ports::list_ports();
+    }
+    if selected != state.port_name {
+        state.port_name = selected.clone();
+        state.send(ServoCommand::Connect { port: selected });
+    }
+}
+
+// Commande de couple envoyée : relecture au prochain cycle, ou état simulé en répétition
+fn torque_changed(state: &mut AppState, servo: &Driver, id: u8, enabled: bool, pending: &mut Option<u8>) {
+    if servo.dry_run() {
+        state.torque.insert(id, enabled);
+    } else {
+        *pending = Some(id);
+    }
+}
+
+// Échec d'une écriture ou d'une lecture que le worker ne rapporte pas autrement
+fn log_failure(state: &mut AppState, id: u8, what: &str, outcome: Result<(), String>) {
+    if let Err(e) = outcome {
+        state.events.push(Event::Error { servo: Some(id), message: format!("{}: {}", what, e) });
+    }
+}
+
+// Deux listes des IDs du dernier scan, et l'échange via un ID temporaire libre
+fn draw_swap_ids(ui: &mut egui::Ui, state: &mut AppState) {
+    let detected = state.servo_ids.clone();
+    let labels: BTreeMap<u8, String> = detected.iter().map(|&id| (id, state.label(id))).collect();
+    // Un ID disparu du scan n'est plus proposé
+    let (mut a, mut b) = state.swap_pair;
+    a = a.filter(|id| detected.contains(id));
+    b = b.filter(|id| detected.contains(id));
+    ui.horizontal(|ui| {
+        for (salt, choice) in [("swap_a", &mut a), ("swap_b", &mut b)] {
+            egui::ComboBox::from_id_salt(salt)
+                .selected_text(choice.map_or("ID…".to_string(), |id| labels[&id].clone()))
+                .show_ui(ui, |ui| {
+                    for &id in &detected {
+                        ui.selectable_value(choice, Some(id), &labels[&id]);
+                    }
+                });
+            if salt == "swap_a" {
+                ui.label("⇄");
+            }
+        }
+        let busy = state.operation.current().is_some();
+        let ready = matches!((a, b), (Some(a), Some(b)) if a != b);
+        if ui.add_enabled(ready && !busy, egui::Button::new("Swap")).clicked() {
+            if let (Some(a), Some(b)) = (a, b) {
+                state.send(ServoCommand::SwapIds { a, b });
+                state.swap_status = None;
+            }
+        }
+    });
+    state.swap_pair = (a, b);
+    ui.label("Three ID changes through a free temporary ID, each verified; undone if a step fails.");
+    if let Some(op) = state.operation.current().filter(|op| op.name == "Swap IDs") {
+        ui.label(format!("⏳ {}", op.step));
+    } else if let Some(status) = &state.swap_status {
+        ui.label(status);
+    }
+}
+
+// Table des registres du servo : valeurs lues, et saisie des registres modifiables
+fn draw_registers(ui: &mut egui::Ui, state: &mut AppState, id: u8) {
+    let palette = state.theme.palette();
+    let busy = state.operation.current().is_some();
+    ui.horizontal(|ui| {
+        if ui.add_enabled(!busy, egui::Button::new("Read all")).clicked() {
+            state.send(ServoCommand::ReadAllRegisters { id });
+            state.registers.status = Some("Reading...".to_string());
+        }
+        if let Some(op) = state.operation.current().filter(|op| op.name == "Write register") {
+            ui.label(format!("⏳ {}", op.step));
+        } else if let Some(status) = &state.registers.status {
+            ui.label(status);
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label("File:");
+        ui.text_edit_singleline(&mut state.registers.backup_path);
+        let path = state.registers.backup_path.trim().to_string();
+        if ui.add_enabled(!busy && !path.is_empty(), egui::Button::new("Export config")).clicked() {
+            state.send(ServoCommand::ExportConfig { id, path: path.clone() });
+            state.registers.status = Some("Exporting...".to_string());
+        }
+        if ui.add_enabled(!busy && !path.is_empty(), egui::Button::new("Import config")).clicked() {
+            let (include_id, force_model) = (state.registers.include_id, state.registers.force_model);
+            state.send(ServoCommand::ImportConfig { id, path, include_id, force_model });
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut state.registers.include_id, "Restore the ID")
+            .on_hover_text("Changes the servo ID to the one in the file, once the other registers are written");
+        ui.checkbox(&mut state.registers.force_model, "Allow another model");
+    });
+    egui::CollapsingHeader::new("Danger zone").show(ui, |ui| {
+        draw_factory_reset(ui, state, id);
+    });
+    if state.registers.id != Some(id) {
+        ui.label("Registers not read yet for this servo.");
+        return;
+    }
+
+    // L'EEPROM garde la valeur hors tension : écriture confirmée
+    if let Some((target, addr, value)) = state.registers.pending_eeprom {
+        let name = registers::register_at(addr).map_or("?", |r| r.name);
+        ui.horizontal(|ui| {
+            palette.status_label(
+                ui,
+                Status::Warning,
+                format!("Write {} = {} to the EEPROM of ID {}? It is kept after power-off.", name, value, target),
+            );
+            if ui.add_enabled(!busy, egui::Button::new("Confirm write")).clicked() {
+                state.send(ServoCommand::WriteRegister { id: target, addr, value });
+                state.registers.pending_eeprom = None;
+            }
+            if ui.button("Cancel").clicked() {
+                state.registers.pending_eeprom = None;
+            }
+        });
+    }
+
+    egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+        egui::Grid::new("register_map").num_columns(5).striped(true).show(ui, |ui| {
+            for header in ["Register", "Address", "Value", "New value", ""] {
+                ui.strong(header);
+            }
+            ui.end_row();
+            for register in registers::all_registers() {
+                let memory = if register.is_eeprom() { "EEPROM" } else { "RAM" };
+                ui.label(register.name).on_hover_text(format!("{}, {}", register.group.label(), memory));
+                ui.label(register.address.to_string());
+                let current = match state.registers.values.get(&register.address) {
+                    Some(Ok(value)) => {
+                        ui.label(value.to_string());
+                        Some(*value)
+                    }
+                    Some(Err(e)) => {
+                        ui.label("—").on_hover_text(e.as_str());
+                        None
+                    }
+                    None => {
+                        ui.label("");
+                        None
+                    }
+                };
+                match current.filter(|_| register.writable) {
+                    Some(current) => {
+                        let edit = state.registers.edits.entry(register.address).or_insert(current);
+                        ui.add(egui::DragValue::new(edit).range(register.min..=register.max));
+                        let value = *edit;
+                        if ui.add_enabled(value != current && !busy, egui::Button::new("Write")).clicked() {
+                            if register.is_eeprom() {
+                                state.registers.pending_eeprom = Some((id, register.address, value));
+                            } else {
+                                state.send(ServoCommand::WriteRegister { id, addr: register.address, value });
+                            }
+                        }
+                    }
+                    None => {
+                        ui.label("");
+                        ui.label("");
+                    }
+                }
+                ui.end_row();
+            }
+        });
+    });
+}
+
+// Retour aux réglages d'usine : l'ID du servo doit être retapé pour activer le bouton
+fn draw_factory_reset(ui: &mut egui::Ui, state: &mut AppState, id: u8) {
+    let palette = state.theme.palette();
+    palette.status_label(
+        ui,
+        Status::Danger,
+        format!("Factory reset restores every EEPROM register of ID {}: the ID reverts to 1, offset, limits and gains are lost.", id),
+    );
+    let busy = state.operation.current().is_some();
+    ui.horizontal(|ui| {
+        ui.label("Type the servo ID to confirm:");
+        ui.add(egui::TextEdit::singleline(&mut state.registers.reset_confirm).desired_width(40.0));
+        let confirmed = state.registers.reset_confirm.trim() == id.to_string();
+        if ui.add_enabled(confirmed && !busy, egui::Button::new("Factory reset")).clicked() {
+            state.send(ServoCommand::FactoryReset { id });
+            state.registers.reset_confirm.clear();
+            state.registers.status = None;
+        }
+        if let Some(op) = state.operation.current().filter(|op| op.name == "Factory reset") {
+            ui.label(format!("⏳ {}", op.step));
+        }
+    });
+}
+
+// Butées matérielles du servo : relevées au scan, ajustées à la main ou sur la position actuelle
+fn draw_angle_limits(ui: &mut egui::Ui, state: &mut AppState, id: u8) {
+    let palette = state.theme.palette();
+    let hardware = state.angle_limits.get(&id).copied();
+    match hardware {
+        Some(limits) if limits.is_disabled() => ui.label("Servo: disabled (0 / 0, continuous rotation)"),
+        Some(limits) => ui.label(format!(
+            "Servo: {} → {} ({} / {} ticks)",
+            state.angle.format(limits.min),
+            state.angle.format(limits.max),
+            limits.min,
+            limits.max
+        )),
+        None => ui.label("Servo: not read"),
+    };
+    let Some(hardware) = hardware else { return };
+
+    // Saisie repartie des valeurs du servo à chaque changement de sélection
+    let mut input = match state.angle_limits_input {
+        Some((input_id, input)) if input_id == id => input,
+        _ => hardware,
+    };
+    let position = state.servo_data.position;
+    ui.horizontal(|ui| {
+        ui.label("Min / max (ticks):");
+        ui.add(egui::DragValue::new(&mut input.min).range(0..=4095)).on_hover_text(state.angle.format(input.min));
+        ui.add(egui::DragValue::new(&mut input.max).range(0..=4095)).on_hover_text(state.angle.format(input.max));
+    });
+    ui.horizontal(|ui| {
+        if ui.add_enabled(position.is_some(), egui::Button::new("Set min = current position")).clicked() {
+            input.min = position.unwrap_or_default();
+        }
+        if ui.add_enabled(position.is_some(), egui::Button::new("Set max = current position")).clicked() {
+            input.max = position.unwrap_or_default();
+        }
+    });
+    state.angle_limits_input = Some((id, input));
+
+    let busy = state.operation.current().is_some();
+    ui.horizontal(|ui| {
+        let check = input.check();
+        let button = ui
+            .add_enabled(input != hardware && check.is_ok() && !busy, egui::Button::new("Write to servo"))
+            .on_hover_text("Min/Max Angle Limit registers (EEPROM), read back after writing");
+        if let Err(e) = check {
+            button.on_disabled_hover_text(e);
+        } else if button.clicked() {
+            state.pending_angle_limits = Some((id, input));
+            state.angle_limits_status = None;
+        }
+        if let Some(op) = state.operation.current().filter(|op| op.name == "Angle limits") {
+            ui.label(format!("⏳ {}", op.step));
+        } else if let Some(status) = &state.angle_limits_status {
+            ui.label(status);
+        }
+    });
+    if let Some((target, limits)) = state.pending_angle_limits.filter(|(target, _)| *target == id) {
+        ui.horizontal(|ui| {
+            palette.status_label(
+                ui,
+                Status::Warning,
+                format!("Write angle limits {}..{} to the EEPROM of ID {}?", limits.min, limits.max, target),
+            );
+            if ui.button("Confirm").clicked() {
+                state.send(ServoCommand::WriteAngleLimits { id: target, limits });
+                state.pending_angle_limits = None;
+            }
+            if ui.button("Cancel").clicked() {
+                state.pending_angle_limits = None;
+            }
+        });
+    }
+}
+
+// Effort max du servo (pince qui ne doit pas écraser), et charge rapportée à cette limite
+fn draw_torque_limit(ui: &mut egui::Ui, state: &mut AppState, id: u8) {
+    let Some(limit) = state.torque_limits.get(&id).copied() else {
+        ui.label("Servo: not read");
+        return;
+    };
+    let load = state.servo_data.load.unwrap_or_default();
+    ui.add(
+        egui::ProgressBar::new(limit.load_ratio(load))
+            .desired_width(200.0)
+            .text(format!("Load {:+.1}% of {:.1}% allowed", load, limit.percent())),
+    );
+
+    let mut input = match state.torque_limit_input {
+        Some((input_id, input)) if input_id == id => input,
+        _ => limit.percent(),
+    };
+    let busy = state.operation.current().is_some();
+    ui.horizontal(|ui| {
+        let slider = ui
+            .add_enabled(!busy, egui::Slider::new(&mut input, 0.0..=100.0).suffix(" %").fixed_decimals(1))
+            .on_hover_text("Torque Limit register (RAM): reset to Max Torque at power-up");
+        // Écrite au relâchement, relue par le worker
+        if slider.drag_stopped() || (slider.changed() && !slider.is_pointer_button_down_on()) {
+            let limit = TorqueLimit::from_percent(input);
+            state.send(ServoCommand::WriteTorqueLimit { id, limit });
+            state.torque_limit_status = None;
+        }
+        if let Some(op) = state.operation.current().filter(|op| op.name == "Torque limit") {
+            ui.label(format!("⏳ {}", op.step));
+        } else if let Some(status) = &state.torque_limit_status {
+            ui.label(status);
+        }
+    });
+    state.torque_limit_input = Some((id, input));
+}
+
+// Gains P/I/D du servo, et essai ±200 ticks autour de la position actuelle
+fn draw_pid_tuning(ui: &mut egui::Ui, state: &mut AppState, id: u8) {
+    // Gains lus à la première ouverture du panneau pour ce servo
+    if state.pid.id != Some(id) {
+        state.pid = PidPanel { id: Some(id), ..PidPanel::default() };
+        state.send(ServoCommand::ReadPid { id });
+        state.pid.status = Some("Reading gains...".to_string());
+    }
+    let busy = state.operation.current().is_some();
+    ui.horizontal(|ui| {
+        let input = &mut state.pid.input;
+        for (label, value) in [("P", &mut input.p), ("I", &mut input.i), ("D", &mut input.d)] {
+            ui.label(label);
+            ui.add(egui::DragValue::new(value).range(0..=254));
+        }
+        match state.pid.read {
+            Some(read) => ui.label(format!("servo: {} / {} / {}", read.p, read.i, read.d)),
+            None => ui.label("servo: not read"),
+        };
+    });
+    ui.horizontal(|ui| {
+        let changed = state.pid.read != Some(state.pid.input);
+        if ui.add_enabled(changed && !busy, egui::Button::new("Write")).clicked() {
+            state.send(ServoCommand::WritePid { id, gains: state.pid.input });
+        }
+        let factory = PidGains::factory();
+        if ui
+            .add_enabled(state.pid.read != Some(factory) && !busy, egui::Button::new("Restore defaults"))
+            .on_hover_text(format!("Factory gains: {} / {} / {}", factory.p, factory.i, factory.d))
+            .clicked()
+        {
+            state.pid.input = factory;
+            state.send(ServoCommand::WritePid { id, gains: factory });
+        }
+        if ui.button("Re-read").clicked() {
+            state.send(ServoCommand::ReadPid { id });
+        }
+        if ui
+            .add_enabled(!busy, egui::Button::new("Step test"))
+            .on_hover_text(format!("Torque on, moves ±{} ticks around the current position", tuning::STEP_TICKS))
+            .clicked()
+        {
+            state.send(ServoCommand::StepTest { id });
+        }
+    });
+    if let Some(op) = state.operation.current().filter(|op| op.name == "Step test" || op.name == "PID gains") {
+        ui.label(format!("⏳ {}", op.step));
+    } else if let Some(status) = &state.pid.status {
+        ui.label(status);
+    }
+
+    let Some(response) = &state.pid.response else { return };
+    ui.label(format!(
+        "Overshoot: {} ticks, settle time: {:.0} ms (worst step)",
+        response.overshoot_ticks,
+        response.settle_time_s * 1000.0
+    ));
+    let palette = state.theme.palette();
+    let end = response.samples.last().map_or(0.0, |(t, _)| *t);
+    // Consigne en escalier, jusqu'à la consigne suivante
+    let mut steps: Vec<[f64; 2]> = Vec::new();
+    for (i, &(t, target)) in response.targets.iter().enumerate() {
+        let until = response.targets.get(i + 1).map_or(end, |(next, _)| *next);
+        steps.push([t, f64::from(target)]);
+        steps.push([until, f64::from(target)]);
+    }
+    Plot::new("step_response_plot")
+        .height(150.0)
+        .view_aspect(2.0)
+        .legend(Legend::default())
+        .show(ui, |plot_ui| {
+            plot_ui.line(Line::new("Target", PlotPoints::from(steps)).color(palette.trace(1)).style(LineStyle::dashed_loose()));
+            let points: PlotPoints = response.samples.iter().map(|&(t, pos)| [t, f64::from(pos)]).collect();
+            plot_ui.line(Line::new("Position", points).color(palette.trace(0)));
+        });
+}
+
+// Lecture de toute la table ; un servo muet dès le premier registre est une erreur
+fn read_registers(bus: &mut RegisterPort, id: u8) -> Result<BTreeMap<u8, Result<i32, String>>, String> {
+    let mut values = BTreeMap::new();
+    for register in registers::all_registers() {
+        let read = bus.read(id, register);
+        if let (true, Err(e)) = (values.is_empty(), &read) {
+            return Err(e.clone());
+        }
+        values.insert(register.address, read);
+    }
+    Ok(values)
+}
+
+// Contrôle de doublon d'un scan complet : remplace celui du scan précédent
+fn set_duplicates(state: &mut AppState, mut duplicates: Vec<u8>) {
+    duplicates.sort_unstable();
+    duplicates.dedup();
+    for &id in duplicates.iter().filter(|id| !state.duplicate_ids.contains(id)) {
+        println!("/!\\ ID {} : plusieurs servos semblent répondre, n'en gardez qu'un branché", id);
+        state.events.push(Event::AlertRaised {
+            servo: Some(id),
+            message: "possible duplicate ID: moves and ID change blocked".to_string(),
+        });
+    }
+    let resolved: Vec<u8> = state.duplicate_ids.iter().copied().filter(|id| !duplicates.contains(id)).collect();
+    for id in resolved {
+        state.events.push(Event::AlertCleared { servo: Some(id), message: "duplicate ID resolved".to_string() });
+    }
+    state.duplicate_ids = duplicates;
+}
+
+// Blocage confirmé : arrêt selon le réglage, servo marqué bloqué jusqu'à l'acquittement
+fn stop_stalled(state: &mut AppState, servo: &Driver, id: u8, stall: Stall) {
+    let action = state.stall_settings.action;
+    match action {
+        StallAction::Hold => {
+            if let Some(pos) = state.servo_data.position {
+                let outcome = servo.move_to(id, pos, 0, state.acceleration, false).map(|_| ()).ok_or_else(|| "no response".to_string());
+                log_failure(state, id, "stall hold", outcome);
+                state.target_position = pos;
+            }
+        }
+        StallAction::TorqueOff => {
+            let outcome = servo.disable_torque(id);
+            log_failure(state, id, "stall torque off", outcome);
+            state.torque.insert(id, false);
+        }
+    }
+    state.stalls.insert(id, stall);
+    state.pending_large_move = None;
+    state.events.push(Event::AlertRaised {
+        servo: Some(id),
+        message: format!("stalled ({}): {}", stall.describe(), action.label().to_lowercase()),
+    });
+    state.sounds.notify(SoundClass::Stall);
+}
+
+// --- MANETTE ---
+// Cette interface n'a ni groupes ni mode roue : seuls les servos détectés sont proposés
+fn draw_gamepad(ui: &mut egui::Ui, state: &mut AppState) -> bool {
+    let targets: Vec<(BindingTarget, String)> = state.servo_ids.iter().map(|&id| (BindingTarget::Servo(id), state.label(id))).collect();
+    let status = state.pad_status.clone();
+    let changed = gamepad::mapping_panel(ui, &mut state.gamepad, &targets, &status);
+    ui.separator();
+    ui.strong("Calibration");
+    let servos: Vec<(u8, String)> = state.servo_ids.iter().map(|&id| (id, state.label(id))).collect();
+    let outcome = gamepad::calibration_panel(ui, &mut state.gamepad, &servos, &status, &mut state.pad_calibration);
+    if let Some((id, position)) = outcome.test_move {
+        if state.selected_servo == Some(id) {
+            state.target_position = position;
+        }
+        let command = Command::Move { id, position, speed: gamepad::CALIBRATION_TEST_SPEED, acceleration: state.acceleration, acknowledge_large: false };
+        let _ = state.command_sender.send(Timed::new(SOURCE_GAMEPAD, ServoCommand::Servo(command)));
+    }
+    changed || outcome.changed
+}
+
+// Échantillon de la manette en consignes Move. Le jog part de la position lue ; l'opérateur tient
+// l'homme mort, la garde du premier mouvement ne s'applique pas.
+fn gamepad_commands(state: &Mutex<AppState>, servo: &Driver, pad: &mut Gamepad, controller: &mut PadController) -> Vec<Timed<ServoCommand>> {
+    let sample = pad.sample();
+    let mut state = state.lock().unwrap();
+    let deadman_held = sample.as_ref().is_some_and(|(_, pad)| pad.is_pressed(state.gamepad.deadman_button));
+    state.pad_status = PadStatus { connected: sample.as_ref().map(|(name, _)| name.clone()), deadman_held, sample: sample.as_ref().map(|(_, pad)| pad.clone()) };
+    let members = |target: &BindingTarget| match target {
+        BindingTarget::Servo(id) if state.servo_ids.contains(id) => vec![*id],
+        _ => Vec::new(),
+    };
+    let movable = |id: u8| {
+        if state.estop.is_stopped(id) || state.thermal.is_locked(id) || state.stalls.contains_key(&id) {
+            return None;
+        }
+        let current = match state.selected_servo {
+            Some(selected) if selected == id => state.servo_data.position,
+            _ => servo.read_position(id),
+        };
+        Some((current?, state.limits.get(&id).copied().unwrap_or_default()))
+    };
+    let commands = controller.update(&state.gamepad, sample.as_ref().map(|(_, pad)| pad), Instant::now(), members, movable);
+    let mut moves = Vec::new();
+    for command in commands {
+        // Les liaisons en vitesse visent le mode roue, absent de cette interface
+        let PadCommand::Move { id, position } = command else { continue };
+        if state.selected_servo == Some(id) {
+            state.target_position = position;
+        }
+        let (speed, acceleration) = (state.target_speed, state.acceleration);
+        moves.push(Timed::new(SOURCE_GAMEPAD, ServoCommand::Servo(Command::Move { id, position, speed, acceleration, acknowledge_large: false })));
+    }
+    moves
+}
+
+// Butées et verrous d'un servo, vérifiés avant chaque consigne
+fn move_constraints(state: &AppState, id: u8) -> MoveConstraints {
+    MoveConstraints {
+        limits: state.limits.get(&id).copied().unwrap_or_default(),
+        cut_off: state.thermal.is_locked(id),
+        emergency_stop: state.estop.is_stopped(id),
+        stalled: state.stalls.contains_key(&id),
+        duplicate_id: state.duplicate_ids.contains(&id),
+        ..Default::default()
+    }
+}
+
+fn monitoring_thread(
+    state: Arc<Mutex<AppState>>,
+    ctx: egui::Context,
+    rx: Receiver<Timed<ServoCommand>>,
+    responder: Responder,
+    mut worker: ServoWorker,
+    shutdown: ShutdownSignal,
+) {
+    let dry_run = state.lock().unwrap().dry_run.clone();
+    let recorder = worker.recorder();
+    let inversions = worker.inversions();
+    let poll_interval = Config::load().bus.poll_interval(POLL_INTERVAL);
     let mut cycle_count = 0u32;
     let mut cached_servo_ids: Vec<u8> = Vec::new();
+    // Garde du premier Move réarmée à chaque changement de sélection ou reconnexion ; couple
+    // activé avant chaque mouvement
+    let mut dispatcher = Dispatcher::new(state.lock().unwrap().first_move_guard);
+    dispatcher.set_torque_on_move(true);
+    let mut guarded_servo: Option<u8> = None;
+    // Alertes en cours, pour ne sonner qu'au franchissement du seuil
+    let mut over_temperature = false;
+    let mut stalled_move: Option<Instant> = None;
+    let mut displayed: Option<DisplayedState> = None;
+    // Commandes reçues, en attente d'une connexion
+    let mut backlog: VecDeque<Timed<ServoCommand>> = VecDeque::new();
+    // Demandes de scan auxquelles répondre à la fin du balayage en cours
+    let mut scan_requests: Vec<CommandId> = Vec::new();
+    // Servo dont le couple doit être relu au plus vite (commande envoyée), et dernier servo relu
+    let mut torque_pending: Option<u8> = None;
+    let mut torque_checked: Option<u8> = None;
+    let mut telemetry_log: Option<TelemetryLog> = None;
+    // Servos dont les butées matérielles ont été lues depuis leur détection
+    let mut limits_checked: Vec<u8> = Vec::new();
+    // Manette lue à chaque cycle (gilrs se crée dans le thread qui la lit)
+    let mut pad = Gamepad::new();
+    let mut pad_controller = PadController::default();
+    let mut move_queue = MoveQueue::default();
     
     loop {
-        // Essayer de se connecter si pas de connexion
-        if servo_connection.is_none() {
-            servo_connection = ST3215::new(PORT).ok();
-            if servo_connection.is_some() {
-                // Scanner les servos au démarrage
-                if let Some(ref servo) = servo_connection {
-                    cached_servo_ids = servo.list_servos();
+        // Fenêtre fermée : la transaction précédente est finie, la file est abandonnée
+        if shutdown.is_requested() {
+            let settings = state.lock().unwrap().exit.clone();
+            if let Some(servo) = worker.driver().filter(|_| !settings.is_noop()) {
+                for step in shutdown::park_and_release(servo, &cached_servo_ids, &settings) {
+                    match step.outcome {
+                        Ok(()) => println!("ID {}: {} on exit", step.id, step.action.label()),
+                        Err(e) => eprintln!("ID {}: {} on exit failed: {}", step.id, step.action.label(), e),
+                    }
+                }
+            }
+            recorder.stop();
+            return;
+        }
+        let mut raw_request: Option<Vec<u8>> = None;
+        let mut fast_scan = false;
+        // Scan complet demandé (vrai) ou annulé (faux) : la dernière demande du cycle l'emporte
+        let mut full_scan: Option<bool> = None;
+        // Consigne restée sans réponse, comptée avec les relevés muets
+        let mut send_failed = false;
+        // Lecture ou écriture de l'éditeur de registres, faite hors de l'emprunt de la connexion
+        let mut register_request: Option<ServoCommand> = None;
+        let mut torque_read: Option<u8> = None;
+        let mut load_read: Option<u8> = None;
+        // Relevés du cycle pour le journal continu (la charge est complétée en fin de cycle)
+        let mut log_frame: Option<TelemetryFrame> = None;
+        // Commande traitée pendant ce cycle : les messages de statut ont pu changer
+        let mut handled = false;
+        let mut link_lost = false;
+
+        // Un changement de port s'applique tout de suite, même déconnecté
+        let mut requested_port = None;
+        backlog.extend(rx.try_iter());
+        // Consignes de la manette après celles de l'interface, soumises au même arrêt d'urgence
+        if let Some(servo) = worker.driver() {
+            backlog.extend(gamepad_commands(&state, servo, &mut pad, &mut pad_controller));
+        }
+        backlog.retain(|timed| match &timed.command {
+            ServoCommand::Connect { port } => {
+                requested_port = Some(port.clone());
+                false
+            }
+            _ => true,
+        });
+
+        // Arrêt d'urgence : traité avant la file, dont les consignes de mouvement sont abandonnées
+        let emergency = estop::take_emergency(
+            &mut backlog,
+            |timed| matches!(timed.command, ServoCommand::Servo(Command::EmergencyStop)),
+            |timed| match &timed.command {
+                ServoCommand::Servo(command) => command.is_motion(),
+                ServoCommand::QueueMove { .. } => true,
+                _ => false,
+            },
+        );
+        if emergency {
+            let failures = match worker.driver().map(|servo| dispatcher.execute(servo, &Command::EmergencyStop, |id| move_constraints(&state.lock().unwrap(), id), &cached_servo_ids)) {
+                Some(Ok(Executed::Stopped { failures })) => failures,
+                _ => Vec::new(),
+            };
+            let mut state = state.lock().unwrap();
+            for (id, e) in failures {
+                log_failure(&mut state, id, "emergency stop torque off", Err(e));
+            }
+            for &id in &cached_servo_ids {
+                state.torque.insert(id, false);
+            }
+            state.estop.trigger(cached_servo_ids.iter().copied());
+            state.pending_large_move = None;
+            move_queue.clear_all();
+            state.queued_moves.clear();
+            state.events.push(Event::EmergencyStop);
+            state.sounds.notify(SoundClass::EmergencyStop);
+            handled = true;
+        }
+
+        // Choix fait dans la fenêtre de conflit de port
+        let mut force_lock = false;
+        let port_choice = state.lock().unwrap().port_choice.take();
+        match port_choice {
+            Some(ConflictChoice::SwitchPort(new_port)) => requested_port = Some(new_port),
+            Some(ConflictChoice::TakeOver) => force_lock = true,
+            None => {}
+        }
+        if let Some(new_port) = requested_port.filter(|p| p != worker.port()) {
+            scan_requests.clear();
+            responder.new_epoch();
+            cached_servo_ids.clear();
+            let mut state = state.lock().unwrap();
+            state.connected = false;
+            state.reconnecting = false;
+            state.scan_progress = None;
+            state.servo_ids.clear();
+            state.selected_servo = None;
+            state.port_name = new_port.clone();
+            state.events.push(Event::PortChanged { from: worker.port().to_string(), to: new_port.clone() });
+            // L'ancien port est fermé avant d'ouvrir le nouveau
+            worker.set_port(new_port);
+            handled = true;
+        }
+
+        // Connexion, ou reconnexion après une coupure, si aucune autre instance ne tient le port
+        {
+            let state = state.lock().unwrap();
+            worker.set_rescan(state.rescan.clone());
+            worker.set_stall(state.stall_settings.clone());
+        }
+        let events = worker.maintain(force_lock);
+        let conflict = events.iter().find_map(|event| match event {
+            WorkerEvent::PortConflict(owner) => Some(owner.clone()),
+            _ => None,
+        });
+        if conflict.is_some() {
+            ctx.request_repaint();
+        }
+        state.lock().unwrap().port_conflict = conflict;
+        for event in events {
+            match event {
+                WorkerEvent::Connected { port } => {
+                    // Les commandes restées en file pendant la coupure ne sont plus attendues par l'interface
+                    responder.new_epoch();
+                    cached_servo_ids.clear();
+                    move_queue.clear_all();
                     let mut state = state.lock().unwrap();
+                    state.queued_moves.clear();
+                    // La sélection est conservée : le suivi reprend dès que le scan retrouve le servo
                     state.connected = true;
-                    state.servo_ids = cached_servo_ids.clone();
+                    state.reconnecting = false;
+                    state.servo_ids.clear();
+                    state.pending_large_move = None;
+                    state.events.push(Event::Connected { port });
+                    dispatcher.arm_first_moves();
+                }
+                WorkerEvent::PortMoved { from, to } => {
+                    let mut state = state.lock().unwrap();
+                    state.port_name = to.clone();
+                    state.events.push(Event::PortChanged { from, to });
                 }
+                _ => {}
             }
         }
         
-        if let Some(ref servo) = servo_connection {
+        if let Some(servo) = worker.driver() {
+            // Files de consignes : la suivante part, par le chemin de Move, quand la précédente est atteinte
+            for id in move_queue.ids() {
+                let now = Instant::now();
+                match move_queue.poll(id, now, || (servo.is_moving(id), servo.read_position(id))) {
+                    QueueStep::Wait => {}
+                    QueueStep::Next(queued) => {
+                        // Garde du premier mouvement évaluée ici : un refus dans Move laisserait la file attendre un but jamais envoyé
+                        dispatcher.set_first_move_delta(state.lock().unwrap().first_move_guard);
+                        let first_move = match queued.acknowledge_large {
+                            true => None,
+                            false => dispatcher.first_move_check(id, || servo.read_position(id)),
+                        };
+                        let constraints = MoveConstraints { first_move, ..move_constraints(&state.lock().unwrap(), id) };
+                        match validate_move(&constraints, queued.position.into(), queued.speed.into(), queued.acceleration.into()) {
+                            Ok(m) => {
+                                let distance = servo.read_position(id).map_or(units::MAX_TICKS, |pos| pos.abs_diff(m.position));
+                                let timeout = estimate_move_duration(distance, m.speed, m.acceleration) + QUEUE_TIMEOUT_MARGIN;
+                                move_queue.started(id, m.position, timeout, now);
+                                // Garde déjà passée ci-dessus
+                                backlog.push_back(Timed::new(SOURCE_WORKER, ServoCommand::Servo(Command::Move {
+                                    id,
+                                    position: m.position,
+                                    speed: m.speed,
+                                    acceleration: m.acceleration,
+                                    acknowledge_large: true,
+                                })));
+                            }
+                            Err(e) => {
+                                let dropped = move_queue.clear(id) + 1;
+                                let outcome = Err(format!("{}; {} queued move(s) dropped", e, dropped));
+                                let mut state = state.lock().unwrap();
+                                if let ValidationError::LargeFirstMove { current, .. } = e {
+                                    let QueuedMove { position, speed, acceleration, .. } = queued;
+                                    state.pending_large_move = Some(PendingLargeMove { id, position, speed, acceleration, current });
+                                }
+                                state.events.push(Event::command(Some(id), format!("Queued move → {}", queued.position), outcome));
+                            }
+                        }
+                    }
+                    QueueStep::TimedOut { goal, dropped } => {
+                        state.lock().unwrap().events.push(Event::AlertRaised {
+                            servo: Some(id),
+                            message: format!("queued move to {} not reached in time, {} queued move(s) dropped", goal, dropped),
+                        });
+                    }
+                }
+            }
+            state.lock().unwrap().queued_moves = move_queue.depths();
             // Traiter toutes les commandes en attente
-            while let Ok(cmd) = rx.try_recv() {
+            while let Some(Timed { id: command_id, source, enqueued, command: cmd }) = backlog.pop_front() {
+                handled = true;
+                log::debug!(
+                    target: logging::WORKER,
+                    "#{} from {} after {} µs in queue: {:?}",
+                    command_id,
+                    source,
+                    enqueued.elapsed().as_micros(),
+                    cmd
+                );
+                recorder.command(source, &cmd);
                 match cmd {
-                    ServoCommand::Move { id, position, speed, acceleration } => {
-                        // Activer le torque avant de bouger
-                        let _ = servo.enable_torque(id);
-                        thread::sleep(Duration::from_millis(10));
-                        let _ = servo.move_to(id, position, speed, acceleration, false);
+                    // Déjà appliqué en début de cycle
+                    ServoCommand::Servo(Command::EmergencyStop) => {}
+                    ServoCommand::Servo(command) => {
+                        let (selected, max_delta) = {
+                            let state = state.lock().unwrap();
+                            (state.selected_servo, state.first_move_guard)
+                        };
+                        if selected != guarded_servo {
+                            guarded_servo = selected;
+                            dispatcher.arm_first_moves();
+                        }
+                        dispatcher.set_first_move_delta(max_delta);
+                        // Verrou thermique levé seulement une fois le servo redescendu sous le réarmement
+                        if let Command::Torque { id, enable: true } = command {
+                            let released = {
+                                let state = state.lock().unwrap();
+                                state.thermal.release(&state.derating, id)
+                            };
+                            if let Err(e) = released {
+                                responder.send(command_id, "torque on", Some(id), Err(e.clone()));
+                                let mut state = state.lock().unwrap();
+                                state.events.push(Event::command(Some(id), "Torque ON", Err(e)));
+                                torque_changed(&mut state, servo, id, false, &mut torque_pending);
+                                continue;
+                            }
+                        }
+                        let executed = dispatcher.execute(servo, &command, |id| move_constraints(&state.lock().unwrap(), id), &cached_servo_ids);
+                        match executed {
+                            Ok(Executed::Moved { id, accepted: m, start, outcome }) => {
+                                let mut state = state.lock().unwrap();
+                                if m.clamped {
+                                    let limits = move_constraints(&state, id).limits;
+                                    let requested = match command {
+                                        Command::Move { position, .. } => position,
+                                        _ => m.position,
+                                    };
+                                    state.events.push(Event::AlertRaised {
+                                        servo: Some(id),
+                                        message: format!("move clamped {} → {} (soft limits {}-{})", requested, m.position, limits.min, limits.max),
+                                    });
+                                }
+                                send_failed |= outcome.is_err();
+                                if let (Ok(()), Some(start)) = (&outcome, start) {
+                                    state.last_move_timing = Some(MoveTiming {
+                                        id,
+                                        started: Instant::now(),
+                                        estimated: estimate_move_duration(start.abs_diff(m.position), m.speed, m.acceleration),
+                                        measured: None,
+                                        target: m.position,
+                                        final_position: None,
+                                    });
+                                }
+                                state.events.push(Event::command(Some(id), format!("Move → {}", m.position), outcome));
+                                torque_changed(&mut state, servo, id, true, &mut torque_pending);
+                            }
+                            Ok(Executed::Torque { id, enable, outcome }) => {
+                                let mut state = state.lock().unwrap();
+                                let enabled = enable && outcome.is_ok();
+                                if enabled {
+                                    state.estop.release(id);
+                                }
+                                let (what, summary) = match enable {
+                                    true => ("torque on", "Torque ON"),
+                                    false => ("torque off", "Torque OFF"),
+                                };
+                                responder.send(command_id, what, Some(id), outcome.clone());
+                                state.events.push(Event::command(Some(id), summary, outcome));
+                                torque_changed(&mut state, servo, id, enabled, &mut torque_pending);
+                            }
+                            Ok(Executed::Stopped { .. }) => unreachable!("emergency stop handled before the backlog"),
+                            Err(e) => {
+                                let Command::Move { id, position, speed, acceleration, .. } = command else { continue };
+                                let mut state = state.lock().unwrap();
+                                if let ValidationError::LargeFirstMove { current, .. } = e {
+                                    state.pending_large_move = Some(PendingLargeMove { id, position, speed, acceleration, current });
+                                }
+                                state.events.push(Event::command(Some(id), format!("Move → {}", position), Err(e.to_string())));
+                            }
+                        }
+                    }
+                    ServoCommand::QueueMove { id, position, speed, acceleration, acknowledge_large } => {
+                        move_queue.push(id, QueuedMove { position, speed, acceleration, acknowledge_large });
+                    }
+                    ServoCommand::ClearQueue { id } => {
+                        let dropped = move_queue.clear(id);
+                        state.lock().unwrap().events.push(Event::command(Some(id), format!("Clear queue ({} move(s))", dropped), Ok(())));
+                    }
+                    ServoCommand::ScanServos { exhaustive: false } => {
+                        fast_scan = true;
+                        scan_requests.push(command_id);
+                    }
+                    ServoCommand::ScanServos { exhaustive: true } => {
+                        scan_requests.push(command_id);
+                        full_scan = Some(true);
+                    }
+                    ServoCommand::CancelScan => full_scan = Some(false),
+                    ServoCommand::CaptureSnapshot { id, label, sequence, path } => {
+                        // La capture mesure une réponse réelle : sans objet en répétition. Elle
+                        // fait bouger le servo, donc arrêt d'urgence et verrou thermique la bloquent
+                        // comme un jog.
+                        let (blocked, constraints) = {
+                            let state = state.lock().unwrap();
+                            let blocked = if servo.dry_run() {
+                                Err("not available in dry run".to_string())
+                            } else if state.estop.is_stopped(id) {
+                                Err(ValidationError::EmergencyStop.to_string())
+                            } else if state.thermal.is_locked(id) {
+                                Err(ValidationError::OverheatCutOff.to_string())
+                            } else {
+                                Ok(())
+                            };
+                            (blocked, move_constraints(&state, id))
+                        };
+                        let outcome = blocked
+                            .and_then(|_| snapshot::capture(servo.backend(), id, &label, &sequence, &constraints))
+                            .and_then(|snap| snap.save(std::path::Path::new(&path)).map(|_| snap.samples.len()));
+                        let mut state = state.lock().unwrap();
+                        state.snapshot_status = Some(match &outcome {
+                            Ok(count) => format!("✓ {} samples saved to {}", count, path),
+                            Err(e) => format!("✗ {}", e),
+                        });
+                        state.events.push(Event::command(Some(id), format!("Snapshot '{}'", label), outcome.map(|_| ())));
+                    }
+                    ServoCommand::RawInstruction { frame } => {
+                        if packet::is_destructive(frame[4]) {
+                            let mut state = state.lock().unwrap();
+                            if let Err(e) = state.operation.begin(frame[2], format!("Raw {}", packet::instruction_name(frame[4]))) {
+                                state.console_result = Some(format!("✗ {}", e));
+                                state.events.push(Event::command(Some(frame[2]), "Raw instruction", Err(e)));
+                                continue;
+                            }
+                            state.operation.progress("sending");
+                        }
+                        if servo.dry_run() && packet::is_destructive(frame[4]) {
+                            let summary = format!("Raw {} [{}]", packet::instruction_name(frame[4]), packet::to_hex(&frame));
+                            log::info!(target: logging::DRY_RUN, "{}", summary);
+                            let mut state = state.lock().unwrap();
+                            state.console_result = Some("[dry run] not sent".to_string());
+                            state.events.push(Event::command(Some(frame[2]), summary, Ok(())));
+                            state.operation.finish();
+                            continue;
+                        }
+                        // Traité hors de l'emprunt de la connexion (voir plus bas)
+                        raw_request = Some(frame);
+                        break;
+                    }
+                    // Déjà appliqués en début de cycle
+                    ServoCommand::Connect { .. } => {}
+                    ServoCommand::ReadAllRegisters { id } => register_request = Some(ServoCommand::ReadAllRegisters { id }),
+                    ServoCommand::ExportConfig { id, path } => register_request = Some(ServoCommand::ExportConfig { id, path }),
+                    ServoCommand::WriteAngleLimits { id, limits } => {
+                        let mut state = state.lock().unwrap();
+                        let check = limits.check().and_then(|_| match state.duplicate_ids.contains(&id) {
+                            true => Err(format!("ID {} may be shared by several servos", id)),
+                            false => state.operation.begin(id, "Angle limits"),
+                        });
+                        let summary = format!("Angle limits {}..{}", limits.min, limits.max);
+                        if let Err(e) = check {
+                            state.angle_limits_status = Some(format!("✗ {}", e));
+                            state.events.push(Event::command(Some(id), summary, Err(e)));
+                            continue;
+                        }
+                        if servo.dry_run() {
+                            log::info!(target: logging::DRY_RUN, "ID {}: angle limits {}..{}", id, limits.min, limits.max);
+                            state.angle_limits_status = Some("[dry run] not written".to_string());
+                            state.events.push(Event::command(Some(id), summary, Ok(())));
+                            state.operation.finish();
+                            continue;
+                        }
+                        state.operation.progress("writing limits");
+                        register_request = Some(ServoCommand::WriteAngleLimits { id, limits });
+                    }
+                    ServoCommand::FactoryReset { id } => {
+                        let mut state = state.lock().unwrap();
+                        // Le servo repasse en ID 1 : un autre servo sous cet ID répondrait avec lui
+                        let check = match (state.duplicate_ids.contains(&id), id != 1 && state.servo_ids.contains(&1)) {
+                            (true, _) => Err(format!("ID {} may be shared by several servos", id)),
+                            (_, true) => Err("a servo already answers as ID 1: unplug it before the reset".to_string()),
+                            _ => state.operation.begin(id, "Factory reset"),
+                        };
+                        if let Err(e) = check {
+                            state.registers.status = Some(format!("✗ {}", e));
+                            state.events.push(Event::command(Some(id), "Factory reset", Err(e)));
+                            continue;
+                        }
+                        if servo.dry_run() {
+                            log::info!(target: logging::DRY_RUN, "ID {}: factory reset", id);
+                            state.registers.status = Some("[dry run] reset not sent".to_string());
+                            state.events.push(Event::command(Some(id), "Factory reset", Ok(())));
+                            state.operation.finish();
+                            continue;
+                        }
+                        state.operation.progress("resetting");
+                        register_request = Some(ServoCommand::FactoryReset { id });
+                    }
+                    ServoCommand::WriteTorqueLimit { id, limit } => {
+                        let mut state = state.lock().unwrap();
+                        let check = match state.duplicate_ids.contains(&id) {
+                            true => Err(format!("ID {} may be shared by several servos", id)),
+                            false => state.operation.begin(id, "Torque limit"),
+                        };
+                        let summary = format!("Torque limit {:.1}%", limit.percent());
+                        if let Err(e) = check {
+                            state.torque_limit_status = Some(format!("✗ {}", e));
+                            state.events.push(Event::command(Some(id), summary, Err(e)));
+                            continue;
+                        }
+                        if servo.dry_run() {
+                            log::info!(target: logging::DRY_RUN, "ID {}: torque limit {:.1}%", id, limit.percent());
+                            state.torque_limit_status = Some("[dry run] not written".to_string());
+                            state.events.push(Event::command(Some(id), summary, Ok(())));
+                            state.operation.finish();
+                            continue;
+                        }
+                        state.operation.progress("writing limit");
+                        register_request = Some(ServoCommand::WriteTorqueLimit { id, limit });
+                    }
+                    ServoCommand::ReadPid { id } => register_request = Some(ServoCommand::ReadPid { id }),
+                    ServoCommand::WritePid { id, gains } => {
+                        let mut state = state.lock().unwrap();
+                        let check = match state.duplicate_ids.contains(&id) {
+                            true => Err(format!("ID {} may be shared by several servos", id)),
+                            false => state.operation.begin(id, "PID gains"),
+                        };
+                        let summary = format!("PID gains {} / {} / {}", gains.p, gains.i, gains.d);
+                        if let Err(e) = check {
+                            state.pid.status = Some(format!("✗ {}", e));
+                            state.events.push(Event::command(Some(id), summary, Err(e)));
+                            continue;
+                        }
+                        if servo.dry_run() {
+                            log::info!(target: logging::DRY_RUN, "ID {}: PID gains {} / {} / {}", id, gains.p, gains.i, gains.d);
+                            state.pid.status = Some("[dry run] not written".to_string());
+                            state.events.push(Event::command(Some(id), summary, Ok(())));
+                            state.operation.finish();
+                            continue;
+                        }
+                        state.operation.progress("writing gains");
+                        register_request = Some(ServoCommand::WritePid { id, gains });
+                    }
+                    ServoCommand::StepTest { id } => {
+                        let (allowed, constraints) = {
+                            let mut state = state.lock().unwrap();
+                            // La réponse mesurée n'a pas de sens en répétition
+                            let check = if servo.dry_run() {
+                                Err("not available in dry run".to_string())
+                            } else if state.duplicate_ids.contains(&id) {
+                                Err(format!("ID {} may be shared by several servos", id))
+                            } else if state.stalls.contains_key(&id) {
+                                Err(format!("ID {} is stalled: clear the stall first", id))
+                            } else if state.estop.is_stopped(id) {
+                                Err(ValidationError::EmergencyStop.to_string())
+                            } else if state.thermal.is_locked(id) {
+                                Err(ValidationError::OverheatCutOff.to_string())
+                            } else {
+                                state.operation.begin(id, "Step test")
+                            };
+                            if let Err(e) = check {
+                                state.pid.status = Some(format!("✗ {}", e));
+                                state.events.push(Event::command(Some(id), "Step test", Err(e)));
+                                continue;
+                            }
+                            state.operation.progress("recording");
+                            let limits = state.limits.get(&id).copied().unwrap_or_default();
+                            (limits.range_within(state.angle_limits.get(&id)), move_constraints(&state, id))
+                        };
+                        let outcome = tuning::step_response(servo.backend(), id, &allowed, &constraints);
+                        let mut state = state.lock().unwrap();
+                        match outcome {
+                            Ok(response) => {
+                                state.torque.insert(id, true);
+                                let summary = format!(
+                                    "Step test: overshoot {} ticks, settle {:.0} ms",
+                                    response.overshoot_ticks,
+                                    response.settle_time_s * 1000.0
+                                );
+                                state.events.push(Event::command(Some(id), summary, Ok(())));
+                                state.pid.status = Some(format!("✓ {} samples", response.samples.len()));
+                                if state.pid.id == Some(id) {
+                                    state.pid.response = Some(response);
+                                }
+                            }
+                            Err(e) => {
+                                state.pid.status = Some(format!("✗ {}", e));
+                                state.events.push(Event::command(Some(id), "Step test", Err(e)));
+                            }
+                        }
+                        state.operation.finish();
+                    }
+                    ServoCommand::SetCenter { id } => {
+                        let mut state = state.lock().unwrap();
+                        let check = match state.duplicate_ids.contains(&id) {
+                            true => Err(format!("ID {} may be shared by several servos", id)),
+                            false => state.operation.begin(id, "Set center"),
+                        };
+                        if let Err(e) = check {
+                            state.center_status = Some(format!("✗ {}", e));
+                            state.events.push(Event::command(Some(id), "Set center", Err(e)));
+                            continue;
+                        }
+                        if servo.dry_run() {
+                            log::info!(target: logging::DRY_RUN, "ID {}: set center", id);
+                            state.center_status = Some("[dry run] not written".to_string());
+                            state.events.push(Event::command(Some(id), "Set center", Ok(())));
+                            state.operation.finish();
+                            continue;
+                        }
+                        state.operation.progress("writing offset");
+                        register_request = Some(ServoCommand::SetCenter { id });
                     }
-                    ServoCommand::EnableTorque { id } => {
-                        let _ = servo.enable_torque(id);
+                    ServoCommand::ImportConfig { id, path, include_id, force_model } => {
+                        let mut state = state.lock().unwrap();
+                        let check = match state.duplicate_ids.contains(&id) {
+                            true => Err(format!("ID {} may be shared by several servos", id)),
+                            false => ConfigDump::load(std::path::Path::new(&path)),
+                        };
+                        let dump = match check {
+                            Ok(dump) => dump,
+                            Err(e) => {
+                                state.registers.status = Some(format!("✗ {}", e));
+                                state.events.push(Event::command(Some(id), "Import config", Err(e)));
+                                continue;
+                            }
+                        };
+                        if servo.dry_run() {
+                            log::info!(target: logging::DRY_RUN, "ID {}: import {} ({} registers)", id, path, dump.registers.len());
+                            state.registers.status = Some("[dry run] not written".to_string());
+                            state.events.push(Event::command(Some(id), format!("Import config {}", path), Ok(())));
+                            continue;
+                        }
+                        // Nouvel ID contrôlé avant toute écriture, avec les ID du dernier scan
+                        let new_id = dump.id().filter(|&new_id| include_id && new_id != id);
+                        if let Some(Err(e)) = new_id.map(|new_id| ids::check_free_id(id, new_id, &cached_servo_ids, false)) {
+                            state.registers.status = Some(format!("✗ {}", e));
+                            state.events.push(Event::command(Some(id), "Import config", Err(e)));
+                            continue;
+                        }
+                        if let Err(e) = state.operation.begin(id, "Import config") {
+                            state.registers.status = Some(format!("✗ {}", e));
+                            continue;
+                        }
+                        state.operation.progress(format!("writing {}", path));
+                        register_request = Some(ServoCommand::ImportConfig { id, path, include_id, force_model });
                     }
-                    ServoCommand::DisableTorque { id } => {
-                        let _ = servo.disable_torque(id);
+                    ServoCommand::WriteRegister { id, addr, value } => {
+                        let mut state = state.lock().unwrap();
+                        let check = match registers::register_at(addr) {
+                            None => Err(format!("no register at address {}", addr)),
+                            Some(register) => register.check_write(value).and_then(|_| {
+                                // Deux servos sous cet ID recevraient tous deux la valeur
+                                match state.duplicate_ids.contains(&id) {
+                                    true => Err(format!("ID {} may be shared by several servos", id)),
+                                    false => state.operation.begin(id, "Write register"),
+                                }
+                            }),
+                        };
+                        let summary = format!("Write {} = {}", registers::register_at(addr).map_or("?", |r| r.name), value);
+                        if let Err(e) = check {
+                            state.registers.status = Some(format!("✗ {}", e));
+                            state.events.push(Event::command(Some(id), summary, Err(e)));
+                            continue;
+                        }
+                        if servo.dry_run() {
+                            log::info!(target: logging::DRY_RUN, "ID {}: {}", id, summary);
+                            state.registers.status = Some("[dry run] not written".to_string());
+                            state.events.push(Event::command(Some(id), summary, Ok(())));
+                            state.operation.finish();
+                            continue;
+                        }
+                        state.operation.progress("writing");
+                        register_request = Some(ServoCommand::WriteRegister { id, addr, value });
                     }
-                    ServoCommand::ScanServos => {
-                        cached_servo_ids = servo.list_servos();
+                    ServoCommand::SwapIds { a, b } => {
+                        let plan = {
+                            let mut state = state.lock().unwrap();
+                            let plan = idchange::swap_plan(a, b, &cached_servo_ids).and_then(|plan| {
+                                match [a, b].into_iter().find(|id| state.duplicate_ids.contains(id)) {
+                                    Some(id) => Err(format!("ID {} may be shared by several servos", id)),
+                                    None => state.operation.begin(a, "Swap IDs").map(|_| plan),
+                                }
+                            });
+                            if let Err(e) = &plan {
+                                state.swap_status = Some(format!("✗ {}", e));
+                                state.events.push(Event::command(Some(a), format!("Swap IDs {} ⇄ {}", a, b), Err(e.clone())));
+                            }
+                            plan
+                        };
+                        let Ok(plan) = plan else { continue };
+                        let mut step_count = 0;
+                        let outcome = idchange::run_swap(&plan, |from, to| {
+                            step_count += 1;
+                            let step = match step_count <= plan.len() {
+                                true => format!("ID {} → {} ({}/{})", from, to, step_count, plan.len()),
+                                false => format!("rolling back: ID {} → {}", from, to),
+                            };
+                            state.lock().unwrap().operation.progress(step);
+                            servo.change_id_checked(from, to).and_then(|verdict| match verdict {
+                                // L'ancien ID répond encore : l'étape suivante le réutiliserait
+                                IdChangeOutcome::Done => Ok(()),
+                                _ => Err(verdict.describe(from, to)),
+                            })
+                        });
+                        state.lock().unwrap().operation.progress("rescanning bus");
+                        let scanned = servo.list_servos();
                         let mut state = state.lock().unwrap();
+                        cached_servo_ids = state.id_changes.merge_scan(&scanned);
                         state.servo_ids = cached_servo_ids.clone();
+                        let summary = format!("Swap IDs {} ⇄ {}", a, b);
+                        state.swap_status = Some(match &outcome {
+                            Ok(()) => format!("✓ IDs {} and {} swapped", a, b),
+                            Err(e) => format!("✗ {}", e),
+                        });
+                        if let Err(e) = &outcome {
+                            eprintln!("Failed to swap IDs {} and {}: {}", a, b, e);
+                        }
+                        state.events.push(Event::command(Some(a), summary, outcome));
+                        state.operation.finish();
                     }
                     ServoCommand::ChangeId { old_id, new_id } => {
-                        match servo.change_id(old_id, new_id) {
-                            Ok(_) => {
-                                println!("Servo ID changed successfully from {} to {}", old_id, new_id);
-                                // Rescan servos to update the list
-                                cached_servo_ids = servo.list_servos();
+                        {
+                            let mut state = state.lock().unwrap();
+                            // Deux servos sous cet ID recevraient tous deux le nouvel ID
+                            let check = match state.duplicate_ids.contains(&old_id) {
+                                true => Err(format!("ID {} may be shared by several servos: unplug all but one and rescan", old_id)),
+                                false => state.operation.begin(old_id, "Change ID"),
+                            };
+                            if let Err(e) = check {
+                                responder.send(command_id, "change id", Some(old_id), Err(e.clone()));
+                                state.id_change_status = Some(format!("✗ {}", e));
+                                state.events.push(Event::command(Some(old_id), format!("Change ID → {}", new_id), Err(e)));
+                                continue;
+                            }
+                            state.operation.progress(format!("writing ID {} (1/3)", new_id));
+                        }
+                        let outcome = servo.change_id(old_id, new_id).map(|_| {
+                            // Le nouvel ID doit répondre, et l'ancien ne plus répondre
+                            state.lock().unwrap().operation.progress("verifying (2/3)");
+                            thread::sleep(Duration::from_millis(50));
+                            if servo.dry_run() {
+                                return IdChangeOutcome::Done;
+                            }
+                            check_id_change(|id| servo.ping_servo(id), old_id, new_id)
+                        });
+                        match outcome {
+                            // Écriture envoyée : rescan dans tous les cas, la liste montre les ID qui répondent
+                            Ok(verdict) => {
+                                let description = verdict.describe(old_id, new_id);
+                                match verdict.is_success() {
+                                    true => println!("Servo ID changed from {} to {}: {}", old_id, new_id, description),
+                                    false => eprintln!("Failed to change servo ID {} to {}: {}", old_id, new_id, description),
+                                }
+                                state.lock().unwrap().operation.progress("rescanning bus (3/3)");
+                                let scanned = servo.list_servos();
                                 let mut state = state.lock().unwrap();
+                                if verdict == IdChangeOutcome::PowerCycleRequired {
+                                    state.id_changes.insert(old_id, new_id);
+                                    state.events.push(Event::AlertRaised {
+                                        servo: Some(new_id),
+                                        message: format!("power-cycle required (still answering as ID {})", old_id),
+                                    });
+                                }
+                                // Rescan servos to update the list
+                                cached_servo_ids = state.id_changes.merge_scan(&scanned);
                                 state.servo_ids = cached_servo_ids.clone();
-                                // Update selected servo if it was the old one
-                                if state.selected_servo == Some(old_id) {
-                                    state.selected_servo = Some(new_id);
+                                let summary = format!("Change ID → {}", new_id);
+                                if verdict.is_success() {
+                                    // Update selected servo if it was the old one
+                                    if state.selected_servo == Some(old_id) {
+                                        state.selected_servo = Some(new_id);
+                                    }
+                                    state.id_change_status = Some(format!("✓ ID {} → {}: {}", old_id, new_id, description));
+                                    state.events.push(Event::command(Some(old_id), summary, Ok(())));
+                                    responder.send(command_id, "change id", Some(old_id), Ok(()));
+                                } else {
+                                    state.id_change_status = Some(format!("✗ ID {} → {}: {}", old_id, new_id, description));
+                                    state.events.push(Event::command(Some(old_id), summary, Err(description.clone())));
+                                    responder.send(command_id, "change id", Some(old_id), Err(description));
                                 }
+                                state.operation.finish();
                             }
                             Err(e) => {
                                 eprintln!("Failed to change servo ID: {}", e);
+                                responder.send(command_id, "change id", Some(old_id), Err(e.clone()));
+                                let mut state = state.lock().unwrap();
+                                state.id_change_status = Some(format!("✗ ID {} → {}: {}", old_id, new_id, e));
+                                state.events.push(Event::command(Some(old_id), format!("Change ID → {}", new_id), Err(e)));
+                                state.operation.finish();
                             }
                         }
                     }
                 }
             }
-            
-            // Lecture des données du servo sélectionné (lock court)
-            let (selected_servo, start_time) = {
-                let state = state.lock().unwrap();
-                (state.selected_servo, state.start_time)
+        } else {
+            // Pas de connexion (port disparu à la réouverture après un accès direct)
+            let mut state = state.lock().unwrap();
+            if state.connected {
+                responder.new_epoch();
+                scan_requests.clear();
+                state.reconnecting = true;
+                state.events.push(Event::Disconnected { port: worker.port().to_string() });
+                state.sounds.notify(SoundClass::Disconnect);
+            }
+            state.connected = false;
+        }
+
+        match full_scan {
+            Some(true) => worker.start_scan(),
+            // Les servos déjà trouvés restent dans la liste
+            Some(false) => {
+                if let Some(run) = worker.cancel_scan() {
+                    let summary = format!("Scan cancelled at ID {} ({} found)", run.progress().0, run.found().len());
+                    state.lock().unwrap().events.push(Event::command(None, summary, Ok(())));
+                }
+            }
+            None => {}
+        }
+        if send_failed {
+            worker.count_failure();
+        }
+
+        // Scan demandé : un lot d'IDs par cycle, les servos trouvés sont suivis aussitôt. Hors
+        // scan, rescan étalé vers les IDs pas encore détectés, sauf pendant une opération destructive
+        let (id_changes, busy) = {
+            let state = state.lock().unwrap();
+            (state.id_changes.clone(), state.operation.current().is_some())
+        };
+        let events = worker.scan_step(|id| cached_servo_ids.contains(&id) || id_changes.is_masked(id), busy);
+        for event in events {
+            match event {
+                WorkerEvent::ScanHits(hits) => {
+                    for id in hits.into_iter().filter(|&id| !id_changes.is_masked(id)) {
+                        if !cached_servo_ids.contains(&id) {
+                            cached_servo_ids.push(id);
+                            cached_servo_ids.sort_unstable();
+                        }
+                    }
+                }
+                WorkerEvent::ScanDone { found, mut duplicates } => {
+                    if let Some(servo) = worker.driver() {
+                        duplicates.extend(found.iter().copied().filter(|&id| ids::probe_duplicate(|| servo.read_position(id))));
+                    }
+                    let mut state = state.lock().unwrap();
+                    cached_servo_ids = state.id_changes.merge_scan(&found);
+                    set_duplicates(&mut state, duplicates);
+                    let summary = format!("Scan ({} found)", cached_servo_ids.len());
+                    state.events.push(Event::command(None, summary, Ok(())));
+                }
+                WorkerEvent::ServoFound(id) => {
+                    cached_servo_ids.push(id);
+                    cached_servo_ids.sort_unstable();
+                    println!("Nouveau servo détecté : ID {}", id);
+                    state.lock().unwrap().events.push(Event::ServoFound { servo: id });
+                }
+                _ => {}
+            }
+            state.lock().unwrap().servo_ids = cached_servo_ids.clone();
+            handled = true;
+        }
+
+        let (selected_servo, start_time, pending_timing, paused, stalled) = {
+            let state = state.lock().unwrap();
+            let pending_timing = state.last_move_timing.filter(|t| t.measured.is_none());
+            let paused = state.selected_servo.is_some_and(|id| state.operation.pauses(id));
+            let stalled = state.selected_servo.is_some_and(|id| state.stalls.contains_key(&id));
+            (state.selected_servo, state.start_time, pending_timing, paused, stalled)
+        };
+
+        // Lecture des données du servo sélectionné (lock court) ; télémétrie suspendue pendant
+        // une opération destructive sur ce servo
+        let polled = selected_servo.filter(|id| worker.is_connected() && cached_servo_ids.contains(id) && !paused);
+        if let Some(servo_id) = polled {
+            if torque_pending == Some(servo_id)
+                || torque_checked != Some(servo_id)
+                || cycle_count.is_multiple_of(TORQUE_READ_CYCLES)
+            {
+                torque_read = Some(servo_id);
+            }
+            if cycle_count % LOAD_READ_CYCLES == 2 {
+                load_read = Some(servo_id);
+            }
+
+            // Position, température et drapeau de mouvement à chaque cycle (la retombée du
+            // drapeau clôt le dernier mouvement) ; tension, courant et vitesse en alternance.
+            // Coupure thermique, blocage et liaison perdue sont jugés par le worker.
+            let plan = PollPlan {
+                temperature: true,
+                voltage: cycle_count.is_multiple_of(5),
+                current: cycle_count % 5 == 1,
+                speed: cycle_count.is_multiple_of(3),
+                moving: true,
             };
-            
-            if let Some(servo_id) = selected_servo {
-                if cached_servo_ids.contains(&servo_id) {
-                    // Lire position et température à chaque cycle
-                    let pos = servo.read_position(servo_id);
-                    let temp = servo.read_temperature(servo_id);
-                    
-                    let voltage = if cycle_count % 5 == 0 {
-                        servo.read_voltage(servo_id)
-                    } else {
-                        None
-                    };
-                    
-                    let current = if cycle_count % 5 == 1 {
-                        servo.read_current(servo_id)
-                    } else {
-                        None
-                    };
-                    
-                    let speed = if cycle_count % 3 == 0 {
-                        servo.read_speed(servo_id).map(|s| s as u16)
-                    } else {
-                        None
+            // Blocage déjà signalé : pas de nouvelle détection avant l'acquittement
+            let protection = Protection { stall: !stalled, ..Protection::default() };
+            let (readings, events) = worker.poll(&[servo_id], plan, |_| protection);
+            let reading = readings.first().copied().unwrap_or(Telemetry { id: servo_id, ..Default::default() });
+            let Telemetry { position: pos, temperature: temp, voltage, current, speed, is_moving: moving, .. } = reading;
+
+            // Mettre à jour l'état
+            let mut state = state.lock().unwrap();
+            let time = start_time.elapsed().as_secs_f64();
+
+            for (metric, value) in [
+                (Metric::Position, pos.map(f64::from)),
+                (Metric::Temperature, temp.map(f64::from)),
+                (Metric::Voltage, voltage.map(f64::from)),
+                (Metric::Current, current.map(f64::from)),
+            ] {
+                if let Some(value) = value {
+                    state.telemetry.observe(servo_id, metric, time, value);
+                }
+            }
+            let frame = TelemetryFrame { delta: pos.map(|pos| i32::from(state.target_position) - i32::from(pos)), ..reading.frame(time) };
+            state.processors.process(&frame);
+            log_frame = Some(frame);
+
+            if pos.is_none() {
+                log_failure(&mut state, servo_id, "position read", Err("no response".to_string()));
+            }
+            if let Some(pos) = pos {
+                state.servo_data.position = Some(pos);
+                state.position_history.push(time, pos as f64);
+            }
+
+            if let Some(temp) = temp {
+                state.servo_data.temperature = Some(temp);
+                state.temperature_history.push(time, temp as f64);
+            }
+
+            if let Some(v) = voltage {
+                state.servo_data.voltage = Some(v);
+                state.voltage_history.push(time, v as f64);
+            }
+
+            if let Some(c) = current {
+                state.servo_data.current = Some(c);
+                state.current_history.push(time, c as f64);
+            }
+
+            if let Some(s) = speed {
+                state.servo_data.speed = Some(s.unsigned_abs());
+                state.speed_history.push(time, s as f64);
+            }
+
+            for event in events {
+                match event {
+                    // Coupure thermique : couple déjà coupé, consignes refusées jusqu'à la réactivation
+                    WorkerEvent::ThermalCutOff { id, temperature, outcome } => {
+                        log_failure(&mut state, id, "thermal cut-off torque off", outcome);
+                        state.torque.insert(id, false);
+                        state.pending_large_move = None;
+                        state.events.push(Event::AlertRaised {
+                            servo: Some(id),
+                            message: format!("thermal lockout at {}°C: torque cut", temperature),
+                        });
+                        state.sounds.notify(SoundClass::OverTemperature);
+                    }
+                    // Blocage mécanique : courant excessif pendant un mouvement
+                    WorkerEvent::Stalled { id, stall } => {
+                        if let Some(servo) = worker.driver() {
+                            stop_stalled(&mut state, servo, id, stall);
+                        }
+                    }
+                    // Servo muet plusieurs cycles de suite, ou adaptateur retiré
+                    WorkerEvent::LinkLost { .. } => link_lost = true,
+                    _ => {}
+                }
+            }
+
+            if let Some(temp) = temp {
+                let threshold = state.over_temperature;
+                if temp > threshold && !over_temperature {
+                    over_temperature = true;
+                    state.events.push(Event::AlertRaised {
+                        servo: Some(servo_id),
+                        message: format!("over-temperature {}°C", temp),
+                    });
+                    state.sounds.notify(SoundClass::OverTemperature);
+                } else if temp <= threshold && over_temperature {
+                    over_temperature = false;
+                    state.events.push(Event::AlertCleared {
+                        servo: Some(servo_id),
+                        message: format!("temperature back to {}°C", temp),
+                    });
+                }
+            }
+
+            // Mouvement qui dépasse largement sa durée estimée : blocage probable
+            if let Some(timing) = pending_timing.filter(|t| t.id == servo_id) {
+                if stalled_move != Some(timing.started) && timing.started.elapsed() > timing.estimated + STALL_MARGIN {
+                    stalled_move = Some(timing.started);
+                    state.events.push(Event::AlertRaised {
+                        servo: Some(servo_id),
+                        message: "move stalled".to_string(),
+                    });
+                    state.sounds.notify(SoundClass::Stall);
+                }
+            }
+
+            state.servo_data.is_moving = moving;
+            if let Some(moving) = moving {
+                if let Some(timing) = state.last_move_timing.as_mut().filter(|t| t.id == servo_id) {
+                    // Le drapeau peut ne pas être encore levé juste après l'envoi
+                    if !moving && timing.measured.is_none() && timing.started.elapsed() > Duration::from_millis(50) {
+                        timing.measured = Some(timing.started.elapsed());
+                        timing.final_position = pos;
+                    }
+                }
+            }
+
+            state.servo_data.last_update = Instant::now();
+        }
+
+        // Poignée périmée, déjà fermée par le worker : reconnexion et scan aux cycles suivants
+        if link_lost {
+            scan_requests.clear();
+            responder.new_epoch();
+            torque_read = None;
+            load_read = None;
+            let mut state = state.lock().unwrap();
+            if let Some(
+                ServoCommand::WriteRegister { id, .. }
+                | ServoCommand::ImportConfig { id, .. }
+                | ServoCommand::SetCenter { id }
+                | ServoCommand::WriteAngleLimits { id, .. }
+                | ServoCommand::WriteTorqueLimit { id, .. }
+                | ServoCommand::FactoryReset { id }
+                | ServoCommand::WritePid { id, .. },
+            ) = register_request.take()
+            {
+                state.registers.status = Some("✗ link lost".to_string());
+                state.events.push(Event::command(Some(id), "Register write", Err("link lost".to_string())));
+                state.operation.finish();
+            }
+            // Un échange brut rouvrirait le port sans scan : abandonné avec un échec
+            if let Some(frame) = raw_request.take() {
+                state.console_result = Some("✗ link lost".to_string());
+                state.events.push(Event::command(Some(frame[2]), "Raw instruction", Err("link lost".to_string())));
+                if packet::is_destructive(frame[4]) {
+                    state.operation.finish();
+                }
+            }
+            state.connected = false;
+            state.reconnecting = true;
+            state.scan_progress = None;
+            state.pending_large_move = None;
+            state.events.push(Event::Disconnected { port: worker.port().to_string() });
+            state.sounds.notify(SoundClass::Disconnect);
+            handled = true;
+        }
+        
+        if let Some(id) = torque_read {
+            // Registre hors des lectures du pilote : accès direct
+            let enabled = if dry_run.load(Ordering::Relaxed) {
+                None
+            } else {
+                worker.with_bus(|bus| bus.read(id, &TORQUE_ENABLE)).ok()
+            };
+            torque_checked = Some(id);
+            if torque_pending == Some(id) {
+                torque_pending = None;
+            }
+            if let Some(enabled) = enabled {
+                let mut state = state.lock().unwrap();
+                if state.torque.insert(id, enabled != 0) != Some(enabled != 0) {
+                    handled = true;
+                }
+            }
+        }
+
+        if let Some(id) = load_read.filter(|_| !dry_run.load(Ordering::Relaxed)) {
+            // Le pilote ne lit que l'octet bas, sans le sens : registre lu directement
+            let read = worker.with_bus(|bus| bus.read(id, &PRESENT_LOAD));
+            if let Ok(raw) = read {
+                let load = inversions.signed(id, raw as f32 * 0.1);
+                if let Some(frame) = log_frame.as_mut().filter(|f| f.servo == id) {
+                    frame.load = Some(load);
+                }
+                let mut state = state.lock().unwrap();
+                if state.selected_servo == Some(id) {
+                    let time = state.start_time.elapsed().as_secs_f64();
+                    state.servo_data.load = Some(load);
+                    state.load_history.push(time, load as f64);
+                    state.telemetry.observe(id, Metric::Load, time, load as f64);
+                    handled = true;
+                    // Blocage mécanique : charge excessive pendant un mouvement
+                    let protection = Protection { stall: !state.stalls.contains_key(&id), ..Protection::default() };
+                    if let Some(WorkerEvent::Stalled { id, stall }) = worker.observe_load(id, load, protection) {
+                        if let Some(servo) = worker.driver() {
+                            stop_stalled(&mut state, servo, id, stall);
+                        }
+                    }
+                }
+            }
+        }
+
+        if fast_scan && !link_lost {
+            // Réponses chevauchées ou aucune réponse : le worker pinge les autres IDs un par un
+            let outcome = worker.fast_scan();
+            // Scan complet d'emblée : contrôle de doublon des IDs qui ont répondu, hors verrou
+            let probed: Vec<u8> = match (&outcome, worker.driver()) {
+                (Ok(result), Some(servo)) if !result.needs_sweep() => {
+                    result.ids.iter().copied().filter(|&id| ids::probe_duplicate(|| servo.read_position(id))).collect()
+                }
+                _ => Vec::new(),
+            };
+            let mut state = state.lock().unwrap();
+            match outcome {
+                Ok(result) if result.needs_sweep() => {}
+                Ok(mut result) => {
+                    result.duplicates.extend(probed);
+                    set_duplicates(&mut state, result.duplicates);
+                    cached_servo_ids = state.id_changes.merge_scan(&result.ids);
+                    state.servo_ids = cached_servo_ids.clone();
+                    let summary = format!("Broadcast scan ({} found)", cached_servo_ids.len());
+                    state.events.push(Event::command(None, summary, Ok(())));
+                }
+                Err(e) => state.events.push(Event::command(None, "Broadcast scan", Err(e))),
+            }
+            handled = true;
+        }
+
+        // Scan terminé ou annulé (les servos trouvés restent) : les demandes en attente sont soldées
+        let scan_progress = worker.scan_progress();
+        state.lock().unwrap().scan_progress = scan_progress;
+        if scan_progress.is_none() {
+            for command_id in scan_requests.drain(..) {
+                responder.send(command_id, "scan", None, Ok(()));
+            }
+        }
+
+        if let Some(request) = register_request {
+            // Registres hors des lectures du pilote : accès direct
+            match request {
+                ServoCommand::ReadAllRegisters { id } => {
+                    let outcome = worker.with_bus(|bus| read_registers(bus, id));
+                    let mut state = state.lock().unwrap();
+                    let panel = &mut state.registers;
+                    match outcome {
+                        Ok(values) => {
+                            let failed = values.values().filter(|v| v.is_err()).count();
+                            panel.status = Some(match failed {
+                                0 => format!("✓ {} registers read", values.len()),
+                                _ => format!("✓ {} registers read, {} unreadable", values.len() - failed, failed),
+                            });
+                            panel.id = Some(id);
+                            panel.values = values;
+                            panel.edits.clear();
+                            panel.pending_eeprom = None;
+                        }
+                        Err(e) => panel.status = Some(format!("✗ {}", e)),
+                    }
+                }
+                ServoCommand::WriteRegister { id, addr, value } => {
+                    let register = registers::register_at(addr);
+                    let outcome = match register {
+                        Some(register) => worker.with_bus(|bus| bus.write(id, register, value)),
+                        None => Err(format!("no register at address {}", addr)),
                     };
-                    
-                    // Mettre à jour l'état
+                    let name = register.map_or("?", |r| r.name);
                     let mut state = state.lock().unwrap();
-                    let time = start_time.elapsed().as_secs_f64();
-                    
-                    if let Some(pos) = pos {
-                        state.servo_data.position = Some(pos);
-                        state.position_history.push((time, pos as f64));
-                        if state.position_history.len() > 100 {
-                            state.position_history.remove(0);
+                    state.registers.status = Some(match &outcome {
+                        Ok(read) => format!("✓ {} = {} (read back)", name, read),
+                        Err(e) => format!("✗ {}", e),
+                    });
+                    if let (Ok(read), Some(register)) = (&outcome, register) {
+                        if state.registers.id == Some(id) {
+                            state.registers.values.insert(register.address, Ok(*read));
+                            state.registers.edits.remove(&register.address);
                         }
                     }
-                    
-                    if let Some(temp) = temp {
-                        state.servo_data.temperature = Some(temp);
-                        state.temperature_history.push((time, temp as f64));
-                        if state.temperature_history.len() > 100 {
-                            state.temperature_history.remove(0);
+                    state.events.push(Event::command(Some(id), format!("Write {} = {}", name, value), outcome.map(|_| ())));
+                    state.operation.finish();
+                    // Butées matérielles et limite de couple relues au cycle suivant
+                    if name.ends_with("Angle Limit") || name == "Torque Limit" {
+                        limits_checked.retain(|&checked| checked != id);
+                    }
+                }
+                ServoCommand::ExportConfig { id, path } => {
+                    let outcome = worker
+                        .with_bus(|bus| ConfigDump::read(bus, id))
+                        .and_then(|dump| dump.save(std::path::Path::new(&path)).map(|_| dump.registers.len()));
+                    let mut state = state.lock().unwrap();
+                    state.registers.status = Some(match &outcome {
+                        Ok(count) => format!("✓ {} registers saved to {}", count, path),
+                        Err(e) => format!("✗ {}", e),
+                    });
+                    state.events.push(Event::command(Some(id), format!("Export config {}", path), outcome.map(|_| ())));
+                }
+                ServoCommand::ImportConfig { id, path, include_id, force_model } => {
+                    // Fichier déjà validé à la réception de la commande ; relu ici hors du verrou
+                    let outcome = ConfigDump::load(std::path::Path::new(&path)).and_then(|dump| {
+                        worker.with_bus(|bus| {
+                            backup::check_model(bus, id, &dump, force_model)?;
+                            Ok((backup::restore(bus, id, &dump), dump.id().filter(|&new_id| include_id && new_id != id)))
+                        })
+                    });
+                    let mut state = state.lock().unwrap();
+                    let summary = format!("Import config {}", path);
+                    match outcome {
+                        Ok((rows, new_id)) => {
+                            let written = rows.iter().filter(|r| r.status == RestoreStatus::Written).count();
+                            let failed: Vec<&str> = rows.iter().filter(|r| r.failed()).map(|r| r.name.as_str()).collect();
+                            if state.registers.id == Some(id) {
+                                for row in rows.iter().filter(|r| r.status == RestoreStatus::Written) {
+                                    if let Some(register) = registers::find_register(&row.name) {
+                                        state.registers.values.insert(register.address, Ok(row.value));
+                                        state.registers.edits.remove(&register.address);
+                                    }
+                                }
+                            }
+                            let outcome = match failed.is_empty() {
+                                true => Ok(()),
+                                false => Err(format!("write-back failed: {}", failed.join(", "))),
+                            };
+                            state.registers.status = Some(match &outcome {
+                                Ok(()) => format!("✓ {} registers written and verified", written),
+                                Err(e) => format!("✗ {} written, {}", written, e),
+                            });
+                            state.events.push(Event::command(Some(id), summary, outcome));
+                            limits_checked.retain(|&checked| checked != id);
+                            // Changement d'ID vérifié, avec rescan, au cycle suivant
+                            if let Some(new_id) = new_id {
+                                backlog.push_back(Timed::new(SOURCE_WORKER, ServoCommand::ChangeId { old_id: id, new_id }));
+                            }
+                        }
+                        Err(e) => {
+                            state.registers.status = Some(format!("✗ {}", e));
+                            state.events.push(Event::command(Some(id), summary, Err(e)));
                         }
                     }
-                    
-                    if let Some(v) = voltage {
-                        state.servo_data.voltage = Some(v);
+                    state.operation.finish();
+                }
+                ServoCommand::ReadPid { id } => {
+                    let outcome = worker.with_bus(|bus| PidGains::read(bus, id));
+                    let mut state = state.lock().unwrap();
+                    if state.pid.id == Some(id) {
+                        match outcome {
+                            Ok(gains) => {
+                                state.pid.read = Some(gains);
+                                state.pid.input = gains;
+                                state.pid.status = None;
+                            }
+                            Err(e) => state.pid.status = Some(format!("✗ {}", e)),
+                        }
                     }
-                    
-                    if let Some(c) = current {
-                        state.servo_data.current = Some(c);
+                }
+                ServoCommand::WritePid { id, gains } => {
+                    // Relecture des trois gains dans tous les cas pour confirmer ce que le servo a gardé
+                    let outcome = worker.with_bus(|bus| {
+                        let written = gains.write(bus, id);
+                        let read = PidGains::read(bus, id);
+                        written.and(read)
+                    });
+                    let mut state = state.lock().unwrap();
+                    if let (Ok(read), true) = (&outcome, state.pid.id == Some(id)) {
+                        state.pid.read = Some(*read);
+                        state.pid.input = *read;
                     }
-                    
-                    if let Some(s) = speed {
-                        state.servo_data.speed = Some(s);
+                    state.pid.status = Some(match &outcome {
+                        Ok(read) => format!("✓ gains written and read back: {} / {} / {}", read.p, read.i, read.d),
+                        Err(e) => format!("✗ {}", e),
+                    });
+                    let summary = format!("PID gains {} / {} / {}", gains.p, gains.i, gains.d);
+                    state.events.push(Event::command(Some(id), summary, outcome.map(|_| ())));
+                    state.operation.finish();
+                }
+                ServoCommand::WriteAngleLimits { id, limits } => {
+                    let outcome = worker.with_bus(|bus| {
+                        let written = limits.write(bus, id);
+                        // Relecture dans tous les cas : une écriture partielle reste visible
+                        let read = AngleLimits::read(bus, id);
+                        written.and(read)
+                    });
+                    let mut state = state.lock().unwrap();
+                    if let Ok(read) = &outcome {
+                        state.angle_limits.insert(id, *read);
+                        state.angle_limits_input = None;
                     }
-                    
-                    state.servo_data.last_update = Instant::now();
+                    state.angle_limits_status = Some(match &outcome {
+                        Ok(read) => format!("✓ {}..{} written and read back", read.min, read.max),
+                        Err(e) => format!("✗ {}", e),
+                    });
+                    let summary = format!("Angle limits {}..{}", limits.min, limits.max);
+                    state.events.push(Event::command(Some(id), summary, outcome.map(|_| ())));
+                    state.operation.finish();
+                }
+                ServoCommand::FactoryReset { id } => {
+                    // L'instruction passe par une trame brute ; on laisse le servo redémarrer
+                    let outcome = worker.with_backend(|bus| {
+                        let outcome = packet::factory_reset(bus, id);
+                        thread::sleep(Duration::from_millis(500));
+                        outcome
+                    });
+                    let mut state = state.lock().unwrap();
+                    if outcome.is_ok() {
+                        // Tout ce qui a été lu sur ce servo est périmé
+                        if state.registers.id == Some(id) {
+                            state.registers.id = None;
+                            state.registers.values.clear();
+                            state.registers.edits.clear();
+                        }
+                        if state.pid.id == Some(id) {
+                            state.pid = PidPanel::default();
+                        }
+                        state.angle_limits.remove(&id);
+                        state.torque_limits.remove(&id);
+                        state.identities.remove(&id);
+                        limits_checked.retain(|&checked| checked != id && checked != 1);
+                        if state.selected_servo == Some(id) {
+                            state.selected_servo = Some(1);
+                        }
+                        // Le servo réapparaît en ID 1 au scan
+                        backlog.push_back(Timed::new(SOURCE_WORKER, ServoCommand::ScanServos { exhaustive: false }));
+                    }
+                    state.registers.status = Some(match &outcome {
+                        Ok(()) => format!("✓ ID {} reset to factory settings, now ID 1; rescanning", id),
+                        Err(e) => format!("✗ {}", e),
+                    });
+                    state.events.push(Event::command(Some(id), "Factory reset", outcome));
+                    state.operation.finish();
+                }
+                ServoCommand::WriteTorqueLimit { id, limit } => {
+                    let outcome = worker.with_bus(|bus| {
+                        let written = limit.write(bus, id);
+                        let read = TorqueLimit::read(bus, id);
+                        written.and(read)
+                    });
+                    let mut state = state.lock().unwrap();
+                    if let Ok(read) = &outcome {
+                        state.torque_limits.insert(id, *read);
+                        state.torque_limit_input = None;
+                    }
+                    state.torque_limit_status = Some(match &outcome {
+                        Ok(read) => format!("✓ {:.1}% written and read back", read.percent()),
+                        Err(e) => format!("✗ {}", e),
+                    });
+                    let summary = format!("Torque limit {:.1}%", limit.percent());
+                    state.events.push(Event::command(Some(id), summary, outcome.map(|_| ())));
+                    state.operation.finish();
+                }
+                ServoCommand::SetCenter { id } => {
+                    let outcome = worker.with_bus(|bus| {
+                        let centering = calibration::plan(bus, id)?;
+                        calibration::apply(bus, id, &centering).map(|position| (centering, position))
+                    });
+                    let mut state = state.lock().unwrap();
+                    if let Ok((centering, position)) = &outcome {
+                        // Butées, consigne et historique passent dans la nouvelle référence
+                        if let Some(limits) = state.limits.get(&id).copied() {
+                            state.limits.insert(id, centering.limits(&limits));
+                            let mut config = Config::load();
+                            config.limits = state.limits.clone();
+                            if let Err(e) = config.save() {
+                                eprintln!("Could not save soft limits: {}", e);
+                            }
+                        }
+                        if state.selected_servo == Some(id) {
+                            state.target_position = centering.apply(state.target_position);
+                            state.servo_data.position = Some(*position);
+                            state.position_history.shift_values(-f64::from(centering.shift));
+                        }
+                        if let (Some(register), true) = (registers::find_register("Position Offset"), state.registers.id == Some(id)) {
+                            state.registers.values.insert(register.address, Ok(centering.offset));
+                            state.registers.edits.remove(&register.address);
+                        }
+                    }
+                    state.center_status = Some(match &outcome {
+                        Ok((centering, position)) => format!("✓ offset {} written, position reads {}", centering.offset, position),
+                        Err(e) => format!("✗ {}", e),
+                    });
+                    state.events.push(Event::command(Some(id), "Set center", outcome.map(|_| ())));
+                    state.operation.finish();
                 }
+                _ => {}
             }
-        } else {
-            // Pas de connexion
+            handled = true;
+        }
+
+        // Butées matérielles, limite de couple et identité des servos détectés depuis le dernier
+        // cycle, en un seul accès direct
+        limits_checked.retain(|id| cached_servo_ids.contains(id));
+        let unread: Vec<u8> = cached_servo_ids.iter().copied().filter(|id| !limits_checked.contains(id)).collect();
+        if !unread.is_empty() && scan_progress.is_none() && worker.is_connected() && !link_lost {
+            type Reads = (Result<AngleLimits, String>, Result<TorqueLimit, String>, ServoIdentity);
+            let read: Vec<(u8, Reads)> = worker
+                .with_bus(|bus| {
+                    Ok(unread
+                        .iter()
+                        .map(|&id| {
+                            let identity = ServoIdentity::read(bus, id);
+                            (id, (AngleLimits::read(bus, id), TorqueLimit::read(bus, id), identity))
+                        })
+                        .collect())
+                })
+                .unwrap_or_else(|e| unread.iter().map(|&id| (id, (Err(e.clone()), Err(e.clone()), ServoIdentity::default()))).collect());
+            limits_checked.extend(&unread);
             let mut state = state.lock().unwrap();
-            state.connected = false;
+            for (id, (limits, torque, identity)) in read {
+                state.identities.insert(id, identity);
+                match limits {
+                    Ok(limits) => {
+                        state.angle_limits.insert(id, limits);
+                    }
+                    Err(e) => {
+                        state.angle_limits.remove(&id);
+                        state.events.push(Event::Error { servo: Some(id), message: format!("angle limits: {}", e) });
+                    }
+                }
+                match torque {
+                    Ok(limit) => {
+                        state.torque_limits.insert(id, limit);
+                    }
+                    Err(e) => {
+                        state.torque_limits.remove(&id);
+                        state.events.push(Event::Error { servo: Some(id), message: format!("torque limit: {}", e) });
+                    }
+                }
+            }
+            handled = true;
         }
-        
+
+        if let Some(frame) = raw_request {
+            let outcome = describe_raw_response(worker.with_backend(|bus| bus.send_raw(&frame)));
+
+            let mut state = state.lock().unwrap();
+            let summary = format!("Raw {} [{}]", packet::instruction_name(frame[4]), packet::to_hex(&frame));
+            state.console_result = Some(match &outcome {
+                Ok(text) => text.clone(),
+                Err(e) => format!("✗ {}", e),
+            });
+            state.events.push(Event::command(Some(frame[2]), summary, outcome.map(|_| ())));
+            if packet::is_destructive(frame[4]) {
+                state.operation.finish();
+            }
+        }
+
+        // Journal continu : fichier ouvert, écrit et vidé hors du verrou
+        let (log_enabled, log_settings) = {
+            let state = state.lock().unwrap();
+            (state.log_enabled, state.log_settings.clone())
+        };
+        if let Some(outcome) = telemetrylog::sync(&mut telemetry_log, log_enabled, &log_settings, log_frame.as_slice()) {
+            let mut state = state.lock().unwrap();
+            state.log_status = Some(match outcome {
+                Ok(status) => status,
+                Err(e) => {
+                    state.log_enabled = false;
+                    format!("✗ {}", e)
+                }
+            });
+            handled = true;
+        }
+
         cycle_count = cycle_count.wrapping_add(1);
-        ctx.request_repaint();
-        thread::sleep(Duration::from_millis(100));
+        let now = DisplayedState::of(&state.lock().unwrap());
+        if handled || displayed.as_ref() != Some(&now) {
+            displayed = Some(now);
+            ctx.request_repaint();
+        }
+        thread::sleep(poll_interval);
     }
 }
 
@@ -475,9 +4025,43 @@ fn main() -> Result<(), eframe::Error> {
         ..Default::default()
     };
     
+    // Traitements de télémétrie : enregistrer ici les processeurs maison
+    let mut processors = ProcessorRegistry::default();
+    processors.register(Box::new(MovingAverage::new(Metric::Current, 10)));
+
+    // Servos virtuels : bus simulé démarré avant la fenêtre
+    let launched = logging::setup(std::env::args().skip(1).collect()).and_then(|args| {
+        let replay = Replay::from_args(&args)?;
+        if replay.is_some() && Simulation::from_args(&args)?.is_some() {
+            return Err("--replay and --simulate cannot be combined".to_string());
+        }
+        BusConfig::check_baud(Config::load().bus.baud)?;
+        let port = match args.iter().position(|a| a == "--port") {
+            Some(i) => Some(args.get(i + 1).ok_or("--port expects a value")?.clone()),
+            None => None,
+        };
+        Ok((Simulation::launch(&args)?, replay, port))
+    });
+    let (simulation, replay, port) = match launched {
+        Ok(launched) => launched,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let launch = LaunchOptions {
+        simulation,
+        replay,
+        port,
+        expert_mode: std::env::args().any(|a| a == "--expert"),
+        dry_run: std::env::args().any(|a| a == "--dry-run"),
+        pin_port: std::env::args().any(|a| a == "--pin-port"),
+        processors,
+    };
+
     eframe::run_native(
         "Cogni-Robot Servo Control",
         options,
-        Box::new(|cc| Ok(Box::new(ServoGuiApp::new(cc)))),
+        Box::new(move |cc| Ok(Box::new(ServoGuiApp::new(cc, launch)))),
     )
 }
@@ -0,0 +1,54 @@
+//! Simulateur de servos ST3215 derrière un pseudo-terminal, pour tester la GUI et la CLI
+//! sans matériel.
+//!
+//! simserial [--ids 1,2,3] [--drop-every N] [--corrupt-every N] [--delay-ms N]
+//!           [--trace | --log-level LEVEL] [--log-file PATH]
+//!
+//! `--trace` (ou `SIMSERIAL_TRACE`) affiche chaque trame reçue et sa réponse.
+
+#[cfg(unix)]
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    use servo_control::logging::{self, LogOptions};
+    use servo_control::sim::{self, FaultConfig, SimBus};
+    use std::time::Duration;
+
+    let (mut log_options, args) = LogOptions::extract(std::env::args().skip(1).collect())?;
+    if std::env::var_os("SIMSERIAL_TRACE").is_some() {
+        log_options.level = log::LevelFilter::Trace;
+    }
+    logging::init(&log_options)?;
+    let value = |name: &str| args.iter().position(|a| a == name).and_then(|i| args.get(i + 1));
+
+    let ids: Vec<u8> = match value("--ids") {
+        Some(list) => list
+            .split(',')
+            .map(|id| id.trim().parse().map_err(|_| format!("Invalid ID: {}", id)))
+            .collect::<Result<_, _>>()?,
+        None => vec![1],
+    };
+    let number = |name: &str| -> Result<u64, String> {
+        value(name)
+            .map(|v| v.parse().map_err(|_| format!("Invalid value for {}: {}", name, v)))
+            .unwrap_or(Ok(0))
+    };
+    let faults = FaultConfig {
+        drop_every: number("--drop-every")? as u32,
+        corrupt_every: number("--corrupt-every")? as u32,
+        delay: Duration::from_millis(number("--delay-ms")?),
+    };
+
+    let (mut master, _slave, path) = sim::open_pty()?;
+    let mut bus = SimBus::new(&ids, faults);
+
+    println!("Simulated servos {:?} on {}", bus.ids(), path);
+    println!("Faults: {:?}", bus.faults());
+
+    sim::serve(&mut master, &mut bus)?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn main() {
+    eprintln!("simserial needs a Unix pseudo-terminal");
+    std::process::exit(1);
+}
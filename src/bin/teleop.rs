@@ -0,0 +1,405 @@
+//! Téléopération maître-esclave : bras meneur non motorisé sur un port, bras suiveur sur l'autre.
+//! Un thread possède les deux connexions et enchaîne les cycles ; l'interface règle la
+//! correspondance des articulations, démarre, arrête et affiche la cadence.
+
+use eframe::egui;
+use servo_control::config::Config;
+use servo_control::ids::MAX_SERVO_ID;
+use servo_control::logging;
+use servo_control::portlock::{self, PortLock};
+use servo_control::shutdown::{self, ShutdownSignal};
+use servo_control::sim::Simulation;
+use servo_control::teleop::{self, TeleopJoint, TeleopSettings, TeleopStats};
+use servo_control::theme::{self, Status, Theme};
+use servo_control::worker::ServoWorker;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+// --- CONSTANTES ---
+// Attente du thread à l'arrêt, entre deux vérifications des demandes de l'interface
+const IDLE_POLL: Duration = Duration::from_millis(50);
+// Rafraîchissement de l'interface pendant la téléopération
+const REPAINT_INTERVAL: Duration = Duration::from_millis(50);
+
+// --- ÉTAT PARTAGÉ ---
+#[derive(Default)]
+struct SharedState {
+    settings: TeleopSettings,
+    running: bool,
+    // Scan des deux bus demandé par l'interface, et résultat du dernier
+    scan_requested: bool,
+    scanning: bool,
+    status: Option<(Status, String)>,
+    stats: TeleopStats,
+    // Dernière position lue et consigne envoyée, par servo suiveur
+    positions: HashMap<u8, (u16, u16)>,
+    theme: Theme,
+    // Bus simulés (--simulate) à la place des ports série : les ports ne sont pas enregistrés
+    simulation: Option<Simulation>,
+}
+
+// Suiveur gelé : la téléopération s'arrête, la raison reste affichée
+fn freeze(state: &Arc<Mutex<SharedState>>, reason: String) {
+    eprintln!("Teleoperation frozen: {}", reason);
+    let mut s = state.lock().unwrap();
+    s.running = false;
+    s.status = Some((Status::Danger, format!("Frozen: {}", reason)));
+}
+
+// --- APPLICATION GUI ---
+struct TeleopApp {
+    state: Arc<Mutex<SharedState>>,
+    dry_run: Arc<AtomicBool>,
+    shutdown: ShutdownSignal,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl TeleopApp {
+    fn new(cc: &eframe::CreationContext<'_>, launch: LaunchOptions) -> Self {
+        let config = Config::load();
+        let mut settings = config.teleop.clone();
+        if let Some((_, leader, follower)) = &launch.simulation {
+            settings.leader_port = leader.clone();
+            settings.follower_port = follower.clone();
+        }
+        let state = Arc::new(Mutex::new(SharedState {
+            settings,
+            theme: config.ui.theme,
+            simulation: launch.simulation.map(|(sim, _, _)| sim),
+            ..Default::default()
+        }));
+        theme::apply(config.ui.theme, &cc.egui_ctx);
+
+        // Le meneur est toujours lu pour de vrai ; la répétition ne concerne que le suiveur
+        let dry_run = Arc::new(AtomicBool::new(launch.dry_run));
+        let mut leader = ServoWorker::new(String::new(), Arc::new(AtomicBool::new(false)));
+        let mut follower = ServoWorker::new(String::new(), dry_run.clone());
+        leader.set_retry(config.retry.clone());
+        follower.set_retry(config.retry.clone());
+        let shutdown = ShutdownSignal::new();
+        let (worker_state, worker_shutdown, ctx) = (state.clone(), shutdown.clone(), cc.egui_ctx.clone());
+        let worker = thread::spawn(move || teleop_worker(worker_state, ctx, leader, follower, worker_shutdown));
+        Self { state, dry_run, shutdown, worker: Some(worker) }
+    }
+}
+
+impl eframe::App for TeleopApp {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.shutdown.request();
+        if let Some(worker) = self.worker.take() {
+            if !shutdown::join_within(worker, shutdown::JOIN_MARGIN) {
+                eprintln!("Teleoperation thread still busy; exiting anyway");
+            }
+        }
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let mut state = self.state.lock().unwrap();
+        let palette = state.theme.palette();
+        let mut dry_run = self.dry_run.load(Ordering::Relaxed);
+
+        // --- EN-TÊTE ---
+        let mut top_frame = egui::Frame::side_top_panel(&ctx.style());
+        if dry_run {
+            top_frame = top_frame.fill(palette.warning());
+        } else if state.simulation.is_some() {
+            top_frame = top_frame.fill(palette.info());
+        }
+        egui::TopBottomPanel::top("top_panel").frame(top_frame).show(ctx, |ui| {
+            ui.add_space(8.0);
+            if dry_run {
+                ui.vertical_centered(|ui| {
+                    ui.heading(egui::RichText::new("DRY RUN — nothing is written to the follower").strong().color(egui::Color32::BLACK));
+                });
+            }
+            if let Some(simulation) = &state.simulation {
+                ui.vertical_centered(|ui| {
+                    ui.heading(egui::RichText::new(format!("{} (two buses)", simulation.banner())).strong().color(egui::Color32::BLACK));
+                });
+            }
+            ui.horizontal(|ui| {
+                ui.heading("🤖 Teleoperation");
+                if ui.checkbox(&mut dry_run, "Dry run").changed() {
+                    self.dry_run.store(dry_run, Ordering::Relaxed);
+                }
+            });
+            ui.add_space(8.0);
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let SharedState { settings, running, status, stats, positions, scanning, scan_requested, simulation, .. } = &mut *state;
+
+            // Ports et mouvement du suiveur : figés pendant la téléopération
+            ui.add_enabled_ui(!*running, |ui| {
+                egui::Grid::new("teleop_bus").num_columns(2).show(ui, |ui| {
+                    ui.label("Leader port:");
+                    ui.add_enabled(simulation.is_none(), egui::TextEdit::singleline(&mut settings.leader_port))
+                        .on_hover_text("Unpowered input arm: torque is disabled on its joints when teleoperation starts");
+                    ui.end_row();
+                    ui.label("Follower port:");
+                    ui.add_enabled(simulation.is_none(), egui::TextEdit::singleline(&mut settings.follower_port));
+                    ui.end_row();
+                    ui.label("Follower speed / accel:");
+                    ui.horizontal(|ui| {
+                        ui.add(egui::DragValue::new(&mut settings.speed).range(0..=3400)).on_hover_text("0 = maximum speed");
+                        ui.add(egui::DragValue::new(&mut settings.acceleration).range(0..=254)).on_hover_text("0 = maximum acceleration");
+                    });
+                    ui.end_row();
+                });
+            });
+
+            ui.horizontal(|ui| {
+                if *running {
+                    if ui.button("⏹ Stop").on_hover_text("The follower holds its last target").clicked() {
+                        *running = false;
+                        *status = None;
+                    }
+                } else {
+                    let active = settings.joints.iter().any(|joint| joint.enabled);
+                    let start = ui.add_enabled(active && !*scanning, egui::Button::new("▶ Start"))
+                        .on_hover_text("The follower first moves to the leader's pose at the follower speed");
+                    if start.clicked() {
+                        match teleop::check_joints(&settings.joints) {
+                            Ok(()) => {
+                                *running = true;
+                                *stats = TeleopStats::default();
+                                positions.clear();
+                                *status = None;
+                            }
+                            Err(e) => *status = Some((Status::Danger, e)),
+                        }
+                    }
+                    if ui.add_enabled(!*scanning, egui::Button::new("Scan")).on_hover_text("Find the servos on both buses; unmapped leader IDs get a 1:1 joint").clicked() {
+                        *scan_requested = true;
+                        *scanning = true;
+                    }
+                    if *scanning {
+                        ui.spinner();
+                    }
+                }
+                if ui.button("💾 Save settings").on_hover_text("Ports and joint map to the configuration file ([teleop])").clicked() {
+                    let mut config = Config::load();
+                    let ports = (config.teleop.leader_port.clone(), config.teleop.follower_port.clone());
+                    config.teleop = settings.clone();
+                    if simulation.is_some() {
+                        (config.teleop.leader_port, config.teleop.follower_port) = ports;
+                    }
+                    *status = Some(match config.save() {
+                        Ok(()) => (Status::Ok, "Settings saved".to_string()),
+                        Err(e) => (Status::Danger, format!("Could not save settings: {}", e)),
+                    });
+                }
+            });
+            if let Some((level, text)) = status.as_ref() {
+                palette.status_label(ui, *level, text);
+            }
+
+            // Cadence et durée des cycles
+            if *running || stats.cycles > 0 {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{:.0} Hz", stats.rate_hz));
+                    ui.separator();
+                    ui.label(format!("cycle {:.1} ms", stats.cycle().as_secs_f64() * 1000.0)).on_hover_text(format!(
+                        "Leader read {:.1} ms, follower write {:.1} ms",
+                        stats.read.as_secs_f64() * 1000.0,
+                        stats.write.as_secs_f64() * 1000.0
+                    ));
+                    ui.separator();
+                    ui.label(format!("max {:.1} ms", stats.max_cycle.as_secs_f64() * 1000.0))
+                        .on_hover_text("Longest cycle over the last second");
+                    ui.separator();
+                    ui.weak(format!("{} cycles", stats.cycles));
+                });
+            }
+
+            // --- ARTICULATIONS ---
+            ui.separator();
+            let mut removed = None;
+            egui::Grid::new("teleop_joints").num_columns(8).striped(true).show(ui, |ui| {
+                for header in ["On", "Leader", "Follower", "Offset", "Inverted", "Leader pos", "Target", ""] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+                for (index, joint) in settings.joints.iter_mut().enumerate() {
+                    ui.push_id(index, |ui| ui.checkbox(&mut joint.enabled, ""));
+                    ui.add_enabled(!*running, egui::DragValue::new(&mut joint.leader).range(0..=MAX_SERVO_ID));
+                    ui.add_enabled(!*running, egui::DragValue::new(&mut joint.follower).range(0..=MAX_SERVO_ID));
+                    ui.add(egui::DragValue::new(&mut joint.offset).range(-4095..=4095).suffix(" ticks"));
+                    ui.push_id(("inverted", index), |ui| ui.checkbox(&mut joint.inverted, ""))
+                        .inner
+                        .on_hover_text("Mirror the leader position around 2048");
+                    match positions.get(&joint.follower).filter(|_| joint.enabled) {
+                        Some(&(leader, target)) => {
+                            ui.label(leader.to_string());
+                            ui.label(target.to_string());
+                        }
+                        None => {
+                            ui.weak("—");
+                            ui.weak("—");
+                        }
+                    }
+                    if ui.add_enabled(!*running, egui::Button::new("🗑").small()).clicked() {
+                        removed = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(index) = removed {
+                settings.joints.remove(index);
+            }
+            if ui.add_enabled(!*running, egui::Button::new("+ Add joint")).clicked() {
+                let next = settings.joints.iter().map(|joint| joint.leader.max(joint.follower)).max().map_or(1, |id| id.saturating_add(1));
+                settings.joints.push(TeleopJoint::identity(next.min(MAX_SERVO_ID)));
+            }
+        });
+
+        if state.running || state.scanning {
+            ctx.request_repaint_after(REPAINT_INTERVAL);
+        }
+    }
+}
+
+// --- BACKEND (THREAD) ---
+// Les deux bus ne sont verrouillés et ouverts que pendant la téléopération et le scan
+fn teleop_worker(state: Arc<Mutex<SharedState>>, ctx: egui::Context, mut leader: ServoWorker, mut follower: ServoWorker, shutdown: ShutdownSignal) {
+    let mut locks: (Option<PortLock>, Option<PortLock>) = (None, None);
+    // Couple coupé sur le meneur et activé sur le suiveur pour la session en cours
+    let mut started = false;
+    // Dernière consigne envoyée par servo suiveur : une consigne inchangée n'est pas réécrite
+    let mut sent: HashMap<u8, u16> = HashMap::new();
+    let config = Config::load();
+    let (scan_range, follower_limits, first_move) = (config.bus.scan, config.limits, config.first_move);
+
+    while !shutdown.is_requested() {
+        let (running, scan, settings) = {
+            let mut s = state.lock().unwrap();
+            (s.running, std::mem::take(&mut s.scan_requested), s.settings.clone())
+        };
+        if !running && !scan {
+            if started {
+                println!("Téléopération arrêtée");
+                started = false;
+            }
+            release(&mut locks, &mut leader, &mut follower);
+            thread::sleep(IDLE_POLL);
+            continue;
+        }
+
+        // Ports verrouillés puis ouverts ; un bus inaccessible gèle le suiveur
+        leader.set_port(settings.leader_port.as_str());
+        follower.set_port(settings.follower_port.as_str());
+        let opened = open(&mut locks.0, &mut leader, "leader").and_then(|()| open(&mut locks.1, &mut follower, "follower"));
+        if let Err(reason) = opened {
+            if scan {
+                let mut s = state.lock().unwrap();
+                s.scanning = false;
+                s.status = Some((Status::Danger, format!("Scan failed: {}", reason)));
+            } else {
+                freeze(&state, reason);
+            }
+            started = false;
+            release(&mut locks, &mut leader, &mut follower);
+            ctx.request_repaint();
+            continue;
+        }
+        let (Some(leader_driver), Some(follower_driver)) = (leader.driver(), follower.driver()) else { continue };
+
+        if scan {
+            let leader_ids: Vec<u8> = scan_range.ids().filter(|&id| leader_driver.ping_servo(id)).collect();
+            let follower_ids: Vec<u8> = scan_range.ids().filter(|&id| follower_driver.ping_servo(id)).collect();
+            println!("Scan : meneur {:?}, suiveur {:?}", leader_ids, follower_ids);
+            let mut s = state.lock().unwrap();
+            let added = s.settings.add_identity_joints(&leader_ids);
+            let missing: Vec<String> = s.settings.joints.iter()
+                .filter(|joint| joint.enabled && !follower_ids.contains(&joint.follower))
+                .map(|joint| joint.follower.to_string())
+                .collect();
+            s.scanning = false;
+            s.status = Some(match missing.is_empty() {
+                true => (Status::Ok, format!("Leader: {} servo(s), follower: {} servo(s), {} joint(s) added", leader_ids.len(), follower_ids.len(), added)),
+                false => (Status::Warning, format!("Follower ID {} not found", missing.join(", "))),
+            });
+            ctx.request_repaint();
+            continue;
+        }
+
+        let joints = settings.active_joints();
+        if !started {
+            sent.clear();
+            if let Err(e) = teleop::start(leader_driver, follower_driver, &joints, &follower_limits, first_move.max_delta) {
+                freeze(&state, e);
+                continue;
+            }
+            println!("Téléopération démarrée : {} articulation(s)", joints.len());
+            started = true;
+        }
+        match teleop::cycle(leader_driver, follower_driver, &joints, &settings, &follower_limits, &mut sent) {
+            Ok(report) => {
+                let mut s = state.lock().unwrap();
+                s.stats.record(&report, Instant::now());
+                s.positions = report.joints.iter().map(|&(joint, position, target)| (joint.follower, (position, target))).collect();
+            }
+            Err(e) => {
+                freeze(&state, e);
+                started = false;
+                ctx.request_repaint();
+            }
+        }
+    }
+}
+
+// Bus fermés et verrous rendus, pour les autres outils
+fn release(locks: &mut (Option<PortLock>, Option<PortLock>), leader: &mut ServoWorker, follower: &mut ServoWorker) {
+    leader.disconnect();
+    follower.disconnect();
+    *locks = (None, None);
+}
+
+// Verrou puis connexion d'un bus ; l'erreur nomme le bus
+fn open(lock: &mut Option<PortLock>, worker: &mut ServoWorker, bus: &str) -> Result<(), String> {
+    portlock::hold(lock, worker.port(), false).map_err(|owner| format!("{} port {} already in use by {}", bus, worker.port(), owner))?;
+    worker.connect();
+    match worker.is_connected() {
+        true => Ok(()),
+        false => Err(format!("{} port {}: could not open", bus, worker.port())),
+    }
+}
+
+// --- LIGNE DE COMMANDE ---
+struct LaunchOptions {
+    dry_run: bool,
+    // Deux bus simulés et leurs ports (meneur, suiveur)
+    simulation: Option<(Simulation, String, String)>,
+}
+
+impl LaunchOptions {
+    fn parse(args: &[String]) -> Result<Self, String> {
+        let simulation = match Simulation::from_args(args)? {
+            Some(simulation) => {
+                let start = || simulation.start().map_err(|e| format!("Could not start the simulated bus: {}", e));
+                let (leader, follower) = (start()?, start()?);
+                Some((simulation, leader, follower))
+            }
+            None => None,
+        };
+        Ok(Self { dry_run: args.iter().any(|a| a == "--dry-run"), simulation })
+    }
+}
+
+fn main() -> Result<(), eframe::Error> {
+    let launch = match logging::setup(std::env::args().skip(1).collect()).and_then(|args| LaunchOptions::parse(&args)) {
+        Ok(launch) => launch,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(2);
+        }
+    };
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([620.0, 520.0]),
+        ..Default::default()
+    };
+    eframe::run_native("Servo Teleoperation", options, Box::new(move |cc| Ok(Box::new(TeleopApp::new(cc, launch)))))
+}
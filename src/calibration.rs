@@ -0,0 +1,104 @@
+//! Réglage du milieu après montage : l'articulation posée à la main, couple coupé, la position
+//! actuelle devient la position 2048 par le registre de correction (EEPROM). Les positions lues
+//! ensuite sont décalées d'autant : butées logicielles et historiques suivent.
+
+use crate::limits::SoftLimits;
+use crate::registers::{self, Register, RegisterPort, TORQUE_ENABLE};
+use crate::units::MAX_TICKS;
+
+pub const CENTER: u16 = 2048;
+/// Correction max du registre, en valeur absolue (11 bits et un bit de signe)
+pub const MAX_OFFSET: i32 = 2047;
+
+/// Réglage calculé : correction à écrire, et décalage des positions lues
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Centering {
+    pub offset: i32,
+    pub shift: i32,
+}
+
+impl Centering {
+    /// `position` lue avec la correction `offset` en place ; le servo lit position brute − correction
+    pub fn new(position: u16, offset: i32) -> Result<Self, String> {
+        let shift = i32::from(position) - i32::from(CENTER);
+        // Un tour complet donne la même position mécanique
+        let offset = (offset + shift + 2048).rem_euclid(4096) - 2048;
+        if offset.abs() > MAX_OFFSET {
+            return Err(format!("position {} needs an offset of {}, beyond ±{}", position, offset, MAX_OFFSET));
+        }
+        Ok(Self { offset, shift })
+    }
+
+    /// Position lue après le réglage, pour une position lue avant
+    pub fn apply(&self, position: u16) -> u16 {
+        (i32::from(position) - self.shift).clamp(0, i32::from(MAX_TICKS)) as u16
+    }
+
+    pub fn limits(&self, limits: &SoftLimits) -> SoftLimits {
+        SoftLimits { min: self.apply(limits.min), max: self.apply(limits.max), ..*limits }
+    }
+}
+
+fn register(name: &str) -> &'static Register {
+    registers::all_registers().find(|r| r.name == name).expect("register in table")
+}
+
+/// Réglage pour la pose actuelle ; refusé couple actif, l'articulation doit être posée à la main
+pub fn plan(bus: &mut RegisterPort, id: u8) -> Result<Centering, String> {
+    if bus.read(id, &TORQUE_ENABLE)? != 0 {
+        return Err(format!("torque is on for ID {}: turn it off and pose the joint by hand", id));
+    }
+    let position = bus.read(id, register("Present Position"))?;
+    let offset = bus.read(id, register("Position Offset"))?;
+    Centering::new(position as u16, offset)
+}
+
+/// Écrit la correction (relue), puis aligne la consigne sur la nouvelle position pour que la
+/// réactivation du couple ne fasse pas sauter l'articulation. Retourne la position relue.
+pub fn apply(bus: &mut RegisterPort, id: u8, centering: &Centering) -> Result<u16, String> {
+    bus.write(id, register("Position Offset"), centering.offset)?;
+    let position = bus.read(id, register("Present Position"))?;
+    bus.write(id, register("Goal Position"), position)?;
+    Ok(position as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendCall, MockBackend, MockServo};
+
+    #[test]
+    fn offset_adds_to_the_existing_correction() {
+        assert_eq!(Centering::new(2148, 0), Ok(Centering { offset: 100, shift: 100 }));
+        assert_eq!(Centering::new(1948, 30), Ok(Centering { offset: -70, shift: -100 }));
+        // Un tour complet plus loin : même position mécanique
+        assert_eq!(Centering::new(100, -2000).unwrap().offset, 148);
+        assert!(Centering::new(0, 0).is_err());
+    }
+
+    #[test]
+    fn readings_and_soft_limits_follow_the_new_center() {
+        let centering = Centering::new(2148, 0).unwrap();
+        assert_eq!(centering.apply(2148), CENTER);
+        assert_eq!(centering.apply(50), 0);
+        let limits = SoftLimits { min: 1000, max: 4095, ..SoftLimits::default() };
+        assert_eq!(centering.limits(&limits), SoftLimits { min: 900, max: 3995, ..limits });
+    }
+
+    #[test]
+    fn calibration_is_refused_under_torque_and_aligns_the_goal() {
+        let mock = MockBackend::new().with_servo(2, MockServo { position: 2148, torque: true, ..MockServo::default() });
+        let mut bus = RegisterPort::new(&mock);
+        assert!(plan(&mut bus, 2).unwrap_err().contains("torque is on"));
+
+        mock.update(2, |s| s.torque = false);
+        let centering = plan(&mut bus, 2).unwrap();
+        assert_eq!(centering, Centering { offset: 100, shift: 100 });
+        assert_eq!(apply(&mut bus, 2, &centering), Ok(2148));
+        let write = |address, data: &[u8]| BackendCall::WriteRegister { id: 2, address, data: data.to_vec() };
+        assert_eq!(
+            mock.calls(),
+            vec![write(st3215::STS_LOCK, &[0]), write(31, &[100, 0]), write(st3215::STS_LOCK, &[1]), write(42, &[0x64, 0x08])]
+        );
+    }
+}
@@ -0,0 +1,120 @@
+//! Chorégraphie : une même forme d'onde périodique sur plusieurs servos, décalée en phase.
+//!
+//! ```json
+//! { "waveform": "sine", "amplitude": 400, "period_s": 3.0,
+//!   "servos": [ { "id": 1, "phase": 0.0 }, { "id": 2, "phase": 0.25 } ] }
+//! ```
+
+use crate::units::MAX_TICKS;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::TAU;
+use std::path::Path;
+use std::time::Duration;
+
+pub const CHOREOGRAPHY_FILE: &str = "choreography.json";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Waveform {
+    #[default]
+    Sine,
+    Triangle,
+    Square,
+    Sawtooth,
+}
+
+impl Waveform {
+    pub const ALL: [Waveform; 4] = [Waveform::Sine, Waveform::Triangle, Waveform::Square, Waveform::Sawtooth];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Waveform::Sine => "Sine",
+            Waveform::Triangle => "Triangle",
+            Waveform::Square => "Square",
+            Waveform::Sawtooth => "Sawtooth",
+        }
+    }
+
+    /// Valeur dans `-1..=1` pour une phase en tours (seule la partie fractionnaire compte)
+    pub fn sample(self, phase: f64) -> f64 {
+        let p = phase.rem_euclid(1.0);
+        match self {
+            Waveform::Sine => (p * TAU).sin(),
+            // Même départ que le sinus : 0 → 1 → 0 → -1 → 0
+            Waveform::Triangle => 1.0 - 4.0 * (p - 0.25).rem_euclid(1.0).min(1.0 - (p - 0.25).rem_euclid(1.0)),
+            Waveform::Square => if p < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Sawtooth => 2.0 * (p + 0.5).rem_euclid(1.0) - 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChoreographyServo {
+    pub id: u8,
+    /// Décalage de phase en tours (0..1)
+    #[serde(default)]
+    pub phase: f64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Choreography {
+    #[serde(default)]
+    pub waveform: Waveform,
+    /// Amplitude crête en ticks autour de la position de départ de chaque servo
+    pub amplitude: u16,
+    pub period_s: f64,
+    #[serde(default)]
+    pub servos: Vec<ChoreographyServo>,
+}
+
+impl Default for Choreography {
+    fn default() -> Self {
+        Self { waveform: Waveform::Sine, amplitude: 300, period_s: 3.0, servos: Vec::new() }
+    }
+}
+
+impl Choreography {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Répartit les décalages régulièrement le long de la chaîne (onde qui se propage)
+    pub fn distribute_phases(&mut self) {
+        let n = self.servos.len().max(1) as f64;
+        for (i, servo) in self.servos.iter_mut().enumerate() {
+            servo.phase = i as f64 / n;
+        }
+    }
+
+    /// Consigne d'un servo pour la phase globale `phase` (en tours)
+    pub fn target(&self, servo: &ChoreographyServo, center: u16, phase: f64) -> u16 {
+        let offset = self.waveform.sample(phase + servo.phase) * self.amplitude as f64;
+        (center as f64 + offset).round().clamp(0.0, MAX_TICKS as f64) as u16
+    }
+}
+
+/// Phase globale accumulée : changer la période en cours ne fait pas sauter les consignes
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PhaseClock {
+    phase: f64,
+}
+
+impl PhaseClock {
+    /// Avance de `dt` à la période `period_s` et renvoie la phase (en tours, 0..1)
+    pub fn advance(&mut self, dt: Duration, period_s: f64) -> f64 {
+        if period_s > 0.0 {
+            self.phase = (self.phase + dt.as_secs_f64() / period_s).rem_euclid(1.0);
+        }
+        self.phase
+    }
+
+    pub fn phase(&self) -> f64 {
+        self.phase
+    }
+}
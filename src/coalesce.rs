@@ -0,0 +1,86 @@
+//! Débit des consignes de slider : mode d'envoi côté interface et fusion des consignes en file
+//! côté worker, pour que le bus half-duplex ne soit pas saturé pendant un glissement.
+
+use serde::{Deserialize, Serialize};
+
+/// Moment où un slider de position envoie sa consigne
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SliderMode {
+    /// Le servo suit le slider pendant le glissement (petits robots)
+    #[default]
+    Live,
+    /// Une seule consigne au relâchement (bras chargé)
+    OnRelease,
+}
+
+impl SliderMode {
+    pub const ALL: [SliderMode; 2] = [SliderMode::Live, SliderMode::OnRelease];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SliderMode::Live => "Live follow",
+            SliderMode::OnRelease => "On release",
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl SliderMode {
+    /// Vrai si la consigne du widget (slider ou champ) doit partir maintenant : à chaque
+    /// changement en suivi direct, sinon à la fin du glissement ou à une saisie sans glissement
+    pub fn should_send(self, response: &egui::Response) -> bool {
+        match self {
+            SliderMode::Live => response.changed(),
+            SliderMode::OnRelease => response.drag_stopped() || (response.changed() && !response.is_pointer_button_down_on()),
+        }
+    }
+}
+
+/// Ne garde, pour chaque clé, que la dernière commande ; les commandes sans clé sont toutes
+/// gardées. L'ordre d'arrivée est conservé : la consigne retenue reste à sa place dans la file.
+pub fn keep_latest<T, K: PartialEq>(commands: Vec<T>, key: impl Fn(&T) -> Option<K>) -> Vec<T> {
+    let keys: Vec<Option<K>> = commands.iter().map(key).collect();
+    commands
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| match &keys[*i] {
+            Some(k) => !keys[i + 1..].iter().any(|later| later.as_ref() == Some(k)),
+            None => true,
+        })
+        .map(|(_, command)| command)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (servo, consigne) ; servo `None` : commande jamais fusionnée (arrêt d'urgence...)
+    fn key(command: &(Option<u8>, u16)) -> Option<u8> {
+        command.0
+    }
+
+    #[test]
+    fn keeps_last_command_per_key_in_arrival_order() {
+        let commands = vec![(Some(1), 100), (Some(2), 200), (Some(1), 110), (None, 0), (Some(1), 120), (Some(3), 300)];
+        assert_eq!(
+            keep_latest(commands, key),
+            vec![(Some(2), 200), (None, 0), (Some(1), 120), (Some(3), 300)]
+        );
+    }
+
+    #[test]
+    fn unkeyed_commands_are_all_kept() {
+        let commands = vec![(None, 1), (None, 2), (None, 3)];
+        assert_eq!(keep_latest(commands.clone(), key), commands);
+        assert!(keep_latest(Vec::<(Option<u8>, u16)>::new(), key).is_empty());
+    }
+
+    #[test]
+    fn slider_mode_serialization() {
+        assert_eq!(serde_json::to_string(&SliderMode::OnRelease).unwrap(), "\"on-release\"");
+        assert_eq!(serde_json::from_str::<SliderMode>("\"live\"").unwrap(), SliderMode::Live);
+        assert_eq!(SliderMode::default(), SliderMode::Live);
+    }
+}
@@ -0,0 +1,442 @@
+//! Réglages persistants, lus dans `init-servo.toml` : celui du répertoire courant s'il existe,
+//! sinon celui du répertoire de configuration (`$XDG_CONFIG_HOME/init-servo/`, par défaut
+//! `~/.config/init-servo/`). Les options de la ligne de commande priment sur le fichier, qui prime
+//! sur les valeurs par défaut.
+
+use crate::coalesce::SliderMode;
+use crate::derating::DeratingCurve;
+use crate::groups::ServoGroup;
+use crate::follow::FollowSettings;
+use crate::gamepad::GamepadSettings;
+use crate::history::MIN_HISTORY;
+use crate::hotplug::RescanSettings;
+use crate::jog::JogStep;
+use crate::ids::ScanRange;
+use crate::limits::SoftLimits;
+use crate::retry::RetrySettings;
+use crate::shutdown::ExitSettings;
+use crate::stall::StallSettings;
+use crate::teleop::TeleopSettings;
+use crate::telemetrylog::LogSettings;
+use crate::theme::Theme;
+use crate::units::AngleDisplay;
+use crate::validation::FirstMoveSettings;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use st3215::DEFAULT_BAUDRATE;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const CONFIG_FILE: &str = "init-servo.toml";
+/// Sous-répertoire du répertoire de configuration de l'utilisateur
+pub const CONFIG_DIR: &str = "init-servo";
+pub const DEFAULT_PORT: &str = "/dev/ttyACM0";
+/// Écart consigne/position (ticks) jugé correct sur les cartes de servo-all
+pub const DEFAULT_DELTA_TOLERANCE: u16 = 10;
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Config {
+    #[serde(default)]
+    pub bus: BusConfig,
+    #[serde(default)]
+    pub ui: UiConfig,
+    #[serde(default)]
+    pub alerts: AlertConfig,
+    #[serde(default)]
+    pub derating: DeratingCurve,
+    #[serde(default)]
+    pub cli: CliConfig,
+    #[serde(default)]
+    pub logging: LogSettings,
+    /// Butées logicielles par ID (`[limits.3]`), gardées d'une connexion à l'autre
+    #[serde(default)]
+    pub limits: BTreeMap<u8, SoftLimits>,
+    #[serde(default)]
+    pub stall: StallSettings,
+    #[serde(default)]
+    pub rescan: RescanSettings,
+    /// Nouvelles tentatives des transactions du bus (`[retry] attempts = 3, delay_ms = 2`)
+    #[serde(default)]
+    pub retry: RetrySettings,
+    /// Parcage et coupure du couple à la fermeture (`[exit]`)
+    #[serde(default)]
+    pub exit: ExitSettings,
+    /// Téléopération entre deux bus (`[teleop]`), pour servo-teleop
+    #[serde(default)]
+    pub teleop: TeleopSettings,
+    /// Manette dans les interfaces (`[gamepad]`) : homme mort, cadence et liaisons
+    #[serde(default)]
+    pub gamepad: GamepadSettings,
+    /// Nom, réglage de mouvement et sens de chaque servo (`[servos.3]`), repris à la détection
+    #[serde(default)]
+    pub servos: BTreeMap<u8, ServoSettings>,
+    /// Groupes nommés (`[[groups]] name = "left leg", ids = [1, 2, 3]`), commandés d'un coup
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub groups: Vec<ServoGroup>,
+    /// Garde du premier mouvement après sélection ou connexion (`[first_move] max_delta = 500`)
+    #[serde(default)]
+    pub first_move: FirstMoveSettings,
+    /// Erreur de lecture du fichier dont ces valeurs (par défaut) tiennent lieu : `save` refuse
+    /// alors de l'écraser
+    #[serde(skip)]
+    pub load_error: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServoSettings {
+    /// Nom affiché à la place de l'ID : « coude gauche (3) »
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    /// Vitesse et accélération des consignes ; sans valeur, le réglage commun
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub speed: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub acceleration: Option<u8>,
+    /// Sens inversé (`inversion`) : positions, butées et poses en valeurs miroir autour de 2048
+    #[serde(alias = "invert")]
+    pub inverted: bool,
+    /// Suivi d'un autre servo (`follow`), calculé par l'interface multi-servos
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follow: Option<FollowSettings>,
+}
+
+impl ServoSettings {
+    /// Rien à garder : l'entrée est retirée du fichier
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// « coude gauche (3) », ou « ID 3 » sans nom
+pub fn servo_label(id: u8, name: &str) -> String {
+    match name.trim() {
+        "" => format!("ID {}", id),
+        name => format!("{} ({})", name, id),
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BusConfig {
+    pub port: String,
+    /// Le pilote n'ouvre le port qu'à `DEFAULT_BAUDRATE` : toute autre valeur est refusée
+    pub baud: u32,
+    /// Plage d'ID balayée par l'interface multi-servos
+    pub scan: ScanRange,
+    /// Pause entre deux cycles de lecture ; sans valeur, celle propre à chaque interface
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poll_interval_ms: Option<u64>,
+    /// Reste sur `port` même si l'adaptateur réapparaît sous un autre chemin (plusieurs
+    /// adaptateurs identiques)
+    pub pin_port: bool,
+}
+
+impl Default for BusConfig {
+    fn default() -> Self {
+        Self { port: DEFAULT_PORT.to_string(), baud: DEFAULT_BAUDRATE, scan: ScanRange::default(), poll_interval_ms: None, pin_port: false }
+    }
+}
+
+impl BusConfig {
+    pub fn check_baud(baud: u32) -> Result<(), String> {
+        match baud == DEFAULT_BAUDRATE {
+            true => Ok(()),
+            false => Err(format!("{} baud is not supported: the driver always opens the port at {} baud", baud, DEFAULT_BAUDRATE)),
+        }
+    }
+
+    pub fn poll_interval(&self, default: Duration) -> Duration {
+        self.poll_interval_ms.map_or(default, Duration::from_millis)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AlertConfig {
+    /// Alerte de surchauffe au-delà de cette température (°C)
+    pub over_temperature: u8,
+}
+
+impl Default for AlertConfig {
+    fn default() -> Self {
+        Self { over_temperature: 60 }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct UiConfig {
+    #[serde(default)]
+    pub theme: Theme,
+    #[serde(default)]
+    pub slider_mode: SliderMode,
+    /// Unité des positions affichées (les consignes restent en ticks)
+    #[serde(default)]
+    pub angle: AngleDisplay,
+    /// Points conservés par série des graphiques (100 à 100 000)
+    #[serde(default = "default_history_samples")]
+    pub history_samples: usize,
+    /// Pas du jog au clavier de servo-gui
+    #[serde(default)]
+    pub jog_step: JogStep,
+    /// Écart consigne/position (ticks) affiché en vert sur les cartes de servo-all
+    #[serde(default = "default_delta_tolerance")]
+    pub delta_tolerance: u16,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            slider_mode: SliderMode::default(),
+            angle: AngleDisplay::default(),
+            history_samples: default_history_samples(),
+            jog_step: JogStep::default(),
+            delta_tolerance: DEFAULT_DELTA_TOLERANCE,
+        }
+    }
+}
+
+fn default_history_samples() -> usize {
+    MIN_HISTORY
+}
+
+fn default_delta_tolerance() -> u16 {
+    DEFAULT_DELTA_TOLERANCE
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct CliConfig {
+    /// En quittant `servo-cli shell`, coupe le couple des servos mis sous couple pendant la session
+    #[serde(default)]
+    pub torque_off_on_exit: bool,
+}
+
+/// Fichier du répertoire de configuration de l'utilisateur ; `None` sans `$XDG_CONFIG_HOME` ni `$HOME`
+pub fn user_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(base.join(CONFIG_DIR).join(CONFIG_FILE))
+}
+
+/// Fichier lu et écrit : celui du répertoire courant s'il existe, sinon celui de l'utilisateur
+/// s'il existe, sinon le répertoire courant
+pub fn resolve_path() -> PathBuf {
+    let local = PathBuf::from(CONFIG_FILE);
+    if local.exists() {
+        return local;
+    }
+    user_path().filter(|path| path.exists()).unwrap_or(local)
+}
+
+impl Config {
+    /// Valeurs par défaut si le fichier est absent ; un fichier invalide est signalé puis ignoré,
+    /// et gardé tel quel jusqu'à sa correction (`load_error`)
+    pub fn load() -> Self {
+        Self::load_from(&resolve_path())
+    }
+
+    pub fn load_from(path: &Path) -> Self {
+        match std::fs::read_to_string(path) {
+            Ok(text) => toml::from_str(&text).unwrap_or_else(|e| {
+                let error = format!("{}: {}", path.display(), e);
+                eprintln!("{}", error);
+                Config { load_error: Some(error), ..Config::default() }
+            }),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Config::default(),
+            Err(e) => {
+                let error = format!("{}: {}", path.display(), e);
+                eprintln!("{}", error);
+                Config { load_error: Some(error), ..Config::default() }
+            }
+        }
+    }
+
+    /// Écrit dans le fichier d'où la configuration a été lue (`resolve_path`)
+    pub fn save(&self) -> Result<(), String> {
+        self.save_to(&resolve_path())
+    }
+
+    /// Refusé si la lecture a échoué : les valeurs par défaut remplaceraient tout le fichier
+    pub fn save_to(&self, path: &Path) -> Result<(), String> {
+        if let Some(error) = &self.load_error {
+            return Err(format!("settings not saved, fix the file first ({})", error));
+        }
+        let text = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+        std::fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Nom du servo suivi de son ID, ou « ID 3 » sans nom
+    pub fn servo_label(&self, id: u8) -> String {
+        servo_label(id, self.servos.get(&id).map_or("", |servo| servo.name.as_str()))
+    }
+
+    /// Réglages d'un servo remplacés ; une entrée vide est retirée
+    pub fn set_servo(&mut self, id: u8, settings: ServoSettings) {
+        match settings.is_empty() {
+            true => self.servos.remove(&id),
+            false => self.servos.insert(id, settings),
+        };
+    }
+}
+
+// Commentaire placé avant chaque section du modèle, et exemple après les sections vides
+const TEMPLATE_SECTIONS: &[(&str, &str, &str)] = &[
+    ("[bus]", "# Port série, débit (seul 1000000 est pris en charge), plage d'ID scannée par servo-all.\n# poll_interval_ms = 100 remplace la cadence de lecture des interfaces. pin_port = true : pas de\n# bascule vers le nouveau chemin de l'adaptateur après un rebranchement (--pin-port).", ""),
+    ("[ui]", "# Apparence des interfaces ; history_samples : points gardés par courbe (100 à 100000) ;\n# jog_step : pas du jog au clavier de servo-gui (fine = 1, medium = 10, coarse = 100 ticks) ;\n# delta_tolerance : écart consigne/position (ticks) jugé atteint sur les cartes de servo-all", ""),
+    ("[alerts]", "# Seuil de l'alerte de surchauffe (°C)", ""),
+    ("[derating]", "# Réduction du couple avec la température, coupure à `cutoff`, réarmement à `rearm`", ""),
+    ("[cli]", "# torque_off_on_exit : en quittant `servo-cli shell`, coupe le couple des servos activés", ""),
+    ("[logging]", "# Journal continu de télémétrie (CSV), remplacé au-delà de max_size_mb", ""),
+    ("[limits]", "# Butées logicielles par ID", "# [limits.1]\n# min = 500\n# max = 3500"),
+    ("[stall]", "# Détection de blocage : charge (%) ou courant (mA) pendant `polls` lectures", ""),
+    ("[rescan]", "# Nouveau scan périodique pour détecter les servos branchés à chaud", ""),
+    ("[retry]", "# Nouvelles tentatives d'une transaction du bus en échec", ""),
+    ("[exit]", "# À la fermeture : coupure du couple partout, et parcage des ID de [exit.park]", ""),
+    ("[exit.park]", "", "# 1 = 2048"),
+    ("[gamepad]", "# Manette dans servo-gui et servo-all : rien ne bouge sans l'homme mort tenu (deadman = true).\n# Les [[gamepad.calibrations]] (course, zone morte et courbe par manette et par axe) sont\n# relevées dans le panneau Gamepad.", "# [[gamepad.bindings]]\n# target = { servo = 3 }\n# input = { axis = \"left_stick_x\" }\n# mode = \"jog\"\n# sensitivity = 1.0\n# deadzone = 0.15"),
+    ("[teleop]", "# servo-teleop : bras meneur (couple coupé) et bras suiveur sur deux ports. Sans articulation,\n# le scan reprend les IDs du meneur tels quels.", "# [[teleop.joints]]\n# leader = 1\n# follower = 11\n# offset = 0\n# inverted = false\n# enabled = true"),
+    ("[first_move]", "# Premier mouvement après sélection ou connexion : au-delà de max_delta ticks de la position\n# lue, la consigne doit être confirmée (bouton, ou --large en ligne de commande). 0 = désactivée.", ""),
+    ("[servos]", "# Réglages par ID : nom affiché, vitesse et accélération par défaut, sens inversé\n# (positions, butées logicielles et poses en miroir autour de 2048) et suivi d'un autre servo.\n# Un servo listé ici mais absent au scan est signalé « not detected » dans les interfaces.", "# [servos.3]\n# name = \"coude gauche\"\n# speed = 800\n# acceleration = 30\n# inverted = true\n# follow = { enabled = true, source = 2, scale = -1.0, offset = 0, deadband = 8 }"),
+];
+
+// Aucun groupe par défaut : la section n'est qu'un exemple commenté
+const GROUPS_EXAMPLE: &str = "\n# Groupes de servos commandés d'un coup dans servo-all (couple, pose, décalage commun)\n# [[groups]]\n# name = \"left leg\"\n# ids = [1, 2, 3]\n";
+
+/// Modèle commenté écrit par `servo-cli config init`, avec les valeurs par défaut
+pub fn template() -> String {
+    let defaults = toml::to_string_pretty(&Config::default()).unwrap_or_default();
+    let mut text = format!("# Configuration d'init-servo ({}). Les options de la ligne de commande priment.\n", CONFIG_FILE);
+    for line in defaults.lines() {
+        let section = TEMPLATE_SECTIONS.iter().find(|(header, _, _)| *header == line);
+        if let Some((_, comment, _)) = section.filter(|(_, comment, _)| !comment.is_empty()) {
+            text.push_str(comment);
+            text.push('\n');
+        }
+        text.push_str(line);
+        text.push('\n');
+        if let Some((_, _, example)) = section.filter(|(_, _, example)| !example.is_empty()) {
+            text.push_str(example);
+            text.push('\n');
+        }
+    }
+    text.push_str(GROUPS_EXAMPLE);
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_file(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("init-servo-config-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn broken_file_is_never_overwritten() {
+        let path = temp_file("broken.toml");
+        std::fs::write(&path, "[bus\nport = \"/dev/ttyUSB0\"\n").unwrap();
+        let mut config = Config::load_from(&path);
+        assert!(config.load_error.is_some());
+
+        config.bus.port = "/dev/ttyUSB1".to_string();
+        assert!(config.save_to(&path).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[bus\nport = \"/dev/ttyUSB0\"\n");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn delta_tolerance_defaults_and_round_trips() {
+        let path = temp_file("tolerance.toml");
+        std::fs::write(&path, "[ui]\ntheme = \"high-contrast\"\n").unwrap();
+        let mut config = Config::load_from(&path);
+        assert_eq!(config.ui.delta_tolerance, DEFAULT_DELTA_TOLERANCE);
+
+        config.ui.delta_tolerance = 25;
+        config.save_to(&path).unwrap();
+        assert_eq!(Config::load_from(&path).ui.delta_tolerance, 25);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn missing_file_is_created_on_save() {
+        let path = temp_file("missing.toml");
+        let _ = std::fs::remove_file(&path);
+        let mut config = Config::load_from(&path);
+        assert!(config.load_error.is_none());
+
+        config.bus.port = "/dev/ttyUSB1".to_string();
+        config.save_to(&path).unwrap();
+        assert_eq!(Config::load_from(&path).bus.port, "/dev/ttyUSB1");
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn soft_limits_are_kept_per_id() {
+        let path = temp_file("limits.toml");
+        std::fs::write(&path, "[limits.3]\nmin = 1000\nmax = 3000\n").unwrap();
+        let mut config = Config::load_from(&path);
+        assert_eq!(config.limits[&3], SoftLimits { min: 1000, max: 3000, ..SoftLimits::default() });
+
+        config.limits.insert(7, SoftLimits { slowdown: true, ..SoftLimits::default() });
+        config.save_to(&path).unwrap();
+        assert_eq!(Config::load_from(&path).limits, config.limits);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn servo_settings_round_trip_and_label() {
+        let path = temp_file("servos.toml");
+        std::fs::write(&path, "[servos.3]\nname = \"coude gauche\"\nspeed = 800\ninvert = true\n").unwrap();
+        let mut config = Config::load_from(&path);
+        let elbow = ServoSettings { name: "coude gauche".to_string(), speed: Some(800), inverted: true, ..ServoSettings::default() };
+        assert_eq!(config.servos[&3], elbow);
+        assert_eq!(config.servo_label(3), "coude gauche (3)");
+        assert_eq!(config.servo_label(4), "ID 4");
+        assert_eq!(servo_label(5, "  "), "ID 5");
+
+        // Entrée vide : retirée du fichier
+        config.set_servo(3, ServoSettings::default());
+        config.set_servo(4, ServoSettings { acceleration: Some(30), ..ServoSettings::default() });
+        config.save_to(&path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(!text.contains("[servos.3]") && !text.contains("name ="));
+        assert_eq!(Config::load_from(&path).servos, config.servos);
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn bus_settings_parse_and_fall_back() {
+        let path = temp_file("bus.toml");
+        std::fs::write(&path, "[bus]\nport = \"/dev/ttyACM0\"\nscan = \"3-7\"\npoll_interval_ms = 40\n").unwrap();
+        let config = Config::load_from(&path);
+        assert!(config.load_error.is_none());
+        assert_eq!(config.bus.port, "/dev/ttyACM0");
+        assert_eq!(config.bus.baud, DEFAULT_BAUDRATE);
+        assert_eq!(config.bus.scan, ScanRange { start: 3, end: 7 });
+        assert_eq!(config.bus.poll_interval(Duration::from_millis(100)), Duration::from_millis(40));
+        assert_eq!(BusConfig::default().poll_interval(Duration::from_millis(100)), Duration::from_millis(100));
+
+        assert!(BusConfig::check_baud(DEFAULT_BAUDRATE).is_ok());
+        assert!(BusConfig::check_baud(115_200).unwrap_err().contains("115200"));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn template_loads_as_the_defaults() {
+        let path = temp_file("template.toml");
+        std::fs::write(&path, template()).unwrap();
+        let config = Config::load_from(&path);
+        assert!(config.load_error.is_none());
+        assert_eq!(config.bus, BusConfig::default());
+        assert!(template().contains("# [servos.3]"));
+        let _ = std::fs::remove_file(&path);
+    }
+}
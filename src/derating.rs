@@ -0,0 +1,229 @@
+//! Réduction progressive de la vitesse maximale et de la limite de couple quand un servo
+//! chauffe, avec coupure finale
+//! verrouillée : le couple ne revient qu'une fois le servo redescendu sous `rearm` et sur
+//! réactivation explicite.
+//!
+//! ```toml
+//! [derating]
+//! hysteresis = 3
+//! cutoff = 65
+//! rearm = 55
+//! breakpoints = [
+//!     { temperature = 50, percent = 80 },
+//!     { temperature = 55, percent = 60 },
+//! ]
+//! ```
+
+use crate::limits::TorqueLimit;
+use crate::motion::MAX_SPEED;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+
+/// À partir de `temperature` (°C), vitesse et couple plafonnés à `percent` % du maximum
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Breakpoint {
+    pub temperature: u8,
+    pub percent: u8,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeratingCurve {
+    pub breakpoints: Vec<Breakpoint>,
+    /// Écart (°C) sous un seuil avant de relâcher le plafond, pour éviter le battement
+    pub hysteresis: u8,
+    /// Coupure du couple, au-dessus de la courbe
+    pub cutoff: u8,
+    /// Température sous laquelle un servo coupé peut être remis sous couple
+    pub rearm: u8,
+}
+
+impl Default for DeratingCurve {
+    fn default() -> Self {
+        Self {
+            breakpoints: vec![
+                Breakpoint { temperature: 50, percent: 80 },
+                Breakpoint { temperature: 55, percent: 60 },
+                Breakpoint { temperature: 60, percent: 40 },
+                Breakpoint { temperature: 63, percent: 25 },
+            ],
+            hysteresis: 3,
+            cutoff: 65,
+            rearm: 55,
+        }
+    }
+}
+
+impl DeratingCurve {
+    /// Pourcentage du seuil le plus haut atteint (100 sous le premier seuil)
+    pub fn percent_at(&self, temperature: u8) -> u8 {
+        self.breakpoints
+            .iter()
+            .filter(|b| temperature >= b.temperature)
+            .map(|b| b.percent.min(100))
+            .min()
+            .unwrap_or(100)
+    }
+
+    pub fn is_cut_off(&self, temperature: u8) -> bool {
+        temperature >= self.cutoff
+    }
+
+    pub fn is_rearmed(&self, temperature: u8) -> bool {
+        temperature < self.rearm.min(self.cutoff)
+    }
+}
+
+/// Servos coupés pour surchauffe ; pour chacun, vrai une fois redescendu sous le seuil de réarmement.
+/// Partagé entre le worker, qui verrouille, et l'interface : le clone suit les changements.
+#[derive(Clone, Debug, Default)]
+pub struct ThermalLockout {
+    locked: Arc<Mutex<BTreeMap<u8, bool>>>,
+}
+
+impl ThermalLockout {
+    /// Relevé de température ; vrai si le servo vient d'être verrouillé
+    pub fn observe(&self, curve: &DeratingCurve, id: u8, temperature: u8) -> bool {
+        let mut locked = self.locked.lock().unwrap();
+        if let Some(cooled) = locked.get_mut(&id) {
+            *cooled = curve.is_rearmed(temperature);
+            return false;
+        }
+        if curve.is_cut_off(temperature) {
+            locked.insert(id, false);
+            return true;
+        }
+        false
+    }
+
+    pub fn is_locked(&self, id: u8) -> bool {
+        self.locked.lock().unwrap().contains_key(&id)
+    }
+
+    /// `Some(refroidi)` pour un servo verrouillé
+    pub fn state(&self, id: u8) -> Option<bool> {
+        self.locked.lock().unwrap().get(&id).copied()
+    }
+
+    /// Réactivation explicite du couple : refusée tant que le servo est trop chaud
+    pub fn release(&self, curve: &DeratingCurve, id: u8) -> Result<(), String> {
+        let mut locked = self.locked.lock().unwrap();
+        match locked.get(&id) {
+            Some(false) => Err(format!("thermal lockout: wait until below {}°C", curve.rearm)),
+            Some(true) => {
+                locked.remove(&id);
+                Ok(())
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Levée sans condition (dérogation temporaire)
+    pub fn clear(&self, id: u8) {
+        self.locked.lock().unwrap().remove(&id);
+    }
+}
+
+/// État de déclassement d'un servo, mis à jour à chaque lecture de température
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Derating {
+    percent: u8,
+}
+
+impl Default for Derating {
+    fn default() -> Self {
+        Self { percent: 100 }
+    }
+}
+
+impl Derating {
+    /// Plafond réduit immédiatement, relâché seulement `hysteresis` °C sous le seuil franchi
+    pub fn update(&mut self, curve: &DeratingCurve, temperature: u8) -> u8 {
+        let now = curve.percent_at(temperature);
+        if now < self.percent {
+            self.percent = now;
+        } else {
+            let relaxed = curve.percent_at(temperature.saturating_add(curve.hysteresis));
+            self.percent = self.percent.max(relaxed);
+        }
+        self.percent
+    }
+
+    pub fn percent(&self) -> u8 {
+        self.percent
+    }
+
+    /// Vitesse demandée (0 = max) ramenée sous le plafond
+    pub fn cap(&self, speed: u16) -> u16 {
+        if self.percent >= 100 {
+            return speed;
+        }
+        let limit = ((MAX_SPEED as u32 * self.percent as u32) / 100).max(1) as u16;
+        if speed == 0 { limit } else { speed.min(limit) }
+    }
+
+    /// Limite de couple réglée par l'utilisateur, ramenée au même pourcentage
+    pub fn cap_torque(&self, limit: TorqueLimit) -> TorqueLimit {
+        let percent = u32::from(self.percent.min(100));
+        TorqueLimit { permille: (u32::from(limit.permille) * percent / 100) as u16 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cap_follows_the_curve_with_hysteresis() {
+        let curve = DeratingCurve::default();
+        let mut derating = Derating::default();
+        assert_eq!(derating.update(&curve, 56), 60);
+        // 53 °C : sous le seuil de 55, mais pas encore de `hysteresis` degrés
+        assert_eq!(derating.update(&curve, 53), 60);
+        assert_eq!(derating.update(&curve, 51), 80);
+        assert_eq!(derating.cap(0), (MAX_SPEED as u32 * 80 / 100) as u16);
+        assert_eq!(derating.cap(500), 500);
+    }
+
+    #[test]
+    fn torque_limit_is_derated_like_the_speed() {
+        let curve = DeratingCurve::default();
+        let mut derating = Derating::default();
+        let gripper = TorqueLimit::from_percent(50.0);
+        assert_eq!(derating.cap_torque(gripper), gripper);
+
+        derating.update(&curve, 61);
+        assert_eq!(derating.cap_torque(gripper), TorqueLimit { permille: 200 });
+        assert_eq!(derating.cap_torque(TorqueLimit::default()), TorqueLimit { permille: 400 });
+    }
+
+    #[test]
+    fn lockout_latches_until_cooled_and_released() {
+        let curve = DeratingCurve::default();
+        let lockout = ThermalLockout::default();
+        assert!(!lockout.observe(&curve, 1, 64));
+        assert!(lockout.observe(&curve, 1, 66));
+        assert!(!lockout.observe(&curve, 1, 67), "locked once");
+
+        // Sous la coupure mais pas sous le réarmement : toujours verrouillé
+        lockout.observe(&curve, 1, 60);
+        assert_eq!(lockout.state(1), Some(false));
+        assert!(lockout.release(&curve, 1).unwrap_err().contains("55"));
+
+        lockout.observe(&curve, 1, 54);
+        assert_eq!(lockout.state(1), Some(true));
+        assert_eq!(lockout.release(&curve, 1), Ok(()));
+        assert!(!lockout.is_locked(1));
+        assert_eq!(lockout.release(&curve, 2), Ok(()));
+    }
+
+    #[test]
+    fn clear_lifts_the_lockout_while_hot() {
+        let curve = DeratingCurve::default();
+        let lockout = ThermalLockout::default();
+        lockout.observe(&curve, 3, 70);
+        lockout.clear(3);
+        assert_eq!(lockout.state(3), None);
+    }
+}
@@ -0,0 +1,258 @@
+//! Mode répétition (dry-run) : toutes les validations et le journal, mais aucune écriture sur le bus.
+//!
+//! `Driver` enveloppe un `ServoBackend` (le `ST3215` réel ou un bus scripté) : les lectures passent
+//! telles quelles (via `Deref`), les écritures
+//! sont journalisées puis ignorées tant que le mode est actif. Les mouvements sont alors simulés
+//! pour que l'attente d'arrivée se termine normalement.
+
+use crate::backend::ServoBackend;
+use crate::idchange::{check_id_change, IdChangeOutcome};
+use crate::ids::{self, Access};
+use crate::mode::ServoMode;
+use crate::motion::estimate_move_duration;
+use std::collections::HashMap;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Mouvement simulé : interpolation linéaire de `from` vers `to` jusqu'à `arrival`
+#[derive(Clone, Copy, Debug)]
+struct SimulatedMove {
+    from: u16,
+    to: u16,
+    started: Instant,
+    arrival: Instant,
+}
+
+impl SimulatedMove {
+    fn position(&self, now: Instant) -> u16 {
+        if now >= self.arrival {
+            return self.to;
+        }
+        let total = (self.arrival - self.started).as_secs_f64();
+        let progress = if total > 0.0 { (now - self.started).as_secs_f64() / total } else { 1.0 };
+        (self.from as f64 + (self.to as f64 - self.from as f64) * progress).round() as u16
+    }
+}
+
+pub struct Driver {
+    inner: Box<dyn ServoBackend>,
+    dry_run: Arc<AtomicBool>,
+    simulated: Mutex<HashMap<u8, SimulatedMove>>,
+}
+
+impl Deref for Driver {
+    type Target = dyn ServoBackend;
+
+    fn deref(&self) -> &(dyn ServoBackend + 'static) {
+        self.inner.as_ref()
+    }
+}
+
+impl Driver {
+    pub fn new(inner: impl ServoBackend + 'static, dry_run: Arc<AtomicBool>) -> Self {
+        Self::from_boxed(Box::new(inner), dry_run)
+    }
+
+    pub fn from_boxed(inner: Box<dyn ServoBackend>, dry_run: Arc<AtomicBool>) -> Self {
+        Self { inner, dry_run, simulated: Mutex::new(HashMap::new()) }
+    }
+
+    /// Accès direct au bus, hors répétition : pour les coupures de sécurité
+    pub fn backend(&self) -> &dyn ServoBackend {
+        self.inner.as_ref()
+    }
+
+    pub fn dry_run(&self) -> bool {
+        self.dry_run.load(Ordering::Relaxed)
+    }
+
+    pub fn move_to(&self, id: u8, position: u16, speed: u16, acceleration: u8, wait: bool) -> Option<bool> {
+        if !self.dry_run() {
+            self.simulated.lock().unwrap().remove(&id);
+            return self.inner.move_to(id, position, speed, acceleration, wait);
+        }
+        let from = self.position(id)?;
+        let started = Instant::now();
+        let arrival = started + estimate_move_duration(from.abs_diff(position), speed, acceleration);
+        println!("[dry-run] ID {}: move {} → {} (speed {}, acc {})", id, from, position, speed, acceleration);
+        self.simulated.lock().unwrap().insert(id, SimulatedMove { from, to: position, started, arrival });
+        Some(true)
+    }
+
+    pub fn enable_torque(&self, id: u8) -> Result<(), String> {
+        if self.dry_run() {
+            println!("[dry-run] ID {}: torque on", id);
+            return Ok(());
+        }
+        self.inner.enable_torque(id)
+    }
+
+    pub fn disable_torque(&self, id: u8) -> Result<(), String> {
+        if self.dry_run() {
+            println!("[dry-run] ID {}: torque off", id);
+            return Ok(());
+        }
+        self.inner.disable_torque(id)
+    }
+
+    /// Vitesse signée d'un servo en mode roue (passe le servo en mode roue)
+    pub fn rotate(&self, id: u8, speed: i16) -> Result<(), String> {
+        if self.dry_run() {
+            println!("[dry-run] ID {}: wheel speed {}", id, speed);
+            return Ok(());
+        }
+        self.inner.rotate(id, speed)
+    }
+
+    /// Changement de mode. Vers le mode position, la roue est d'abord arrêtée et la consigne
+    /// alignée sur la position actuelle, pour que le servo ne reparte pas vers une ancienne consigne.
+    pub fn set_mode(&self, id: u8, mode: ServoMode) -> Result<(), String> {
+        if self.dry_run() {
+            println!("[dry-run] ID {}: {} mode", id, mode.label().to_lowercase());
+            return Ok(());
+        }
+        if mode == ServoMode::Position {
+            self.inner.rotate(id, 0)?;
+            let position = self.inner.read_position(id).ok_or_else(|| format!("ID {}: no position reading", id))?;
+            self.inner
+                .write_position(id, position)
+                .ok_or_else(|| format!("ID {}: goal position not acknowledged", id))?;
+        }
+        self.inner.set_mode(id, mode.register_value())
+    }
+
+    pub fn change_id(&self, id: u8, new_id: u8) -> Result<(), String> {
+        ids::check_target(id, Access::Eeprom, false)?;
+        ids::check_new_id(new_id)?;
+        if self.dry_run() {
+            println!("[dry-run] ID {}: change ID to {}", id, new_id);
+            return Ok(());
+        }
+        self.inner.change_id(id, new_id)
+    }
+
+    /// Changement d'ID suivi de la relecture des deux ID (fait d'office en dry-run)
+    pub fn change_id_checked(&self, id: u8, new_id: u8) -> Result<IdChangeOutcome, String> {
+        self.change_id(id, new_id)?;
+        if self.dry_run() {
+            return Ok(IdChangeOutcome::Done);
+        }
+        std::thread::sleep(Duration::from_millis(50));
+        Ok(check_id_change(|id| self.ping_servo(id), id, new_id))
+    }
+
+    /// Position simulée en dry-run, sinon position lue
+    pub fn position(&self, id: u8) -> Option<u16> {
+        match self.simulated.lock().unwrap().get(&id) {
+            Some(sim) if self.dry_run() => Some(sim.position(Instant::now())),
+            _ => self.inner.read_position(id),
+        }
+    }
+
+    /// Mouvement en cours : simulé en dry-run (arrivée à la durée estimée), sinon lu
+    pub fn is_moving(&self, id: u8) -> Option<bool> {
+        match self.simulated.lock().unwrap().get(&id) {
+            Some(sim) if self.dry_run() => Some(Instant::now() < sim.arrival),
+            _ => self.inner.is_moving(id),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendCall, MockBackend, MockServo};
+
+    fn driver(mock: &MockBackend, dry_run: bool) -> Driver {
+        Driver::new(mock.clone(), Arc::new(AtomicBool::new(dry_run)))
+    }
+
+    #[test]
+    fn checked_id_change_pings_both_ids() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default());
+        assert_eq!(driver(&mock, false).change_id_checked(1, 5), Ok(IdChangeOutcome::Done));
+        assert_eq!(mock.calls(), vec![BackendCall::ChangeId { id: 1, new_id: 5 }]);
+        assert!(mock.servo(5).is_some());
+
+        // Servo absent : l'écriture reste sans réponse, rien n'est vérifié
+        assert!(driver(&mock, false).change_id_checked(1, 6).is_err());
+        assert!(driver(&mock, false).change_id_checked(5, st3215::BROADCAST_ID).is_err());
+    }
+
+    #[test]
+    fn dry_run_id_change_writes_nothing() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default());
+        assert_eq!(driver(&mock, true).change_id_checked(1, 5), Ok(IdChangeOutcome::Done));
+        assert!(mock.calls().is_empty());
+        assert!(mock.servo(1).is_some());
+    }
+
+    // Étape d'échange telle que la CLI et l'interface l'exécutent
+    fn verified_step(driver: &Driver, from: u8, to: u8) -> Result<(), String> {
+        driver.change_id_checked(from, to).and_then(|verdict| match verdict {
+            IdChangeOutcome::Done => Ok(()),
+            _ => Err(verdict.describe(from, to)),
+        })
+    }
+
+    #[test]
+    fn swap_exchanges_two_servos_on_the_bus() {
+        let mock = MockBackend::new()
+            .with_servo(1, MockServo { position: 1000, ..MockServo::default() })
+            .with_servo(2, MockServo { position: 3000, ..MockServo::default() });
+        let driver = driver(&mock, false);
+        let plan = crate::idchange::swap_plan(1, 2, &driver.list_servos()).unwrap();
+
+        assert_eq!(crate::idchange::run_swap(&plan, |from, to| verified_step(&driver, from, to)), Ok(()));
+        assert_eq!((mock.servo(1).unwrap().position, mock.servo(2).unwrap().position), (3000, 1000));
+        assert_eq!(driver.list_servos(), vec![1, 2]);
+    }
+
+    #[test]
+    fn failed_swap_puts_the_first_servo_back() {
+        let mock = MockBackend::new().with_servo(1, MockServo { position: 1000, ..MockServo::default() }).with_servo(2, MockServo::default());
+        let driver = driver(&mock, false);
+        let plan = crate::idchange::swap_plan(1, 2, &driver.list_servos()).unwrap();
+
+        // Servo 2 débranché entre le scan et l'échange
+        mock.unplug(2);
+        assert!(crate::idchange::run_swap(&plan, |from, to| verified_step(&driver, from, to)).is_err());
+        assert_eq!(driver.list_servos(), vec![1]);
+        assert_eq!(mock.servo(1).unwrap().position, 1000);
+    }
+
+
+    #[test]
+    fn back_to_position_mode_stops_the_wheel_first() {
+        let mock = MockBackend::new().with_servo(4, MockServo { position: 1500, ..MockServo::default() });
+        let driver = driver(&mock, false);
+        driver.rotate(4, -800).unwrap();
+        assert_eq!(mock.servo(4).unwrap().mode, 1);
+
+        mock.take_calls();
+        driver.set_mode(4, ServoMode::Position).unwrap();
+        assert_eq!(
+            mock.calls(),
+            vec![
+                BackendCall::Rotate { id: 4, speed: 0 },
+                BackendCall::WritePosition { id: 4, position: 1500 },
+                BackendCall::SetMode { id: 4, mode: 0 },
+            ]
+        );
+        // Vers le mode roue : rien à arrêter
+        mock.take_calls();
+        driver.set_mode(4, ServoMode::Wheel).unwrap();
+        assert_eq!(mock.calls(), vec![BackendCall::SetMode { id: 4, mode: 1 }]);
+    }
+
+    #[test]
+    fn dry_run_wheel_commands_write_nothing() {
+        let mock = MockBackend::new().with_servo(4, MockServo::default());
+        let driver = driver(&mock, true);
+        driver.rotate(4, 500).unwrap();
+        driver.set_mode(4, ServoMode::Position).unwrap();
+        assert!(mock.calls().is_empty());
+    }
+}
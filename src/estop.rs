@@ -0,0 +1,43 @@
+//! Arrêt d'urgence : couple coupé sur tous les servos connus et consignes en file abandonnées.
+//! Un servo arrêté refuse toute consigne tant que son couple n'a pas été réactivé explicitement.
+
+use std::collections::{BTreeSet, VecDeque};
+
+/// Retire de la file l'arrêt d'urgence et toutes les consignes de mouvement qui y attendent ;
+/// vrai si un arrêt était demandé. Les autres commandes restent dans l'ordre d'arrivée.
+pub fn take_emergency<T>(queue: &mut VecDeque<T>, is_stop: impl Fn(&T) -> bool, is_motion: impl Fn(&T) -> bool) -> bool {
+    if !queue.iter().any(&is_stop) {
+        return false;
+    }
+    queue.retain(|command| !is_stop(command) && !is_motion(command));
+    true
+}
+
+/// Servos arrêtés par l'arrêt d'urgence, en attente d'une réactivation du couple
+#[derive(Clone, Debug, Default)]
+pub struct EmergencyStop {
+    stopped: BTreeSet<u8>,
+}
+
+impl EmergencyStop {
+    pub fn trigger(&mut self, ids: impl IntoIterator<Item = u8>) {
+        self.stopped.extend(ids);
+    }
+
+    /// Réactivation explicite du couple d'un servo ; vrai s'il était arrêté
+    pub fn release(&mut self, id: u8) -> bool {
+        self.stopped.remove(&id)
+    }
+
+    pub fn is_stopped(&self, id: u8) -> bool {
+        self.stopped.contains(&id)
+    }
+
+    pub fn is_active(&self) -> bool {
+        !self.stopped.is_empty()
+    }
+
+    pub fn stopped(&self) -> impl Iterator<Item = u8> + '_ {
+        self.stopped.iter().copied()
+    }
+}
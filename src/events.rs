@@ -0,0 +1,307 @@
+//! Journal unifié des événements d'une session (connexions, commandes, alertes, erreurs,
+//! annotations). Le journal est borné, et les échecs répétés à l'identique sont regroupés
+//! sur une seule entrée avec un compteur.
+
+use crate::theme::Status;
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// Entrées gardées en mémoire ; les plus anciennes sont oubliées au-delà
+pub const MAX_EVENTS: usize = 5000;
+/// Entrées récentes parmi lesquelles on cherche un échec identique à regrouper
+const COALESCE_WINDOW: usize = 16;
+
+/// Catégorie d'un événement, utilisée pour le filtrage de la timeline
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EventKind {
+    Connection,
+    Command,
+    Alert,
+    Error,
+    EmergencyStop,
+    Annotation,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 6] = [
+        EventKind::Connection,
+        EventKind::Command,
+        EventKind::Alert,
+        EventKind::Error,
+        EventKind::EmergencyStop,
+        EventKind::Annotation,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            EventKind::Connection => "Connection",
+            EventKind::Command => "Command",
+            EventKind::Alert => "Alert",
+            EventKind::Error => "Error",
+            EventKind::EmergencyStop => "E-Stop",
+            EventKind::Annotation => "Annotation",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    Connected { port: String },
+    Disconnected { port: String },
+    PortChanged { from: String, to: String },
+    /// Servo apparu sur le bus pendant la session (rescan périodique)
+    ServoFound { servo: u8 },
+    Command {
+        servo: Option<u8>,
+        command: String,
+        ok: bool,
+        detail: Option<String>,
+    },
+    AlertRaised { servo: Option<u8>, message: String },
+    AlertCleared { servo: Option<u8>, message: String },
+    /// Lecture ou écriture sur le bus qui échoue en dehors d'une commande de l'utilisateur
+    Error { servo: Option<u8>, message: String },
+    EmergencyStop,
+    Annotation { text: String },
+}
+
+impl Event {
+    /// Raccourci pour une commande avec son résultat
+    pub fn command(servo: Option<u8>, command: impl Into<String>, outcome: Result<(), String>) -> Self {
+        let (ok, detail) = match outcome {
+            Ok(()) => (true, None),
+            Err(e) => (false, Some(e)),
+        };
+        Event::Command { servo, command: command.into(), ok, detail }
+    }
+
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::Connected { .. }
+            | Event::Disconnected { .. }
+            | Event::PortChanged { .. }
+            | Event::ServoFound { .. } => EventKind::Connection,
+            Event::Command { .. } => EventKind::Command,
+            Event::AlertRaised { .. } | Event::AlertCleared { .. } => EventKind::Alert,
+            Event::Error { .. } => EventKind::Error,
+            Event::EmergencyStop => EventKind::EmergencyStop,
+            Event::Annotation { .. } => EventKind::Annotation,
+        }
+    }
+
+    pub fn servo(&self) -> Option<u8> {
+        match self {
+            Event::Command { servo, .. }
+            | Event::AlertRaised { servo, .. }
+            | Event::AlertCleared { servo, .. }
+            | Event::Error { servo, .. } => *servo,
+            Event::ServoFound { servo } => Some(*servo),
+            _ => None,
+        }
+    }
+
+    /// Texte court affiché dans la timeline
+    pub fn summary(&self) -> String {
+        match self {
+            Event::Connected { port } => format!("Connected to {}", port),
+            Event::Disconnected { port } => format!("Disconnected from {}", port),
+            Event::PortChanged { from, to } => format!("Port moved {} → {}", from, to),
+            Event::ServoFound { .. } => "New servo found".to_string(),
+            Event::Command { command, ok: true, .. } => format!("{} ✓", command),
+            Event::Command { command, detail, .. } => {
+                format!("{} ✗ {}", command, detail.as_deref().unwrap_or(""))
+            }
+            Event::AlertRaised { message, .. } => format!("Alert: {}", message),
+            Event::AlertCleared { message, .. } => format!("Cleared: {}", message),
+            Event::Error { message, .. } => format!("Error: {}", message),
+            Event::EmergencyStop => "EMERGENCY STOP".to_string(),
+            Event::Annotation { text } => format!("Note: {}", text),
+        }
+    }
+
+    /// Gravité affichée dans le panneau des événements
+    pub fn severity(&self) -> Status {
+        match self {
+            Event::Command { ok: false, .. } | Event::Error { .. } | Event::EmergencyStop => Status::Danger,
+            Event::AlertRaised { .. } | Event::Disconnected { .. } => Status::Warning,
+            _ => Status::Ok,
+        }
+    }
+
+    // Seuls les échecs se répètent assez pour noyer le journal
+    fn coalesces(&self) -> bool {
+        matches!(self, Event::Command { ok: false, .. } | Event::Error { .. })
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TimedEvent {
+    /// Secondes écoulées depuis le début de session (même base que les historiques des graphiques)
+    pub elapsed: f64,
+    /// Horodatage absolu en millisecondes UNIX
+    pub unix_ms: u64,
+    #[serde(flatten)]
+    pub event: Event,
+    /// Occurrences regroupées sur cette entrée (1 pour un événement isolé)
+    pub count: u32,
+    /// Instant de la dernière occurrence, même base que `elapsed`
+    pub last_elapsed: f64,
+}
+
+pub struct EventStore {
+    start: Instant,
+    events: Vec<TimedEvent>,
+    revision: u64,
+}
+
+impl EventStore {
+    pub fn new(start: Instant) -> Self {
+        Self { start, events: Vec::new(), revision: 0 }
+    }
+
+    /// Ajoute l'événement ; un échec identique à une entrée récente incrémente son compteur
+    pub fn push(&mut self, event: Event) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        self.revision += 1;
+        if event.coalesces() {
+            if let Some(previous) = self.events.iter_mut().rev().take(COALESCE_WINDOW).find(|e| e.event == event) {
+                previous.count += 1;
+                previous.last_elapsed = elapsed;
+                return;
+            }
+        }
+        let unix_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        if self.events.len() >= MAX_EVENTS {
+            // Par paquets, pour ne pas décaler tout le journal à chaque ajout
+            self.events.drain(..MAX_EVENTS / 10);
+        }
+        self.events.push(TimedEvent { elapsed, unix_ms, event, count: 1, last_elapsed: elapsed });
+    }
+
+    pub fn events(&self) -> &[TimedEvent] {
+        &self.events
+    }
+
+    /// Compteur d'ajouts, regroupés compris : change à chaque `push`
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    /// Événements dont le type est accepté par `kinds` et, si précisé, concernant `servo`
+    pub fn filtered<'a>(
+        &'a self,
+        kinds: &'a [EventKind],
+        servo: Option<u8>,
+    ) -> impl Iterator<Item = &'a TimedEvent> + 'a {
+        self.events.iter().filter(move |e| {
+            kinds.contains(&e.event.kind()) && (servo.is_none() || e.event.servo() == servo)
+        })
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(&self.events)
+    }
+
+    pub fn export_json(&self, path: &Path) -> io::Result<()> {
+        let json = self.to_json().map_err(io::Error::other)?;
+        fs::write(path, json)
+    }
+}
+
+#[cfg(feature = "gui")]
+mod gui {
+    use super::EventStore;
+    use crate::theme::{Palette, Status};
+
+    /// Panneau repliable en bas de fenêtre ; l'en-tête compte les erreurs de la session.
+    /// `label` donne le libellé affiché d'un servo (nom et ID)
+    pub fn bottom_panel(ctx: &egui::Context, store: &EventStore, palette: &Palette, label: &dyn Fn(u8) -> String) {
+        let errors: u32 = store.events().iter().filter(|e| e.event.severity() == Status::Danger).map(|e| e.count).sum();
+        egui::TopBottomPanel::bottom("events_panel").resizable(true).show(ctx, |ui| {
+            let title = if errors > 0 { format!("Events ({} errors)", errors) } else { "Events".to_string() };
+            egui::CollapsingHeader::new(title).id_salt("events_log").show(ui, |ui| {
+                log_view(ui, store, palette, label);
+            });
+        });
+    }
+
+    /// Liste défilante des événements, colorés selon leur gravité, les plus récents en bas
+    pub fn log_view(ui: &mut egui::Ui, store: &EventStore, palette: &Palette, label: &dyn Fn(u8) -> String) {
+        egui::ScrollArea::vertical().stick_to_bottom(true).auto_shrink([false, true]).max_height(200.0).show(ui, |ui| {
+            for timed in store.events() {
+                let servo = timed.event.servo().map(|id| format!("{} · ", label(id))).unwrap_or_default();
+                let repeats = if timed.count > 1 {
+                    format!("  ×{} (last {:.1}s)", timed.count, timed.last_elapsed)
+                } else {
+                    String::new()
+                };
+                palette.status_label(
+                    ui,
+                    timed.event.severity(),
+                    format!("{:>7.1}s  {}{}{}", timed.elapsed, servo, timed.event.summary(), repeats),
+                );
+            }
+        });
+    }
+}
+
+#[cfg(feature = "gui")]
+pub use gui::{bottom_panel, log_view};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failure(servo: u8) -> Event {
+        Event::command(Some(servo), "Move → 2048", Err("no response".to_string()))
+    }
+
+    #[test]
+    fn repeated_failures_share_one_entry() {
+        let mut store = EventStore::new(Instant::now());
+        store.push(failure(1));
+        store.push(Event::command(Some(1), "Move → 2048", Ok(())));
+        store.push(failure(1));
+        store.push(failure(2));
+        store.push(Event::command(Some(1), "Move → 2048", Ok(())));
+
+        let counts: Vec<u32> = store.events().iter().map(|e| e.count).collect();
+        assert_eq!(counts, vec![2, 1, 1, 1]);
+        assert_eq!(store.revision(), 5);
+        assert_eq!(store.events()[0].event.severity(), Status::Danger);
+        assert_eq!(store.events()[1].event.severity(), Status::Ok);
+    }
+
+    #[test]
+    fn failures_beyond_the_window_get_a_new_entry() {
+        let mut store = EventStore::new(Instant::now());
+        store.push(failure(1));
+        for i in 0..COALESCE_WINDOW {
+            store.push(Event::Annotation { text: i.to_string() });
+        }
+        store.push(failure(1));
+        assert_eq!(store.events().len(), COALESCE_WINDOW + 2);
+    }
+
+    #[test]
+    fn store_is_capped_and_filtered() {
+        let mut store = EventStore::new(Instant::now());
+        for i in 0..MAX_EVENTS + 1 {
+            store.push(Event::Annotation { text: i.to_string() });
+        }
+        assert_eq!(store.events().len(), MAX_EVENTS - MAX_EVENTS / 10 + 1);
+        assert_eq!(store.events().last().unwrap().event, Event::Annotation { text: MAX_EVENTS.to_string() });
+
+        store.push(Event::Error { servo: Some(4), message: "position read failed".to_string() });
+        store.push(failure(5));
+        assert_eq!(store.filtered(&[EventKind::Error, EventKind::Command], Some(4)).count(), 1);
+        assert_eq!(store.filtered(&[EventKind::Command], None).count(), 1);
+    }
+}
@@ -0,0 +1,185 @@
+//! Import des fichiers de paramètres exportés par le logiciel FD de Feetech.
+//!
+//! Formats acceptés, une entrée par ligne (sections `[...]` et commentaires `#`/`;` ignorés) :
+//!
+//! ```text
+//! Min Angle Limit=0           ; Nom=Valeur
+//! P Coefficient,32            ; Nom,Valeur
+//! 31,Position Offset,-12      ; Adresse,Nom,Valeur
+//! ```
+//!
+//! Les registres inconnus sont listés puis ignorés, sans faire échouer l'import.
+
+use crate::registers::{find_register, Register, RegisterPort};
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FdEntry {
+    pub register: &'static Register,
+    pub value: i32,
+    pub line: usize,
+}
+
+/// Ligne ignorée : registre inconnu ou valeur invalide
+#[derive(Clone, Debug, PartialEq)]
+pub struct Skipped {
+    pub line: usize,
+    pub name: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FdImport {
+    pub entries: Vec<FdEntry>,
+    pub skipped: Vec<Skipped>,
+}
+
+pub fn load(path: &Path) -> Result<FdImport, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    Ok(parse(&text))
+}
+
+pub fn parse(text: &str) -> FdImport {
+    let mut import = FdImport::default();
+    for (index, raw_line) in text.lines().enumerate() {
+        let line = index + 1;
+        let content = raw_line.split(['#', ';']).next().unwrap_or("").trim();
+        if content.is_empty() || content.starts_with('[') {
+            continue;
+        }
+
+        let fields: Vec<&str> = content.split(['=', ',', '\t']).map(str::trim).collect();
+        let (address, name, value) = match fields.as_slice() {
+            [name, value] => (None, *name, *value),
+            [first, second, third, ..] => match first.parse::<u8>() {
+                Ok(address) => (Some(address), *second, *third),
+                // Nom,Valeur,Unité
+                Err(_) => (None, *first, *second),
+            },
+            _ => {
+                import.skipped.push(Skipped { line, name: content.to_string(), reason: "not a name/value pair".into() });
+                continue;
+            }
+        };
+
+        let Some(register) = find_register(name) else {
+            import.skipped.push(Skipped { line, name: name.to_string(), reason: "unknown register".into() });
+            continue;
+        };
+        if address.is_some_and(|a| a != register.address) {
+            import.skipped.push(Skipped {
+                line,
+                name: name.to_string(),
+                reason: format!("address {} does not match {} ({})", address.unwrap_or(0), register.name, register.address),
+            });
+            continue;
+        }
+        match value.parse::<i32>().map_err(|_| format!("invalid value '{}'", value)).and_then(|v| register.encode(v).map(|_| v)) {
+            Ok(value) => {
+                // Une entrée répétée remplace la précédente
+                import.entries.retain(|e| e.register != register);
+                import.entries.push(FdEntry { register, value, line });
+            }
+            Err(reason) => import.skipped.push(Skipped { line, name: name.to_string(), reason }),
+        }
+    }
+    import.entries.sort_by_key(|e| e.register.address);
+    import
+}
+
+/// Ligne de l'aperçu : valeur du fichier face à la valeur lue sur le servo
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffRow {
+    pub register: &'static Register,
+    pub file: i32,
+    pub live: Option<i32>,
+}
+
+impl DiffRow {
+    pub fn changed(&self) -> bool {
+        self.live != Some(self.file)
+    }
+}
+
+/// Aperçu : chaque entrée du fichier face à la valeur lue sur le servo
+pub fn diff(bus: &mut RegisterPort, id: u8, import: &FdImport) -> Vec<DiffRow> {
+    import
+        .entries
+        .iter()
+        .map(|e| DiffRow { register: e.register, file: e.value, live: bus.read(id, e.register).ok() })
+        .collect()
+}
+
+/// Écrit les entrées choisies puis retourne celles dont la relecture ne correspond pas
+pub fn apply(bus: &mut RegisterPort, id: u8, entries: &[&FdEntry]) -> Result<Vec<DiffRow>, String> {
+    let values: Vec<(&'static Register, i32)> = entries.iter().map(|e| (e.register, e.value)).collect();
+    Ok(bus
+        .write_verified(id, &values)?
+        .into_iter()
+        .map(|m| DiffRow { register: m.register, file: m.expected, live: m.read })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = include_str!("../samples/fd-export-sts3215.txt");
+
+    fn value_of(import: &FdImport, name: &str) -> Option<i32> {
+        import.entries.iter().find(|e| e.register.name == name).map(|e| e.value)
+    }
+
+    #[test]
+    fn sample_export_is_parsed() {
+        let import = parse(SAMPLE);
+        let names: Vec<&str> = import.entries.iter().map(|e| e.register.name).collect();
+        assert_eq!(names.len(), 21);
+        assert_eq!(value_of(&import, "ID"), Some(1));
+        assert_eq!(value_of(&import, "Max Angle Limit"), Some(4095));
+        assert_eq!(value_of(&import, "Max Torque"), Some(1000));
+        assert_eq!(value_of(&import, "P Coefficient"), Some(32));
+        assert_eq!(value_of(&import, "Overload Torque"), Some(80));
+        // Registre signé : l'offset négatif est gardé tel quel
+        let offset = import.entries.iter().find(|e| e.register.name == "Position Offset").unwrap();
+        assert_eq!((offset.value, offset.line), (-12, 19));
+        // Triées par adresse
+        assert!(import.entries.windows(2).all(|w| w[0].register.address < w[1].register.address));
+    }
+
+    #[test]
+    fn unknown_registers_are_skipped() {
+        let import = parse(SAMPLE);
+        let skipped: Vec<(usize, &str, &str)> = import.skipped.iter().map(|s| (s.line, s.name.as_str(), s.reason.as_str())).collect();
+        assert_eq!(skipped, [(24, "Goal Acceleration", "unknown register"), (25, "Vendor Reserved", "unknown register")]);
+        assert!(!import.entries.iter().any(|e| e.register.name.contains("Vendor")));
+    }
+
+    #[test]
+    fn line_forms_are_accepted() {
+        let import = parse("Min Angle Limit=100\nP Coefficient,40\n31,Position Offset,-5\nMax Torque,900,‰\n");
+        assert_eq!(value_of(&import, "Min Angle Limit"), Some(100));
+        assert_eq!(value_of(&import, "P Coefficient"), Some(40));
+        assert_eq!(value_of(&import, "Position Offset"), Some(-5));
+        assert_eq!(value_of(&import, "Max Torque"), Some(900));
+        assert!(import.skipped.is_empty(), "{:?}", import.skipped);
+    }
+
+    #[test]
+    fn address_mismatch_is_skipped() {
+        let import = parse("30,Position Offset,-5\n");
+        assert!(import.entries.is_empty());
+        assert_eq!(import.skipped.len(), 1);
+        assert_eq!(import.skipped[0].reason, "address 30 does not match Position Offset (31)");
+    }
+
+    #[test]
+    fn invalid_values_and_repeats() {
+        let import = parse("Max Torque=abc\nP Coefficient=10\nP Coefficient=12\nnonsense\n");
+        let skipped: Vec<(usize, &str)> = import.skipped.iter().map(|s| (s.line, s.reason.as_str())).collect();
+        assert_eq!(skipped, [(1, "invalid value 'abc'"), (4, "not a name/value pair")]);
+        // La dernière valeur d'un registre répété l'emporte
+        assert_eq!(import.entries.len(), 1);
+        assert_eq!((import.entries[0].value, import.entries[0].line), (12, 3));
+    }
+}
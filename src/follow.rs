@@ -0,0 +1,84 @@
+//! Mode suivi : un servo recopie en continu la position d'un autre (marionnette), à l'échelle et
+//! au décalage près. La consigne est calculée par le thread du bus à chaque cycle de lecture.
+//!
+//! ```toml
+//! [servos.2.follow]
+//! enabled = true
+//! source = 1
+//! scale = -1.0     # négatif : sens opposé, en miroir autour de 2048
+//! offset = 100
+//! deadband = 8
+//! ```
+
+use crate::units::{CENTER_TICKS, MAX_TICKS};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FollowSettings {
+    pub enabled: bool,
+    /// Servo recopié
+    pub source: u8,
+    /// Facteur appliqué à l'écart de la source au centre (2048)
+    pub scale: f32,
+    /// Décalage (ticks) ajouté à la consigne
+    pub offset: i32,
+    /// Écart minimal (ticks) avec la dernière consigne envoyée, pour ne pas brouter
+    pub deadband: u16,
+}
+
+impl Default for FollowSettings {
+    fn default() -> Self {
+        Self { enabled: false, source: 1, scale: 1.0, offset: 0, deadband: 8 }
+    }
+}
+
+impl FollowSettings {
+    /// Consigne du suiveur pour une position de la source, dans la plage du servo
+    pub fn target(&self, source: u16) -> u16 {
+        let center = f32::from(CENTER_TICKS);
+        let target = center + (f32::from(source) - center) * self.scale + self.offset as f32;
+        target.round().clamp(0.0, f32::from(MAX_TICKS)) as u16
+    }
+
+    /// Consigne à envoyer : la première, puis seulement au-delà de la zone morte
+    pub fn should_send(&self, last: Option<u16>, target: u16) -> bool {
+        last.is_none_or(|last| target.abs_diff(last) >= self.deadband.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn target_scales_around_the_center() {
+        let follow = FollowSettings::default();
+        assert_eq!(follow.target(1500), 1500);
+        let mirrored = FollowSettings { scale: -1.0, offset: 100, ..follow };
+        assert_eq!(mirrored.target(1500), 2696);
+        let half = FollowSettings { scale: 0.5, ..follow };
+        assert_eq!(half.target(3048), 2548);
+        // Hors de la plage du servo : bornée
+        let pushed = FollowSettings { offset: 500, ..follow };
+        assert_eq!(pushed.target(3900), MAX_TICKS);
+        assert_eq!(FollowSettings { offset: -500, ..follow }.target(100), 0);
+    }
+
+    #[test]
+    fn deadband_filters_small_changes() {
+        let follow = FollowSettings { deadband: 8, ..FollowSettings::default() };
+        assert!(follow.should_send(None, 2048));
+        assert!(!follow.should_send(Some(2048), 2055));
+        assert!(follow.should_send(Some(2048), 2040));
+        // Zone morte nulle : une consigne identique n'est pas renvoyée
+        let none = FollowSettings { deadband: 0, ..follow };
+        assert!(!none.should_send(Some(2048), 2048) && none.should_send(Some(2048), 2049));
+    }
+
+    #[test]
+    fn follow_settings_load_with_defaults() {
+        let follow: FollowSettings = toml::from_str("enabled = true\nsource = 4\nscale = -1.0\n").unwrap();
+        assert_eq!(follow, FollowSettings { enabled: true, source: 4, scale: -1.0, ..FollowSettings::default() });
+    }
+}
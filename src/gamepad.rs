@@ -0,0 +1,997 @@
+//! Manette de jeu dans les interfaces : un axe, ou une paire de boutons, pilote un servo ou un
+//! groupe, en position absolue, en jog (déplacement incrémental) ou en vitesse (mode roue).
+//!
+//! La manette est lue dans la boucle du bus (`Gamepad`, via gilrs) ; `PadController` en tire des
+//! consignes à cadence plafonnée, envoyées comme celles des sliders : butées logicielles, arrêt
+//! d'urgence et validation s'appliquent. Par défaut, rien ne bouge sans l'homme mort (gâchette
+//! haute gauche) tenu.
+//!
+//! ```toml
+//! [gamepad]
+//! deadman = true
+//! deadman_button = "left_trigger"
+//! rate_hz = 20.0
+//! [[gamepad.bindings]]
+//! target = { servo = 3 }              # ou { group = "left leg" }
+//! input = { axis = "left_stick_x" }   # ou { buttons = ["dpad_down", "dpad_up"] }
+//! mode = "jog"                        # position, jog ou velocity
+//! sensitivity = 1.0
+//! deadzone = 0.15
+//! [[gamepad.calibrations]]                 # relevé dans le panneau, par manette et par axe
+//! controller = "Xbox Wireless Controller [030000005e040000...]"
+//! axis = "left_stick_x"
+//! min = -0.97
+//! center = 0.02
+//! max = 1.0
+//! deadzone = 0.08                      # remplace celle des liaisons de cet axe
+//! curve = "expo"                       # linear ou expo
+//! expo = 0.5
+//! ```
+
+use crate::limits::SoftLimits;
+use crate::mode::MAX_WHEEL_SPEED;
+use crate::units::{CENTER_TICKS, MAX_TICKS};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// Déplacement en jog (ticks/s), axe à fond et sensibilité 1
+pub const JOG_TICKS_PER_S: f32 = 1000.0;
+
+/// Écart maximal pris en compte entre deux échantillons : une boucle du bus ralentie (scan, accès
+/// registre) ne se traduit pas par un saut en jog
+const MAX_SAMPLE_GAP: Duration = Duration::from_millis(200);
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PadAxis {
+    #[default]
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftZ,
+    RightZ,
+}
+
+impl PadAxis {
+    pub const ALL: [PadAxis; 6] = [
+        PadAxis::LeftStickX,
+        PadAxis::LeftStickY,
+        PadAxis::RightStickX,
+        PadAxis::RightStickY,
+        PadAxis::LeftZ,
+        PadAxis::RightZ,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PadAxis::LeftStickX => "Left stick X",
+            PadAxis::LeftStickY => "Left stick Y",
+            PadAxis::RightStickX => "Right stick X",
+            PadAxis::RightStickY => "Right stick Y",
+            PadAxis::LeftZ => "Left trigger (analog)",
+            PadAxis::RightZ => "Right trigger (analog)",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftTrigger,
+    LeftTrigger2,
+    RightTrigger,
+    RightTrigger2,
+    Select,
+    Start,
+    #[serde(rename = "dpad_up")]
+    DPadUp,
+    #[serde(rename = "dpad_down")]
+    DPadDown,
+    #[serde(rename = "dpad_left")]
+    DPadLeft,
+    #[serde(rename = "dpad_right")]
+    DPadRight,
+}
+
+impl PadButton {
+    pub const ALL: [PadButton; 14] = [
+        PadButton::South,
+        PadButton::East,
+        PadButton::North,
+        PadButton::West,
+        PadButton::LeftTrigger,
+        PadButton::LeftTrigger2,
+        PadButton::RightTrigger,
+        PadButton::RightTrigger2,
+        PadButton::Select,
+        PadButton::Start,
+        PadButton::DPadUp,
+        PadButton::DPadDown,
+        PadButton::DPadLeft,
+        PadButton::DPadRight,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PadButton::South => "South (A)",
+            PadButton::East => "East (B)",
+            PadButton::North => "North (Y)",
+            PadButton::West => "West (X)",
+            PadButton::LeftTrigger => "LB",
+            PadButton::LeftTrigger2 => "LT",
+            PadButton::RightTrigger => "RB",
+            PadButton::RightTrigger2 => "RT",
+            PadButton::Select => "Select",
+            PadButton::Start => "Start",
+            PadButton::DPadUp => "D-pad up",
+            PadButton::DPadDown => "D-pad down",
+            PadButton::DPadLeft => "D-pad left",
+            PadButton::DPadRight => "D-pad right",
+        }
+    }
+}
+
+/// Entrée liée : un axe (-1 à 1), ou deux boutons (moins, plus)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PadInput {
+    Axis(PadAxis),
+    Buttons(PadButton, PadButton),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BindingTarget {
+    Servo(u8),
+    Group(String),
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PadMode {
+    /// Axe au repos = 2048, à fond = 2048 ± 2048 × sensibilité
+    Position,
+    /// L'axe déplace la consigne, d'autant plus vite qu'il est poussé
+    #[default]
+    Jog,
+    /// Vitesse signée d'un servo en mode roue
+    Velocity,
+}
+
+impl PadMode {
+    pub const ALL: [PadMode; 3] = [PadMode::Position, PadMode::Jog, PadMode::Velocity];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PadMode::Position => "Position",
+            PadMode::Jog => "Jog",
+            PadMode::Velocity => "Velocity (wheel)",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PadBinding {
+    pub target: BindingTarget,
+    pub input: PadInput,
+    pub mode: PadMode,
+    pub sensitivity: f32,
+    /// Part de la course de l'axe ignorée autour du repos (0 à 1)
+    pub deadzone: f32,
+}
+
+impl Default for PadBinding {
+    fn default() -> Self {
+        Self {
+            target: BindingTarget::Servo(1),
+            input: PadInput::Axis(PadAxis::LeftStickX),
+            mode: PadMode::Jog,
+            sensitivity: 1.0,
+            deadzone: 0.15,
+        }
+    }
+}
+
+/// Réponse d'un axe étalonné, une fois la zone morte retirée
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseCurve {
+    #[default]
+    Linear,
+    /// Plus douce autour du repos, pour les petits déplacements ; pleine course inchangée
+    Expo,
+}
+
+impl ResponseCurve {
+    pub const ALL: [ResponseCurve; 2] = [ResponseCurve::Linear, ResponseCurve::Expo];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ResponseCurve::Linear => "Linear",
+            ResponseCurve::Expo => "Expo",
+        }
+    }
+}
+
+/// Étalonnage d'un axe d'une manette : course relevée, zone morte et courbe. Il remplace la zone
+/// morte des liaisons de cet axe tant que cette manette est branchée.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AxisCalibration {
+    /// Nom et UUID de la manette : chaque manette garde ses réglages
+    pub controller: String,
+    pub axis: PadAxis,
+    /// Valeurs brutes relevées : butées et repos
+    pub min: f32,
+    pub center: f32,
+    pub max: f32,
+    pub deadzone: f32,
+    pub curve: ResponseCurve,
+    /// Part cubique de la courbe expo (0 = linéaire, 1 = cubique)
+    pub expo: f32,
+}
+
+impl Default for AxisCalibration {
+    fn default() -> Self {
+        Self {
+            controller: String::new(),
+            axis: PadAxis::LeftStickX,
+            min: -1.0,
+            center: 0.0,
+            max: 1.0,
+            deadzone: 0.15,
+            curve: ResponseCurve::Linear,
+            expo: 0.5,
+        }
+    }
+}
+
+impl AxisCalibration {
+    /// Valeur brute ramenée entre -1 et 1 : chaque côté du repos sur sa propre course, puis zone
+    /// morte et courbe
+    pub fn apply(&self, raw: f32) -> f32 {
+        let span = if raw >= self.center { self.max - self.center } else { self.center - self.min };
+        if span <= f32::EPSILON {
+            return 0.0;
+        }
+        let value = apply_deadzone(((raw - self.center) / span).clamp(-1.0, 1.0), self.deadzone);
+        match self.curve {
+            ResponseCurve::Linear => value,
+            ResponseCurve::Expo => {
+                let expo = self.expo.clamp(0.0, 1.0);
+                value * (1.0 - expo) + value.powi(3) * expo
+            }
+        }
+    }
+}
+
+/// Écart minimal entre les butées relevées pour qu'un relevé soit retenu
+pub const MIN_CAPTURE_SPAN: f32 = 0.3;
+
+/// Relevé de la course d'un axe : commencé stick au repos (le centre), puis le stick est promené
+/// jusqu'en butée dans les deux sens
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AxisCapture {
+    pub axis: PadAxis,
+    pub min: f32,
+    pub center: f32,
+    pub max: f32,
+}
+
+impl AxisCapture {
+    pub fn new(axis: PadAxis, rest: f32) -> Self {
+        Self { axis, min: rest, center: rest, max: rest }
+    }
+
+    pub fn observe(&mut self, value: f32) {
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Course assez large pour être retenue ; une gâchette n'a qu'un côté
+    pub fn is_complete(&self) -> bool {
+        self.max - self.min >= MIN_CAPTURE_SPAN
+    }
+
+    pub fn apply_to(&self, calibration: &mut AxisCalibration) {
+        calibration.axis = self.axis;
+        (calibration.min, calibration.center, calibration.max) = (self.min, self.center, self.max);
+    }
+}
+
+/// Vitesse (pas/s) des consignes d'essai d'un étalonnage
+pub const CALIBRATION_TEST_SPEED: u16 = 300;
+
+/// Consigne absolue d'une valeur d'axe : repos = 2048, à fond = 0 ou 4095
+pub fn position_for(value: f32) -> u16 {
+    let center = f32::from(CENTER_TICKS);
+    (center + value * center).clamp(0.0, f32::from(MAX_TICKS)).round() as u16
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamepadSettings {
+    /// Aucun mouvement sans `deadman_button` tenu
+    pub deadman: bool,
+    pub deadman_button: PadButton,
+    /// Consignes envoyées au plus `rate_hz` fois par seconde
+    pub rate_hz: f32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub bindings: Vec<PadBinding>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calibrations: Vec<AxisCalibration>,
+}
+
+impl Default for GamepadSettings {
+    fn default() -> Self {
+        Self { deadman: true, deadman_button: PadButton::LeftTrigger, rate_hz: 20.0, bindings: Vec::new(), calibrations: Vec::new() }
+    }
+}
+
+impl GamepadSettings {
+    pub fn calibration(&self, controller: &str, axis: PadAxis) -> Option<&AxisCalibration> {
+        self.calibrations.iter().find(|c| c.controller == controller && c.axis == axis)
+    }
+
+    /// Ajoute ou remplace l'étalonnage de cette manette et de cet axe
+    pub fn set_calibration(&mut self, calibration: AxisCalibration) {
+        self.calibrations.retain(|c| c.controller != calibration.controller || c.axis != calibration.axis);
+        self.calibrations.push(calibration);
+    }
+
+    /// Valeur d'une liaison entre -1 et 1 : étalonnage de l'axe pour cette manette s'il existe,
+    /// sinon zone morte de la liaison
+    pub fn input_value(&self, pad: &PadState, binding: &PadBinding) -> f32 {
+        match binding.input {
+            PadInput::Axis(axis) => match self.calibration(&pad.controller, axis) {
+                Some(calibration) => calibration.apply(pad.raw(axis)),
+                None => apply_deadzone(pad.value(&binding.input), binding.deadzone),
+            },
+            PadInput::Buttons(..) => pad.value(&binding.input),
+        }
+    }
+}
+
+/// État de la manette à un instant
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PadState {
+    /// Nom et UUID de la manette, clé de ses étalonnages
+    pub controller: String,
+    pub axes: HashMap<PadAxis, f32>,
+    pub pressed: HashSet<PadButton>,
+}
+
+impl PadState {
+    pub fn is_pressed(&self, button: PadButton) -> bool {
+        self.pressed.contains(&button)
+    }
+
+    pub fn raw(&self, axis: PadAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0).clamp(-1.0, 1.0)
+    }
+
+    /// Valeur de l'entrée entre -1 et 1 ; deux boutons tenus s'annulent
+    pub fn value(&self, input: &PadInput) -> f32 {
+        match *input {
+            PadInput::Axis(axis) => self.raw(axis),
+            PadInput::Buttons(minus, plus) => f32::from(self.is_pressed(plus) as u8) - f32::from(self.is_pressed(minus) as u8),
+        }
+    }
+}
+
+/// Zone morte retirée, le reste de la course ramené entre 0 et 1
+pub fn apply_deadzone(value: f32, deadzone: f32) -> f32 {
+    let deadzone = deadzone.clamp(0.0, 0.99);
+    if value.abs() <= deadzone {
+        return 0.0;
+    }
+    value.signum() * (value.abs() - deadzone) / (1.0 - deadzone)
+}
+
+/// Consigne tirée de la manette
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PadCommand {
+    Move { id: u8, position: u16 },
+    Wheel { id: u8, speed: i16 },
+}
+
+/// Passage de la manette aux consignes, dans la boucle du bus
+#[derive(Debug, Default)]
+pub struct PadController {
+    last_tick: Option<Instant>,
+    // Consigne visée par servo (jog et position absolue) : seules les variations sont envoyées
+    targets: HashMap<u8, f32>,
+    // Dernière vitesse de roue envoyée, par servo
+    wheels: HashMap<u8, i16>,
+}
+
+impl PadController {
+    /// Consignes d'un échantillon, au plus `rate_hz` fois par seconde ; sans manette (`None`), comme
+    /// homme mort relâché. `members` donne les servos d'une cible ; `servo` la consigne actuelle
+    /// (point de départ du jog) et les butées d'un servo qui peut bouger, `None` pour un servo
+    /// absent ou arrêté.
+    pub fn update(
+        &mut self,
+        settings: &GamepadSettings,
+        pad: Option<&PadState>,
+        now: Instant,
+        members: impl Fn(&BindingTarget) -> Vec<u8>,
+        servo: impl Fn(u8) -> Option<(u16, SoftLimits)>,
+    ) -> Vec<PadCommand> {
+        let period = Duration::from_secs_f64(1.0 / f64::from(settings.rate_hz.clamp(1.0, 200.0)));
+        if self.last_tick.is_some_and(|last| now.saturating_duration_since(last) < period) {
+            return Vec::new();
+        }
+        let dt = self.last_tick.map_or(period, |last| now.saturating_duration_since(last).min(MAX_SAMPLE_GAP));
+        self.last_tick = Some(now);
+
+        let mut commands = Vec::new();
+        let pad = match pad {
+            Some(pad) if !settings.deadman || pad.is_pressed(settings.deadman_button) => pad,
+            _ => {
+                self.release(&mut commands);
+                return commands;
+            }
+        };
+        let mut driven = HashSet::new();
+        for binding in &settings.bindings {
+            let value = settings.input_value(pad, binding) * binding.sensitivity;
+            for id in members(&binding.target) {
+                let Some((current, limits)) = servo(id) else {
+                    self.targets.remove(&id);
+                    self.wheels.remove(&id);
+                    continue;
+                };
+                let range = limits.range();
+                let (low, high) = (f32::from(*range.start()), f32::from(*range.end()));
+                match binding.mode {
+                    PadMode::Position => {
+                        let target = f32::from(position_for(value)).clamp(low, high);
+                        if self.targets.insert(id, target) != Some(target) {
+                            commands.push(PadCommand::Move { id, position: target as u16 });
+                        }
+                    }
+                    PadMode::Jog if value == 0.0 => {
+                        // Axe au repos : le prochain jog repart de la consigne, quelle qu'elle soit
+                        self.targets.remove(&id);
+                    }
+                    PadMode::Jog => {
+                        let base = self.targets.get(&id).copied().unwrap_or(f32::from(current));
+                        let target = (base + value * JOG_TICKS_PER_S * dt.as_secs_f32()).clamp(low, high);
+                        self.targets.insert(id, target);
+                        if target.round() != base.round() {
+                            commands.push(PadCommand::Move { id, position: target.round() as u16 });
+                        }
+                    }
+                    PadMode::Velocity => {
+                        let max = f32::from(MAX_WHEEL_SPEED);
+                        let speed = (value * max).clamp(-max, max).round() as i16;
+                        driven.insert(id);
+                        if self.wheels.insert(id, speed).unwrap_or(0) != speed {
+                            commands.push(PadCommand::Wheel { id, speed });
+                        }
+                    }
+                }
+            }
+        }
+        // Roue qui n'est plus liée (liaison retirée ou changée de mode) : arrêtée
+        let unbound: Vec<u8> = self.wheels.keys().copied().filter(|id| !driven.contains(id)).collect();
+        for id in unbound {
+            if self.wheels.remove(&id).is_some_and(|speed| speed != 0) {
+                commands.push(PadCommand::Wheel { id, speed: 0 });
+            }
+        }
+        commands
+    }
+
+    // Homme mort relâché : les roues s'arrêtent, le jog repartira de la consigne du servo
+    fn release(&mut self, commands: &mut Vec<PadCommand>) {
+        self.targets.clear();
+        for (id, speed) in self.wheels.drain() {
+            if speed != 0 {
+                commands.push(PadCommand::Wheel { id, speed: 0 });
+            }
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+mod pad {
+    use super::{PadAxis, PadButton, PadState};
+
+    impl PadAxis {
+        fn gilrs(self) -> gilrs::Axis {
+            match self {
+                PadAxis::LeftStickX => gilrs::Axis::LeftStickX,
+                PadAxis::LeftStickY => gilrs::Axis::LeftStickY,
+                PadAxis::RightStickX => gilrs::Axis::RightStickX,
+                PadAxis::RightStickY => gilrs::Axis::RightStickY,
+                PadAxis::LeftZ => gilrs::Axis::LeftZ,
+                PadAxis::RightZ => gilrs::Axis::RightZ,
+            }
+        }
+    }
+
+    impl PadButton {
+        fn gilrs(self) -> gilrs::Button {
+            match self {
+                PadButton::South => gilrs::Button::South,
+                PadButton::East => gilrs::Button::East,
+                PadButton::North => gilrs::Button::North,
+                PadButton::West => gilrs::Button::West,
+                PadButton::LeftTrigger => gilrs::Button::LeftTrigger,
+                PadButton::LeftTrigger2 => gilrs::Button::LeftTrigger2,
+                PadButton::RightTrigger => gilrs::Button::RightTrigger,
+                PadButton::RightTrigger2 => gilrs::Button::RightTrigger2,
+                PadButton::Select => gilrs::Button::Select,
+                PadButton::Start => gilrs::Button::Start,
+                PadButton::DPadUp => gilrs::Button::DPadUp,
+                PadButton::DPadDown => gilrs::Button::DPadDown,
+                PadButton::DPadLeft => gilrs::Button::DPadLeft,
+                PadButton::DPadRight => gilrs::Button::DPadRight,
+            }
+        }
+    }
+
+    /// Manettes vues par gilrs ; à créer dans le thread qui la lit
+    pub struct Gamepad {
+        gilrs: Option<gilrs::Gilrs>,
+    }
+
+    impl Gamepad {
+        pub fn new() -> Self {
+            let gilrs = match gilrs::Gilrs::new() {
+                Ok(gilrs) => Some(gilrs),
+                Err(e) => {
+                    log::warn!("gamepad support unavailable: {}", e);
+                    None
+                }
+            };
+            Self { gilrs }
+        }
+
+        /// Événements en attente consommés, puis nom et état de la première manette branchée
+        pub fn sample(&mut self) -> Option<(String, PadState)> {
+            let gilrs = self.gilrs.as_mut()?;
+            while gilrs.next_event().is_some() {}
+            let (_, pad) = gilrs.gamepads().find(|(_, pad)| pad.is_connected())?;
+            let uuid: String = pad.uuid().iter().map(|byte| format!("{:02x}", byte)).collect();
+            let controller = format!("{} [{}]", pad.name(), uuid);
+            let axes = PadAxis::ALL.iter().map(|&axis| (axis, pad.value(axis.gilrs()))).collect();
+            let pressed = PadButton::ALL.iter().copied().filter(|button| pad.is_pressed(button.gilrs())).collect();
+            Some((pad.name().to_string(), PadState { controller, axes, pressed }))
+        }
+    }
+
+    impl Default for Gamepad {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+pub use pad::Gamepad;
+
+#[cfg(feature = "gui")]
+mod gui {
+    use super::{
+        position_for, AxisCalibration, AxisCapture, BindingTarget, GamepadSettings, PadAxis, PadBinding, PadButton, PadInput, PadMode,
+        PadState, ResponseCurve,
+    };
+    use egui_plot::{Line, Plot, PlotPoints, Points};
+
+    /// Manette vue par la boucle du bus, pour le panneau
+    #[derive(Clone, Debug, Default, PartialEq)]
+    pub struct PadStatus {
+        /// Nom de la manette branchée
+        pub connected: Option<String>,
+        pub deadman_held: bool,
+        /// Dernier échantillon, pour l'étalonnage
+        pub sample: Option<PadState>,
+    }
+
+    /// Étalonnage en cours dans le panneau : axe choisi, relevé et essai sur un servo
+    #[derive(Debug, Default)]
+    pub struct CalibrationForm {
+        pub axis: PadAxis,
+        capture: Option<AxisCapture>,
+        message: Option<String>,
+        /// Servo qui suit l'axe étalonné pendant l'essai
+        pub test_servo: Option<u8>,
+        pub testing: bool,
+        last_test: Option<u16>,
+    }
+
+    /// Ce que le panneau d'étalonnage attend de l'interface
+    #[derive(Clone, Copy, Debug, Default, PartialEq)]
+    pub struct CalibrationOutcome {
+        /// Étalonnage modifié : la configuration est à enregistrer
+        pub changed: bool,
+        /// Consigne d'essai (servo, position), à envoyer à `CALIBRATION_TEST_SPEED`
+        pub test_move: Option<(u8, u16)>,
+    }
+
+    fn button_combo(ui: &mut egui::Ui, salt: impl std::hash::Hash, button: &mut PadButton) {
+        egui::ComboBox::from_id_salt(salt).selected_text(button.label()).show_ui(ui, |ui| {
+            for candidate in PadButton::ALL {
+                ui.selectable_value(button, candidate, candidate.label());
+            }
+        });
+    }
+
+    /// Panneau de correspondance : homme mort, cadence et liaisons. `targets` liste les cibles
+    /// proposées avec leur libellé ; `true` si un réglage a changé.
+    pub fn mapping_panel(ui: &mut egui::Ui, settings: &mut GamepadSettings, targets: &[(BindingTarget, String)], status: &PadStatus) -> bool {
+        let before = settings.clone();
+        match &status.connected {
+            Some(name) => ui.label(format!("🎮 {}", name)),
+            None => ui.weak("No gamepad connected"),
+        };
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut settings.deadman, "Deadman").on_hover_text("Nothing moves unless this button is held");
+            ui.add_enabled_ui(settings.deadman, |ui| button_combo(ui, "deadman_button", &mut settings.deadman_button));
+            if settings.deadman && status.connected.is_some() {
+                match status.deadman_held {
+                    true => ui.strong("held"),
+                    false => ui.weak("released"),
+                };
+            }
+            ui.separator();
+            ui.label("Rate:");
+            ui.add(egui::DragValue::new(&mut settings.rate_hz).range(1.0..=100.0).suffix(" Hz"))
+                .on_hover_text("Commands sent at most this many times per second");
+        });
+
+        let mut removed = None;
+        for (index, binding) in settings.bindings.iter_mut().enumerate() {
+            ui.push_id(index, |ui| {
+                ui.horizontal(|ui| {
+                    let selected = targets.iter().find(|(target, _)| *target == binding.target).map_or_else(
+                        || match &binding.target {
+                            BindingTarget::Servo(id) => format!("ID {}", id),
+                            BindingTarget::Group(name) => name.clone(),
+                        },
+                        |(_, label)| label.clone(),
+                    );
+                    egui::ComboBox::from_id_salt("target").selected_text(selected).show_ui(ui, |ui| {
+                        for (target, label) in targets {
+                            ui.selectable_value(&mut binding.target, target.clone(), label);
+                        }
+                    });
+                    let is_axis = matches!(binding.input, PadInput::Axis(_));
+                    if ui.selectable_label(is_axis, "Axis").clicked() && !is_axis {
+                        binding.input = PadInput::Axis(PadAxis::LeftStickX);
+                    }
+                    if ui.selectable_label(!is_axis, "Buttons").clicked() && is_axis {
+                        binding.input = PadInput::Buttons(PadButton::DPadDown, PadButton::DPadUp);
+                    }
+                    match &mut binding.input {
+                        PadInput::Axis(axis) => {
+                            egui::ComboBox::from_id_salt("axis").selected_text(axis.label()).show_ui(ui, |ui| {
+                                for candidate in PadAxis::ALL {
+                                    ui.selectable_value(axis, candidate, candidate.label());
+                                }
+                            });
+                        }
+                        PadInput::Buttons(minus, plus) => {
+                            button_combo(ui, "minus", minus);
+                            ui.label("−/+");
+                            button_combo(ui, "plus", plus);
+                        }
+                    }
+                    egui::ComboBox::from_id_salt("mode").selected_text(binding.mode.label()).show_ui(ui, |ui| {
+                        for mode in PadMode::ALL {
+                            ui.selectable_value(&mut binding.mode, mode, mode.label());
+                        }
+                    });
+                    ui.label("×");
+                    ui.add(egui::DragValue::new(&mut binding.sensitivity).range(-4.0..=4.0).speed(0.01))
+                        .on_hover_text("Sensitivity; negative reverses the direction");
+                    ui.label("Deadzone:");
+                    ui.add(egui::DragValue::new(&mut binding.deadzone).range(0.0..=0.9).speed(0.01));
+                    if ui.small_button("🗑").clicked() {
+                        removed = Some(index);
+                    }
+                });
+            });
+        }
+        if let Some(index) = removed {
+            settings.bindings.remove(index);
+        }
+        if ui.button("+ Add binding").clicked() {
+            let target = targets.first().map_or(BindingTarget::Servo(1), |(target, _)| target.clone());
+            settings.bindings.push(PadBinding { target, ..Default::default() });
+        }
+        *settings != before
+    }
+
+    /// Étalonnage d'un axe de la manette branchée : relevé de la course, zone morte, courbe avec
+    /// son aperçu (axe → consigne absolue), essai sur un servo de `servos` et enregistrement
+    pub fn calibration_panel(
+        ui: &mut egui::Ui,
+        settings: &mut GamepadSettings,
+        servos: &[(u8, String)],
+        status: &PadStatus,
+        form: &mut CalibrationForm,
+    ) -> CalibrationOutcome {
+        let mut outcome = CalibrationOutcome::default();
+        let Some(pad) = &status.sample else {
+            form.capture = None;
+            form.testing = false;
+            ui.weak("Connect a gamepad to calibrate its axes");
+            return outcome;
+        };
+        let raw = pad.raw(form.axis);
+        let stored = settings.calibration(&pad.controller, form.axis).cloned();
+        // Réglages de départ : ceux enregistrés, sinon ceux par défaut ; rien n'est écrit sans modification
+        let baseline = stored.clone().unwrap_or_else(|| AxisCalibration { controller: pad.controller.clone(), axis: form.axis, ..Default::default() });
+        let mut calibration = baseline.clone();
+
+        ui.horizontal(|ui| {
+            ui.label("Axis:");
+            egui::ComboBox::from_id_salt("calibration_axis").selected_text(form.axis.label()).show_ui(ui, |ui| {
+                for axis in PadAxis::ALL {
+                    if ui.selectable_value(&mut form.axis, axis, axis.label()).changed() {
+                        form.capture = None;
+                        form.last_test = None;
+                    }
+                }
+            });
+            ui.monospace(format!("raw {:+.2} → {:+.2}", raw, calibration.apply(raw)));
+            if stored.is_none() {
+                ui.weak("not calibrated");
+            }
+        });
+
+        ui.horizontal(|ui| match form.capture.as_mut() {
+            None => {
+                if ui.button("⏺ Capture range").on_hover_text("Start with the stick at rest, then move it to both ends").clicked() {
+                    form.capture = Some(AxisCapture::new(form.axis, raw));
+                    form.message = None;
+                }
+            }
+            Some(capture) => {
+                capture.observe(raw);
+                ui.monospace(format!("min {:+.2}  center {:+.2}  max {:+.2}", capture.min, capture.center, capture.max));
+                if ui.button("✓ Done").clicked() {
+                    if capture.is_complete() {
+                        capture.apply_to(&mut calibration);
+                        form.message = None;
+                    } else {
+                        form.message = Some("Range too small: move the stick to both ends".to_string());
+                    }
+                    form.capture = None;
+                }
+                if ui.button("Cancel").clicked() {
+                    form.capture = None;
+                }
+            }
+        });
+        if let Some(message) = &form.message {
+            ui.colored_label(ui.visuals().warn_fg_color, message);
+        }
+
+        ui.horizontal(|ui| {
+            ui.label("Deadzone:");
+            ui.add(egui::DragValue::new(&mut calibration.deadzone).range(0.0..=0.9).speed(0.01));
+            egui::ComboBox::from_id_salt("calibration_curve").selected_text(calibration.curve.label()).show_ui(ui, |ui| {
+                for curve in ResponseCurve::ALL {
+                    ui.selectable_value(&mut calibration.curve, curve, curve.label());
+                }
+            });
+            if calibration.curve == ResponseCurve::Expo {
+                ui.add(egui::DragValue::new(&mut calibration.expo).range(0.0..=1.0).speed(0.01)).on_hover_text("0 = linear, 1 = cubic");
+            }
+        });
+
+        // Aperçu : course brute de l'axe en abscisse, consigne absolue en ordonnée
+        let curve: PlotPoints = (-100..=100)
+            .map(|i| {
+                let x = f64::from(i) / 100.0;
+                [x, f64::from(position_for(calibration.apply(x as f32)))]
+            })
+            .collect();
+        let current = f64::from(position_for(calibration.apply(raw)));
+        Plot::new("calibration_preview")
+            .height(140.0)
+            .allow_drag(false)
+            .allow_zoom(false)
+            .allow_scroll(false)
+            .include_y(0.0)
+            .include_y(4095.0)
+            .show(ui, |plot_ui| {
+                plot_ui.line(Line::new("response", curve));
+                plot_ui.points(Points::new("stick", vec![[f64::from(raw), current]]).radius(4.0));
+            });
+
+        ui.horizontal(|ui| {
+            let selected = form.test_servo.and_then(|id| servos.iter().find(|(servo, _)| *servo == id)).map_or("Servo…", |(_, label)| label.as_str());
+            egui::ComboBox::from_id_salt("calibration_servo").selected_text(selected).show_ui(ui, |ui| {
+                for (id, label) in servos {
+                    ui.selectable_value(&mut form.test_servo, Some(*id), label);
+                }
+            });
+            let can_test = form.test_servo.is_some_and(|id| servos.iter().any(|(servo, _)| *servo == id));
+            form.testing &= can_test;
+            ui.add_enabled(can_test, egui::Checkbox::new(&mut form.testing, "Test"))
+                .on_hover_text("The servo follows this axis at reduced speed, deadman permitting; use --simulate to try it on the simulated bus");
+            if stored.is_some() && ui.button("🗑 Forget").on_hover_text("Remove this axis calibration for this gamepad").clicked() {
+                settings.calibrations.retain(|c| c.controller != pad.controller || c.axis != form.axis);
+                outcome.changed = true;
+            }
+        });
+        if form.testing && (!settings.deadman || status.deadman_held) {
+            let position = position_for(calibration.apply(raw));
+            if let Some(id) = form.test_servo.filter(|_| form.last_test != Some(position)) {
+                form.last_test = Some(position);
+                outcome.test_move = Some((id, position));
+            }
+        } else {
+            form.last_test = None;
+        }
+
+        if !outcome.changed && calibration != baseline {
+            settings.set_calibration(calibration);
+            outcome.changed = true;
+        }
+        outcome
+    }
+}
+
+#[cfg(feature = "gui")]
+pub use gui::{calibration_panel, mapping_panel, CalibrationForm, CalibrationOutcome, PadStatus};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAD: &str = "Test pad [00]";
+
+    fn pad(value: f32, deadman: bool) -> PadState {
+        let mut state = PadState { controller: PAD.to_string(), ..Default::default() };
+        state.axes.insert(PadAxis::LeftStickX, value);
+        if deadman {
+            state.pressed.insert(PadButton::LeftTrigger);
+        }
+        state
+    }
+
+    fn position_settings() -> GamepadSettings {
+        let binding = PadBinding { mode: PadMode::Position, deadzone: 0.0, ..Default::default() };
+        GamepadSettings { bindings: vec![binding], ..Default::default() }
+    }
+
+    #[test]
+    fn calibration_scales_each_side_of_the_center() {
+        let calibration = AxisCalibration { min: -0.8, center: 0.1, max: 0.6, deadzone: 0.0, ..Default::default() };
+        assert_eq!(calibration.apply(0.1), 0.0);
+        assert!((calibration.apply(0.6) - 1.0).abs() < 1e-6);
+        assert!((calibration.apply(-0.8) + 1.0).abs() < 1e-6);
+        assert!((calibration.apply(0.35) - 0.5).abs() < 1e-6);
+        // Au-delà des butées relevées : pleine course, pas plus
+        assert_eq!(calibration.apply(1.0), 1.0);
+    }
+
+    #[test]
+    fn expo_softens_the_center_but_keeps_full_travel() {
+        let expo = AxisCalibration { deadzone: 0.0, curve: ResponseCurve::Expo, expo: 1.0, ..Default::default() };
+        assert!((expo.apply(0.5) - 0.125).abs() < 1e-6);
+        assert!((expo.apply(1.0) - 1.0).abs() < 1e-6);
+        assert!((expo.apply(-1.0) + 1.0).abs() < 1e-6);
+        let deadzone = AxisCalibration { deadzone: 0.2, ..Default::default() };
+        assert_eq!(deadzone.apply(0.15), 0.0);
+    }
+
+    #[test]
+    fn capture_needs_a_wide_enough_range() {
+        let mut capture = AxisCapture::new(PadAxis::LeftStickY, 0.05);
+        capture.observe(0.1);
+        assert!(!capture.is_complete());
+        capture.observe(-0.9);
+        capture.observe(0.95);
+        assert!(capture.is_complete());
+        let mut calibration = AxisCalibration::default();
+        capture.apply_to(&mut calibration);
+        assert_eq!((calibration.axis, calibration.min, calibration.center, calibration.max), (PadAxis::LeftStickY, -0.9, 0.05, 0.95));
+    }
+
+    #[test]
+    fn calibrations_are_kept_per_controller() {
+        let mut settings = position_settings();
+        let calibration = AxisCalibration { controller: PAD.to_string(), center: 0.2, max: 0.7, deadzone: 0.0, ..Default::default() };
+        settings.set_calibration(calibration.clone());
+        settings.set_calibration(AxisCalibration { max: 0.45, ..calibration });
+        assert_eq!(settings.calibrations.len(), 1);
+        let binding = settings.bindings[0].clone();
+        assert!((settings.input_value(&pad(0.45, true), &binding) - 1.0).abs() < 1e-6);
+        // Autre manette : pas d'étalonnage, valeur brute
+        let other = PadState { controller: "Other pad [01]".to_string(), ..pad(0.45, true) };
+        assert!((settings.input_value(&other, &binding) - 0.45).abs() < 1e-6);
+    }
+
+    #[test]
+    fn controller_uses_the_calibration_in_position_mode() {
+        let mut settings = position_settings();
+        settings.set_calibration(AxisCalibration { controller: PAD.to_string(), center: 0.2, max: 0.6, deadzone: 0.0, ..Default::default() });
+        let mut controller = PadController::default();
+        let commands = controller.update(&settings, Some(&pad(0.6, true)), Instant::now(), |_| vec![1], |_| Some((2048, SoftLimits::default())));
+        assert_eq!(commands, vec![PadCommand::Move { id: 1, position: MAX_TICKS }]);
+    }
+
+    #[test]
+    fn nothing_moves_without_the_deadman() {
+        let mut controller = PadController::default();
+        let commands = controller.update(&position_settings(), Some(&pad(1.0, false)), Instant::now(), |_| vec![1], |_| Some((2048, SoftLimits::default())));
+        assert!(commands.is_empty());
+        assert_eq!(position_for(0.0), CENTER_TICKS);
+        assert_eq!(position_for(-1.0), 0);
+    }
+
+
+    #[test]
+    fn jog_moves_from_the_current_target_at_the_sample_rate() {
+        let binding = PadBinding { deadzone: 0.0, ..Default::default() };
+        let settings = GamepadSettings { bindings: vec![binding], rate_hz: 20.0, ..Default::default() };
+        let mut controller = PadController::default();
+        let start = Instant::now();
+        let step = Duration::from_millis(50);
+        let mut update = |value: f32, at: Duration| controller.update(&settings, Some(&pad(value, true)), start + at, |_| vec![1], |_| Some((1000, SoftLimits::default())));
+
+        // Premier échantillon : une période de jog
+        let first = 1000 + (JOG_TICKS_PER_S * 0.05).round() as u16;
+        assert_eq!(update(1.0, Duration::ZERO), vec![PadCommand::Move { id: 1, position: first }]);
+        // Trop tôt : rien
+        assert!(update(1.0, Duration::from_millis(10)).is_empty());
+        let second = 1000 + (JOG_TICKS_PER_S * 0.1).round() as u16;
+        assert_eq!(update(1.0, step), vec![PadCommand::Move { id: 1, position: second }]);
+        // Axe relâché, puis reparti : depuis la consigne lue
+        assert!(update(0.0, step * 2).is_empty());
+        assert_eq!(update(-1.0, step * 3), vec![PadCommand::Move { id: 1, position: 1000 - (JOG_TICKS_PER_S * 0.05).round() as u16 }]);
+    }
+
+    #[test]
+    fn wheels_stop_when_the_deadman_is_released() {
+        let binding = PadBinding { mode: PadMode::Velocity, deadzone: 0.0, ..Default::default() };
+        let settings = GamepadSettings { bindings: vec![binding], ..Default::default() };
+        let mut controller = PadController::default();
+        let start = Instant::now();
+        let wheel = |_| Some((2048, SoftLimits::default()));
+
+        let commands = controller.update(&settings, Some(&pad(-0.5, true)), start, |_| vec![4], wheel);
+        assert_eq!(commands, vec![PadCommand::Wheel { id: 4, speed: -MAX_WHEEL_SPEED / 2 }]);
+        // Même vitesse : rien de renvoyé
+        assert!(controller.update(&settings, Some(&pad(-0.5, true)), start + Duration::from_secs(1), |_| vec![4], wheel).is_empty());
+        // Manette débranchée : comme l'homme mort relâché
+        let commands = controller.update(&settings, None, start + Duration::from_secs(2), |_| vec![4], wheel);
+        assert_eq!(commands, vec![PadCommand::Wheel { id: 4, speed: 0 }]);
+    }
+
+    #[test]
+    fn position_mode_respects_soft_limits_and_absent_servos() {
+        let mut controller = PadController::default();
+        let limits = SoftLimits { min: 1000, max: 3000, ..Default::default() };
+        let members = |_: &BindingTarget| vec![1, 2];
+        let servo = |id| (id == 1).then_some((2048, limits));
+        let commands = controller.update(&position_settings(), Some(&pad(1.0, true)), Instant::now(), members, servo);
+        assert_eq!(commands, vec![PadCommand::Move { id: 1, position: 3000 }]);
+    }
+
+    #[test]
+    fn button_pairs_and_deadzone() {
+        let mut state = pad(0.0, true);
+        let input = PadInput::Buttons(PadButton::LeftTrigger, PadButton::RightTrigger);
+        assert_eq!(state.value(&input), -1.0);
+        state.pressed.insert(PadButton::RightTrigger);
+        assert_eq!(state.value(&input), 0.0);
+        assert_eq!(apply_deadzone(0.1, 0.15), 0.0);
+        assert!((apply_deadzone(-0.575, 0.15) + 0.5).abs() < 1e-6);
+    }
+}
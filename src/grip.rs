@@ -0,0 +1,120 @@
+//! Mode préhension limité en courant : fermer jusqu'à atteindre un courant seuil, puis maintenir.
+
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GripSettings {
+    /// Position de fin de course côté fermeture (donne le sens de fermeture)
+    pub closed_position: u16,
+    pub open_position: u16,
+    /// Courant (mA) à partir duquel on considère l'objet saisi
+    pub current_threshold_ma: f32,
+    /// Durée pendant laquelle le seuil doit être maintenu avant de bloquer la consigne
+    pub hold_time: Duration,
+    /// Avance de la consigne (ticks) à chaque cycle du worker
+    pub step: u16,
+    pub speed: u16,
+}
+
+impl Default for GripSettings {
+    fn default() -> Self {
+        Self {
+            closed_position: 3072,
+            open_position: 2048,
+            current_threshold_ma: 300.0,
+            hold_time: Duration::from_millis(200),
+            step: 10,
+            speed: 200,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GripStatus {
+    Idle,
+    Closing,
+    /// `contact` est faux si la fin de course a été atteinte sans dépasser le seuil
+    Holding { position: u16, current_ma: f32, contact: bool },
+    Opening,
+}
+
+pub struct GripController {
+    settings: GripSettings,
+    target: u16,
+    over_since: Option<Instant>,
+    status: GripStatus,
+}
+
+// Tolérance (ticks) pour considérer la position d'ouverture atteinte
+const OPEN_TOLERANCE: u16 = 20;
+
+impl GripController {
+    /// Démarre une fermeture depuis la position actuelle
+    pub fn close(settings: GripSettings, start_position: u16) -> Self {
+        Self {
+            settings,
+            target: start_position,
+            over_since: None,
+            status: GripStatus::Closing,
+        }
+    }
+
+    /// Démarre une ouverture vers la position configurée
+    pub fn open(settings: GripSettings) -> Self {
+        Self {
+            settings,
+            target: settings.open_position,
+            over_since: None,
+            status: GripStatus::Opening,
+        }
+    }
+
+    pub fn status(&self) -> GripStatus {
+        self.status
+    }
+
+    pub fn settings(&self) -> &GripSettings {
+        &self.settings
+    }
+
+    /// Fait avancer la machine d'état avec les dernières mesures.
+    /// Retourne la nouvelle consigne de position à envoyer, le cas échéant.
+    pub fn update(&mut self, position: u16, current_ma: f32, now: Instant) -> Option<u16> {
+        match self.status {
+            GripStatus::Closing => {
+                if current_ma.abs() >= self.settings.current_threshold_ma {
+                    let since = *self.over_since.get_or_insert(now);
+                    if now.duration_since(since) >= self.settings.hold_time {
+                        // Seuil tenu : on fige la consigne sur la position atteinte
+                        self.status = GripStatus::Holding { position, current_ma, contact: true };
+                        self.target = position;
+                        return Some(position);
+                    }
+                    // On n'avance plus tant que le seuil est dépassé
+                    return None;
+                }
+
+                self.over_since = None;
+                if self.target == self.settings.closed_position {
+                    self.status = GripStatus::Holding { position, current_ma, contact: false };
+                    return None;
+                }
+
+                let closed = self.settings.closed_position;
+                self.target = if closed > self.target {
+                    self.target.saturating_add(self.settings.step).min(closed)
+                } else {
+                    self.target.saturating_sub(self.settings.step).max(closed)
+                };
+                Some(self.target)
+            }
+            GripStatus::Opening => {
+                if position.abs_diff(self.target) <= OPEN_TOLERANCE {
+                    self.status = GripStatus::Idle;
+                }
+                None
+            }
+            GripStatus::Holding { .. } | GripStatus::Idle => None,
+        }
+    }
+}
@@ -0,0 +1,141 @@
+//! Groupes de servos nommés (une jambe, un bras), commandés d'un coup depuis l'interface
+//! multi-servos : couple, pose du groupe et décalage commun.
+//!
+//! ```toml
+//! [[groups]]
+//! name = "left leg"
+//! ids = [1, 2, 3]
+//! [groups.pose]      # capturée depuis l'interface
+//! 1 = 2048
+//! 2 = 1800
+//! ```
+
+use crate::ids::MAX_SERVO_ID;
+use crate::limits::SoftLimits;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServoGroup {
+    pub name: String,
+    pub ids: Vec<u8>,
+    /// Pose du groupe (ticks par ID) ; vide tant qu'aucune n'a été capturée
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub pose: BTreeMap<u8, u16>,
+}
+
+impl ServoGroup {
+    /// Membres présents sur le bus, et membres absents
+    pub fn members(&self, detected: &[u8]) -> (Vec<u8>, Vec<u8>) {
+        self.ids.iter().partition(|id| detected.contains(id))
+    }
+
+    /// Consignes de la pose pour les membres détectés, et IDs de la pose absents du bus
+    pub fn pose_targets(&self, detected: &[u8]) -> (Vec<(u8, u16)>, Vec<u8>) {
+        let present = self.pose.iter().filter(|(id, _)| detected.contains(id)).map(|(&id, &pos)| (id, pos));
+        let missing = self.pose.keys().copied().filter(|id| !detected.contains(id));
+        (present.collect(), missing.collect())
+    }
+}
+
+/// Consignes décalées de `offset` ticks à partir de `base` (ID, position), dans la plage du
+/// servo. Les positions sont logiques : un servo inversé tourne dans l'autre sens.
+pub fn offset_targets(base: &BTreeMap<u8, u16>, offset: i32) -> Vec<(u8, u16)> {
+    base.iter().map(|(&id, &position)| (id, (position as i32 + offset).clamp(0, 4095) as u16)).collect()
+}
+
+/// Consigne d'un servo pour une action sur tous les servos (« Center all », « Go to… ») : position
+/// ramenée dans ses butées, et sa propre vitesse plafonnée par `speed_cap` (0 = vitesse max, donc
+/// le plafond). Une position différente de `target` signale une consigne bornée.
+pub fn bulk_target(target: u16, limits: &SoftLimits, speed: u16, speed_cap: Option<u16>) -> (u16, u16) {
+    let speed = match (speed_cap, speed) {
+        (Some(cap), 0) => cap,
+        (Some(cap), speed) => speed.min(cap),
+        (None, speed) => speed,
+    };
+    (limits.clamp(target), speed)
+}
+
+/// Noms présents et uniques, IDs valides, et chaque servo dans un seul groupe
+pub fn check_groups(groups: &[ServoGroup]) -> Result<(), String> {
+    let mut names = BTreeSet::new();
+    let mut owners: BTreeMap<u8, &str> = BTreeMap::new();
+    for group in groups {
+        let name = group.name.trim();
+        if name.is_empty() {
+            return Err("a group has no name".to_string());
+        }
+        if !names.insert(name) {
+            return Err(format!("group '{}' is defined twice", name));
+        }
+        for &id in &group.ids {
+            if id > MAX_SERVO_ID {
+                return Err(format!("group '{}': ID {} is reserved (valid IDs: 0-{})", name, id, MAX_SERVO_ID));
+            }
+            match owners.insert(id, name) {
+                Some(owner) if owner == name => return Err(format!("group '{}' lists ID {} twice", name, id)),
+                Some(owner) => return Err(format!("ID {} is in both '{}' and '{}'", id, owner, name)),
+                None => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn group(name: &str, ids: &[u8]) -> ServoGroup {
+        ServoGroup { name: name.to_string(), ids: ids.to_vec(), ..ServoGroup::default() }
+    }
+
+    #[test]
+    fn group_commands_reach_detected_members_only() {
+        let leg = ServoGroup { pose: BTreeMap::from([(1, 2048), (2, 1800), (3, 900)]), ..group("left leg", &[1, 2, 3]) };
+        assert_eq!(leg.members(&[1, 3, 7]), (vec![1, 3], vec![2]));
+        assert_eq!(leg.pose_targets(&[1, 3, 7]), (vec![(1, 2048), (3, 900)], vec![2]));
+    }
+
+    #[test]
+    fn offsets_stay_within_the_servo_range() {
+        let base = BTreeMap::from([(1, 100), (2, 2048), (3, 4000)]);
+        assert_eq!(offset_targets(&base, 200), vec![(1, 300), (2, 2248), (3, 4095)]);
+        assert_eq!(offset_targets(&base, -200), vec![(1, 0), (2, 1848), (3, 3800)]);
+    }
+
+    #[test]
+    fn groups_are_checked_for_names_and_overlaps() {
+        assert!(check_groups(&[group("left leg", &[1, 2]), group("right leg", &[3, 4])]).is_ok());
+        assert_eq!(check_groups(&[group(" ", &[1])]), Err("a group has no name".to_string()));
+        assert!(check_groups(&[group("arm", &[1]), group("arm", &[2])]).unwrap_err().contains("defined twice"));
+        assert!(check_groups(&[group("arm", &[1, 1])]).unwrap_err().contains("lists ID 1 twice"));
+        assert_eq!(check_groups(&[group("arm", &[1]), group("leg", &[1])]), Err("ID 1 is in both 'arm' and 'leg'".to_string()));
+        assert!(check_groups(&[group("arm", &[MAX_SERVO_ID + 1])]).unwrap_err().contains("reserved"));
+    }
+
+    #[test]
+    fn groups_load_from_toml() {
+        let text = "[[groups]]\nname = \"left leg\"\nids = [1, 2]\n[groups.pose]\n1 = 2048\n";
+        #[derive(Deserialize)]
+        struct File {
+            groups: Vec<ServoGroup>,
+        }
+        let file: File = toml::from_str(text).unwrap();
+        assert_eq!(file.groups, vec![ServoGroup { pose: BTreeMap::from([(1, 2048)]), ..group("left leg", &[1, 2]) }]);
+    }
+
+
+    #[test]
+    fn bulk_targets_are_clamped_and_speed_capped() {
+        let limits = SoftLimits { min: 1000, max: 3000, ..SoftLimits::default() };
+        assert_eq!(bulk_target(2048, &limits, 600, Some(400)), (2048, 400));
+        assert_eq!(bulk_target(2048, &limits, 200, Some(400)), (2048, 200));
+        // Vitesse max (0) : plafonnée aussi
+        assert_eq!(bulk_target(2048, &limits, 0, Some(400)), (2048, 400));
+        // « Go to… » : vitesse propre du servo, consigne bornée
+        assert_eq!(bulk_target(3500, &limits, 0, None), (3000, 0));
+        assert_eq!(bulk_target(500, &limits, 800, None), (1000, 800));
+    }
+}
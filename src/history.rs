@@ -0,0 +1,137 @@
+//! Séries horodatées des graphiques : tampon circulaire de capacité fixe, le point le plus
+//! ancien est écrasé une fois la capacité atteinte.
+
+use std::collections::VecDeque;
+
+/// Bornes du réglage de longueur d'historique (points par série)
+pub const MIN_HISTORY: usize = 100;
+pub const MAX_HISTORY: usize = 100_000;
+
+#[derive(Clone, Debug)]
+pub struct History {
+    points: VecDeque<(f64, f64)>,
+    capacity: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(MIN_HISTORY)
+    }
+}
+
+impl History {
+    /// Capacité ramenée à `MIN_HISTORY..=MAX_HISTORY`
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.clamp(MIN_HISTORY, MAX_HISTORY);
+        Self { points: VecDeque::with_capacity(capacity), capacity }
+    }
+
+    pub fn push(&mut self, time: f64, value: f64) {
+        if self.points.len() == self.capacity {
+            self.points.pop_front();
+        }
+        self.points.push_back((time, value));
+    }
+
+    /// Points du plus ancien au plus récent
+    pub fn iter_ordered(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.points.iter().copied()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Nouvelle capacité ; en la réduisant, on garde les points les plus récents
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.clamp(MIN_HISTORY, MAX_HISTORY);
+        while self.points.len() > self.capacity {
+            self.points.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Point le plus ancien encore en mémoire
+    pub fn first(&self) -> Option<(f64, f64)> {
+        self.points.front().copied()
+    }
+
+    /// Décale toutes les valeurs (changement de référence des positions)
+    pub fn shift_values(&mut self, delta: f64) {
+        for point in &mut self.points {
+            point.1 += delta;
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<(f64, f64)> {
+        self.iter_ordered().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filled(capacity: usize, count: usize) -> History {
+        let mut history = History::new(capacity);
+        for i in 0..count {
+            history.push(i as f64, i as f64 * 10.0);
+        }
+        history
+    }
+
+    #[test]
+    fn oldest_point_is_dropped_when_full() {
+        let history = filled(MIN_HISTORY, MIN_HISTORY + 5);
+        assert_eq!(history.len(), MIN_HISTORY);
+        assert_eq!(history.first(), Some((5.0, 50.0)));
+        assert_eq!(history.iter_ordered().last(), Some(((MIN_HISTORY + 4) as f64, (MIN_HISTORY + 4) as f64 * 10.0)));
+        assert!(history.to_vec().windows(2).all(|w| w[0].0 < w[1].0));
+    }
+
+    #[test]
+    fn capacity_is_clamped() {
+        assert_eq!(History::new(0).capacity(), MIN_HISTORY);
+        assert_eq!(History::new(usize::MAX).capacity(), MAX_HISTORY);
+        assert_eq!(History::default().capacity(), MIN_HISTORY);
+        assert!(History::default().is_empty());
+    }
+
+    #[test]
+    fn shrinking_keeps_the_most_recent_points() {
+        let mut history = filled(1000, 300);
+        history.set_capacity(150);
+        assert_eq!(history.capacity(), 150);
+        assert_eq!(history.len(), 150);
+        assert_eq!(history.first(), Some((150.0, 1500.0)));
+
+        // Agrandir ne perd rien et laisse de la place pour la suite
+        history.set_capacity(200);
+        history.push(300.0, 3000.0);
+        assert_eq!(history.len(), 151);
+        assert_eq!(history.first(), Some((150.0, 1500.0)));
+    }
+
+    #[test]
+    fn values_are_shifted_in_place() {
+        let mut history = filled(MIN_HISTORY, 3);
+        history.shift_values(-5.0);
+        assert_eq!(history.to_vec(), vec![(0.0, -5.0), (1.0, 5.0), (2.0, 15.0)]);
+    }
+
+    #[test]
+    fn shift_moves_every_value() {
+        let mut history = History::default();
+        history.push(0.0, 2148.0);
+        history.push(0.1, 2200.0);
+        history.shift_values(-100.0);
+        assert_eq!(history.to_vec(), vec![(0.0, 2048.0), (0.1, 2100.0)]);
+    }
+}
@@ -0,0 +1,361 @@
+//! Découverte des servos sans bloquer le worker : scan rapide par ping en diffusion, scan
+//! complet découpé en lots, rescan périodique du bus pour trouver les servos branchés en
+//! cours de session, et présence des servos suivis (hors ligne faute de réponse). Les balayages ID par ID sont étalés sur les cycles de polling (quelques
+//! pings par cycle) pour ne jamais retarder les commandes ni la télémétrie des servos suivis.
+//!
+//! ```toml
+//! [rescan]
+//! enabled = true
+//! interval_s = 10
+//! ```
+
+use crate::backend::ServoBackend;
+use crate::packet;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Pings envoyés au plus par cycle de polling par le rescan périodique
+pub const PINGS_PER_CYCLE: usize = 2;
+/// IDs pingés par cycle pendant un scan demandé (connexion, bouton Scan)
+pub const SCAN_BATCH: usize = 4;
+/// Durée d'écoute des réponses à un ping en diffusion
+pub const BROADCAST_WINDOW: Duration = Duration::from_millis(100);
+
+/// Scan ID par ID, un lot par cycle du worker
+#[derive(Clone, Debug)]
+pub struct IncrementalScan {
+    pending: VecDeque<u8>,
+    end: u8,
+    found: Vec<u8>,
+}
+
+impl IncrementalScan {
+    pub fn new(ids: impl IntoIterator<Item = u8>) -> Self {
+        Self::resuming(ids, Vec::new())
+    }
+
+    /// Balayage des `ids` restants, `found` ayant déjà répondu par ailleurs
+    pub fn resuming(ids: impl IntoIterator<Item = u8>, found: Vec<u8>) -> Self {
+        let pending: VecDeque<u8> = ids.into_iter().filter(|id| !found.contains(id)).collect();
+        let end = pending.back().copied().unwrap_or_default();
+        Self { pending, end, found }
+    }
+
+    /// Pinge le lot suivant ; retourne les IDs qui ont répondu dans ce lot
+    pub fn step(&mut self, mut ping: impl FnMut(u8) -> bool) -> Vec<u8> {
+        let mut hits = Vec::new();
+        for _ in 0..SCAN_BATCH {
+            let Some(id) = self.pending.pop_front() else { break };
+            if ping(id) {
+                hits.push(id);
+            }
+        }
+        self.found.extend(&hits);
+        self.found.sort_unstable();
+        hits
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// (prochain ID pingé, dernier ID du balayage), pour l'affichage
+    pub fn progress(&self) -> (u8, u8) {
+        (self.pending.front().copied().unwrap_or(self.end), self.end)
+    }
+
+    /// IDs trouvés depuis le début du scan, croissants
+    pub fn found(&self) -> &[u8] {
+        &self.found
+    }
+}
+
+/// Résultat d'un ping en diffusion
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct FastScan {
+    /// IDs qui ont répondu, croissants et sans doublon (même forme que `list_servos()`)
+    pub ids: Vec<u8>,
+    /// IDs dont plusieurs réponses sont arrivées : deux servos partagent cet ID
+    pub duplicates: Vec<u8>,
+    /// Octets hors de toute trame valide : des réponses se sont chevauchées
+    pub garbled: usize,
+}
+
+impl FastScan {
+    /// Trames reçues en réponse au ping ; l'écho éventuel de la trame envoyée est ignoré
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let (responses, garbled) = packet::split_responses(bytes);
+        let mut scan = Self { garbled, ..Self::default() };
+        for id in responses.into_iter().map(|r| r.id).filter(|&id| id < st3215::BROADCAST_ID) {
+            if !scan.ids.contains(&id) {
+                scan.ids.push(id);
+            } else if !scan.duplicates.contains(&id) {
+                scan.duplicates.push(id);
+            }
+        }
+        scan.ids.sort_unstable();
+        scan.duplicates.sort_unstable();
+        scan
+    }
+
+    /// Diffusion sans réponse, ou réponses illisibles : il faut balayer ID par ID les IDs restants
+    pub fn needs_sweep(&self) -> bool {
+        self.ids.is_empty() || self.garbled > 0
+    }
+}
+
+/// Ping en diffusion sur le bus
+pub fn fast_scan(bus: &dyn ServoBackend) -> Result<FastScan, String> {
+    bus.broadcast_ping(BROADCAST_WINDOW).map(|bytes| FastScan::from_bytes(&bytes))
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RescanSettings {
+    pub enabled: bool,
+    /// Délai entre le début de deux balayages
+    pub interval_s: u64,
+}
+
+impl Default for RescanSettings {
+    fn default() -> Self {
+        Self { enabled: true, interval_s: 10 }
+    }
+}
+
+impl RescanSettings {
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_s.max(1))
+    }
+}
+
+/// Balayage en cours : IDs restant à pinger, et début du prochain balayage
+#[derive(Clone, Debug)]
+pub struct BackgroundScan {
+    pending: VecDeque<u8>,
+    next_sweep: Instant,
+}
+
+impl BackgroundScan {
+    /// Le scan de connexion vient d'avoir lieu : premier balayage après un intervalle
+    pub fn new(settings: &RescanSettings, now: Instant) -> Self {
+        Self { pending: VecDeque::new(), next_sweep: now + settings.interval() }
+    }
+
+    /// IDs à pinger pendant ce cycle, pris dans `ids` hors de ceux que `known` accepte ;
+    /// aucun entre deux balayages ou quand le rescan est désactivé
+    pub fn next_batch(
+        &mut self,
+        settings: &RescanSettings,
+        ids: impl IntoIterator<Item = u8>,
+        known: impl Fn(u8) -> bool,
+        now: Instant,
+    ) -> Vec<u8> {
+        if !settings.enabled {
+            self.pending.clear();
+            self.next_sweep = now + settings.interval();
+            return Vec::new();
+        }
+        if self.pending.is_empty() && now >= self.next_sweep {
+            self.pending = ids.into_iter().filter(|&id| !known(id)).collect();
+            self.next_sweep = now + settings.interval();
+        }
+        let mut batch = Vec::new();
+        while batch.len() < PINGS_PER_CYCLE {
+            let Some(id) = self.pending.pop_front() else { break };
+            // Trouvé entre-temps (scan manuel) : inutile de le pinger
+            if !known(id) {
+                batch.push(id);
+            }
+        }
+        batch
+    }
+}
+
+/// Servo sans aucune réponse depuis ce délai : hors ligne
+pub const OFFLINE_AFTER: Duration = Duration::from_secs(2);
+/// Un servo hors ligne n'est relu qu'un cycle de polling sur ce nombre, pour détecter son retour
+pub const OFFLINE_POLL_CYCLES: u32 = 25;
+
+/// Présence d'un servo suivi : en ligne dès qu'une lecture répond, hors ligne après
+/// `OFFLINE_AFTER` sans aucune réponse
+#[derive(Clone, Copy, Debug)]
+pub struct Presence {
+    last_seen: Instant,
+    online: bool,
+}
+
+impl Presence {
+    pub fn new(now: Instant) -> Self {
+        Self { last_seen: now, online: true }
+    }
+
+    /// Lectures d'un cycle ; `Some(en ligne)` quand l'état change
+    pub fn observe(&mut self, responded: bool, now: Instant) -> Option<bool> {
+        let was_online = self.online;
+        if responded {
+            self.last_seen = now;
+            self.online = true;
+        } else if now.saturating_duration_since(self.last_seen) > OFFLINE_AFTER {
+            self.online = false;
+        }
+        (self.online != was_online).then_some(self.online)
+    }
+
+    pub fn is_online(&self) -> bool {
+        self.online
+    }
+
+    /// Dernière réponse, pour l'affichage d'un servo hors ligne
+    pub fn last_seen(&self) -> Instant {
+        self.last_seen
+    }
+
+    /// Servo relu au cycle `cycle` du polling
+    pub fn should_poll(&self, cycle: u32) -> bool {
+        self.online || cycle.is_multiple_of(OFFLINE_POLL_CYCLES)
+    }
+}
+
+#[cfg(feature = "gui")]
+mod gui {
+    use super::RescanSettings;
+
+    /// Barre d'avancement d'un scan en cours ; vrai si l'utilisateur l'annule
+    pub fn scan_progress(ui: &mut egui::Ui, (current, end): (u8, u8)) -> bool {
+        ui.horizontal(|ui| {
+            let ratio = if end == 0 { 1.0 } else { f32::from(current) / f32::from(end) };
+            ui.add(egui::ProgressBar::new(ratio).desired_width(200.0).text(format!("Scanning ID {}/{}", current, end)));
+            ui.button("Cancel").clicked()
+        })
+        .inner
+    }
+
+    /// Réglages du rescan sur une ligne ; vrai si l'un d'eux a changé
+    pub fn settings_editor(ui: &mut egui::Ui, settings: &mut RescanSettings) -> bool {
+        let before = settings.clone();
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut settings.enabled, "Rescan for new servos every")
+                .on_hover_text("Pings the IDs not yet detected, a few per polling cycle");
+            ui.add_enabled(
+                settings.enabled,
+                egui::DragValue::new(&mut settings.interval_s).range(1..=600).suffix(" s"),
+            );
+        });
+        *settings != before
+    }
+}
+
+#[cfg(feature = "gui")]
+pub use gui::{scan_progress, settings_editor};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn servo_goes_offline_after_silence_and_back_on_any_answer() {
+        let start = Instant::now();
+        let mut presence = Presence::new(start);
+        assert_eq!(presence.observe(false, start + Duration::from_secs(1)), None);
+        assert_eq!(presence.observe(false, start + OFFLINE_AFTER), None);
+        assert_eq!(presence.observe(false, start + OFFLINE_AFTER + Duration::from_millis(1)), Some(false));
+        assert_eq!(presence.observe(false, start + Duration::from_secs(10)), None);
+        assert_eq!(presence.last_seen(), start);
+
+        let back = start + Duration::from_secs(11);
+        assert_eq!(presence.observe(true, back), Some(true));
+        assert!(presence.is_online());
+        assert_eq!(presence.last_seen(), back);
+    }
+
+    #[test]
+    fn offline_servo_is_polled_once_every_few_cycles() {
+        let start = Instant::now();
+        let mut presence = Presence::new(start);
+        assert!((1..=OFFLINE_POLL_CYCLES).all(|cycle| presence.should_poll(cycle)));
+
+        presence.observe(false, start + OFFLINE_AFTER * 2);
+        let polled: Vec<u32> = (1..=OFFLINE_POLL_CYCLES * 2).filter(|&cycle| presence.should_poll(cycle)).collect();
+        assert_eq!(polled, vec![OFFLINE_POLL_CYCLES, OFFLINE_POLL_CYCLES * 2]);
+    }
+
+    #[test]
+    fn background_sweep_pings_unknown_ids_a_few_per_cycle() {
+        let settings = RescanSettings { enabled: true, interval_s: 10 };
+        let start = Instant::now();
+        let mut scan = BackgroundScan::new(&settings, start);
+        let known = |id| id == 2;
+        assert!(scan.next_batch(&settings, 1..=5, known, start + Duration::from_secs(9)).is_empty());
+
+        let due = start + Duration::from_secs(10);
+        assert_eq!(scan.next_batch(&settings, 1..=5, known, due), vec![1, 3]);
+        assert_eq!(scan.next_batch(&settings, 1..=5, known, due), vec![4, 5]);
+        assert!(scan.next_batch(&settings, 1..=5, known, due).is_empty());
+        assert_eq!(scan.next_batch(&settings, 1..=5, known, due + Duration::from_secs(10)), vec![1, 3]);
+    }
+
+    #[test]
+    fn disabled_rescan_drops_the_pending_sweep() {
+        let settings = RescanSettings { enabled: true, interval_s: 0 };
+        let start = Instant::now();
+        let mut scan = BackgroundScan::new(&settings, start);
+        let later = start + settings.interval();
+        assert_eq!(scan.next_batch(&settings, 1..=6, |_| false, later).len(), PINGS_PER_CYCLE);
+
+        let disabled = RescanSettings { enabled: false, ..settings };
+        assert!(scan.next_batch(&disabled, 1..=6, |_| false, later).is_empty());
+        // Réactivé : le balayage interrompu ne reprend qu'à l'intervalle suivant
+        assert!(scan.next_batch(&settings, 1..=6, |_| false, later).is_empty());
+        assert_eq!(scan.next_batch(&settings, 1..=6, |_| false, later + settings.interval()), vec![1, 2]);
+    }
+
+    #[test]
+    fn incremental_scan_pings_one_batch_per_step() {
+        let mut scan = IncrementalScan::new(1..=10);
+        assert_eq!(scan.progress(), (1, 10));
+        let mut pinged = Vec::new();
+        let hits = scan.step(|id| {
+            pinged.push(id);
+            id % 3 == 0
+        });
+        assert_eq!((pinged, hits), (vec![1, 2, 3, 4], vec![3]));
+        assert_eq!(scan.progress(), (5, 10));
+
+        while !scan.is_done() {
+            scan.step(|id| id % 3 == 0);
+        }
+        assert_eq!(scan.found(), &[3, 6, 9]);
+        assert_eq!(scan.progress(), (10, 10));
+    }
+
+    #[test]
+    fn resumed_scan_skips_ids_already_found() {
+        let mut scan = IncrementalScan::resuming(1..=5, vec![4, 2]);
+        let mut pinged = Vec::new();
+        while !scan.is_done() {
+            scan.step(|id| {
+                pinged.push(id);
+                id == 5
+            });
+        }
+        assert_eq!(pinged, vec![1, 3, 5]);
+        assert_eq!(scan.found(), &[2, 4, 5]);
+    }
+
+    #[test]
+    fn broadcast_replies_give_sorted_ids_and_duplicates() {
+        let mut bytes = packet::build_frame(st3215::BROADCAST_ID, st3215::INST_PING, &[]).unwrap();
+        for id in [7, 2, 7, 5] {
+            bytes.extend(packet::build_frame(id, 0, &[]).unwrap());
+        }
+        let scan = FastScan::from_bytes(&bytes);
+        assert_eq!(scan, FastScan { ids: vec![2, 5, 7], duplicates: vec![7], garbled: 0 });
+        assert!(!scan.needs_sweep());
+
+        bytes.push(0x42);
+        assert!(FastScan::from_bytes(&bytes).needs_sweep());
+        assert!(FastScan::from_bytes(&[]).needs_sweep());
+    }
+}
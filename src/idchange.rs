@@ -0,0 +1,199 @@
+//! Suivi des changements d'ID : certains servos continuent de répondre sur l'ancien ID
+//! tant qu'ils n'ont pas été remis sous tension. Échange de deux ID en trois changements
+//! via un ID temporaire libre.
+
+use crate::ids::MAX_SERVO_ID;
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum IdChangeOutcome {
+    /// Le nouvel ID répond, l'ancien non
+    Done,
+    /// Les deux ID répondent : remise sous tension nécessaire
+    PowerCycleRequired,
+    /// Seul l'ancien ID répond : l'écriture n'a pas pris (EEPROM, alimentation)
+    StillOldId,
+    /// Aucun des deux ne répond
+    NoResponse,
+}
+
+impl IdChangeOutcome {
+    /// Nouvel ID en place (éventuellement après remise sous tension)
+    pub fn is_success(self) -> bool {
+        matches!(self, IdChangeOutcome::Done | IdChangeOutcome::PowerCycleRequired)
+    }
+
+    /// État constaté, avec les ID qui répondent
+    pub fn describe(self, old_id: u8, new_id: u8) -> String {
+        match self {
+            IdChangeOutcome::Done => format!("ID {} verified, ID {} no longer answers", new_id, old_id),
+            IdChangeOutcome::PowerCycleRequired => {
+                format!("ID {} answers but so does ID {}: power-cycle the servo to apply", new_id, old_id)
+            }
+            IdChangeOutcome::StillOldId => format!("write not applied: the servo still answers on ID {}, not on ID {}", old_id, new_id),
+            IdChangeOutcome::NoResponse => format!("neither ID {} nor ID {} answers: check the servo power", old_id, new_id),
+        }
+    }
+}
+
+pub fn verify_id_change(new_responds: bool, old_responds: bool) -> IdChangeOutcome {
+    match (new_responds, old_responds) {
+        (true, false) => IdChangeOutcome::Done,
+        (true, true) => IdChangeOutcome::PowerCycleRequired,
+        (false, true) => IdChangeOutcome::StillOldId,
+        (false, false) => IdChangeOutcome::NoResponse,
+    }
+}
+
+/// Relecture après l'écriture de l'ID. L'ancien ID est pingé en premier : son attente absorbe la
+/// réponse à l'écriture, que le pilote ne lit pas et qui fausserait le ping du nouvel ID.
+pub fn check_id_change(mut ping: impl FnMut(u8) -> bool, old_id: u8, new_id: u8) -> IdChangeOutcome {
+    let old_responds = ping(old_id);
+    verify_id_change(ping(new_id), old_responds)
+}
+
+/// Échange des ID `a` et `b` : `a` → ID temporaire libre, `b` → `a`, temporaire → `b`.
+/// Les deux ID doivent figurer dans le dernier scan `detected`.
+pub fn swap_plan(a: u8, b: u8, detected: &[u8]) -> Result<[(u8, u8); 3], String> {
+    if detected.len() < 2 {
+        return Err(format!("swapping IDs needs two detected servos ({} detected)", detected.len()));
+    }
+    if a == b {
+        return Err(format!("cannot swap ID {} with itself", a));
+    }
+    if let Some(missing) = [a, b].into_iter().find(|id| !detected.contains(id)) {
+        return Err(format!("ID {} is not in the latest scan", missing));
+    }
+    // ID le plus haut encore libre : loin des numérotations habituelles
+    let temporary = (0..=MAX_SERVO_ID)
+        .rev()
+        .find(|id| !detected.contains(id))
+        .ok_or("no free ID left for the swap".to_string())?;
+    Ok([(a, temporary), (b, a), (temporary, b)])
+}
+
+/// Exécute les changements dans l'ordre ; `step(ancien, nouveau)` écrit puis vérifie un changement.
+/// Au premier échec, les changements déjà faits sont défaits en sens inverse.
+pub fn run_swap(plan: &[(u8, u8)], mut step: impl FnMut(u8, u8) -> Result<(), String>) -> Result<(), String> {
+    for (i, &(from, to)) in plan.iter().enumerate() {
+        if let Err(e) = step(from, to) {
+            let failure = format!("step {}/{} (ID {} → {}) failed: {}", i + 1, plan.len(), from, to, e);
+            let rollback: Result<(), String> = plan[..i].iter().rev().try_for_each(|&(done_from, done_to)| step(done_to, done_from));
+            return Err(match rollback {
+                Ok(()) if i == 0 => failure,
+                Ok(()) => format!("{}; previous steps rolled back", failure),
+                Err(e) => format!("{}; rollback failed too: {}", failure, e),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Changements d'ID en attente de remise sous tension (ancien ID → nouvel ID)
+#[derive(Clone, Debug, Default)]
+pub struct PendingIdChanges {
+    pending: BTreeMap<u8, u8>,
+}
+
+impl PendingIdChanges {
+    pub fn insert(&mut self, old_id: u8, new_id: u8) {
+        self.pending.insert(old_id, new_id);
+    }
+
+    /// Ancien ID fantôme d'un servo, s'il attend encore une remise sous tension
+    pub fn power_cycle_required(&self, new_id: u8) -> Option<u8> {
+        self.pending.iter().find(|(_, &n)| n == new_id).map(|(&old, _)| old)
+    }
+
+    /// Ancien ID masqué en attendant la remise sous tension
+    pub fn is_masked(&self, id: u8) -> bool {
+        self.pending.contains_key(&id)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Fusionne un scan : masque les anciens ID fantômes tant que le nouvel ID répond aussi,
+    /// et oublie les changements dont l'ancien ID a cessé de répondre (servo redémarré).
+    pub fn merge_scan(&mut self, scanned: &[u8]) -> Vec<u8> {
+        self.pending
+            .retain(|old, new| scanned.contains(old) && scanned.contains(new));
+        scanned
+            .iter()
+            .copied()
+            .filter(|id| !self.pending.contains_key(id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn outcome_from_pings() {
+        assert_eq!(verify_id_change(true, false), IdChangeOutcome::Done);
+        assert_eq!(verify_id_change(true, true), IdChangeOutcome::PowerCycleRequired);
+        assert_eq!(verify_id_change(false, true), IdChangeOutcome::StillOldId);
+        assert_eq!(verify_id_change(false, false), IdChangeOutcome::NoResponse);
+        assert!(IdChangeOutcome::PowerCycleRequired.is_success());
+        assert!(!IdChangeOutcome::StillOldId.is_success());
+        assert!(IdChangeOutcome::StillOldId.describe(1, 5).contains("still answers on ID 1"));
+    }
+
+    #[test]
+    fn old_id_is_pinged_first() {
+        let mut pinged = Vec::new();
+        let outcome = check_id_change(|id| {
+            pinged.push(id);
+            id == 5
+        }, 1, 5);
+        assert_eq!(outcome, IdChangeOutcome::Done);
+        assert_eq!(pinged, vec![1, 5]);
+    }
+
+    #[test]
+    fn swap_goes_through_the_highest_free_id() {
+        assert_eq!(swap_plan(1, 2, &[1, 2, 253]), Ok([(1, 252), (2, 1), (252, 2)]));
+        assert!(swap_plan(1, 1, &[1, 2]).is_err());
+        assert_eq!(swap_plan(1, 3, &[1, 2]), Err("ID 3 is not in the latest scan".to_string()));
+        assert!(swap_plan(1, 2, &[1]).unwrap_err().contains("two detected servos"));
+    }
+
+    #[test]
+    fn failed_swap_step_rolls_back() {
+        let plan = [(1, 252), (2, 1), (252, 2)];
+        let mut steps = Vec::new();
+        let outcome = run_swap(&plan, |from, to| {
+            steps.push((from, to));
+            match (from, to) {
+                (2, 1) => Err("no reply".to_string()),
+                _ => Ok(()),
+            }
+        });
+        assert_eq!(outcome, Err("step 2/3 (ID 2 → 1) failed: no reply; previous steps rolled back".to_string()));
+        assert_eq!(steps, vec![(1, 252), (2, 1), (252, 1)]);
+
+        let mut steps = Vec::new();
+        let outcome = run_swap(&plan, |from, to| {
+            steps.push((from, to));
+            Ok(())
+        });
+        assert_eq!(outcome, Ok(()));
+        assert_eq!(steps, plan.to_vec());
+    }
+
+    #[test]
+    fn ghost_ids_are_masked_until_the_power_cycle() {
+        let mut pending = PendingIdChanges::default();
+        pending.insert(1, 5);
+        assert_eq!(pending.power_cycle_required(5), Some(1));
+        assert_eq!(pending.merge_scan(&[1, 5, 7]), vec![5, 7]);
+        assert!(pending.is_masked(1));
+
+        // Servo redémarré : l'ancien ID ne répond plus, le changement est oublié
+        assert_eq!(pending.merge_scan(&[5, 7]), vec![5, 7]);
+        assert!(pending.is_empty());
+    }
+}
@@ -0,0 +1,84 @@
+//! Identité d'un servo détecté : numéro de modèle et version du firmware, lus au scan pour
+//! distinguer les révisions d'un même parc (« ID 3 · ST3215 · FW 2.54 »).
+
+use crate::registers::{self, Register, RegisterPort};
+
+/// Modèles connus, par numéro de modèle
+const MODELS: &[(u16, &str)] = &[(777, "ST3215"), (2825, "ST3250"), (1284, "SCS0009"), (11272, "SM8512BL")];
+
+fn register(name: &str) -> &'static Register {
+    registers::find_register(name).expect("register in table")
+}
+
+/// Registres d'information d'un servo ; `None` pour une lecture en échec
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ServoIdentity {
+    pub model: Option<u16>,
+    /// (majeure, mineure)
+    pub firmware: Option<(u8, u8)>,
+}
+
+impl ServoIdentity {
+    /// Chaque lecture est indépendante : un registre illisible laisse seulement son champ vide
+    pub fn read(bus: &mut RegisterPort, id: u8) -> Self {
+        let model = bus.read(id, register("Model")).ok().map(|v| v as u16);
+        let major = bus.read(id, register("Firmware Major")).ok();
+        let minor = bus.read(id, register("Firmware Minor")).ok();
+        let firmware = major.zip(minor).map(|(major, minor)| (major as u8, minor as u8));
+        Self { model, firmware }
+    }
+
+    pub fn model_label(&self) -> String {
+        match self.model {
+            Some(number) => match MODELS.iter().find(|(known, _)| *known == number) {
+                Some((_, name)) => name.to_string(),
+                None => format!("model {}", number),
+            },
+            None => "unknown model".to_string(),
+        }
+    }
+
+    pub fn firmware_label(&self) -> String {
+        match self.firmware {
+            Some((major, minor)) => format!("FW {}.{}", major, minor),
+            None => "FW unknown".to_string(),
+        }
+    }
+
+    /// « ID 3 · ST3215 · FW 2.54 », ou « ID 3 · unknown » si rien n'a pu être lu
+    pub fn describe(&self, id: u8) -> String {
+        if self.model.is_none() && self.firmware.is_none() {
+            return format!("ID {} · unknown", id);
+        }
+        format!("ID {} · {} · {}", id, self.model_label(), self.firmware_label())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{MockBackend, MockServo};
+
+    #[test]
+    fn identity_is_read_from_the_info_registers() {
+        let mock = MockBackend::new().with_servo(3, MockServo::default());
+        for (address, value) in [(0, 3), (1, 10), (3, 0x09), (4, 0x03)] {
+            mock.set_register(3, address, value);
+        }
+        let identity = ServoIdentity::read(&mut RegisterPort::new(&mock), 3);
+        assert_eq!(identity, ServoIdentity { model: Some(777), firmware: Some((3, 10)) });
+        assert_eq!(identity.describe(3), "ID 3 · ST3215 · FW 3.10");
+
+        // Servo absent : rien n'est lu
+        let missing = ServoIdentity::read(&mut RegisterPort::new(&mock), 4);
+        assert_eq!(missing, ServoIdentity::default());
+        assert_eq!(missing.describe(4), "ID 4 · unknown");
+    }
+
+    #[test]
+    fn unknown_models_and_partial_readings_are_labelled() {
+        let identity = ServoIdentity { model: Some(1234), firmware: None };
+        assert_eq!(identity.describe(2), "ID 2 · model 1234 · FW unknown");
+        assert_eq!(ServoIdentity { model: None, firmware: Some((2, 54)) }.describe(2), "ID 2 · unknown model · FW 2.54");
+    }
+}
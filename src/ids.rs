@@ -0,0 +1,215 @@
+//! Garde-fous sur les ID saisis : 254 est l'ID de diffusion, exécuté par tous les servos du bus.
+
+use serde::{Deserialize, Serialize};
+use st3215::BROADCAST_ID;
+
+/// Plus grand ID attribuable à un servo
+pub const MAX_SERVO_ID: u8 = 253;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Access {
+    /// Commande ou lecture : diffusion possible sur accord explicite
+    Command,
+    /// Changement d'ID ou écriture EEPROM : jamais en diffusion
+    Eeprom,
+}
+
+/// Vérifie l'ID visé par une opération ; `broadcast` est l'accord explicite de l'utilisateur
+pub fn check_target(id: u8, access: Access, broadcast: bool) -> Result<u8, String> {
+    match id {
+        id if id <= MAX_SERVO_ID => Ok(id),
+        BROADCAST_ID if access == Access::Eeprom => {
+            Err("ID 254 is the broadcast ID: every servo on the bus would be rewritten".to_string())
+        }
+        BROADCAST_ID if broadcast => Ok(id),
+        BROADCAST_ID => Err(
+            "ID 254 is the broadcast ID: every servo on the bus would execute it (explicit broadcast confirmation required)"
+                .to_string(),
+        ),
+        _ => Err(format!("ID {} is reserved (valid IDs: 0-{})", id, MAX_SERVO_ID)),
+    }
+}
+
+/// Nouvel ID attribué à un servo : jamais l'ID de diffusion ni 255
+pub fn check_new_id(id: u8) -> Result<u8, String> {
+    if id > MAX_SERVO_ID {
+        return Err(format!("new ID {} is reserved (valid IDs: 0-{})", id, MAX_SERVO_ID));
+    }
+    Ok(id)
+}
+
+/// Nouvel ID confronté au dernier scan : deux servos sur le même ID répondent ensemble et ne se
+/// séparent qu'en les rebranchant un par un. `force` est l'accord explicite de l'utilisateur.
+pub fn check_free_id(old_id: u8, new_id: u8, detected: &[u8], force: bool) -> Result<u8, String> {
+    let new_id = check_new_id(new_id)?;
+    if new_id == old_id {
+        return Err(format!("servo already has ID {}", new_id));
+    }
+    if detected.contains(&new_id) && !force {
+        return Err(format!("ID {} is already used by another servo on the bus (override required)", new_id));
+    }
+    Ok(new_id)
+}
+
+/// Suite d'ID à attribuer, "10,11,20,21" : IDs attribuables, sans répétition
+pub fn parse_id_list(input: &str) -> Result<Vec<u8>, String> {
+    let mut list = Vec::new();
+    for raw in input.split(',').map(str::trim).filter(|raw| !raw.is_empty()) {
+        let id = raw.parse::<u8>().map_err(|_| format!("ID list '{}': invalid ID '{}'", input, raw))?;
+        let id = check_new_id(id)?;
+        if list.contains(&id) {
+            return Err(format!("ID list '{}': ID {} appears twice", input, id));
+        }
+        list.push(id);
+    }
+    if list.is_empty() {
+        return Err(format!("ID list '{}' is empty", input));
+    }
+    Ok(list)
+}
+
+/// Lectures de position rapprochées pour le contrôle de doublon
+pub const DUPLICATE_PROBES: usize = 6;
+/// Lectures en échec (sur `DUPLICATE_PROBES`) au-delà desquelles l'ID est suspect
+const DUPLICATE_FAILURES: usize = 2;
+/// Écart (ticks) entre lectures rapprochées au-delà duquel deux servos répondent tour à tour
+const DUPLICATE_SPREAD: u16 = 20;
+
+/// Servos neufs, tous livrés en ID 1 : deux servos sur le même ID répondent ensemble, leurs trames
+/// se chevauchent (lectures en échec) ou alternent entre deux positions.
+/// `reads` : lectures de position enchaînées sur un même ID.
+pub fn looks_duplicated(reads: &[Option<u16>]) -> bool {
+    let failures = reads.iter().filter(|r| r.is_none()).count();
+    let positions = reads.iter().flatten();
+    let spread = positions.clone().max().zip(positions.min()).map_or(0, |(max, min)| max - min);
+    failures >= DUPLICATE_FAILURES || spread > DUPLICATE_SPREAD
+}
+
+/// Enchaîne `DUPLICATE_PROBES` lectures de position d'un même ID (`read`) et les juge
+pub fn probe_duplicate(mut read: impl FnMut() -> Option<u16>) -> bool {
+    let reads: Vec<Option<u16>> = (0..DUPLICATE_PROBES).map(|_| read()).collect();
+    looks_duplicated(&reads)
+}
+
+/// Plage d'ID balayée à la recherche de servos (bornes incluses) ; « 1-15 » dans la configuration
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ScanRange {
+    pub start: u8,
+    pub end: u8,
+}
+
+impl Default for ScanRange {
+    fn default() -> Self {
+        Self { start: 1, end: 15 }
+    }
+}
+
+impl ScanRange {
+    /// Bornes ramenées à 0..=253 ; une plage inversée est refusée
+    pub fn new(start: u8, end: u8) -> Result<Self, String> {
+        let (start, end) = (start.min(MAX_SERVO_ID), end.min(MAX_SERVO_ID));
+        if start > end {
+            return Err(format!("scan range {}-{} is inverted", start, end));
+        }
+        Ok(Self { start, end })
+    }
+
+    pub fn ids(self) -> std::ops::RangeInclusive<u8> {
+        self.start..=self.end
+    }
+}
+
+impl std::fmt::Display for ScanRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}-{}", self.start, self.end)
+    }
+}
+
+/// Accepte "1-24"
+impl std::str::FromStr for ScanRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (start, end) = s.split_once('-').ok_or(format!("scan range '{}': expected START-END", s))?;
+        let parse = |raw: &str| raw.trim().parse::<u8>().map_err(|_| format!("scan range '{}': invalid ID '{}'", s, raw));
+        Self::new(parse(start)?, parse(end)?)
+    }
+}
+
+impl TryFrom<String> for ScanRange {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        s.parse()
+    }
+}
+
+impl From<ScanRange> for String {
+    fn from(range: ScanRange) -> Self {
+        range.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn broadcast_requires_confirmation_and_never_for_eeprom() {
+        assert_eq!(check_target(12, Access::Eeprom, false), Ok(12));
+        assert!(check_target(BROADCAST_ID, Access::Command, false).is_err());
+        assert_eq!(check_target(BROADCAST_ID, Access::Command, true), Ok(BROADCAST_ID));
+        assert!(check_target(BROADCAST_ID, Access::Eeprom, true).is_err());
+        assert!(check_target(255, Access::Command, true).is_err());
+    }
+
+    #[test]
+    fn free_id_checks() {
+        assert_eq!(check_free_id(1, 5, &[1, 2], false), Ok(5));
+        assert!(check_free_id(1, 1, &[1], true).is_err());
+        assert!(check_free_id(1, 2, &[1, 2], false).is_err());
+        assert_eq!(check_free_id(1, 2, &[1, 2], true), Ok(2));
+        assert!(check_free_id(1, BROADCAST_ID, &[], true).is_err());
+    }
+
+    #[test]
+    fn id_list_parsing() {
+        assert_eq!(parse_id_list("10, 11,20,,21"), Ok(vec![10, 11, 20, 21]));
+        assert!(parse_id_list("10,10").is_err());
+        assert!(parse_id_list("10,x").is_err());
+        assert!(parse_id_list("254").is_err());
+        assert!(parse_id_list(" , ").is_err());
+    }
+
+    #[test]
+    fn duplicate_detection() {
+        assert!(!looks_duplicated(&[Some(2048); DUPLICATE_PROBES]));
+        assert!(!looks_duplicated(&[Some(2048), None, Some(2050), Some(2049), Some(2048), Some(2047)]));
+        assert!(looks_duplicated(&[Some(2048), None, Some(2050), None, Some(2048), Some(2047)]));
+        assert!(looks_duplicated(&[Some(2048), Some(1000), Some(2048), Some(1000), Some(2048), Some(1000)]));
+
+        let mut reads = [Some(500), Some(3000)].into_iter().cycle();
+        assert!(probe_duplicate(|| reads.next().unwrap()));
+    }
+
+    #[test]
+    fn scan_range_parsing() {
+        assert_eq!("1-24".parse(), Ok(ScanRange { start: 1, end: 24 }));
+        assert_eq!(" 3 - 7".parse(), Ok(ScanRange { start: 3, end: 7 }));
+        assert_eq!(ScanRange::new(10, 255), Ok(ScanRange { start: 10, end: MAX_SERVO_ID }));
+        assert!("7-3".parse::<ScanRange>().is_err());
+        assert!("12".parse::<ScanRange>().is_err());
+        assert!("a-3".parse::<ScanRange>().is_err());
+        assert_eq!(ScanRange::default().to_string(), "1-15");
+        assert_eq!(ScanRange::new(2, 4).unwrap().ids().collect::<Vec<_>>(), vec![2, 3, 4]);
+    }
+
+    #[test]
+    fn scan_range_serializes_as_text() {
+        let range = ScanRange::new(1, 24).unwrap();
+        assert_eq!(serde_json::to_string(&range).unwrap(), "\"1-24\"");
+        assert_eq!(serde_json::from_str::<ScanRange>("\"1-24\"").unwrap(), range);
+        assert!(serde_json::from_str::<ScanRange>("\"24-1\"").is_err());
+    }
+}
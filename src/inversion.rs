@@ -0,0 +1,241 @@
+//! Sens inversé par servo, pour les articulations montées en miroir (jambe gauche et jambe
+//! droite) : la même position logique donne des rotations opposées.
+//!
+//! Une position logique est le miroir de la position brute autour de 2048. `InvertedBackend`
+//! convertit entre les deux juste sous le pilote : interfaces, butées logicielles, poses et
+//! historiques ne voient que des positions logiques, et le sens de la vitesse et de la charge
+//! suit. Les accès directs aux registres (sync write, charge complète, EEPROM) restent en
+//! valeurs brutes et passent par `Inversions` au besoin.
+
+use crate::backend::ServoBackend;
+use crate::units::{CENTER_TICKS, MAX_TICKS};
+use std::collections::BTreeSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Position miroir autour de `CENTER_TICKS` ; 0, qui n'a pas de miroir sur un tour, donne 4095
+pub fn mirror(position: u16) -> u16 {
+    (2 * CENTER_TICKS).saturating_sub(position).min(MAX_TICKS)
+}
+
+/// IDs inversés, partagés entre le bus et l'interface ; le clone suit les changements
+#[derive(Clone, Debug, Default)]
+pub struct Inversions {
+    ids: Arc<Mutex<BTreeSet<u8>>>,
+}
+
+impl Inversions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, id: u8, inverted: bool) {
+        let mut ids = self.ids.lock().unwrap();
+        match inverted {
+            true => ids.insert(id),
+            false => ids.remove(&id),
+        };
+    }
+
+    pub fn is_inverted(&self, id: u8) -> bool {
+        self.ids.lock().unwrap().contains(&id)
+    }
+
+    /// Position brute d'une position logique, et inversement (le miroir est sa propre réciproque)
+    pub fn position(&self, id: u8, position: u16) -> u16 {
+        if self.is_inverted(id) { mirror(position) } else { position }
+    }
+
+    /// Vitesse ou charge signée, dans un sens comme dans l'autre
+    pub fn signed<T: std::ops::Neg<Output = T>>(&self, id: u8, value: T) -> T {
+        if self.is_inverted(id) { -value } else { value }
+    }
+
+    /// Consignes (id, position, vitesse) converties en positions brutes, pour un sync write
+    pub fn raw_targets(&self, targets: &[(u8, u16, u16)]) -> Vec<(u8, u16, u16)> {
+        targets.iter().map(|&(id, position, speed)| (id, self.position(id, position), speed)).collect()
+    }
+}
+
+/// Correspondance entre position logique et rotation réelle d'un servo : sens, et offset matériel
+/// (registre Position Offset) quand il a été lu
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Mapping {
+    pub inverted: bool,
+    pub offset: Option<i32>,
+}
+
+impl Mapping {
+    /// Écarts avec la destination d'une copie de position : la même position logique n'y donne pas
+    /// le même angle. Un offset non lu n'est pas comparé.
+    pub fn differences(&self, destination: &Mapping) -> Vec<String> {
+        let direction = |inverted| if inverted { "inverted" } else { "normal" };
+        let mut differences = Vec::new();
+        if self.inverted != destination.inverted {
+            differences.push(format!(
+                "direction differs: source {}, destination {}",
+                direction(self.inverted),
+                direction(destination.inverted)
+            ));
+        }
+        if let (Some(source), Some(dest)) = (self.offset, destination.offset) {
+            if source != dest {
+                differences.push(format!("Position Offset differs: source {}, destination {}", source, dest));
+            }
+        }
+        differences
+    }
+}
+
+pub struct InvertedBackend {
+    inner: Box<dyn ServoBackend>,
+    inversions: Inversions,
+}
+
+impl InvertedBackend {
+    pub fn new(inner: Box<dyn ServoBackend>, inversions: Inversions) -> Self {
+        Self { inner, inversions }
+    }
+}
+
+impl ServoBackend for InvertedBackend {
+    fn ping_servo(&self, id: u8) -> bool {
+        self.inner.ping_servo(id)
+    }
+
+    fn list_servos(&self) -> Vec<u8> {
+        self.inner.list_servos()
+    }
+
+    fn read_position(&self, id: u8) -> Option<u16> {
+        self.inner.read_position(id).map(|position| self.inversions.position(id, position))
+    }
+
+    fn read_temperature(&self, id: u8) -> Option<u8> {
+        self.inner.read_temperature(id)
+    }
+
+    fn read_voltage(&self, id: u8) -> Option<f32> {
+        self.inner.read_voltage(id)
+    }
+
+    fn read_current(&self, id: u8) -> Option<f32> {
+        self.inner.read_current(id)
+    }
+
+    fn read_speed(&self, id: u8) -> Option<i16> {
+        self.inner.read_speed(id).map(|speed| self.inversions.signed(id, speed))
+    }
+
+    fn read_load(&self, id: u8) -> Option<f32> {
+        self.inner.read_load(id).map(|load| self.inversions.signed(id, load))
+    }
+
+    fn read_mode(&self, id: u8) -> Option<u8> {
+        self.inner.read_mode(id)
+    }
+
+    fn is_moving(&self, id: u8) -> Option<bool> {
+        self.inner.is_moving(id)
+    }
+
+    fn move_to(&self, id: u8, position: u16, speed: u16, acceleration: u8, wait: bool) -> Option<bool> {
+        self.inner.move_to(id, self.inversions.position(id, position), speed, acceleration, wait)
+    }
+
+    fn write_position(&self, id: u8, position: u16) -> Option<bool> {
+        self.inner.write_position(id, self.inversions.position(id, position))
+    }
+
+    fn enable_torque(&self, id: u8) -> Result<(), String> {
+        self.inner.enable_torque(id)
+    }
+
+    fn disable_torque(&self, id: u8) -> Result<(), String> {
+        self.inner.disable_torque(id)
+    }
+
+    /// Roue : le sens de rotation suit l'inversion
+    fn rotate(&self, id: u8, speed: i16) -> Result<(), String> {
+        self.inner.rotate(id, self.inversions.signed(id, speed))
+    }
+
+    fn set_mode(&self, id: u8, mode: u8) -> Result<(), String> {
+        self.inner.set_mode(id, mode)
+    }
+
+    fn change_id(&self, id: u8, new_id: u8) -> Result<(), String> {
+        self.inner.change_id(id, new_id)
+    }
+
+    // Accès directs en valeurs brutes
+    fn read_register(&self, id: u8, address: u8, size: u8) -> Result<Vec<u8>, String> {
+        self.inner.read_register(id, address, size)
+    }
+
+    fn write_register(&self, id: u8, address: u8, data: &[u8]) -> Result<(), String> {
+        self.inner.write_register(id, address, data)
+    }
+
+    fn send_raw(&self, frame: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        self.inner.send_raw(frame)
+    }
+
+    fn broadcast_ping(&self, window: Duration) -> Result<Vec<u8>, String> {
+        self.inner.broadcast_ping(window)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendCall, MockBackend, MockServo};
+
+    #[test]
+    fn mirror_is_its_own_inverse() {
+        assert_eq!(mirror(CENTER_TICKS), CENTER_TICKS);
+        assert_eq!(mirror(1000), 3096);
+        assert_eq!(mirror(mirror(1000)), 1000);
+        assert_eq!(mirror(0), MAX_TICKS);
+    }
+
+    #[test]
+    fn mapping_differences() {
+        let normal = Mapping { inverted: false, offset: Some(0) };
+        assert!(normal.differences(&normal).is_empty());
+        let inverted = Mapping { inverted: true, ..normal };
+        assert_eq!(normal.differences(&inverted), ["direction differs: source normal, destination inverted"]);
+        let shifted = Mapping { offset: Some(-12), ..normal };
+        assert_eq!(normal.differences(&shifted), ["Position Offset differs: source 0, destination -12"]);
+        // Offset inconnu d'un côté : pas de comparaison
+        let unread = Mapping { offset: None, ..normal };
+        assert!(unread.differences(&shifted).is_empty());
+    }
+
+
+    #[test]
+    fn inverted_servos_see_logical_positions() {
+        let mock = MockBackend::new()
+            .with_servo(1, MockServo { position: 1000, speed: 300, load: 20.0, ..MockServo::default() })
+            .with_servo(2, MockServo { position: 1000, speed: 300, ..MockServo::default() });
+        let inversions = Inversions::new();
+        inversions.set(1, true);
+        let bus = InvertedBackend::new(Box::new(mock.clone()), inversions.clone());
+
+        assert_eq!((bus.read_position(1), bus.read_speed(1), bus.read_load(1)), (Some(3096), Some(-300), Some(-20.0)));
+        assert_eq!((bus.read_position(2), bus.read_speed(2)), (Some(1000), Some(300)));
+        bus.move_to(1, 2548, 100, 0, false);
+        bus.rotate(1, 500).unwrap();
+        assert_eq!(
+            mock.take_calls(),
+            vec![BackendCall::MoveTo { id: 1, position: 1548, speed: 100, acceleration: 0 }, BackendCall::Rotate { id: 1, speed: -500 }]
+        );
+
+        // Le clone partagé avec l'interface suit les changements
+        inversions.set(1, false);
+        assert_eq!(bus.read_position(1), Some(1548));
+        assert_eq!(inversions.raw_targets(&[(1, 1000, 50)]), vec![(1, 1000, 50)]);
+        inversions.set(2, true);
+        assert_eq!(inversions.raw_targets(&[(1, 1000, 50), (2, 1000, 50)]), vec![(1, 1000, 50), (2, 3096, 50)]);
+    }
+}
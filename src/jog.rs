@@ -0,0 +1,127 @@
+//! Jog au clavier : les flèches (ou -/+) déplacent la consigne du servo sélectionné d'un pas
+//! réglable (1, 10 ou 100 ticks), dans ses butées.
+//!
+//! Une touche tenue fait un pas à l'appui puis, passé `REPEAT_DELAY`, un pas tous les
+//! `REPEAT_INTERVAL`, quelle que soit la répétition du clavier : la consigne avance régulièrement
+//! au lieu de suivre les rafales d'événements.
+
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+
+/// Attente avant la répétition d'une touche tenue
+pub const REPEAT_DELAY: Duration = Duration::from_millis(400);
+/// Un pas par intervalle ensuite
+pub const REPEAT_INTERVAL: Duration = Duration::from_millis(80);
+/// Pas rattrapés au plus d'un coup après une image lente : pas de saut de consigne
+const MAX_CATCH_UP: u32 = 3;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JogStep {
+    Fine,
+    #[default]
+    Medium,
+    Coarse,
+}
+
+impl JogStep {
+    pub fn ticks(self) -> u16 {
+        match self {
+            JogStep::Fine => 1,
+            JogStep::Medium => 10,
+            JogStep::Coarse => 100,
+        }
+    }
+
+    /// Pas suivant, en boucle
+    pub fn next(self) -> Self {
+        match self {
+            JogStep::Fine => JogStep::Medium,
+            JogStep::Medium => JogStep::Coarse,
+            JogStep::Coarse => JogStep::Fine,
+        }
+    }
+}
+
+/// Répétition d'une touche de jog tenue
+#[derive(Clone, Copy, Debug, Default)]
+pub struct KeyRepeat {
+    // Sens tenu, instant de l'appui et pas déjà faits
+    held: Option<(i8, Instant, u32)>,
+}
+
+impl KeyRepeat {
+    /// Pas signés à faire maintenant pour le sens tenu (-1, 0 ou 1) ; changer de sens vaut un
+    /// nouvel appui
+    pub fn update(&mut self, direction: i8, now: Instant) -> i32 {
+        let direction = direction.signum();
+        match self.held {
+            _ if direction == 0 => {
+                self.held = None;
+                0
+            }
+            Some((held, since, done)) if held == direction => {
+                let elapsed = now.saturating_duration_since(since);
+                let due = match elapsed.checked_sub(REPEAT_DELAY) {
+                    Some(repeating) => 2 + (repeating.as_millis() / REPEAT_INTERVAL.as_millis()) as u32,
+                    None => 1,
+                };
+                let steps = due.saturating_sub(done).min(MAX_CATCH_UP);
+                self.held = Some((held, since, due.max(done)));
+                i32::from(direction) * steps as i32
+            }
+            _ => {
+                self.held = Some((direction, now, 1));
+                i32::from(direction)
+            }
+        }
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.held.is_some()
+    }
+}
+
+/// Consigne déplacée de `steps` pas, ramenée dans `range`
+pub fn jog_target(target: u16, steps: i32, step: JogStep, range: RangeInclusive<u16>) -> u16 {
+    nudge(target, steps * i32::from(step.ticks()), range)
+}
+
+/// Consigne déplacée de `delta` ticks, ramenée dans `range`
+pub fn nudge(target: u16, delta: i32, range: RangeInclusive<u16>) -> u16 {
+    (i32::from(target) + delta).clamp(i32::from(*range.start()), i32::from(*range.end())) as u16
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn held_key_steps_once_then_repeats() {
+        let mut repeat = KeyRepeat::default();
+        let start = Instant::now();
+        assert_eq!(repeat.update(1, start), 1);
+        assert_eq!(repeat.update(1, start + Duration::from_millis(300)), 0);
+        // Fin du délai : premier pas répété, puis un pas par intervalle
+        assert_eq!(repeat.update(1, start + REPEAT_DELAY), 1);
+        assert_eq!(repeat.update(1, start + REPEAT_DELAY + REPEAT_INTERVAL), 1);
+        assert_eq!(repeat.update(1, start + REPEAT_DELAY + REPEAT_INTERVAL + Duration::from_millis(10)), 0);
+        // Image lente : rattrapage limité
+        assert_eq!(repeat.update(1, start + REPEAT_DELAY + REPEAT_INTERVAL * 20), MAX_CATCH_UP as i32);
+
+        // Changement de sens : nouvel appui
+        assert_eq!(repeat.update(-1, start + Duration::from_secs(5)), -1);
+        assert_eq!(repeat.update(0, start + Duration::from_secs(5)), 0);
+        assert!(!repeat.is_held());
+    }
+
+    #[test]
+    fn jog_stays_within_the_range() {
+        assert_eq!(jog_target(2048, 3, JogStep::Medium, 0..=4095), 2078);
+        assert_eq!(jog_target(1050, -2, JogStep::Coarse, 1000..=3000), 1000);
+        assert_eq!(nudge(2990, 50, 1000..=3000), 3000);
+        assert_eq!(JogStep::Coarse.next(), JogStep::Fine);
+        assert_eq!(JogStep::default().ticks(), 10);
+    }
+}
@@ -0,0 +1,425 @@
+//! Séquences d'images clés sur plusieurs servos : chaque image donne, à un instant de la
+//! séquence, la position de certains servos. Entre deux images, la consigne est interpolée
+//! linéairement à chaque cycle du worker.
+//!
+//! En lecture par étapes, chaque image est envoyée une seule fois, à l'instant de la précédente,
+//! comme un mouvement de groupe synchronisé : ses servos l'atteignent ensemble en `duration_s`
+//! (par défaut l'écart avec l'image précédente ; 0 = rythme de l'axe le plus lent). Une première
+//! image à 0 s part en même temps que la deuxième, qui la remplace : pour rejoindre d'abord une
+//! pose de départ, lui donner un instant non nul.
+//!
+//! ```json
+//! { "name": "salut", "keyframes": [
+//!     { "time_s": 0.0, "positions": { "1": 2048, "2": 2048 } },
+//!     { "time_s": 1.5, "positions": { "1": 1500, "2": 2600 }, "speed": 800 },
+//!     { "time_s": 3.0, "positions": { "1": 2048 }, "duration_s": 1.0 }
+//! ] }
+//! ```
+//!
+//! Le même contenu est accepté en TOML (extension `.toml`).
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Keyframe {
+    /// Instant de l'image depuis le début de la séquence
+    pub time_s: f64,
+    /// Position (ticks) par ID ; un servo absent d'une image est interpolé entre ses voisines
+    pub positions: BTreeMap<u8, u16>,
+    /// Vitesse max pour rejoindre cette image (absente = vitesse max du servo)
+    #[serde(default)]
+    pub speed: Option<u16>,
+    /// Durée du mouvement vers cette image en lecture par étapes (absente = écart avec l'image
+    /// précédente)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration_s: Option<f64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct KeyframeSequence {
+    #[serde(default)]
+    pub name: String,
+    pub keyframes: Vec<Keyframe>,
+}
+
+/// Consigne d'un servo à un instant de la séquence
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyframeTarget {
+    pub id: u8,
+    pub position: u16,
+    pub speed: Option<u16>,
+}
+
+/// Étape de la lecture par étapes : une image à atteindre par tous ses servos en même temps
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyframeStep {
+    pub targets: Vec<KeyframeTarget>,
+    /// Durée minimale du mouvement (zéro = rythme de l'axe le plus lent)
+    pub duration: Duration,
+}
+
+impl KeyframeSequence {
+    /// Lecture JSON, ou TOML selon l'extension ; le contenu est vérifié
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let sequence: KeyframeSequence = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?
+        } else {
+            serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?
+        };
+        sequence.check().map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(sequence)
+    }
+
+    /// Écriture JSON, ou TOML selon l'extension
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let text = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::to_string_pretty(self).map_err(|e| e.to_string())?
+        } else {
+            serde_json::to_string_pretty(self).map_err(|e| e.to_string())?
+        };
+        std::fs::write(path, text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Images comprises entre `start_s` et `end_s`, décalées pour que la première soit à 0
+    pub fn trimmed(&self, start_s: f64, end_s: f64) -> Self {
+        let kept: Vec<&Keyframe> = self.keyframes.iter().filter(|k| k.time_s >= start_s && k.time_s <= end_s).collect();
+        let offset = kept.first().map_or(0.0, |k| k.time_s);
+        let keyframes = kept.into_iter().map(|k| Keyframe { time_s: k.time_s - offset, ..k.clone() }).collect();
+        Self { name: self.name.clone(), keyframes }
+    }
+
+    /// Au moins une image, instants positifs et croissants
+    pub fn check(&self) -> Result<(), String> {
+        if self.keyframes.is_empty() {
+            return Err("sequence has no keyframes".to_string());
+        }
+        let mut previous = 0.0;
+        for (i, keyframe) in self.keyframes.iter().enumerate() {
+            if !keyframe.time_s.is_finite() || keyframe.time_s < previous {
+                return Err(format!("keyframe {}: time_s {} is not after the previous keyframe", i + 1, keyframe.time_s));
+            }
+            if keyframe.positions.is_empty() {
+                return Err(format!("keyframe {}: no positions", i + 1));
+            }
+            if keyframe.duration_s.is_some_and(|d| !d.is_finite() || d < 0.0) {
+                return Err(format!("keyframe {}: duration_s must be zero or more", i + 1));
+            }
+            previous = keyframe.time_s;
+        }
+        Ok(())
+    }
+
+    /// Durée totale : instant de la dernière image
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.keyframes.last().map_or(0.0, |k| k.time_s.max(0.0)))
+    }
+
+    /// Servos cités dans au moins une image
+    pub fn ids(&self) -> BTreeSet<u8> {
+        self.keyframes.iter().flat_map(|k| k.positions.keys().copied()).collect()
+    }
+
+    /// Consignes à l'instant `t` : avant sa première image (ou après sa dernière) un servo
+    /// garde la position de celle-ci ; la vitesse est celle de l'image suivante
+    pub fn sample(&self, t: f64) -> Vec<KeyframeTarget> {
+        self.ids()
+            .into_iter()
+            .filter_map(|id| {
+                let frames: Vec<(&Keyframe, u16)> =
+                    self.keyframes.iter().filter_map(|k| k.positions.get(&id).map(|&pos| (k, pos))).collect();
+                let next = frames.iter().position(|(k, _)| k.time_s > t);
+                let (position, speed) = match next {
+                    None => frames.last().map(|(k, pos)| (*pos, k.speed))?,
+                    Some(0) => (frames[0].1, frames[0].0.speed),
+                    Some(i) => {
+                        let ((before, from), (after, to)) = (frames[i - 1], frames[i]);
+                        let ratio = (t - before.time_s) / (after.time_s - before.time_s);
+                        let position = f64::from(from) + (f64::from(to) - f64::from(from)) * ratio;
+                        (position.round() as u16, after.speed)
+                    }
+                };
+                Some(KeyframeTarget { id, position, speed })
+            })
+            .collect()
+    }
+}
+
+/// Lecture en cours : horloge de séquence qui s'arrête pendant la pause
+#[derive(Clone, Debug)]
+pub struct Playback {
+    pub sequence: KeyframeSequence,
+    pub looped: bool,
+    /// Lecture par étapes (`step`) plutôt qu'interpolée (`tick`)
+    pub stepped: bool,
+    elapsed: Duration,
+    last_tick: Instant,
+    paused: bool,
+    finished: bool,
+    // Prochaine image à envoyer en lecture par étapes
+    next_step: usize,
+}
+
+impl Playback {
+    pub fn new(sequence: KeyframeSequence, looped: bool, stepped: bool, now: Instant) -> Self {
+        Self {
+            sequence,
+            looped,
+            stepped,
+            elapsed: Duration::ZERO,
+            last_tick: now,
+            paused: false,
+            finished: false,
+            next_step: 0,
+        }
+    }
+
+    pub fn set_paused(&mut self, paused: bool, now: Instant) {
+        if !paused {
+            self.last_tick = now;
+        }
+        self.paused = paused;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Avance l'horloge et retourne les consignes du cycle (aucune en pause) ;
+    /// `None` quand la séquence est terminée, sa dernière image ayant été envoyée
+    pub fn tick(&mut self, now: Instant) -> Option<Vec<KeyframeTarget>> {
+        if self.paused {
+            return Some(Vec::new());
+        }
+        if self.finished {
+            return None;
+        }
+        let duration = self.sequence.duration();
+        self.elapsed += now - self.last_tick;
+        self.last_tick = now;
+        if self.looped && !duration.is_zero() {
+            while self.elapsed >= duration {
+                self.elapsed -= duration;
+            }
+        } else if self.elapsed >= duration {
+            self.finished = !self.looped;
+        }
+        let t = self.elapsed.min(duration);
+        Some(self.sequence.sample(t.as_secs_f64()))
+    }
+
+    /// Lecture par étapes : avance l'horloge et retourne l'étape devenue due (aucune en pause).
+    /// Plusieurs images dues d'un coup, après un cycle lent, sont fusionnées : la plus récente
+    /// l'emporte par servo et donne la durée. `None` une fois la dernière image envoyée et son
+    /// instant passé.
+    pub fn step(&mut self, now: Instant) -> Option<Option<KeyframeStep>> {
+        if self.paused {
+            return Some(None);
+        }
+        if self.finished {
+            return None;
+        }
+        let duration = self.sequence.duration();
+        self.elapsed += now - self.last_tick;
+        self.last_tick = now;
+        let mut due: Option<(BTreeMap<u8, KeyframeTarget>, Duration)> = None;
+        loop {
+            let frames = &self.sequence.keyframes;
+            while let Some(frame) = frames.get(self.next_step) {
+                // Une image part à l'instant de la précédente (la première, au début)
+                let start = self.next_step.checked_sub(1).map_or(0.0, |i| frames[i].time_s);
+                if self.elapsed.as_secs_f64() < start {
+                    break;
+                }
+                let (targets, duration) = due.get_or_insert_with(Default::default);
+                for (&id, &position) in &frame.positions {
+                    targets.insert(id, KeyframeTarget { id, position, speed: frame.speed });
+                }
+                *duration = Duration::from_secs_f64(frame.duration_s.unwrap_or(frame.time_s - start).max(0.0));
+                self.next_step += 1;
+            }
+            if self.next_step < frames.len() || self.elapsed < duration {
+                break;
+            }
+            // Dernière image envoyée et atteinte : fin, ou retour à la première
+            if !self.looped || duration.is_zero() {
+                self.finished = !self.looped;
+                break;
+            }
+            self.elapsed -= duration;
+            self.next_step = 0;
+        }
+        Some(due.map(|(targets, duration)| KeyframeStep { targets: targets.into_values().collect(), duration }))
+    }
+
+    /// Avancement dans la séquence (0 à 1)
+    pub fn progress(&self) -> f32 {
+        let duration = self.sequence.duration();
+        if duration.is_zero() {
+            return 1.0;
+        }
+        (self.elapsed.as_secs_f64() / duration.as_secs_f64()).min(1.0) as f32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(time_s: f64, positions: &[(u8, u16)], speed: Option<u16>) -> Keyframe {
+        Keyframe { time_s, positions: positions.iter().copied().collect(), speed, duration_s: None }
+    }
+
+    fn two_servos() -> KeyframeSequence {
+        KeyframeSequence {
+            name: "salut".to_string(),
+            keyframes: vec![
+                frame(0.0, &[(1, 1000), (2, 2000)], None),
+                frame(1.0, &[(1, 2000)], Some(800)),
+                frame(2.0, &[(2, 3000)], None),
+            ],
+        }
+    }
+
+    #[test]
+    fn sample_interpolates_each_servo_between_its_own_keyframes() {
+        let sequence = two_servos();
+        assert_eq!(
+            sequence.sample(0.5),
+            vec![
+                KeyframeTarget { id: 1, position: 1500, speed: Some(800) },
+                KeyframeTarget { id: 2, position: 2250, speed: None },
+            ]
+        );
+        assert_eq!(
+            sequence.sample(5.0),
+            vec![
+                KeyframeTarget { id: 1, position: 2000, speed: Some(800) },
+                KeyframeTarget { id: 2, position: 3000, speed: None },
+            ]
+        );
+        assert_eq!(sequence.duration(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn check_rejects_empty_and_unordered_sequences() {
+        assert!(KeyframeSequence::default().check().is_err());
+        let mut sequence = two_servos();
+        sequence.keyframes[2].time_s = 0.5;
+        assert!(sequence.check().unwrap_err().starts_with("keyframe 3"));
+        let mut sequence = two_servos();
+        sequence.keyframes[1].positions.clear();
+        assert!(sequence.check().unwrap_err().contains("no positions"));
+    }
+
+    #[test]
+    fn playback_runs_to_the_last_keyframe_and_stops() {
+        let start = Instant::now();
+        let mut playback = Playback::new(two_servos(), false, false, start);
+        assert_eq!(playback.tick(start + Duration::from_millis(500)).unwrap()[0].position, 1500);
+
+        playback.set_paused(true, start + Duration::from_millis(500));
+        assert_eq!(playback.tick(start + Duration::from_secs(10)), Some(Vec::new()));
+        playback.set_paused(false, start + Duration::from_secs(10));
+        assert_eq!(playback.progress(), 0.25);
+
+        let last = playback.tick(start + Duration::from_secs(12)).unwrap();
+        assert_eq!(last, two_servos().sample(2.0));
+        assert_eq!(playback.tick(start + Duration::from_secs(13)), None);
+    }
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("init-servo-keyframes-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join(name)
+    }
+
+    #[test]
+    fn sequence_file_is_loaded_and_checked() {
+        let path = temp_file("salut.json");
+        std::fs::write(&path, r#"{ "name": "salut", "keyframes": [
+            { "time_s": 0.0, "positions": { "1": 2048 } },
+            { "time_s": 1.5, "positions": { "1": 1500 }, "speed": 800 }
+        ] }"#)
+        .unwrap();
+        let sequence = KeyframeSequence::load(&path).unwrap();
+        assert_eq!(sequence.keyframes[1], frame(1.5, &[(1, 1500)], Some(800)));
+
+        std::fs::write(&path, r#"{ "keyframes": [] }"#).unwrap();
+        assert!(KeyframeSequence::load(&path).unwrap_err().contains("no keyframes"));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn trimmed_recording_starts_at_zero() {
+        let trimmed = two_servos().trimmed(1.0, 2.0);
+        assert_eq!(trimmed.keyframes, vec![frame(0.0, &[(1, 2000)], Some(800)), frame(1.0, &[(2, 3000)], None)]);
+        assert_eq!(trimmed.name, "salut");
+        assert!(two_servos().trimmed(3.0, 4.0).keyframes.is_empty());
+    }
+
+    #[test]
+    fn recording_round_trips_as_json_and_toml() {
+        for name in ["teach.json", "teach.toml"] {
+            let path = temp_file(name);
+            two_servos().save(&path).unwrap();
+            assert_eq!(KeyframeSequence::load(&path), Ok(two_servos()), "{}", name);
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+
+
+    fn stepped() -> KeyframeSequence {
+        let mut last = frame(2.5, &[(2, 600)], None);
+        last.duration_s = Some(0.2);
+        KeyframeSequence { name: "pas".to_string(), keyframes: vec![frame(0.5, &[(1, 1000)], None), frame(1.5, &[(1, 2000), (2, 500)], Some(800)), last] }
+    }
+
+    fn step(targets: &[(u8, u16, Option<u16>)], duration_s: f64) -> Option<Option<KeyframeStep>> {
+        let targets = targets.iter().map(|&(id, position, speed)| KeyframeTarget { id, position, speed }).collect();
+        Some(Some(KeyframeStep { targets, duration: Duration::from_secs_f64(duration_s) }))
+    }
+
+    #[test]
+    fn each_step_leaves_at_the_previous_keyframe() {
+        let start = Instant::now();
+        let at = |s: f64| start + Duration::from_secs_f64(s);
+        let mut playback = Playback::new(stepped(), false, true, start);
+
+        assert_eq!(playback.step(at(0.0)), step(&[(1, 1000, None)], 0.5));
+        assert_eq!(playback.step(at(0.3)), Some(None));
+        assert_eq!(playback.step(at(0.5)), step(&[(1, 2000, Some(800)), (2, 500, Some(800))], 1.0));
+        // Durée donnée par l'image plutôt que l'écart
+        assert_eq!(playback.step(at(1.5)), step(&[(2, 600, None)], 0.2));
+        assert_eq!(playback.step(at(2.0)), Some(None));
+        // Dernière image atteinte : fin
+        assert_eq!(playback.step(at(2.5)), Some(None));
+        assert_eq!(playback.step(at(3.0)), None);
+    }
+
+    #[test]
+    fn overdue_steps_are_merged() {
+        let start = Instant::now();
+        let mut playback = Playback::new(stepped(), false, true, start);
+        let merged = playback.step(start + Duration::from_secs_f64(1.6));
+        assert_eq!(merged, step(&[(1, 2000, Some(800)), (2, 600, None)], 0.2));
+    }
+
+    #[test]
+    fn looped_steps_start_over_and_pause_holds_the_clock() {
+        let start = Instant::now();
+        let at = |s: f64| start + Duration::from_secs_f64(s);
+        let mut playback = Playback::new(stepped(), true, true, start);
+        playback.step(at(1.6));
+
+        playback.set_paused(true, at(1.6));
+        assert_eq!(playback.step(at(5.0)), Some(None));
+        // Reprise : la pause ne compte pas, puis retour à la première image
+        playback.set_paused(false, at(5.0));
+        assert_eq!(playback.step(at(5.5)), Some(None));
+        assert_eq!(playback.step(at(6.0)), step(&[(1, 1000, None)], 0.5));
+    }
+}
@@ -0,0 +1,198 @@
+//! Latence des commandes : horodatage de bout en bout et histogrammes par source et par type.
+
+use crate::response::{self, CommandId};
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+/// Nombre de seaux de l'histogramme (puissances de 2 en microsecondes, jusqu'à ~35 min)
+const BUCKETS: usize = 32;
+
+/// Commande étiquetée avec sa source, son instant de mise en file et son identifiant de corrélation
+pub struct Timed<T> {
+    pub id: CommandId,
+    pub source: &'static str,
+    pub enqueued: Instant,
+    pub command: T,
+}
+
+impl<T> Timed<T> {
+    pub fn new(source: &'static str, command: T) -> Self {
+        Self { id: response::next_id(), source, enqueued: Instant::now(), command }
+    }
+}
+
+/// Horodatages d'une commande : mise en file, sortie de file, écriture sur le bus
+#[derive(Clone, Copy, Debug)]
+pub struct CommandTiming {
+    pub enqueued: Instant,
+    pub dequeued: Instant,
+    pub written: Instant,
+}
+
+impl CommandTiming {
+    /// Attente dans la file du worker
+    pub fn queue_wait(&self) -> Duration {
+        self.dequeued.saturating_duration_since(self.enqueued)
+    }
+
+    /// Temps passé à écrire sur le bus
+    pub fn write_time(&self) -> Duration {
+        self.written.saturating_duration_since(self.dequeued)
+    }
+
+    pub fn total(&self) -> Duration {
+        self.written.saturating_duration_since(self.enqueued)
+    }
+}
+
+/// Histogramme logarithmique : le seau `i` couvre `[2^i, 2^(i+1))` µs (le seau 0 inclut 0)
+#[derive(Clone, Debug)]
+pub struct Histogram {
+    buckets: [u64; BUCKETS],
+    count: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self { buckets: [0; BUCKETS], count: 0 }
+    }
+}
+
+impl Histogram {
+    pub fn bucket_of(latency: Duration) -> usize {
+        let micros = latency.as_micros().max(1);
+        (micros.ilog2() as usize).min(BUCKETS - 1)
+    }
+
+    pub fn record(&mut self, latency: Duration) {
+        self.buckets[Self::bucket_of(latency)] += 1;
+        self.count += 1;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn buckets(&self) -> &[u64] {
+        &self.buckets
+    }
+
+    /// Borne haute du seau contenant le quantile `q` (0..=1)
+    pub fn percentile(&self, q: f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, &n) in self.buckets.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                return Some(Duration::from_micros(1 << (i + 1)));
+            }
+        }
+        None
+    }
+}
+
+/// Histogrammes de latence totale, par (source, type de commande)
+#[derive(Clone, Debug, Default)]
+pub struct LatencyStats {
+    histograms: BTreeMap<(&'static str, &'static str), Histogram>,
+}
+
+impl LatencyStats {
+    pub fn record(&mut self, source: &'static str, command: &'static str, timing: &CommandTiming) {
+        self.histograms.entry((source, command)).or_default().record(timing.total());
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = (&'static str, &'static str, &Histogram)> {
+        self.histograms.iter().map(|(&(source, command), h)| (source, command, h))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.histograms.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.histograms.clear();
+    }
+}
+
+/// Affichage court d'une latence : µs en dessous de la milliseconde
+pub fn format_latency(latency: Duration) -> String {
+    if latency < Duration::from_millis(1) {
+        format!("{} µs", latency.as_micros())
+    } else {
+        format!("{:.1} ms", latency.as_secs_f64() * 1000.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timing_splits_queue_and_write() {
+        let enqueued = Instant::now();
+        let timing = CommandTiming {
+            enqueued,
+            dequeued: enqueued + Duration::from_millis(3),
+            written: enqueued + Duration::from_millis(5),
+        };
+        assert_eq!(timing.queue_wait(), Duration::from_millis(3));
+        assert_eq!(timing.write_time(), Duration::from_millis(2));
+        assert_eq!(timing.total(), Duration::from_millis(5));
+    }
+
+    #[test]
+    fn buckets_are_powers_of_two() {
+        assert_eq!(Histogram::bucket_of(Duration::ZERO), 0);
+        assert_eq!(Histogram::bucket_of(Duration::from_micros(1)), 0);
+        assert_eq!(Histogram::bucket_of(Duration::from_micros(2)), 1);
+        assert_eq!(Histogram::bucket_of(Duration::from_micros(1023)), 9);
+        assert_eq!(Histogram::bucket_of(Duration::from_micros(1024)), 10);
+        assert_eq!(Histogram::bucket_of(Duration::from_secs(100_000)), BUCKETS - 1);
+    }
+
+    #[test]
+    fn percentiles_give_the_bucket_upper_bound() {
+        let mut histogram = Histogram::default();
+        assert_eq!(histogram.percentile(0.5), None);
+        for _ in 0..9 {
+            histogram.record(Duration::from_micros(100));
+        }
+        histogram.record(Duration::from_millis(5));
+        assert_eq!(histogram.count(), 10);
+        assert_eq!(histogram.percentile(0.5), Some(Duration::from_micros(128)));
+        assert_eq!(histogram.percentile(0.9), Some(Duration::from_micros(128)));
+        assert_eq!(histogram.percentile(0.99), Some(Duration::from_micros(8192)));
+        assert_eq!(histogram.percentile(0.0), Some(Duration::from_micros(128)));
+    }
+
+    #[test]
+    fn stats_are_kept_per_source_and_command() {
+        let now = Instant::now();
+        let timing = CommandTiming { enqueued: now, dequeued: now, written: now + Duration::from_micros(300) };
+        let mut stats = LatencyStats::default();
+        stats.record("card", "move", &timing);
+        stats.record("card", "move", &timing);
+        stats.record("pose", "group move", &timing);
+        let rows: Vec<(&str, &str, u64)> = stats.rows().map(|(source, command, h)| (source, command, h.count())).collect();
+        assert_eq!(rows, vec![("card", "move", 2), ("pose", "group move", 1)]);
+        stats.clear();
+        assert!(stats.is_empty());
+    }
+
+    #[test]
+    fn latency_formatting() {
+        assert_eq!(format_latency(Duration::from_micros(850)), "850 µs");
+        assert_eq!(format_latency(Duration::from_micros(2460)), "2.5 ms");
+    }
+
+
+    #[test]
+    fn each_timed_command_gets_its_own_id() {
+        let (first, second) = (Timed::new("keyboard", ()), Timed::new("keyboard", ()));
+        assert_ne!(first.id, second.id);
+    }
+}
@@ -0,0 +1,63 @@
+//! Briques communes aux binaires de contrôle des servomoteurs ST3215.
+
+pub mod events;
+pub mod grip;
+pub mod units;
+pub mod motion;
+pub mod packet;
+pub mod ports;
+pub mod assertions;
+pub mod sequence;
+pub mod snapshot;
+pub mod sound;
+pub mod oplock;
+pub mod reference;
+pub mod palette;
+pub mod idchange;
+pub mod ids;
+pub mod sim;
+pub mod config;
+pub mod theme;
+pub mod odometer;
+pub mod limits;
+pub mod latency;
+pub mod portlock;
+pub mod choreography;
+pub mod registers;
+pub mod fdimport;
+pub mod regdiff;
+pub mod derating;
+pub mod dryrun;
+pub mod report;
+pub mod warmup;
+pub mod overrides;
+pub mod validation;
+pub mod plugins;
+pub mod shell;
+pub mod coalesce;
+pub mod estop;
+pub mod telemetrylog;
+pub mod history;
+pub mod poses;
+pub mod keyframes;
+pub mod stall;
+pub mod hotplug;
+pub mod backup;
+pub mod calibration;
+pub mod tuning;
+pub mod mode;
+pub mod identity;
+pub mod worker;
+pub mod backend;
+pub mod response;
+pub mod retry;
+pub mod logging;
+pub mod recording;
+pub mod shutdown;
+pub mod groups;
+pub mod inversion;
+pub mod follow;
+pub mod teleop;
+pub mod gamepad;
+pub mod jog;
+pub mod movequeue;
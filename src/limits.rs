@@ -0,0 +1,264 @@
+//! Butées logicielles par servo, avec zone d'approche ralentie, butées matérielles écrites
+//! dans l'EEPROM du servo, et limite de couple.
+
+use crate::inversion;
+use crate::registers::{self, Register, RegisterPort};
+use crate::units::MAX_TICKS;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SoftLimits {
+    pub min: u16,
+    pub max: u16,
+    /// Ralentit les mouvements qui finissent près d'une butée
+    pub slowdown: bool,
+    /// Largeur (ticks) de la zone d'approche, de chaque côté
+    pub approach_zone: u16,
+    /// Vitesse plafond dans la zone d'approche
+    pub creep_speed: u16,
+}
+
+impl Default for SoftLimits {
+    fn default() -> Self {
+        Self { min: 0, max: MAX_TICKS, slowdown: false, approach_zone: 200, creep_speed: 150 }
+    }
+}
+
+impl SoftLimits {
+    pub fn clamp(&self, position: u16) -> u16 {
+        position.clamp(self.min, self.max.max(self.min))
+    }
+
+    /// Butées vues depuis l'autre sens, quand l'inversion d'un servo change ; la plage complète
+    /// reste complète (0 n'a pas de miroir)
+    pub fn mirrored(&self) -> Self {
+        let max = self.max.max(self.min);
+        let min = if max >= MAX_TICKS { 0 } else { inversion::mirror(max) };
+        Self { min, max: inversion::mirror(self.min), ..*self }
+    }
+
+    /// Fenêtre autorisée, pour borner les sliders
+    pub fn range(&self) -> RangeInclusive<u16> {
+        self.min..=self.max.max(self.min)
+    }
+
+    /// Fenêtre autorisée, restreinte aux butées matérielles du servo quand elles sont connues ;
+    /// des butées logicielles hors des butées matérielles laissent place à ces dernières
+    pub fn range_within(&self, hardware: Option<&AngleLimits>) -> RangeInclusive<u16> {
+        let Some(hardware) = hardware else { return self.range() };
+        let (soft, hard) = (self.range(), hardware.range());
+        let (min, max) = (*soft.start().max(hard.start()), *soft.end().min(hard.end()));
+        if min <= max { min..=max } else { hard }
+    }
+
+    /// Découpe un mouvement en segments (position, vitesse) : vitesse demandée jusqu'au bord
+    /// de la zone d'approche, puis vitesse d'approche jusqu'à la consigne.
+    pub fn plan(&self, current: u16, target: u16, speed: u16) -> Vec<(u16, u16)> {
+        let target = self.clamp(target);
+        let min_edge = self.min.saturating_add(self.approach_zone);
+        let max_edge = self.max.saturating_sub(self.approach_zone);
+        // Seuls les mouvements qui se dirigent vers une butée sont ralentis
+        let toward_min = target < current && target < min_edge;
+        let toward_max = target > current && target > max_edge;
+        if !self.slowdown || !(toward_min || toward_max) {
+            return vec![(target, speed)];
+        }
+
+        // Vitesse 0 = vitesse max pour le servo : on la plafonne aussi
+        let creep = if speed == 0 { self.creep_speed } else { speed.min(self.creep_speed) };
+        let (edge, edge_is_ahead) = if toward_min {
+            (min_edge, current > min_edge)
+        } else {
+            (max_edge, current < max_edge)
+        };
+
+        if edge_is_ahead {
+            vec![(edge, speed), (target, creep)]
+        } else {
+            vec![(target, creep)]
+        }
+    }
+}
+
+/// Butées matérielles (registres Min/Max Angle Limit) : le servo refuse d'aller au-delà,
+/// quel que soit le programme qui le pilote
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AngleLimits {
+    pub min: u16,
+    pub max: u16,
+}
+
+fn register(name: &str) -> &'static Register {
+    registers::all_registers().find(|r| r.name == name).expect("register in table")
+}
+
+impl AngleLimits {
+    pub fn read(bus: &mut RegisterPort, id: u8) -> Result<Self, String> {
+        let min = bus.read(id, register("Min Angle Limit"))?;
+        let max = bus.read(id, register("Max Angle Limit"))?;
+        Ok(Self { min: min as u16, max: max as u16 })
+    }
+
+    /// Écrit les deux registres, chacun relu
+    pub fn write(&self, bus: &mut RegisterPort, id: u8) -> Result<(), String> {
+        self.check()?;
+        bus.write(id, register("Min Angle Limit"), i32::from(self.min))?;
+        bus.write(id, register("Max Angle Limit"), i32::from(self.max))?;
+        Ok(())
+    }
+
+    /// 0 et 0 désactivent les butées (rotation continue) : toute la plage est alors permise
+    pub fn is_disabled(&self) -> bool {
+        self.min == 0 && self.max == 0
+    }
+
+    pub fn range(&self) -> RangeInclusive<u16> {
+        if self.is_disabled() {
+            0..=MAX_TICKS
+        } else {
+            self.min..=self.max.max(self.min)
+        }
+    }
+
+    /// Butées brutes vues en positions logiques d'un servo inversé ; désactivées, elles le restent
+    pub fn mirrored(&self) -> Self {
+        if self.is_disabled() {
+            return *self;
+        }
+        Self { min: inversion::mirror(self.max), max: inversion::mirror(self.min) }
+    }
+
+    pub fn check(&self) -> Result<(), String> {
+        if self.max > MAX_TICKS {
+            return Err(format!("max angle limit {} beyond {}", self.max, MAX_TICKS));
+        }
+        if self.min >= self.max && !self.is_disabled() {
+            return Err(format!("min angle limit {} is not below max {}", self.min, self.max));
+        }
+        Ok(())
+    }
+}
+
+/// Limite de couple (registre Torque Limit, en 0,1 %) : effort max du servo, par exemple pour une
+/// pince qui ne doit pas écraser ce qu'elle tient. Registre RAM, repris de Max Torque à la mise
+/// sous tension.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TorqueLimit {
+    pub permille: u16,
+}
+
+impl Default for TorqueLimit {
+    fn default() -> Self {
+        Self { permille: 1000 }
+    }
+}
+
+impl TorqueLimit {
+    pub fn from_percent(percent: f32) -> Self {
+        Self { permille: (percent.clamp(0.0, 100.0) * 10.0).round() as u16 }
+    }
+
+    pub fn percent(&self) -> f32 {
+        f32::from(self.permille) / 10.0
+    }
+
+    pub fn read(bus: &mut RegisterPort, id: u8) -> Result<Self, String> {
+        Ok(Self { permille: bus.read(id, register("Torque Limit"))? as u16 })
+    }
+
+    /// Écrit le registre, relu
+    pub fn write(&self, bus: &mut RegisterPort, id: u8) -> Result<(), String> {
+        bus.write(id, register("Torque Limit"), i32::from(self.permille)).map(|_| ())
+    }
+
+    /// Charge (%) rapportée à la limite : 1 = le servo pousse à la limite
+    pub fn load_ratio(&self, load_percent: f32) -> f32 {
+        if self.permille == 0 {
+            return if load_percent == 0.0 { 0.0 } else { 1.0 };
+        }
+        (load_percent.abs() / self.percent()).clamp(0.0, 1.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{MockBackend, MockServo};
+
+    #[test]
+    fn soft_limits_bound_the_slider_window() {
+        let limits = SoftLimits { min: 500, max: 3000, ..SoftLimits::default() };
+        assert_eq!(limits.range(), 500..=3000);
+        assert_eq!(limits.clamp(100), 500);
+        assert_eq!(limits.clamp(3500), 3000);
+        // Butées croisées : fenêtre réduite à la butée basse
+        assert_eq!(SoftLimits { min: 3000, max: 500, ..SoftLimits::default() }.range(), 3000..=3000);
+        assert_eq!(SoftLimits::default().range(), 0..=MAX_TICKS);
+    }
+
+
+    #[test]
+    fn angle_limits_are_checked_before_writing() {
+        assert!(AngleLimits { min: 0, max: 0 }.check().is_ok());
+        assert!(AngleLimits { min: 1000, max: 1000 }.check().is_err());
+        assert!(AngleLimits { min: 0, max: 4096 }.check().is_err());
+
+        let mock = MockBackend::new().with_servo(3, MockServo::default());
+        let mut bus = RegisterPort::new(&mock);
+        assert!(AngleLimits { min: 3000, max: 1000 }.write(&mut bus, 3).is_err());
+        assert!(mock.calls().is_empty());
+
+        let limits = AngleLimits { min: 1000, max: 3000 };
+        limits.write(&mut bus, 3).unwrap();
+        assert_eq!(AngleLimits::read(&mut bus, 3), Ok(limits));
+    }
+
+    #[test]
+    fn hardware_limits_narrow_the_slider_window() {
+        let hardware = AngleLimits { min: 1000, max: 3000 };
+        let soft = SoftLimits { min: 500, max: 2500, ..SoftLimits::default() };
+        assert_eq!(soft.range_within(Some(&hardware)), 1000..=2500);
+        assert_eq!(soft.range_within(None), 500..=2500);
+        // Butées désactivées : toute la plage
+        assert_eq!(soft.range_within(Some(&AngleLimits { min: 0, max: 0 })), 500..=2500);
+        // Aucun recouvrement : les butées matérielles l'emportent
+        let outside = SoftLimits { min: 3500, max: 4000, ..SoftLimits::default() };
+        assert_eq!(outside.range_within(Some(&hardware)), 1000..=3000);
+    }
+
+    #[test]
+    fn mirrored_limits_swap_around_the_center() {
+        assert_eq!(AngleLimits { min: 1000, max: 3000 }.mirrored(), AngleLimits { min: 1096, max: 3096 });
+        assert_eq!(AngleLimits { min: 0, max: 0 }.mirrored(), AngleLimits { min: 0, max: 0 });
+        let soft = SoftLimits { min: 1000, max: 3000, ..SoftLimits::default() };
+        assert_eq!(soft.mirrored().range(), 1096..=3096);
+        assert_eq!(SoftLimits::default().mirrored().range(), 0..=MAX_TICKS);
+    }
+
+
+    #[test]
+    fn torque_limit_in_percent_and_load_ratio() {
+        assert_eq!(TorqueLimit::from_percent(42.55).permille, 426);
+        assert_eq!(TorqueLimit::from_percent(150.0), TorqueLimit::default());
+        assert_eq!(TorqueLimit::from_percent(-5.0).permille, 0);
+
+        let half = TorqueLimit { permille: 500 };
+        assert_eq!(half.percent(), 50.0);
+        assert_eq!(half.load_ratio(-25.0), 0.5);
+        assert_eq!(half.load_ratio(80.0), 1.0);
+        // Limite nulle : toute charge est « à la limite »
+        assert_eq!(TorqueLimit { permille: 0 }.load_ratio(0.0), 0.0);
+        assert_eq!(TorqueLimit { permille: 0 }.load_ratio(3.0), 1.0);
+    }
+
+    #[test]
+    fn torque_limit_is_written_to_ram_and_read_back() {
+        let mock = MockBackend::new().with_servo(2, MockServo::default());
+        let mut bus = RegisterPort::new(&mock);
+        TorqueLimit { permille: 300 }.write(&mut bus, 2).unwrap();
+        assert_eq!(mock.calls(), vec![crate::backend::BackendCall::WriteRegister { id: 2, address: 48, data: vec![0x2C, 0x01] }]);
+        assert_eq!(TorqueLimit::read(&mut bus, 2), Ok(TorqueLimit { permille: 300 }));
+    }
+}
@@ -0,0 +1,424 @@
+//! Journal structuré des échanges sur le bus, via la crate `log`.
+//!
+//! Chaque binaire accepte `--log-level LEVEL` (`off`, `error`, `warn`, `info`, `debug`, `trace` ;
+//! `warn` par défaut), `--trace` (raccourci de `--log-level trace`) et `--log-file PATH`. Les
+//! lignes vont sur stderr, ou dans le fichier s'il est donné ; les dernières restent aussi en
+//! mémoire pour la console des interfaces.
+//!
+//! Cibles : `bus` (transactions du pilote et accès registre, avec leur temps aller-retour),
+//! `worker` (commandes traitées), `sim` (trames du bus simulé), `dry_run` (écritures retenues
+//! par le mode répétition des interfaces, en `info`), `override` (dérogations accordées et
+//! levées, en `warn`). Au-delà de `warn`, les autres
+//! crates (egui, wgpu…) sont filtrées pour ne pas noyer les échanges.
+
+use crate::backend::ServoBackend;
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+pub const BUS: &str = "bus";
+pub const WORKER: &str = "worker";
+pub const SIM: &str = "sim";
+pub const DRY_RUN: &str = "dry_run";
+pub const OVERRIDE: &str = "override";
+
+pub const DEFAULT_LEVEL: LevelFilter = LevelFilter::Warn;
+/// Lignes gardées pour la console des interfaces
+pub const CONSOLE_LINES: usize = 2000;
+
+#[derive(Clone, Debug)]
+pub struct LogOptions {
+    pub level: LevelFilter,
+    pub file: Option<PathBuf>,
+}
+
+impl Default for LogOptions {
+    fn default() -> Self {
+        Self { level: DEFAULT_LEVEL, file: None }
+    }
+}
+
+impl LogOptions {
+    /// Retire les options de journal des arguments, avant l'analyse propre au binaire
+    pub fn extract(args: Vec<String>) -> Result<(Self, Vec<String>), String> {
+        let mut options = Self::default();
+        let mut rest = Vec::new();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            // `--log-level=debug` comme `--log-level debug`
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--log-") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+            match flag.as_str() {
+                "--trace" => options.level = LevelFilter::Trace,
+                "--log-level" => {
+                    let value = inline
+                        .or_else(|| args.next())
+                        .ok_or("--log-level needs a level (off, error, warn, info, debug, trace)")?;
+                    options.level = value.parse().map_err(|_| format!("Invalid log level: {}", value))?;
+                }
+                "--log-file" => {
+                    let value = inline.or_else(|| args.next()).ok_or("--log-file needs a path")?;
+                    options.file = Some(PathBuf::from(value));
+                }
+                _ => rest.push(arg),
+            }
+        }
+        Ok((options, rest))
+    }
+}
+
+/// Ligne du journal ; `time` en secondes depuis l'installation du journal
+#[derive(Clone, Debug)]
+pub struct LogLine {
+    pub time: f64,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+impl fmt::Display for LogLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:>9.3} {:<5} {}: {}", self.time, self.level, self.target, self.message)
+    }
+}
+
+struct Logger {
+    start: Instant,
+    file: Option<Mutex<File>>,
+    lines: Mutex<VecDeque<LogLine>>,
+}
+
+fn is_ours(target: &str) -> bool {
+    [BUS, WORKER, SIM, DRY_RUN, OVERRIDE].contains(&target) || target.starts_with("servo_control")
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level() && (metadata.level() <= Level::Warn || is_ours(metadata.target()))
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = LogLine {
+            time: self.start.elapsed().as_secs_f64(),
+            level: record.level(),
+            target: record.target().to_string(),
+            message: record.args().to_string(),
+        };
+        match &self.file {
+            Some(file) => {
+                let _ = writeln!(file.lock().unwrap(), "{}", line);
+            }
+            None => eprintln!("{}", line),
+        }
+        let mut lines = self.lines.lock().unwrap();
+        if lines.len() >= CONSOLE_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            let _ = file.lock().unwrap().flush();
+        }
+    }
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Installe le journal du processus ; le fichier est ouvert en ajout
+pub fn init(options: &LogOptions) -> Result<(), String> {
+    let file = match &options.file {
+        Some(path) => Some(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("{}: {}", path.display(), e))?,
+        ),
+        None => None,
+    };
+    let logger = LOGGER.get_or_init(|| Logger {
+        start: Instant::now(),
+        file: file.map(Mutex::new),
+        lines: Mutex::new(VecDeque::new()),
+    });
+    log::set_logger(logger).map_err(|e| e.to_string())?;
+    log::set_max_level(options.level);
+    Ok(())
+}
+
+/// Options de journal retirées des arguments puis journal installé ; renvoie les autres arguments
+pub fn setup(args: Vec<String>) -> Result<Vec<String>, String> {
+    let (options, args) = LogOptions::extract(args)?;
+    init(&options)?;
+    Ok(args)
+}
+
+/// Niveau modifié en cours de route (console des interfaces)
+pub fn set_level(level: LevelFilter) {
+    log::set_max_level(level);
+}
+
+/// Dernières lignes, de la plus ancienne à la plus récente ; vide sans `init`
+pub fn with_lines<T>(f: impl FnOnce(&VecDeque<LogLine>) -> T) -> T {
+    match LOGGER.get() {
+        Some(logger) => f(&logger.lines.lock().unwrap()),
+        None => f(&VecDeque::new()),
+    }
+}
+
+pub fn clear() {
+    if let Some(logger) = LOGGER.get() {
+        logger.lines.lock().unwrap().clear();
+    }
+}
+
+// --- TRANSACTIONS DU PILOTE ---
+/// Journalise chaque transaction du pilote avec son temps aller-retour : `trace` pour une réponse,
+/// `debug` pour un échec. Placé sous `retry::RetryBackend`, il voit chaque tentative.
+pub struct LoggedBackend {
+    inner: Box<dyn ServoBackend>,
+}
+
+impl LoggedBackend {
+    pub fn new(inner: Box<dyn ServoBackend>) -> Self {
+        Self { inner }
+    }
+
+    fn traced<T: fmt::Debug>(
+        &self,
+        id: u8,
+        what: impl FnOnce() -> String,
+        ok: impl Fn(&T) -> bool,
+        f: impl FnOnce(&dyn ServoBackend) -> T,
+    ) -> T {
+        let start = Instant::now();
+        let result = f(self.inner.as_ref());
+        let rtt = start.elapsed().as_micros();
+        // Libellé formaté seulement si la ligne est gardée
+        match ok(&result) {
+            true => log::trace!(target: BUS, "ID {} {} → {:?} ({} µs)", id, what(), result, rtt),
+            false => log::debug!(target: BUS, "ID {} {} failed: {:?} ({} µs)", id, what(), result, rtt),
+        }
+        result
+    }
+
+    fn read<T: fmt::Debug>(&self, id: u8, register: &str, f: impl FnOnce(&dyn ServoBackend) -> Option<T>) -> Option<T> {
+        self.traced(id, || format!("read {}", register), Option::is_some, f)
+    }
+
+    fn write(&self, id: u8, what: impl FnOnce() -> String, f: impl FnOnce(&dyn ServoBackend) -> Result<(), String>) -> Result<(), String> {
+        self.traced(id, what, Result::is_ok, f)
+    }
+}
+
+impl ServoBackend for LoggedBackend {
+    fn ping_servo(&self, id: u8) -> bool {
+        // Un ID absent ne répond pas : ce n'est pas un échec
+        self.traced(id, || "ping".to_string(), |_| true, |b| b.ping_servo(id))
+    }
+
+    fn list_servos(&self) -> Vec<u8> {
+        let start = Instant::now();
+        let servos = self.inner.list_servos();
+        log::trace!(target: BUS, "scan → {:?} ({} µs)", servos, start.elapsed().as_micros());
+        servos
+    }
+
+    fn read_position(&self, id: u8) -> Option<u16> {
+        self.read(id, "Present Position", |b| b.read_position(id))
+    }
+
+    fn read_temperature(&self, id: u8) -> Option<u8> {
+        self.read(id, "Present Temperature", |b| b.read_temperature(id))
+    }
+
+    fn read_voltage(&self, id: u8) -> Option<f32> {
+        self.read(id, "Present Voltage", |b| b.read_voltage(id))
+    }
+
+    fn read_current(&self, id: u8) -> Option<f32> {
+        self.read(id, "Present Current", |b| b.read_current(id))
+    }
+
+    fn read_speed(&self, id: u8) -> Option<i16> {
+        self.read(id, "Present Speed", |b| b.read_speed(id))
+    }
+
+    fn read_load(&self, id: u8) -> Option<f32> {
+        self.read(id, "Present Load", |b| b.read_load(id))
+    }
+
+    fn read_mode(&self, id: u8) -> Option<u8> {
+        self.read(id, "Mode", |b| b.read_mode(id))
+    }
+
+    fn is_moving(&self, id: u8) -> Option<bool> {
+        self.read(id, "Moving", |b| b.is_moving(id))
+    }
+
+    fn move_to(&self, id: u8, position: u16, speed: u16, acceleration: u8, wait: bool) -> Option<bool> {
+        let what = || format!("write Goal Position = {} (speed {}, acc {})", position, speed, acceleration);
+        self.traced(id, what, Option::is_some, |b| b.move_to(id, position, speed, acceleration, wait))
+    }
+
+    fn write_position(&self, id: u8, position: u16) -> Option<bool> {
+        let what = || format!("write Goal Position = {}", position);
+        self.traced(id, what, Option::is_some, |b| b.write_position(id, position))
+    }
+
+    fn enable_torque(&self, id: u8) -> Result<(), String> {
+        self.write(id, || "write Torque Enable = 1".to_string(), |b| b.enable_torque(id))
+    }
+
+    fn disable_torque(&self, id: u8) -> Result<(), String> {
+        self.write(id, || "write Torque Enable = 0".to_string(), |b| b.disable_torque(id))
+    }
+
+    fn rotate(&self, id: u8, speed: i16) -> Result<(), String> {
+        self.write(id, || format!("wheel speed = {}", speed), |b| b.rotate(id, speed))
+    }
+
+    fn set_mode(&self, id: u8, mode: u8) -> Result<(), String> {
+        self.write(id, || format!("write Mode = {}", mode), |b| b.set_mode(id, mode))
+    }
+
+    fn change_id(&self, id: u8, new_id: u8) -> Result<(), String> {
+        self.write(id, || format!("write ID = {}", new_id), |b| b.change_id(id, new_id))
+    }
+
+    fn read_register(&self, id: u8, address: u8, size: u8) -> Result<Vec<u8>, String> {
+        self.traced(id, || format!("read @{} ({} bytes)", address, size), Result::is_ok, |b| b.read_register(id, address, size))
+    }
+
+    fn write_register(&self, id: u8, address: u8, data: &[u8]) -> Result<(), String> {
+        self.write(id, || format!("write @{} = {:?}", address, data), |b| b.write_register(id, address, data))
+    }
+
+    fn send_raw(&self, frame: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        let id = frame.get(2).copied().unwrap_or(0);
+        self.traced(id, || format!("raw frame {}", crate::packet::to_hex(frame)), Result::is_ok, |b| b.send_raw(frame))
+    }
+
+    fn broadcast_ping(&self, window: Duration) -> Result<Vec<u8>, String> {
+        self.traced(st3215::BROADCAST_ID, || "broadcast ping".to_string(), Result::is_ok, |b| b.broadcast_ping(window))
+    }
+}
+
+#[cfg(feature = "gui")]
+mod gui {
+    use super::{clear, set_level, with_lines};
+    use log::LevelFilter;
+
+    /// Console qui suit le journal : niveau modifiable, filtre texte, défilement collé en bas
+    pub fn console(ui: &mut egui::Ui) {
+        let filter_id = egui::Id::new("log_console_filter");
+        let mut filter: String = ui.ctx().data_mut(|d| d.get_temp(filter_id)).unwrap_or_default();
+        ui.horizontal(|ui| {
+            ui.label("Level:");
+            let mut level = log::max_level();
+            egui::ComboBox::from_id_salt("log_console_level")
+                .selected_text(level.as_str())
+                .show_ui(ui, |ui| {
+                    for candidate in LevelFilter::iter() {
+                        ui.selectable_value(&mut level, candidate, candidate.as_str());
+                    }
+                });
+            if level != log::max_level() {
+                set_level(level);
+            }
+            ui.add(egui::TextEdit::singleline(&mut filter).hint_text("Filter (ID 3, Goal Position…)").desired_width(180.0));
+            if ui.button("Clear").clicked() {
+                clear();
+            }
+        });
+        let lines: Vec<String> = with_lines(|lines| {
+            lines.iter().map(|line| line.to_string()).filter(|line| filter.is_empty() || line.contains(filter.as_str())).collect()
+        });
+        if lines.is_empty() {
+            ui.weak("Nothing logged at this level yet.");
+        }
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        egui::ScrollArea::vertical()
+            .id_salt("log_console_scroll")
+            .max_height(240.0)
+            .auto_shrink([false, true])
+            .stick_to_bottom(true)
+            .show_rows(ui, row_height, lines.len(), |ui, rows| {
+                for line in &lines[rows] {
+                    ui.monospace(line);
+                }
+            });
+        ui.ctx().data_mut(|d| d.insert_temp(filter_id, filter));
+    }
+}
+
+#[cfg(feature = "gui")]
+pub use gui::console;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendCall, MockBackend, MockServo};
+
+    #[test]
+    fn dry_run_and_override_targets_are_ours() {
+        assert!(is_ours(DRY_RUN));
+        assert!(is_ours(OVERRIDE));
+        assert!(is_ours("servo_control::portlock"));
+        assert!(!is_ours("eframe"));
+    }
+
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn log_options_are_taken_out_of_the_arguments() {
+        let (options, rest) = LogOptions::extract(args(&["--port", "/dev/ttyUSB0", "--log-level", "debug", "--log-file=bus.log"])).unwrap();
+        assert_eq!(options.level, LevelFilter::Debug);
+        assert_eq!(options.file, Some(PathBuf::from("bus.log")));
+        assert_eq!(rest, args(&["--port", "/dev/ttyUSB0"]));
+
+        let (options, rest) = LogOptions::extract(args(&["--trace", "scan"])).unwrap();
+        assert_eq!((options.level, options.file), (LevelFilter::Trace, None));
+        assert_eq!(rest, args(&["scan"]));
+        assert_eq!(LogOptions::extract(Vec::new()).unwrap().0.level, DEFAULT_LEVEL);
+
+        assert!(LogOptions::extract(args(&["--log-level", "loud"])).is_err());
+        assert!(LogOptions::extract(args(&["--log-level"])).is_err());
+        assert!(LogOptions::extract(args(&["--log-file"])).is_err());
+    }
+
+    #[test]
+    fn log_line_shows_time_level_and_target() {
+        let line = LogLine { time: 1.5, level: Level::Debug, target: BUS.to_string(), message: "ID 3 ping".to_string() };
+        assert_eq!(line.to_string(), "    1.500 DEBUG bus: ID 3 ping");
+    }
+
+    #[test]
+    fn logged_backend_passes_transactions_through() {
+        let mock = MockBackend::new().with_servo(3, MockServo { position: 1200, ..MockServo::default() });
+        let bus = LoggedBackend::new(Box::new(mock.clone()));
+        assert!(bus.ping_servo(3) && !bus.ping_servo(4));
+        assert_eq!(bus.read_position(3), Some(1200));
+        assert_eq!(bus.move_to(3, 2000, 100, 10, false), Some(true));
+        assert!(bus.enable_torque(4).is_err());
+        assert_eq!(
+            mock.calls(),
+            vec![BackendCall::MoveTo { id: 3, position: 2000, speed: 100, acceleration: 10 }, BackendCall::EnableTorque(4)]
+        );
+    }
+}
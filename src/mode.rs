@@ -0,0 +1,55 @@
+//! Mode de fonctionnement d'un servo (registre Mode) : asservi en position, ou roue en rotation
+//! continue pilotée par une vitesse signée. Les modes PWM et pas à pas ne sont pas gérés.
+
+/// Vitesse de roue max, dans un sens comme dans l'autre (pas/s)
+pub const MAX_WHEEL_SPEED: i16 = 3400;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ServoMode {
+    #[default]
+    Position,
+    Wheel,
+}
+
+impl ServoMode {
+    pub const ALL: [ServoMode; 2] = [ServoMode::Position, ServoMode::Wheel];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ServoMode::Position => "Position",
+            ServoMode::Wheel => "Wheel",
+        }
+    }
+
+    /// Valeur du registre Mode
+    pub fn register_value(self) -> u8 {
+        match self {
+            ServoMode::Position => 0,
+            ServoMode::Wheel => 1,
+        }
+    }
+
+    /// `None` pour les modes non gérés
+    pub fn from_register(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(ServoMode::Position),
+            1 => Some(ServoMode::Wheel),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modes_map_to_the_mode_register() {
+        for mode in ServoMode::ALL {
+            assert_eq!(ServoMode::from_register(mode.register_value()), Some(mode));
+        }
+        // Modes PWM et pas à pas : non gérés
+        assert_eq!(ServoMode::from_register(2), None);
+        assert_eq!(ServoMode::from_register(3), None);
+    }
+}
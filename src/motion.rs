@@ -0,0 +1,163 @@
+//! Estimation de la durée d'un mouvement à partir du profil trapézoïdal du ST3215.
+
+use std::time::Duration;
+
+/// Unité du registre d'accélération : 100 ticks/s²
+pub const ACCELERATION_UNIT: f64 = 100.0;
+/// Vitesse utilisée par le servo quand la consigne de vitesse vaut 0
+pub const MAX_SPEED: u16 = 3400;
+
+/// Registre d'accélération → ticks/s² (0 = accélération maximale, renvoie `None`)
+pub fn acceleration_ticks_per_s2(acceleration: u8) -> Option<f64> {
+    if acceleration == 0 {
+        None
+    } else {
+        Some(acceleration as f64 * ACCELERATION_UNIT)
+    }
+}
+
+/// Ticks/s² → degrés/s²
+pub fn ticks_to_degrees_per_s2(ticks_per_s2: f64) -> f64 {
+    ticks_per_s2 * 360.0 / 4096.0
+}
+
+/// Durée estimée pour parcourir `distance` ticks (accélération et décélération symétriques)
+pub fn estimate_move_duration(distance: u16, speed: u16, acceleration: u8) -> Duration {
+    let distance = distance as f64;
+    let speed = if speed == 0 { MAX_SPEED } else { speed } as f64;
+
+    let seconds = match acceleration_ticks_per_s2(acceleration) {
+        // Accélération "infinie" : vitesse constante sur tout le trajet
+        None => distance / speed,
+        Some(accel) => {
+            let ramp_time = speed / accel;
+            let ramp_distance = 0.5 * accel * ramp_time * ramp_time;
+            if 2.0 * ramp_distance >= distance {
+                // Profil triangulaire : la vitesse de consigne n'est jamais atteinte
+                2.0 * (distance / accel).sqrt()
+            } else {
+                2.0 * ramp_time + (distance - 2.0 * ramp_distance) / speed
+            }
+        }
+    };
+
+    Duration::from_secs_f64(seconds)
+}
+
+/// Vitesse (ticks/s) permettant de parcourir `distance` en `duration`, bornée à `1..=cap`
+pub fn speed_for_duration(distance: u16, duration: Duration, acceleration: u8, cap: u16) -> u16 {
+    let cap = if cap == 0 { MAX_SPEED } else { cap.min(MAX_SPEED) };
+    let distance = distance as f64;
+    let seconds = duration.as_secs_f64();
+    if distance == 0.0 || seconds <= 0.0 {
+        return cap;
+    }
+
+    let speed = match acceleration_ticks_per_s2(acceleration) {
+        None => distance / seconds,
+        Some(accel) => {
+            // Trapèze : T = v/a + d/v  →  v² - aT·v + a·d = 0, on garde la plus petite racine
+            let discriminant = accel * accel * seconds * seconds - 4.0 * accel * distance;
+            if discriminant < 0.0 {
+                // Durée inatteignable même en profil triangulaire
+                return cap;
+            }
+            (accel * seconds - discriminant.sqrt()) / 2.0
+        }
+    };
+
+    (speed.ceil() as u16).clamp(1, cap)
+}
+
+/// Vitesses par servo pour que tous les mouvements `(id, distance, cap)` se terminent ensemble.
+///
+/// Si un servo ne peut pas tenir `duration` à sa vitesse max, la durée commune est allongée
+/// d'autant ; retourne la durée retenue et les vitesses.
+pub fn coordinated_speeds(
+    moves: &[(u8, u16, u16)],
+    duration: Duration,
+    acceleration: u8,
+) -> (Duration, Vec<(u8, u16)>) {
+    let duration = moves
+        .iter()
+        .map(|&(_, distance, cap)| estimate_move_duration(distance, cap, acceleration))
+        .fold(duration, Duration::max);
+
+    let speeds = moves
+        .iter()
+        .map(|&(id, distance, cap)| (id, speed_for_duration(distance, duration, acceleration, cap)))
+        .collect();
+
+    (duration, speeds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: Duration, b: f64) -> bool {
+        (a.as_secs_f64() - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn acceleration_register_in_physical_units() {
+        assert_eq!(acceleration_ticks_per_s2(0), None);
+        assert_eq!(acceleration_ticks_per_s2(10), Some(1000.0));
+        assert_eq!(ticks_to_degrees_per_s2(4096.0), 360.0);
+    }
+
+    #[test]
+    fn duration_at_constant_speed() {
+        assert!(close(estimate_move_duration(1000, 500, 0), 2.0));
+        // Vitesse 0 : vitesse max du servo
+        assert!(close(estimate_move_duration(3400, 0, 0), 1.0));
+        assert_eq!(estimate_move_duration(0, 500, 10), Duration::ZERO);
+    }
+
+    #[test]
+    fn duration_of_trapezoid_and_triangle_profiles() {
+        // 1000 ticks/s atteints en 1 s sur 500 ticks, puis 1000 ticks en palier
+        assert!(close(estimate_move_duration(2000, 1000, 10), 3.0));
+        // Trop court pour atteindre la vitesse de consigne
+        assert!(close(estimate_move_duration(500, 1000, 10), 2.0 * 0.5f64.sqrt()));
+    }
+
+    #[test]
+    fn speed_for_duration_inverts_the_estimate() {
+        assert_eq!(speed_for_duration(2000, Duration::from_secs(3), 10, 0), 1000);
+        assert_eq!(speed_for_duration(1000, Duration::from_secs(2), 0, 0), 500);
+        for (distance, seconds) in [(300, 1.5), (1800, 2.0), (4000, 5.0)] {
+            let speed = speed_for_duration(distance, Duration::from_secs_f64(seconds), 20, 0);
+            let estimated = estimate_move_duration(distance, speed, 20).as_secs_f64();
+            assert!(estimated <= seconds && estimated > seconds * 0.95, "{} ticks: {} s", distance, estimated);
+        }
+    }
+
+    #[test]
+    fn speed_for_duration_is_bounded() {
+        // Rien à parcourir, ou durée inatteignable : vitesse plafond
+        assert_eq!(speed_for_duration(0, Duration::from_secs(1), 10, 800), 800);
+        assert_eq!(speed_for_duration(4000, Duration::from_millis(100), 10, 800), 800);
+        assert_eq!(speed_for_duration(4000, Duration::from_millis(100), 0, 0), MAX_SPEED);
+        assert_eq!(speed_for_duration(1, Duration::from_secs(60), 0, 800), 1);
+    }
+
+    #[test]
+    fn coordinated_moves_finish_together() {
+        let moves = [(1, 2000, 0), (2, 500, 0), (3, 0, 0)];
+        let (duration, speeds) = coordinated_speeds(&moves, Duration::from_secs(3), 10);
+        assert_eq!(duration, Duration::from_secs(3));
+        assert_eq!(speeds[0], (1, 1000));
+        let arrival = estimate_move_duration(500, speeds[1].1, 10).as_secs_f64();
+        assert!((arrival - 3.0).abs() < 0.05);
+        assert_eq!(speeds[2], (3, MAX_SPEED));
+    }
+
+    #[test]
+    fn slow_servo_stretches_the_common_duration() {
+        // 3000 ticks plafonnés à 500 ticks/s : 6 s au moins, quelle que soit la durée demandée
+        let (duration, speeds) = coordinated_speeds(&[(1, 3000, 500), (2, 600, 0)], Duration::from_secs(2), 0);
+        assert!(close(duration, 6.0));
+        assert_eq!(speeds, vec![(1, 500), (2, 100)]);
+    }
+}
@@ -0,0 +1,138 @@
+//! File de consignes par servo : « va en A, attends d'y être, puis va en B » sans attente active
+//! dans l'interface. Le thread du bus n'envoie la consigne suivante qu'une fois la précédente
+//! atteinte : drapeau de mouvement retombé et position à `ARRIVAL_TOLERANCE` près du but.
+//!
+//! Un but pas atteint à temps (servo bloqué, consigne refusée) vide la file du servo au lieu de
+//! la bloquer : la suite d'un script ne doit pas partir d'une position inconnue.
+
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Écart max (ticks) entre position lue et but pour passer à la consigne suivante
+pub const ARRIVAL_TOLERANCE: u16 = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QueuedMove {
+    pub position: u16,
+    pub speed: u16,
+    pub acceleration: u8,
+    /// Grand premier mouvement déjà confirmé par l'utilisateur
+    pub acknowledge_large: bool,
+}
+
+/// Issue d'un passage sur la file d'un servo
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum QueueStep {
+    /// Rien à envoyer : file vide, ou but en cours pas encore atteint
+    Wait,
+    /// Consigne à envoyer ; `started` arme ensuite l'attente de son but
+    Next(QueuedMove),
+    /// But pas atteint dans le délai : la file du servo a été vidée
+    TimedOut { goal: u16, dropped: usize },
+}
+
+#[derive(Debug, Default)]
+struct ServoQueue {
+    pending: VecDeque<QueuedMove>,
+    // But envoyé, et échéance pour l'atteindre
+    active: Option<(u16, Instant)>,
+}
+
+#[derive(Debug, Default)]
+pub struct MoveQueue {
+    queues: BTreeMap<u8, ServoQueue>,
+}
+
+impl MoveQueue {
+    pub fn push(&mut self, id: u8, queued: QueuedMove) {
+        self.queues.entry(id).or_default().pending.push_back(queued);
+    }
+
+    /// Consignes en attente, but en cours compris
+    pub fn depth(&self, id: u8) -> usize {
+        self.queues.get(&id).map_or(0, |queue| queue.pending.len() + usize::from(queue.active.is_some()))
+    }
+
+    /// Profondeur par servo, pour l'affichage
+    pub fn depths(&self) -> BTreeMap<u8, usize> {
+        self.queues.keys().map(|&id| (id, self.depth(id))).filter(|&(_, depth)| depth > 0).collect()
+    }
+
+    pub fn ids(&self) -> Vec<u8> {
+        self.queues.keys().copied().collect()
+    }
+
+    /// File du servo vidée ; rend le nombre de consignes abandonnées
+    pub fn clear(&mut self, id: u8) -> usize {
+        let dropped = self.depth(id);
+        self.queues.remove(&id);
+        dropped
+    }
+
+    pub fn clear_all(&mut self) {
+        self.queues.clear();
+    }
+
+    /// Passage sur la file d'un servo. `read` (drapeau de mouvement, position) n'est appelé que
+    /// si un but est en cours ; un drapeau illisible laisse décider la position seule.
+    pub fn poll(&mut self, id: u8, now: Instant, read: impl FnOnce() -> (Option<bool>, Option<u16>)) -> QueueStep {
+        let Some(queue) = self.queues.get_mut(&id) else { return QueueStep::Wait };
+        if let Some((goal, deadline)) = queue.active {
+            let (moving, position) = read();
+            let arrived = moving != Some(true) && position.is_some_and(|pos| pos.abs_diff(goal) <= ARRIVAL_TOLERANCE);
+            if !arrived {
+                if now < deadline {
+                    return QueueStep::Wait;
+                }
+                return QueueStep::TimedOut { goal, dropped: self.clear(id) };
+            }
+            queue.active = None;
+        }
+        match queue.pending.pop_front() {
+            Some(next) => QueueStep::Next(next),
+            None => {
+                self.queues.remove(&id);
+                QueueStep::Wait
+            }
+        }
+    }
+
+    /// Consigne envoyée : la suivante attend que `goal` soit atteint, au plus `timeout`
+    pub fn started(&mut self, id: u8, goal: u16, timeout: Duration, now: Instant) {
+        self.queues.entry(id).or_default().active = Some((goal, now + timeout));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn queued(position: u16, acknowledge_large: bool) -> QueuedMove {
+        QueuedMove { position, speed: 500, acceleration: 0, acknowledge_large }
+    }
+
+    #[test]
+    fn next_keeps_the_acknowledgement() {
+        let mut queue = MoveQueue::default();
+        let now = Instant::now();
+        queue.push(1, queued(1000, false));
+        queue.push(1, queued(3000, true));
+        assert_eq!(queue.poll(1, now, || unreachable!()), QueueStep::Next(queued(1000, false)));
+        queue.started(1, 1000, Duration::from_secs(1), now);
+        assert_eq!(queue.poll(1, now, || (Some(false), Some(1004))), QueueStep::Next(queued(3000, true)));
+    }
+
+    #[test]
+    fn unreached_goal_drops_the_queue() {
+        let mut queue = MoveQueue::default();
+        let now = Instant::now();
+        queue.push(1, queued(1000, false));
+        queue.push(1, queued(2000, false));
+        assert!(matches!(queue.poll(1, now, || unreachable!()), QueueStep::Next(_)));
+        queue.started(1, 1000, Duration::from_millis(100), now);
+        assert_eq!(queue.poll(1, now, || (Some(true), Some(500))), QueueStep::Wait);
+        let late = now + Duration::from_millis(200);
+        assert_eq!(queue.poll(1, late, || (Some(false), Some(500))), QueueStep::TimedOut { goal: 1000, dropped: 2 });
+        assert_eq!(queue.depth(1), 0);
+    }
+}
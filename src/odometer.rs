@@ -0,0 +1,154 @@
+//! Odomètre cumulé par servo (ticks parcourus), persistant entre les sessions.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const ODOMETER_FILE: &str = "odometer.json";
+/// Au-delà, un écart entre deux lectures est un redémarrage ou une lecture erronée, pas un mouvement
+pub const MAX_PLAUSIBLE_STEP: u16 = 1000;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OdometerEntry {
+    pub ticks: u64,
+    pub first_seen_unix_ms: u64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Odometer {
+    entries: BTreeMap<String, OdometerEntry>,
+    // Dernière position lue par servo (non persistée)
+    #[serde(skip)]
+    last_position: HashMap<String, u16>,
+}
+
+fn now_unix_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+impl Odometer {
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|text| serde_json::from_str(&text).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Ajoute le déplacement depuis la lecture précédente du servo `key`
+    pub fn record(&mut self, key: &str, position: u16) {
+        let entry = self.entries.entry(key.to_string()).or_insert_with(|| OdometerEntry {
+            ticks: 0,
+            first_seen_unix_ms: now_unix_ms(),
+        });
+        if let Some(previous) = self.last_position.insert(key.to_string(), position) {
+            let step = previous.abs_diff(position);
+            if step <= MAX_PLAUSIBLE_STEP {
+                entry.ticks += step as u64;
+            }
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&OdometerEntry> {
+        self.entries.get(key)
+    }
+
+    /// Remise à zéro après remplacement du servo
+    pub fn reset(&mut self, key: &str) {
+        self.entries.insert(key.to_string(), OdometerEntry { ticks: 0, first_seen_unix_ms: now_unix_ms() });
+        self.last_position.remove(key);
+    }
+}
+
+/// Clé d'un servo dans le fichier : son nom, qui le suit s'il change d'ID, sinon « ID n »
+pub fn key(id: u8, name: &str) -> String {
+    match name.trim() {
+        "" => format!("ID {}", id),
+        name => name.to_string(),
+    }
+}
+
+/// « 1.2 M », « 35.4 k » ou « 812 »
+pub fn format_ticks(ticks: u64) -> String {
+    match ticks {
+        t if t >= 1_000_000 => format!("{:.1} M", t as f64 / 1_000_000.0),
+        t if t >= 1_000 => format!("{:.1} k", t as f64 / 1_000.0),
+        t => t.to_string(),
+    }
+}
+
+/// Date AAAA-MM-JJ (UTC) d'un horodatage UNIX en millisecondes
+pub fn format_date(unix_ms: u64) -> String {
+    // Conversion jours → date civile (algorithme de H. Hinnant)
+    let days = (unix_ms / 86_400_000) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn key_prefers_name_over_id() {
+        assert_eq!(key(3, "shoulder"), "shoulder");
+        assert_eq!(key(3, "  elbow "), "elbow");
+        assert_eq!(key(3, ""), "ID 3");
+        assert_eq!(key(3, "   "), "ID 3");
+    }
+
+    #[test]
+    fn record_ignores_implausible_jumps() {
+        let mut odometer = Odometer::default();
+        odometer.record("wrist", 2000);
+        odometer.record("wrist", 2300);
+        odometer.record("wrist", 100);
+        odometer.record("wrist", 150);
+        assert_eq!(odometer.get("wrist").unwrap().ticks, 350);
+    }
+
+    #[test]
+    fn entries_follow_the_name_across_ids() {
+        let mut odometer = Odometer::default();
+        odometer.record(&key(1, "gripper"), 1000);
+        odometer.record(&key(1, "gripper"), 1100);
+        // Même servo réadressé en ID 5 : le compteur continue
+        odometer.record(&key(5, "gripper"), 1100);
+        odometer.record(&key(5, "gripper"), 1150);
+        assert_eq!(odometer.get("gripper").unwrap().ticks, 150);
+        assert!(odometer.get("ID 5").is_none());
+    }
+
+    #[test]
+    fn reset_restarts_the_count() {
+        let mut odometer = Odometer::default();
+        odometer.record("base", 0);
+        odometer.record("base", 500);
+        odometer.reset("base");
+        odometer.record("base", 900);
+        assert_eq!(odometer.get("base").unwrap().ticks, 0);
+    }
+
+    #[test]
+    fn format_ticks_is_compact() {
+        assert_eq!(format_ticks(812), "812");
+        assert_eq!(format_ticks(35_400), "35.4 k");
+        assert_eq!(format_ticks(1_200_000), "1.2 M");
+        assert_eq!(format_date(0), "1970-01-01");
+    }
+}
@@ -0,0 +1,82 @@
+//! Verrou des opérations destructives ou en plusieurs étapes (changement d'ID, écritures
+//! EEPROM, reset...) : une seule à la fois, la télémétrie du servo concerné est suspendue.
+
+use std::time::Instant;
+
+#[derive(Clone, Debug)]
+pub struct Operation {
+    pub servo: u8,
+    pub name: String,
+    /// Étape en cours, affichée comme progression
+    pub step: String,
+    pub started: Instant,
+}
+
+#[derive(Debug, Default)]
+pub struct OperationLock {
+    current: Option<Operation>,
+}
+
+impl OperationLock {
+    /// Réserve le bus pour une opération, ou explique pourquoi c'est impossible
+    pub fn begin(&mut self, servo: u8, name: impl Into<String>) -> Result<(), String> {
+        if let Some(op) = &self.current {
+            return Err(format!("operation in progress on ID {} ({})", op.servo, op.name));
+        }
+        self.current = Some(Operation {
+            servo,
+            name: name.into(),
+            step: String::new(),
+            started: Instant::now(),
+        });
+        Ok(())
+    }
+
+    pub fn progress(&mut self, step: impl Into<String>) {
+        if let Some(op) = self.current.as_mut() {
+            op.step = step.into();
+        }
+    }
+
+    pub fn finish(&mut self) {
+        self.current = None;
+    }
+
+    pub fn current(&self) -> Option<&Operation> {
+        self.current.as_ref()
+    }
+
+    /// Vrai si la télémétrie de `servo` doit être suspendue
+    pub fn pauses(&self, servo: u8) -> bool {
+        self.current.as_ref().is_some_and(|op| op.servo == servo)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn one_operation_at_a_time() {
+        let mut lock = OperationLock::default();
+        assert_eq!(lock.begin(3, "change ID"), Ok(()));
+        assert_eq!(lock.begin(5, "factory reset"), Err("operation in progress on ID 3 (change ID)".to_string()));
+        lock.finish();
+        assert!(lock.current().is_none());
+        assert_eq!(lock.begin(5, "factory reset"), Ok(()));
+    }
+
+    #[test]
+    fn progress_and_paused_telemetry() {
+        let mut lock = OperationLock::default();
+        lock.progress("ignored without an operation");
+        assert!(!lock.pauses(3));
+
+        lock.begin(3, "swap IDs").unwrap();
+        lock.progress("step 2/3");
+        let op = lock.current().unwrap();
+        assert_eq!((op.servo, op.name.as_str(), op.step.as_str()), (3, "swap IDs", "step 2/3"));
+        assert!(lock.pauses(3));
+        assert!(!lock.pauses(4));
+    }
+}
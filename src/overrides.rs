@@ -0,0 +1,89 @@
+//! Dérogations temporaires aux sécurités (coupure thermique, plafonds de vitesse, butées) :
+//! accordées pour une durée limitée puis annulées automatiquement. Un réglage permanent passe
+//! par le fichier de configuration, jamais par ce raccourci.
+
+use std::time::{Duration, Instant};
+
+pub const DEFAULT_OVERRIDE_DURATION: Duration = Duration::from_secs(5 * 60);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OverrideKind {
+    /// Coupure du couple en surchauffe désactivée
+    TemperatureCutoff,
+    /// Plafonds de vitesse levés (déclassement thermique, plafond du mode coordonné)
+    SpeedCap,
+    /// Butées logicielles levées
+    SoftLimits,
+}
+
+impl OverrideKind {
+    pub const ALL: [OverrideKind; 3] = [OverrideKind::TemperatureCutoff, OverrideKind::SpeedCap, OverrideKind::SoftLimits];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OverrideKind::TemperatureCutoff => "Temperature cut-off off",
+            OverrideKind::SpeedCap => "Speed caps lifted",
+            OverrideKind::SoftLimits => "Soft limits lifted",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Override {
+    pub kind: OverrideKind,
+    /// `None` : tous les servos
+    pub servo: Option<u8>,
+    pub expires: Instant,
+}
+
+impl Override {
+    pub fn remaining(&self, now: Instant) -> Duration {
+        self.expires.saturating_duration_since(now)
+    }
+
+    pub fn describe(&self) -> String {
+        match self.servo {
+            Some(id) => format!("{} (ID {})", self.kind.label(), id),
+            None => format!("{} (all servos)", self.kind.label()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Overrides {
+    active: Vec<Override>,
+}
+
+impl Overrides {
+    pub fn find(&self, kind: OverrideKind, servo: Option<u8>) -> Option<&Override> {
+        self.active.iter().find(|o| o.kind == kind && o.servo == servo)
+    }
+
+    /// Accorde (ou prolonge, après confirmation côté interface) une dérogation pour `duration`
+    pub fn grant(&mut self, kind: OverrideKind, servo: Option<u8>, duration: Duration, now: Instant) -> &Override {
+        self.active.retain(|o| !(o.kind == kind && o.servo == servo));
+        self.active.push(Override { kind, servo, expires: now + duration });
+        self.active.last().unwrap()
+    }
+
+    pub fn revoke(&mut self, kind: OverrideKind, servo: Option<u8>) -> Option<Override> {
+        let index = self.active.iter().position(|o| o.kind == kind && o.servo == servo)?;
+        Some(self.active.remove(index))
+    }
+
+    /// Vrai si la sécurité `kind` est levée pour `servo` (dérogation propre ou globale)
+    pub fn is_active(&self, kind: OverrideKind, servo: u8) -> bool {
+        self.active.iter().any(|o| o.kind == kind && o.servo.is_none_or(|id| id == servo))
+    }
+
+    /// Retire les dérogations échues et les retourne pour le journal
+    pub fn expire(&mut self, now: Instant) -> Vec<Override> {
+        let (expired, active) = self.active.drain(..).partition(|o| o.expires <= now);
+        self.active = active;
+        expired
+    }
+
+    pub fn active(&self) -> &[Override] {
+        &self.active
+    }
+}
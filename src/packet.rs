@@ -0,0 +1,263 @@
+//! Construction et décodage des trames du protocole ST3215, pour la console d'instructions bas niveau.
+
+use crate::backend::ServoBackend;
+use crate::ids::{self, Access};
+use crate::registers::EEPROM_END;
+use st3215::{
+    PortHandler, ProtocolPacketHandler, BROADCAST_ID, INST_ACTION, INST_PING, INST_READ, INST_REG_WRITE,
+    INST_SYNC_READ, INST_SYNC_WRITE, INST_WRITE, STS_ACC, TXPACKET_MAX_LEN,
+};
+use std::time::{Duration, Instant};
+
+/// Instruction de retour aux réglages d'usine (absente des constantes du pilote)
+pub const INST_RESET: u8 = 0x06;
+
+/// Somme de contrôle : complément à un de la somme ID + longueur + instruction + paramètres
+pub fn checksum(id: u8, instruction: u8, params: &[u8]) -> u8 {
+    let length = params.len() as u8 + 2;
+    let sum = params
+        .iter()
+        .fold(id.wrapping_add(length).wrapping_add(instruction), |acc, b| acc.wrapping_add(*b));
+    !sum
+}
+
+/// Trame complète : FF FF ID LEN INSTR PARAMS... CHECKSUM
+pub fn build_frame(id: u8, instruction: u8, params: &[u8]) -> Result<Vec<u8>, String> {
+    if params.len() + 6 > TXPACKET_MAX_LEN {
+        return Err(format!("too many parameters ({} bytes)", params.len()));
+    }
+    let mut frame = vec![0xFF, 0xFF, id, params.len() as u8 + 2, instruction];
+    frame.extend_from_slice(params);
+    frame.push(checksum(id, instruction, params));
+    Ok(frame)
+}
+
+/// Octets écrits par servo dans un sync write de consigne : accélération, position, durée, vitesse
+const SYNC_MOVE_LENGTH: u8 = 7;
+
+/// Sync write des consignes `(id, position, vitesse)` : une seule trame en diffusion, tous les
+/// servos démarrent au même instant. Aucune réponse n'est renvoyée.
+pub fn sync_move_frame(targets: &[(u8, u16, u16)], acceleration: u8) -> Result<Vec<u8>, String> {
+    let mut params = vec![STS_ACC, SYNC_MOVE_LENGTH];
+    for &(id, position, speed) in targets {
+        params.extend_from_slice(&[id, acceleration]);
+        params.extend_from_slice(&position.to_le_bytes());
+        params.extend_from_slice(&[0, 0]);
+        params.extend_from_slice(&speed.to_le_bytes());
+    }
+    build_frame(BROADCAST_ID, INST_SYNC_WRITE, &params)
+}
+
+/// Accepte "2A 00 08", "2a,00,08" ou "0x2A 0x00"
+pub fn parse_hex(input: &str) -> Result<Vec<u8>, String> {
+    input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|tok| !tok.is_empty())
+        .map(|tok| {
+            let digits = tok.trim_start_matches("0x").trim_start_matches("0X");
+            u8::from_str_radix(digits, 16).map_err(|_| format!("invalid hex byte: {}", tok))
+        })
+        .collect()
+}
+
+pub fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02X}", b)).collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Response {
+    pub id: u8,
+    pub error: u8,
+    pub params: Vec<u8>,
+}
+
+/// Décode une trame de statut en vérifiant en-tête, longueur et somme de contrôle
+pub fn decode_response(frame: &[u8]) -> Result<Response, String> {
+    if frame.len() < 6 || frame[0] != 0xFF || frame[1] != 0xFF {
+        return Err("missing FF FF header".to_string());
+    }
+    let length = frame[3] as usize;
+    if length < 2 || frame.len() < length + 4 {
+        return Err(format!("truncated frame (length field {})", length));
+    }
+    let params = &frame[5..length + 3];
+    let expected = checksum(frame[2], frame[4], params);
+    let received = frame[length + 3];
+    if expected != received {
+        return Err(format!("bad checksum: expected {:02X}, got {:02X}", expected, received));
+    }
+    Ok(Response { id: frame[2], error: frame[4], params: params.to_vec() })
+}
+
+pub fn instruction_name(instruction: u8) -> &'static str {
+    match instruction {
+        INST_PING => "PING",
+        INST_READ => "READ",
+        INST_WRITE => "WRITE",
+        INST_REG_WRITE => "REG_WRITE",
+        INST_ACTION => "ACTION",
+        INST_RESET => "RESET",
+        INST_SYNC_READ => "SYNC_READ",
+        INST_SYNC_WRITE => "SYNC_WRITE",
+        _ => "vendor/unknown",
+    }
+}
+
+/// Instructions qui modifient l'état des servos
+pub fn is_destructive(instruction: u8) -> bool {
+    !matches!(instruction, INST_PING | INST_READ | INST_SYNC_READ)
+}
+
+/// Une instruction destructive envoyée à tout le bus exige une confirmation explicite
+pub fn needs_broadcast_confirmation(id: u8, instruction: u8) -> bool {
+    id == BROADCAST_ID && is_destructive(instruction)
+}
+
+/// Effet durable (écriture EEPROM, changement d'ID, reset) : refusé en diffusion
+pub fn access(instruction: u8, params: &[u8]) -> Access {
+    match instruction {
+        INST_RESET => Access::Eeprom,
+        INST_WRITE | INST_REG_WRITE if params.first().is_some_and(|&address| address < EEPROM_END) => Access::Eeprom,
+        _ => Access::Command,
+    }
+}
+
+/// Envoie une trame brute sur un port ouvert et retourne la réponse éventuelle
+pub fn send_frame(port: &mut PortHandler, frame: &[u8]) -> Result<Option<Vec<u8>>, String> {
+    let mut handler = ProtocolPacketHandler::new(port);
+    let mut txpacket = frame.to_vec();
+    let (rxpacket, result, _error) = handler.tx_rx_packet(&mut txpacket);
+    if result.is_success() {
+        Ok(rxpacket)
+    } else {
+        Err(format!("{:?}", result))
+    }
+}
+
+/// Découpe un flux reçu en trames de statut valides ; retourne aussi le nombre d'octets
+/// qui n'appartiennent à aucune trame valide (réponses entrelacées, bruit)
+pub fn split_responses(bytes: &[u8]) -> (Vec<Response>, usize) {
+    let (mut responses, mut garbled) = (Vec::new(), 0);
+    let mut rest = bytes;
+    while !rest.is_empty() {
+        let frame_len = rest.get(3).map_or(0, |&length| length as usize + 4);
+        match decode_response(rest) {
+            Ok(response) => {
+                responses.push(response);
+                rest = &rest[frame_len..];
+            }
+            Err(_) => {
+                garbled += 1;
+                rest = &rest[1..];
+            }
+        }
+    }
+    (responses, garbled)
+}
+
+/// Ping en diffusion sur un port ouvert : tout ce qui arrive pendant `window` est retourné brut,
+/// chaque servo présent répondant par sa propre trame de statut
+pub fn broadcast_ping(port: &mut PortHandler, window: Duration) -> Result<Vec<u8>, String> {
+    port.write_port(&build_frame(BROADCAST_ID, INST_PING, &[])?)?;
+    let deadline = Instant::now() + window;
+    let mut received = Vec::new();
+    while Instant::now() < deadline {
+        match port.get_bytes_available()? {
+            0 => std::thread::sleep(Duration::from_millis(1)),
+            available => received.extend(port.read_port(available as usize)?),
+        }
+    }
+    Ok(received)
+}
+
+/// Retour aux réglages d'usine (instruction RESET) : toute l'EEPROM reprend ses valeurs par
+/// défaut, ID compris (1). Refusé en diffusion.
+pub fn factory_reset(bus: &dyn ServoBackend, id: u8) -> Result<(), String> {
+    ids::check_target(id, Access::Eeprom, false)?;
+    let reply = bus.send_raw(&build_frame(id, INST_RESET, &[])?)?
+        .ok_or_else(|| format!("ID {}: no reply to the reset instruction", id))?;
+    let response = decode_response(&reply)?;
+    if response.error != 0 {
+        return Err(format!("ID {}: reset refused (error byte {:02X})", id, response.error));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendCall, MockBackend, MockServo};
+
+    #[test]
+    fn ping_frame_matches_the_datasheet() {
+        assert_eq!(build_frame(1, INST_PING, &[]).unwrap(), vec![0xFF, 0xFF, 0x01, 0x02, 0x01, 0xFB]);
+        // Lecture de la position (adresse 0x38, 2 octets)
+        assert_eq!(build_frame(1, INST_READ, &[0x38, 0x02]).unwrap(), vec![0xFF, 0xFF, 0x01, 0x04, 0x02, 0x38, 0x02, 0xBE]);
+        assert!(build_frame(1, INST_WRITE, &[0; TXPACKET_MAX_LEN]).is_err());
+    }
+
+    #[test]
+    fn sync_move_frame_layout() {
+        let frame = sync_move_frame(&[(1, 2048, 500), (2, 0x0102, 0)], 20).unwrap();
+        assert_eq!(&frame[2..5], &[BROADCAST_ID, 2 * 8 + 4, INST_SYNC_WRITE]);
+        assert_eq!(&frame[5..7], &[STS_ACC, SYNC_MOVE_LENGTH]);
+        assert_eq!(&frame[7..15], &[1, 20, 0x00, 0x08, 0, 0, 0xF4, 0x01]);
+        assert_eq!(&frame[15..23], &[2, 20, 0x02, 0x01, 0, 0, 0, 0]);
+        assert!(decode_response(&frame).is_ok());
+    }
+
+    #[test]
+    fn hex_input_formats() {
+        assert_eq!(parse_hex("2A 00 08"), Ok(vec![0x2A, 0x00, 0x08]));
+        assert_eq!(parse_hex("2a,00,08"), Ok(vec![0x2A, 0x00, 0x08]));
+        assert_eq!(parse_hex(" 0x2A  0X0f "), Ok(vec![0x2A, 0x0F]));
+        assert!(parse_hex("2A GG").unwrap_err().contains("GG"));
+        assert_eq!(to_hex(&[0xFF, 0x01, 0x0A]), "FF 01 0A");
+    }
+
+    #[test]
+    fn status_frames_are_checked() {
+        let frame = build_frame(3, 0x20, &[0x00, 0x08]).unwrap();
+        assert_eq!(decode_response(&frame), Ok(Response { id: 3, error: 0x20, params: vec![0x00, 0x08] }));
+
+        let mut corrupted = frame.clone();
+        *corrupted.last_mut().unwrap() ^= 0xFF;
+        assert!(decode_response(&corrupted).unwrap_err().contains("checksum"));
+        assert!(decode_response(&frame[..5]).is_err());
+        assert!(decode_response(&[0xFE, 0xFF, 1, 2, 0, 0]).unwrap_err().contains("header"));
+    }
+
+    #[test]
+    fn interleaved_replies_are_split() {
+        let mut bytes = vec![0x00, 0x42];
+        bytes.extend(build_frame(1, 0, &[]).unwrap());
+        bytes.push(0x13);
+        bytes.extend(build_frame(4, 0, &[0x01]).unwrap());
+        let (responses, garbled) = split_responses(&bytes);
+        assert_eq!(responses.iter().map(|r| r.id).collect::<Vec<_>>(), vec![1, 4]);
+        assert_eq!(garbled, 3);
+    }
+
+    #[test]
+    fn destructive_instructions_and_access() {
+        assert!(!is_destructive(INST_PING) && !is_destructive(INST_READ));
+        assert!(is_destructive(INST_WRITE) && is_destructive(INST_RESET));
+        assert!(needs_broadcast_confirmation(BROADCAST_ID, INST_ACTION));
+        assert!(!needs_broadcast_confirmation(BROADCAST_ID, INST_PING));
+        assert!(!needs_broadcast_confirmation(1, INST_WRITE));
+
+        assert_eq!(access(INST_RESET, &[]), Access::Eeprom);
+        assert_eq!(access(INST_WRITE, &[0x05, 0x02]), Access::Eeprom);
+        assert_eq!(access(INST_WRITE, &[0x2A, 0x00, 0x08]), Access::Command);
+        assert_eq!(access(INST_READ, &[0x05, 0x01]), Access::Command);
+    }
+
+    #[test]
+    fn factory_reset_targets_one_servo() {
+        let mock = MockBackend::new().with_servo(3, MockServo::default());
+        assert_eq!(factory_reset(&mock, 3), Ok(()));
+        assert_eq!(mock.calls(), vec![BackendCall::RawFrame(build_frame(3, INST_RESET, &[]).unwrap())]);
+        assert!(factory_reset(&mock, BROADCAST_ID).is_err());
+        assert!(factory_reset(&mock, 9).is_err());
+    }
+}
@@ -0,0 +1,220 @@
+//! Palette de commandes : actions nommées, recherche approximative au clavier.
+
+/// Action proposée par la palette ; `K` est le type du raccourci clavier de l'interface
+pub struct Action<S, K> {
+    pub name: String,
+    /// Mots supplémentaires pris en compte par la recherche
+    pub keywords: String,
+    pub shortcut: Option<K>,
+    pub enabled: Box<dyn Fn(&S) -> bool>,
+    pub run: Box<dyn Fn(&mut S)>,
+}
+
+impl<S, K> Action<S, K> {
+    pub fn new(name: impl Into<String>, run: impl Fn(&mut S) + 'static) -> Self {
+        Self {
+            name: name.into(),
+            keywords: String::new(),
+            shortcut: None,
+            enabled: Box::new(|_| true),
+            run: Box::new(run),
+        }
+    }
+
+    pub fn keywords(mut self, keywords: impl Into<String>) -> Self {
+        self.keywords = keywords.into();
+        self
+    }
+
+    pub fn shortcut(mut self, shortcut: K) -> Self {
+        self.shortcut = Some(shortcut);
+        self
+    }
+
+    pub fn enabled_when(mut self, enabled: impl Fn(&S) -> bool + 'static) -> Self {
+        self.enabled = Box::new(enabled);
+        self
+    }
+}
+
+/// Score d'une correspondance approximative : les caractères de `query` doivent apparaître
+/// dans l'ordre dans `text` ; les suites contiguës et les débuts de mot comptent davantage.
+pub fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut previous: Option<usize> = None;
+
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (pos..text.len()).find(|&i| text[i] == q)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == found) {
+            score += 3;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 2;
+        }
+        previous = Some(found);
+        pos = found + 1;
+    }
+
+    // À score égal, les textes courts d'abord
+    Some(score * 100 - text.len() as i32)
+}
+
+/// Actions correspondant à `query`, de la meilleure à la moins bonne
+pub fn search<'a, S, K>(actions: &'a [Action<S, K>], query: &str) -> Vec<&'a Action<S, K>> {
+    let mut matches: Vec<(i32, &Action<S, K>)> = actions
+        .iter()
+        .filter_map(|action| {
+            let by_name = fuzzy_score(query, &action.name);
+            let by_keyword = fuzzy_score(query, &format!("{} {}", action.name, action.keywords)).map(|s| s - 50);
+            by_name.max(by_keyword).map(|score| (score, action))
+        })
+        .collect();
+    matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+    matches.into_iter().map(|(_, action)| action).collect()
+}
+
+/// Fenêtre de la palette (Ctrl+P) : recherche en cours et ligne sélectionnée
+#[cfg(feature = "gui")]
+#[derive(Default)]
+pub struct PaletteState {
+    pub open: bool,
+    pub query: String,
+    pub selected: usize,
+}
+
+#[cfg(feature = "gui")]
+pub const PALETTE_SHORTCUT: egui::KeyboardShortcut = egui::KeyboardShortcut::new(egui::Modifiers::COMMAND, egui::Key::P);
+
+#[cfg(feature = "gui")]
+impl PaletteState {
+    /// Ctrl+P ouvre ou ferme la palette. Ouverte, elle est affichée et l'action choisie est
+    /// exécutée ; fermée, ce sont les raccourcis des actions actives qui le sont.
+    pub fn update<S>(&mut self, ctx: &egui::Context, actions: &[Action<S, egui::KeyboardShortcut>], state: &mut S) {
+        if ctx.input_mut(|i| i.consume_shortcut(&PALETTE_SHORTCUT)) {
+            *self = PaletteState { open: !self.open, ..Default::default() };
+        }
+        if self.open {
+            self.show(ctx, actions, state);
+            return;
+        }
+        for action in actions {
+            if let Some(sc) = &action.shortcut {
+                if (action.enabled)(state) && ctx.input_mut(|i| i.consume_shortcut(sc)) {
+                    (action.run)(state);
+                }
+            }
+        }
+    }
+
+    fn show<S>(&mut self, ctx: &egui::Context, actions: &[Action<S, egui::KeyboardShortcut>], state: &mut S) {
+        let results = search(actions, &self.query);
+        self.selected = self.selected.min(results.len().saturating_sub(1));
+
+        let (up, down, enter, escape) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::Escape),
+            )
+        });
+        if escape {
+            self.open = false;
+            return;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        if down && self.selected + 1 < results.len() {
+            self.selected += 1;
+        }
+
+        let mut chosen: Option<&Action<S, egui::KeyboardShortcut>> = None;
+        egui::Window::new("Command palette")
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, [0.0, 60.0])
+            .fixed_size([420.0, 0.0])
+            .show(ctx, |ui| {
+                let input = ui.add(egui::TextEdit::singleline(&mut self.query)
+                    .hint_text("Type a command...")
+                    .desired_width(f32::INFINITY));
+                input.request_focus();
+                if input.changed() {
+                    self.selected = 0;
+                }
+
+                ui.separator();
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for (index, action) in results.iter().enumerate() {
+                        let enabled = (action.enabled)(state);
+                        ui.horizontal(|ui| {
+                            let row = ui.add_enabled(
+                                enabled,
+                                egui::Button::selectable(index == self.selected, &action.name),
+                            );
+                            if row.clicked() {
+                                chosen = Some(action);
+                            }
+                            if let Some(sc) = &action.shortcut {
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    ui.weak(ctx.format_shortcut(sc));
+                                });
+                            }
+                        });
+                    }
+                });
+            });
+
+        if enter {
+            chosen = chosen.or(results.get(self.selected).copied());
+        }
+        if let Some(action) = chosen.filter(|a| (a.enabled)(state)) {
+            (action.run)(state);
+            self.open = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn actions() -> Vec<Action<u32, ()>> {
+        vec![
+            Action::new("Torque off all", |n| *n += 1).keywords("disable release"),
+            Action::new("Rescan bus", |n| *n += 10),
+            Action::new("Emergency stop", |n| *n += 100).keywords("estop halt"),
+        ]
+    }
+
+    #[test]
+    fn fuzzy_score_needs_characters_in_order() {
+        assert!(fuzzy_score("rsb", "Rescan bus").is_some());
+        assert!(fuzzy_score("bsr", "Rescan bus").is_none());
+        // Début de mot et suite contiguë avant des lettres éparses
+        assert!(fuzzy_score("bus", "Rescan bus") > fuzzy_score("rcn", "Rescan bus"));
+    }
+
+    #[test]
+    fn search_ranks_names_before_keywords() {
+        let actions = actions();
+        let names: Vec<&str> = search(&actions, "halt").iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names, ["Emergency stop"]);
+        let names: Vec<&str> = search(&actions, "res").iter().map(|a| a.name.as_str()).collect();
+        assert_eq!(names.first(), Some(&"Rescan bus"));
+        assert_eq!(search(&actions, "").len(), 3);
+    }
+
+    #[test]
+    fn chosen_action_runs_on_state() {
+        let actions = actions();
+        let mut count = 0;
+        (search(&actions, "estop")[0].run)(&mut count);
+        assert_eq!(count, 100);
+    }
+}
@@ -0,0 +1,205 @@
+//! Traitements de télémétrie enregistrés au démarrage : chaque processeur reçoit les relevés
+//! bruts d'un servo et en déduit des valeurs (moyennes, indicateurs maison...), affichées et
+//! exportées comme les mesures.
+//!
+//! L'enregistrement est fait à la compilation dans `main` ; le registre ne manipule que des
+//! `Box<dyn TelemetryProcessor>`, un chargement dynamique pourra s'y brancher tel quel.
+
+use crate::history::History;
+use crate::report::Metric;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+/// Relevés d'un cycle de monitoring ; `None` quand la valeur n'a pas été lue à ce cycle
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct TelemetryFrame {
+    pub servo: u8,
+    /// Secondes depuis le début de la session
+    pub time: f64,
+    pub position: Option<u16>,
+    pub temperature: Option<u8>,
+    pub voltage: Option<f32>,
+    pub current: Option<f32>,
+    pub load: Option<f32>,
+    /// Vitesse signée (pas/s)
+    pub speed: Option<i16>,
+    /// Écart consigne − position (ticks), pour un servo piloté en position
+    pub delta: Option<i32>,
+}
+
+impl TelemetryFrame {
+    pub fn get(&self, metric: Metric) -> Option<f64> {
+        match metric {
+            Metric::Position => self.position.map(f64::from),
+            Metric::Temperature => self.temperature.map(f64::from),
+            Metric::Voltage => self.voltage.map(f64::from),
+            Metric::Current => self.current.map(f64::from),
+            Metric::Load => self.load.map(f64::from),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DerivedValue {
+    /// Nom affiché, aussi utilisé comme colonne d'export
+    pub name: String,
+    pub unit: &'static str,
+    pub value: f64,
+}
+
+pub trait TelemetryProcessor: Send {
+    fn name(&self) -> &str;
+    /// Appelé à chaque relevé ; retourne les valeurs dérivées mises à jour (éventuellement aucune)
+    fn process(&mut self, frame: &TelemetryFrame) -> Vec<DerivedValue>;
+}
+
+#[derive(Default)]
+pub struct ProcessorRegistry {
+    processors: Vec<Box<dyn TelemetryProcessor>>,
+    latest: BTreeMap<u8, BTreeMap<String, DerivedValue>>,
+    history: BTreeMap<String, History>,
+    // Points conservés par série dérivée, comme les graphiques
+    history_capacity: usize,
+}
+
+impl ProcessorRegistry {
+    pub fn register(&mut self, processor: Box<dyn TelemetryProcessor>) -> &mut Self {
+        self.processors.push(processor);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processors.is_empty()
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.processors.iter().map(|p| p.name())
+    }
+
+    /// Passe le relevé à tous les processeurs et mémorise leurs sorties
+    pub fn process(&mut self, frame: &TelemetryFrame) {
+        for processor in &mut self.processors {
+            for derived in processor.process(frame) {
+                let capacity = self.history_capacity;
+                self.history
+                    .entry(derived.name.clone())
+                    .or_insert_with(|| History::new(capacity))
+                    .push(frame.time, derived.value);
+                self.latest.entry(frame.servo).or_default().insert(derived.name.clone(), derived);
+            }
+        }
+    }
+
+    /// Dernières valeurs dérivées d'un servo, par nom
+    pub fn latest(&self, servo: u8) -> impl Iterator<Item = &DerivedValue> {
+        self.latest.get(&servo).into_iter().flat_map(|values| values.values())
+    }
+
+    /// Dernières valeurs de tous les servos
+    pub fn all_latest(&self) -> impl Iterator<Item = (u8, &DerivedValue)> {
+        self.latest.iter().flat_map(|(id, values)| values.values().map(move |v| (*id, v)))
+    }
+
+    /// Séries horodatées par nom, pour les exports
+    pub fn history(&self) -> &BTreeMap<String, History> {
+        &self.history
+    }
+
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.history_capacity = capacity;
+        for history in self.history.values_mut() {
+            history.set_capacity(capacity);
+        }
+    }
+}
+
+/// Exemple : moyenne glissante d'une mesure sur les `window` derniers relevés
+pub struct MovingAverage {
+    metric: Metric,
+    window: usize,
+    name: String,
+    samples: HashMap<u8, VecDeque<f64>>,
+}
+
+impl MovingAverage {
+    pub fn new(metric: Metric, window: usize) -> Self {
+        Self {
+            metric,
+            window: window.max(1),
+            name: format!("{} avg ({})", metric.label(), window.max(1)),
+            samples: HashMap::new(),
+        }
+    }
+}
+
+impl TelemetryProcessor for MovingAverage {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn process(&mut self, frame: &TelemetryFrame) -> Vec<DerivedValue> {
+        let Some(value) = frame.get(self.metric) else {
+            return Vec::new();
+        };
+        let samples = self.samples.entry(frame.servo).or_default();
+        samples.push_back(value);
+        if samples.len() > self.window {
+            samples.pop_front();
+        }
+        let average = samples.iter().sum::<f64>() / samples.len() as f64;
+        vec![DerivedValue { name: self.name.clone(), unit: self.metric.unit(), value: average }]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(servo: u8, time: f64, temperature: Option<u8>) -> TelemetryFrame {
+        TelemetryFrame { servo, time, temperature, ..Default::default() }
+    }
+
+    #[test]
+    fn moving_average_is_per_servo_and_windowed() {
+        let mut average = MovingAverage::new(Metric::Temperature, 2);
+        assert_eq!(average.name(), "Temperature avg (2)");
+        assert_eq!(average.process(&frame(1, 0.0, Some(30)))[0].value, 30.0);
+        assert_eq!(average.process(&frame(1, 1.0, Some(40)))[0].value, 35.0);
+        assert_eq!(average.process(&frame(1, 2.0, Some(50)))[0].value, 45.0);
+        // Un autre servo a sa propre fenêtre
+        assert_eq!(average.process(&frame(2, 2.0, Some(20)))[0].value, 20.0);
+        // Mesure absente à ce cycle : aucune sortie
+        assert!(average.process(&frame(1, 3.0, None)).is_empty());
+        assert_eq!(MovingAverage::new(Metric::Load, 0).name(), "Load avg (1)");
+    }
+
+    #[test]
+    fn registry_keeps_latest_values_and_history() {
+        let mut registry = ProcessorRegistry::default();
+        assert!(registry.is_empty());
+        registry.set_history_capacity(500);
+        registry.register(Box::new(MovingAverage::new(Metric::Temperature, 1)));
+        assert_eq!(registry.names().collect::<Vec<_>>(), vec!["Temperature avg (1)"]);
+
+        for (time, temperature) in [(0.0, 30), (1.0, 31), (2.0, 32)] {
+            registry.process(&frame(4, time, Some(temperature)));
+        }
+        let latest: Vec<f64> = registry.latest(4).map(|v| v.value).collect();
+        assert_eq!(latest, vec![32.0]);
+        assert_eq!(registry.latest(5).count(), 0);
+        assert_eq!(registry.all_latest().map(|(id, v)| (id, v.value)).collect::<Vec<_>>(), vec![(4, 32.0)]);
+        let history = &registry.history()["Temperature avg (1)"];
+        assert_eq!(history.to_vec(), vec![(0.0, 30.0), (1.0, 31.0), (2.0, 32.0)]);
+        assert_eq!(history.capacity(), 500);
+
+        registry.set_history_capacity(1000);
+        assert_eq!(registry.history()["Temperature avg (1)"].capacity(), 1000);
+    }
+
+    #[test]
+    fn frame_metric_lookup() {
+        let frame = TelemetryFrame { position: Some(2048), voltage: Some(12.0), ..Default::default() };
+        assert_eq!(frame.get(Metric::Position), Some(2048.0));
+        assert_eq!(frame.get(Metric::Voltage), Some(12.0));
+        assert_eq!(frame.get(Metric::Current), None);
+    }
+}
@@ -0,0 +1,252 @@
+//! Verrou d'instance par port série : un fichier contenant le PID du processus qui pilote le bus.
+
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::logging;
+
+/// Processus qui détient le verrou d'un port
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LockOwner {
+    pub pid: u32,
+    pub program: String,
+}
+
+impl fmt::Display for LockOwner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (PID {})", self.program, self.pid)
+    }
+}
+
+#[derive(Debug)]
+pub enum LockError {
+    /// Une autre instance vivante pilote déjà ce port
+    Held(LockOwner),
+    Io(io::Error),
+}
+
+impl fmt::Display for LockError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockError::Held(owner) => write!(f, "port already in use by {}", owner),
+            LockError::Io(e) => write!(f, "lock file error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for LockError {}
+
+impl From<io::Error> for LockError {
+    fn from(e: io::Error) -> Self {
+        LockError::Io(e)
+    }
+}
+
+/// Verrou détenu sur un port ; le fichier est supprimé au drop
+#[derive(Debug)]
+pub struct PortLock {
+    port: String,
+    path: PathBuf,
+}
+
+impl PortLock {
+    /// Prend le verrou du port. Un verrou laissé par un processus mort est nettoyé.
+    pub fn acquire(port: &str) -> Result<Self, LockError> {
+        let path = lock_path(port);
+        for _ in 0..2 {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(owner_line(&current_owner()).as_bytes())?;
+                    return Ok(Self { port: port.to_string(), path });
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => match read_owner(&path) {
+                    Some(owner) if owner.pid == std::process::id() => {
+                        return Ok(Self { port: port.to_string(), path });
+                    }
+                    Some(owner) if is_alive(owner.pid) => return Err(LockError::Held(owner)),
+                    // Verrou périmé ou illisible : on le retire et on réessaie
+                    _ => {
+                        let _ = fs::remove_file(&path);
+                    }
+                },
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(io::Error::other(format!("could not create {}", path.display())).into())
+    }
+
+    /// Prend le verrou même s'il est détenu par une autre instance
+    pub fn force(port: &str) -> io::Result<Self> {
+        let path = lock_path(port);
+        fs::write(&path, owner_line(&current_owner()))?;
+        Ok(Self { port: port.to_string(), path })
+    }
+
+    pub fn port(&self) -> &str {
+        &self.port
+    }
+}
+
+impl Drop for PortLock {
+    fn drop(&mut self) {
+        // Ne pas supprimer un verrou repris entre-temps par une autre instance
+        if read_owner(&self.path).is_some_and(|owner| owner.pid == std::process::id()) {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Garde `slot` verrouillé sur `port` (le verrou d'un ancien port est relâché).
+/// Une erreur de fichier n'empêche pas la connexion : le verrou reste indicatif.
+pub fn hold(slot: &mut Option<PortLock>, port: &str, force: bool) -> Result<(), LockOwner> {
+    if slot.as_ref().is_some_and(|lock| lock.port == port) {
+        return Ok(());
+    }
+    *slot = None;
+    let result = if force { PortLock::force(port).map_err(LockError::Io) } else { PortLock::acquire(port) };
+    match result {
+        Ok(lock) => {
+            *slot = Some(lock);
+            Ok(())
+        }
+        Err(LockError::Held(owner)) => Err(owner),
+        Err(LockError::Io(e)) => {
+            log::warn!(target: logging::BUS, "could not lock {}: {}", port, e);
+            Ok(())
+        }
+    }
+}
+
+/// Instance vivante qui détient le port, s'il y en a une
+pub fn owner(port: &str) -> Option<LockOwner> {
+    read_owner(&lock_path(port)).filter(|owner| is_alive(owner.pid))
+}
+
+/// Fichier de verrou du port, dans le répertoire d'exécution de l'utilisateur. Le chemin est
+/// résolu d'abord : un lien udev (`/dev/serial/by-id/...`) et le nœud qu'il désigne partagent
+/// le même verrou. Un nom qui n'est pas un chemin existant (`COM3`) est gardé tel quel.
+pub fn lock_path(port: &str) -> PathBuf {
+    let dir = std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(std::env::temp_dir);
+    let resolved = fs::canonicalize(port).map(|p| p.to_string_lossy().into_owned()).unwrap_or_else(|_| port.to_string());
+    let name: String = resolved
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    dir.join(format!("init-servo-{}.lock", name.trim_matches('_')))
+}
+
+fn current_owner() -> LockOwner {
+    let program = std::env::current_exe()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "unknown".to_string());
+    LockOwner { pid: std::process::id(), program }
+}
+
+// Format : « PID programme »
+fn owner_line(owner: &LockOwner) -> String {
+    format!("{} {}\n", owner.pid, owner.program)
+}
+
+fn read_owner(path: &Path) -> Option<LockOwner> {
+    let content = fs::read_to_string(path).ok()?;
+    let (pid, program) = content.trim().split_once(' ')?;
+    Some(LockOwner { pid: pid.parse().ok()?, program: program.to_string() })
+}
+
+#[cfg(unix)]
+pub fn is_alive(pid: u32) -> bool {
+    // Signal 0 : vérifie l'existence sans rien envoyer ; EPERM = vivant mais à un autre utilisateur
+    let result = unsafe { libc::kill(pid as libc::pid_t, 0) };
+    result == 0 || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Sans moyen de vérifier, on considère le processus vivant (reprise via `force`)
+#[cfg(not(unix))]
+pub fn is_alive(_pid: u32) -> bool {
+    true
+}
+
+/// Choix de l'utilisateur face à un port déjà verrouillé
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConflictChoice {
+    SwitchPort(String),
+    TakeOver,
+}
+
+#[cfg(feature = "gui")]
+mod gui {
+    use super::{ConflictChoice, LockOwner};
+
+    /// Fenêtre affichée tant qu'une autre instance détient `port`
+    pub fn conflict_dialog(ctx: &egui::Context, port: &str, owner: &LockOwner) -> Option<ConflictChoice> {
+        let choice_id = egui::Id::new("port_conflict_choice");
+        let mut selected: String = ctx.data_mut(|d| d.get_temp(choice_id)).unwrap_or_default();
+        let mut choice = None;
+
+        egui::Window::new("Serial port in use")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                ui.label(format!("{} is already controlled by {}.", port, owner));
+                ui.label("Waiting for it to release the port. You can also pick another port or take over the bus.");
+                ui.add_space(6.0);
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("conflict_port")
+                        .selected_text(if selected.is_empty() { "Other port…" } else { selected.as_str() })
+                        .show_ui(ui, |ui| {
+                            for candidate in crate::ports::list_ports().into_iter().filter(|p| p != port) {
+                                let label = candidate.clone();
+                                ui.selectable_value(&mut selected, candidate, label);
+                            }
+                        });
+                    if ui.add_enabled(!selected.is_empty(), egui::Button::new("Use this port")).clicked() {
+                        choice = Some(ConflictChoice::SwitchPort(selected.clone()));
+                    }
+                });
+                if ui.button("⚠ Take over")
+                    .on_hover_text("Both instances will write to the bus until the other one is closed")
+                    .clicked()
+                {
+                    choice = Some(ConflictChoice::TakeOver);
+                }
+            });
+
+        ctx.data_mut(|d| d.insert_temp(choice_id, selected));
+        choice
+    }
+}
+
+#[cfg(feature = "gui")]
+pub use gui::conflict_dialog;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_port_keeps_its_name() {
+        assert!(lock_path("COM3").ends_with("init-servo-COM3.lock"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_shares_the_lock_of_its_target() {
+        let dir = std::env::temp_dir().join(format!("init-servo-portlock-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let device = dir.join("ttyUSB0");
+        let link = dir.join("usb-1a86_USB_Serial-if00-port0");
+        fs::write(&device, "").unwrap();
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&device, &link).unwrap();
+
+        let same = lock_path(link.to_str().unwrap()) == lock_path(device.to_str().unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(same);
+    }
+}
@@ -0,0 +1,156 @@
+//! Énumération des ports série et suivi d'un adaptateur USB qui change de chemin après rebranchement.
+
+use serialport::{available_ports, SerialPortType};
+
+/// Identité USB d'un adaptateur, utilisée pour le retrouver sous un autre chemin
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortIdentity {
+    pub vid: u16,
+    pub pid: u16,
+    pub serial_number: Option<String>,
+}
+
+/// Chemins de tous les ports série disponibles
+pub fn list_ports() -> Vec<String> {
+    available_ports()
+        .map(|ports| ports.into_iter().map(|p| p.port_name).collect())
+        .unwrap_or_default()
+}
+
+fn usb_ports() -> Vec<(String, PortIdentity)> {
+    available_ports()
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|p| match p.port_type {
+            SerialPortType::UsbPort(info) => Some((
+                p.port_name,
+                PortIdentity { vid: info.vid, pid: info.pid, serial_number: info.serial_number },
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Identité USB du port `path`, si c'est un adaptateur USB
+pub fn identify(path: &str) -> Option<PortIdentity> {
+    usb_ports().into_iter().find(|(name, _)| name == path).map(|(_, id)| id)
+}
+
+/// Ouvertures du port en échec avant de chercher l'adaptateur sous un autre chemin
+pub const MIGRATION_FAILURES: u32 = 5;
+
+/// Cherche le nouveau chemin de l'adaptateur `identity` (autre que `old_path`)
+pub fn find_migrated_port(identity: &PortIdentity, old_path: &str) -> Option<String> {
+    pick_migrated_port(usb_ports(), identity, old_path)
+}
+
+// Même VID/PID et même numéro de série. Sans numéro de série, un adaptateur identique ne se
+// distingue pas de l'original : on ne retient le port que s'il est le seul candidat.
+fn pick_migrated_port(ports: Vec<(String, PortIdentity)>, wanted: &PortIdentity, old_path: &str) -> Option<String> {
+    let mut candidates = ports
+        .into_iter()
+        .filter(|(name, id)| name != old_path && id.vid == wanted.vid && id.pid == wanted.pid)
+        .filter(|(_, id)| wanted.serial_number.is_none() || id.serial_number == wanted.serial_number);
+    let (name, _) = candidates.next()?;
+    match wanted.serial_number.is_some() || candidates.next().is_none() {
+        true => Some(name),
+        false => None,
+    }
+}
+
+/// Suivi de l'adaptateur du bus : identité relevée à la connexion, nouveau chemin cherché après
+/// `MIGRATION_FAILURES` ouvertures en échec. Épinglé (`[bus] pin_port`), le port configuré est
+/// seul essayé, pour les montages à plusieurs adaptateurs identiques.
+#[derive(Clone, Debug, Default)]
+pub struct PortTracker {
+    identity: Option<PortIdentity>,
+    failures: u32,
+    pinned: bool,
+}
+
+impl PortTracker {
+    pub fn new(pinned: bool) -> Self {
+        Self { pinned, ..Default::default() }
+    }
+
+    /// Port ouvert : l'adaptateur est mémorisé à la première connexion
+    pub fn connected(&mut self, path: &str) {
+        self.failures = 0;
+        if self.identity.is_none() {
+            self.identity = identify(path);
+        }
+    }
+
+    /// Port choisi par l'utilisateur : l'adaptateur suivi est oublié
+    pub fn reset(&mut self) {
+        self.identity = None;
+        self.failures = 0;
+    }
+
+    /// Ouverture de `path` en échec ; retourne le nouveau chemin de l'adaptateur s'il a été
+    /// retrouvé ailleurs. Sans identité relevée, aucun port n'est deviné.
+    pub fn open_failed(&mut self, path: &str) -> Option<String> {
+        if self.pinned {
+            return None;
+        }
+        self.failures += 1;
+        if self.failures < MIGRATION_FAILURES {
+            return None;
+        }
+        self.failures = 0;
+        find_migrated_port(self.identity.as_ref()?, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(serial: Option<&str>) -> PortIdentity {
+        PortIdentity { vid: 0x1a86, pid: 0x55d3, serial_number: serial.map(String::from) }
+    }
+
+    fn port(name: &str, identity: PortIdentity) -> (String, PortIdentity) {
+        (name.to_string(), identity)
+    }
+
+    #[test]
+    fn serial_number_picks_the_right_adapter() {
+        let ports = vec![
+            port("/dev/ttyACM1", adapter(Some("B"))),
+            port("/dev/ttyACM2", adapter(Some("A"))),
+        ];
+        assert_eq!(pick_migrated_port(ports.clone(), &adapter(Some("A")), "/dev/ttyACM0"), Some("/dev/ttyACM2".into()));
+        assert_eq!(pick_migrated_port(ports, &adapter(Some("C")), "/dev/ttyACM0"), None);
+    }
+
+    #[test]
+    fn other_devices_and_old_path_are_ignored() {
+        let other = PortIdentity { vid: 0x0403, pid: 0x6001, serial_number: Some("A".into()) };
+        let ports = vec![port("/dev/ttyACM0", adapter(Some("A"))), port("/dev/ttyUSB0", other)];
+        assert_eq!(pick_migrated_port(ports, &adapter(Some("A")), "/dev/ttyACM0"), None);
+    }
+
+    #[test]
+    fn without_serial_number_only_a_single_match_is_taken() {
+        let single = vec![port("/dev/ttyACM1", adapter(None))];
+        assert_eq!(pick_migrated_port(single, &adapter(None), "/dev/ttyACM0"), Some("/dev/ttyACM1".into()));
+        let twins = vec![port("/dev/ttyACM1", adapter(None)), port("/dev/ttyACM2", adapter(None))];
+        assert_eq!(pick_migrated_port(twins, &adapter(None), "/dev/ttyACM0"), None);
+    }
+
+    #[test]
+    fn tracker_needs_an_identity_and_respects_the_pin() {
+        // Aucun adaptateur relevé : rien n'est deviné, même après de nombreux échecs
+        let mut tracker = PortTracker::new(false);
+        for _ in 0..MIGRATION_FAILURES * 3 {
+            assert_eq!(tracker.open_failed("/dev/ttyACM0"), None);
+        }
+        let mut pinned = PortTracker::new(true);
+        pinned.identity = Some(adapter(Some("A")));
+        for _ in 0..MIGRATION_FAILURES * 3 {
+            assert_eq!(pinned.open_failed("/dev/ttyACM0"), None);
+        }
+        assert_eq!(pinned.failures, 0);
+    }
+}
@@ -0,0 +1,109 @@
+//! Poses nommées : positions de tous les servos capturées d'un coup, pour revenir à une
+//! attitude connue.
+//!
+//! ```json
+//! { "poses": [ { "name": "repos", "positions": { "1": 2048, "2": 1800 } } ] }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub const POSES_FILE: &str = "poses.json";
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Pose {
+    pub name: String,
+    /// Position (ticks) par ID
+    pub positions: BTreeMap<u8, u16>,
+}
+
+impl Pose {
+    /// Consignes des servos détectés, et IDs de la pose absents du bus
+    pub fn targets(&self, detected: &[u8]) -> (Vec<(u8, u16)>, Vec<u8>) {
+        let present = self.positions.iter().filter(|(id, _)| detected.contains(id)).map(|(&id, &pos)| (id, pos));
+        let missing = self.positions.keys().copied().filter(|id| !detected.contains(id));
+        (present.collect(), missing.collect())
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PoseLibrary {
+    #[serde(default)]
+    pub poses: Vec<Pose>,
+}
+
+impl PoseLibrary {
+    /// Fichier absent : bibliothèque vide
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match std::fs::read_to_string(path) {
+            Ok(text) => serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    /// Enregistre la pose ; une pose du même nom est remplacée sur place
+    pub fn capture(&mut self, name: &str, positions: impl IntoIterator<Item = (u8, u16)>) {
+        let pose = Pose { name: name.to_string(), positions: positions.into_iter().collect() };
+        match self.poses.iter_mut().find(|p| p.name == name) {
+            Some(existing) => *existing = pose,
+            None => self.poses.push(pose),
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.poses.retain(|p| p.name != name);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Pose> {
+        self.poses.iter().find(|p| p.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capture_replaces_a_pose_of_the_same_name() {
+        let mut library = PoseLibrary::default();
+        library.capture("repos", [(1, 2048), (2, 1800)]);
+        library.capture("salut", [(1, 1000)]);
+        library.capture("repos", [(1, 2100)]);
+
+        assert_eq!(library.poses.iter().map(|p| p.name.as_str()).collect::<Vec<_>>(), vec!["repos", "salut"]);
+        assert_eq!(library.get("repos").unwrap().positions, BTreeMap::from([(1, 2100)]));
+        library.remove("salut");
+        assert!(library.get("salut").is_none());
+    }
+
+    #[test]
+    fn targets_skip_servos_missing_from_the_bus() {
+        let pose = Pose { name: "repos".to_string(), positions: BTreeMap::from([(1, 2048), (2, 1800), (5, 3000)]) };
+        assert_eq!(pose.targets(&[1, 5, 7]), (vec![(1, 2048), (5, 3000)], vec![2]));
+    }
+
+    #[test]
+    fn library_round_trips_and_starts_empty() {
+        let dir = std::env::temp_dir().join(format!("init-servo-poses-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(POSES_FILE);
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(PoseLibrary::load(&path), Ok(PoseLibrary::default()));
+
+        let mut library = PoseLibrary::default();
+        library.capture("repos", [(1, 2048), (2, 1800)]);
+        library.save(&path).unwrap();
+        assert_eq!(PoseLibrary::load(&path), Ok(library));
+
+        std::fs::write(&path, "{ \"poses\": [ { \"name\": 3 } ] }").unwrap();
+        assert!(PoseLibrary::load(&path).unwrap_err().contains(POSES_FILE));
+        let _ = std::fs::remove_file(&path);
+    }
+}
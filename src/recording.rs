@@ -0,0 +1,785 @@
+//! Enregistrement d'une session du bus et rejeu hors ligne, pour revoir ce qu'a vu l'utilisateur
+//! sans le matériel.
+//!
+//! Le fichier JSONL contient une ligne par événement, daté en secondes depuis le début :
+//! ```text
+//! {"kind":"header","version":1,"unix_ms":1735732800123}
+//! {"kind":"sample","t":0.012,"id":3,"read":"position","value":2051.0}
+//! {"kind":"command","t":0.249,"source":"ui","command":"Move { id: 3, position: 1000 }"}
+//! {"kind":"write","t":0.250,"id":3,"op":"Goal Position = 1000","ok":true}
+//! ```
+//! `RecordingBackend` enveloppe le pilote : il garde chaque relevé reçu (les lectures en échec ne
+//! sont pas des relevés) et chaque écriture avec son issue ; le worker y ajoute les commandes.
+//!
+//! Au rejeu (`--replay fichier.jsonl`), `ReplayBackend` remplace le pilote : une lecture renvoie le
+//! dernier relevé enregistré avant l'instant de rejeu, une écriture est acceptée sans effet. Le
+//! worker et l'interface tournent normalement, donc courbes, alertes et journal d'événements se
+//! reconstruisent à partir des mêmes relevés ; les commandes y sont ajoutées en annotations.
+
+use crate::backend::ServoBackend;
+use crate::packet;
+use crate::telemetrylog::FLUSH_INTERVAL;
+use crate::worker::Connector;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Version du format, écrite dans l'en-tête
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Multiplicateurs de vitesse proposés au rejeu
+pub const REPLAY_SPEEDS: [f64; 3] = [0.5, 1.0, 4.0];
+
+/// Registre lu par le pilote
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Reading {
+    Ping,
+    Position,
+    Temperature,
+    Voltage,
+    Current,
+    Speed,
+    Load,
+    Mode,
+    Moving,
+}
+
+/// Ligne du fichier
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Entry {
+    Header { version: u32, unix_ms: u64 },
+    Sample { t: f64, id: u8, read: Reading, value: f64 },
+    Write { t: f64, id: u8, op: String, ok: bool },
+    Command { t: f64, source: String, command: String },
+}
+
+/// `session-<unix>.jsonl` dans le répertoire courant
+pub fn default_path() -> PathBuf {
+    let unix_s = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    PathBuf::from(format!("session-{}.jsonl", unix_s))
+}
+
+// --- ENREGISTREMENT ---
+struct Session {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    start: Instant,
+    entries: u64,
+    flushed: Instant,
+}
+
+/// Enregistrement en cours, vu par l'interface
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordingStatus {
+    pub path: PathBuf,
+    pub entries: u64,
+    pub elapsed: f64,
+}
+
+/// Enregistreur partagé entre le pilote, le worker et l'interface ; sans session, tout est ignoré
+#[derive(Clone, Default)]
+pub struct Recorder {
+    session: Arc<Mutex<Option<Session>>>,
+}
+
+impl Recorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Commence un enregistrement dans `path` (remplacé s'il existe) ; arrête le précédent
+    pub fn start(&self, path: impl Into<PathBuf>) -> Result<(), String> {
+        let path = path.into();
+        self.stop();
+        let file = File::create(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut writer = BufWriter::new(file);
+        let unix_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0);
+        write_entry(&mut writer, &Entry::Header { version: FORMAT_VERSION, unix_ms }).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let now = Instant::now();
+        *self.session.lock().unwrap() = Some(Session { path, writer, start: now, entries: 0, flushed: now });
+        Ok(())
+    }
+
+    /// Termine l'enregistrement ; renvoie le fichier écrit
+    pub fn stop(&self) -> Option<PathBuf> {
+        let mut session = self.session.lock().unwrap().take()?;
+        if let Err(e) = session.writer.flush() {
+            log::warn!("{}: {}", session.path.display(), e);
+        }
+        Some(session.path)
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.session.lock().unwrap().is_some()
+    }
+
+    pub fn status(&self) -> Option<RecordingStatus> {
+        self.session.lock().unwrap().as_ref().map(|s| RecordingStatus {
+            path: s.path.clone(),
+            entries: s.entries,
+            elapsed: s.start.elapsed().as_secs_f64(),
+        })
+    }
+
+    /// Relevé reçu du pilote
+    pub fn sample(&self, id: u8, read: Reading, value: f64) {
+        self.append(|t| Entry::Sample { t, id, read, value });
+    }
+
+    /// Écriture envoyée au pilote ; le libellé n'est formaté que pendant un enregistrement
+    pub fn write(&self, id: u8, op: impl FnOnce() -> String, ok: bool) {
+        self.append(|t| Entry::Write { t, id, op: op(), ok });
+    }
+
+    /// Commande prise en charge par le worker
+    pub fn command(&self, source: &str, command: &impl fmt::Debug) {
+        self.append(|t| Entry::Command { t, source: source.to_string(), command: format!("{:?}", command) });
+    }
+
+    fn append(&self, entry: impl FnOnce(f64) -> Entry) {
+        let mut guard = self.session.lock().unwrap();
+        let Some(session) = guard.as_mut() else { return };
+        // À la milliseconde : assez pour les courbes, et des lignes courtes
+        let t = (session.start.elapsed().as_secs_f64() * 1000.0).round() / 1000.0;
+        let entry = entry(t);
+        let mut result = write_entry(&mut session.writer, &entry);
+        session.entries += 1;
+        if result.is_ok() && session.flushed.elapsed() >= FLUSH_INTERVAL {
+            result = session.writer.flush();
+            session.flushed = Instant::now();
+        }
+        // Disque plein ou fichier retiré : l'enregistrement s'arrête plutôt que de perdre des lignes
+        if let Err(e) = result {
+            log::warn!("{}: {}; recording stopped", session.path.display(), e);
+            *guard = None;
+        }
+    }
+}
+
+fn write_entry(writer: &mut impl Write, entry: &Entry) -> std::io::Result<()> {
+    serde_json::to_writer(&mut *writer, entry)?;
+    writer.write_all(b"\n")
+}
+
+/// Garde chaque relevé reçu et chaque écriture du pilote enveloppé
+pub struct RecordingBackend {
+    inner: Box<dyn ServoBackend>,
+    recorder: Recorder,
+}
+
+impl RecordingBackend {
+    pub fn new(inner: Box<dyn ServoBackend>, recorder: Recorder) -> Self {
+        Self { inner, recorder }
+    }
+
+    fn read<T: Copy>(&self, id: u8, read: Reading, value: impl Fn(T) -> f64, f: impl FnOnce(&dyn ServoBackend) -> Option<T>) -> Option<T> {
+        let result = f(self.inner.as_ref());
+        if let Some(v) = result {
+            self.recorder.sample(id, read, value(v));
+        }
+        result
+    }
+
+    fn write(&self, id: u8, op: impl FnOnce() -> String, f: impl FnOnce(&dyn ServoBackend) -> Result<(), String>) -> Result<(), String> {
+        let result = f(self.inner.as_ref());
+        self.recorder.write(id, op, result.is_ok());
+        result
+    }
+}
+
+impl ServoBackend for RecordingBackend {
+    fn ping_servo(&self, id: u8) -> bool {
+        // Seules les réponses comptent : un ID absent n'a rien envoyé
+        let found = self.inner.ping_servo(id);
+        if found {
+            self.recorder.sample(id, Reading::Ping, 1.0);
+        }
+        found
+    }
+
+    fn list_servos(&self) -> Vec<u8> {
+        let servos = self.inner.list_servos();
+        for &id in &servos {
+            self.recorder.sample(id, Reading::Ping, 1.0);
+        }
+        servos
+    }
+
+    fn read_position(&self, id: u8) -> Option<u16> {
+        self.read(id, Reading::Position, f64::from, |b| b.read_position(id))
+    }
+
+    fn read_temperature(&self, id: u8) -> Option<u8> {
+        self.read(id, Reading::Temperature, f64::from, |b| b.read_temperature(id))
+    }
+
+    fn read_voltage(&self, id: u8) -> Option<f32> {
+        self.read(id, Reading::Voltage, f64::from, |b| b.read_voltage(id))
+    }
+
+    fn read_current(&self, id: u8) -> Option<f32> {
+        self.read(id, Reading::Current, f64::from, |b| b.read_current(id))
+    }
+
+    fn read_speed(&self, id: u8) -> Option<i16> {
+        self.read(id, Reading::Speed, f64::from, |b| b.read_speed(id))
+    }
+
+    fn read_load(&self, id: u8) -> Option<f32> {
+        self.read(id, Reading::Load, f64::from, |b| b.read_load(id))
+    }
+
+    fn read_mode(&self, id: u8) -> Option<u8> {
+        self.read(id, Reading::Mode, f64::from, |b| b.read_mode(id))
+    }
+
+    fn is_moving(&self, id: u8) -> Option<bool> {
+        self.read(id, Reading::Moving, |moving| if moving { 1.0 } else { 0.0 }, |b| b.is_moving(id))
+    }
+
+    fn move_to(&self, id: u8, position: u16, speed: u16, acceleration: u8, wait: bool) -> Option<bool> {
+        let result = self.inner.move_to(id, position, speed, acceleration, wait);
+        let op = || format!("Goal Position = {} (speed {}, acc {})", position, speed, acceleration);
+        self.recorder.write(id, op, result.is_some());
+        result
+    }
+
+    fn write_position(&self, id: u8, position: u16) -> Option<bool> {
+        let result = self.inner.write_position(id, position);
+        self.recorder.write(id, || format!("Goal Position = {}", position), result.is_some());
+        result
+    }
+
+    fn enable_torque(&self, id: u8) -> Result<(), String> {
+        self.write(id, || "Torque Enable = 1".to_string(), |b| b.enable_torque(id))
+    }
+
+    fn disable_torque(&self, id: u8) -> Result<(), String> {
+        self.write(id, || "Torque Enable = 0".to_string(), |b| b.disable_torque(id))
+    }
+
+    fn rotate(&self, id: u8, speed: i16) -> Result<(), String> {
+        self.write(id, || format!("wheel speed = {}", speed), |b| b.rotate(id, speed))
+    }
+
+    fn set_mode(&self, id: u8, mode: u8) -> Result<(), String> {
+        self.write(id, || format!("Mode = {}", mode), |b| b.set_mode(id, mode))
+    }
+
+    fn change_id(&self, id: u8, new_id: u8) -> Result<(), String> {
+        self.write(id, || format!("ID = {}", new_id), |b| b.change_id(id, new_id))
+    }
+
+    // Lectures directes hors relevés ; écritures et trames gardées comme les autres écritures
+    fn read_register(&self, id: u8, address: u8, size: u8) -> Result<Vec<u8>, String> {
+        self.inner.read_register(id, address, size)
+    }
+
+    fn write_register(&self, id: u8, address: u8, data: &[u8]) -> Result<(), String> {
+        self.write(id, || format!("@{} = {:?}", address, data), |b| b.write_register(id, address, data))
+    }
+
+    fn send_raw(&self, frame: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        let result = self.inner.send_raw(frame);
+        let id = frame.get(2).copied().unwrap_or(0);
+        self.recorder.write(id, || format!("raw frame {}", packet::to_hex(frame)), result.is_ok());
+        result
+    }
+
+    fn broadcast_ping(&self, window: Duration) -> Result<Vec<u8>, String> {
+        self.inner.broadcast_ping(window)
+    }
+}
+
+// --- REJEU ---
+/// Commande enregistrée, rejouée en annotation
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecordedCommand {
+    pub t: f64,
+    pub source: String,
+    pub command: String,
+}
+
+/// Fichier d'enregistrement chargé en mémoire
+#[derive(Clone, Debug, Default)]
+pub struct Recording {
+    samples: HashMap<(u8, Reading), Vec<(f64, f64)>>,
+    commands: Vec<RecordedCommand>,
+    ids: Vec<u8>,
+    duration: f64,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = File::open(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let mut recording = Self::default();
+        let mut ids = BTreeSet::new();
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| format!("{}: {}", path.display(), e))?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry: Entry = serde_json::from_str(&line).map_err(|e| format!("{}, line {}: {}", path.display(), index + 1, e))?;
+            let t = match entry {
+                Entry::Header { version, .. } if version > FORMAT_VERSION => {
+                    return Err(format!("{}: format version {} is newer than this program ({})", path.display(), version, FORMAT_VERSION));
+                }
+                Entry::Header { .. } => continue,
+                Entry::Sample { t, id, read, value } => {
+                    ids.insert(id);
+                    recording.samples.entry((id, read)).or_default().push((t, value));
+                    t
+                }
+                Entry::Write { t, .. } => t,
+                Entry::Command { t, source, command } => {
+                    recording.commands.push(RecordedCommand { t, source, command });
+                    t
+                }
+            };
+            recording.duration = recording.duration.max(t);
+        }
+        // Lignes écrites par plusieurs threads : l'ordre du fichier n'est pas garanti
+        for samples in recording.samples.values_mut() {
+            samples.sort_by(|a, b| a.0.total_cmp(&b.0));
+        }
+        recording.commands.sort_by(|a, b| a.t.total_cmp(&b.t));
+        // Le rejeu commence à la première ligne, pas au clic sur Record
+        let first = recording
+            .samples
+            .values()
+            .filter_map(|samples| samples.first().map(|(t, _)| *t))
+            .chain(recording.commands.first().map(|c| c.t))
+            .fold(recording.duration, f64::min);
+        for samples in recording.samples.values_mut() {
+            samples.iter_mut().for_each(|(t, _)| *t -= first);
+        }
+        recording.commands.iter_mut().for_each(|c| c.t -= first);
+        recording.duration -= first;
+        recording.ids = ids.into_iter().collect();
+        Ok(recording)
+    }
+
+    /// Servos qui ont répondu au moins une fois
+    pub fn ids(&self) -> &[u8] {
+        &self.ids
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    /// Dernier relevé à l'instant `t` ; avant le premier, le premier. `None` si jamais relevé.
+    pub fn value(&self, id: u8, read: Reading, t: f64) -> Option<f64> {
+        let samples = self.samples.get(&(id, read))?;
+        let index = samples.partition_point(|(at, _)| *at <= t);
+        samples.get(index.saturating_sub(1)).map(|(_, value)| *value)
+    }
+}
+
+#[derive(Clone, Debug)]
+struct Clock {
+    /// Instant de rejeu à `anchor`
+    base: f64,
+    anchor: Instant,
+    speed: f64,
+    paused: bool,
+    /// Commandes déjà rendues par `take_commands`
+    commands_shown: usize,
+}
+
+/// Rejeu partagé entre le pilote de rejeu et les commandes de l'interface
+#[derive(Clone)]
+pub struct Replay {
+    path: PathBuf,
+    recording: Arc<Recording>,
+    clock: Arc<Mutex<Clock>>,
+}
+
+impl Replay {
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, String> {
+        let path = path.into();
+        let recording = Recording::load(&path)?;
+        if recording.ids().is_empty() {
+            return Err(format!("{}: no servo answered during this recording", path.display()));
+        }
+        let clock = Clock { base: 0.0, anchor: Instant::now(), speed: 1.0, paused: false, commands_shown: 0 };
+        Ok(Self { path, recording: Arc::new(recording), clock: Arc::new(Mutex::new(clock)) })
+    }
+
+    /// `--replay FICHIER` ; `None` sans le drapeau
+    pub fn from_args(args: &[String]) -> Result<Option<Self>, String> {
+        let Some(i) = args.iter().position(|a| a == "--replay") else { return Ok(None) };
+        let path = args.get(i + 1).ok_or("--replay expects a recording file")?;
+        Self::open(path).map(Some)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Nom de port affiché et verrouillé à la place du port série
+    pub fn port(&self) -> String {
+        format!("replay:{}", self.path.display())
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.recording.duration()
+    }
+
+    /// Instant de rejeu, en secondes depuis le début de l'enregistrement
+    pub fn time(&self) -> f64 {
+        let clock = self.clock.lock().unwrap();
+        Self::time_of(&clock).min(self.duration())
+    }
+
+    fn time_of(clock: &Clock) -> f64 {
+        match clock.paused {
+            true => clock.base,
+            false => clock.base + clock.anchor.elapsed().as_secs_f64() * clock.speed,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.time() >= self.duration()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.clock.lock().unwrap().paused
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        self.rebase(|clock| clock.paused = paused);
+    }
+
+    pub fn speed(&self) -> f64 {
+        self.clock.lock().unwrap().speed
+    }
+
+    pub fn set_speed(&self, speed: f64) {
+        self.rebase(|clock| clock.speed = speed);
+    }
+
+    /// Retour au début ; les commandes seront annotées de nouveau
+    pub fn restart(&self) {
+        let mut clock = self.clock.lock().unwrap();
+        clock.base = 0.0;
+        clock.anchor = Instant::now();
+        clock.commands_shown = 0;
+    }
+
+    /// Fige l'instant courant avant de changer d'allure, pour que le temps ne saute pas
+    fn rebase(&self, f: impl FnOnce(&mut Clock)) {
+        let mut clock = self.clock.lock().unwrap();
+        clock.base = Self::time_of(&clock).min(self.recording.duration());
+        clock.anchor = Instant::now();
+        f(&mut clock);
+    }
+
+    /// Commandes dont l'heure est passée depuis le dernier appel
+    pub fn take_commands(&self) -> Vec<RecordedCommand> {
+        let mut clock = self.clock.lock().unwrap();
+        let now = Self::time_of(&clock);
+        let due = self.recording.commands.partition_point(|c| c.t <= now);
+        let start = clock.commands_shown.min(due);
+        clock.commands_shown = due;
+        self.recording.commands[start..due].to_vec()
+    }
+
+    pub fn backend(&self) -> ReplayBackend {
+        ReplayBackend { replay: self.clone() }
+    }
+
+    /// Ouverture du pilote de rejeu pour `worker::ServoWorker::with_connector`, quel que soit le port
+    pub fn connector(&self) -> Connector {
+        let replay = self.clone();
+        Box::new(move |_| Ok(Box::new(replay.backend()) as Box<dyn ServoBackend>))
+    }
+
+    /// Bandeau des interfaces
+    pub fn banner(&self) -> String {
+        let name = self.path.file_name().map_or_else(|| self.path.display().to_string(), |n| n.to_string_lossy().into_owned());
+        format!("REPLAY — {}, no hardware connected", name)
+    }
+}
+
+/// Pilote qui rend les relevés enregistrés à l'instant de rejeu
+pub struct ReplayBackend {
+    replay: Replay,
+}
+
+impl ReplayBackend {
+    fn value(&self, id: u8, read: Reading) -> Option<f64> {
+        self.replay.recording.value(id, read, self.replay.time())
+    }
+}
+
+impl ServoBackend for ReplayBackend {
+    fn ping_servo(&self, id: u8) -> bool {
+        self.replay.recording.ids().contains(&id)
+    }
+
+    fn list_servos(&self) -> Vec<u8> {
+        self.replay.recording.ids().to_vec()
+    }
+
+    fn read_position(&self, id: u8) -> Option<u16> {
+        self.value(id, Reading::Position).map(|v| v as u16)
+    }
+
+    fn read_temperature(&self, id: u8) -> Option<u8> {
+        self.value(id, Reading::Temperature).map(|v| v as u8)
+    }
+
+    fn read_voltage(&self, id: u8) -> Option<f32> {
+        self.value(id, Reading::Voltage).map(|v| v as f32)
+    }
+
+    fn read_current(&self, id: u8) -> Option<f32> {
+        self.value(id, Reading::Current).map(|v| v as f32)
+    }
+
+    fn read_speed(&self, id: u8) -> Option<i16> {
+        self.value(id, Reading::Speed).map(|v| v as i16)
+    }
+
+    fn read_load(&self, id: u8) -> Option<f32> {
+        self.value(id, Reading::Load).map(|v| v as f32)
+    }
+
+    fn read_mode(&self, id: u8) -> Option<u8> {
+        self.value(id, Reading::Mode).map(|v| v as u8)
+    }
+
+    fn is_moving(&self, id: u8) -> Option<bool> {
+        self.value(id, Reading::Moving).map(|v| v != 0.0)
+    }
+
+    // Écritures acceptées sans effet : les relevés suivent l'enregistrement, pas les consignes
+    fn move_to(&self, _id: u8, _position: u16, _speed: u16, _acceleration: u8, _wait: bool) -> Option<bool> {
+        Some(true)
+    }
+
+    fn write_position(&self, _id: u8, _position: u16) -> Option<bool> {
+        Some(true)
+    }
+
+    fn enable_torque(&self, _id: u8) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn disable_torque(&self, _id: u8) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn rotate(&self, _id: u8, _speed: i16) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn set_mode(&self, _id: u8, _mode: u8) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn change_id(&self, id: u8, _new_id: u8) -> Result<(), String> {
+        Err(format!("ID {}: cannot change an ID while replaying a recording", id))
+    }
+
+    // Aucun registre enregistré : les accès directs échouent, sauf la diffusion qui retrouve les
+    // servos de l'enregistrement
+    fn read_register(&self, id: u8, address: u8, _size: u8) -> Result<Vec<u8>, String> {
+        Err(format!("ID {}: register {} was not recorded", id, address))
+    }
+
+    fn write_register(&self, id: u8, _address: u8, _data: &[u8]) -> Result<(), String> {
+        Err(format!("ID {}: cannot write registers while replaying a recording", id))
+    }
+
+    fn send_raw(&self, _frame: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        Err("cannot send raw frames while replaying a recording".to_string())
+    }
+
+    fn broadcast_ping(&self, _window: Duration) -> Result<Vec<u8>, String> {
+        let frames = self.replay.recording.ids().iter().map(|&id| packet::build_frame(id, 0, &[]));
+        frames.collect::<Result<Vec<_>, _>>().map(|frames| frames.concat())
+    }
+}
+
+#[cfg(feature = "gui")]
+mod gui {
+    use super::{default_path, Recorder, Replay, REPLAY_SPEEDS};
+
+    /// Bouton d'enregistrement de session, avec le fichier et le nombre de lignes pendant
+    /// l'enregistrement ; une erreur d'ouverture reste affichée à côté
+    pub fn record_button(ui: &mut egui::Ui, recorder: &Recorder) {
+        let error_id = egui::Id::new("recording_error");
+        match recorder.status() {
+            Some(status) => {
+                let text = egui::RichText::new(format!("⏹ Stop recording ({:.0} s)", status.elapsed)).color(ui.visuals().error_fg_color);
+                let hover = format!("Recording to {} ({} lines)", status.path.display(), status.entries);
+                if ui.button(text).on_hover_text(hover).clicked() {
+                    recorder.stop();
+                }
+                // Durée affichée à jour
+                ui.ctx().request_repaint_after(std::time::Duration::from_secs(1));
+            }
+            None => {
+                let hover = "Save every command and telemetry sample to a JSONL file, for --replay";
+                if ui.button("⏺ Record").on_hover_text(hover).clicked() {
+                    let result = recorder.start(default_path());
+                    ui.ctx().data_mut(|d| d.insert_temp(error_id, result.err()));
+                }
+                if let Some(error) = ui.ctx().data_mut(|d| d.get_temp::<Option<String>>(error_id)).flatten() {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+            }
+        }
+    }
+
+    /// Pause, vitesse, retour au début et avancement du rejeu
+    pub fn replay_controls(ui: &mut egui::Ui, replay: &Replay) {
+        let paused = replay.is_paused();
+        if ui.button(if paused { "▶ Play" } else { "⏸ Pause" }).clicked() {
+            replay.set_paused(!paused);
+        }
+        if ui.button("⏮ Restart").on_hover_text("Back to the start; plots keep the samples already shown").clicked() {
+            replay.restart();
+        }
+        let speed = replay.speed();
+        for candidate in REPLAY_SPEEDS {
+            if ui.selectable_label(speed == candidate, format!("{}×", candidate)).clicked() {
+                replay.set_speed(candidate);
+            }
+        }
+        let (time, duration) = (replay.time(), replay.duration());
+        let progress = if duration > 0.0 { (time / duration) as f32 } else { 1.0 };
+        ui.add(egui::ProgressBar::new(progress).desired_width(160.0).text(format!("{:.1} / {:.1} s", time, duration)));
+        if !paused && !replay.is_finished() {
+            ui.ctx().request_repaint_after(std::time::Duration::from_millis(200));
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+pub use gui::{record_button, replay_controls};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{MockBackend, MockServo};
+
+    fn temp_file(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("init-servo-{}-{}.jsonl", name, std::process::id()))
+    }
+
+    // Sans l'instant, qui dépend de l'horloge
+    fn untimed(entry: Entry) -> Entry {
+        match entry {
+            Entry::Sample { id, read, value, .. } => Entry::Sample { t: 0.0, id, read, value },
+            Entry::Write { id, op, ok, .. } => Entry::Write { t: 0.0, id, op, ok },
+            Entry::Command { source, command, .. } => Entry::Command { t: 0.0, source, command },
+            header => header,
+        }
+    }
+
+    #[test]
+    fn recording_keeps_readings_writes_and_commands() {
+        let path = temp_file("record");
+        let mock = MockBackend::new().with_servo(3, MockServo { position: 1500, ..MockServo::default() });
+        let recorder = Recorder::new();
+        let bus = RecordingBackend::new(Box::new(mock), recorder.clone());
+        // Sans session, rien n'est gardé
+        bus.read_position(3);
+        assert_eq!(recorder.status(), None);
+
+        recorder.start(&path).unwrap();
+        assert_eq!(bus.read_position(3), Some(1500));
+        // Lecture en échec : pas un relevé
+        assert_eq!(bus.read_position(4), None);
+        assert!(bus.enable_torque(4).is_err());
+        recorder.command("ui", &"Stop");
+        assert_eq!(recorder.status().unwrap().entries, 3);
+        assert_eq!(recorder.stop(), Some(path.clone()));
+        assert!(!recorder.is_recording());
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        let entries: Vec<Entry> = content.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert!(matches!(entries[0], Entry::Header { version: FORMAT_VERSION, .. }));
+        assert_eq!(
+            entries[1..].iter().cloned().map(untimed).collect::<Vec<_>>(),
+            vec![
+                Entry::Sample { t: 0.0, id: 3, read: Reading::Position, value: 1500.0 },
+                Entry::Write { t: 0.0, id: 4, op: "Torque Enable = 1".to_string(), ok: false },
+                Entry::Command { t: 0.0, source: "ui".to_string(), command: "\"Stop\"".to_string() },
+            ]
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    const SESSION: &str = r#"{"kind":"header","version":1,"unix_ms":0}
+{"kind":"sample","t":2.5,"id":3,"read":"position","value":1200.0}
+{"kind":"sample","t":1.5,"id":3,"read":"position","value":1000.0}
+{"kind":"command","t":2.0,"source":"ui","command":"Move"}
+
+{"kind":"write","t":4.0,"id":3,"op":"Torque Enable = 0","ok":true}
+{"kind":"sample","t":3.0,"id":5,"read":"temperature","value":41.0}
+"#;
+
+    #[test]
+    fn loaded_recording_starts_at_its_first_line() {
+        let path = temp_file("load");
+        std::fs::write(&path, SESSION).unwrap();
+        let recording = Recording::load(&path).unwrap();
+        assert_eq!(recording.ids(), &[3, 5]);
+        assert_eq!(recording.duration(), 2.5);
+        assert_eq!(recording.value(3, Reading::Position, 0.0), Some(1000.0));
+        assert_eq!(recording.value(3, Reading::Position, 0.9), Some(1000.0));
+        assert_eq!(recording.value(3, Reading::Position, 1.0), Some(1200.0));
+        // Avant son premier relevé, un servo rend ce premier relevé
+        assert_eq!(recording.value(5, Reading::Temperature, 0.0), Some(41.0));
+        assert_eq!(recording.value(5, Reading::Position, 1.0), None);
+
+        std::fs::write(&path, SESSION.replace("\"version\":1", "\"version\":2")).unwrap();
+        assert!(Recording::load(&path).unwrap_err().contains("newer"));
+        std::fs::write(&path, "{\"kind\":\"header\",\"version\":1,\"unix_ms\":0}\nnot json\n").unwrap();
+        assert!(Recording::load(&path).unwrap_err().contains("line 2"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn replay_answers_from_the_recording() {
+        let path = temp_file("replay");
+        std::fs::write(&path, SESSION).unwrap();
+        let replay = Replay::from_args(&["--replay".to_string(), path.display().to_string()]).unwrap().unwrap();
+        replay.set_paused(true);
+        replay.restart();
+        assert_eq!(replay.time(), 0.0);
+        assert!(replay.banner().starts_with("REPLAY — init-servo-replay-"));
+
+        let bus = replay.backend();
+        assert_eq!(bus.list_servos(), vec![3, 5]);
+        assert!(bus.ping_servo(5) && !bus.ping_servo(4));
+        assert_eq!(bus.read_position(3), Some(1000));
+        assert_eq!(bus.read_temperature(5), Some(41));
+        // Écritures sans effet, changement d'ID et registres refusés
+        assert_eq!(bus.move_to(3, 3000, 0, 0, false), Some(true));
+        assert_eq!(bus.read_position(3), Some(1000));
+        assert!(bus.change_id(3, 4).is_err());
+        assert!(bus.read_register(3, 56, 2).is_err());
+        let replies = bus.broadcast_ping(Duration::ZERO).unwrap();
+        assert_eq!(crate::hotplug::FastScan::from_bytes(&replies).ids, vec![3, 5]);
+        // La commande est à 0,5 s : pas encore due
+        assert!(replay.take_commands().is_empty());
+
+        assert!(Replay::from_args(&["--replay".to_string()]).is_err());
+        assert!(matches!(Replay::from_args(&[]), Ok(None)));
+        std::fs::write(&path, "{\"kind\":\"command\",\"t\":0.0,\"source\":\"ui\",\"command\":\"Scan\"}\n").unwrap();
+        assert!(Replay::open(&path).err().unwrap().contains("no servo answered"));
+        std::fs::remove_file(&path).unwrap();
+    }
+}
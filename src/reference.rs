@@ -0,0 +1,141 @@
+//! Export CSV de l'historique de monitoring et import de ces fichiers comme traces de référence.
+//!
+//! Format : première colonne = temps (s), colonnes suivantes = séries nommées ; une cellule
+//! vide signifie « pas de mesure à cet instant ». Les lignes commençant par `#` sont des
+//! commentaires (servo, date d'export), ignorés à l'import.
+//!
+//! ```text
+//! # servo ID 1, exported 2025-01-01 12:00 UTC
+//! time_s,position,temperature
+//! 0.100,2048,31
+//! 0.200,2051,
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Séries d'un fichier de référence, indexées par nom de colonne
+#[derive(Clone, Debug)]
+pub struct ReferenceData {
+    pub name: String,
+    pub series: BTreeMap<String, Vec<(f64, f64)>>,
+}
+
+impl ReferenceData {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let name = path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+        let series = parse_csv(&text).map_err(|e| format!("{}: {}", name, e))?;
+        Ok(Self { name, series })
+    }
+
+    pub fn get(&self, column: &str) -> Option<&[(f64, f64)]> {
+        self.series.get(column).map(Vec::as_slice)
+    }
+}
+
+/// Analyse un CSV ; les erreurs indiquent la ligne et la colonne fautives (à partir de 1)
+pub fn parse_csv(text: &str) -> Result<BTreeMap<String, Vec<(f64, f64)>>, String> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty() && !l.trim_start().starts_with('#'));
+    let (header_index, header) = lines.next().ok_or("empty file")?;
+    let columns: Vec<String> = header.split(',').map(|c| c.trim().to_string()).collect();
+    if columns.len() < 2 {
+        return Err(format!("line {}: expected a time column and at least one data column", header_index + 1));
+    }
+
+    let mut series: BTreeMap<String, Vec<(f64, f64)>> =
+        columns[1..].iter().map(|c| (c.clone(), Vec::new())).collect();
+
+    for (index, line) in lines {
+        let line_no = index + 1;
+        let cells: Vec<&str> = line.split(',').map(str::trim).collect();
+        if cells.len() > columns.len() {
+            return Err(format!("line {}: {} columns, header has {}", line_no, cells.len(), columns.len()));
+        }
+        let parse = |col: usize| -> Result<f64, String> {
+            cells[col].parse::<f64>().map_err(|_| {
+                format!("line {}, column {} ({}): invalid number '{}'", line_no, col + 1, columns[col], cells[col])
+            })
+        };
+        let time = parse(0)?;
+        for (col, cell) in cells.iter().enumerate().skip(1) {
+            if cell.is_empty() {
+                continue;
+            }
+            let value = parse(col)?;
+            if let Some(points) = series.get_mut(&columns[col]) {
+                points.push((time, value));
+            }
+        }
+    }
+
+    Ok(series)
+}
+
+/// Fusionne des séries temporelles en un CSV (une ligne par instant distinct), précédé des
+/// lignes de commentaire `comments`
+pub fn to_csv(comments: &[String], columns: &[(&str, &[(f64, f64)])]) -> String {
+    // Clé en millisecondes pour regrouper les mesures d'un même cycle
+    let mut rows: BTreeMap<i64, Vec<Option<f64>>> = BTreeMap::new();
+    for (index, (_, points)) in columns.iter().enumerate() {
+        for &(t, v) in points.iter() {
+            let row = rows.entry((t * 1000.0).round() as i64).or_insert_with(|| vec![None; columns.len()]);
+            row[index] = Some(v);
+        }
+    }
+
+    let mut out = String::new();
+    for comment in comments {
+        out.push_str(&format!("# {}\n", comment));
+    }
+    out.push_str("time_s");
+    for (name, _) in columns {
+        out.push(',');
+        out.push_str(name);
+    }
+    out.push('\n');
+    for (ms, values) in rows {
+        out.push_str(&format!("{:.3}", ms as f64 / 1000.0));
+        for value in values {
+            out.push(',');
+            if let Some(v) = value {
+                out.push_str(&v.to_string());
+            }
+        }
+        out.push('\n');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exported_file_reads_back() {
+        let position = [(0.1, 2048.0), (0.2, 2051.0)];
+        let temperature = [(0.1, 31.0)];
+        let text = to_csv(&["servo ID 1".to_string()], &[("position", &position), ("temperature", &temperature)]);
+        assert_eq!(text, "# servo ID 1\ntime_s,position,temperature\n0.100,2048,31\n0.200,2051,\n");
+
+        let series = parse_csv(&text).unwrap();
+        assert_eq!(series["position"], position);
+        assert_eq!(series["temperature"], temperature);
+    }
+
+    #[test]
+    fn errors_point_at_the_file_line_past_comments() {
+        let header_only_time = "# servo ID 1\n# exported 2025-01-01 12:00 UTC\ntime_s\n0.100\n";
+        assert_eq!(parse_csv(header_only_time).unwrap_err(), "line 3: expected a time column and at least one data column");
+
+        let bad_cell = "# servo ID 1\ntime_s,position\n0.100,2048\n\n0.200,abc\n";
+        assert_eq!(parse_csv(bad_cell).unwrap_err(), "line 5, column 2 (position): invalid number 'abc'");
+        assert_eq!(parse_csv("# only a comment\n").unwrap_err(), "empty file");
+    }
+}
@@ -0,0 +1,79 @@
+//! Comparaison registre par registre de deux servos, avec cache des lectures.
+
+use crate::registers::{Register, RegisterPort, REGISTERS};
+use std::collections::HashMap;
+
+/// Valeurs déjà lues, par (ID, adresse) : relancer la comparaison ne relit que ce qui manque
+#[derive(Debug, Default)]
+pub struct RegisterCache {
+    values: HashMap<(u8, u8), i32>,
+}
+
+impl RegisterCache {
+    pub fn read(&mut self, bus: &mut RegisterPort, id: u8, register: &Register) -> Option<i32> {
+        if let Some(&value) = self.values.get(&(id, register.address)) {
+            return Some(value);
+        }
+        let value = bus.read(id, register).ok()?;
+        self.values.insert((id, register.address), value);
+        Some(value)
+    }
+
+    pub fn set(&mut self, id: u8, register: &Register, value: i32) {
+        self.values.insert((id, register.address), value);
+    }
+
+    /// Oublie les valeurs d'un servo (relecture forcée)
+    pub fn forget(&mut self, id: u8) {
+        self.values.retain(|(cached, _), _| *cached != id);
+    }
+
+    pub fn clear(&mut self) {
+        self.values.clear();
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegisterDiff {
+    pub register: &'static Register,
+    pub a: Option<i32>,
+    pub b: Option<i32>,
+}
+
+impl RegisterDiff {
+    /// Recopiable de A vers B : registre modifiable et valeur de A connue
+    pub fn copyable(&self) -> bool {
+        self.register.group.is_writable() && self.a.is_some()
+    }
+}
+
+/// Registres dont la valeur diffère entre `a` et `b` (ou illisibles sur l'un des deux)
+pub fn compare(bus: &mut RegisterPort, cache: &mut RegisterCache, a: u8, b: u8) -> Vec<RegisterDiff> {
+    REGISTERS
+        .iter()
+        .map(|register| RegisterDiff {
+            register,
+            a: cache.read(bus, a, register),
+            b: cache.read(bus, b, register),
+        })
+        .filter(|row| row.a != row.b || row.a.is_none())
+        .collect()
+}
+
+/// Recopie la valeur de `from` dans `to` (EEPROM), vérifiée par relecture
+pub fn copy(bus: &mut RegisterPort, cache: &mut RegisterCache, from: u8, to: u8, register: &'static Register) -> Result<i32, String> {
+    let value = cache
+        .read(bus, from, register)
+        .ok_or_else(|| format!("could not read {} on ID {}", register.name, from))?;
+    let mismatches = bus.write_verified(to, &[(register, value)])?;
+    match mismatches.first() {
+        None => {
+            cache.set(to, register, value);
+            Ok(value)
+        }
+        Some(m) => {
+            cache.forget(to);
+            Err(format!("{} on ID {}: wrote {}, read back {:?}", register.name, to, m.expected, m.read))
+        }
+    }
+}
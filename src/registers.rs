@@ -0,0 +1,397 @@
+//! Table des registres du ST3215 (EEPROM, puis RAM) et accès direct registre par registre.
+
+use crate::ids::{self, Access};
+use crate::backend::{SerialBackend, ServoBackend};
+use crate::logging::LoggedBackend;
+use st3215::STS_LOCK;
+
+/// Première adresse hors EEPROM (couple, consignes... en RAM)
+pub const EEPROM_END: u8 = 40;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RegisterGroup {
+    /// Version et modèle, en lecture seule
+    Info,
+    /// ID, débit, délai de réponse : jamais recopiés, pour garder le servo joignable
+    Communication,
+    Limits,
+    Pid,
+    Offsets,
+    Deadband,
+    Protections,
+    Other,
+    /// Registres RAM : commandes en cours et relevés
+    Runtime,
+}
+
+impl RegisterGroup {
+    pub fn label(self) -> &'static str {
+        match self {
+            RegisterGroup::Info => "info",
+            RegisterGroup::Communication => "communication",
+            RegisterGroup::Limits => "limits",
+            RegisterGroup::Pid => "PID",
+            RegisterGroup::Offsets => "offsets",
+            RegisterGroup::Deadband => "deadband",
+            RegisterGroup::Protections => "protections",
+            RegisterGroup::Other => "other",
+            RegisterGroup::Runtime => "runtime",
+        }
+    }
+
+    /// Groupes qui expliquent le plus souvent un écart de comportement
+    pub fn is_important(self) -> bool {
+        matches!(self, RegisterGroup::Pid | RegisterGroup::Limits | RegisterGroup::Deadband | RegisterGroup::Protections)
+    }
+
+    /// Registres que l'on accepte d'écrire lors d'un import ou d'une copie
+    pub fn is_writable(self) -> bool {
+        !matches!(self, RegisterGroup::Info | RegisterGroup::Communication | RegisterGroup::Runtime)
+    }
+}
+
+/// Registre EEPROM du ST3215, avec le nom utilisé par le logiciel FD de Feetech
+#[derive(Debug, PartialEq, Eq)]
+pub struct Register {
+    pub name: &'static str,
+    pub address: u8,
+    /// 1 ou 2 octets (petit-boutiste)
+    pub size: u8,
+    pub group: RegisterGroup,
+    /// Bit de signe (signe + module), pour l'offset de position
+    pub sign_bit: Option<u8>,
+    /// Autres libellés rencontrés selon les versions de FD
+    pub aliases: &'static [&'static str],
+    /// Modifiable depuis l'éditeur de registres (l'ID et le débit ont leurs propres outils)
+    pub writable: bool,
+    /// Bornes de la valeur lisible acceptée à l'écriture
+    pub min: i32,
+    pub max: i32,
+    /// Valeur d'usine, quand la documentation Feetech la donne
+    pub factory: Option<i32>,
+}
+
+const fn register(name: &'static str, address: u8, size: u8, group: RegisterGroup, aliases: &'static [&'static str]) -> Register {
+    let max = if size == 1 { 0xFF } else { 0xFFFF };
+    let writable = !matches!(group, RegisterGroup::Info);
+    Register { name, address, size, group, sign_bit: None, aliases, writable, min: 0, max, factory: None }
+}
+
+const fn factory(register: Register, value: i32) -> Register {
+    Register { factory: Some(value), ..register }
+}
+
+const fn bounded(register: Register, min: i32, max: i32) -> Register {
+    Register { min, max, ..register }
+}
+
+const fn read_only(register: Register) -> Register {
+    Register { writable: false, ..register }
+}
+
+// Registre RAM, hors import et copie
+const fn runtime(name: &'static str, address: u8, size: u8) -> Register {
+    register(name, address, size, RegisterGroup::Runtime, &[])
+}
+
+pub const REGISTERS: &[Register] = &[
+    register("Firmware Major", 0, 1, RegisterGroup::Info, &["Main Version"]),
+    register("Firmware Minor", 1, 1, RegisterGroup::Info, &["Sub Version"]),
+    register("Model", 3, 2, RegisterGroup::Info, &["Model Number"]),
+    read_only(register("ID", 5, 1, RegisterGroup::Communication, &[])),
+    read_only(register("Baud Rate", 6, 1, RegisterGroup::Communication, &["Baudrate", "BPS"])),
+    bounded(register("Return Delay", 7, 1, RegisterGroup::Communication, &["Return Delay Time"]), 0, 254),
+    bounded(register("Response Status Level", 8, 1, RegisterGroup::Communication, &["Status Return Level"]), 0, 1),
+    bounded(register("Min Angle Limit", 9, 2, RegisterGroup::Limits, &["Min Position Limit"]), 0, 4095),
+    bounded(register("Max Angle Limit", 11, 2, RegisterGroup::Limits, &["Max Position Limit"]), 0, 4095),
+    bounded(register("Max Temperature Limit", 13, 1, RegisterGroup::Protections, &["Max Temperature"]), 0, 100),
+    register("Max Input Voltage", 14, 1, RegisterGroup::Protections, &["Max Voltage"]),
+    register("Min Input Voltage", 15, 1, RegisterGroup::Protections, &["Min Voltage"]),
+    bounded(register("Max Torque", 16, 2, RegisterGroup::Limits, &["Max Torque Limit"]), 0, 1000),
+    register("Phase", 18, 1, RegisterGroup::Other, &[]),
+    register("Unloading Condition", 19, 1, RegisterGroup::Protections, &["Protection Switch"]),
+    register("LED Alarm Condition", 20, 1, RegisterGroup::Protections, &["LED Alarm"]),
+    factory(register("P Coefficient", 21, 1, RegisterGroup::Pid, &["Position P", "P"]), 32),
+    factory(register("D Coefficient", 22, 1, RegisterGroup::Pid, &["Position D", "D"]), 32),
+    factory(register("I Coefficient", 23, 1, RegisterGroup::Pid, &["Position I", "I"]), 0),
+    register("Minimum Startup Force", 24, 2, RegisterGroup::Pid, &["Punch", "Min Startup Force"]),
+    register("CW Dead Band", 26, 1, RegisterGroup::Deadband, &["CW Insensitive Area", "CW Dead Zone"]),
+    register("CCW Dead Band", 27, 1, RegisterGroup::Deadband, &["CCW Insensitive Area", "CCW Dead Zone"]),
+    register("Protection Current", 28, 2, RegisterGroup::Protections, &["Max Current"]),
+    register("Angular Resolution", 30, 1, RegisterGroup::Other, &[]),
+    Register { sign_bit: Some(11), min: -2047, max: 2047, ..register("Position Offset", 31, 2, RegisterGroup::Offsets, &["Offset", "Ofs"]) },
+    bounded(register("Mode", 33, 1, RegisterGroup::Other, &["Operating Mode", "Work Mode"]), 0, 3),
+    register("Protective Torque", 34, 1, RegisterGroup::Protections, &[]),
+    register("Protection Time", 35, 1, RegisterGroup::Protections, &[]),
+    register("Overload Torque", 36, 1, RegisterGroup::Protections, &[]),
+    register("Speed P Coefficient", 37, 1, RegisterGroup::Pid, &["Speed closed-loop P", "Velocity P"]),
+    register("Overcurrent Protection Time", 38, 1, RegisterGroup::Protections, &[]),
+    register("Speed I Coefficient", 39, 1, RegisterGroup::Pid, &["Speed closed-loop I", "Velocity I"]),
+];
+
+/// Registres RAM, hors de `REGISTERS` : jamais comparés ni recopiés
+///
+/// Activation du couple
+pub const TORQUE_ENABLE: Register = bounded(runtime("Torque Enable", 40, 1), 0, 1);
+/// Charge présente (RAM), en 0,1 % ; le bit 10 donne le sens
+pub const PRESENT_LOAD: Register = read_only(Register { sign_bit: Some(10), ..runtime("Present Load", 60, 2) });
+
+/// Registres RAM affichés par l'éditeur, relevés en lecture seule
+pub const RAM_REGISTERS: &[Register] = &[
+    TORQUE_ENABLE,
+    bounded(runtime("Acceleration", 41, 1), 0, 254),
+    bounded(runtime("Goal Position", 42, 2), 0, 4095),
+    runtime("Goal Time", 44, 2),
+    bounded(runtime("Goal Speed", 46, 2), 0, 3400),
+    bounded(runtime("Torque Limit", 48, 2), 0, 1000),
+    bounded(runtime("Lock", 55, 1), 0, 1),
+    read_only(runtime("Present Position", 56, 2)),
+    read_only(Register { sign_bit: Some(15), ..runtime("Present Speed", 58, 2) }),
+    PRESENT_LOAD,
+    read_only(runtime("Present Voltage", 62, 1)),
+    read_only(runtime("Present Temperature", 63, 1)),
+    read_only(runtime("Status", 65, 1)),
+    read_only(runtime("Moving", 66, 1)),
+    read_only(runtime("Present Current", 69, 2)),
+];
+
+/// Tous les registres connus, EEPROM puis RAM
+pub fn all_registers() -> impl Iterator<Item = &'static Register> {
+    REGISTERS.iter().chain(RAM_REGISTERS)
+}
+
+pub fn register_at(address: u8) -> Option<&'static Register> {
+    all_registers().find(|r| r.address == address)
+}
+
+// Comparaison tolérante : casse, espaces, tirets et soulignés ignorés
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| c.is_ascii_alphanumeric()).map(|c| c.to_ascii_lowercase()).collect()
+}
+
+pub fn find_register(name: &str) -> Option<&'static Register> {
+    let key = normalize(name);
+    REGISTERS
+        .iter()
+        .find(|r| normalize(r.name) == key || r.aliases.iter().any(|a| normalize(a) == key))
+}
+
+impl Register {
+    pub fn is_eeprom(&self) -> bool {
+        self.address < EEPROM_END
+    }
+
+    /// Écriture depuis l'éditeur : registre modifiable et valeur dans ses bornes
+    pub fn check_write(&self, value: i32) -> Result<(), String> {
+        if !self.writable {
+            return Err(format!("{} is read-only", self.name));
+        }
+        if !(self.min..=self.max).contains(&value) {
+            return Err(format!("{}: {} out of range {}..={}", self.name, value, self.min, self.max));
+        }
+        Ok(())
+    }
+
+    /// Valeur lisible → valeur brute du registre
+    pub fn encode(&self, value: i32) -> Result<u16, String> {
+        let max = if self.size == 1 { 0xFF } else { 0xFFFF };
+        match self.sign_bit {
+            Some(bit) => {
+                let magnitude = value.unsigned_abs();
+                if magnitude >= 1 << bit {
+                    return Err(format!("{}: {} out of range ±{}", self.name, value, (1 << bit) - 1));
+                }
+                Ok(magnitude as u16 | if value < 0 { 1 << bit } else { 0 })
+            }
+            None if (0..=max).contains(&value) => Ok(value as u16),
+            None => Err(format!("{}: {} out of range 0..={}", self.name, value, max)),
+        }
+    }
+
+    pub fn decode(&self, raw: u16) -> i32 {
+        match self.sign_bit {
+            Some(bit) if raw & (1 << bit) != 0 => -((raw & ((1 << bit) - 1)) as i32),
+            _ => raw as i32,
+        }
+    }
+}
+
+/// Registre dont la relecture ne correspond pas à la valeur écrite
+#[derive(Clone, Debug, PartialEq)]
+pub struct Mismatch {
+    pub register: &'static Register,
+    pub expected: i32,
+    pub read: Option<i32>,
+}
+
+// Bus propre à la ligne de commande, ou emprunté au pilote d'un thread de communication
+enum Bus<'a> {
+    Owned(Box<dyn ServoBackend>),
+    Borrowed(&'a dyn ServoBackend),
+}
+
+/// Accès registre par registre, sur un port ouvert pour l'occasion ou sur un bus déjà ouvert
+pub struct RegisterPort<'a> {
+    bus: Bus<'a>,
+}
+
+impl RegisterPort<'static> {
+    pub fn open(port_name: &str) -> Result<Self, String> {
+        let backend = SerialBackend::open_direct(port_name)?;
+        Ok(Self { bus: Bus::Owned(Box::new(LoggedBackend::new(Box::new(backend)))) })
+    }
+}
+
+impl<'a> RegisterPort<'a> {
+    pub fn new(backend: &'a dyn ServoBackend) -> Self {
+        Self { bus: Bus::Borrowed(backend) }
+    }
+
+    fn backend(&self) -> &dyn ServoBackend {
+        match &self.bus {
+            Bus::Owned(backend) => backend.as_ref(),
+            Bus::Borrowed(backend) => *backend,
+        }
+    }
+
+    pub fn read(&mut self, id: u8, register: &Register) -> Result<i32, String> {
+        let data = self
+            .backend()
+            .read_register(id, register.address, register.size)
+            .map_err(|e| format!("{}: {}", register.name, e))?;
+        let raw = if register.size == 1 { data[0] as u16 } else { u16::from_le_bytes([data[0], data[1]]) };
+        Ok(register.decode(raw))
+    }
+
+    fn write_raw(&mut self, id: u8, address: u8, data: &[u8]) -> Result<(), String> {
+        self.backend().write_register(id, address, data)
+    }
+
+    /// Écrit un registre depuis l'éditeur puis le relit ; l'EEPROM n'est déverrouillée que
+    /// le temps de l'écriture. Retourne la valeur relue.
+    pub fn write(&mut self, id: u8, register: &Register, value: i32) -> Result<i32, String> {
+        let access = if register.is_eeprom() { Access::Eeprom } else { Access::Command };
+        ids::check_target(id, access, false)?;
+        register.check_write(value)?;
+        let bytes = register.encode(value)?.to_le_bytes();
+        if register.is_eeprom() {
+            self.write_raw(id, STS_LOCK, &[0])?;
+            let outcome = self.write_raw(id, register.address, &bytes[..register.size as usize]);
+            self.write_raw(id, STS_LOCK, &[1])?;
+            outcome?;
+        } else {
+            self.write_raw(id, register.address, &bytes[..register.size as usize])?;
+        }
+        let read = self.read(id, register)?;
+        if read != value {
+            return Err(format!("{} on ID {}: wrote {}, read back {}", register.name, id, value, read));
+        }
+        Ok(read)
+    }
+
+    /// Écrit les valeurs en EEPROM (déverrouillée le temps de l'écriture) puis relit chaque registre.
+    /// Retourne les registres dont la relecture ne correspond pas.
+    pub fn write_verified(&mut self, id: u8, values: &[(&'static Register, i32)]) -> Result<Vec<Mismatch>, String> {
+        ids::check_target(id, Access::Eeprom, false)?;
+        if let Some((register, _)) = values.iter().find(|(r, _)| !r.group.is_writable()) {
+            return Err(format!("{} is a {} register and is never written", register.name, register.group.label()));
+        }
+        // Tout est encodé avant de déverrouiller : une valeur hors plage ne laisse pas l'EEPROM ouverte
+        let encoded = values
+            .iter()
+            .map(|&(register, value)| Ok((register, register.encode(value)?.to_le_bytes())))
+            .collect::<Result<Vec<_>, String>>()?;
+        self.write_raw(id, STS_LOCK, &[0])?;
+        let mut outcome = Ok(());
+        for (register, bytes) in encoded {
+            outcome = self.write_raw(id, register.address, &bytes[..register.size as usize]);
+            if outcome.is_err() {
+                break;
+            }
+        }
+        // On reverrouille même après une erreur d'écriture
+        self.write_raw(id, STS_LOCK, &[1])?;
+        outcome?;
+
+        Ok(values
+            .iter()
+            .map(|&(register, expected)| Mismatch { register, expected, read: self.read(id, register).ok() })
+            .filter(|m| m.read != Some(m.expected))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendCall, MockBackend, MockServo};
+
+    fn register(name: &str) -> &'static Register {
+        find_register(name).unwrap()
+    }
+
+    #[test]
+    fn signed_registers_round_trip() {
+        let offset = register("Position Offset");
+        assert_eq!(offset.encode(-100), Ok(0x800 | 100));
+        assert_eq!(offset.decode(0x800 | 100), -100);
+        assert!(offset.encode(2048).is_err());
+        assert!(register("Return Delay").encode(256).is_err());
+    }
+
+    #[test]
+    fn write_verified_unlocks_around_the_writes() {
+        let mock = MockBackend::new().with_servo(3, MockServo::default());
+        let values = [(register("P Coefficient"), 20), (register("Position Offset"), -12)];
+        let mismatches = RegisterPort::new(&mock).write_verified(3, &values).unwrap();
+
+        assert!(mismatches.is_empty());
+        let write = |address, data: &[u8]| BackendCall::WriteRegister { id: 3, address, data: data.to_vec() };
+        assert_eq!(mock.calls(), vec![write(STS_LOCK, &[0]), write(21, &[20]), write(31, &[12, 0x08]), write(STS_LOCK, &[1])]);
+    }
+
+    #[test]
+    fn unencodable_value_leaves_the_eeprom_locked() {
+        let mock = MockBackend::new().with_servo(3, MockServo::default());
+        // Hors plage signée : l'encodage échoue après un premier registre valide
+        let values = [(register("P Coefficient"), 20), (register("Position Offset"), 5000)];
+        assert!(RegisterPort::new(&mock).write_verified(3, &values).is_err());
+        assert!(mock.calls().is_empty());
+    }
+
+    #[test]
+    fn communication_registers_are_never_written() {
+        let mock = MockBackend::new().with_servo(3, MockServo::default());
+        assert!(RegisterPort::new(&mock).write_verified(3, &[(register("Return Delay"), 0)]).is_err());
+        assert!(mock.calls().is_empty());
+    }
+
+    #[test]
+    fn editor_write_checks_bounds_and_reads_back() {
+        let mock = MockBackend::new().with_servo(3, MockServo::default());
+        let mut bus = RegisterPort::new(&mock);
+        let write = |address, data: &[u8]| BackendCall::WriteRegister { id: 3, address, data: data.to_vec() };
+
+        // EEPROM : déverrouillée le temps de l'écriture seulement
+        assert_eq!(bus.write(3, register("Max Angle Limit"), 3000), Ok(3000));
+        assert_eq!(mock.take_calls(), vec![write(STS_LOCK, &[0]), write(11, &[0xB8, 0x0B]), write(STS_LOCK, &[1])]);
+
+        // RAM : écrite directement
+        let torque_limit = register_at(48).unwrap();
+        assert_eq!(bus.write(3, torque_limit, 500), Ok(500));
+        assert_eq!(mock.take_calls(), vec![write(48, &[0xF4, 0x01])]);
+
+        assert!(bus.write(3, torque_limit, 1001).unwrap_err().contains("out of range"));
+        assert!(bus.write(3, register("ID"), 4).unwrap_err().contains("read-only"));
+        assert!(bus.write(3, register_at(56).unwrap(), 100).unwrap_err().contains("read-only"));
+        assert!(mock.calls().is_empty());
+    }
+
+    #[test]
+    fn register_map_lists_eeprom_then_ram() {
+        let addresses: Vec<u8> = all_registers().map(|r| r.address).collect();
+        assert!(addresses.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", addresses);
+        assert!(all_registers().filter(|r| r.group == RegisterGroup::Runtime).all(|r| !r.is_eeprom()));
+        assert_eq!(register_at(PRESENT_LOAD.address), Some(&PRESENT_LOAD));
+    }
+}
@@ -0,0 +1,515 @@
+//! Rapport de fin de session : une page de synthèse (Markdown, HTML en option) construite à
+//! partir du journal d'événements, des relevés min/max de télémétrie et des odomètres.
+
+use crate::events::{Event, TimedEvent};
+use crate::odometer::{format_date, format_ticks, OdometerEntry};
+use crate::plugins::DerivedValue;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+pub const REPORT_FILE: &str = "session_report.md";
+pub const REPORT_HTML_FILE: &str = "session_report.html";
+/// Au-delà, deux relevés de puissance sont séparés par une coupure : pas d'intégration
+const MAX_ENERGY_GAP_S: f64 = 5.0;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Metric {
+    Position,
+    Temperature,
+    Voltage,
+    Current,
+    Load,
+}
+
+impl Metric {
+    pub fn label(self) -> &'static str {
+        match self {
+            Metric::Position => "Position",
+            Metric::Temperature => "Temperature",
+            Metric::Voltage => "Voltage",
+            Metric::Current => "Current",
+            Metric::Load => "Load",
+        }
+    }
+
+    pub fn unit(self) -> &'static str {
+        match self {
+            Metric::Position => "",
+            Metric::Temperature => "°C",
+            Metric::Voltage => "V",
+            Metric::Current => "mA",
+            Metric::Load => "%",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Watermark {
+    pub min: f64,
+    pub max: f64,
+}
+
+/// Relevés d'un servo sur la session
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServoTelemetry {
+    pub watermarks: BTreeMap<Metric, Watermark>,
+    /// Énergie consommée (J), intégrée depuis tension × courant
+    pub energy_j: f64,
+    last_voltage: Option<f64>,
+    // Dernier relevé de puissance : (instant en s, W)
+    last_power: Option<(f64, f64)>,
+}
+
+impl ServoTelemetry {
+    pub fn observe(&mut self, metric: Metric, time: f64, value: f64) {
+        self.watermarks
+            .entry(metric)
+            .and_modify(|w| {
+                w.min = w.min.min(value);
+                w.max = w.max.max(value);
+            })
+            .or_insert(Watermark { min: value, max: value });
+
+        match metric {
+            Metric::Voltage => self.last_voltage = Some(value),
+            Metric::Current => {
+                let Some(voltage) = self.last_voltage else { return };
+                let power = voltage * value / 1000.0;
+                if let Some((t0, p0)) = self.last_power {
+                    let dt = time - t0;
+                    if dt > 0.0 && dt <= MAX_ENERGY_GAP_S {
+                        self.energy_j += (p0 + power) / 2.0 * dt;
+                    }
+                }
+                self.last_power = Some((time, power));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Télémétrie de tous les servos de la session
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SessionTelemetry {
+    pub servos: BTreeMap<u8, ServoTelemetry>,
+}
+
+impl SessionTelemetry {
+    pub fn observe(&mut self, id: u8, metric: Metric, time: f64, value: f64) {
+        self.servos.entry(id).or_default().observe(metric, time, value);
+    }
+
+    pub fn energy_j(&self) -> f64 {
+        self.servos.values().map(|s| s.energy_j).sum()
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServoReport {
+    pub commands: usize,
+    pub failed: usize,
+    pub telemetry: Option<ServoTelemetry>,
+}
+
+/// Alerte levée, avec sa durée si elle a été levée puis effacée pendant la session
+#[derive(Clone, Debug, PartialEq)]
+pub struct AlertSpan {
+    pub servo: Option<u8>,
+    pub message: String,
+    pub raised: f64,
+    pub duration: Option<f64>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SessionReport {
+    pub started_unix_ms: u64,
+    pub duration: f64,
+    pub servos: BTreeMap<u8, ServoReport>,
+    pub alerts: Vec<AlertSpan>,
+    pub emergency_stops: Vec<f64>,
+    pub reconnects: usize,
+    pub energy_j: f64,
+    /// Dernières valeurs des traitements de télémétrie enregistrés, par servo
+    pub derived: Vec<(u8, DerivedValue)>,
+    /// Odomètre cumulé des servos, toutes sessions confondues
+    pub odometers: BTreeMap<u8, OdometerEntry>,
+    /// Fichiers de la session (enregistrement, exports) : libellé, chemin
+    pub links: Vec<(String, String)>,
+}
+
+impl SessionReport {
+    pub fn build(started_unix_ms: u64, duration: f64, events: &[TimedEvent], telemetry: &SessionTelemetry) -> Self {
+        let mut report = SessionReport {
+            started_unix_ms,
+            duration,
+            energy_j: telemetry.energy_j(),
+            ..Default::default()
+        };
+        for (id, servo) in &telemetry.servos {
+            report.servos.entry(*id).or_default().telemetry = Some(servo.clone());
+        }
+
+        let mut connections: usize = 0;
+        for timed in events {
+            match &timed.event {
+                Event::Command { servo: Some(id), ok, .. } => {
+                    let servo = report.servos.entry(*id).or_default();
+                    // Un échec répété compte pour chacune de ses occurrences
+                    servo.commands += timed.count as usize;
+                    if !ok {
+                        servo.failed += timed.count as usize;
+                    }
+                }
+                Event::Connected { .. } => connections += 1,
+                Event::PortChanged { .. } => report.reconnects += 1,
+                Event::AlertRaised { servo, message } => report.alerts.push(AlertSpan {
+                    servo: *servo,
+                    message: message.clone(),
+                    raised: timed.elapsed,
+                    duration: None,
+                }),
+                // Le message d'effacement diffère de celui de la levée : on ferme la plus ancienne
+                // alerte encore ouverte sur le même servo
+                Event::AlertCleared { servo, .. } => {
+                    if let Some(open) = report.alerts.iter_mut().find(|a| a.servo == *servo && a.duration.is_none()) {
+                        open.duration = Some(timed.elapsed - open.raised);
+                    }
+                }
+                Event::EmergencyStop => report.emergency_stops.push(timed.elapsed),
+                _ => {}
+            }
+        }
+        report.reconnects += connections.saturating_sub(1);
+        report
+    }
+
+    pub fn link(&mut self, label: impl Into<String>, path: impl Into<String>) {
+        self.links.push((label.into(), path.into()));
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        let _ = writeln!(md, "# Session report\n");
+        let _ = writeln!(md, "- Started: {}", format_timestamp(self.started_unix_ms));
+        let _ = writeln!(md, "- Duration: {}", format_duration(self.duration));
+        let _ = writeln!(md, "- Servos used: {}", self.servos.len());
+        let _ = writeln!(md, "- Emergency stops: {}", self.emergency_stops.len());
+        let _ = writeln!(md, "- Reconnects: {}", self.reconnects);
+        let _ = writeln!(md, "- Energy consumed: {}", format_energy(self.energy_j));
+
+        let _ = writeln!(md, "\n## Servos\n");
+        if self.servos.is_empty() {
+            let _ = writeln!(md, "No servo used.");
+        } else {
+            let _ = writeln!(md, "| ID | Commands | Failed | Min / max | Energy |");
+            let _ = writeln!(md, "|---:|---:|---:|---|---:|");
+            for (id, servo) in &self.servos {
+                let (watermarks, energy) = match &servo.telemetry {
+                    Some(t) => (format_watermarks(t), format_energy(t.energy_j)),
+                    None => ("—".to_string(), "—".to_string()),
+                };
+                let _ = writeln!(md, "| {} | {} | {} | {} | {} |", id, servo.commands, servo.failed, watermarks, energy);
+            }
+        }
+
+        let _ = writeln!(md, "\n## Alerts\n");
+        if self.alerts.is_empty() {
+            let _ = writeln!(md, "No alert.");
+        } else {
+            let _ = writeln!(md, "| At | Servo | Alert | Duration |");
+            let _ = writeln!(md, "|---:|---|---|---:|");
+            for alert in &self.alerts {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {} | {} |",
+                    format_duration(alert.raised),
+                    alert.servo.map(|id| format!("ID {}", id)).unwrap_or("—".into()),
+                    alert.message.replace('|', "\\|"),
+                    alert.duration.map(format_duration).unwrap_or("still active".into())
+                );
+            }
+        }
+
+        if !self.emergency_stops.is_empty() {
+            let _ = writeln!(md, "\n## Emergency stops\n");
+            for at in &self.emergency_stops {
+                let _ = writeln!(md, "- {}", format_duration(*at));
+            }
+        }
+
+        if !self.derived.is_empty() {
+            let _ = writeln!(md, "\n## Derived metrics\n");
+            let _ = writeln!(md, "| ID | Metric | Last value |");
+            let _ = writeln!(md, "|---:|---|---:|");
+            for (id, derived) in &self.derived {
+                let _ = writeln!(
+                    md,
+                    "| {} | {} | {:.2}{} |",
+                    id,
+                    derived.name.replace('|', "\\|"),
+                    derived.value,
+                    derived.unit
+                );
+            }
+        }
+
+        if !self.odometers.is_empty() {
+            let _ = writeln!(md, "\n## Odometer\n");
+            let _ = writeln!(md, "| ID | Total travel | Since |");
+            let _ = writeln!(md, "|---:|---:|---|");
+            for (id, entry) in &self.odometers {
+                let _ = writeln!(md, "| {} | {} ticks | {} |", id, format_ticks(entry.ticks), format_date(entry.first_seen_unix_ms));
+            }
+        }
+
+        if !self.links.is_empty() {
+            let _ = writeln!(md, "\n## Files\n");
+            for (label, path) in &self.links {
+                let _ = writeln!(md, "- [{}]({})", label, path);
+            }
+        }
+        md
+    }
+
+    /// Même contenu que le Markdown, en page HTML autonome
+    pub fn to_html(&self) -> String {
+        let mut html = String::from("<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Session report</title></head><body>\n");
+        let mut in_table = false;
+        let mut in_list = false;
+        for line in self.to_markdown().lines() {
+            if !line.starts_with('|') && in_table {
+                html.push_str("</table>\n");
+                in_table = false;
+            }
+            if !line.starts_with("- ") && in_list {
+                html.push_str("</ul>\n");
+                in_list = false;
+            }
+            if let Some(title) = line.strip_prefix("## ") {
+                let _ = writeln!(html, "<h2>{}</h2>", escape_html(title));
+            } else if let Some(title) = line.strip_prefix("# ") {
+                let _ = writeln!(html, "<h1>{}</h1>", escape_html(title));
+            } else if let Some(item) = line.strip_prefix("- ") {
+                if !in_list {
+                    html.push_str("<ul>\n");
+                    in_list = true;
+                }
+                let item = match item.strip_prefix('[').and_then(|rest| rest.split_once("](")) {
+                    Some((label, path)) => {
+                        let path = path.trim_end_matches(')');
+                        format!("<a href=\"{}\">{}</a>", escape_html(path), escape_html(label))
+                    }
+                    None => escape_html(item),
+                };
+                let _ = writeln!(html, "<li>{}</li>", item);
+            } else if line.starts_with("|---") {
+                continue;
+            } else if line.starts_with('|') {
+                let cell = if in_table { "td" } else { "th" };
+                if !in_table {
+                    html.push_str("<table border=\"1\" cellspacing=\"0\" cellpadding=\"4\">\n");
+                    in_table = true;
+                }
+                html.push_str("<tr>");
+                for value in line.trim_matches('|').split(" | ") {
+                    let _ = write!(html, "<{0}>{1}</{0}>", cell, escape_html(&value.trim().replace("\\|", "|")));
+                }
+                html.push_str("</tr>\n");
+            } else if !line.is_empty() {
+                let _ = writeln!(html, "<p>{}</p>", escape_html(line));
+            }
+        }
+        if in_table {
+            html.push_str("</table>\n");
+        }
+        if in_list {
+            html.push_str("</ul>\n");
+        }
+        html.push_str("</body></html>\n");
+        html
+    }
+
+    /// Écrit le rapport dans `dir` et retourne les fichiers créés
+    pub fn write(&self, dir: &Path, html: bool) -> Result<Vec<PathBuf>, String> {
+        let mut written = Vec::new();
+        let mut outputs = vec![(dir.join(REPORT_FILE), self.to_markdown())];
+        if html {
+            outputs.push((dir.join(REPORT_HTML_FILE), self.to_html()));
+        }
+        for (path, text) in outputs {
+            std::fs::write(&path, text).map_err(|e| format!("{}: {}", path.display(), e))?;
+            written.push(path);
+        }
+        Ok(written)
+    }
+}
+
+fn format_watermarks(telemetry: &ServoTelemetry) -> String {
+    if telemetry.watermarks.is_empty() {
+        return "—".to_string();
+    }
+    telemetry
+        .watermarks
+        .iter()
+        .map(|(metric, w)| format!("{} {}–{}{}", metric.label(), trim_number(w.min), trim_number(w.max), metric.unit()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+// Entier sans décimale, sinon une décimale
+fn trim_number(value: f64) -> String {
+    if value.fract() == 0.0 { format!("{}", value as i64) } else { format!("{:.1}", value) }
+}
+
+fn format_energy(joules: f64) -> String {
+    if joules >= 3600.0 { format!("{:.2} Wh", joules / 3600.0) } else { format!("{:.1} J", joules) }
+}
+
+pub fn format_duration(seconds: f64) -> String {
+    let total = seconds.max(0.0).round() as u64;
+    match (total / 3600, total / 60 % 60, total % 60) {
+        (0, 0, s) => format!("{}s", s),
+        (0, m, s) => format!("{}m{:02}s", m, s),
+        (h, m, s) => format!("{}h{:02}m{:02}s", h, m, s),
+    }
+}
+
+pub fn format_timestamp(unix_ms: u64) -> String {
+    format!("{} {:02}:{:02} UTC", format_date(unix_ms), unix_ms / 3_600_000 % 24, unix_ms / 60_000 % 60)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timed(elapsed: f64, event: Event) -> TimedEvent {
+        TimedEvent { elapsed, unix_ms: 0, event, count: 1, last_elapsed: elapsed }
+    }
+
+    #[test]
+    fn watermarks_track_min_and_max() {
+        let mut servo = ServoTelemetry::default();
+        servo.observe(Metric::Temperature, 0.0, 35.0);
+        servo.observe(Metric::Temperature, 1.0, 42.0);
+        servo.observe(Metric::Temperature, 2.0, 38.0);
+        assert_eq!(servo.watermarks[&Metric::Temperature], Watermark { min: 35.0, max: 42.0 });
+        assert_eq!(format_watermarks(&servo), "Temperature 35–42°C");
+    }
+
+    #[test]
+    fn energy_is_integrated_and_skips_gaps() {
+        let mut servo = ServoTelemetry::default();
+        // 12 V × 500 mA = 6 W pendant 2 s
+        servo.observe(Metric::Voltage, 0.0, 12.0);
+        servo.observe(Metric::Current, 0.0, 500.0);
+        servo.observe(Metric::Current, 2.0, 500.0);
+        assert!((servo.energy_j - 12.0).abs() < 1e-9);
+        // Coupure plus longue que MAX_ENERGY_GAP_S : rien n'est ajouté
+        servo.observe(Metric::Current, 10.0, 500.0);
+        assert!((servo.energy_j - 12.0).abs() < 1e-9);
+        // Courant sans tension connue : ignoré
+        let mut other = ServoTelemetry::default();
+        other.observe(Metric::Current, 0.0, 500.0);
+        other.observe(Metric::Current, 1.0, 500.0);
+        assert_eq!(other.energy_j, 0.0);
+    }
+
+    #[test]
+    fn build_counts_commands_alerts_and_reconnects() {
+        let mut repeated = timed(3.0, Event::command(Some(1), "move", Err("timeout".into())));
+        repeated.count = 3;
+        let events = vec![
+            timed(0.0, Event::Connected { port: "/dev/ttyUSB0".into() }),
+            timed(1.0, Event::command(Some(1), "move", Ok(()))),
+            repeated,
+            timed(4.0, Event::AlertRaised { servo: Some(2), message: "Overheat 71°C".into() }),
+            timed(9.0, Event::AlertCleared { servo: Some(2), message: "Temperature back to normal".into() }),
+            timed(10.0, Event::AlertRaised { servo: None, message: "Bus voltage low".into() }),
+            timed(12.0, Event::EmergencyStop),
+            timed(15.0, Event::PortChanged { from: "/dev/ttyUSB0".into(), to: "/dev/ttyUSB1".into() }),
+            timed(16.0, Event::Connected { port: "/dev/ttyUSB1".into() }),
+        ];
+        let report = SessionReport::build(0, 20.0, &events, &SessionTelemetry::default());
+        assert_eq!(report.servos[&1], ServoReport { commands: 4, failed: 3, telemetry: None });
+        assert_eq!(report.alerts.len(), 2);
+        assert_eq!(report.alerts[0].duration, Some(5.0));
+        assert_eq!(report.alerts[1].duration, None);
+        assert_eq!(report.emergency_stops, vec![12.0]);
+        assert_eq!(report.reconnects, 2);
+    }
+
+    #[test]
+    fn markdown_and_html_output() {
+        let mut telemetry = SessionTelemetry::default();
+        telemetry.observe(3, Metric::Position, 0.0, 1000.0);
+        telemetry.observe(3, Metric::Position, 1.0, 2048.0);
+        let events = vec![timed(2.0, Event::AlertRaised { servo: Some(3), message: "a|b <x>".into() })];
+        let mut report = SessionReport::build(0, 75.0, &events, &telemetry);
+        report.link("Recording", "session.csv");
+
+        let md = report.to_markdown();
+        assert!(md.contains("- Duration: 1m15s"));
+        assert!(md.contains("| 3 | 0 | 0 | Position 1000–2048 | 0.0 J |"));
+        assert!(md.contains("| 2s | ID 3 | a\\|b <x> | still active |"));
+        assert!(md.contains("- [Recording](session.csv)"));
+        assert!(!md.contains("## Odometer"));
+
+        let html = report.to_html();
+        assert!(html.contains("<td>a|b &lt;x&gt;</td>"));
+        assert!(html.contains("<a href=\"session.csv\">Recording</a>"));
+        assert!(html.contains("<th>ID</th>"));
+        assert!(!html.contains("|---"));
+    }
+
+    // Session synthétique couvrant chaque section du rapport
+    fn synthetic_session() -> SessionReport {
+        let mut telemetry = SessionTelemetry::default();
+        for (time, position, current) in [(0.0, 1024.0, 120.0), (1.0, 2048.0, 480.0), (2.0, 3072.0, 240.0)] {
+            telemetry.observe(1, Metric::Voltage, time, 12.0);
+            telemetry.observe(1, Metric::Position, time, position);
+            telemetry.observe(1, Metric::Current, time, current);
+        }
+        telemetry.observe(2, Metric::Temperature, 4.0, 38.0);
+        telemetry.observe(2, Metric::Temperature, 9.0, 71.5);
+        let mut repeated = timed(30.0, Event::command(Some(1), "move", Err("timeout".into())));
+        repeated.count = 2;
+        let events = vec![
+            timed(0.0, Event::Connected { port: "/dev/ttyUSB0".into() }),
+            timed(1.0, Event::command(Some(1), "move", Ok(()))),
+            timed(2.0, Event::command(Some(2), "torque on", Ok(()))),
+            repeated,
+            timed(40.0, Event::AlertRaised { servo: Some(2), message: "Overheat 71°C".into() }),
+            timed(95.0, Event::AlertCleared { servo: Some(2), message: "Temperature back to normal".into() }),
+            timed(120.0, Event::EmergencyStop),
+            timed(130.0, Event::Connected { port: "/dev/ttyUSB0".into() }),
+            timed(200.0, Event::AlertRaised { servo: None, message: "Bus voltage | low".into() }),
+        ];
+        let mut report = SessionReport::build(1_735_732_800_000, 3725.0, &events, &telemetry);
+        report.derived = vec![(1, DerivedValue { name: "Power".into(), unit: " W", value: 2.88 })];
+        report.odometers.insert(1, OdometerEntry { ticks: 1_254_300, first_seen_unix_ms: 1_730_000_000_000 });
+        report.odometers.insert(2, OdometerEntry { ticks: 35_420, first_seen_unix_ms: 1_735_689_600_000 });
+        report.link("Session recording", "session-1735732800.jsonl");
+        report.link("Telemetry log", "telemetry.csv");
+        report
+    }
+
+    #[test]
+    fn markdown_matches_the_golden_file() {
+        assert_eq!(synthetic_session().to_markdown(), include_str!("../samples/session-report.md"));
+    }
+
+    #[test]
+    fn duration_and_energy_formatting() {
+        assert_eq!(format_duration(42.4), "42s");
+        assert_eq!(format_duration(3725.0), "1h02m05s");
+        assert_eq!(format_duration(-3.0), "0s");
+        assert_eq!(format_energy(12.34), "12.3 J");
+        assert_eq!(format_energy(7200.0), "2.00 Wh");
+        assert_eq!(format_timestamp(90_000_000), "1970-01-02 01:00 UTC");
+    }
+}
@@ -0,0 +1,205 @@
+//! Retour des commandes vers l'interface : chaque commande envoyée au worker porte un identifiant
+//! de corrélation, et le worker renvoie `CommandResult` une fois qu'elle a abouti ou échoué.
+//!
+//! Les résultats sont datés d'une époque, incrémentée par le worker à chaque connexion et à
+//! chaque perte de liaison. Une commande suivie pendant une époque antérieure n'est plus attendue :
+//! son résultat, s'il arrive, est écarté au lieu d'être appliqué.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Identifiant de corrélation, unique dans le processus
+pub type CommandId = u64;
+
+/// Durée d'affichage d'une notification d'échec
+pub const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+pub fn next_id() -> CommandId {
+    NEXT_ID.fetch_add(1, Ordering::Relaxed)
+}
+
+/// Issue d'une commande, renvoyée par le worker
+#[derive(Clone, Debug, PartialEq)]
+pub struct CommandResult {
+    pub id: CommandId,
+    pub cmd: &'static str,
+    pub servo: Option<u8>,
+    pub outcome: Result<(), String>,
+    /// Époque de connexion pendant laquelle la commande a été traitée
+    pub epoch: u64,
+}
+
+/// Canal de résultats : le `Responder` va au worker, le `Tracker` reste côté interface
+pub fn channel() -> (Responder, Tracker) {
+    let (tx, rx) = mpsc::channel();
+    let epoch = Arc::new(AtomicU64::new(0));
+    let responder = Responder { tx, epoch: epoch.clone() };
+    let tracker = Tracker { rx, epoch, in_flight: HashMap::new(), toasts: Vec::new() };
+    (responder, tracker)
+}
+
+// --- CÔTÉ WORKER ---
+#[derive(Clone)]
+pub struct Responder {
+    tx: Sender<CommandResult>,
+    epoch: Arc<AtomicU64>,
+}
+
+impl Responder {
+    /// Connexion ouverte ou perdue : les commandes encore attendues deviennent périmées
+    pub fn new_epoch(&self) {
+        self.epoch.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn send(&self, id: CommandId, cmd: &'static str, servo: Option<u8>, outcome: Result<(), String>) {
+        let epoch = self.epoch.load(Ordering::SeqCst);
+        // Interface fermée : plus personne n'attend le résultat
+        let _ = self.tx.send(CommandResult { id, cmd, servo, outcome, epoch });
+    }
+}
+
+// --- CÔTÉ INTERFACE ---
+#[derive(Clone, Debug)]
+struct InFlight {
+    cmd: &'static str,
+    servo: Option<u8>,
+    epoch: u64,
+}
+
+/// Échec affiché brièvement
+#[derive(Clone, Debug)]
+pub struct Toast {
+    pub message: String,
+    pub shown: Instant,
+}
+
+pub struct Tracker {
+    rx: Receiver<CommandResult>,
+    epoch: Arc<AtomicU64>,
+    in_flight: HashMap<CommandId, InFlight>,
+    toasts: Vec<Toast>,
+}
+
+impl Tracker {
+    /// Commande envoyée dont on attend le résultat
+    pub fn track(&mut self, id: CommandId, cmd: &'static str, servo: Option<u8>) {
+        let epoch = self.epoch.load(Ordering::SeqCst);
+        self.in_flight.insert(id, InFlight { cmd, servo, epoch });
+    }
+
+    /// Résultats arrivés depuis le dernier appel, hors résultats périmés ou non suivis. Un échec
+    /// ajoute aussi une notification.
+    pub fn poll(&mut self) -> Vec<CommandResult> {
+        let epoch = self.epoch.load(Ordering::SeqCst);
+        self.in_flight.retain(|_, pending| pending.epoch == epoch);
+        let mut resolved = Vec::new();
+        for result in self.rx.try_iter() {
+            let Some(pending) = self.in_flight.remove(&result.id) else { continue };
+            if result.epoch != pending.epoch {
+                continue;
+            }
+            if let Err(e) = &result.outcome {
+                let message = match result.servo {
+                    Some(id) => format!("ID {}: {} failed: {}", id, result.cmd, e),
+                    None => format!("{} failed: {}", result.cmd, e),
+                };
+                self.toasts.push(Toast { message, shown: Instant::now() });
+            }
+            resolved.push(result);
+        }
+        resolved
+    }
+
+    /// Une commande de ce type est-elle en attente pour ce servo (`None` : commande de bus)
+    pub fn pending(&self, cmd: &str, servo: Option<u8>) -> bool {
+        self.in_flight.values().any(|p| p.cmd == cmd && p.servo == servo)
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.in_flight.is_empty()
+    }
+
+    /// Notifications encore affichées ; les plus anciennes sont retirées
+    pub fn toasts(&mut self) -> &[Toast] {
+        let now = Instant::now();
+        self.toasts.retain(|t| now.saturating_duration_since(t.shown) < TOAST_DURATION);
+        &self.toasts
+    }
+}
+
+#[cfg(feature = "gui")]
+mod gui {
+    use super::{Tracker, TOAST_DURATION};
+
+    /// Notifications d'échec empilées en bas à droite, au-dessus des panneaux
+    pub fn show_toasts(ctx: &egui::Context, tracker: &mut Tracker) {
+        let toasts = tracker.toasts();
+        if toasts.is_empty() {
+            return;
+        }
+        egui::Area::new(egui::Id::new("command_toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -12.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                for toast in toasts {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.colored_label(ui.visuals().error_fg_color, format!("✗ {}", toast.message));
+                    });
+                }
+            });
+        // Repeint pour retirer la notification à l'expiration, même sans activité
+        ctx.request_repaint_after(TOAST_DURATION / 4);
+    }
+}
+
+#[cfg(feature = "gui")]
+pub use gui::show_toasts;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn results_resolve_tracked_commands_and_toast_failures() {
+        let (responder, mut tracker) = channel();
+        let (torque, moved) = (next_id(), next_id());
+        tracker.track(torque, "torque", Some(3));
+        tracker.track(moved, "move", Some(3));
+        assert!(tracker.pending("torque", Some(3)) && !tracker.pending("torque", None));
+
+        responder.send(torque, "torque", Some(3), Ok(()));
+        responder.send(moved, "move", Some(3), Err("no response".to_string()));
+        // Résultat d'une commande non suivie : écarté
+        responder.send(next_id(), "scan", None, Err("port closed".to_string()));
+
+        let resolved = tracker.poll();
+        assert_eq!(resolved.iter().map(|r| r.id).collect::<Vec<_>>(), vec![torque, moved]);
+        assert!(tracker.is_idle());
+        let toasts: Vec<_> = tracker.toasts().iter().map(|t| t.message.clone()).collect();
+        assert_eq!(toasts, vec!["ID 3: move failed: no response"]);
+    }
+
+    #[test]
+    fn a_new_epoch_drops_commands_still_in_flight() {
+        let (responder, mut tracker) = channel();
+        let stale = next_id();
+        tracker.track(stale, "move", Some(1));
+        responder.new_epoch();
+        responder.send(stale, "move", Some(1), Err("link lost".to_string()));
+        assert!(tracker.poll().is_empty());
+        assert!(tracker.is_idle());
+        assert!(tracker.toasts().is_empty());
+
+        // Suivie après la reconnexion : attendue normalement
+        let fresh = next_id();
+        tracker.track(fresh, "move", Some(1));
+        responder.send(fresh, "move", Some(1), Ok(()));
+        assert_eq!(tracker.poll().len(), 1);
+    }
+}
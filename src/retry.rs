@@ -0,0 +1,294 @@
+//! Nouvelles tentatives sur les transactions du bus, pour les liaisons longues où une trame se
+//! perd ou arrive avec une somme de contrôle fausse.
+//!
+//! `RetryBackend` enveloppe un `ServoBackend` : chaque lecture ou écriture en échec est relancée
+//! jusqu'à `attempts` fois, et chaque échec est compté pour le servo visé dans `CommErrors`.
+//! Toutes les écritures relancées sont idempotentes (consigne, couple, mode, vitesse roue) ; le
+//! changement d'ID ne l'est pas (une réponse perdue après l'écriture ferait échouer la seconde
+//! tentative) et n'est envoyé qu'une fois. Le ping et le scan ne sont pas relancés non plus :
+//! un ID absent ne répond pas, ce n'est pas une erreur de liaison.
+
+use crate::backend::ServoBackend;
+use crate::logging;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Fenêtre pendant laquelle un compteur qui vient d'augmenter est signalé
+pub const CLIMBING_WINDOW: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetrySettings {
+    /// Tentatives par transaction, la première comprise
+    pub attempts: u32,
+    /// Pause entre deux tentatives
+    pub delay_ms: u64,
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        Self { attempts: 3, delay_ms: 2 }
+    }
+}
+
+impl RetrySettings {
+    pub fn attempts(&self) -> u32 {
+        self.attempts.max(1)
+    }
+
+    pub fn delay(&self) -> Duration {
+        Duration::from_millis(self.delay_ms)
+    }
+}
+
+/// Échecs de transaction d'un servo
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ErrorCount {
+    pub total: u64,
+    pub last: Option<Instant>,
+}
+
+impl ErrorCount {
+    /// Le compteur a augmenté récemment : câble ou connecteur à vérifier
+    pub fn is_climbing(&self, now: Instant) -> bool {
+        self.last.is_some_and(|last| now.saturating_duration_since(last) < CLIMBING_WINDOW)
+    }
+}
+
+/// Compteurs par ID, partagés entre le bus et l'interface ; ils survivent aux reconnexions
+#[derive(Clone, Debug, Default)]
+pub struct CommErrors {
+    counts: Arc<Mutex<BTreeMap<u8, ErrorCount>>>,
+}
+
+impl CommErrors {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, id: u8) {
+        let mut counts = self.counts.lock().unwrap();
+        let count = counts.entry(id).or_default();
+        count.total += 1;
+        count.last = Some(Instant::now());
+    }
+
+    pub fn get(&self, id: u8) -> ErrorCount {
+        self.counts.lock().unwrap().get(&id).copied().unwrap_or_default()
+    }
+
+    pub fn snapshot(&self) -> BTreeMap<u8, ErrorCount> {
+        self.counts.lock().unwrap().clone()
+    }
+
+    pub fn reset(&self) {
+        self.counts.lock().unwrap().clear();
+    }
+}
+
+pub struct RetryBackend {
+    inner: Box<dyn ServoBackend>,
+    settings: RetrySettings,
+    errors: CommErrors,
+}
+
+impl RetryBackend {
+    pub fn new(inner: Box<dyn ServoBackend>, settings: RetrySettings, errors: CommErrors) -> Self {
+        Self { inner, settings, errors }
+    }
+
+    /// `f` relancée tant que `ok` refuse son résultat ; le dernier résultat est rendu tel quel
+    fn retry<T>(&self, id: u8, ok: impl Fn(&T) -> bool, f: impl Fn(&dyn ServoBackend) -> T) -> T {
+        let attempts = self.settings.attempts();
+        let mut attempt = 1;
+        loop {
+            let result = f(self.inner.as_ref());
+            if ok(&result) || attempt >= attempts {
+                if !ok(&result) {
+                    self.errors.record(id);
+                    log::debug!(target: logging::BUS, "ID {}: giving up after {} attempts", id, attempts);
+                }
+                return result;
+            }
+            self.errors.record(id);
+            log::debug!(target: logging::BUS, "ID {}: attempt {}/{} failed, retrying", id, attempt, attempts);
+            attempt += 1;
+            thread::sleep(self.settings.delay());
+        }
+    }
+
+    fn read<T>(&self, id: u8, f: impl Fn(&dyn ServoBackend) -> Option<T>) -> Option<T> {
+        self.retry(id, Option::is_some, f)
+    }
+
+    fn write(&self, id: u8, f: impl Fn(&dyn ServoBackend) -> Result<(), String>) -> Result<(), String> {
+        self.retry(id, Result::is_ok, f)
+    }
+}
+
+impl ServoBackend for RetryBackend {
+    fn ping_servo(&self, id: u8) -> bool {
+        self.inner.ping_servo(id)
+    }
+
+    fn list_servos(&self) -> Vec<u8> {
+        self.inner.list_servos()
+    }
+
+    fn read_position(&self, id: u8) -> Option<u16> {
+        self.read(id, |b| b.read_position(id))
+    }
+
+    fn read_temperature(&self, id: u8) -> Option<u8> {
+        self.read(id, |b| b.read_temperature(id))
+    }
+
+    fn read_voltage(&self, id: u8) -> Option<f32> {
+        self.read(id, |b| b.read_voltage(id))
+    }
+
+    fn read_current(&self, id: u8) -> Option<f32> {
+        self.read(id, |b| b.read_current(id))
+    }
+
+    fn read_speed(&self, id: u8) -> Option<i16> {
+        self.read(id, |b| b.read_speed(id))
+    }
+
+    fn read_load(&self, id: u8) -> Option<f32> {
+        self.read(id, |b| b.read_load(id))
+    }
+
+    fn read_mode(&self, id: u8) -> Option<u8> {
+        self.read(id, |b| b.read_mode(id))
+    }
+
+    fn is_moving(&self, id: u8) -> Option<bool> {
+        self.read(id, |b| b.is_moving(id))
+    }
+
+    /// Renvoyer la même consigne est sans effet sur un servo qui l'a déjà reçue
+    fn move_to(&self, id: u8, position: u16, speed: u16, acceleration: u8, wait: bool) -> Option<bool> {
+        self.read(id, |b| b.move_to(id, position, speed, acceleration, wait))
+    }
+
+    fn write_position(&self, id: u8, position: u16) -> Option<bool> {
+        self.read(id, |b| b.write_position(id, position))
+    }
+
+    fn enable_torque(&self, id: u8) -> Result<(), String> {
+        self.write(id, |b| b.enable_torque(id))
+    }
+
+    fn disable_torque(&self, id: u8) -> Result<(), String> {
+        self.write(id, |b| b.disable_torque(id))
+    }
+
+    fn rotate(&self, id: u8, speed: i16) -> Result<(), String> {
+        self.write(id, |b| b.rotate(id, speed))
+    }
+
+    fn set_mode(&self, id: u8, mode: u8) -> Result<(), String> {
+        self.write(id, |b| b.set_mode(id, mode))
+    }
+
+    fn change_id(&self, id: u8, new_id: u8) -> Result<(), String> {
+        self.inner.change_id(id, new_id)
+    }
+
+    // Accès directs sans nouvelle tentative : l'appelant décide (écriture EEPROM, trame brute)
+    fn read_register(&self, id: u8, address: u8, size: u8) -> Result<Vec<u8>, String> {
+        self.inner.read_register(id, address, size)
+    }
+
+    fn write_register(&self, id: u8, address: u8, data: &[u8]) -> Result<(), String> {
+        self.inner.write_register(id, address, data)
+    }
+
+    fn send_raw(&self, frame: &[u8]) -> Result<Option<Vec<u8>>, String> {
+        self.inner.send_raw(frame)
+    }
+
+    fn broadcast_ping(&self, window: Duration) -> Result<Vec<u8>, String> {
+        self.inner.broadcast_ping(window)
+    }
+}
+
+#[cfg(feature = "gui")]
+mod gui {
+    use super::ErrorCount;
+    use crate::theme::{Palette, Status};
+    use std::time::Instant;
+
+    /// « errors: N » d'un servo, en rouge tant que le compteur augmente ; rien sans échec
+    pub fn error_label(ui: &mut egui::Ui, palette: &Palette, count: ErrorCount) {
+        if count.total == 0 {
+            return;
+        }
+        let text = format!("errors: {}", count.total);
+        let label = match count.is_climbing(Instant::now()) {
+            true => palette.status_label(ui, Status::Danger, text),
+            false => ui.weak(text),
+        };
+        label.on_hover_text("Failed bus transactions since launch, retries included");
+    }
+}
+
+#[cfg(feature = "gui")]
+pub use gui::error_label;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendCall, MockBackend, MockServo};
+
+    fn retrying(mock: &MockBackend, attempts: u32) -> (RetryBackend, CommErrors) {
+        let errors = CommErrors::new();
+        let settings = RetrySettings { attempts, delay_ms: 0 };
+        (RetryBackend::new(Box::new(mock.clone()), settings, errors.clone()), errors)
+    }
+
+    #[test]
+    fn failed_writes_are_retried_and_counted() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default());
+        let (bus, errors) = retrying(&mock, 3);
+
+        assert_eq!(bus.enable_torque(1), Ok(()));
+        assert_eq!(mock.take_calls(), vec![BackendCall::EnableTorque(1)]);
+        assert_eq!(errors.get(1).total, 0);
+
+        // Servo muet : trois tentatives, trois échecs comptés
+        assert!(bus.enable_torque(5).is_err());
+        assert_eq!(mock.take_calls(), vec![BackendCall::EnableTorque(5); 3]);
+        assert_eq!(bus.read_position(5), None);
+        assert_eq!(errors.get(5).total, 6);
+        assert!(errors.get(5).is_climbing(Instant::now()));
+        assert_eq!(errors.snapshot().keys().copied().collect::<Vec<_>>(), vec![5]);
+    }
+
+    #[test]
+    fn id_change_and_pings_are_sent_once() {
+        let mock = MockBackend::new();
+        let (bus, errors) = retrying(&mock, 3);
+        assert!(bus.change_id(5, 6).is_err());
+        assert_eq!(mock.calls(), vec![BackendCall::ChangeId { id: 5, new_id: 6 }]);
+        // Un ID absent n'est pas une erreur de liaison
+        assert!(!bus.ping_servo(5));
+        assert_eq!(errors.get(5).total, 0);
+    }
+
+    #[test]
+    fn at_least_one_attempt_and_counters_reset() {
+        let mock = MockBackend::new();
+        let (bus, errors) = retrying(&mock, 0);
+        assert!(bus.disable_torque(2).is_err());
+        assert_eq!(mock.calls().len(), 1);
+        assert_eq!(errors.get(2).total, 1);
+        errors.reset();
+        assert_eq!(errors.get(2), ErrorCount::default());
+        assert!(!ErrorCount::default().is_climbing(Instant::now()));
+    }
+}
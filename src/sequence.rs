@@ -0,0 +1,46 @@
+//! Séquences de mouvements rejouables, stockées en JSON.
+//!
+//! ```json
+//! { "name": "belt-check", "steps": [
+//!     { "position": 1024, "speed": 800, "acceleration": 50, "dwell_ms": 500 },
+//!     { "position": 3072, "speed": 800, "acceleration": 50, "dwell_ms": 500 }
+//! ] }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SequenceStep {
+    pub position: u16,
+    #[serde(default)]
+    pub speed: u16,
+    #[serde(default)]
+    pub acceleration: u8,
+    /// Attente après l'arrivée avant l'étape suivante
+    #[serde(default)]
+    pub dwell_ms: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Sequence {
+    #[serde(default)]
+    pub name: String,
+    pub steps: Vec<SequenceStep>,
+}
+
+impl Sequence {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let sequence: Sequence = serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        if sequence.steps.is_empty() {
+            return Err(format!("{}: sequence has no steps", path.display()));
+        }
+        Ok(sequence)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
@@ -0,0 +1,33 @@
+//! Mode shell de la CLI : découpage des lignes saisies et complétion des commandes et des ID.
+
+/// Commandes reconnues par le shell, dans l'ordre de l'aide
+pub const SHELL_COMMANDS: &[&str] = &["scan", "read", "move", "torque", "help", "exit"];
+/// Historique des commandes, dans le répertoire courant comme le fichier de configuration
+pub const HISTORY_FILE: &str = ".servo-cli-history";
+
+/// Découpe une ligne en commandes (séparées par `;`), chacune en mots ; les commandes vides sont ignorées
+pub fn split_line(line: &str) -> Vec<Vec<String>> {
+    line.split(';')
+        .map(|command| command.split_whitespace().map(str::to_string).collect::<Vec<_>>())
+        .filter(|words| !words.is_empty())
+        .collect()
+}
+
+/// Complétion du mot sous le curseur : début du mot dans `before_cursor` et candidats.
+/// Premier mot : commandes ; ensuite l'ID des commandes qui en prennent un, puis on/off pour `torque`.
+pub fn completions(before_cursor: &str, known_ids: &[u8]) -> (usize, Vec<String>) {
+    let segment_start = before_cursor.rfind(';').map_or(0, |i| i + 1);
+    let word_start = before_cursor[segment_start..]
+        .rfind(char::is_whitespace)
+        .map_or(segment_start, |i| segment_start + i + 1);
+    let prefix = &before_cursor[word_start..];
+    let previous: Vec<&str> = before_cursor[segment_start..word_start].split_whitespace().collect();
+
+    let candidates: Vec<String> = match previous.as_slice() {
+        [] => SHELL_COMMANDS.iter().map(|c| c.to_string()).collect(),
+        ["read" | "move" | "torque"] => known_ids.iter().map(|id| id.to_string()).collect(),
+        ["torque", _] => vec!["on".to_string(), "off".to_string()],
+        _ => Vec::new(),
+    };
+    (word_start, candidates.into_iter().filter(|c| c.starts_with(prefix)).collect())
+}
@@ -0,0 +1,227 @@
+//! Arrêt propre : la fenêtre (ou Ctrl+C dans la CLI) demande l'arrêt, le thread du bus termine la
+//! transaction en cours, applique les réglages `[exit]` puis rend la main avant la fin du processus.
+//!
+//! ```toml
+//! [exit]
+//! release_torque = false   # couple coupé partout en quittant ; un bras chargé retomberait
+//! park_speed = 400
+//! [exit.park]              # position de repos par ID, rejointe avant la coupure
+//! 1 = 2048
+//! 2 = 1024
+//! ```
+
+use crate::dryrun::Driver;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// Accélération des mouvements de parcage
+pub const PARK_ACCELERATION: u8 = 50;
+
+/// Intervalle de relecture du drapeau de mouvement pendant le parcage
+const PARK_POLL: Duration = Duration::from_millis(50);
+
+/// Marge laissée au thread du bus, en plus du parcage, pour finir sa transaction
+pub const JOIN_MARGIN: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExitSettings {
+    /// Coupe le couple de tous les servos détectés en quittant
+    pub release_torque: bool,
+    /// Position de repos par ID
+    pub park: BTreeMap<u8, u16>,
+    pub park_speed: u16,
+    /// Attente maximale de la fin du parcage
+    pub park_timeout_ms: u64,
+}
+
+impl Default for ExitSettings {
+    fn default() -> Self {
+        Self { release_torque: false, park: BTreeMap::new(), park_speed: 400, park_timeout_ms: 3000 }
+    }
+}
+
+impl ExitSettings {
+    /// Rien à écrire sur le bus en quittant
+    pub fn is_noop(&self) -> bool {
+        !self.release_torque && self.park.is_empty()
+    }
+
+    pub fn park_timeout(&self) -> Duration {
+        Duration::from_millis(self.park_timeout_ms)
+    }
+}
+
+/// Demande d'arrêt partagée ; les clones voient la même demande
+#[derive(Clone, Debug, Default)]
+pub struct ShutdownSignal {
+    requested: Arc<AtomicBool>,
+}
+
+impl ShutdownSignal {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExitAction {
+    Park,
+    Release,
+}
+
+impl ExitAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            ExitAction::Park => "park",
+            ExitAction::Release => "torque off",
+        }
+    }
+}
+
+/// Écriture faite en quittant, pour le compte rendu
+#[derive(Clone, Debug, PartialEq)]
+pub struct ExitStep {
+    pub id: u8,
+    pub action: ExitAction,
+    pub outcome: Result<(), String>,
+}
+
+/// Parcage des servos présents qui ont une position de repos, attente de la fin du mouvement,
+/// puis coupure du couple si demandée. En répétition, rien n'est écrit.
+pub fn park_and_release(driver: &Driver, ids: &[u8], settings: &ExitSettings) -> Vec<ExitStep> {
+    let mut steps = Vec::new();
+    let mut parked = Vec::new();
+    for (&id, &position) in settings.park.iter().filter(|(id, _)| ids.contains(id)) {
+        let outcome = match driver.move_to(id, position, settings.park_speed, PARK_ACCELERATION, false) {
+            Some(_) => {
+                parked.push(id);
+                Ok(())
+            }
+            None => Err(format!("ID {}: no response", id)),
+        };
+        steps.push(ExitStep { id, action: ExitAction::Park, outcome });
+    }
+    let deadline = Instant::now() + settings.park_timeout();
+    while !parked.is_empty() && Instant::now() < deadline {
+        thread::sleep(PARK_POLL);
+        parked.retain(|&id| driver.is_moving(id) != Some(false));
+    }
+    for &id in &parked {
+        log::warn!("ID {}: still moving after {} ms of parking", id, settings.park_timeout_ms);
+    }
+    if settings.release_torque {
+        for &id in ids {
+            steps.push(ExitStep { id, action: ExitAction::Release, outcome: driver.disable_torque(id) });
+        }
+    }
+    steps
+}
+
+/// Attend la fin du thread au plus `timeout` ; `false` s'il tourne encore (il est alors abandonné
+/// à la fin du processus)
+pub fn join_within(handle: JoinHandle<()>, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while !handle.is_finished() && Instant::now() < deadline {
+        thread::sleep(PARK_POLL);
+    }
+    if !handle.is_finished() {
+        return false;
+    }
+    if handle.join().is_err() {
+        log::warn!("bus thread panicked during shutdown");
+    }
+    true
+}
+
+#[cfg(feature = "gui")]
+mod gui {
+    use super::ExitSettings;
+
+    /// Case « Release on exit » ; `true` si elle vient de changer
+    pub fn release_checkbox(ui: &mut egui::Ui, settings: &mut ExitSettings) -> bool {
+        let mut hover = "Disable torque on every servo when the window closes. Leave off for arms that would collapse.".to_string();
+        if !settings.park.is_empty() {
+            let ids: Vec<String> = settings.park.keys().map(|id| id.to_string()).collect();
+            hover.push_str(&format!("\nID {} move to their park position first ([exit.park]).", ids.join(", ")));
+        }
+        ui.checkbox(&mut settings.release_torque, "Release on exit").on_hover_text(hover).changed()
+    }
+}
+
+#[cfg(feature = "gui")]
+pub use gui::release_checkbox;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendCall, MockBackend, MockServo};
+
+    fn driver(mock: &MockBackend, dry_run: bool) -> Driver {
+        Driver::new(mock.clone(), Arc::new(AtomicBool::new(dry_run)))
+    }
+
+    fn settings() -> ExitSettings {
+        let park = BTreeMap::from([(1, 1000), (3, 3000), (4, 2048)]);
+        ExitSettings { release_torque: true, park, park_timeout_ms: 200, ..ExitSettings::default() }
+    }
+
+    #[test]
+    fn present_servos_are_parked_then_released() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default()).with_servo(2, MockServo::default());
+        // ID 3 n'a pas été détecté ; ID 4 l'a été mais ne répond plus
+        let steps = park_and_release(&driver(&mock, false), &[1, 2, 4], &settings());
+
+        let summary: Vec<_> = steps.iter().map(|s| (s.id, s.action, s.outcome.is_ok())).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (1, ExitAction::Park, true),
+                (4, ExitAction::Park, false),
+                (1, ExitAction::Release, true),
+                (2, ExitAction::Release, true),
+                (4, ExitAction::Release, false),
+            ]
+        );
+        assert_eq!(mock.servo(1).unwrap().position, 1000);
+        assert!(!mock.calls().contains(&BackendCall::MoveTo { id: 3, position: 3000, speed: 400, acceleration: PARK_ACCELERATION }));
+        assert!(!mock.servo(2).unwrap().torque);
+    }
+
+    #[test]
+    fn dry_run_exit_writes_nothing() {
+        let mock = MockBackend::new().with_servo(1, MockServo { position: 3000, torque: true, ..MockServo::default() });
+        let steps = park_and_release(&driver(&mock, true), &[1], &settings());
+        assert!(steps.iter().all(|s| s.outcome.is_ok()));
+        assert!(mock.calls().is_empty());
+        assert!(ExitSettings::default().is_noop() && !settings().is_noop());
+    }
+
+    #[test]
+    fn bus_thread_is_joined_or_left_behind() {
+        let signal = ShutdownSignal::new();
+        let seen = signal.clone();
+        let quick = thread::spawn(move || {
+            while !seen.is_requested() {
+                thread::yield_now();
+            }
+        });
+        signal.request();
+        assert!(join_within(quick, Duration::from_secs(1)));
+
+        let slow = thread::spawn(|| thread::sleep(Duration::from_millis(500)));
+        assert!(!join_within(slow, Duration::from_millis(60)));
+    }
+}
@@ -0,0 +1,581 @@
+//! Bus ST3215 simulé : table mémoire par servo, dynamique simple et injection de fautes.
+//!
+//! Exposé derrière un pseudo-terminal, par le binaire `simserial` ou par les interfaces lancées
+//! avec `--simulate` : le port s'ouvre alors comme un adaptateur réel, accès directs compris.
+
+use crate::ids;
+use crate::logging;
+use crate::packet::{self, build_frame, checksum, INST_RESET};
+use st3215::{
+    BROADCAST_ID, INST_ACTION, INST_PING, INST_READ, INST_REG_WRITE, INST_SYNC_READ, INST_SYNC_WRITE, INST_WRITE,
+    STS_GOAL_POSITION_L, STS_GOAL_SPEED_L, STS_ID, STS_LOCK, STS_MODE, STS_MODEL_L, STS_MOVING, STS_OFS_L, STS_PRESENT_CURRENT_L,
+    STS_PRESENT_LOAD_L, STS_PRESENT_POSITION_L, STS_PRESENT_SPEED_L, STS_PRESENT_TEMPERATURE,
+    STS_PRESENT_VOLTAGE, STS_TORQUE_ENABLE,
+};
+use std::collections::BTreeMap;
+use std::io;
+use std::time::{Duration, Instant};
+
+const MODEL_NUMBER: u16 = 777;
+// Version de firmware annoncée (majeure, mineure), aux adresses 0 et 1
+const FIRMWARE: (u8, u8) = (3, 10);
+const SIM_MAX_SPEED: f64 = 3400.0;
+// Courant (mA par unité du registre) et courant simulé en mouvement
+const CURRENT_UNIT_MA: f64 = 6.5;
+const MOVING_CURRENT_MA: f64 = 180.0;
+// Registres sans constante dans le pilote : couple max (EEPROM) et limite de couple (RAM)
+const MAX_TORQUE_L: u8 = 16;
+const TORQUE_LIMIT_L: u8 = 48;
+// Échauffement : la température tend vers l'ambiante plus tant de °C par % de charge
+const AMBIENT_TEMPERATURE: f64 = 30.0;
+const HEATING_PER_LOAD_PERCENT: f64 = 2.0;
+const THERMAL_TIME_CONSTANT_S: f64 = 120.0;
+// Articulation soumise à la pesanteur : sans couple, elle retombe vers sa position de repos
+const GRAVITY_REST: f64 = 1024.0;
+const GRAVITY_SPEED: f64 = 300.0;
+// Tension nominale (unités de 0,1 V) et bruit de mesure, en unités
+const NOMINAL_VOLTAGE: u8 = 120;
+const VOLTAGE_NOISE: u32 = 1;
+/// Nombre de servos simulés par défaut avec `--simulate`
+pub const DEFAULT_SIMULATED_SERVOS: u8 = 3;
+
+/// Fautes injectées dans les réponses
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FaultConfig {
+    /// Ne répond pas à une requête sur N (0 = jamais)
+    pub drop_every: u32,
+    /// Corrompt la somme de contrôle d'une réponse sur N (0 = jamais)
+    pub corrupt_every: u32,
+    /// Retard ajouté avant chaque réponse
+    pub delay: Duration,
+}
+
+/// Servo simulé : table de contrôle de 256 octets et position flottante
+struct SimServo {
+    memory: [u8; 256],
+    position: f64,
+    temperature: f64,
+    registered: Option<(u8, Vec<u8>)>,
+}
+
+impl SimServo {
+    fn new(id: u8, position: u16) -> Self {
+        let mut memory = [0u8; 256];
+        memory[STS_MODEL_L as usize..STS_MODEL_L as usize + 2].copy_from_slice(&MODEL_NUMBER.to_le_bytes());
+        memory[STS_ID as usize] = id;
+        (memory[0], memory[1]) = FIRMWARE;
+        memory[STS_LOCK as usize] = 1;
+        memory[STS_PRESENT_VOLTAGE as usize] = NOMINAL_VOLTAGE;
+        memory[STS_PRESENT_TEMPERATURE as usize] = AMBIENT_TEMPERATURE as u8;
+        memory[STS_GOAL_POSITION_L as usize..STS_GOAL_POSITION_L as usize + 2]
+            .copy_from_slice(&position.to_le_bytes());
+        let mut servo = Self { memory, position: position as f64, temperature: AMBIENT_TEMPERATURE, registered: None };
+        // Couple max en EEPROM, repris par la limite de couple à la mise sous tension
+        servo.set_word(MAX_TORQUE_L, 1000);
+        servo.set_word(TORQUE_LIMIT_L, 1000);
+        servo.sync_present(0.0);
+        servo
+    }
+
+    fn word(&self, address: u8) -> u16 {
+        u16::from_le_bytes([self.memory[address as usize], self.memory[address as usize + 1]])
+    }
+
+    fn set_word(&mut self, address: u8, value: u16) {
+        self.memory[address as usize..address as usize + 2].copy_from_slice(&value.to_le_bytes());
+    }
+
+    // Correction de position (bit 11 = signe) : positions lues et consignes sont décalées d'autant
+    fn offset(&self) -> f64 {
+        let raw = self.word(STS_OFS_L);
+        let magnitude = (raw & 0x7FF) as f64;
+        if raw & (1 << 11) != 0 { -magnitude } else { magnitude }
+    }
+
+    fn step(&mut self, dt: f64, gravity: bool) {
+        let velocity = self.motion(dt, gravity);
+        self.sync_present(velocity);
+        self.heat(dt);
+    }
+
+    // Avance la position vers la consigne à la vitesse de consigne ; retourne la vitesse
+    fn motion(&mut self, dt: f64, gravity: bool) -> f64 {
+        if self.memory[STS_TORQUE_ENABLE as usize] == 0 {
+            if !gravity {
+                return 0.0;
+            }
+            return self.travel_towards(GRAVITY_REST, GRAVITY_SPEED, dt);
+        }
+        // Mode roue : rotation continue à la vitesse de consigne signée (bit 15)
+        if self.memory[STS_MODE as usize] == 1 {
+            let raw = self.word(STS_GOAL_SPEED_L);
+            let speed = ((raw & 0x7FFF) as f64).min(SIM_MAX_SPEED);
+            let velocity = if raw & (1 << 15) != 0 { -speed } else { speed };
+            self.position = (self.position + velocity * dt).rem_euclid(4096.0);
+            return velocity;
+        }
+        let goal = self.word(STS_GOAL_POSITION_L) as f64 + self.offset();
+        let speed = match self.word(STS_GOAL_SPEED_L) {
+            0 => SIM_MAX_SPEED,
+            s => s as f64,
+        };
+        self.travel_towards(goal, speed, dt)
+    }
+
+    fn travel_towards(&mut self, goal: f64, speed: f64, dt: f64) -> f64 {
+        let remaining = goal - self.position;
+        let travel = remaining.abs().min(speed * dt);
+        self.position += travel.copysign(remaining);
+        if dt > 0.0 { (travel / dt).copysign(remaining) } else { 0.0 }
+    }
+
+    // Premier ordre vers la température d'équilibre de la charge actuelle
+    fn heat(&mut self, dt: f64) {
+        let load_percent = (self.word(STS_PRESENT_LOAD_L) & 0x3FF) as f64 / 10.0;
+        let equilibrium = AMBIENT_TEMPERATURE + HEATING_PER_LOAD_PERCENT * load_percent;
+        self.temperature += (equilibrium - self.temperature) * (dt / THERMAL_TIME_CONSTANT_S).min(1.0);
+        self.memory[STS_PRESENT_TEMPERATURE as usize] = self.temperature.round() as u8;
+    }
+
+    fn sync_present(&mut self, velocity: f64) {
+        let moving = velocity != 0.0;
+        // Une articulation qui tombe sans couple bouge sans que le moteur ne force
+        let driven = moving && self.memory[STS_TORQUE_ENABLE as usize] != 0;
+        // Sens codé par un bit de signe : bit 15 pour la vitesse, bit 10 pour la charge
+        let negative = velocity < 0.0;
+        let present = (self.position - self.offset()).rem_euclid(4096.0);
+        self.set_word(STS_PRESENT_POSITION_L, present.round() as u16);
+        self.set_word(STS_PRESENT_SPEED_L, velocity.abs().round() as u16 | if negative { 1 << 15 } else { 0 });
+        self.set_word(STS_PRESENT_LOAD_L, if driven { 100 | if negative { 1 << 10 } else { 0 } } else { 0 });
+        let current = if driven { MOVING_CURRENT_MA } else { 0.0 };
+        self.set_word(STS_PRESENT_CURRENT_L, (current / CURRENT_UNIT_MA).round() as u16);
+        self.memory[STS_MOVING as usize] = moving as u8;
+    }
+
+    fn write(&mut self, address: u8, data: &[u8]) {
+        for (offset, byte) in data.iter().enumerate() {
+            if let Some(cell) = self.memory.get_mut(address as usize + offset) {
+                *cell = *byte;
+            }
+        }
+    }
+
+    fn read(&self, address: u8, length: u8) -> Vec<u8> {
+        let start = address as usize;
+        let end = (start + length as usize).min(self.memory.len());
+        self.memory[start..end].to_vec()
+    }
+}
+
+pub struct SimBus {
+    servos: BTreeMap<u8, SimServo>,
+    faults: FaultConfig,
+    requests: u32,
+    last_step: Instant,
+    gravity_joint: Option<u8>,
+    // Générateur du bruit de tension (xorshift, graine fixe : sessions reproductibles)
+    noise: u32,
+}
+
+impl SimBus {
+    pub fn new(ids: &[u8], faults: FaultConfig) -> Self {
+        let servos = ids.iter().map(|&id| (id, SimServo::new(id, 2048))).collect();
+        Self { servos, faults, requests: 0, last_step: Instant::now(), gravity_joint: None, noise: 0x2545_F491 }
+    }
+
+    /// Articulation qui retombe vers sa position de repos quand son couple est coupé
+    pub fn with_gravity(mut self, id: u8) -> Self {
+        self.gravity_joint = Some(id);
+        self
+    }
+
+    pub fn ids(&self) -> Vec<u8> {
+        self.servos.keys().copied().collect()
+    }
+
+    pub fn faults(&self) -> FaultConfig {
+        self.faults
+    }
+
+    fn advance(&mut self) {
+        let dt = self.last_step.elapsed().as_secs_f64();
+        self.last_step = Instant::now();
+        for (&id, servo) in self.servos.iter_mut() {
+            servo.step(dt, self.gravity_joint == Some(id));
+            self.noise ^= self.noise << 13;
+            self.noise ^= self.noise >> 17;
+            self.noise ^= self.noise << 5;
+            let deviation = (self.noise % (2 * VOLTAGE_NOISE + 1)) as u8;
+            servo.memory[STS_PRESENT_VOLTAGE as usize] = NOMINAL_VOLTAGE + deviation - VOLTAGE_NOISE as u8;
+        }
+    }
+
+    /// Traite une trame d'instruction complète et retourne la réponse éventuelle
+    pub fn handle(&mut self, frame: &[u8]) -> Option<Vec<u8>> {
+        let (id, instruction, params) = (frame[2], frame[4], &frame[5..frame.len() - 1]);
+        if checksum(id, instruction, params) != frame[frame.len() - 1] {
+            return None;
+        }
+        self.advance();
+
+        let reply = match instruction {
+            INST_SYNC_WRITE => {
+                // addr, longueur, puis [id, données...] pour chaque servo
+                let (address, length) = (*params.first()?, *params.get(1)? as usize);
+                for chunk in params[2..].chunks(length + 1).filter(|c| c.len() == length + 1) {
+                    if let Some(servo) = self.servos.get_mut(&chunk[0]) {
+                        servo.write(address, &chunk[1..]);
+                    }
+                }
+                None
+            }
+            INST_SYNC_READ => None,
+            INST_ACTION => {
+                for servo in self.servos.values_mut() {
+                    if let Some((address, data)) = servo.registered.take() {
+                        servo.write(address, &data);
+                    }
+                }
+                None
+            }
+            // Ping en diffusion : chaque servo répond à son tour, par ID croissant
+            INST_PING if id == BROADCAST_ID => {
+                let replies: Vec<u8> = self
+                    .servos
+                    .values()
+                    .filter_map(|servo| build_frame(servo.memory[STS_ID as usize], 0, &[]).ok())
+                    .flatten()
+                    .collect();
+                (!replies.is_empty()).then_some(replies)
+            }
+            _ if id == BROADCAST_ID => {
+                if instruction == INST_WRITE && params.len() > 1 {
+                    for servo in self.servos.values_mut() {
+                        servo.write(params[0], &params[1..]);
+                    }
+                }
+                None
+            }
+            _ => {
+                let servo = self.servos.get_mut(&id)?;
+                let payload = match instruction {
+                    INST_PING => Vec::new(),
+                    INST_READ if params.len() == 2 => servo.read(params[0], params[1]),
+                    INST_WRITE if params.len() > 1 => {
+                        servo.write(params[0], &params[1..]);
+                        Vec::new()
+                    }
+                    INST_REG_WRITE if params.len() > 1 => {
+                        servo.registered = Some((params[0], params[1..].to_vec()));
+                        Vec::new()
+                    }
+                    // Réglages d'usine : table neuve en ID 1, l'arbre reste où il est
+                    INST_RESET => {
+                        *servo = SimServo::new(1, (servo.position.round() as i64).rem_euclid(4096) as u16);
+                        Vec::new()
+                    }
+                    _ => return None,
+                };
+                let reply_id = servo.memory[STS_ID as usize];
+                // Changement d'ID : le servo répond désormais sous son nouvel ID
+                if reply_id != id {
+                    if let Some(servo) = self.servos.remove(&id) {
+                        self.servos.insert(reply_id, servo);
+                    }
+                }
+                // Trame de statut : l'octet d'erreur prend la place de l'instruction. Le reset est
+                // acquitté sous l'ancien ID, avant le redémarrage du servo.
+                let status_id = if instruction == INST_RESET { id } else { reply_id };
+                build_frame(status_id, 0, &payload).ok()
+            }
+        };
+
+        self.inject_faults(reply)
+    }
+
+    fn inject_faults(&mut self, reply: Option<Vec<u8>>) -> Option<Vec<u8>> {
+        let mut reply = reply?;
+        self.requests = self.requests.wrapping_add(1);
+        if self.faults.drop_every > 0 && self.requests.is_multiple_of(self.faults.drop_every) {
+            return None;
+        }
+        if self.faults.corrupt_every > 0 && self.requests.is_multiple_of(self.faults.corrupt_every) {
+            if let Some(last) = reply.last_mut() {
+                *last = last.wrapping_add(1);
+            }
+        }
+        Some(reply)
+    }
+}
+
+/// Extrait la prochaine trame complète (FF FF ID LEN ...) du tampon de réception
+pub fn extract_frame(buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
+    loop {
+        let start = buffer.windows(2).position(|w| w == [0xFF, 0xFF])?;
+        buffer.drain(..start);
+        if buffer.len() < 4 {
+            return None;
+        }
+        let length = buffer[3] as usize;
+        if length < 2 {
+            // Longueur impossible : on saute cet en-tête
+            buffer.drain(..2);
+            continue;
+        }
+        if buffer.len() < length + 4 {
+            return None;
+        }
+        return Some(buffer.drain(..length + 4).collect());
+    }
+}
+
+/// Servos virtuels des interfaces : `--simulate[=N]` (IDs 1 à N) et `--gravity-joint ID`
+/// (articulation qui retombe sans couple, ID 1 par défaut)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Simulation {
+    pub ids: Vec<u8>,
+    pub gravity_joint: Option<u8>,
+}
+
+impl Simulation {
+    /// `None` sans `--simulate`
+    pub fn from_args(args: &[String]) -> Result<Option<Self>, String> {
+        let count = match args.iter().find(|a| *a == "--simulate" || a.starts_with("--simulate=")) {
+            None => return Ok(None),
+            Some(flag) => match flag.strip_prefix("--simulate=") {
+                None => DEFAULT_SIMULATED_SERVOS,
+                Some(raw) => raw
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|n| (1..=ids::MAX_SERVO_ID).contains(n))
+                    .ok_or(format!("--simulate expects a servo count between 1 and {}", ids::MAX_SERVO_ID))?,
+            },
+        };
+        let ids: Vec<u8> = (1..=count).collect();
+        let gravity_joint = match args.iter().position(|a| a == "--gravity-joint") {
+            None => Some(1),
+            Some(i) => {
+                let raw = args.get(i + 1).ok_or("--gravity-joint expects a value")?;
+                let id = raw.parse::<u8>().map_err(|_| format!("Invalid ID: {}", raw))?;
+                if !ids.contains(&id) {
+                    return Err(format!("--gravity-joint {}: no simulated servo with this ID (1-{})", id, count));
+                }
+                Some(id)
+            }
+        };
+        Ok(Some(Self { ids, gravity_joint }))
+    }
+
+    /// `--simulate` lu et bus démarré, avec le port à ouvrir ; `None` sans le drapeau
+    pub fn launch(args: &[String]) -> Result<Option<(Self, String)>, String> {
+        let Some(simulation) = Self::from_args(args)? else { return Ok(None) };
+        let port = simulation.start().map_err(|e| format!("Could not start the simulated bus: {}", e))?;
+        Ok(Some((simulation, port)))
+    }
+
+    /// Démarre le bus simulé dans un thread ; retourne le port à ouvrir
+    pub fn start(&self) -> io::Result<String> {
+        let mut bus = SimBus::new(&self.ids, FaultConfig::default());
+        if let Some(id) = self.gravity_joint {
+            bus = bus.with_gravity(id);
+        }
+        spawn(bus)
+    }
+
+    /// Bandeau des interfaces, pour que personne ne croie piloter du matériel
+    pub fn banner(&self) -> String {
+        let plural = if self.ids.len() > 1 { "s" } else { "" };
+        let range = match (self.ids.first(), self.ids.last()) {
+            (Some(first), Some(last)) if first != last => format!("ID {}-{}", first, last),
+            (Some(first), _) => format!("ID {}", first),
+            _ => String::new(),
+        };
+        format!("SIMULATION — {} virtual servo{} ({}), no hardware connected", self.ids.len(), plural, range)
+    }
+}
+
+/// Ouvre une paire maître/esclave de pseudo-terminal en mode brut ; retourne aussi le chemin
+/// de l'esclave, à ouvrir comme un port série
+#[cfg(unix)]
+pub fn open_pty() -> io::Result<(std::fs::File, std::fs::File, String)> {
+    use std::ffi::CStr;
+    use std::fs::File;
+    use std::os::fd::FromRawFd;
+
+    let mut master = 0;
+    let mut slave = 0;
+    let mut name = [0 as libc::c_char; 256];
+    // SAFETY: tampons valides, termios et winsize optionnels
+    let rc = unsafe { libc::openpty(&mut master, &mut slave, name.as_mut_ptr(), std::ptr::null(), std::ptr::null()) };
+    if rc != 0 {
+        return Err(io::Error::last_os_error());
+    }
+
+    // SAFETY: descripteurs fraîchement ouverts, possédés par les `File` retournés
+    let (master, slave) = unsafe { (File::from_raw_fd(master), File::from_raw_fd(slave)) };
+    set_raw(&slave)?;
+    // SAFETY: openpty écrit une chaîne terminée par NUL
+    let path = unsafe { CStr::from_ptr(name.as_ptr()) }.to_string_lossy().into_owned();
+    Ok((master, slave, path))
+}
+
+// Pas d'écho ni de traduction de fins de ligne : les trames sont binaires
+#[cfg(unix)]
+fn set_raw(file: &std::fs::File) -> io::Result<()> {
+    use std::os::fd::AsRawFd;
+    // SAFETY: termios est rempli par tcgetattr avant usage
+    unsafe {
+        let mut tio: libc::termios = std::mem::zeroed();
+        if libc::tcgetattr(file.as_raw_fd(), &mut tio) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        libc::cfmakeraw(&mut tio);
+        if libc::tcsetattr(file.as_raw_fd(), libc::TCSANOW, &tio) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Répond aux trames reçues sur le maître, avec le retard injecté ; chaque échange est journalisé
+/// au niveau `trace` (cible `sim`)
+#[cfg(unix)]
+pub fn serve(master: &mut std::fs::File, bus: &mut SimBus) -> io::Result<()> {
+    use std::io::{Read, Write};
+
+    let delay = bus.faults().delay;
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 256];
+    loop {
+        let n = master.read(&mut chunk)?;
+        buffer.extend_from_slice(&chunk[..n]);
+
+        while let Some(frame) = extract_frame(&mut buffer) {
+            if let Some(reply) = bus.handle(&frame) {
+                if !delay.is_zero() {
+                    std::thread::sleep(delay);
+                }
+                master.write_all(&reply)?;
+                log::trace!(target: logging::SIM, "→ {} ← {}", packet::to_hex(&frame), packet::to_hex(&reply));
+            }
+        }
+    }
+}
+
+/// Sert le bus dans un thread, pour toute la durée du processus ; retourne le chemin du port
+#[cfg(unix)]
+pub fn spawn(mut bus: SimBus) -> io::Result<String> {
+    let (mut master, slave, path) = open_pty()?;
+    std::thread::spawn(move || {
+        // L'esclave reste ouvert entre deux connexions du client, sinon la lecture du maître échoue
+        let _slave = slave;
+        if let Err(e) = serve(&mut master, &mut bus) {
+            eprintln!("Simulated bus stopped: {}", e);
+        }
+    });
+    Ok(path)
+}
+
+#[cfg(not(unix))]
+pub fn spawn(_bus: SimBus) -> io::Result<String> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "the simulated bus needs a Unix pseudo-terminal"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hotplug::FastScan;
+
+    #[test]
+    fn broadcast_ping_is_answered_by_every_servo() {
+        let mut bus = SimBus::new(&[3, 1, 7], FaultConfig::default());
+        let replies = bus.handle(&build_frame(BROADCAST_ID, INST_PING, &[]).unwrap()).unwrap();
+        assert_eq!(FastScan::from_bytes(&replies).ids, vec![1, 3, 7]);
+        assert_eq!(SimBus::new(&[], FaultConfig::default()).handle(&build_frame(BROADCAST_ID, INST_PING, &[]).unwrap()), None);
+    }
+
+
+    #[test]
+    fn wheel_mode_turns_at_the_signed_goal_speed() {
+        let mut servo = SimServo::new(1, 100);
+        servo.memory[STS_TORQUE_ENABLE as usize] = 1;
+        servo.memory[STS_MODE as usize] = 1;
+        servo.set_word(STS_GOAL_SPEED_L, 400 | 1 << 15);
+        servo.step(0.5, false);
+        // Un tour complet : la position repart de 4095
+        assert_eq!(servo.word(STS_PRESENT_POSITION_L), 3996);
+        assert_eq!(servo.word(STS_PRESENT_SPEED_L), 400 | 1 << 15);
+        assert_eq!(servo.memory[STS_MOVING as usize], 1);
+
+        servo.set_word(STS_GOAL_SPEED_L, 0);
+        servo.step(0.5, false);
+        assert_eq!(servo.word(STS_PRESENT_POSITION_L), 3996);
+        assert_eq!(servo.memory[STS_MOVING as usize], 0);
+    }
+
+
+    #[test]
+    fn reset_restores_id_one_and_keeps_the_shaft() {
+        let mut bus = SimBus::new(&[5], FaultConfig::default());
+        bus.servos.get_mut(&5).unwrap().position = 3000.0;
+        let reply = bus.handle(&build_frame(5, INST_RESET, &[]).unwrap());
+        // Acquitté sous l'ancien ID
+        assert_eq!(reply, Some(build_frame(5, 0, &[]).unwrap()));
+        assert_eq!(bus.ids(), vec![1]);
+        let position = bus.handle(&build_frame(1, INST_READ, &[STS_PRESENT_POSITION_L, 2]).unwrap()).unwrap();
+        assert_eq!(position, build_frame(1, 0, &3000u16.to_le_bytes()).unwrap());
+    }
+
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn simulate_flag_picks_servos_and_gravity_joint() {
+        assert_eq!(Simulation::from_args(&args(&["--port", "/dev/ttyUSB0"])), Ok(None));
+        let default = Simulation::from_args(&args(&["--simulate"])).unwrap().unwrap();
+        assert_eq!(default, Simulation { ids: vec![1, 2, 3], gravity_joint: Some(1) });
+        assert_eq!(default.banner(), "SIMULATION — 3 virtual servos (ID 1-3), no hardware connected");
+
+        let one = Simulation::from_args(&args(&["--simulate=1"])).unwrap().unwrap();
+        assert_eq!(one.banner(), "SIMULATION — 1 virtual servo (ID 1), no hardware connected");
+        let joint = Simulation::from_args(&args(&["--simulate=5", "--gravity-joint", "4"])).unwrap().unwrap();
+        assert_eq!(joint.gravity_joint, Some(4));
+
+        assert!(Simulation::from_args(&args(&["--simulate=0"])).is_err());
+        assert!(Simulation::from_args(&args(&["--simulate=2", "--gravity-joint", "3"])).is_err());
+        assert!(Simulation::from_args(&args(&["--simulate", "--gravity-joint"])).is_err());
+    }
+
+    #[test]
+    fn frames_are_split_out_of_the_serial_stream() {
+        let ping = build_frame(1, INST_PING, &[]).unwrap();
+        let read = build_frame(2, INST_READ, &[STS_PRESENT_POSITION_L, 2]).unwrap();
+        // Octets parasites, en-tête de longueur impossible, puis deux trames et un début de troisième
+        let mut buffer = vec![0x00, 0xFF, 0xFF, 0x01, 0x01];
+        buffer.extend(&ping);
+        buffer.extend(&read);
+        buffer.extend(&read[..3]);
+        assert_eq!(extract_frame(&mut buffer), Some(ping));
+        assert_eq!(extract_frame(&mut buffer), Some(read.clone()));
+        assert_eq!(extract_frame(&mut buffer), None);
+        assert_eq!(buffer, read[..3]);
+    }
+
+    #[test]
+    fn gravity_joint_falls_only_without_torque() {
+        let mut servo = SimServo::new(1, 3000);
+        servo.memory[STS_TORQUE_ENABLE as usize] = 1;
+        servo.step(0.5, true);
+        assert_eq!(servo.word(STS_PRESENT_POSITION_L), 3000);
+
+        servo.memory[STS_TORQUE_ENABLE as usize] = 0;
+        servo.step(0.5, false);
+        assert_eq!(servo.word(STS_PRESENT_POSITION_L), 3000);
+        servo.step(0.5, true);
+        assert_eq!(servo.word(STS_PRESENT_POSITION_L), 3000 - (GRAVITY_SPEED * 0.5) as u16);
+        // Chute sans couple : le moteur ne force pas
+        assert_eq!(servo.word(STS_PRESENT_LOAD_L), 0);
+    }
+}
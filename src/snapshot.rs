@@ -0,0 +1,218 @@
+//! Instantanés de réponse d'un servo à une séquence de test, pour comparer avant/après
+//! un réglage mécanique.
+
+use crate::sequence::Sequence;
+use serde::{Deserialize, Serialize};
+use crate::backend::ServoBackend;
+use crate::validation::{validate_move, MoveConstraints};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Écart (ticks) sous lequel le servo est considéré stabilisé sur sa consigne
+pub const SETTLE_TOLERANCE: u16 = 10;
+const SAMPLE_PERIOD: Duration = Duration::from_millis(20);
+// Limite par étape si le servo n'atteint jamais sa consigne
+const STEP_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct ResponseSample {
+    /// Secondes depuis le début de la capture
+    pub t: f64,
+    pub position: u16,
+    pub current_ma: Option<f32>,
+    pub temperature: Option<u8>,
+}
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+pub struct ResponseMetrics {
+    /// Pire temps de stabilisation parmi les étapes
+    pub settle_time_s: f64,
+    /// Pire dépassement de consigne parmi les étapes
+    pub overshoot_ticks: u16,
+    pub peak_current_ma: f32,
+    pub temperature_rise: i16,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub label: String,
+    pub servo_id: u8,
+    pub unix_ms: u64,
+    pub sequence: Sequence,
+    pub samples: Vec<ResponseSample>,
+    pub metrics: ResponseMetrics,
+}
+
+impl Snapshot {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(path, json).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+}
+
+/// Ligne du tableau de comparaison
+#[derive(Clone, Debug)]
+pub struct MetricDiff {
+    pub name: &'static str,
+    pub unit: &'static str,
+    pub before: f64,
+    pub after: f64,
+}
+
+impl MetricDiff {
+    pub fn delta(&self) -> f64 {
+        self.after - self.before
+    }
+}
+
+/// Joue la séquence sur `id` et enregistre la réponse complète. Toutes les étapes sont validées
+/// contre `constraints` avant le premier mouvement : une étape hors butées est ramenée dedans.
+pub fn capture(
+    driver: &dyn ServoBackend,
+    id: u8,
+    label: &str,
+    sequence: &Sequence,
+    constraints: &MoveConstraints,
+) -> Result<Snapshot, String> {
+    let moves = sequence
+        .steps
+        .iter()
+        .map(|step| {
+            validate_move(constraints, step.position.into(), step.speed.into(), step.acceleration.into())
+                .map_err(|e| format!("ID {}: {}", id, e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    driver.enable_torque(id)?;
+    let start = Instant::now();
+    let mut samples = Vec::new();
+    let mut metrics = ResponseMetrics::default();
+
+    for (step, m) in sequence.steps.iter().zip(moves) {
+        let origin = driver
+            .read_position(id)
+            .ok_or_else(|| format!("ID {}: no position reading", id))?;
+        driver
+            .move_to(id, m.position, m.speed, m.acceleration, false)
+            .ok_or_else(|| format!("ID {}: move not acknowledged", id))?;
+
+        let step_start = Instant::now();
+        // Instant de la dernière sortie de la bande de tolérance
+        let mut settled_at: Option<Duration> = None;
+        let mut dwell_start: Option<Instant> = None;
+
+        while step_start.elapsed() < STEP_TIMEOUT {
+            if let Some(position) = driver.read_position(id) {
+                samples.push(ResponseSample {
+                    t: start.elapsed().as_secs_f64(),
+                    position,
+                    current_ma: driver.read_current(id),
+                    temperature: driver.read_temperature(id),
+                });
+
+                // Dépassement : au-delà de la consigne, dans le sens du mouvement
+                let overshoot = if m.position >= origin {
+                    position.saturating_sub(m.position)
+                } else {
+                    m.position.saturating_sub(position)
+                };
+                metrics.overshoot_ticks = metrics.overshoot_ticks.max(overshoot);
+
+                if position.abs_diff(m.position) <= SETTLE_TOLERANCE {
+                    settled_at.get_or_insert(step_start.elapsed());
+                    dwell_start.get_or_insert_with(Instant::now);
+                } else {
+                    settled_at = None;
+                    dwell_start = None;
+                }
+            }
+
+            let dwell_done = dwell_start
+                .map(|d| d.elapsed() >= Duration::from_millis(step.dwell_ms))
+                .unwrap_or(false);
+            if dwell_done && driver.is_moving(id) == Some(false) {
+                break;
+            }
+            thread::sleep(SAMPLE_PERIOD);
+        }
+
+        let settle = settled_at.unwrap_or(STEP_TIMEOUT).as_secs_f64();
+        metrics.settle_time_s = metrics.settle_time_s.max(settle);
+    }
+
+    metrics.peak_current_ma = samples
+        .iter()
+        .filter_map(|s| s.current_ma)
+        .fold(0.0, f32::max);
+    let temperatures: Vec<u8> = samples.iter().filter_map(|s| s.temperature).collect();
+    if let (Some(first), Some(last)) = (temperatures.first(), temperatures.last()) {
+        metrics.temperature_rise = *last as i16 - *first as i16;
+    }
+
+    let unix_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0);
+
+    Ok(Snapshot {
+        label: label.to_string(),
+        servo_id: id,
+        unix_ms,
+        sequence: sequence.clone(),
+        samples,
+        metrics,
+    })
+}
+
+/// Tableau des écarts de métriques entre deux instantanés
+pub fn compare(before: &Snapshot, after: &Snapshot) -> Vec<MetricDiff> {
+    let (b, a) = (&before.metrics, &after.metrics);
+    vec![
+        MetricDiff { name: "Settle time", unit: "s", before: b.settle_time_s, after: a.settle_time_s },
+        MetricDiff { name: "Overshoot", unit: "ticks", before: b.overshoot_ticks as f64, after: a.overshoot_ticks as f64 },
+        MetricDiff { name: "Peak current", unit: "mA", before: b.peak_current_ma as f64, after: a.peak_current_ma as f64 },
+        MetricDiff { name: "Temperature rise", unit: "°C", before: b.temperature_rise as f64, after: a.temperature_rise as f64 },
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendCall, MockBackend, MockServo};
+    use crate::limits::SoftLimits;
+    use crate::sequence::SequenceStep;
+
+    fn sequence(positions: &[u16]) -> Sequence {
+        let steps = positions.iter().map(|&position| SequenceStep { position, speed: 500, acceleration: 20, dwell_ms: 0 }).collect();
+        Sequence { name: "test".to_string(), steps }
+    }
+
+    #[test]
+    fn steps_are_clamped_to_the_soft_limits() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default());
+        let constraints = MoveConstraints { limits: SoftLimits { min: 1000, max: 3000, ..Default::default() }, ..Default::default() };
+        capture(&mock, 1, "clamped", &sequence(&[3500, 2000]), &constraints).unwrap();
+        let targets: Vec<u16> = mock
+            .calls()
+            .into_iter()
+            .filter_map(|call| match call {
+                BackendCall::MoveTo { position, .. } => Some(position),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(targets, vec![3000, 2000]);
+    }
+
+    #[test]
+    fn refused_capture_moves_nothing() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default());
+        let constraints = MoveConstraints { stalled: true, ..Default::default() };
+        assert!(capture(&mock, 1, "stalled", &sequence(&[2100]), &constraints).is_err());
+        assert!(mock.calls().is_empty());
+    }
+}
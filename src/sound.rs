@@ -0,0 +1,171 @@
+//! Alertes sonores pour les événements critiques : un son WAV embarqué par classe, joué sur la
+//! sortie audio par défaut (rodio). La cloche du terminal sert de secours, sans sortie audio ou
+//! sans la feature `gui`.
+//!
+//! Le son est joué sur un thread dédié pour ne bloquer ni l'interface ni le worker série.
+
+use std::io::Write;
+use std::sync::mpsc::{channel, Sender};
+use std::thread;
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SoundClass {
+    EmergencyStop,
+    OverTemperature,
+    Stall,
+    Disconnect,
+    // Fin d'une routine longue (échauffement...)
+    Completion,
+}
+
+impl SoundClass {
+    pub const ALL: [SoundClass; 5] = [
+        SoundClass::EmergencyStop,
+        SoundClass::OverTemperature,
+        SoundClass::Stall,
+        SoundClass::Disconnect,
+        SoundClass::Completion,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SoundClass::EmergencyStop => "E-Stop",
+            SoundClass::OverTemperature => "Over-temperature",
+            SoundClass::Stall => "Stall",
+            SoundClass::Disconnect => "Disconnect",
+            SoundClass::Completion => "Routine complete",
+        }
+    }
+
+    // Son embarqué : bips du même nombre que la cloche, plus aigus pour les alertes graves
+    #[cfg(feature = "rodio")]
+    fn wav(self) -> &'static [u8] {
+        match self {
+            SoundClass::EmergencyStop => include_bytes!("../assets/sounds/estop.wav"),
+            SoundClass::OverTemperature => include_bytes!("../assets/sounds/overtemp.wav"),
+            SoundClass::Stall => include_bytes!("../assets/sounds/stall.wav"),
+            SoundClass::Disconnect => include_bytes!("../assets/sounds/disconnect.wav"),
+            SoundClass::Completion => include_bytes!("../assets/sounds/complete.wav"),
+        }
+    }
+
+    // Nombre de bips, pour distinguer les alertes à l'oreille
+    fn beeps(self) -> u32 {
+        match self {
+            SoundClass::EmergencyStop => 4,
+            SoundClass::OverTemperature => 3,
+            SoundClass::Stall => 2,
+            SoundClass::Disconnect => 1,
+            SoundClass::Completion => 5,
+        }
+    }
+}
+
+pub struct SoundAlerts {
+    pub muted: bool,
+    pub enabled: Vec<SoundClass>,
+    tx: Sender<SoundClass>,
+}
+
+impl SoundAlerts {
+    pub fn new() -> Self {
+        let (tx, rx) = channel::<SoundClass>();
+        thread::spawn(move || {
+            // Le thread s'arrête quand le `SoundAlerts` est détruit
+            let mut player = Player::default();
+            while let Ok(class) = rx.recv() {
+                if !player.play(class) {
+                    ring_bell(class.beeps());
+                }
+            }
+        });
+        Self { muted: false, enabled: SoundClass::ALL.to_vec(), tx }
+    }
+
+    /// Joue l'alerte si la classe est activée et que le son n'est pas coupé
+    pub fn notify(&self, class: SoundClass) {
+        if !self.muted && self.enabled.contains(&class) {
+            let _ = self.tx.send(class);
+        }
+    }
+}
+
+// Cloche du terminal : sans effet pour une interface lancée hors d'un terminal
+fn ring_bell(beeps: u32) {
+    let mut err = std::io::stderr();
+    for _ in 0..beeps {
+        let _ = err.write_all(b"\x07");
+        let _ = err.flush();
+        thread::sleep(Duration::from_millis(200));
+    }
+}
+
+// Sortie audio du thread des alertes, ouverte au premier son : `Some(None)` quand il n'y en a
+// pas (pas de carte son, serveur audio arrêté), la cloche prend alors le relais
+#[cfg(feature = "rodio")]
+#[derive(Default)]
+struct Player {
+    output: Option<Option<rodio::OutputStream>>,
+}
+
+#[cfg(feature = "rodio")]
+impl Player {
+    // Joue le son de la classe jusqu'au bout ; faux s'il n'a pas pu être joué
+    fn play(&mut self, class: SoundClass) -> bool {
+        let output = self.output.get_or_insert_with(|| match rodio::OutputStreamBuilder::open_default_stream() {
+            Ok(mut stream) => {
+                stream.log_on_drop(false);
+                Some(stream)
+            }
+            Err(e) => {
+                log::warn!("no audio output ({}), sound alerts use the terminal bell", e);
+                None
+            }
+        });
+        let Some(stream) = output else { return false };
+        match rodio::play(stream.mixer(), std::io::Cursor::new(class.wav())) {
+            Ok(sink) => {
+                sink.sleep_until_end();
+                true
+            }
+            Err(e) => {
+                log::warn!("{} sound: {}", class.label(), e);
+                false
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "rodio"))]
+#[derive(Default)]
+struct Player;
+
+#[cfg(not(feature = "rodio"))]
+impl Player {
+    fn play(&mut self, _class: SoundClass) -> bool {
+        false
+    }
+}
+
+impl Default for SoundAlerts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(all(test, feature = "rodio"))]
+mod tests {
+    use super::*;
+    use rodio::Source;
+
+    #[test]
+    fn bundled_sounds_decode() {
+        for class in SoundClass::ALL {
+            let decoder = rodio::Decoder::new(std::io::Cursor::new(class.wav())).unwrap();
+            assert_eq!(decoder.channels(), 1, "{}", class.label());
+            let duration = decoder.total_duration().unwrap();
+            assert!(duration > Duration::from_millis(300) && duration < Duration::from_secs(1), "{}", class.label());
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Détection de blocage mécanique : charge ou courant au-dessus d'un seuil pendant plusieurs
+//! relevés consécutifs d'un servo en mouvement. Le servo est alors arrêté et marqué bloqué
+//! jusqu'à l'acquittement de l'utilisateur.
+//!
+//! ```toml
+//! [stall]
+//! enabled = true
+//! load_percent = 90.0
+//! current_ma = 0.0
+//! polls = 3
+//! action = "hold"
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// Réponse à un blocage confirmé
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StallAction {
+    /// Consigne ramenée à la position actuelle : le servo tient sans forcer
+    #[default]
+    Hold,
+    TorqueOff,
+}
+
+impl StallAction {
+    pub const ALL: [StallAction; 2] = [StallAction::Hold, StallAction::TorqueOff];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StallAction::Hold => "Hold position",
+            StallAction::TorqueOff => "Torque off",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StallSettings {
+    pub enabled: bool,
+    /// Charge (%, en valeur absolue) jugée excessive ; 0 = ignorée
+    pub load_percent: f32,
+    /// Courant (mA) jugé excessif ; 0 = ignoré
+    pub current_ma: f32,
+    /// Relevés consécutifs au-dessus d'un seuil avant de conclure au blocage
+    pub polls: u32,
+    pub action: StallAction,
+}
+
+impl Default for StallSettings {
+    fn default() -> Self {
+        Self { enabled: true, load_percent: 90.0, current_ma: 0.0, polls: 3, action: StallAction::Hold }
+    }
+}
+
+/// Blocage constaté, avec les pics relevés pendant la surcharge
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stall {
+    pub peak_load: Option<f32>,
+    pub peak_current: Option<f32>,
+}
+
+impl Stall {
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(load) = self.peak_load {
+            parts.push(format!("peak load {:.0}%", load));
+        }
+        if let Some(current) = self.peak_current {
+            parts.push(format!("peak current {:.0} mA", current));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Compteur de relevés en surcharge d'un servo
+#[derive(Clone, Debug, Default)]
+pub struct StallDetector {
+    over: u32,
+    peak_load: Option<f32>,
+    peak_current: Option<f32>,
+}
+
+impl StallDetector {
+    /// Relevé de charge et/ou de courant ; retourne le blocage une fois confirmé (le compteur
+    /// repart alors de zéro). Un relevé hors mouvement ou sous les seuils remet le compteur à zéro.
+    pub fn observe(&mut self, settings: &StallSettings, moving: bool, load: Option<f32>, current: Option<f32>) -> Option<Stall> {
+        if !settings.enabled || !moving {
+            self.reset();
+            return None;
+        }
+        // Relevé sans grandeur surveillée : le compteur n'avance ni ne repart
+        let load = load.filter(|_| settings.load_percent > 0.0);
+        let current = current.filter(|_| settings.current_ma > 0.0);
+        if load.is_none() && current.is_none() {
+            return None;
+        }
+        let load_over = load.is_some_and(|l| l.abs() >= settings.load_percent);
+        let current_over = current.is_some_and(|c| c.abs() >= settings.current_ma);
+        if !(load_over || current_over) {
+            self.reset();
+            return None;
+        }
+        self.over += 1;
+        if let Some(load) = load {
+            self.peak_load = Some(self.peak_load.map_or(load.abs(), |p| p.max(load.abs())));
+        }
+        if let Some(current) = current {
+            self.peak_current = Some(self.peak_current.map_or(current.abs(), |p| p.max(current.abs())));
+        }
+        if self.over < settings.polls.max(1) {
+            return None;
+        }
+        let stall = Stall { peak_load: self.peak_load, peak_current: self.peak_current };
+        self.reset();
+        Some(stall)
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+}
+
+#[cfg(feature = "gui")]
+mod gui {
+    use super::{StallAction, StallSettings};
+
+    /// Réglages de détection sur une ligne ; vrai si l'un d'eux a changé
+    pub fn settings_editor(ui: &mut egui::Ui, settings: &mut StallSettings) -> bool {
+        let before = settings.clone();
+        ui.horizontal_wrapped(|ui| {
+            ui.checkbox(&mut settings.enabled, "Enabled");
+            ui.label("Load above:");
+            ui.add(egui::DragValue::new(&mut settings.load_percent).range(0.0..=100.0).suffix(" %"))
+                .on_hover_text("0 = load not watched");
+            ui.label("or current above:");
+            ui.add(egui::DragValue::new(&mut settings.current_ma).range(0.0..=3000.0).suffix(" mA"))
+                .on_hover_text("0 = current not watched");
+            ui.label("for");
+            ui.add(egui::DragValue::new(&mut settings.polls).range(1..=50).suffix(" readings"));
+            ui.label("then");
+            egui::ComboBox::from_id_salt("stall_action")
+                .selected_text(settings.action.label())
+                .show_ui(ui, |ui| {
+                    for action in StallAction::ALL {
+                        ui.selectable_value(&mut settings.action, action, action.label());
+                    }
+                });
+        });
+        *settings != before
+    }
+}
+
+#[cfg(feature = "gui")]
+pub use gui::settings_editor;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stall_needs_consecutive_overloaded_polls_while_moving() {
+        let settings = StallSettings::default();
+        let mut detector = StallDetector::default();
+        assert_eq!(detector.observe(&settings, true, Some(95.0), None), None);
+        assert_eq!(detector.observe(&settings, true, Some(-97.0), None), None);
+        assert_eq!(detector.observe(&settings, true, Some(92.0), None), Some(Stall { peak_load: Some(97.0), peak_current: None }));
+        // Le compteur repart de zéro après un blocage confirmé
+        assert_eq!(detector.observe(&settings, true, Some(95.0), None), None);
+
+        // Un relevé sous le seuil ou à l'arrêt remet le compteur à zéro
+        detector.observe(&settings, true, Some(50.0), None);
+        detector.observe(&settings, true, Some(95.0), None);
+        detector.observe(&settings, false, Some(95.0), None);
+        assert_eq!(detector.observe(&settings, true, Some(95.0), None), None);
+    }
+
+    #[test]
+    fn unwatched_readings_neither_count_nor_reset() {
+        let settings = StallSettings { current_ma: 800.0, polls: 2, ..StallSettings::default() };
+        let mut detector = StallDetector::default();
+        assert_eq!(detector.observe(&settings, true, None, Some(900.0)), None);
+        assert_eq!(detector.observe(&settings, true, None, None), None);
+        let stall = detector.observe(&settings, true, Some(10.0), Some(1200.0)).unwrap();
+        assert_eq!(stall.describe(), "peak load 10%, peak current 1200 mA");
+
+        let disabled = StallSettings { enabled: false, ..StallSettings::default() };
+        assert!((0..5).all(|_| detector.observe(&disabled, true, Some(100.0), None).is_none()));
+    }
+}
@@ -0,0 +1,199 @@
+//! Journal continu de télémétrie (essais d'endurance) : une ligne CSV par servo et par cycle de
+//! lecture, en ajout seul. Le fichier est vidé sur disque au moins une fois par seconde et
+//! remplacé par `nom-1.csv`, `nom-2.csv`... quand il dépasse la taille maximale. `delta` est
+//! l'écart consigne − position, vide pour un servo en mode roue.
+//!
+//! ```text
+//! unix_ms,time_s,servo,position,temperature,voltage,current,load,speed,delta
+//! 1735732800123,12.300,1,2048,31,12.1,45,-3.2,0,-12
+//! ```
+
+use crate::plugins::TelemetryFrame;
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+pub const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+const HEADER: &str = "unix_ms,time_s,servo,position,temperature,voltage,current,load,speed,delta";
+
+/// Réglages du journal, lus dans `[logging]` du fichier de configuration
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogSettings {
+    pub path: String,
+    /// Taille (Mo) au-delà de laquelle on passe au fichier suivant
+    pub max_size_mb: u64,
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self { path: "telemetry.csv".to_string(), max_size_mb: 50 }
+    }
+}
+
+pub struct TelemetryLog {
+    base: PathBuf,
+    max_bytes: u64,
+    index: u32,
+    path: PathBuf,
+    writer: BufWriter<File>,
+    written: u64,
+    flushed: Instant,
+}
+
+impl TelemetryLog {
+    /// Ouvre (ou reprend) le premier fichier de la série qui n'a pas atteint la taille maximale
+    pub fn open(settings: &LogSettings) -> Result<Self, String> {
+        let base = PathBuf::from(&settings.path);
+        let max_bytes = settings.max_size_mb.max(1) * 1024 * 1024;
+        let mut index = 0;
+        while std::fs::metadata(numbered(&base, index)).is_ok_and(|m| m.len() >= max_bytes) {
+            index += 1;
+        }
+        let (path, writer, written) = open_file(&base, index)?;
+        Ok(Self { base, max_bytes, index, path, writer, written, flushed: Instant::now() })
+    }
+
+    /// Fichier en cours d'écriture
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn write(&mut self, frame: &TelemetryFrame) -> Result<(), String> {
+        if self.written >= self.max_bytes {
+            self.writer.flush().map_err(|e| format!("{}: {}", self.path.display(), e))?;
+            self.index += 1;
+            (self.path, self.writer, self.written) = open_file(&self.base, self.index)?;
+        }
+        let unix_ms = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_millis()).unwrap_or(0);
+        let cell = |value: Option<String>| value.unwrap_or_default();
+        let line = format!(
+            "{},{:.3},{},{},{},{},{},{},{},{}\n",
+            unix_ms,
+            frame.time,
+            frame.servo,
+            cell(frame.position.map(|v| v.to_string())),
+            cell(frame.temperature.map(|v| v.to_string())),
+            cell(frame.voltage.map(|v| format!("{:.2}", v))),
+            cell(frame.current.map(|v| format!("{:.1}", v))),
+            cell(frame.load.map(|v| format!("{:.1}", v))),
+            cell(frame.speed.map(|v| v.to_string())),
+            cell(frame.delta.map(|v| v.to_string())),
+        );
+        self.writer
+            .write_all(line.as_bytes())
+            .map_err(|e| format!("{}: {}", self.path.display(), e))?;
+        self.written += line.len() as u64;
+        self.flush_due()
+    }
+
+    /// Vide le tampon si la dernière écriture sur disque date de plus d'une seconde
+    pub fn flush_due(&mut self) -> Result<(), String> {
+        if self.flushed.elapsed() < FLUSH_INTERVAL {
+            return Ok(());
+        }
+        self.flushed = Instant::now();
+        self.writer.flush().map_err(|e| format!("{}: {}", self.path.display(), e))
+    }
+}
+
+// `telemetry.csv`, puis `telemetry-1.csv`, `telemetry-2.csv`...
+fn numbered(base: &Path, index: u32) -> PathBuf {
+    if index == 0 {
+        return base.to_path_buf();
+    }
+    let stem = base.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let name = match base.extension() {
+        Some(ext) => format!("{}-{}.{}", stem, index, ext.to_string_lossy()),
+        None => format!("{}-{}", stem, index),
+    };
+    base.with_file_name(name)
+}
+
+// Ouverture en ajout ; l'en-tête n'est écrit que dans un fichier vide
+fn open_file(base: &Path, index: u32) -> Result<(PathBuf, BufWriter<File>, u64), String> {
+    let path = numbered(base, index);
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut written = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let mut writer = BufWriter::new(file);
+    if written == 0 {
+        writeln!(writer, "{}", HEADER).map_err(|e| format!("{}: {}", path.display(), e))?;
+        written = HEADER.len() as u64 + 1;
+    }
+    Ok((path, writer, written))
+}
+
+/// Ouvre ou ferme `log` selon l'interrupteur de l'interface, puis écrit les relevés du cycle.
+/// Retourne le nouvel état quand il change (ouverture, passage au fichier suivant, arrêt) ;
+/// en cas d'erreur le journal est fermé et l'erreur retournée.
+pub fn sync(
+    log: &mut Option<TelemetryLog>,
+    enabled: bool,
+    settings: &LogSettings,
+    frames: &[TelemetryFrame],
+) -> Option<Result<String, String>> {
+    if !enabled {
+        let mut closed = log.take()?;
+        return Some(
+            closed
+                .writer
+                .flush()
+                .map(|_| format!("Stopped logging to {}", closed.path.display()))
+                .map_err(|e| format!("{}: {}", closed.path.display(), e)),
+        );
+    }
+    let mut status = None;
+    if log.is_none() {
+        match TelemetryLog::open(settings) {
+            Ok(opened) => {
+                status = Some(Ok(format!("Logging to {}", opened.path.display())));
+                *log = Some(opened);
+            }
+            Err(e) => return Some(Err(e)),
+        }
+    }
+    let current = log.as_mut()?;
+    let index = current.index;
+    let outcome = frames.iter().try_for_each(|frame| current.write(frame)).and_then(|_| current.flush_due());
+    if let Err(e) = outcome {
+        *log = None;
+        return Some(Err(e));
+    }
+    if current.index != index {
+        status = Some(Ok(format!("Logging to {}", current.path.display())));
+    }
+    status
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delta_is_logged_after_the_speed() {
+        let dir = std::env::temp_dir().join(format!("init-servo-telemetrylog-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let settings = LogSettings { path: dir.join("telemetry.csv").to_string_lossy().into_owned(), max_size_mb: 1 };
+        let _ = std::fs::remove_file(&settings.path);
+        let mut log = None;
+        let tracking = TelemetryFrame { servo: 1, time: 1.5, position: Some(2036), speed: Some(0), delta: Some(-12), ..Default::default() };
+        let wheel = TelemetryFrame { servo: 2, time: 1.5, position: Some(100), ..Default::default() };
+        assert!(sync(&mut log, true, &settings, &[tracking, wheel]).unwrap().is_ok());
+        assert!(sync(&mut log, false, &settings, &[]).unwrap().is_ok());
+
+        let text = std::fs::read_to_string(&settings.path).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], HEADER);
+        // unix_ms dépend de l'horloge : seules les colonnes suivantes sont comparées
+        let columns = |line: &str| line.split_once(',').unwrap().1.to_string();
+        assert_eq!(columns(lines[1]), "1.500,1,2036,,,,,0,-12");
+        assert_eq!(columns(lines[2]), "1.500,2,100,,,,,,");
+        let _ = std::fs::remove_file(&settings.path);
+    }
+}
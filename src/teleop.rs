@@ -0,0 +1,335 @@
+//! Téléopération maître-esclave sur deux bus : un bras meneur non motorisé (couple coupé, manipulé
+//! à la main) sur un port, un bras suiveur motorisé sur l'autre. À chaque cycle, les positions de
+//! toutes les articulations du meneur sont lues puis envoyées au suiveur, aussi vite que les bus
+//! le permettent.
+//!
+//! Une lecture ou une écriture sans réponse gèle le suiveur : le cycle s'arrête sans rien écrire de
+//! plus, et la téléopération doit être relancée.
+//!
+//! ```toml
+//! [teleop]
+//! leader_port = "/dev/ttyACM0"
+//! follower_port = "/dev/ttyACM1"
+//! [[teleop.joints]]   # sans articulation : IDs du meneur repris tels quels au scan
+//! leader = 1
+//! follower = 11
+//! offset = -40
+//! inverted = true
+//! ```
+
+use crate::dryrun::Driver;
+use crate::ids::MAX_SERVO_ID;
+use crate::inversion;
+use crate::limits::SoftLimits;
+use crate::units::MAX_TICKS;
+use crate::validation::{validate_move, FirstMoveGuard, MoveConstraints};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::time::{Duration, Instant};
+
+/// Fenêtre de mesure de la cadence
+const RATE_WINDOW: Duration = Duration::from_secs(1);
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TeleopSettings {
+    pub leader_port: String,
+    pub follower_port: String,
+    /// Vitesse et accélération des consignes du suiveur (0 = maximum)
+    pub speed: u16,
+    pub acceleration: u8,
+    /// Correspondance meneur → suiveur, par articulation
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub joints: Vec<TeleopJoint>,
+}
+
+impl Default for TeleopSettings {
+    fn default() -> Self {
+        Self {
+            leader_port: "/dev/ttyACM0".to_string(),
+            follower_port: "/dev/ttyACM1".to_string(),
+            speed: 1500,
+            acceleration: 50,
+            joints: Vec::new(),
+        }
+    }
+}
+
+impl TeleopSettings {
+    pub fn active_joints(&self) -> Vec<TeleopJoint> {
+        self.joints.iter().copied().filter(|joint| joint.enabled).collect()
+    }
+
+    /// Articulation 1:1 pour chaque ID du meneur qui n'en a pas encore
+    pub fn add_identity_joints(&mut self, leader_ids: &[u8]) -> usize {
+        let known: BTreeSet<u8> = self.joints.iter().map(|joint| joint.leader).collect();
+        let added: Vec<TeleopJoint> = leader_ids.iter().filter(|id| !known.contains(id)).map(|&id| TeleopJoint::identity(id)).collect();
+        self.joints.extend(&added);
+        added.len()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TeleopJoint {
+    pub leader: u8,
+    pub follower: u8,
+    /// Décalage (ticks) ajouté après l'éventuel miroir
+    pub offset: i32,
+    /// Position du meneur en miroir autour de 2048 (articulation montée à l'envers)
+    pub inverted: bool,
+    pub enabled: bool,
+}
+
+impl Default for TeleopJoint {
+    fn default() -> Self {
+        Self::identity(1)
+    }
+}
+
+impl TeleopJoint {
+    pub fn identity(id: u8) -> Self {
+        Self { leader: id, follower: id, offset: 0, inverted: false, enabled: true }
+    }
+
+    /// Consigne du suiveur pour une position du meneur
+    pub fn target(&self, leader: u16) -> u16 {
+        let position = if self.inverted { inversion::mirror(leader) } else { leader };
+        (i32::from(position) + self.offset).clamp(0, i32::from(MAX_TICKS)) as u16
+    }
+}
+
+/// IDs valides, et un seul meneur par servo suiveur
+pub fn check_joints(joints: &[TeleopJoint]) -> Result<(), String> {
+    let mut followers = BTreeSet::new();
+    for joint in joints.iter().filter(|joint| joint.enabled) {
+        if let Some(id) = [joint.leader, joint.follower].into_iter().find(|&id| id > MAX_SERVO_ID) {
+            return Err(format!("ID {} is reserved (valid IDs: 0-{})", id, MAX_SERVO_ID));
+        }
+        if !followers.insert(joint.follower) {
+            return Err(format!("follower ID {} is driven by two joints", joint.follower));
+        }
+    }
+    Ok(())
+}
+
+/// Mise en route : couple coupé sur le meneur pour le manipuler, couple activé sur le suiveur.
+/// Le premier mouvement est gardé avant de toucher au couple : refusé si la consigne d'une
+/// articulation (bornée par `limits`) est à plus de `max_delta` ticks du suiveur (0 = sans garde).
+pub fn start(
+    leader: &Driver,
+    follower: &Driver,
+    joints: &[TeleopJoint],
+    limits: &BTreeMap<u8, SoftLimits>,
+    max_delta: u16,
+) -> Result<(), String> {
+    let guard = FirstMoveGuard::new();
+    for joint in joints {
+        let Some(first_move) = guard.check(joint.follower, max_delta, || follower.read_position(joint.follower)) else { continue };
+        let position = leader.read_position(joint.leader).ok_or(format!("leader ID {}: no response", joint.leader))?;
+        let limits = limits.get(&joint.follower).copied().unwrap_or_default();
+        let constraints = MoveConstraints { limits, first_move: Some(first_move), ..Default::default() };
+        validate_move(&constraints, joint.target(position).into(), 0, 0)
+            .map_err(|e| format!("follower ID {}: {}; bring the leader arm to the follower's pose", joint.follower, e))?;
+    }
+    for joint in joints {
+        leader.disable_torque(joint.leader).map_err(|e| format!("leader ID {}: {}", joint.leader, e))?;
+        follower.enable_torque(joint.follower).map_err(|e| format!("follower ID {}: {}", joint.follower, e))?;
+    }
+    Ok(())
+}
+
+/// Lecture et écriture d'un cycle
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CycleReport {
+    /// (articulation, position du meneur, consigne du suiveur)
+    pub joints: Vec<(TeleopJoint, u16, u16)>,
+    pub read: Duration,
+    pub write: Duration,
+}
+
+/// Un cycle : toutes les positions du meneur d'abord, puis les consignes du suiveur qui ont changé
+/// depuis `sent`, ramenées dans les butées logicielles du suiveur (`limits`). Une lecture sans
+/// réponse ou une consigne refusée arrête le cycle avant toute écriture.
+pub fn cycle(
+    leader: &Driver,
+    follower: &Driver,
+    joints: &[TeleopJoint],
+    settings: &TeleopSettings,
+    limits: &BTreeMap<u8, SoftLimits>,
+    sent: &mut HashMap<u8, u16>,
+) -> Result<CycleReport, String> {
+    let started = Instant::now();
+    let mut report = CycleReport::default();
+    let mut moves = Vec::new();
+    for &joint in joints {
+        let position = leader.read_position(joint.leader).ok_or(format!("leader ID {}: no response", joint.leader))?;
+        let constraints = MoveConstraints { limits: limits.get(&joint.follower).copied().unwrap_or_default(), ..Default::default() };
+        let m = validate_move(&constraints, joint.target(position).into(), settings.speed.into(), settings.acceleration.into())
+            .map_err(|e| format!("follower ID {}: {}", joint.follower, e))?;
+        report.joints.push((joint, position, m.position));
+        moves.push(m);
+    }
+    report.read = started.elapsed();
+    for (&(joint, _, target), m) in report.joints.iter().zip(moves) {
+        if sent.get(&joint.follower) == Some(&target) {
+            continue;
+        }
+        follower
+            .move_to(joint.follower, target, m.speed, m.acceleration, false)
+            .ok_or(format!("follower ID {}: no response", joint.follower))?;
+        sent.insert(joint.follower, target);
+    }
+    report.write = started.elapsed() - report.read;
+    Ok(report)
+}
+
+/// Cadence et durée des cycles
+#[derive(Clone, Debug, Default)]
+pub struct TeleopStats {
+    pub cycles: u64,
+    /// Cycles par seconde, sur la dernière fenêtre complète
+    pub rate_hz: f32,
+    /// Lecture du meneur et écriture du suiveur au dernier cycle
+    pub read: Duration,
+    pub write: Duration,
+    /// Plus long cycle de la dernière fenêtre complète
+    pub max_cycle: Duration,
+    window_start: Option<Instant>,
+    window_cycles: u32,
+    window_max: Duration,
+}
+
+impl TeleopStats {
+    pub fn record(&mut self, report: &CycleReport, now: Instant) {
+        self.cycles += 1;
+        self.read = report.read;
+        self.write = report.write;
+        self.window_cycles += 1;
+        self.window_max = self.window_max.max(report.read + report.write);
+        let start = *self.window_start.get_or_insert(now);
+        let elapsed = now.saturating_duration_since(start);
+        if elapsed >= RATE_WINDOW {
+            self.rate_hz = self.window_cycles as f32 / elapsed.as_secs_f32();
+            self.max_cycle = self.window_max;
+            self.window_start = Some(now);
+            self.window_cycles = 0;
+            self.window_max = Duration::ZERO;
+        }
+    }
+
+    pub fn cycle(&self) -> Duration {
+        self.read + self.write
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendCall, MockBackend, MockServo};
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    fn driver(mock: &MockBackend) -> Driver {
+        Driver::new(mock.clone(), Arc::new(AtomicBool::new(false)))
+    }
+
+    #[test]
+    fn follower_targets_stay_inside_its_soft_limits() {
+        let leader = MockBackend::new().with_servo(1, MockServo { position: 3900, ..Default::default() });
+        let follower = MockBackend::new().with_servo(11, MockServo::default());
+        let joints = [TeleopJoint { follower: 11, ..TeleopJoint::identity(1) }];
+        let limits = BTreeMap::from([(11, SoftLimits { min: 500, max: 3500, ..Default::default() })]);
+        let mut sent = HashMap::new();
+
+        let report = cycle(&driver(&leader), &driver(&follower), &joints, &TeleopSettings::default(), &limits, &mut sent).unwrap();
+        assert_eq!(report.joints[0].2, 3500);
+        assert!(matches!(follower.calls()[..], [BackendCall::MoveTo { id: 11, position: 3500, .. }]));
+    }
+
+    #[test]
+    fn invalid_settings_freeze_before_any_write() {
+        let leader = MockBackend::new().with_servo(1, MockServo::default());
+        let follower = MockBackend::new().with_servo(1, MockServo::default());
+        let settings = TeleopSettings { speed: u16::MAX, ..Default::default() };
+        let result = cycle(&driver(&leader), &driver(&follower), &[TeleopJoint::identity(1)], &settings, &BTreeMap::new(), &mut HashMap::new());
+        assert!(result.is_err());
+        assert!(follower.calls().is_empty());
+    }
+
+    #[test]
+    fn start_refuses_a_large_first_jump() {
+        let leader = MockBackend::new().with_servo(1, MockServo { position: 3000, ..Default::default() });
+        let follower = MockBackend::new().with_servo(1, MockServo { position: 1000, ..Default::default() });
+        let joints = [TeleopJoint::identity(1)];
+        assert!(start(&driver(&leader), &driver(&follower), &joints, &BTreeMap::new(), 500).is_err());
+        assert!(leader.calls().is_empty() && follower.calls().is_empty());
+
+        leader.update(1, |servo| servo.position = 1200);
+        assert!(start(&driver(&leader), &driver(&follower), &joints, &BTreeMap::new(), 500).is_ok());
+        assert!(follower.servo(1).unwrap().torque);
+    }
+
+    #[test]
+    fn mirrored_joint_with_offset() {
+        let joint = TeleopJoint { inverted: true, offset: -40, ..TeleopJoint::identity(2) };
+        assert_eq!(joint.target(2048), 2008);
+        assert_eq!(joint.target(0), MAX_TICKS - 40);
+        assert!(check_joints(&[TeleopJoint::identity(3), TeleopJoint { leader: 4, ..TeleopJoint::identity(3) }]).is_err());
+    }
+
+
+    #[test]
+    fn only_changed_targets_are_sent() {
+        let leader = MockBackend::new()
+            .with_servo(1, MockServo { position: 1000, ..Default::default() })
+            .with_servo(2, MockServo { position: 2000, ..Default::default() });
+        let follower = MockBackend::new().with_servo(1, MockServo::default()).with_servo(2, MockServo::default());
+        let joints = [TeleopJoint::identity(1), TeleopJoint::identity(2)];
+        let (settings, mut sent) = (TeleopSettings::default(), HashMap::new());
+        let run = |sent: &mut HashMap<u8, u16>| cycle(&driver(&leader), &driver(&follower), &joints, &settings, &BTreeMap::new(), sent);
+
+        run(&mut sent).unwrap();
+        assert_eq!(follower.take_calls().len(), 2);
+        leader.update(2, |servo| servo.position = 2100);
+        let report = run(&mut sent).unwrap();
+        assert_eq!(report.joints.iter().map(|&(_, position, target)| (position, target)).collect::<Vec<_>>(), vec![(1000, 1000), (2100, 2100)]);
+        assert_eq!(
+            follower.take_calls(),
+            vec![BackendCall::MoveTo { id: 2, position: 2100, speed: settings.speed, acceleration: settings.acceleration }]
+        );
+
+        // Meneur muet : cycle arrêté sans rien écrire
+        leader.unplug(1);
+        leader.update(2, |servo| servo.position = 2200);
+        assert_eq!(run(&mut sent).unwrap_err(), "leader ID 1: no response");
+        assert!(follower.calls().is_empty());
+    }
+
+    #[test]
+    fn scan_adds_identity_joints_once() {
+        let mut settings = TeleopSettings { joints: vec![TeleopJoint { follower: 11, ..TeleopJoint::identity(1) }], ..Default::default() };
+        assert_eq!(settings.add_identity_joints(&[1, 2, 3]), 2);
+        assert_eq!(settings.add_identity_joints(&[1, 2, 3]), 0);
+        settings.joints[1].enabled = false;
+        let active: Vec<(u8, u8)> = settings.active_joints().iter().map(|joint| (joint.leader, joint.follower)).collect();
+        assert_eq!(active, vec![(1, 11), (3, 3)]);
+        // Articulation désactivée : hors vérification
+        let disabled = TeleopJoint { enabled: false, ..TeleopJoint::identity(3) };
+        assert!(check_joints(&[TeleopJoint::identity(3), disabled]).is_ok());
+        assert!(check_joints(&[TeleopJoint::identity(MAX_SERVO_ID + 1)]).is_err());
+    }
+
+    #[test]
+    fn stats_measure_the_rate_over_a_window() {
+        let mut stats = TeleopStats::default();
+        let report = CycleReport { read: Duration::from_millis(3), write: Duration::from_millis(2), ..Default::default() };
+        let start = Instant::now();
+        for i in 0..=50 {
+            stats.record(&report, start + Duration::from_millis(20 * i));
+        }
+        assert_eq!(stats.cycles, 51);
+        assert_eq!(stats.rate_hz, 51.0);
+        assert_eq!((stats.cycle(), stats.max_cycle), (Duration::from_millis(5), Duration::from_millis(5)));
+    }
+}
@@ -0,0 +1,184 @@
+//! Thèmes de couleurs (défaut, contraste élevé, adapté au daltonisme).
+//!
+//! Un état n'est jamais signalé par la couleur seule : chaque `Status` a aussi son icône.
+
+use serde::{Deserialize, Serialize};
+
+pub type Rgb = [u8; 3];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    #[default]
+    Default,
+    HighContrast,
+    ColorblindSafe,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Default, Theme::HighContrast, Theme::ColorblindSafe];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Theme::Default => "Default",
+            Theme::HighContrast => "High contrast",
+            Theme::ColorblindSafe => "Colorblind-safe",
+        }
+    }
+
+    pub fn palette(self) -> Palette {
+        match self {
+            Theme::Default => Palette {
+                ok: [46, 204, 113],
+                warning: [230, 126, 34],
+                danger: [231, 76, 60],
+                info: [52, 152, 219],
+                accent: [155, 89, 182],
+                traces: [[52, 152, 219], [231, 76, 60], [155, 89, 182], [241, 196, 15], [26, 188, 156], [149, 165, 166]],
+            },
+            Theme::HighContrast => Palette {
+                ok: [0, 255, 128],
+                warning: [255, 221, 0],
+                danger: [255, 64, 64],
+                info: [0, 200, 255],
+                accent: [255, 128, 255],
+                traces: [[0, 200, 255], [255, 221, 0], [255, 128, 255], [0, 255, 128], [255, 255, 255], [255, 140, 0]],
+            },
+            // Palette Okabe-Ito : distinguable en deutéranopie et protanopie
+            Theme::ColorblindSafe => Palette {
+                ok: [0, 114, 178],
+                warning: [230, 159, 0],
+                danger: [213, 94, 0],
+                info: [86, 180, 233],
+                accent: [204, 121, 167],
+                traces: [[0, 114, 178], [230, 159, 0], [0, 158, 115], [204, 121, 167], [86, 180, 233], [240, 228, 66]],
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    Warning,
+    Danger,
+}
+
+impl Status {
+    pub fn icon(self) -> &'static str {
+        match self {
+            Status::Ok => "●",
+            Status::Warning => "▲",
+            Status::Danger => "✖",
+        }
+    }
+}
+
+/// Seuils d'affichage de la température (°C)
+pub fn temperature_status(temperature: u8) -> Status {
+    if temperature > 60 {
+        Status::Danger
+    } else if temperature > 45 {
+        Status::Warning
+    } else {
+        Status::Ok
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Palette {
+    pub ok: Rgb,
+    pub warning: Rgb,
+    pub danger: Rgb,
+    pub info: Rgb,
+    pub accent: Rgb,
+    /// Couleurs des courbes, dans l'ordre d'attribution
+    pub traces: [Rgb; 6],
+}
+
+impl Palette {
+    pub fn status_rgb(&self, status: Status) -> Rgb {
+        match status {
+            Status::Ok => self.ok,
+            Status::Warning => self.warning,
+            Status::Danger => self.danger,
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+mod gui {
+    use super::{Palette, Rgb, Status, Theme};
+    use egui::Color32;
+
+    pub fn color(rgb: Rgb) -> Color32 {
+        Color32::from_rgb(rgb[0], rgb[1], rgb[2])
+    }
+
+    impl Palette {
+        pub fn status(&self, status: Status) -> Color32 {
+            color(self.status_rgb(status))
+        }
+
+        pub fn warning(&self) -> Color32 {
+            color(self.warning)
+        }
+
+        pub fn danger(&self) -> Color32 {
+            color(self.danger)
+        }
+
+        pub fn info(&self) -> Color32 {
+            color(self.info)
+        }
+
+        pub fn accent(&self) -> Color32 {
+            color(self.accent)
+        }
+
+        pub fn trace(&self, index: usize) -> Color32 {
+            color(self.traces[index % self.traces.len()])
+        }
+
+        /// Libellé coloré précédé de l'icône de l'état
+        pub fn status_label(&self, ui: &mut egui::Ui, status: Status, text: impl std::fmt::Display) -> egui::Response {
+            ui.colored_label(self.status(status), format!("{} {}", status.icon(), text))
+        }
+    }
+
+    /// Applique les visuels egui du thème en conservant le reste du style
+    pub fn apply(theme: Theme, ctx: &egui::Context) {
+        ctx.style_mut(|style| {
+            let visuals = &mut style.visuals;
+            let base = if visuals.dark_mode { egui::Visuals::dark() } else { egui::Visuals::light() };
+            match theme {
+                Theme::HighContrast => {
+                    let (fg, bg) = if visuals.dark_mode {
+                        (Color32::WHITE, Color32::BLACK)
+                    } else {
+                        (Color32::BLACK, Color32::WHITE)
+                    };
+                    visuals.override_text_color = Some(fg);
+                    visuals.panel_fill = bg;
+                    visuals.window_fill = bg;
+                    visuals.extreme_bg_color = bg;
+                    visuals.widgets.noninteractive.bg_stroke = egui::Stroke::new(1.5, fg);
+                    visuals.widgets.inactive.bg_stroke = egui::Stroke::new(1.0, fg);
+                    visuals.selection.stroke = egui::Stroke::new(2.0, fg);
+                }
+                Theme::Default | Theme::ColorblindSafe => {
+                    visuals.override_text_color = None;
+                    visuals.panel_fill = base.panel_fill;
+                    visuals.window_fill = base.window_fill;
+                    visuals.extreme_bg_color = base.extreme_bg_color;
+                    visuals.widgets.noninteractive.bg_stroke = base.widgets.noninteractive.bg_stroke;
+                    visuals.widgets.inactive.bg_stroke = base.widgets.inactive.bg_stroke;
+                    visuals.selection.stroke = base.selection.stroke;
+                }
+            }
+        });
+    }
+}
+
+#[cfg(feature = "gui")]
+pub use gui::{apply, color};
@@ -0,0 +1,144 @@
+//! Réglage des gains de la boucle de position : lecture et écriture relue des coefficients
+//! P, I et D, et essai de réponse indicielle (±200 ticks autour de la position actuelle) relevé
+//! au rythme maximal du bus.
+
+use crate::registers::{self, Register, RegisterPort};
+use crate::snapshot::SETTLE_TOLERANCE;
+use crate::backend::ServoBackend;
+use crate::validation::{validate_move, MoveConstraints};
+use std::ops::RangeInclusive;
+use std::time::{Duration, Instant};
+
+/// Amplitude de chaque échelon, de part et d'autre de la position de départ
+pub const STEP_TICKS: u16 = 200;
+/// Relevé de chaque échelon, le temps que le servo se stabilise
+const STEP_DURATION: Duration = Duration::from_millis(1500);
+/// Écart minimal entre deux relevés : sans effet sur un vrai bus, plus lent que cela, mais
+/// borne le nombre de points face au simulateur
+const MIN_SAMPLE_PERIOD: Duration = Duration::from_millis(2);
+
+fn register(name: &str) -> &'static Register {
+    registers::find_register(name).expect("register in table")
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PidGains {
+    pub p: u8,
+    pub i: u8,
+    pub d: u8,
+}
+
+impl PidGains {
+    /// Valeurs d'usine de la table des registres
+    pub fn factory() -> Self {
+        let value = |name| register(name).factory.unwrap_or_default() as u8;
+        Self { p: value("P Coefficient"), i: value("I Coefficient"), d: value("D Coefficient") }
+    }
+
+    pub fn read(bus: &mut RegisterPort, id: u8) -> Result<Self, String> {
+        let mut read = |name| bus.read(id, register(name)).map(|v| v as u8);
+        Ok(Self { p: read("P Coefficient")?, i: read("I Coefficient")?, d: read("D Coefficient")? })
+    }
+
+    /// Écrit les trois coefficients (EEPROM), chacun relu
+    pub fn write(&self, bus: &mut RegisterPort, id: u8) -> Result<(), String> {
+        for (name, value) in [("P Coefficient", self.p), ("I Coefficient", self.i), ("D Coefficient", self.d)] {
+            bus.write(id, register(name), i32::from(value))?;
+        }
+        Ok(())
+    }
+}
+
+/// Réponse relevée : positions, consignes successives et pires métriques des échelons
+#[derive(Clone, Debug, Default)]
+pub struct StepResponse {
+    /// (secondes depuis le début de l'essai, position)
+    pub samples: Vec<(f64, u16)>,
+    /// (instant d'envoi, consigne)
+    pub targets: Vec<(f64, u16)>,
+    pub overshoot_ticks: u16,
+    pub settle_time_s: f64,
+}
+
+/// Consignes de l'essai : +200, −200, puis retour au départ ; refusé si l'une sort de `allowed`
+pub fn step_targets(origin: u16, allowed: &RangeInclusive<u16>) -> Result<[u16; 3], String> {
+    let (high, low) = (origin.checked_add(STEP_TICKS), origin.checked_sub(STEP_TICKS));
+    match (high, low) {
+        (Some(high), Some(low)) if allowed.contains(&high) && allowed.contains(&low) => Ok([high, low, origin]),
+        _ => Err(format!(
+            "position {} is too close to the limits {}..{} for a ±{} step",
+            origin,
+            allowed.start(),
+            allowed.end(),
+            STEP_TICKS
+        )),
+    }
+}
+
+/// Joue les trois échelons à vitesse max (plafonnée par `constraints`), position relue aussi vite
+/// que le bus le permet. Les trois consignes sont validées avant le premier mouvement.
+pub fn step_response(
+    driver: &dyn ServoBackend,
+    id: u8,
+    allowed: &RangeInclusive<u16>,
+    constraints: &MoveConstraints,
+) -> Result<StepResponse, String> {
+    let origin = driver.read_position(id).ok_or_else(|| format!("ID {}: no position reading", id))?;
+    let moves = step_targets(origin, allowed)?
+        .into_iter()
+        .map(|target| validate_move(constraints, target.into(), 0, 0).map_err(|e| format!("ID {}: {}", id, e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    driver.enable_torque(id)?;
+    let start = Instant::now();
+    let mut response = StepResponse::default();
+    let mut previous = origin;
+    for m in moves {
+        let target = m.position;
+        driver
+            .move_to(id, target, m.speed, m.acceleration, false)
+            .ok_or_else(|| format!("ID {}: move not acknowledged", id))?;
+        let step_start = Instant::now();
+        response.targets.push((start.elapsed().as_secs_f64(), target));
+        let mut settled_at: Option<Duration> = None;
+        while step_start.elapsed() < STEP_DURATION {
+            let sample_start = Instant::now();
+            let read = driver.read_position(id);
+            std::thread::sleep(MIN_SAMPLE_PERIOD.saturating_sub(sample_start.elapsed()));
+            let Some(position) = read else { continue };
+            response.samples.push((start.elapsed().as_secs_f64(), position));
+            // Dépassement : au-delà de la consigne, dans le sens du mouvement
+            let overshoot = if target >= previous { position.saturating_sub(target) } else { target.saturating_sub(position) };
+            response.overshoot_ticks = response.overshoot_ticks.max(overshoot);
+            if position.abs_diff(target) <= SETTLE_TOLERANCE {
+                settled_at.get_or_insert(step_start.elapsed());
+            } else {
+                settled_at = None;
+            }
+        }
+        let settle = settled_at.unwrap_or(STEP_DURATION).as_secs_f64();
+        response.settle_time_s = response.settle_time_s.max(settle);
+        previous = target;
+    }
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{MockBackend, MockServo};
+
+    #[test]
+    fn steps_stay_inside_the_allowed_range() {
+        assert_eq!(step_targets(2048, &(0..=4095)), Ok([2248, 1848, 2048]));
+        assert!(step_targets(150, &(0..=4095)).is_err());
+        assert!(step_targets(2048, &(1900..=4095)).is_err());
+    }
+
+    #[test]
+    fn step_test_is_refused_before_any_move() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default());
+        let constraints = MoveConstraints { emergency_stop: true, ..Default::default() };
+        assert!(step_response(&mock, 1, &(0..=4095), &constraints).is_err());
+        assert!(mock.calls().is_empty());
+    }
+}
@@ -0,0 +1,213 @@
+//! Conversions d'unités de position (4096 ticks par tour, centre à 2048).
+
+use serde::{Deserialize, Serialize};
+
+pub const TICKS_PER_REV: f32 = 4096.0;
+pub const CENTER_TICKS: u16 = 2048;
+pub const MAX_TICKS: u16 = 4095;
+
+/// Degrés signés autour du centre, l'unité de la CLI (`--deg`)
+const CENTERED_DEGREES: AngleDisplay = AngleDisplay { unit: AngleUnit::Degrees, range: AngleRange::Centered };
+
+/// Ticks bruts → degrés signés autour du centre
+pub fn ticks_to_degrees(ticks: u16) -> f32 {
+    CENTERED_DEGREES.from_ticks(ticks) as f32
+}
+
+/// Degrés signés → ticks bruts, bornés à 0..=4095
+pub fn degrees_to_ticks(degrees: f32) -> u16 {
+    CENTERED_DEGREES.to_ticks(degrees as f64)
+}
+
+/// Unité d'affichage des positions dans les interfaces
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AngleUnit {
+    #[default]
+    Ticks,
+    Degrees,
+    Radians,
+}
+
+impl AngleUnit {
+    pub const ALL: [AngleUnit; 3] = [AngleUnit::Ticks, AngleUnit::Degrees, AngleUnit::Radians];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AngleUnit::Ticks => "ticks",
+            AngleUnit::Degrees => "degrees",
+            AngleUnit::Radians => "radians",
+        }
+    }
+}
+
+/// Origine des angles : centrés sur 2048 (−180°..+180°) ou comptés depuis 0 (0–360°)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum AngleRange {
+    #[default]
+    Centered,
+    Full,
+}
+
+impl AngleRange {
+    pub const ALL: [AngleRange; 2] = [AngleRange::Centered, AngleRange::Full];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AngleRange::Centered => "centered on 2048",
+            AngleRange::Full => "from 0",
+        }
+    }
+}
+
+/// Affichage et saisie des positions dans l'unité choisie ; les consignes restent en ticks
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AngleDisplay {
+    #[serde(default)]
+    pub unit: AngleUnit,
+    #[serde(default)]
+    pub range: AngleRange,
+}
+
+impl AngleDisplay {
+    // Ticks par unité affichée, et tick de l'angle zéro
+    fn scale(self) -> (f64, f64) {
+        let per_turn = match self.unit {
+            AngleUnit::Ticks => return (1.0, 0.0),
+            AngleUnit::Degrees => 360.0,
+            AngleUnit::Radians => std::f64::consts::TAU,
+        };
+        let zero = match self.range {
+            AngleRange::Centered => CENTER_TICKS as f64,
+            AngleRange::Full => 0.0,
+        };
+        (TICKS_PER_REV as f64 / per_turn, zero)
+    }
+
+    pub fn from_ticks(self, ticks: u16) -> f64 {
+        let (ticks_per_unit, zero) = self.scale();
+        (ticks as f64 - zero) / ticks_per_unit
+    }
+
+    /// Valeur affichée → ticks, bornés à 0..=4095
+    pub fn to_ticks(self, value: f64) -> u16 {
+        let (ticks_per_unit, zero) = self.scale();
+        (zero + value * ticks_per_unit).round().clamp(0.0, MAX_TICKS as f64) as u16
+    }
+
+    /// Position lue : entière en ticks, une décimale en degrés, trois en radians
+    pub fn format(self, ticks: u16) -> String {
+        self.format_value(self.from_ticks(ticks))
+    }
+
+    /// Écart signé en ticks (consigne − position), dans l'unité choisie
+    pub fn format_delta(self, ticks: i32) -> String {
+        let (ticks_per_unit, _) = self.scale();
+        let value = ticks as f64 / ticks_per_unit;
+        match self.unit {
+            AngleUnit::Ticks => format!("{:+}", ticks),
+            AngleUnit::Degrees => format!("{:+.1}°", value),
+            AngleUnit::Radians => format!("{:+.3} rad", value),
+        }
+    }
+
+    pub fn format_value(self, value: f64) -> String {
+        match self.unit {
+            AngleUnit::Ticks => format!("{:.0}", value),
+            AngleUnit::Degrees => format!("{:.1}°", value),
+            AngleUnit::Radians => format!("{:.3} rad", value),
+        }
+    }
+
+    /// Saisie clavier dans un slider : nombre, suffixe d'unité facultatif
+    pub fn parse(self, text: &str) -> Option<f64> {
+        let number = text.trim().trim_end_matches("rad").trim_end_matches('°').trim();
+        number.parse().ok()
+    }
+}
+
+#[cfg(feature = "gui")]
+mod gui {
+    use super::{AngleDisplay, AngleRange, AngleUnit};
+    use std::ops::RangeInclusive;
+
+    /// Slider de position borné à `range` (butées logicielles) : la valeur reste en ticks,
+    /// affichée et saisie dans l'unité choisie. Une consigne déjà hors de la fenêtre n'est pas
+    /// ramenée d'office, ce qui enverrait un mouvement que personne n'a demandé.
+    pub fn position_slider(ticks: &mut u16, range: RangeInclusive<u16>, display: AngleDisplay) -> egui::Slider<'_> {
+        egui::Slider::new(ticks, range)
+            .clamping(egui::SliderClamping::Edits)
+            .custom_formatter(move |value, _| display.format(value.round() as u16))
+            .custom_parser(move |text| display.parse(text).map(|value| display.to_ticks(value) as f64))
+    }
+
+    /// Saisie numérique de la position, dans l'unité et la fenêtre du slider ; comme lui, une
+    /// consigne déjà hors de la fenêtre n'est pas ramenée d'office
+    pub fn position_field(ticks: &mut u16, range: RangeInclusive<u16>, display: AngleDisplay) -> egui::DragValue<'_> {
+        egui::DragValue::new(ticks)
+            .range(range)
+            .clamp_existing_to_range(false)
+            .custom_formatter(move |value, _| display.format(value.round() as u16))
+            .custom_parser(move |text| display.parse(text).map(|value| display.to_ticks(value) as f64))
+    }
+
+    /// Petits boutons de pas (−10, −1, +1, +10…) ; le pas cliqué, s'il y en a un
+    pub fn nudge_buttons(ui: &mut egui::Ui, steps: &[i32]) -> Option<i32> {
+        let mut clicked = None;
+        for &step in steps {
+            let text = if step < 0 { format!("−{}", -step) } else { format!("+{}", step) };
+            if ui.small_button(text).clicked() {
+                clicked = Some(step);
+            }
+        }
+        clicked
+    }
+
+    /// Choix de l'unité (et de l'origine pour les angles) ; vrai si le réglage a changé
+    pub fn unit_picker(ui: &mut egui::Ui, display: &mut AngleDisplay) -> bool {
+        let previous = *display;
+        ui.label("Units:");
+        egui::ComboBox::from_id_salt("angle_unit")
+            .selected_text(display.unit.label())
+            .show_ui(ui, |ui| {
+                for unit in AngleUnit::ALL {
+                    ui.selectable_value(&mut display.unit, unit, unit.label());
+                }
+            });
+        if display.unit != AngleUnit::Ticks {
+            egui::ComboBox::from_id_salt("angle_range")
+                .selected_text(display.range.label())
+                .show_ui(ui, |ui| {
+                    for range in AngleRange::ALL {
+                        ui.selectable_value(&mut display.range, range, range.label());
+                    }
+                });
+        }
+        *display != previous
+    }
+}
+
+#[cfg(feature = "gui")]
+pub use gui::{nudge_buttons, position_field, position_slider, unit_picker};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_positions_round_trip_in_each_unit() {
+        let degrees = AngleDisplay { unit: AngleUnit::Degrees, range: AngleRange::Centered };
+        assert_eq!(degrees.format(3072), "90.0°");
+        assert_eq!(degrees.parse(" -90° ").map(|value| degrees.to_ticks(value)), Some(1024));
+        let full = AngleDisplay { unit: AngleUnit::Degrees, range: AngleRange::Full };
+        assert_eq!(full.parse("90").map(|value| full.to_ticks(value)), Some(1024));
+        let radians = AngleDisplay { unit: AngleUnit::Radians, range: AngleRange::Centered };
+        assert_eq!(radians.to_ticks(radians.parse("3.1416 rad").unwrap()), MAX_TICKS);
+        assert_eq!(AngleDisplay::default().format(2048), "2048");
+        assert_eq!(AngleDisplay::default().parse("abc"), None);
+        // Saisie hors plage : bornée
+        assert_eq!(degrees.to_ticks(400.0), MAX_TICKS);
+        assert_eq!(degrees.format_delta(-512), "-45.0°");
+    }
+}
@@ -0,0 +1,390 @@
+//! Validation commune des consignes de mouvement : chaque source (cartes, copie, mode coordonné,
+//! chorégraphie, CLI...) passe par `validate_move` avant d'écrire sur le bus.
+//!
+//! Ordre d'application : bornes des registres, coupure thermique, arrêt d'urgence, blocage, doublon d'ID,
+//! butées logicielles, garde du premier mouvement, plafond de vitesse de la source, puis
+//! déclassement thermique.
+//!
+//! La garde du premier mouvement (`FirstMoveGuard`) refuse, après une sélection ou une
+//! (re)connexion, une consigne trop loin de la position lue ; la source qui a obtenu une
+//! confirmation (bouton, `--large`) ne la renseigne pas dans les contraintes.
+//!
+//! Les vitesses de roue (mode rotation continue) passent par `validate_wheel_speed`.
+
+use crate::derating::Derating;
+use crate::limits::SoftLimits;
+use crate::mode::MAX_WHEEL_SPEED;
+use crate::motion::MAX_SPEED;
+use crate::units::MAX_TICKS;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt;
+
+/// Valeur maximale du registre d'accélération
+pub const MAX_ACCELERATION: u8 = 254;
+
+/// Contraintes d'un servo au moment de la commande
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MoveConstraints {
+    pub limits: SoftLimits,
+    /// Plafond de vitesse propre à la source (ex. mode coordonné)
+    pub speed_cap: Option<u16>,
+    pub derating: Derating,
+    /// Verrou thermique (couple coupé pour surchauffe) : aucun mouvement accepté
+    pub cut_off: bool,
+    /// Arrêt d'urgence non levé : aucun mouvement avant la réactivation du couple
+    pub emergency_stop: bool,
+    /// Blocage mécanique détecté et pas encore acquitté
+    pub stalled: bool,
+    /// Plusieurs servos semblent répondre sous cet ID : la consigne les ferait bouger ensemble
+    pub duplicate_id: bool,
+    /// Servo en mode roue : les consignes de position sont sans effet
+    pub wheel_mode: bool,
+    /// Premier mouvement non confirmé (voir `FirstMoveGuard::check`)
+    pub first_move: Option<FirstMoveCheck>,
+}
+
+/// Écart maximal accepté sans confirmation (`[first_move] max_delta = 500`, 0 = garde désactivée)
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FirstMoveSettings {
+    pub max_delta: u16,
+}
+
+impl Default for FirstMoveSettings {
+    fn default() -> Self {
+        Self { max_delta: 500 }
+    }
+}
+
+/// Premier mouvement d'un servo : position lue (absente si le servo n'a pas répondu) et écart
+/// maximal accepté
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FirstMoveCheck {
+    pub current: Option<u16>,
+    pub max_delta: u16,
+}
+
+/// Servos dont le premier mouvement est passé ; tous les autres sont gardés. Réarmée à la
+/// sélection d'un autre servo et à chaque (re)connexion.
+#[derive(Clone, Debug, Default)]
+pub struct FirstMoveGuard {
+    passed: BTreeSet<u8>,
+}
+
+impl FirstMoveGuard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn arm_all(&mut self) {
+        self.passed.clear();
+    }
+
+    /// Contrôle à joindre aux contraintes ; `current` n'est lu que si le servo est gardé
+    pub fn check(&self, id: u8, max_delta: u16, current: impl FnOnce() -> Option<u16>) -> Option<FirstMoveCheck> {
+        (max_delta > 0 && !self.passed.contains(&id)).then(|| FirstMoveCheck { current: current(), max_delta })
+    }
+
+    /// Consigne acceptée : les suivantes ne sont plus gardées
+    pub fn passed(&mut self, id: u8) {
+        self.passed.insert(id);
+    }
+}
+
+/// Consigne normalisée, prête à envoyer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ValidatedMove {
+    pub position: u16,
+    /// 0 reste « vitesse max » seulement si aucun plafond ne s'applique
+    pub speed: u16,
+    pub acceleration: u8,
+    /// Consigne ramenée dans les butées logicielles
+    pub clamped: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    PositionOutOfRange(i64),
+    SpeedOutOfRange(i64),
+    WheelSpeedOutOfRange(i64),
+    AccelerationOutOfRange(i64),
+    OverheatCutOff,
+    EmergencyStop,
+    Stalled,
+    DuplicateId,
+    WheelMode,
+    /// Premier mouvement trop grand (ou sans position lue) : à confirmer
+    LargeFirstMove { current: Option<u16>, target: u16 },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::PositionOutOfRange(p) => write!(f, "position {} outside 0-{}", p, MAX_TICKS),
+            ValidationError::SpeedOutOfRange(s) => write!(f, "speed {} outside 0-{}", s, MAX_SPEED),
+            ValidationError::WheelSpeedOutOfRange(s) => write!(f, "wheel speed {} outside ±{}", s, MAX_WHEEL_SPEED),
+            ValidationError::AccelerationOutOfRange(a) => write!(f, "acceleration {} outside 0-{}", a, MAX_ACCELERATION),
+            ValidationError::OverheatCutOff => write!(f, "thermal lockout: torque cut for overheating"),
+            ValidationError::EmergencyStop => write!(f, "emergency stop: re-enable torque first"),
+            ValidationError::Stalled => write!(f, "stalled: clear the stall once the mechanism is free"),
+            ValidationError::DuplicateId => write!(f, "possible duplicate ID: unplug all but one servo and rescan"),
+            ValidationError::WheelMode => write!(f, "wheel mode: switch back to position mode first"),
+            ValidationError::LargeFirstMove { current: Some(current), target } => {
+                write!(f, "large first move {} → {} ({} ticks): confirm it first", current, target, current.abs_diff(*target))
+            }
+            ValidationError::LargeFirstMove { current: None, target } => {
+                write!(f, "first move to {} without a position reading: confirm it first", target)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+pub fn validate_move(
+    constraints: &MoveConstraints,
+    target: i64,
+    speed: i64,
+    acceleration: i64,
+) -> Result<ValidatedMove, ValidationError> {
+    let target = u16::try_from(target)
+        .ok()
+        .filter(|&t| t <= MAX_TICKS)
+        .ok_or(ValidationError::PositionOutOfRange(target))?;
+    let speed = u16::try_from(speed)
+        .ok()
+        .filter(|&s| s <= MAX_SPEED)
+        .ok_or(ValidationError::SpeedOutOfRange(speed))?;
+    let acceleration = u8::try_from(acceleration)
+        .ok()
+        .filter(|&a| a <= MAX_ACCELERATION)
+        .ok_or(ValidationError::AccelerationOutOfRange(acceleration))?;
+    if constraints.cut_off {
+        return Err(ValidationError::OverheatCutOff);
+    }
+    if constraints.emergency_stop {
+        return Err(ValidationError::EmergencyStop);
+    }
+    if constraints.stalled {
+        return Err(ValidationError::Stalled);
+    }
+    if constraints.duplicate_id {
+        return Err(ValidationError::DuplicateId);
+    }
+    if constraints.wheel_mode {
+        return Err(ValidationError::WheelMode);
+    }
+
+    let position = constraints.limits.clamp(target);
+    if let Some(check) = constraints.first_move {
+        if check.current.is_none_or(|current| current.abs_diff(position) > check.max_delta) {
+            return Err(ValidationError::LargeFirstMove { current: check.current, target: position });
+        }
+    }
+    let speed = match constraints.speed_cap {
+        // Vitesse 0 = vitesse max pour le servo : on la plafonne aussi
+        Some(cap) if speed == 0 => cap,
+        Some(cap) => speed.min(cap),
+        None => speed,
+    };
+    Ok(ValidatedMove {
+        position,
+        speed: constraints.derating.cap(speed),
+        acceleration,
+        clamped: position != target,
+    })
+}
+
+/// Vitesse de roue signée, plafonnée comme une vitesse de mouvement. L'arrêt (0) est toujours accepté.
+pub fn validate_wheel_speed(constraints: &MoveConstraints, speed: i64) -> Result<i16, ValidationError> {
+    let speed = i16::try_from(speed)
+        .ok()
+        .filter(|s| s.abs() <= MAX_WHEEL_SPEED)
+        .ok_or(ValidationError::WheelSpeedOutOfRange(speed))?;
+    if speed == 0 {
+        return Ok(0);
+    }
+    if constraints.cut_off {
+        return Err(ValidationError::OverheatCutOff);
+    }
+    if constraints.emergency_stop {
+        return Err(ValidationError::EmergencyStop);
+    }
+    if constraints.stalled {
+        return Err(ValidationError::Stalled);
+    }
+    if constraints.duplicate_id {
+        return Err(ValidationError::DuplicateId);
+    }
+    let magnitude = speed.unsigned_abs();
+    let magnitude = constraints.speed_cap.map_or(magnitude, |cap| magnitude.min(cap));
+    let magnitude = constraints.derating.cap(magnitude) as i16;
+    Ok(if speed < 0 { -magnitude } else { magnitude })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locks_are_checked_before_limits() {
+        let constraints = MoveConstraints { emergency_stop: true, cut_off: true, ..Default::default() };
+        assert_eq!(validate_move(&constraints, 2048, 100, 0), Err(ValidationError::OverheatCutOff));
+        assert_eq!(validate_move(&MoveConstraints::default(), 5000, 100, 0), Err(ValidationError::PositionOutOfRange(5000)));
+    }
+
+    #[test]
+    fn targets_are_clamped_and_speed_capped() {
+        let limits = SoftLimits { min: 1000, max: 3000, ..Default::default() };
+        let constraints = MoveConstraints { limits, speed_cap: Some(400), ..Default::default() };
+        let m = validate_move(&constraints, 3500, 0, 10).unwrap();
+        assert_eq!((m.position, m.speed, m.clamped), (3000, 400, true));
+    }
+
+    #[test]
+    fn first_move_guard_until_a_move_passes() {
+        let mut guard = FirstMoveGuard::new();
+        let check = guard.check(3, 500, || Some(1000));
+        let constraints = MoveConstraints { first_move: check, ..Default::default() };
+        assert_eq!(
+            validate_move(&constraints, 2000, 100, 0),
+            Err(ValidationError::LargeFirstMove { current: Some(1000), target: 2000 })
+        );
+        assert!(validate_move(&constraints, 1400, 100, 0).is_ok());
+
+        guard.passed(3);
+        assert_eq!(guard.check(3, 500, || panic!("not read once passed")), None);
+        guard.arm_all();
+        assert!(guard.check(3, 500, || None).is_some());
+        assert_eq!(guard.check(4, 0, || None), None);
+    }
+
+    #[test]
+    fn first_move_without_reading_needs_confirmation() {
+        let check = FirstMoveCheck { current: None, max_delta: 500 };
+        let constraints = MoveConstraints { first_move: Some(check), ..Default::default() };
+        assert!(matches!(validate_move(&constraints, 2048, 0, 0), Err(ValidationError::LargeFirstMove { current: None, .. })));
+    }
+
+    fn derated(temperature: u8) -> Derating {
+        let mut derating = Derating::default();
+        derating.update(&crate::derating::DeratingCurve::default(), temperature);
+        derating
+    }
+
+    #[test]
+    fn each_lock_refuses_moves_and_wheel_speeds() {
+        let base = MoveConstraints::default();
+        let matrix = [
+            (MoveConstraints { cut_off: true, ..base }, ValidationError::OverheatCutOff),
+            (MoveConstraints { emergency_stop: true, ..base }, ValidationError::EmergencyStop),
+            (MoveConstraints { stalled: true, ..base }, ValidationError::Stalled),
+            (MoveConstraints { duplicate_id: true, ..base }, ValidationError::DuplicateId),
+        ];
+        for (constraints, error) in matrix {
+            assert_eq!(validate_move(&constraints, 2048, 100, 0), Err(error.clone()), "{:?}", constraints);
+            assert_eq!(validate_wheel_speed(&constraints, 500), Err(error), "{:?}", constraints);
+            // L'arrêt d'une roue passe toujours
+            assert_eq!(validate_wheel_speed(&constraints, 0), Ok(0));
+        }
+        let wheel = MoveConstraints { wheel_mode: true, ..base };
+        assert_eq!(validate_move(&wheel, 2048, 100, 0), Err(ValidationError::WheelMode));
+        assert_eq!(validate_wheel_speed(&wheel, 500), Ok(500));
+    }
+
+    #[test]
+    fn locks_are_checked_in_order() {
+        let all = MoveConstraints { cut_off: true, emergency_stop: true, stalled: true, duplicate_id: true, wheel_mode: true, ..Default::default() };
+        let order = [
+            (MoveConstraints { ..all }, ValidationError::OverheatCutOff),
+            (MoveConstraints { cut_off: false, ..all }, ValidationError::EmergencyStop),
+            (MoveConstraints { cut_off: false, emergency_stop: false, ..all }, ValidationError::Stalled),
+            (MoveConstraints { cut_off: false, emergency_stop: false, stalled: false, ..all }, ValidationError::DuplicateId),
+            (MoveConstraints { cut_off: false, emergency_stop: false, stalled: false, duplicate_id: false, ..all }, ValidationError::WheelMode),
+        ];
+        for (constraints, error) in order {
+            assert_eq!(validate_move(&constraints, 2048, 100, 0), Err(error));
+        }
+        // Les bornes des registres passent avant les verrous
+        assert_eq!(validate_move(&all, -1, 100, 0), Err(ValidationError::PositionOutOfRange(-1)));
+    }
+
+    #[test]
+    fn register_range_edges() {
+        let free = MoveConstraints::default();
+        let max_speed = i64::from(MAX_SPEED);
+        let max_acceleration = i64::from(MAX_ACCELERATION);
+        let max_ticks = i64::from(MAX_TICKS);
+        let cases = [
+            ((0, 0, 0), Ok(())),
+            ((max_ticks, max_speed, max_acceleration), Ok(())),
+            ((-1, 0, 0), Err(ValidationError::PositionOutOfRange(-1))),
+            ((max_ticks + 1, 0, 0), Err(ValidationError::PositionOutOfRange(max_ticks + 1))),
+            ((2048, -1, 0), Err(ValidationError::SpeedOutOfRange(-1))),
+            ((2048, max_speed + 1, 0), Err(ValidationError::SpeedOutOfRange(max_speed + 1))),
+            ((2048, 0, -1), Err(ValidationError::AccelerationOutOfRange(-1))),
+            ((2048, 0, max_acceleration + 1), Err(ValidationError::AccelerationOutOfRange(max_acceleration + 1))),
+        ];
+        for ((target, speed, acceleration), expected) in cases {
+            assert_eq!(validate_move(&free, target, speed, acceleration).map(|_| ()), expected, "{} {} {}", target, speed, acceleration);
+        }
+        let max_wheel = i64::from(MAX_WHEEL_SPEED);
+        assert_eq!(validate_wheel_speed(&free, max_wheel), Ok(MAX_WHEEL_SPEED));
+        assert_eq!(validate_wheel_speed(&free, -max_wheel), Ok(-MAX_WHEEL_SPEED));
+        assert_eq!(validate_wheel_speed(&free, max_wheel + 1), Err(ValidationError::WheelSpeedOutOfRange(max_wheel + 1)));
+        assert_eq!(validate_wheel_speed(&free, -max_wheel - 1), Err(ValidationError::WheelSpeedOutOfRange(-max_wheel - 1)));
+    }
+
+    #[test]
+    fn soft_limit_edges() {
+        let limits = SoftLimits { min: 1000, max: 3000, ..Default::default() };
+        let constraints = MoveConstraints { limits, ..Default::default() };
+        for (target, position, clamped) in [(999, 1000, true), (1000, 1000, false), (3000, 3000, false), (3001, 3000, true)] {
+            let m = validate_move(&constraints, target, 100, 0).unwrap();
+            assert_eq!((m.position, m.clamped), (position, clamped), "{}", target);
+        }
+    }
+
+    #[test]
+    fn first_move_is_checked_on_the_clamped_target() {
+        let limits = SoftLimits { min: 1000, max: 1500, ..Default::default() };
+        let check = FirstMoveCheck { current: Some(1200), max_delta: 300 };
+        let constraints = MoveConstraints { limits, first_move: Some(check), ..Default::default() };
+        // 4000 est ramené à 1500, à 300 ticks de la position lue : accepté
+        assert_eq!(validate_move(&constraints, 4000, 100, 0).unwrap().position, 1500);
+        let tight = MoveConstraints { first_move: Some(FirstMoveCheck { max_delta: 299, ..check }), ..constraints };
+        assert_eq!(validate_move(&tight, 4000, 100, 0), Err(ValidationError::LargeFirstMove { current: Some(1200), target: 1500 }));
+    }
+
+    #[test]
+    fn derating_applies_after_the_speed_cap() {
+        // 60 °C sur la courbe par défaut : 40 % de 3400 = 1360 pas/s
+        let derating = derated(60);
+        assert_eq!(derating.percent(), 40);
+        let cases = [
+            // (vitesse demandée, plafond de la source, vitesse envoyée)
+            (0, None, 1360),
+            (3000, None, 1360),
+            (800, None, 800),
+            (0, Some(2000), 1360),
+            (3000, Some(400), 400),
+            (0, Some(400), 400),
+        ];
+        for (speed, speed_cap, expected) in cases {
+            let constraints = MoveConstraints { speed_cap, derating, ..Default::default() };
+            assert_eq!(validate_move(&constraints, 2048, speed, 0).unwrap().speed, expected, "{} {:?}", speed, speed_cap);
+        }
+        // Sans plafond ni déclassement, 0 reste « vitesse max »
+        assert_eq!(validate_move(&MoveConstraints::default(), 2048, 0, 0).unwrap().speed, 0);
+    }
+
+    #[test]
+    fn wheel_speed_keeps_its_sign_under_caps() {
+        let constraints = MoveConstraints { speed_cap: Some(1000), derating: derated(60), ..Default::default() };
+        assert_eq!(validate_wheel_speed(&constraints, -3000), Ok(-1000));
+        assert_eq!(validate_wheel_speed(&constraints, 900), Ok(900));
+        let hot = MoveConstraints { derating: derated(63), ..Default::default() };
+        assert_eq!(validate_wheel_speed(&hot, -3400), Ok(-850));
+    }
+}
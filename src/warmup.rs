@@ -0,0 +1,135 @@
+//! Échauffement d'un servo froid : petite oscillation sinusoïdale à vitesse modérée, jusqu'à une
+//! température cible ou une durée maximale, avec suivi de la vitesse de montée en température.
+
+use crate::choreography::{PhaseClock, Waveform};
+use crate::units::MAX_TICKS;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Fenêtre glissante du calcul de montée en température
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+/// Écart minimal entre deux relevés pour estimer une pente
+const RATE_MIN_SPAN: Duration = Duration::from_secs(10);
+/// Consignes sans réponse consécutives avant d'abandonner
+pub const MAX_MISSED_REPLIES: u32 = 5;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WarmupSettings {
+    /// Amplitude crête en ticks autour de la position de départ
+    pub amplitude: u16,
+    pub period_s: f64,
+    pub speed: u16,
+    /// Arrêt dès que cette température (°C) est atteinte
+    pub target_temperature: u8,
+    pub max_duration_s: f64,
+}
+
+impl Default for WarmupSettings {
+    fn default() -> Self {
+        Self { amplitude: 150, period_s: 2.0, speed: 600, target_temperature: 25, max_duration_s: 600.0 }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WarmupEnd {
+    TargetReached,
+    DurationElapsed,
+    Fault(String),
+    Stopped,
+}
+
+impl WarmupEnd {
+    pub fn label(&self) -> String {
+        match self {
+            WarmupEnd::TargetReached => "target temperature reached".to_string(),
+            WarmupEnd::DurationElapsed => "maximum duration elapsed".to_string(),
+            WarmupEnd::Fault(reason) => format!("stopped on fault: {}", reason),
+            WarmupEnd::Stopped => "stopped by user".to_string(),
+        }
+    }
+}
+
+/// Échauffement en cours d'un servo
+#[derive(Clone, Debug)]
+pub struct Warmup {
+    pub settings: WarmupSettings,
+    center: u16,
+    started: Instant,
+    clock: PhaseClock,
+    last_tick: Instant,
+    samples: VecDeque<(Instant, u8)>,
+    start_temperature: Option<u8>,
+    missed: u32,
+}
+
+impl Warmup {
+    pub fn new(settings: WarmupSettings, center: u16, now: Instant) -> Self {
+        Self {
+            settings,
+            center,
+            started: now,
+            clock: PhaseClock::default(),
+            last_tick: now,
+            samples: VecDeque::new(),
+            start_temperature: None,
+            missed: 0,
+        }
+    }
+
+    pub fn center(&self) -> u16 {
+        self.center
+    }
+
+    pub fn elapsed(&self, now: Instant) -> Duration {
+        now - self.started
+    }
+
+    pub fn start_temperature(&self) -> Option<u8> {
+        self.start_temperature
+    }
+
+    /// Consigne suivante : sinus autour de la position de départ
+    pub fn target(&mut self, now: Instant) -> u16 {
+        let phase = self.clock.advance(now - self.last_tick, self.settings.period_s);
+        self.last_tick = now;
+        let offset = Waveform::Sine.sample(phase) * self.settings.amplitude as f64;
+        (self.center as f64 + offset).round().clamp(0.0, MAX_TICKS as f64) as u16
+    }
+
+    /// Résultat de l'envoi d'une consigne ; `false` si le servo ne répond plus
+    pub fn reply(&mut self, answered: bool) -> bool {
+        self.missed = if answered { 0 } else { self.missed + 1 };
+        self.missed < MAX_MISSED_REPLIES
+    }
+
+    pub fn record_temperature(&mut self, now: Instant, temperature: u8) {
+        self.start_temperature.get_or_insert(temperature);
+        self.samples.push_back((now, temperature));
+        while self.samples.front().is_some_and(|(t, _)| now - *t > RATE_WINDOW) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Montée en température (°C/min) sur la dernière minute
+    pub fn rise_rate(&self) -> Option<f64> {
+        let (&(t0, first), &(t1, last)) = (self.samples.front()?, self.samples.back()?);
+        let span = t1 - t0;
+        if span < RATE_MIN_SPAN {
+            return None;
+        }
+        Some((last as f64 - first as f64) / span.as_secs_f64() * 60.0)
+    }
+
+    /// Fin normale : température cible atteinte ou durée maximale écoulée
+    pub fn finished(&self, now: Instant) -> Option<WarmupEnd> {
+        if self.samples.back().is_some_and(|&(_, t)| t >= self.settings.target_temperature) {
+            return Some(WarmupEnd::TargetReached);
+        }
+        if self.elapsed(now).as_secs_f64() >= self.settings.max_duration_s {
+            return Some(WarmupEnd::DurationElapsed);
+        }
+        None
+    }
+}
@@ -0,0 +1,933 @@
+//! Socle commun des threads de communication des deux interfaces : la connexion au bus, le
+//! scan, la scrutation de télémétrie et les protections qui s'appliquent sans l'interface.
+//!
+//! À chaque cycle, l'interface appelle `maintain` (verrou du port, ouverture et reconnexion,
+//! adaptateur retrouvé sous un autre chemin), `scan_step` (scan de connexion étalé, puis rescan
+//! périodique des IDs inconnus) et `poll` (relevés, liaison perdue, coupure thermique et
+//! détection de blocage). Le worker agit lui-même sur le bus quand la sécurité l'exige (couple
+//! coupé en surchauffe) et rend des `WorkerEvent` que chaque interface reporte dans son état.
+//! Les délais (reconnexion, rescan) sont mesurés sur une `Clock`, remplaçable par une horloge
+//! simulée.
+//!
+//! Les lectures de registre hors du pilote, trames brutes et pings en diffusion passent par
+//! `with_backend` / `with_bus`, sur le même bus que les commandes (`backend::SerialBackend` libère
+//! lui-même le port du pilote le temps d'un accès direct).
+//!
+//! L'ouverture du bus passe par un `Connector`, remplaçable par un bus scripté
+//! (`backend::MockBackend`) qui sert aussi les accès directs. Seul un port série réel (`new`) est
+//! verrouillé contre les autres instances et surveillé par la liste des ports.
+//!
+//! Les commandes communes aux deux interfaces (`Command`) sont exécutées par un `Dispatcher`,
+//! qui tient la garde du premier mouvement et les approches lentes près des butées ; chaque
+//! interface enveloppe `Command` dans son propre enum, avec ses commandes à elle.
+//!
+//! Le pilote ouvert est enveloppé dans `retry::RetryBackend` : les transactions en échec sont
+//! relancées, et les échecs comptés par servo dans `comm_errors()`. Chaque tentative est
+//! journalisée avec son temps aller-retour (`logging::LoggedBackend`). Par-dessus, `recorder()`
+//! enregistre la session à la demande (`recording::RecordingBackend`), en positions brutes ; tout
+//! en haut, les servos de `inversions()` sont lus et commandés en positions logiques
+//! (`inversion::InvertedBackend`).
+
+use crate::backend::{SerialBackend, ServoBackend};
+use crate::validation::{validate_move, FirstMoveCheck, FirstMoveGuard, MoveConstraints, ValidatedMove, ValidationError};
+use crate::derating::{DeratingCurve, ThermalLockout};
+use crate::dryrun::Driver;
+use crate::hotplug::{self, BackgroundScan, FastScan, IncrementalScan, RescanSettings};
+use crate::ids;
+use crate::inversion::{InvertedBackend, Inversions};
+use crate::limits::SoftLimits;
+use crate::logging::{self, LoggedBackend};
+use crate::portlock::{self, LockOwner, PortLock};
+use crate::ports::{self, PortTracker};
+use crate::recording::{Recorder, RecordingBackend};
+use crate::registers::RegisterPort;
+use crate::retry::{CommErrors, RetryBackend, RetrySettings};
+use crate::plugins::TelemetryFrame;
+use crate::stall::{Stall, StallDetector, StallSettings};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ops::RangeInclusive;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Distance (ticks) au bord de la zone d'approche à laquelle on passe en vitesse lente
+const APPROACH_HANDOVER: u16 = 30;
+// Pause entre l'activation du couple et la consigne, avec `set_torque_on_move`
+const TORQUE_SETTLE: Duration = Duration::from_millis(10);
+/// Délai entre deux tentatives d'ouverture du port
+pub const RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+/// Cycles de scrutation de suite sans aucune réponse avant de conclure à une liaison perdue
+pub const DISCONNECT_FAILURES: u32 = 10;
+
+/// Ouverture du pilote sur un port
+pub type Connector = Box<dyn FnMut(&str) -> Result<Box<dyn ServoBackend>, String> + Send>;
+
+/// Horloge des délais du worker
+pub trait Clock: Send {
+    fn now(&self) -> Instant;
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Ce qui s'est passé sur le bus pendant le cycle, à reporter dans l'état de l'interface
+#[derive(Clone, Debug, PartialEq)]
+pub enum WorkerEvent {
+    /// Port ouvert ; le scan de connexion est lancé
+    Connected { port: String },
+    /// Port tenu par une autre instance : aucune ouverture tentée
+    PortConflict(LockOwner),
+    /// Adaptateur retrouvé sous un autre chemin, essayé dès le cycle suivant
+    PortMoved { from: String, to: String },
+    /// Bus muet ou adaptateur retiré : port fermé, scan abandonné, reconnexion aux cycles suivants
+    LinkLost { port: String },
+    /// Servos qui ont répondu au lot de scan du cycle
+    ScanHits(Vec<u8>),
+    /// Scan terminé ; `duplicates` : IDs vus en double au ping en diffusion qui l'a précédé
+    ScanDone { found: Vec<u8>, duplicates: Vec<u8> },
+    /// Servo branché en cours de session, trouvé par le rescan périodique
+    ServoFound(u8),
+    /// Servo verrouillé pour surchauffe ; le couple a déjà été coupé, `outcome` en rend compte
+    ThermalCutOff { id: u8, temperature: u8, outcome: Result<(), String> },
+    /// Blocage confirmé ; l'arrêt (position tenue ou couple coupé) revient à l'interface
+    Stalled { id: u8, stall: Stall },
+}
+
+/// Protections d'un servo pour le relevé en cours
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Protection {
+    /// Coupure thermique ; levée (dérogation), le verrou du servo est effacé
+    pub thermal: bool,
+    /// Détection de blocage ; levée pour un servo déjà bloqué, ou qui serre volontairement
+    pub stall: bool,
+}
+
+impl Default for Protection {
+    fn default() -> Self {
+        Self { thermal: true, stall: true }
+    }
+}
+
+pub struct ServoWorker {
+    port: String,
+    dry_run: Arc<AtomicBool>,
+    connector: Connector,
+    // Port série réel : verrou d'instance, suivi de l'adaptateur et liste des ports
+    serial: bool,
+    port_lock: Option<PortLock>,
+    tracker: PortTracker,
+    clock: Box<dyn Clock>,
+    next_attempt: Option<Instant>,
+    link_failures: u32,
+    retry: RetrySettings,
+    errors: CommErrors,
+    recorder: Recorder,
+    inversions: Inversions,
+    scan_ids: RangeInclusive<u8>,
+    scan: Option<IncrementalScan>,
+    broadcast_duplicates: Vec<u8>,
+    rescan: RescanSettings,
+    background: BackgroundScan,
+    derating: DeratingCurve,
+    thermal: ThermalLockout,
+    stall: StallSettings,
+    stall_detectors: HashMap<u8, StallDetector>,
+    // Dernier drapeau de mouvement lu, par servo (il n'est pas relu à chaque cycle)
+    moving: HashMap<u8, bool>,
+    driver: Option<Driver>,
+}
+
+impl ServoWorker {
+    /// Bus série réel ; aucune connexion n'est ouverte avant le premier `connect`
+    pub fn new(port: impl Into<String>, dry_run: Arc<AtomicBool>) -> Self {
+        let connector: Connector = Box::new(|port| SerialBackend::open(port).map(|b| Box::new(b) as Box<dyn ServoBackend>));
+        Self { serial: true, ..Self::with_connector(port, dry_run, connector) }
+    }
+
+    pub fn with_connector(port: impl Into<String>, dry_run: Arc<AtomicBool>, connector: Connector) -> Self {
+        let rescan = RescanSettings::default();
+        Self {
+            port: port.into(),
+            dry_run,
+            connector,
+            serial: false,
+            port_lock: None,
+            tracker: PortTracker::default(),
+            background: BackgroundScan::new(&rescan, Instant::now()),
+            clock: Box::new(SystemClock),
+            next_attempt: None,
+            link_failures: 0,
+            retry: RetrySettings::default(),
+            errors: CommErrors::new(),
+            recorder: Recorder::new(),
+            inversions: Inversions::new(),
+            scan_ids: 0..=ids::MAX_SERVO_ID,
+            scan: None,
+            broadcast_duplicates: Vec::new(),
+            rescan,
+            derating: DeratingCurve::default(),
+            thermal: ThermalLockout::default(),
+            stall: StallSettings::default(),
+            stall_detectors: HashMap::new(),
+            moving: HashMap::new(),
+            driver: None,
+        }
+    }
+
+    /// Horloge simulée pour les tests ; le rescan repart de son heure
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.background = BackgroundScan::new(&self.rescan, clock.now());
+        self.clock = Box::new(clock);
+    }
+
+    /// Nouvelles tentatives appliquées à la prochaine ouverture du pilote
+    pub fn set_retry(&mut self, settings: RetrySettings) {
+        self.retry = settings;
+    }
+
+    /// Port épinglé (`[bus] pin_port`) : l'adaptateur n'est pas cherché ailleurs
+    pub fn set_pinned(&mut self, pinned: bool) {
+        self.tracker = PortTracker::new(pinned);
+    }
+
+    /// IDs balayés par les scans, à partir du prochain
+    pub fn set_scan_range(&mut self, ids: RangeInclusive<u8>) {
+        self.scan_ids = ids;
+    }
+
+    /// Réglages du rescan périodique, relus à chaque cycle
+    pub fn set_rescan(&mut self, settings: RescanSettings) {
+        self.rescan = settings;
+    }
+
+    pub fn set_derating(&mut self, curve: DeratingCurve) {
+        self.derating = curve;
+    }
+
+    /// Réglages de la détection de blocage, relus à chaque relevé
+    pub fn set_stall(&mut self, settings: StallSettings) {
+        self.stall = settings;
+    }
+
+    /// Compteurs d'échecs par servo, partagés : le clone suit les échecs à venir
+    pub fn comm_errors(&self) -> CommErrors {
+        self.errors.clone()
+    }
+
+    /// Enregistreur de session, partagé : le clone démarre et arrête l'enregistrement
+    pub fn recorder(&self) -> Recorder {
+        self.recorder.clone()
+    }
+
+    /// Servos inversés, partagés : un changement vaut dès la transaction suivante
+    pub fn inversions(&self) -> Inversions {
+        self.inversions.clone()
+    }
+
+    /// Servos verrouillés pour surchauffe, partagés : l'interface lit et réarme le verrou
+    /// posé par `poll`
+    pub fn thermal(&self) -> ThermalLockout {
+        self.thermal.clone()
+    }
+
+    pub fn port(&self) -> &str {
+        &self.port
+    }
+
+    /// Changement de port : l'ancien est fermé avant que le nouveau ne soit ouvert, et
+    /// l'adaptateur suivi est oublié
+    pub fn set_port(&mut self, port: impl Into<String>) {
+        let port = port.into();
+        if port != self.port {
+            self.disconnect();
+            self.tracker.reset();
+            self.port = port;
+        }
+    }
+
+    pub fn driver(&self) -> Option<&Driver> {
+        self.driver.as_ref()
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.driver.is_some()
+    }
+
+    /// Ouvre le port s'il ne l'est pas déjà ; `true` seulement pour une connexion nouvelle
+    pub fn connect(&mut self) -> bool {
+        if self.driver.is_some() {
+            return false;
+        }
+        self.reopen();
+        self.driver.is_some()
+    }
+
+    /// Port fermé, scan en cours abandonné ; la reconnexion peut être tentée aussitôt
+    pub fn disconnect(&mut self) {
+        drop(self.driver.take());
+        self.scan = None;
+        self.link_failures = 0;
+        self.next_attempt = None;
+    }
+
+    /// Connexion tenue : verrou du port, ouverture (au plus une tentative par
+    /// `RECONNECT_INTERVAL`), puis scan de connexion. `force_lock` reprend le port à une autre
+    /// instance.
+    pub fn maintain(&mut self, force_lock: bool) -> Vec<WorkerEvent> {
+        if self.serial {
+            if let Err(owner) = portlock::hold(&mut self.port_lock, &self.port, force_lock) {
+                return vec![WorkerEvent::PortConflict(owner)];
+            }
+        }
+        let now = self.clock.now();
+        if self.driver.is_some() || self.next_attempt.is_some_and(|next| now < next) {
+            return Vec::new();
+        }
+        if self.connect() {
+            if self.serial {
+                self.tracker.connected(&self.port);
+            }
+            self.next_attempt = None;
+            self.link_failures = 0;
+            self.start_scan();
+            self.background = BackgroundScan::new(&self.rescan, now);
+            return vec![WorkerEvent::Connected { port: self.port.clone() }];
+        }
+        self.next_attempt = Some(now + RECONNECT_INTERVAL);
+        match self.tracker.open_failed(&self.port).filter(|_| self.serial) {
+            Some(to) => {
+                log::warn!(target: logging::WORKER, "serial adapter moved: {} → {}", self.port, to);
+                self.next_attempt = None;
+                let from = std::mem::replace(&mut self.port, to.clone());
+                vec![WorkerEvent::PortMoved { from, to }]
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Scan complet ID par ID, étalé sur les cycles suivants (les servos déjà trouvés restent)
+    pub fn start_scan(&mut self) {
+        self.broadcast_duplicates.clear();
+        self.scan = Some(IncrementalScan::new(self.scan_ids.clone()));
+    }
+
+    /// Ping en diffusion ; des réponses chevauchées, aucune réponse ou un échec lancent le
+    /// balayage ID par ID des IDs qui n'ont pas répondu
+    pub fn fast_scan(&mut self) -> Result<FastScan, String> {
+        let outcome = self.with_backend(hotplug::fast_scan);
+        match &outcome {
+            Ok(result) if !result.needs_sweep() => {}
+            Ok(result) => {
+                self.broadcast_duplicates = result.duplicates.clone();
+                self.scan = Some(IncrementalScan::resuming(self.scan_ids.clone(), result.ids.clone()));
+            }
+            Err(_) if self.driver.is_some() => self.start_scan(),
+            Err(_) => {}
+        }
+        outcome
+    }
+
+    /// Scan en cours abandonné ; rend le point atteint et les servos déjà trouvés
+    pub fn cancel_scan(&mut self) -> Option<IncrementalScan> {
+        self.scan.take()
+    }
+
+    pub fn scan_progress(&self) -> Option<(u8, u8)> {
+        self.scan.as_ref().map(IncrementalScan::progress)
+    }
+
+    /// Lot du scan en cours ; sans scan et hors `paused` (opération destructive en cours),
+    /// quelques pings du rescan périodique vers les IDs que `known` ne connaît pas
+    pub fn scan_step(&mut self, known: impl Fn(u8) -> bool, paused: bool) -> Vec<WorkerEvent> {
+        let Some(driver) = &self.driver else { return Vec::new() };
+        let mut events = Vec::new();
+        if let Some(run) = self.scan.as_mut() {
+            let hits = run.step(|id| driver.ping_servo(id));
+            if !hits.is_empty() {
+                events.push(WorkerEvent::ScanHits(hits));
+            }
+            if run.is_done() {
+                let duplicates = std::mem::take(&mut self.broadcast_duplicates);
+                events.push(WorkerEvent::ScanDone { found: run.found().to_vec(), duplicates });
+                self.scan = None;
+            }
+            return events;
+        }
+        if paused {
+            return events;
+        }
+        let batch = self.background.next_batch(&self.rescan, self.scan_ids.clone(), known, self.clock.now());
+        events.extend(batch.into_iter().filter(|&id| driver.ping_servo(id)).map(WorkerEvent::ServoFound));
+        events
+    }
+
+    /// Relevés des servos `ids` suivant `plan`, soumis aux protections : coupure thermique
+    /// (couple coupé ici même, aussi en répétition), détection de blocage, et liaison perdue
+    /// quand aucun servo ne répond plusieurs cycles de suite ou que l'adaptateur a disparu
+    pub fn poll(&mut self, ids: &[u8], plan: PollPlan, protection: impl Fn(u8) -> Protection) -> (Vec<Telemetry>, Vec<WorkerEvent>) {
+        let Some(driver) = &self.driver else { return (Vec::new(), Vec::new()) };
+        let readings: Vec<Telemetry> = ids.iter().map(|&id| Telemetry::read(driver, id, plan)).collect();
+        let mut events = Vec::new();
+        for reading in &readings {
+            let id = reading.id;
+            let protection = protection(id);
+            if let Some(moving) = reading.is_moving {
+                self.moving.insert(id, moving);
+            }
+            if let Some(temperature) = reading.temperature {
+                if !protection.thermal {
+                    self.thermal.clear(id);
+                } else if self.thermal.observe(&self.derating, id, temperature) {
+                    let outcome = driver.backend().disable_torque(id);
+                    log::warn!(target: logging::WORKER, "ID {}: thermal cut-off at {}°C, torque off", id, temperature);
+                    events.push(WorkerEvent::ThermalCutOff { id, temperature, outcome });
+                }
+            }
+            if protection.stall {
+                let moving = self.moving.get(&id).copied().unwrap_or(false);
+                let detector = self.stall_detectors.entry(id).or_default();
+                if let Some(stall) = detector.observe(&self.stall, moving, None, reading.current) {
+                    events.push(WorkerEvent::Stalled { id, stall });
+                }
+            }
+        }
+        if readings.iter().any(|r| r.position.is_some()) {
+            self.link_failures = 0;
+        } else if !readings.is_empty() {
+            self.count_failure();
+        }
+        if self.link_failures > 0 && (self.link_failures >= DISCONNECT_FAILURES || !self.port_present()) {
+            log::warn!(target: logging::WORKER, "link lost on {}, reconnecting", self.port);
+            self.disconnect();
+            events.push(WorkerEvent::LinkLost { port: self.port.clone() });
+        }
+        (readings, events)
+    }
+
+    /// Charge lue à part (registre complet, signé) : détection de blocage pendant un mouvement
+    pub fn observe_load(&mut self, id: u8, load: f32, protection: Protection) -> Option<WorkerEvent> {
+        if !protection.stall {
+            return None;
+        }
+        let moving = self.moving.get(&id).copied().unwrap_or(false);
+        let stall = self.stall_detectors.entry(id).or_default().observe(&self.stall, moving, Some(load), None)?;
+        Some(WorkerEvent::Stalled { id, stall })
+    }
+
+    /// Envoi resté sans réponse, compté avec les cycles muets de `poll`
+    pub fn count_failure(&mut self) {
+        self.link_failures += 1;
+    }
+
+    /// Accès direct au bus pendant `f` (trame brute, ping en diffusion), en positions brutes et
+    /// hors simulation ; sans connexion, l'erreur est rendue sans appeler `f`
+    pub fn with_backend<T>(&mut self, f: impl FnOnce(&dyn ServoBackend) -> Result<T, String>) -> Result<T, String> {
+        match &self.driver {
+            Some(driver) => f(driver.backend()),
+            None => Err(format!("{}: not connected", self.port)),
+        }
+    }
+
+    /// Accès direct aux registres pendant `f`
+    pub fn with_bus<T>(&mut self, f: impl FnOnce(&mut RegisterPort) -> Result<T, String>) -> Result<T, String> {
+        self.with_backend(|backend| f(&mut RegisterPort::new(backend)))
+    }
+
+    // Un bus scripté ou rejoué n'a pas d'adaptateur à perdre
+    fn port_present(&self) -> bool {
+        !self.serial || ports::list_ports().contains(&self.port)
+    }
+
+    fn reopen(&mut self) {
+        self.driver = (self.connector)(&self.port).ok().map(|backend| {
+            let logged = Box::new(LoggedBackend::new(backend));
+            let retried = Box::new(RetryBackend::new(logged, self.retry.clone(), self.errors.clone()));
+            let recorded = Box::new(RecordingBackend::new(retried, self.recorder.clone()));
+            Driver::new(InvertedBackend::new(recorded, self.inversions.clone()), self.dry_run.clone())
+        });
+    }
+}
+
+// --- COMMANDES ---
+
+/// Commande de bus commune aux deux interfaces
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Command {
+    /// `acknowledge_large` : premier mouvement de grande amplitude confirmé par l'utilisateur
+    Move { id: u8, position: u16, speed: u16, acceleration: u8, acknowledge_large: bool },
+    Torque { id: u8, enable: bool },
+    /// Coupe le couple de tous les servos connus, avant toute consigne en file
+    EmergencyStop,
+}
+
+impl Command {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Command::Move { .. } => "move",
+            Command::Torque { .. } => "torque",
+            Command::EmergencyStop => "emergency stop",
+        }
+    }
+
+    /// Consigne de mouvement, abandonnée par l'arrêt d'urgence
+    pub fn is_motion(&self) -> bool {
+        matches!(self, Command::Move { .. })
+    }
+}
+
+/// Commande exécutée ; les échecs de bus sont rendus avec elle, pour être journalisés
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Executed {
+    /// `start` : position avant l'envoi ; `accepted.clamped` signale une consigne ramenée
+    /// dans les butées logicielles
+    Moved { id: u8, accepted: ValidatedMove, start: Option<u16>, outcome: Result<(), String> },
+    Torque { id: u8, enable: bool, outcome: Result<(), String> },
+    /// Servos dont la coupure a échoué
+    Stopped { failures: Vec<(u8, String)> },
+}
+
+impl Executed {
+    /// Issue sur le bus, pour le journal ; l'arrêt d'urgence liste les servos en échec
+    pub fn outcome(&self) -> Result<(), String> {
+        match self {
+            Executed::Moved { outcome, .. } | Executed::Torque { outcome, .. } => outcome.clone(),
+            Executed::Stopped { failures } if failures.is_empty() => Ok(()),
+            Executed::Stopped { failures } => {
+                let failed: Vec<String> = failures.iter().map(|(id, e)| format!("ID {}: {}", id, e)).collect();
+                Err(failed.join(", "))
+            }
+        }
+    }
+}
+
+// Mouvement découpé par la zone d'approche : segment en cours et segments restants
+#[derive(Debug)]
+struct Approach {
+    goal: u16,
+    next: VecDeque<(u16, u16)>,
+    acceleration: u8,
+}
+
+fn sent(reply: Option<bool>) -> Result<(), String> {
+    reply.map(|_| ()).ok_or_else(|| "no response".to_string())
+}
+
+/// Exécution des `Command` : garde du premier mouvement, et suite des mouvements découpés
+/// par la zone d'approche des butées logicielles (`advance_approaches` à chaque cycle)
+#[derive(Debug, Default)]
+pub struct Dispatcher {
+    first_moves: FirstMoveGuard,
+    first_move_delta: u16,
+    torque_on_move: bool,
+    approaches: BTreeMap<u8, Approach>,
+}
+
+impl Dispatcher {
+    /// `first_move_delta` : écart maximal d'un premier mouvement sans confirmation (0 = garde désactivée)
+    pub fn new(first_move_delta: u16) -> Self {
+        Self { first_move_delta, ..Self::default() }
+    }
+
+    pub fn set_first_move_delta(&mut self, delta: u16) {
+        self.first_move_delta = delta;
+    }
+
+    /// Couple activé avant chaque consigne de mouvement
+    pub fn set_torque_on_move(&mut self, enabled: bool) {
+        self.torque_on_move = enabled;
+    }
+
+    /// Tous les servos redeviennent gardés (reconnexion, changement de sélection)
+    pub fn arm_first_moves(&mut self) {
+        self.first_moves.arm_all();
+    }
+
+    /// Contrôle du premier mouvement pour une consigne validée hors de `execute`
+    pub fn first_move_check(&self, id: u8, current: impl FnOnce() -> Option<u16>) -> Option<FirstMoveCheck> {
+        self.first_moves.check(id, self.first_move_delta, current)
+    }
+
+    /// Le servo part ailleurs (préhension, mode roue…) : la suite de son approche est abandonnée
+    pub fn cancel_approach(&mut self, id: u8) {
+        self.approaches.remove(&id);
+    }
+
+    pub fn clear_approaches(&mut self) {
+        self.approaches.clear();
+    }
+
+    /// `constraints` n'est appelé que pour un mouvement, `servos` ne sert qu'à l'arrêt d'urgence.
+    /// Seul un mouvement refusé par la validation est une erreur.
+    pub fn execute(
+        &mut self,
+        driver: &Driver,
+        command: &Command,
+        constraints: impl FnOnce(u8) -> MoveConstraints,
+        servos: &[u8],
+    ) -> Result<Executed, ValidationError> {
+        match *command {
+            Command::Move { id, position, speed, acceleration, acknowledge_large } => {
+                // Sans position lue, l'écart ne s'évalue pas : la garde demande confirmation
+                let first_move = match acknowledge_large {
+                    true => None,
+                    false => self.first_move_check(id, || driver.position(id)),
+                };
+                let constraints = MoveConstraints { first_move, ..constraints(id) };
+                let accepted = validate_move(&constraints, position.into(), speed.into(), acceleration.into())?;
+                let torque = match self.torque_on_move {
+                    true => {
+                        let outcome = driver.enable_torque(id);
+                        thread::sleep(TORQUE_SETTLE);
+                        outcome
+                    }
+                    false => Ok(()),
+                };
+                let start = driver.position(id);
+                let outcome = torque.and_then(|()| self.send_move(driver, &constraints.limits, id, start, accepted));
+                // Consigne perdue sur le bus : le premier mouvement reste à faire
+                if outcome.is_ok() {
+                    self.first_moves.passed(id);
+                }
+                Ok(Executed::Moved { id, accepted, start, outcome })
+            }
+            Command::Torque { id, enable } => {
+                let outcome = match enable {
+                    true => driver.enable_torque(id),
+                    false => driver.disable_torque(id),
+                };
+                Ok(Executed::Torque { id, enable, outcome })
+            }
+            Command::EmergencyStop => {
+                self.approaches.clear();
+                // Sécurité : la coupure s'applique aussi en répétition
+                let failures = servos
+                    .iter()
+                    .filter_map(|&id| driver.backend().disable_torque(id).err().map(|e| (id, e)))
+                    .collect();
+                Ok(Executed::Stopped { failures })
+            }
+        }
+    }
+
+    /// Consigne déjà validée (ex. ouverture de pince) envoyée avec son approche lente
+    pub fn start_move(&mut self, driver: &Driver, limits: &SoftLimits, id: u8, m: ValidatedMove) -> Result<(), String> {
+        self.send_move(driver, limits, id, driver.position(id), m)
+    }
+
+    // Premier segment envoyé ; les suivants attendent l'entrée dans la zone d'approche
+    fn send_move(&mut self, driver: &Driver, limits: &SoftLimits, id: u8, start: Option<u16>, m: ValidatedMove) -> Result<(), String> {
+        let current = start.unwrap_or(m.position);
+        let mut segments: VecDeque<(u16, u16)> = limits.plan(current, m.position, m.speed).into();
+        self.approaches.remove(&id);
+        let Some((goal, speed)) = segments.pop_front() else { return Ok(()) };
+        if !segments.is_empty() {
+            self.approaches.insert(id, Approach { goal, next: segments, acceleration: m.acceleration });
+        }
+        sent(driver.move_to(id, goal, speed, m.acceleration, false))
+    }
+
+    /// Passage en vitesse lente à l'entrée de la zone d'approche ; rend les segments envoyés
+    pub fn advance_approaches(&mut self, driver: &Driver) -> Vec<(u8, Result<(), String>)> {
+        let mut sent_segments = Vec::new();
+        self.approaches.retain(|&id, approach| {
+            let Some(pos) = driver.position(id) else { return true };
+            if pos.abs_diff(approach.goal) > APPROACH_HANDOVER {
+                return true;
+            }
+            match approach.next.pop_front() {
+                Some((goal, speed)) => {
+                    sent_segments.push((id, sent(driver.move_to(id, goal, speed, approach.acceleration, false))));
+                    approach.goal = goal;
+                    !approach.next.is_empty()
+                }
+                None => false,
+            }
+        });
+        sent_segments
+    }
+}
+
+/// Relevés d'un cycle en plus de la position, pour alterner les registres lents sans
+/// surcharger le bus
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PollPlan {
+    pub temperature: bool,
+    pub voltage: bool,
+    pub current: bool,
+    pub speed: bool,
+    pub moving: bool,
+}
+
+/// Relevés d'un servo pendant un cycle ; `None` pour une lecture en échec ou non prévue
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Telemetry {
+    pub id: u8,
+    pub position: Option<u16>,
+    pub temperature: Option<u8>,
+    pub voltage: Option<f32>,
+    pub current: Option<f32>,
+    pub speed: Option<i16>,
+    pub is_moving: Option<bool>,
+}
+
+impl Telemetry {
+    /// Le drapeau de mouvement suit la simulation en dry-run, comme `Driver::is_moving`
+    pub fn read(driver: &Driver, id: u8, plan: PollPlan) -> Self {
+        Self {
+            id,
+            position: driver.read_position(id),
+            temperature: if plan.temperature { driver.read_temperature(id) } else { None },
+            voltage: if plan.voltage { driver.read_voltage(id) } else { None },
+            current: if plan.current { driver.read_current(id) } else { None },
+            speed: if plan.speed { driver.read_speed(id) } else { None },
+            is_moving: if plan.moving { driver.is_moving(id) } else { None },
+        }
+    }
+
+    /// Au moins une lecture a répondu : le servo est toujours sur le bus
+    pub fn responded(&self) -> bool {
+        self.position.is_some() || self.temperature.is_some() || self.voltage.is_some()
+    }
+
+    /// Ligne du journal continu ; la charge, lue à part, et l'écart à la consigne, tenue par
+    /// l'interface, sont complétés plus tard
+    pub fn frame(&self, time: f64) -> TelemetryFrame {
+        TelemetryFrame {
+            servo: self.id,
+            time,
+            position: self.position,
+            temperature: self.temperature,
+            voltage: self.voltage,
+            current: self.current,
+            load: None,
+            speed: self.speed,
+            delta: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backend::{BackendCall, MockBackend, MockServo};
+    use crate::hotplug;
+    use crate::registers::{PRESENT_LOAD, TORQUE_ENABLE};
+
+    fn mock_worker(mock: &MockBackend) -> ServoWorker {
+        let bus = mock.clone();
+        let connector: Connector = Box::new(move |_| Ok(Box::new(bus.clone()) as Box<dyn ServoBackend>));
+        ServoWorker::with_connector("mock", Arc::new(AtomicBool::new(false)), connector)
+    }
+
+    fn mock_driver(mock: &MockBackend) -> Driver {
+        Driver::new(mock.clone(), Arc::new(AtomicBool::new(false)))
+    }
+
+    fn move_to(id: u8, position: u16, acknowledge_large: bool) -> Command {
+        Command::Move { id, position, speed: 500, acceleration: 20, acknowledge_large }
+    }
+
+    #[test]
+    fn accepted_move_reaches_the_bus() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default());
+        let driver = mock_driver(&mock);
+        let mut dispatcher = Dispatcher::new(500);
+
+        let done = dispatcher.execute(&driver, &move_to(1, 2300, false), |_| MoveConstraints::default(), &[]).unwrap();
+        assert!(matches!(done, Executed::Moved { id: 1, start: Some(2048), outcome: Ok(()), .. }));
+        assert_eq!(mock.calls(), vec![BackendCall::MoveTo { id: 1, position: 2300, speed: 500, acceleration: 20 }]);
+    }
+
+    #[test]
+    fn torque_is_enabled_before_the_move_on_request() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default());
+        let driver = mock_driver(&mock);
+        let mut dispatcher = Dispatcher::new(0);
+        dispatcher.set_torque_on_move(true);
+
+        dispatcher.execute(&driver, &move_to(1, 1000, false), |_| MoveConstraints::default(), &[]).unwrap();
+        let calls = mock.calls();
+        assert_eq!(calls[0], BackendCall::EnableTorque(1));
+        assert!(matches!(calls[1], BackendCall::MoveTo { id: 1, position: 1000, .. }));
+    }
+
+    #[test]
+    fn rejected_move_sends_nothing() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default());
+        let driver = mock_driver(&mock);
+        let mut dispatcher = Dispatcher::new(0);
+
+        let stopped = |_| MoveConstraints { emergency_stop: true, ..Default::default() };
+        assert_eq!(dispatcher.execute(&driver, &move_to(1, 2100, false), stopped, &[]), Err(ValidationError::EmergencyStop));
+        let locked = |_| MoveConstraints { cut_off: true, ..Default::default() };
+        assert_eq!(dispatcher.execute(&driver, &move_to(1, 2100, false), locked, &[]), Err(ValidationError::OverheatCutOff));
+        let out_of_range = Command::Move { id: 1, position: 5000, speed: 0, acceleration: 0, acknowledge_large: false };
+        assert_eq!(
+            dispatcher.execute(&driver, &out_of_range, |_| MoveConstraints::default(), &[]),
+            Err(ValidationError::PositionOutOfRange(5000))
+        );
+        assert!(mock.calls().is_empty());
+    }
+
+    #[test]
+    fn large_first_move_needs_an_acknowledgement() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default());
+        let driver = mock_driver(&mock);
+        let mut dispatcher = Dispatcher::new(500);
+        let free = |_| MoveConstraints::default();
+
+        assert_eq!(
+            dispatcher.execute(&driver, &move_to(1, 100, false), free, &[]),
+            Err(ValidationError::LargeFirstMove { current: Some(2048), target: 100 })
+        );
+        assert!(mock.calls().is_empty());
+        assert!(dispatcher.execute(&driver, &move_to(1, 100, true), free, &[]).is_ok());
+        // Premier mouvement passé : les suivants ne sont plus gardés, jusqu'au réarmement
+        assert!(dispatcher.execute(&driver, &move_to(1, 4000, false), free, &[]).is_ok());
+        dispatcher.arm_first_moves();
+        assert!(dispatcher.execute(&driver, &move_to(1, 100, false), free, &[]).is_err());
+    }
+
+    #[test]
+    fn lost_first_move_keeps_the_guard_armed() {
+        let mut dispatcher = Dispatcher::new(500);
+        let free = |_| MoveConstraints::default();
+        let absent = MockBackend::new();
+        let done = dispatcher.execute(&mock_driver(&absent), &move_to(1, 100, true), free, &[]).unwrap();
+        assert!(matches!(done, Executed::Moved { outcome: Err(_), .. }));
+
+        // Servo rebranché : la consigne n'est jamais arrivée, le grand écart reste gardé
+        let mock = MockBackend::new().with_servo(1, MockServo::default());
+        assert_eq!(
+            dispatcher.execute(&mock_driver(&mock), &move_to(1, 100, false), free, &[]),
+            Err(ValidationError::LargeFirstMove { current: Some(2048), target: 100 })
+        );
+    }
+
+    #[test]
+    fn unreadable_servo_is_guarded() {
+        let mock = MockBackend::new();
+        let driver = mock_driver(&mock);
+        let mut dispatcher = Dispatcher::new(500);
+        assert_eq!(
+            dispatcher.execute(&driver, &move_to(7, 2048, false), |_| MoveConstraints::default(), &[]),
+            Err(ValidationError::LargeFirstMove { current: None, target: 2048 })
+        );
+    }
+
+    #[test]
+    fn torque_commands_report_their_outcome() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default());
+        let driver = mock_driver(&mock);
+        let mut dispatcher = Dispatcher::new(0);
+        let free = |_| MoveConstraints::default();
+
+        let on = dispatcher.execute(&driver, &Command::Torque { id: 1, enable: true }, free, &[]).unwrap();
+        assert_eq!(on.outcome(), Ok(()));
+        assert!(mock.servo(1).unwrap().torque);
+        dispatcher.execute(&driver, &Command::Torque { id: 1, enable: false }, free, &[]).unwrap();
+        assert!(!mock.servo(1).unwrap().torque);
+        let missing = dispatcher.execute(&driver, &Command::Torque { id: 9, enable: true }, free, &[]).unwrap();
+        assert!(missing.outcome().is_err());
+    }
+
+    #[test]
+    fn emergency_stop_cuts_every_servo_even_in_dry_run() {
+        let mock = MockBackend::new()
+            .with_servo(1, MockServo { torque: true, ..Default::default() })
+            .with_servo(2, MockServo { torque: true, ..Default::default() });
+        let driver = Driver::new(mock.clone(), Arc::new(AtomicBool::new(true)));
+        let mut dispatcher = Dispatcher::new(0);
+
+        let done = dispatcher.execute(&driver, &Command::EmergencyStop, |_| MoveConstraints::default(), &[1, 2, 3]).unwrap();
+        assert!(!mock.servo(1).unwrap().torque);
+        assert!(!mock.servo(2).unwrap().torque);
+        assert!(matches!(done, Executed::Stopped { ref failures } if failures.len() == 1 && failures[0].0 == 3));
+        assert!(done.outcome().unwrap_err().starts_with("ID 3"));
+    }
+
+    #[test]
+    fn approach_zone_sends_the_creep_segment_on_arrival() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default());
+        let driver = mock_driver(&mock);
+        let mut dispatcher = Dispatcher::new(0);
+        let limits = SoftLimits { slowdown: true, ..Default::default() };
+        let constraints = |_| MoveConstraints { limits, ..Default::default() };
+
+        dispatcher.execute(&driver, &move_to(1, 4000, false), constraints, &[]).unwrap();
+        assert_eq!(mock.take_calls(), vec![BackendCall::MoveTo { id: 1, position: 3895, speed: 500, acceleration: 20 }]);
+        // Le servo scripté est déjà au bord de la zone : la suite part au passage suivant
+        assert_eq!(dispatcher.advance_approaches(&driver), vec![(1, Ok(()))]);
+        assert_eq!(mock.take_calls(), vec![BackendCall::MoveTo { id: 1, position: 4000, speed: 150, acceleration: 20 }]);
+        assert!(dispatcher.advance_approaches(&driver).is_empty());
+
+        // Un arrêt d'urgence abandonne l'approche en cours
+        dispatcher.execute(&driver, &move_to(1, 100, false), |_| MoveConstraints { limits, ..Default::default() }, &[]).unwrap();
+        dispatcher.execute(&driver, &Command::EmergencyStop, constraints, &[1]).unwrap();
+        mock.take_calls();
+        assert!(dispatcher.advance_approaches(&driver).is_empty());
+        assert!(mock.calls().is_empty());
+    }
+
+    #[test]
+    fn direct_access_needs_a_connection() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default());
+        let mut worker = mock_worker(&mock);
+        assert!(worker.with_backend(hotplug::fast_scan).is_err());
+        assert!(mock.calls().is_empty());
+    }
+
+    #[test]
+    fn registers_go_through_the_open_bus() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default());
+        mock.set_register(1, PRESENT_LOAD.address, 0x2c);
+        mock.set_register(1, PRESENT_LOAD.address + 1, 0x05);
+        let mut worker = mock_worker(&mock);
+        worker.connect();
+
+        assert_eq!(worker.with_bus(|bus| bus.read(1, &PRESENT_LOAD)), Ok(-300));
+        assert_eq!(worker.with_bus(|bus| bus.write(1, &TORQUE_ENABLE, 1)), Ok(1));
+        assert!(mock.servo(1).unwrap().torque);
+        assert!(worker.is_connected());
+    }
+
+    #[test]
+    fn broadcast_and_raw_frames_reach_the_mock() {
+        let mock = MockBackend::new().with_servo(1, MockServo::default()).with_servo(4, MockServo::default());
+        let mut worker = mock_worker(&mock);
+        worker.connect();
+
+        let scan = worker.with_backend(hotplug::fast_scan).unwrap();
+        assert_eq!(scan.ids, vec![1, 4]);
+        let frame = crate::packet::build_frame(4, st3215::INST_PING, &[]).unwrap();
+        assert!(worker.with_backend(|bus| bus.send_raw(&frame)).unwrap().is_some());
+        assert_eq!(mock.calls(), vec![BackendCall::BroadcastPing, BackendCall::RawFrame(frame)]);
+    }
+
+    #[test]
+    fn moving_flag_is_read_on_every_planned_cycle() {
+        let mock = MockBackend::new().with_servo(1, MockServo { moving: true, ..MockServo::default() });
+        let driver = mock_driver(&mock);
+        let plan = PollPlan { moving: true, ..PollPlan::default() };
+
+        assert_eq!(Telemetry::read(&driver, 1, plan).is_moving, Some(true));
+        mock.update(1, |s| s.moving = false);
+        assert_eq!(Telemetry::read(&driver, 1, plan).is_moving, Some(false));
+        assert_eq!(Telemetry::read(&driver, 1, PollPlan::default()).is_moving, None);
+        assert_eq!(Telemetry::read(&driver, 9, plan).is_moving, None);
+    }
+
+    #[test]
+    fn any_answered_read_counts_as_a_response() {
+        assert!(!Telemetry { id: 1, ..Telemetry::default() }.responded());
+        assert!(Telemetry { id: 1, temperature: Some(40), ..Telemetry::default() }.responded());
+        assert!(Telemetry { id: 1, position: Some(2048), ..Telemetry::default() }.responded());
+    }
+}
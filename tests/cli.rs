@@ -0,0 +1,185 @@
+//! Scénarios de la CLI sur le simulateur : `simserial` sert des servos derrière un
+//! pseudo-terminal, `servo-cli scan` et `servo-cli move` s'y connectent comme à une carte.
+//! Chaque scénario a son répertoire : configuration et verrous de port n'y fuient pas.
+#![cfg(unix)]
+
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdout, Command, Output, Stdio};
+use std::thread;
+use std::time::Duration;
+
+struct Simulator {
+    child: Child,
+    // Gardée ouverte : le simulateur écrit encore sur sa sortie après la bannière
+    _stdout: BufReader<ChildStdout>,
+    port: String,
+    dir: PathBuf,
+}
+
+impl Simulator {
+    // Lance le simulateur et attend la ligne qui annonce son port
+    fn start(name: &str, ids: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("init-servo-cli-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut child = Command::new(env!("CARGO_BIN_EXE_simserial"))
+            .args(["--ids", ids])
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("simserial");
+        let mut stdout = BufReader::new(child.stdout.take().unwrap());
+        let mut banner = String::new();
+        stdout.read_line(&mut banner).unwrap();
+        let port = banner.trim().rsplit(" on ").next().unwrap().to_string();
+        assert!(port.starts_with("/dev/"), "unexpected banner: {}", banner);
+        Simulator { child, _stdout: stdout, port, dir }
+    }
+
+    // Configuration lue par la CLI : `init-servo.toml` du répertoire courant
+    fn config(&self, text: &str) {
+        std::fs::write(self.dir.join("init-servo.toml"), text).unwrap();
+    }
+
+    fn command(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_servo-cli"));
+        command
+            .args(args)
+            .args(["--port", &self.port])
+            .current_dir(&self.dir)
+            .env("HOME", &self.dir)
+            .env("XDG_CONFIG_HOME", &self.dir)
+            .env("XDG_RUNTIME_DIR", &self.dir);
+        command
+    }
+
+    fn cli(&self, args: &[&str]) -> Output {
+        self.command(args).output().expect("servo-cli")
+    }
+
+    // Commande interactive : `input` tient lieu de frappes clavier, puis l'entrée est fermée
+    fn cli_with_input(&self, args: &[&str], input: &str) -> Output {
+        let mut child =
+            self.command(args).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped()).spawn().expect("servo-cli");
+        child.stdin.take().unwrap().write_all(input.as_bytes()).unwrap();
+        child.wait_with_output().expect("servo-cli")
+    }
+}
+
+impl Drop for Simulator {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn scan_lists_simulated_servos() {
+    let sim = Simulator::start("scan", "1,3");
+    let output = sim.cli(&["scan"]);
+    let text = stdout(&output);
+    assert!(output.status.success(), "{}{}", text, stderr(&output));
+    assert!(text.contains("Servomoteurs connectés: [1, 3] (Total: 2)"), "{}", text);
+    assert!(text.contains("ID 1 · ST3215 · FW 3.10"), "{}", text);
+    assert!(text.contains("ID 3 · ST3215 · FW 3.10"), "{}", text);
+    assert!(!text.contains("doublon"), "{}", text);
+}
+
+#[test]
+fn move_reaches_target_and_guards_first_move() {
+    let sim = Simulator::start("move", "1");
+
+    // Servo en 2048 : 2600 dépasse la garde du premier mouvement (500 ticks par défaut)
+    let refused = sim.cli(&["move", "--id", "1", "--pos", "2600"]);
+    assert!(!refused.status.success());
+    assert!(stderr(&refused).contains("--large"), "{}", stderr(&refused));
+
+    let moved = sim.cli(&["move", "--id", "1", "--pos", "2300", "--speed", "1000"]);
+    assert!(moved.status.success(), "{}", stderr(&moved));
+    assert!(stdout(&moved).contains("✓ ID 1 envoyé en position 2300"), "{}", stdout(&moved));
+
+    // Le servo simulé a rejoint 2300 : la même consigne de 2600 passe désormais la garde
+    thread::sleep(Duration::from_millis(800));
+    let moved = sim.cli(&["move", "--id", "1", "--pos", "2600"]);
+    assert!(moved.status.success(), "{}", stderr(&moved));
+    assert!(stdout(&moved).contains("✓ ID 1 envoyé en position 2600"), "{}", stdout(&moved));
+}
+
+#[test]
+fn move_is_clamped_to_configured_soft_limits() {
+    let sim = Simulator::start("limits", "1");
+    sim.config("[limits.1]\nmin = 1000\nmax = 2200\n");
+    let output = sim.cli(&["move", "--id", "1", "--pos", "2500"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let text = stdout(&output);
+    assert!(text.contains("Consigne 2500 ramenée à 2200 (butées logicielles de l'ID 1)"), "{}", text);
+    assert!(text.contains("✓ ID 1 envoyé en position 2200"), "{}", text);
+}
+
+#[test]
+fn move_rejects_broadcast_and_absent_servo() {
+    let sim = Simulator::start("reject", "1");
+
+    let broadcast = sim.cli(&["move", "--id", "254", "--pos", "2048"]);
+    assert!(!broadcast.status.success());
+    assert!(stderr(&broadcast).contains("broadcast ID"), "{}", stderr(&broadcast));
+
+    // Position illisible : la garde refuse sans --large, puis le servo absent ne répond pas
+    let absent = sim.cli(&["move", "--id", "7", "--pos", "2048"]);
+    assert!(!absent.status.success());
+    assert!(stderr(&absent).contains("--large"), "{}", stderr(&absent));
+    let absent = sim.cli(&["move", "--id", "7", "--pos", "2048", "--large"]);
+    assert!(!absent.status.success());
+    assert!(stderr(&absent).contains("servo 7"), "{}", stderr(&absent));
+}
+
+#[test]
+fn copy_pos_from_a_pose_entry() {
+    let sim = Simulator::start("copy-pose", "1");
+    std::fs::write(sim.dir.join("poses.json"), r#"{ "poses": [ { "name": "rest", "positions": { "1": 2300 } } ] }"#).unwrap();
+    let output = sim.cli(&["copy-pos", "--pose", "rest", "--to", "1", "--yes"]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    let text = stdout(&output);
+    assert!(text.contains("pose 'rest', ID 1 → ID 1"), "{}", text);
+    assert!(text.contains("✓ ID 1 envoyé en position 2300"), "{}", text);
+
+    let missing = sim.cli(&["copy-pos", "--pose", "rest", "--entry", "4", "--to", "1", "--yes"]);
+    assert!(!missing.status.success());
+    assert!(stderr(&missing).contains("pas d'entrée pour l'ID 4"), "{}", stderr(&missing));
+}
+
+#[test]
+fn copy_pos_refuses_mismatched_mappings_without_confirmation() {
+    let sim = Simulator::start("copy-mapping", "1,2");
+    sim.config("[servos.2]\ninverted = true\n");
+    let refused = sim.cli(&["copy-pos", "--from", "1", "--to", "2", "--yes"]);
+    assert!(!refused.status.success());
+    assert!(stdout(&refused).contains("direction differs: source normal, destination inverted"), "{}", stdout(&refused));
+    assert!(stderr(&refused).contains("--force-mapping"), "{}", stderr(&refused));
+
+    let forced = sim.cli(&["copy-pos", "--from", "1", "--to", "2", "--yes", "--force-mapping"]);
+    assert!(forced.status.success(), "{}", stderr(&forced));
+    assert!(stdout(&forced).contains("✓ ID 2 envoyé en position 2048"), "{}", stdout(&forced));
+}
+
+#[test]
+fn assign_ids_numbers_one_servo_and_refuses_it_twice() {
+    let sim = Simulator::start("assign", "1");
+    // Deuxième Entrée sans changer de servo : celui qui vient d'être numéroté est refusé
+    let output = sim.cli_with_input(&["assign-ids", "--ids", "5,6"], "\n\n");
+    assert!(output.status.success(), "{}", stderr(&output));
+    let text = stdout(&output);
+    assert!(text.contains("✓ ID vérifiée: 5 (l'ID 1 ne répond plus)"), "{}", text);
+    assert!(text.contains("✗ ID 5 vient d'être attribué"), "{}", text);
+    assert!(text.contains("Récapitulatif (1/2 servomoteurs)"), "{}", text);
+    assert!(text.contains("    1 → 5    vérifié"), "{}", text);
+}
@@ -0,0 +1,398 @@
+//! Scénarios de bout en bout du worker sur le bus scripté : connexion et scan, consignes,
+//! coupure et reconnexion, rafales coalescées, coupure thermique, arrêt d'urgence,
+//! écritures destructives concurrentes et changement d'ID. Chaque scénario vérifie les écritures exactes reçues par le bus.
+//!
+//! Les scénarios de boucle enchaînent `maintain`, `scan_step` et `poll` comme le thread de
+//! communication, sur une horloge simulée que le test avance à la main.
+
+use servo_control::backend::{BackendCall, MockBackend, MockServo, ServoBackend};
+use servo_control::coalesce;
+use servo_control::derating::{DeratingCurve, ThermalLockout};
+use servo_control::estop::{self, EmergencyStop};
+use servo_control::hotplug;
+use servo_control::ids;
+use servo_control::limits::TorqueLimit;
+use servo_control::oplock::OperationLock;
+use servo_control::regdiff::{self, RegisterCache};
+use servo_control::registers;
+use servo_control::validation::{MoveConstraints, ValidationError};
+use servo_control::worker::{
+    Clock, Command, Connector, Dispatcher, Executed, PollPlan, Protection, ServoWorker, Telemetry, WorkerEvent,
+    DISCONNECT_FAILURES, RECONNECT_INTERVAL,
+};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Worker branché sur le bus scripté ; `plugged` à faux, l'ouverture du port échoue
+fn worker_on(mock: &MockBackend, plugged: Arc<AtomicBool>) -> ServoWorker {
+    let bus = mock.clone();
+    let connector: Connector = Box::new(move |port| match plugged.load(Ordering::SeqCst) {
+        true => Ok(Box::new(bus.clone()) as Box<dyn ServoBackend>),
+        false => Err(format!("{}: no such device", port)),
+    });
+    ServoWorker::with_connector("/dev/mock", Arc::new(AtomicBool::new(false)), connector)
+}
+
+// Horloge simulée : le temps n'avance que sur `advance`
+#[derive(Clone)]
+struct FakeClock(Arc<Mutex<Instant>>);
+
+impl FakeClock {
+    fn advance(&self, by: Duration) {
+        *self.0.lock().unwrap() += by;
+    }
+}
+
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        *self.0.lock().unwrap()
+    }
+}
+
+// Worker de boucle : horloge simulée, scan réduit aux IDs 1 à 4 (un seul lot)
+fn looping_worker(mock: &MockBackend, plugged: Arc<AtomicBool>) -> (ServoWorker, FakeClock) {
+    let clock = FakeClock(Arc::new(Mutex::new(Instant::now())));
+    let mut worker = worker_on(mock, plugged);
+    worker.set_clock(clock.clone());
+    worker.set_scan_range(1..=4);
+    (worker, clock)
+}
+
+fn connected(port: &str) -> Vec<WorkerEvent> {
+    vec![WorkerEvent::Connected { port: port.into() }]
+}
+
+fn scanned(ids: &[u8]) -> Vec<WorkerEvent> {
+    vec![WorkerEvent::ScanHits(ids.to_vec()), WorkerEvent::ScanDone { found: ids.to_vec(), duplicates: Vec::new() }]
+}
+
+fn bus_with(ids: &[u8]) -> MockBackend {
+    ids.iter().fold(MockBackend::new(), |mock, &id| mock.with_servo(id, MockServo::default()))
+}
+
+fn move_to(id: u8, position: u16) -> Command {
+    Command::Move { id, position, speed: 800, acceleration: 30, acknowledge_large: false }
+}
+
+fn free(_: u8) -> MoveConstraints {
+    MoveConstraints::default()
+}
+
+#[test]
+fn connect_scan_move_and_read_back() {
+    let mock = bus_with(&[1, 3]);
+    let mut worker = worker_on(&mock, Arc::new(AtomicBool::new(true)));
+    let mut dispatcher = Dispatcher::new(500);
+
+    assert!(worker.connect());
+    let scan = worker.with_backend(hotplug::fast_scan).unwrap();
+    assert_eq!(scan.ids, vec![1, 3]);
+
+    let driver = worker.driver().unwrap();
+    let done = dispatcher.execute(driver, &move_to(3, 2400), free, &scan.ids).unwrap();
+    assert_eq!(done.outcome(), Ok(()));
+    let telemetry = Telemetry::read(driver, 3, PollPlan { moving: true, ..Default::default() });
+    assert_eq!((telemetry.position, telemetry.is_moving), (Some(2400), Some(false)));
+    assert_eq!(
+        mock.calls(),
+        vec![BackendCall::BroadcastPing, BackendCall::MoveTo { id: 3, position: 2400, speed: 800, acceleration: 30 }]
+    );
+}
+
+#[test]
+fn reconnection_rearms_the_first_move_guard() {
+    let mock = bus_with(&[1]);
+    let plugged = Arc::new(AtomicBool::new(true));
+    let mut worker = worker_on(&mock, plugged.clone());
+    let mut dispatcher = Dispatcher::new(500);
+
+    assert!(worker.connect());
+    let ack = Command::Move { id: 1, position: 3500, speed: 800, acceleration: 30, acknowledge_large: true };
+    dispatcher.execute(worker.driver().unwrap(), &ack, free, &[]).unwrap();
+
+    // Adaptateur débranché en plein mouvement : plus de pilote, et pas de réouverture possible
+    plugged.store(false, Ordering::SeqCst);
+    worker.disconnect();
+    assert!(!worker.connect());
+    assert!(worker.with_backend(hotplug::fast_scan).is_err());
+
+    // Rebranché : la position n'est plus connue de l'utilisateur, la garde revient
+    plugged.store(true, Ordering::SeqCst);
+    assert!(worker.connect());
+    dispatcher.arm_first_moves();
+    mock.take_calls();
+    let driver = worker.driver().unwrap();
+    assert_eq!(
+        dispatcher.execute(driver, &move_to(1, 500), free, &[]),
+        Err(ValidationError::LargeFirstMove { current: Some(3500), target: 500 })
+    );
+    assert!(dispatcher.execute(driver, &move_to(1, 3300), free, &[]).is_ok());
+    assert_eq!(mock.calls(), vec![BackendCall::MoveTo { id: 1, position: 3300, speed: 800, acceleration: 30 }]);
+}
+
+#[test]
+fn flood_of_slider_moves_sends_the_last_one() {
+    let mock = bus_with(&[1, 2]);
+    let mut worker = worker_on(&mock, Arc::new(AtomicBool::new(true)));
+    let mut dispatcher = Dispatcher::new(0);
+    worker.connect();
+
+    let mut flood: Vec<Command> = (0..50).map(|step| move_to(1, 2000 + step * 4)).collect();
+    flood.insert(10, Command::Torque { id: 2, enable: true });
+    flood.push(move_to(2, 1000));
+    let queued = coalesce::keep_latest(flood, |command| match command {
+        Command::Move { id, .. } => Some(*id),
+        _ => None,
+    });
+    let driver = worker.driver().unwrap();
+    for command in &queued {
+        dispatcher.execute(driver, command, free, &[]).unwrap();
+    }
+    assert_eq!(
+        mock.calls(),
+        vec![
+            BackendCall::EnableTorque(2),
+            BackendCall::MoveTo { id: 1, position: 2196, speed: 800, acceleration: 30 },
+            BackendCall::MoveTo { id: 2, position: 1000, speed: 800, acceleration: 30 },
+        ]
+    );
+}
+
+#[test]
+fn overheating_servo_is_cut_and_stays_locked_until_cooled() {
+    let mock = bus_with(&[1]);
+    let mut worker = worker_on(&mock, Arc::new(AtomicBool::new(true)));
+    let mut dispatcher = Dispatcher::new(0);
+    let curve = DeratingCurve::default();
+    let thermal = ThermalLockout::default();
+    worker.connect();
+    let driver = worker.driver().unwrap();
+    let plan = PollPlan { temperature: true, ..Default::default() };
+
+    mock.update(1, |servo| servo.temperature = 70);
+    let reading = Telemetry::read(driver, 1, plan);
+    assert!(thermal.observe(&curve, 1, reading.temperature.unwrap()));
+    dispatcher.execute(driver, &Command::Torque { id: 1, enable: false }, free, &[]).unwrap();
+
+    let locked = |id| MoveConstraints { cut_off: thermal.is_locked(id), ..Default::default() };
+    assert_eq!(dispatcher.execute(driver, &move_to(1, 2100), locked, &[]), Err(ValidationError::OverheatCutOff));
+    assert!(thermal.release(&curve, 1).is_err());
+
+    mock.update(1, |servo| servo.temperature = 50);
+    thermal.observe(&curve, 1, Telemetry::read(driver, 1, plan).temperature.unwrap());
+    assert_eq!(thermal.release(&curve, 1), Ok(()));
+    let cooled = |id| MoveConstraints { cut_off: thermal.is_locked(id), ..Default::default() };
+    assert!(dispatcher.execute(driver, &move_to(1, 2100), cooled, &[]).is_ok());
+    assert_eq!(
+        mock.calls(),
+        vec![BackendCall::DisableTorque(1), BackendCall::MoveTo { id: 1, position: 2100, speed: 800, acceleration: 30 }]
+    );
+}
+
+#[test]
+fn emergency_stop_drops_queued_moves_and_blocks_new_ones() {
+    let mock = bus_with(&[1, 2]);
+    let mut worker = worker_on(&mock, Arc::new(AtomicBool::new(true)));
+    let mut dispatcher = Dispatcher::new(0);
+    let mut stopped = EmergencyStop::default();
+    worker.connect();
+
+    let mut queue: VecDeque<Command> =
+        vec![move_to(1, 1000), Command::Torque { id: 2, enable: false }, Command::EmergencyStop, move_to(2, 3000)].into();
+    assert!(estop::take_emergency(&mut queue, |c| *c == Command::EmergencyStop, Command::is_motion));
+    assert_eq!(queue, VecDeque::from(vec![Command::Torque { id: 2, enable: false }]));
+
+    let driver = worker.driver().unwrap();
+    let done = dispatcher.execute(driver, &Command::EmergencyStop, free, &[1, 2]).unwrap();
+    assert_eq!(done, Executed::Stopped { failures: Vec::new() });
+    stopped.trigger([1, 2]);
+    let constraints = |id| MoveConstraints { emergency_stop: stopped.is_stopped(id), ..Default::default() };
+    assert_eq!(dispatcher.execute(driver, &move_to(1, 1000), constraints, &[]), Err(ValidationError::EmergencyStop));
+    assert_eq!(mock.calls(), vec![BackendCall::DisableTorque(1), BackendCall::DisableTorque(2)]);
+}
+
+#[test]
+fn id_change_shows_up_in_the_rescan() {
+    let mock = bus_with(&[1]);
+    let mut worker = worker_on(&mock, Arc::new(AtomicBool::new(true)));
+    worker.connect();
+
+    worker.driver().unwrap().change_id(1, 7).unwrap();
+    let scan = worker.with_backend(hotplug::fast_scan).unwrap();
+    assert_eq!(scan.ids, vec![7]);
+    assert_eq!(mock.calls(), vec![BackendCall::ChangeId { id: 1, new_id: 7 }, BackendCall::BroadcastPing]);
+}
+
+#[test]
+fn worker_loop_cuts_torque_of_an_overheating_servo() {
+    let mock = bus_with(&[1, 2]);
+    let (mut worker, _clock) = looping_worker(&mock, Arc::new(AtomicBool::new(true)));
+    let plan = PollPlan { temperature: true, ..Default::default() };
+    let protected = |_| Protection::default();
+
+    assert_eq!(worker.maintain(false), connected("/dev/mock"));
+    assert_eq!(worker.scan_step(|_| false, false), scanned(&[1, 2]));
+    let (_, events) = worker.poll(&[1, 2], plan, protected);
+    assert!(events.is_empty());
+
+    // Le worker coupe le couple lui-même, sans attendre l'interface, et une seule fois
+    mock.update(2, |servo| servo.temperature = 70);
+    let (readings, events) = worker.poll(&[1, 2], plan, protected);
+    assert_eq!(readings[1].temperature, Some(70));
+    assert_eq!(events, vec![WorkerEvent::ThermalCutOff { id: 2, temperature: 70, outcome: Ok(()) }]);
+    assert!(worker.thermal().is_locked(2));
+    let (_, events) = worker.poll(&[1, 2], plan, protected);
+    assert!(events.is_empty());
+    assert_eq!(mock.take_calls(), vec![BackendCall::DisableTorque(2)]);
+    assert!(!mock.servo(2).unwrap().torque);
+
+    // Dérogation : le verrou est levé et plus rien n'est coupé
+    let (_, events) = worker.poll(&[1, 2], plan, |_| Protection { thermal: false, ..Protection::default() });
+    assert!(events.is_empty());
+    assert!(!worker.thermal().is_locked(2));
+    assert!(mock.calls().is_empty());
+}
+
+#[test]
+fn worker_loop_reconnects_after_a_disconnect_mid_move() {
+    let mock = bus_with(&[1]);
+    let plugged = Arc::new(AtomicBool::new(true));
+    let (mut worker, clock) = looping_worker(&mock, plugged.clone());
+    let mut dispatcher = Dispatcher::new(0);
+    let plan = PollPlan { moving: true, ..Default::default() };
+    let protected = |_| Protection::default();
+
+    assert_eq!(worker.maintain(false), connected("/dev/mock"));
+    assert_eq!(worker.scan_step(|_| false, false), scanned(&[1]));
+    dispatcher.execute(worker.driver().unwrap(), &move_to(1, 2400), free, &[1]).unwrap();
+
+    // Adaptateur arraché : la liaison tient jusqu'au dernier cycle muet toléré
+    plugged.store(false, Ordering::SeqCst);
+    mock.unplug(1);
+    for _ in 1..DISCONNECT_FAILURES {
+        let (readings, events) = worker.poll(&[1], plan, protected);
+        assert_eq!(readings[0].position, None);
+        assert!(events.is_empty());
+    }
+    let (_, events) = worker.poll(&[1], plan, protected);
+    assert_eq!(events, vec![WorkerEvent::LinkLost { port: "/dev/mock".into() }]);
+    assert!(!worker.is_connected());
+    assert_eq!(worker.poll(&[1], plan, protected), (Vec::new(), Vec::new()));
+
+    // Première tentative aussitôt, puis une par intervalle
+    assert!(worker.maintain(false).is_empty());
+    plugged.store(true, Ordering::SeqCst);
+    let _ = mock.clone().with_servo(1, MockServo { position: 2400, ..Default::default() });
+    assert!(worker.maintain(false).is_empty());
+    assert!(!worker.is_connected());
+    clock.advance(RECONNECT_INTERVAL);
+    assert_eq!(worker.maintain(false), connected("/dev/mock"));
+
+    // Le scan de connexion repart de zéro et la scrutation reprend là où le servo s'est arrêté
+    assert_eq!(worker.scan_progress(), Some((1, 4)));
+    assert_eq!(worker.scan_step(|_| false, false), scanned(&[1]));
+    let (readings, events) = worker.poll(&[1], plan, protected);
+    assert_eq!((readings[0].position, readings[0].is_moving), (Some(2400), Some(false)));
+    assert!(events.is_empty());
+    assert_eq!(mock.calls(), vec![BackendCall::MoveTo { id: 1, position: 2400, speed: 800, acceleration: 30 }]);
+}
+
+#[test]
+fn worker_loop_finds_a_hot_plugged_servo_on_the_next_sweep() {
+    let mock = bus_with(&[1]);
+    let (mut worker, clock) = looping_worker(&mock, Arc::new(AtomicBool::new(true)));
+
+    assert_eq!(worker.maintain(false), connected("/dev/mock"));
+    assert_eq!(worker.scan_step(|_| false, false), scanned(&[1]));
+    let _ = mock.clone().with_servo(3, MockServo::default());
+    let known = |id| id == 1;
+    assert!(worker.scan_step(known, false).is_empty());
+
+    // Balayage dû, mais suspendu pendant une opération destructive
+    clock.advance(Duration::from_secs(10));
+    assert!(worker.scan_step(known, true).is_empty());
+    assert_eq!(worker.scan_step(known, false), vec![WorkerEvent::ServoFound(3)]);
+    assert!(mock.calls().is_empty());
+}
+
+#[test]
+fn overlapping_destructive_writes_are_serialized() {
+    let mock = bus_with(&[3, 5]);
+    let mut worker = worker_on(&mock, Arc::new(AtomicBool::new(true)));
+    let mut operation = OperationLock::default();
+    let mut cache = RegisterCache::default();
+    let max_torque = registers::find_register("Max Torque").unwrap();
+    // Max Torque = 800 sur le servo source
+    mock.set_register(5, 16, 0x20);
+    mock.set_register(5, 17, 0x03);
+    worker.connect();
+
+    // Limite de couple en cours sur 5 : la copie EEPROM et le changement de mode attendent
+    operation.begin(5, "Torque limit").unwrap();
+    let busy = Err("operation in progress on ID 5 (Torque limit)".to_string());
+    assert_eq!(operation.begin(3, "Register copy"), busy);
+    assert_eq!(operation.begin(5, "Mode change"), busy);
+    assert!(operation.pauses(5) && !operation.pauses(3));
+    worker.with_bus(|bus| TorqueLimit::from_percent(50.0).write(bus, 5)).unwrap();
+    operation.finish();
+
+    // Relancée une fois le verrou rendu, la copie part seule sur le bus
+    operation.begin(3, "Register copy").unwrap();
+    assert_eq!(worker.with_bus(|bus| regdiff::copy(bus, &mut cache, 5, 3, max_torque)), Ok(800));
+    operation.finish();
+    let write = |id, address, data: &[u8]| BackendCall::WriteRegister { id, address, data: data.to_vec() };
+    assert_eq!(mock.calls(), vec![write(5, 48, &[0xF4, 0x01]), write(3, 55, &[0]), write(3, 16, &[0x20, 0x03]), write(3, 55, &[1])]);
+}
+
+#[test]
+fn failed_moves_and_mute_polls_add_up_to_a_link_loss() {
+    let mock = bus_with(&[1]);
+    let (mut worker, _clock) = looping_worker(&mock, Arc::new(AtomicBool::new(true)));
+    let plan = PollPlan::default();
+    let protected = |_| Protection::default();
+    assert_eq!(worker.maintain(false), connected("/dev/mock"));
+
+    // Une lecture qui répond remet le compte à zéro
+    for _ in 1..DISCONNECT_FAILURES {
+        worker.count_failure();
+    }
+    assert!(worker.poll(&[1], plan, protected).1.is_empty());
+
+    mock.unplug(1);
+    for _ in 0..DISCONNECT_FAILURES / 2 {
+        worker.count_failure();
+    }
+    for _ in DISCONNECT_FAILURES / 2 + 1..DISCONNECT_FAILURES {
+        assert!(worker.poll(&[1], plan, protected).1.is_empty());
+    }
+    let (_, events) = worker.poll(&[1], plan, protected);
+    assert_eq!(events, vec![WorkerEvent::LinkLost { port: "/dev/mock".into() }]);
+    assert!(!worker.is_connected());
+}
+
+#[test]
+fn suspected_duplicate_id_gets_no_moves() {
+    let mock = MockBackend::new()
+        .with_servo(1, MockServo { position: 1000, ..Default::default() })
+        .with_servo(2, MockServo { position: 3000, ..Default::default() });
+    let mut worker = worker_on(&mock, Arc::new(AtomicBool::new(true)));
+    let mut dispatcher = Dispatcher::new(0);
+    assert!(worker.connect());
+    let driver = worker.driver().unwrap();
+
+    // Deux servos sur un même ID répondent tour à tour : les lectures alternent
+    assert!(!ids::probe_duplicate(|| driver.read_position(1)));
+    let mut turn = 0;
+    let shared = ids::probe_duplicate(|| {
+        turn += 1;
+        driver.read_position(if turn % 2 == 0 { 1 } else { 2 })
+    });
+    assert!(shared);
+
+    let flagged = |id| MoveConstraints { duplicate_id: id == 1, ..Default::default() };
+    assert_eq!(dispatcher.execute(driver, &move_to(1, 2048), flagged, &[1, 2]).unwrap_err(), ValidationError::DuplicateId);
+    assert_eq!(dispatcher.execute(driver, &move_to(2, 2048), flagged, &[1, 2]).unwrap().outcome(), Ok(()));
+    assert_eq!(mock.calls(), vec![BackendCall::MoveTo { id: 2, position: 2048, speed: 800, acceleration: 30 }]);
+}